@@ -0,0 +1,74 @@
+//! Time to normalize a growing Claude log line by line as it streams in, through the
+//! executor's `LogNormalizer` and by re-normalizing all output so far on every line, which is
+//! what executors without one still do. The time per line stays flat for the first as the log
+//! grows and keeps climbing for the second.
+
+use std::time::{Duration, Instant};
+
+use codecommand::{
+    executor::{Executor, IncrementalNormalizer},
+    executors::ClaudeExecutor,
+};
+
+const WORKTREE: &str = "/tmp/bench-worktree";
+
+fn claude_line(index: usize) -> String {
+    if index % 3 == 0 {
+        format!(
+            r#"{{"type":"assistant","message":{{"role":"assistant","content":[{{"type":"tool_use","id":"toolu_{}","name":"Read","input":{{"file_path":"{}/src/file_{}.rs"}}}}]}},"session_id":"bench"}}"#,
+            index, WORKTREE, index
+        )
+    } else {
+        format!(
+            r#"{{"type":"assistant","message":{{"role":"assistant","content":[{{"type":"text","text":"Step {} of the plan is done."}}]}},"session_id":"bench"}}"#,
+            index
+        )
+    }
+}
+
+fn incremental(lines: &[String]) -> Duration {
+    let mut normalizer = IncrementalNormalizer::new(Box::new(ClaudeExecutor::default()), WORKTREE);
+    let start = Instant::now();
+    for line in lines {
+        normalizer.push(line);
+        normalizer.push("\n");
+    }
+    assert!(!normalizer.entries().is_empty());
+    start.elapsed()
+}
+
+fn reparsing(lines: &[String]) -> Duration {
+    let executor = ClaudeExecutor::default();
+    let mut logs = String::new();
+    let start = Instant::now();
+    for line in lines {
+        logs.push_str(line);
+        logs.push('\n');
+        executor.normalize_logs(&logs, WORKTREE).unwrap();
+    }
+    start.elapsed()
+}
+
+fn per_line(elapsed: Duration, lines: usize) -> f64 {
+    elapsed.as_secs_f64() * 1e6 / lines as f64
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>16} {:>16} {:>16} {:>16}",
+        "lines", "incremental", "µs/line", "reparsing", "µs/line"
+    );
+    for lines in [500, 1_000, 2_000, 4_000] {
+        let log: Vec<String> = (0..lines).map(claude_line).collect();
+        let incremental = incremental(&log);
+        let reparsing = reparsing(&log);
+        println!(
+            "{:>8} {:>16?} {:>16.2} {:>16?} {:>16.2}",
+            lines,
+            incremental,
+            per_line(incremental, lines),
+            reparsing,
+            per_line(reparsing, lines)
+        );
+    }
+}
@@ -0,0 +1,2 @@
+-- Record why an execution process was stopped (e.g. "operator stop")
+ALTER TABLE execution_processes ADD COLUMN stop_reason TEXT;
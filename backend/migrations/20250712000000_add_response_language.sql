@@ -0,0 +1,4 @@
+-- Per-project override for the language agents should respond in
+ALTER TABLE projects ADD COLUMN response_language TEXT;
+-- Language that was requested when the executor session started
+ALTER TABLE executor_sessions ADD COLUMN response_language TEXT;
@@ -0,0 +1,2 @@
+-- Planned effort for a task (story points, minutes, ...) to compare against actual attempt time
+ALTER TABLE tasks ADD COLUMN estimate REAL;
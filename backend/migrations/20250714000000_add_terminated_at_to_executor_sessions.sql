@@ -0,0 +1,9 @@
+-- When the execution process behind the session finished
+ALTER TABLE executor_sessions ADD COLUMN terminated_at TEXT;
+
+UPDATE executor_sessions
+SET terminated_at = (
+    SELECT ep.completed_at
+    FROM execution_processes ep
+    WHERE ep.id = executor_sessions.execution_process_id
+);
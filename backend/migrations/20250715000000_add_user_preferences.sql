@@ -0,0 +1,10 @@
+-- Per-user UI preferences, kept out of the server config file.
+-- identity is the signed-in GitHub username, or 'local' for single-user installs.
+-- value holds the JSON-encoded preference.
+CREATE TABLE user_preferences (
+    identity   TEXT NOT NULL,
+    key        TEXT NOT NULL,
+    value      TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    PRIMARY KEY (identity, key)
+);
@@ -0,0 +1,10 @@
+-- Stored normalizations of execution output. 'primary' pins what the UI shows for an
+-- execution; 'shadow' holds a re-run with the current parsers awaiting review.
+CREATE TABLE normalized_log_snapshots (
+    execution_process_id BLOB NOT NULL,
+    version              TEXT NOT NULL CHECK (version IN ('primary', 'shadow')),
+    conversation         TEXT NOT NULL,
+    created_at           TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    PRIMARY KEY (execution_process_id, version),
+    FOREIGN KEY (execution_process_id) REFERENCES execution_processes(id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- When the attempt's worktree was removed from disk
+ALTER TABLE task_attempts ADD COLUMN worktree_deleted_at TEXT;
+
+UPDATE task_attempts
+SET worktree_deleted_at = updated_at
+WHERE worktree_deleted = TRUE;
@@ -0,0 +1,13 @@
+-- Persisted state of background jobs run by the job scheduler
+CREATE TABLE scheduled_jobs (
+    name              TEXT PRIMARY KEY,
+    enabled           BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at       TEXT,
+    last_started_at   TEXT,
+    last_finished_at  TEXT,
+    last_duration_ms  INTEGER,
+    last_error        TEXT,
+    last_succeeded    BOOLEAN,
+    run_count         INTEGER NOT NULL DEFAULT 0,
+    updated_at        TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
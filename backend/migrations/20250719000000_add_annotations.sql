@@ -0,0 +1,13 @@
+-- Reviewer annotations on individual entries of an attempt's normalized log.
+-- entry_index is the entry's position in the conversation shown for the attempt.
+CREATE TABLE annotations (
+    id              BLOB PRIMARY KEY,
+    attempt_id      BLOB NOT NULL,
+    entry_index     INTEGER NOT NULL CHECK (entry_index >= 0),
+    annotation_type TEXT NOT NULL CHECK (annotation_type IN ('approved', 'rejected', 'question')),
+    comment         TEXT,
+    created_at      TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    FOREIGN KEY (attempt_id) REFERENCES task_attempts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_annotations_attempt_id ON annotations(attempt_id);
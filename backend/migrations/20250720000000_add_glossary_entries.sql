@@ -0,0 +1,16 @@
+-- Project glossary: internal terms explained to agents in prompts and over MCP.
+-- 'suggested' entries are mined from merged diffs and only used once approved.
+CREATE TABLE glossary_entries (
+    id          BLOB PRIMARY KEY,
+    project_id  BLOB NOT NULL,
+    term        TEXT NOT NULL,
+    definition  TEXT NOT NULL DEFAULT '',
+    file_refs   TEXT NOT NULL DEFAULT '[]',
+    status      TEXT NOT NULL DEFAULT 'approved' CHECK (status IN ('approved', 'suggested', 'dismissed')),
+    occurrences INTEGER NOT NULL DEFAULT 0,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_glossary_entries_project_term ON glossary_entries(project_id, term COLLATE NOCASE);
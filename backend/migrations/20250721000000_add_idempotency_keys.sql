@@ -0,0 +1,15 @@
+-- Responses of mutating requests sent with an Idempotency-Key header, replayed when the
+-- request is retried. status_code stays NULL while the first request is still running.
+CREATE TABLE idempotency_keys (
+    idempotency_key TEXT NOT NULL,
+    route           TEXT NOT NULL,
+    body_hash       TEXT NOT NULL,
+    status_code     INTEGER,
+    content_type    TEXT,
+    response_body   BLOB,
+    created_at      TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    expires_at      TEXT NOT NULL,
+    PRIMARY KEY (idempotency_key, route)
+);
+
+CREATE INDEX idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
@@ -0,0 +1,2 @@
+-- JSON report of how the follow-up prompt was fitted into the executor's token budget
+ALTER TABLE executor_sessions ADD COLUMN prompt_budget TEXT;
@@ -0,0 +1,9 @@
+-- Attempt branches from other installs that were reviewed and deliberately left alone, so
+-- they stop being reported as unknown
+CREATE TABLE ignored_attempt_branches (
+    project_id  BLOB NOT NULL,
+    branch      TEXT NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    PRIMARY KEY (project_id, branch),
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
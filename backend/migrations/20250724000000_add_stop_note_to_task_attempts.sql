@@ -0,0 +1,2 @@
+-- Note left by the user when stopping an attempt, e.g. why the approach was wrong
+ALTER TABLE task_attempts ADD COLUMN stop_note TEXT;
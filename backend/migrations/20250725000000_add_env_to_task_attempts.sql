@@ -0,0 +1,2 @@
+-- Extra environment variables for the attempt's processes, as a JSON object
+ALTER TABLE task_attempts ADD COLUMN env TEXT;
@@ -0,0 +1,42 @@
+-- Benchmark runs replay a project's merged tasks with several executors and score the results
+-- against what was merged
+CREATE TABLE benchmark_runs (
+    id                BLOB PRIMARY KEY,
+    project_id        BLOB NOT NULL,
+    executors         TEXT NOT NULL, -- JSON array of executor types
+    test_command      TEXT,
+    max_duration_secs INTEGER NOT NULL CHECK (max_duration_secs > 0),
+    status            TEXT NOT NULL DEFAULT 'running'
+                         CHECK (status IN ('running', 'completed', 'cancelled')),
+    created_at        TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    completed_at      TEXT,
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+-- One task replayed with one executor. base_commit..reference_commit is what was merged.
+CREATE TABLE benchmark_attempts (
+    id                BLOB PRIMARY KEY,
+    run_id            BLOB NOT NULL,
+    task_id           BLOB NOT NULL,
+    executor          TEXT NOT NULL,
+    source_attempt_id BLOB NOT NULL,
+    base_commit       TEXT NOT NULL,
+    reference_commit  TEXT NOT NULL,
+    task_attempt_id   BLOB,
+    status            TEXT NOT NULL DEFAULT 'pending'
+                         CHECK (status IN ('pending', 'running', 'completed', 'failed', 'timed_out')),
+    diff_similarity   REAL,
+    tests_passed      BOOLEAN,
+    cost_usd          REAL,
+    duration_secs     REAL,
+    error             TEXT,
+    created_at        TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at        TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    FOREIGN KEY (run_id) REFERENCES benchmark_runs(id) ON DELETE CASCADE,
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_benchmark_attempts_run_id ON benchmark_attempts(run_id);
+
+-- Attempts started by a benchmark run; kept out of task status, notifications and stats
+ALTER TABLE task_attempts ADD COLUMN benchmark_run_id BLOB REFERENCES benchmark_runs(id);
@@ -0,0 +1,3 @@
+-- Bytes of executor output that weren't valid UTF-8 or were control characters, and so were
+-- replaced or dropped when the output was stored
+ALTER TABLE execution_processes ADD COLUMN replaced_output_bytes INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Scratch projects are plain directories: attempts run in a copy of the directory instead of a
+-- git worktree, and git features (diff, merge, rebase, pull requests) are unavailable
+ALTER TABLE projects ADD COLUMN project_type TEXT NOT NULL DEFAULT 'git'
+    CHECK (project_type IN ('git', 'scratch'));
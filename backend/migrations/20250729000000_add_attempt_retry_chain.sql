@@ -0,0 +1,5 @@
+-- Attempt this one retries, and how many retries precede it in the chain
+ALTER TABLE task_attempts ADD COLUMN retry_of BLOB REFERENCES task_attempts(id) ON DELETE SET NULL;
+ALTER TABLE task_attempts ADD COLUMN retry_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_task_attempts_retry_of ON task_attempts(retry_of);
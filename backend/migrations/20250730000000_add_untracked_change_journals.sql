@@ -0,0 +1,11 @@
+-- Files git doesn't track in an attempt's worktree, snapshotted after setup, and how they had
+-- changed when the latest execution finished
+CREATE TABLE untracked_change_journals (
+    task_attempt_id   BLOB PRIMARY KEY,
+    baseline          TEXT NOT NULL, -- JSON FileSnapshot
+    changes           TEXT,          -- JSON array of UntrackedChange, once compared
+    truncated         BOOLEAN NOT NULL DEFAULT FALSE,
+    baseline_taken_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    compared_at       TEXT,
+    FOREIGN KEY (task_attempt_id) REFERENCES task_attempts(id) ON DELETE CASCADE
+);
@@ -0,0 +1,34 @@
+-- Outgoing webhooks notified of attempt lifecycle events
+CREATE TABLE webhook_endpoints (
+    id         BLOB PRIMARY KEY,
+    url        TEXT NOT NULL,
+    secret     TEXT NOT NULL,              -- encrypted with the local key
+    events     TEXT NOT NULL DEFAULT '[]', -- JSON array of event types; empty means all
+    enabled    BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+-- One event queued for one endpoint. Rows are kept after delivery as the endpoint's log;
+-- 'dead' ones ran out of attempts and wait for a manual redelivery.
+CREATE TABLE webhook_deliveries (
+    id                   BLOB PRIMARY KEY,
+    endpoint_id          BLOB NOT NULL,
+    event_id             BLOB NOT NULL, -- shared by the deliveries of one event
+    event_type           TEXT NOT NULL,
+    task_attempt_id      BLOB,          -- no foreign key, so the log outlives the attempt
+    payload              TEXT NOT NULL,
+    status               TEXT NOT NULL DEFAULT 'pending'
+                            CHECK (status IN ('pending', 'delivered', 'dead')),
+    attempts             INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at      TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    last_response_status INTEGER,
+    last_error           TEXT,
+    delivered_at         TEXT,
+    created_at           TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at           TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    FOREIGN KEY (endpoint_id) REFERENCES webhook_endpoints(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_webhook_deliveries_endpoint_id ON webhook_deliveries(endpoint_id, created_at);
+CREATE INDEX idx_webhook_deliveries_pending ON webhook_deliveries(status, next_attempt_at);
@@ -0,0 +1,75 @@
+-- no-transaction
+-- Bisect runs of an attempt's commits, streamed as execution processes of their own type.
+-- SQLite can't change a CHECK constraint, so execution_processes is rebuilt, with foreign keys
+-- off so that dropping the old table doesn't cascade into activities, sessions and log
+-- snapshots. Foreign keys can only be switched outside a transaction, hence the explicit one.
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE execution_processes_new (
+    id                    BLOB PRIMARY KEY,
+    task_attempt_id       BLOB NOT NULL,
+    process_type          TEXT NOT NULL DEFAULT 'setupscript'
+                             CHECK (process_type IN ('setupscript','codingagent','devserver','bisect')),
+    status                TEXT NOT NULL DEFAULT 'running'
+                             CHECK (status IN ('running','completed','failed','killed')),
+    command               TEXT NOT NULL,
+    args                  TEXT,  -- JSON array of arguments
+    working_directory     TEXT NOT NULL,
+    stdout                TEXT,
+    stderr                TEXT,
+    exit_code             INTEGER,
+    started_at            TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    completed_at          TEXT,
+    created_at            TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at            TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    executor_type         TEXT,
+    stop_reason           TEXT,
+    replaced_output_bytes INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (task_attempt_id) REFERENCES task_attempts(id) ON DELETE CASCADE
+);
+
+INSERT INTO execution_processes_new (
+    id, task_attempt_id, process_type, status, command, args, working_directory, stdout,
+    stderr, exit_code, started_at, completed_at, created_at, updated_at, executor_type,
+    stop_reason, replaced_output_bytes
+)
+SELECT id, task_attempt_id, process_type, status, command, args, working_directory, stdout,
+       stderr, exit_code, started_at, completed_at, created_at, updated_at, executor_type,
+       stop_reason, replaced_output_bytes
+FROM execution_processes;
+
+DROP TABLE execution_processes;
+ALTER TABLE execution_processes_new RENAME TO execution_processes;
+
+CREATE INDEX idx_execution_processes_task_attempt_id ON execution_processes(task_attempt_id);
+CREATE INDEX idx_execution_processes_status ON execution_processes(status);
+CREATE INDEX idx_execution_processes_type ON execution_processes(process_type);
+
+ALTER TABLE projects ADD COLUMN test_script TEXT;
+
+-- The id is that of the execution process running the bisect
+CREATE TABLE bisect_runs (
+    id               BLOB PRIMARY KEY,
+    task_attempt_id  BLOB NOT NULL,
+    good_commit      TEXT NOT NULL, -- merge base, expected to pass
+    bad_commit       TEXT NOT NULL, -- attempt head, expected to fail
+    command          TEXT NOT NULL,
+    worktree_path    TEXT NOT NULL, -- temporary, removed once the run ends
+    status           TEXT NOT NULL DEFAULT 'running'
+                        CHECK (status IN ('running', 'found', 'failed', 'cancelled')),
+    first_bad_commit TEXT,          -- JSON BisectCommit once found
+    error            TEXT,
+    created_at       TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at       TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    completed_at     TEXT,
+    FOREIGN KEY (task_attempt_id) REFERENCES task_attempts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_bisect_runs_task_attempt_id ON bisect_runs(task_attempt_id);
+CREATE INDEX idx_bisect_runs_status ON bisect_runs(status);
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
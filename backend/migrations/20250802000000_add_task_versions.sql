@@ -0,0 +1,21 @@
+-- Optimistic concurrency for task updates. Every change bumps `version`; the *_version
+-- columns hold the version each field last changed at, so that concurrent updates of
+-- different fields can be merged instead of rejected.
+ALTER TABLE tasks ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE tasks ADD COLUMN title_version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE tasks ADD COLUMN description_version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE tasks ADD COLUMN status_version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE tasks ADD COLUMN estimate_version INTEGER NOT NULL DEFAULT 1;
+
+-- Updates made against an outdated version of a task, whether they were merged or rejected
+CREATE TABLE task_update_conflicts (
+    id          BLOB PRIMARY KEY,
+    project_id  BLOB NOT NULL,
+    task_id     BLOB NOT NULL,
+    source      TEXT NOT NULL CHECK (source IN ('api','mcp')),
+    merged      BOOLEAN NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_task_update_conflicts_project_id ON task_update_conflicts(project_id);
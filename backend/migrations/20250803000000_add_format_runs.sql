@@ -0,0 +1,79 @@
+-- no-transaction
+-- Formatting of the files an attempt changed, run as an execution process of its own type once
+-- the coding agent is done. As for bisect runs, execution_processes is rebuilt with foreign keys
+-- off to widen its process_type CHECK constraint.
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE execution_processes_new (
+    id                    BLOB PRIMARY KEY,
+    task_attempt_id       BLOB NOT NULL,
+    process_type          TEXT NOT NULL DEFAULT 'setupscript'
+                             CHECK (process_type IN ('setupscript','codingagent','devserver','bisect','formatscript')),
+    status                TEXT NOT NULL DEFAULT 'running'
+                             CHECK (status IN ('running','completed','failed','killed')),
+    command               TEXT NOT NULL,
+    args                  TEXT,  -- JSON array of arguments
+    working_directory     TEXT NOT NULL,
+    stdout                TEXT,
+    stderr                TEXT,
+    exit_code             INTEGER,
+    started_at            TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    completed_at          TEXT,
+    created_at            TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at            TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    executor_type         TEXT,
+    stop_reason           TEXT,
+    replaced_output_bytes INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (task_attempt_id) REFERENCES task_attempts(id) ON DELETE CASCADE
+);
+
+INSERT INTO execution_processes_new (
+    id, task_attempt_id, process_type, status, command, args, working_directory, stdout,
+    stderr, exit_code, started_at, completed_at, created_at, updated_at, executor_type,
+    stop_reason, replaced_output_bytes
+)
+SELECT id, task_attempt_id, process_type, status, command, args, working_directory, stdout,
+       stderr, exit_code, started_at, completed_at, created_at, updated_at, executor_type,
+       stop_reason, replaced_output_bytes
+FROM execution_processes;
+
+DROP TABLE execution_processes;
+ALTER TABLE execution_processes_new RENAME TO execution_processes;
+
+CREATE INDEX idx_execution_processes_task_attempt_id ON execution_processes(task_attempt_id);
+CREATE INDEX idx_execution_processes_status ON execution_processes(status);
+CREATE INDEX idx_execution_processes_type ON execution_processes(process_type);
+
+-- One command per line, each given the changed files as arguments
+ALTER TABLE projects ADD COLUMN format_script TEXT;
+ALTER TABLE projects ADD COLUMN format_commit_mode TEXT NOT NULL DEFAULT 'separate'
+    CHECK (format_commit_mode IN ('separate', 'fold'));
+
+-- The id is that of the execution process running the formatters
+CREATE TABLE format_runs (
+    id                      BLOB PRIMARY KEY,
+    task_attempt_id         BLOB NOT NULL,
+    coding_agent_process_id BLOB NOT NULL, -- the run whose changes are formatted
+    agent_succeeded         BOOLEAN NOT NULL,
+    agent_exit_code         INTEGER,
+    command                 TEXT NOT NULL,
+    files                   TEXT NOT NULL, -- JSON array of paths
+    commit_mode             TEXT NOT NULL CHECK (commit_mode IN ('separate', 'fold')),
+    status                  TEXT NOT NULL DEFAULT 'running'
+                               CHECK (status IN ('running', 'formatted', 'unchanged', 'failed', 'cancelled')),
+    commit_sha              TEXT,          -- the separate style commit, if one was made
+    error                   TEXT,
+    created_at              TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at              TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    completed_at            TEXT,
+    FOREIGN KEY (task_attempt_id) REFERENCES task_attempts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_format_runs_task_attempt_id ON format_runs(task_attempt_id);
+CREATE INDEX idx_format_runs_status ON format_runs(status);
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
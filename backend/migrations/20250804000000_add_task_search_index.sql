@@ -0,0 +1,26 @@
+-- Full-text index of task titles and descriptions for quick search. It keeps its own copy of
+-- the text, keyed by task id, since tasks have no stable integer rowid to point at.
+CREATE VIRTUAL TABLE task_search USING fts5(
+    task_id UNINDEXED,
+    title,
+    description,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO task_search (task_id, title, description)
+SELECT id, title, COALESCE(description, '') FROM tasks;
+
+CREATE TRIGGER task_search_insert AFTER INSERT ON tasks BEGIN
+    INSERT INTO task_search (task_id, title, description)
+    VALUES (new.id, new.title, COALESCE(new.description, ''));
+END;
+
+CREATE TRIGGER task_search_update AFTER UPDATE OF title, description ON tasks BEGIN
+    UPDATE task_search
+    SET title = new.title, description = COALESCE(new.description, '')
+    WHERE task_id = old.id;
+END;
+
+CREATE TRIGGER task_search_delete AFTER DELETE ON tasks BEGIN
+    DELETE FROM task_search WHERE task_id = old.id;
+END;
@@ -0,0 +1,23 @@
+-- Hosts contacted by coding agents, observed through a logging proxy for projects that opt in.
+-- Host patterns are JSON arrays of domains; a domain also covers its subdomains.
+CREATE TABLE project_network_policies (
+    project_id  BLOB PRIMARY KEY,
+    enabled     BOOLEAN NOT NULL DEFAULT FALSE,
+    allowlist   TEXT NOT NULL DEFAULT '[]',
+    denylist    TEXT NOT NULL DEFAULT '[]',
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+-- One row per host and port an execution connected to, with the policy's verdict at the time
+CREATE TABLE network_contacts (
+    execution_process_id BLOB NOT NULL,
+    host                 TEXT NOT NULL,
+    port                 INTEGER NOT NULL,
+    verdict              TEXT NOT NULL CHECK (verdict IN ('allowed', 'unlisted', 'denied')),
+    connections          INTEGER NOT NULL DEFAULT 1,
+    first_seen_at        TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    last_seen_at         TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    PRIMARY KEY (execution_process_id, host, port),
+    FOREIGN KEY (execution_process_id) REFERENCES execution_processes(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Executor a task's attempts start with when they don't name one, e.g. picked by the agent
+-- that created the task over MCP
+ALTER TABLE tasks ADD COLUMN executor TEXT;
@@ -0,0 +1,2 @@
+-- Attempt lists page newest first and filter by when attempts were created
+CREATE INDEX idx_task_attempts_created_at ON task_attempts(created_at, id);
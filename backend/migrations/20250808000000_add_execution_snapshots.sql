@@ -0,0 +1,13 @@
+-- The worktree as each execution of an attempt left it, so what a single execution changed can
+-- be diffed. The snapshot commit is kept alive by a ref named after the execution process.
+CREATE TABLE execution_snapshots (
+    execution_process_id BLOB PRIMARY KEY,
+    task_attempt_id      BLOB NOT NULL,
+    snapshot_commit      TEXT NOT NULL, -- the worktree's files, uncommitted changes included
+    head_commit          TEXT NOT NULL, -- the worktree's HEAD at the time
+    created_at           TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    FOREIGN KEY (execution_process_id) REFERENCES execution_processes(id) ON DELETE CASCADE,
+    FOREIGN KEY (task_attempt_id) REFERENCES task_attempts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_execution_snapshots_task_attempt_id ON execution_snapshots(task_attempt_id);
@@ -0,0 +1,5 @@
+-- When the attempt was queued for a slot in its project; NULL once it has one or isn't waiting.
+-- Queued attempts are put back in line from this after a restart.
+ALTER TABLE task_attempts ADD COLUMN queued_at TEXT;
+
+CREATE INDEX idx_task_attempts_queued_at ON task_attempts(queued_at) WHERE queued_at IS NOT NULL;
@@ -1,33 +1,271 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
 
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
 #[cfg(unix)]
 use nix::{sys::signal::Signal, unistd::Pid};
 use tokio::sync::{Mutex, RwLock as TokioRwLock};
 use uuid::Uuid;
 
-use crate::services::{generate_user_id, AnalyticsConfig, AnalyticsService};
+use crate::{
+    models::{
+        config::{Config, ProcessPriorities, ProcessPriority},
+        task_attempt::TaskAttempt,
+    },
+    services::{
+        focus_mode::FocusModeStatus, generate_user_id, network_observer::NetworkObserver,
+        redact_content_properties, AnalyticsConfig, AnalyticsService, AttemptChangeCache,
+        BranchStatusCache, JobScheduler, ProjectEventBus,
+    },
+    utils::process_priority,
+};
+
+type ConfigChangeListener = Arc<dyn Fn(&Config, &Config) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Services to notify, with the old and new config, after the config is updated
+#[derive(Clone, Default)]
+struct ConfigChangeListeners(Arc<StdMutex<Vec<ConfigChangeListener>>>);
+
+impl std::fmt::Debug for ConfigChangeListeners {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ConfigChangeListeners")
+            .field(&self.0.lock().unwrap().len())
+            .finish()
+    }
+}
 
 #[derive(Debug)]
 pub enum ExecutionType {
     SetupScript,
     CodingAgent,
     DevServer,
+    Bisect,
+    FormatScript,
 }
 
+/// Signal a stopped execution exited on, in the order they're sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopSignal {
+    Interrupt,
+    Terminate,
+    Kill,
+}
+
+impl std::fmt::Display for StopSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopSignal::Interrupt => write!(f, "SIGINT"),
+            StopSignal::Terminate => write!(f, "SIGTERM"),
+            StopSignal::Kill => write!(f, "SIGKILL"),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl From<StopSignal> for Signal {
+    fn from(signal: StopSignal) -> Self {
+        match signal {
+            StopSignal::Interrupt => Signal::SIGINT,
+            StopSignal::Terminate => Signal::SIGTERM,
+            StopSignal::Kill => Signal::SIGKILL,
+        }
+    }
+}
+
+/// How often a stopping execution is checked for having exited
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug)]
 pub struct RunningExecution {
     pub task_attempt_id: Uuid,
-    pub _execution_type: ExecutionType,
+    pub execution_type: ExecutionType,
     pub child: command_group::AsyncGroupChild,
+    /// Stopped by focus mode until it ends
+    pub paused: bool,
+}
+
+impl ExecutionType {
+    fn priority(&self, priorities: &ProcessPriorities) -> ProcessPriority {
+        match self {
+            ExecutionType::SetupScript | ExecutionType::FormatScript => priorities.setup_script,
+            ExecutionType::CodingAgent => priorities.coding_agent,
+            ExecutionType::DevServer => priorities.dev_server,
+            ExecutionType::Bisect => priorities.bisect,
+        }
+    }
+
+    /// Focus mode pauses everything but dev servers, which the user is looking at
+    fn pausable(&self) -> bool {
+        !matches!(self, ExecutionType::DevServer)
+    }
+}
+
+impl RunningExecution {
+    fn set_paused(&mut self, paused: bool, priority: ProcessPriority) {
+        let Some(pid) = self.child.id() else {
+            return; // already exited, waiting to be reaped
+        };
+        let result = if paused {
+            process_priority::pause(pid)
+        } else {
+            process_priority::resume(pid, priority)
+        };
+        match result {
+            Ok(()) => self.paused = paused,
+            Err(e) => tracing::warn!(
+                "Failed to {} process group {}: {}",
+                if paused { "pause" } else { "resume" },
+                pid,
+                e
+            ),
+        }
+    }
+}
+
+/// An attempt waiting for a slot in its project, with what starting it needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuedAttempt {
+    pub attempt_id: Uuid,
+    pub task_id: Uuid,
+    pub project_id: Uuid,
+}
+
+/// Attempts running in each project under `max_concurrent_per_project`, and those waiting.
+/// The queue is also recorded in `task_attempts.queued_at`, to be rebuilt after a restart.
+#[derive(Debug, Default)]
+struct ProjectSlots {
+    /// Attempts holding a slot, by project
+    running: HashMap<Uuid, HashSet<Uuid>>,
+    /// Attempts holding a slot whose start is still under way, so they have no process yet
+    starting: HashSet<Uuid>,
+    /// Oldest first
+    queued: VecDeque<QueuedAttempt>,
+}
+
+impl ProjectSlots {
+    fn take(&mut self, attempt: QueuedAttempt) {
+        self.running
+            .entry(attempt.project_id)
+            .or_default()
+            .insert(attempt.attempt_id);
+        self.starting.insert(attempt.attempt_id);
+    }
+
+    fn release(&mut self, attempt_id: Uuid) {
+        self.starting.remove(&attempt_id);
+        self.running.retain(|_, attempts| {
+            attempts.remove(&attempt_id);
+            !attempts.is_empty()
+        });
+    }
+
+    fn has_room(&self, project_id: Uuid, limit: Option<u32>) -> bool {
+        let running = self.running.get(&project_id).map_or(0, HashSet::len);
+        limit.is_none_or(|limit| running < limit as usize)
+    }
+}
+
+/// Simultaneous diff-stream connections allowed per task attempt
+pub const MAX_DIFF_STREAMS_PER_ATTEMPT: usize = 3;
+/// Simultaneous log-stream connections allowed per task attempt
+pub const MAX_LOG_STREAMS_PER_ATTEMPT: usize = 3;
+/// Simultaneous wait requests allowed per task attempt
+pub const MAX_WAITERS_PER_ATTEMPT: usize = 8;
+
+/// Per-attempt counts of a limited kind of connection
+type AttemptSlots = Arc<StdMutex<HashMap<Uuid, usize>>>;
+
+/// A connection slot for one attempt, given back when dropped
+#[derive(Debug)]
+pub struct AttemptSlot {
+    slots: AttemptSlots,
+    attempt_id: Uuid,
+}
+
+impl AttemptSlot {
+    fn try_acquire(slots: &AttemptSlots, attempt_id: Uuid, max: usize) -> Option<Self> {
+        let mut counts = slots.lock().unwrap();
+        let count = counts.entry(attempt_id).or_insert(0);
+        if *count >= max {
+            return None;
+        }
+        *count += 1;
+
+        Some(AttemptSlot {
+            slots: slots.clone(),
+            attempt_id,
+        })
+    }
+}
+
+impl Drop for AttemptSlot {
+    fn drop(&mut self) {
+        let mut counts = self.slots.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.attempt_id) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.attempt_id);
+            }
+        }
+    }
+}
+
+async fn update_analytics_config(
+    analytics: &TokioRwLock<AnalyticsService>,
+    user_id: &str,
+    user_enabled: bool,
+) {
+    // Check if analytics was disabled before this update
+    let was_analytics_disabled = !analytics.read().await.is_enabled();
+
+    let new_config = AnalyticsConfig::new(user_enabled);
+    let new_service = AnalyticsService::new(new_config);
+    let mut analytics = analytics.write().await;
+    *analytics = new_service;
+
+    // If analytics was disabled and is now enabled, fire a session_start event
+    if was_analytics_disabled && analytics.is_enabled() {
+        analytics.track_event(user_id, "session_start", None);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     running_executions: Arc<Mutex<HashMap<Uuid, RunningExecution>>>,
+    running_dev_servers: Arc<Mutex<HashSet<Uuid>>>,
+    /// Executions being stopped, which the monitor and focus mode leave alone meanwhile
+    stopping_executions: Arc<StdMutex<HashSet<Uuid>>>,
+    /// Logging proxies of running executions, kept until the execution ends
+    network_observers: Arc<Mutex<HashMap<Uuid, NetworkObserver>>>,
+    project_slots: Arc<Mutex<ProjectSlots>>,
+    /// Attempts cancelled by the user, whose next stages must not start
+    cancelled_attempts: Arc<StdMutex<HashSet<Uuid>>>,
+    /// When focus mode resumes the executions it paused; `None` when it's off
+    focus_until: Arc<StdMutex<Option<DateTime<Utc>>>>,
+    // Released from `Drop`, so this can't be an async mutex
+    diff_streams: AttemptSlots,
+    log_streams: AttemptSlots,
+    attempt_waiters: AttemptSlots,
     pub db_pool: sqlx::SqlitePool,
     config: Arc<tokio::sync::RwLock<crate::models::config::Config>>,
     pub analytics: Arc<TokioRwLock<AnalyticsService>>,
+    pub jobs: JobScheduler,
+    /// Changed lines of open attempts, for predicting merge conflicts between them
+    pub attempt_changes: AttemptChangeCache,
+    /// Last computed branch status of each attempt, refreshed in the background
+    pub branch_statuses: BranchStatusCache,
+    /// Changes to tasks and attempts, for clients following a project
+    pub project_events: ProjectEventBus,
+    config_listeners: ConfigChangeListeners,
     user_id: String,
+    /// Update check results and restarts into an installed update
+    #[cfg(feature = "self-update")]
+    pub updates: crate::services::self_update::UpdateService,
 }
 
 impl AppState {
@@ -44,30 +282,90 @@ impl AppState {
         let analytics_config = AnalyticsConfig::new(user_enabled);
         let analytics = Arc::new(TokioRwLock::new(AnalyticsService::new(analytics_config)));
 
-        Self {
+        let app_state = Self {
             running_executions: Arc::new(Mutex::new(HashMap::new())),
+            running_dev_servers: Arc::new(Mutex::new(HashSet::new())),
+            stopping_executions: Arc::new(StdMutex::new(HashSet::new())),
+            network_observers: Arc::new(Mutex::new(HashMap::new())),
+            project_slots: Arc::new(Mutex::new(ProjectSlots::default())),
+            cancelled_attempts: Arc::new(StdMutex::new(HashSet::new())),
+            focus_until: Arc::new(StdMutex::new(None)),
+            diff_streams: Arc::new(StdMutex::new(HashMap::new())),
+            log_streams: Arc::new(StdMutex::new(HashMap::new())),
+            attempt_waiters: Arc::new(StdMutex::new(HashMap::new())),
+            jobs: JobScheduler::new(db_pool.clone()),
+            attempt_changes: AttemptChangeCache::default(),
+            branch_statuses: BranchStatusCache::default(),
+            project_events: ProjectEventBus::default(),
+            config_listeners: ConfigChangeListeners::default(),
             db_pool,
             config,
             analytics,
             user_id: generate_user_id(),
+            #[cfg(feature = "self-update")]
+            updates: Default::default(),
+        };
+
+        let analytics = app_state.analytics.clone();
+        let user_id = app_state.user_id.clone();
+        app_state.on_config_change(move |old, new| {
+            let user_enabled = new.analytics_enabled.unwrap_or(true);
+            let changed = old.analytics_enabled.unwrap_or(true) != user_enabled;
+            let analytics = analytics.clone();
+            let user_id = user_id.clone();
+            async move {
+                if changed {
+                    update_analytics_config(&analytics, &user_id, user_enabled).await;
+                }
+            }
+        });
+
+        // Attempts queued before a restart wait for a slot again, in their original order
+        match TaskAttempt::find_queued(&app_state.db_pool).await {
+            Ok(queued) => {
+                app_state.project_slots.lock().await.queued = queued
+                    .into_iter()
+                    .map(|(attempt_id, task_id, project_id)| QueuedAttempt {
+                        attempt_id,
+                        task_id,
+                        project_id,
+                    })
+                    .collect();
+            }
+            Err(e) => tracing::error!("Failed to restore queued task attempts: {}", e),
         }
+
+        app_state
     }
 
-    pub async fn update_analytics_config(&self, user_enabled: bool) {
-        // Check if analytics was disabled before this update
-        let was_analytics_disabled = {
-            let analytics = self.analytics.read().await;
-            !analytics.is_enabled()
-        };
+    /// Record whether an attempt waits for a slot, so the queue survives restarts
+    async fn persist_queued(&self, attempt_id: Uuid, queued: bool) {
+        if let Err(e) = TaskAttempt::set_queued(&self.db_pool, attempt_id, queued).await {
+            tracing::error!(
+                "Failed to record whether task attempt {} is queued: {}",
+                attempt_id,
+                e
+            );
+        }
+    }
 
-        let new_config = AnalyticsConfig::new(user_enabled);
-        let new_service = AnalyticsService::new(new_config);
-        let mut analytics = self.analytics.write().await;
-        *analytics = new_service;
+    /// Run `listener` with the old and new config whenever the config is updated
+    pub fn on_config_change<F, Fut>(&self, listener: F)
+    where
+        F: Fn(&Config, &Config) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.config_listeners.0.lock().unwrap().push(Arc::new(
+            move |old, new| -> BoxFuture<'static, ()> { Box::pin(listener(old, new)) },
+        ));
+    }
 
-        // If analytics was disabled and is now enabled, fire a session_start event
-        if was_analytics_disabled && analytics.is_enabled() {
-            analytics.track_event(&self.user_id, "session_start", None);
+    /// Let the registered listeners react to a config update, in registration order. Call
+    /// after the new config is stored.
+    pub async fn notify_config_changed(&self, old: &Config, new: &Config) {
+        let listeners = self.config_listeners.0.lock().unwrap().clone();
+        for listener in listeners {
+            listener(old, new).await;
         }
     }
 
@@ -79,11 +377,31 @@ impl AppState {
             .any(|exec| exec.task_attempt_id == attempt_id)
     }
 
+    /// The process of the attempt's running setup script or coding agent, if any
+    pub async fn running_attempt_stage(&self, attempt_id: Uuid) -> Option<Uuid> {
+        let executions = self.running_executions.lock().await;
+        executions
+            .iter()
+            .find(|(_, exec)| {
+                exec.task_attempt_id == attempt_id
+                    && matches!(
+                        exec.execution_type,
+                        ExecutionType::SetupScript | ExecutionType::CodingAgent
+                    )
+            })
+            .map(|(id, _)| *id)
+    }
+
     pub async fn get_running_executions_for_monitor(&self) -> Vec<(Uuid, Uuid, bool, Option<i64>)> {
         let mut executions = self.running_executions.lock().await;
         let mut completed_executions = Vec::new();
+        // Whoever stops them handles their completion
+        let stopping = self.stopping_executions.lock().unwrap().clone();
 
         for (execution_id, running_exec) in executions.iter_mut() {
+            if stopping.contains(execution_id) {
+                continue;
+            }
             match running_exec.child.try_wait() {
                 Ok(Some(status)) => {
                     let success = status.success();
@@ -111,50 +429,498 @@ impl AppState {
         }
 
         // Remove completed executions from the map
+        let mut dev_servers = self.running_dev_servers.lock().await;
+        let mut network_observers = self.network_observers.lock().await;
         for (execution_id, _, _, _) in &completed_executions {
             executions.remove(execution_id);
+            dev_servers.remove(execution_id);
+            network_observers.remove(execution_id);
         }
 
         completed_executions
     }
 
     // Running executions setters
-    pub async fn add_running_execution(&self, execution_id: Uuid, execution: RunningExecution) {
+    /// Track a spawned execution, lowering its priority as configured. Agents started while
+    /// focus mode is on are paused right away.
+    pub async fn add_running_execution(&self, execution_id: Uuid, mut execution: RunningExecution) {
+        let priority = execution
+            .execution_type
+            .priority(&self.config.read().await.process_priorities);
+        if let Some(pid) = execution.child.id() {
+            if let Err(e) = process_priority::set_priority(pid, priority) {
+                tracing::warn!(
+                    "Failed to lower the priority of execution {}: {}",
+                    execution_id,
+                    e
+                );
+            }
+        }
+
         let mut executions = self.running_executions.lock().await;
+        if self.focus_mode_until().is_some() && execution.execution_type.pausable() {
+            execution.set_paused(true, priority);
+        }
         executions.insert(execution_id, execution);
     }
 
+    /// Stop an execution. Returns whether it was running; see `stop_running_execution`.
     pub async fn stop_running_execution_by_id(
         &self,
         execution_id: Uuid,
     ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        let mut executions = self.running_executions.lock().await;
-        let Some(exec) = executions.get_mut(&execution_id) else {
-            return Ok(false);
+        self.stop_running_execution(execution_id)
+            .await
+            .map(|signal| signal.is_some())
+    }
+
+    /// Stop an execution's whole process group, escalating from SIGINT to SIGTERM to SIGKILL
+    /// with `graceful_shutdown_secs` between them. On Windows the process tree is killed
+    /// right away. Returns the signal it exited on, or `None` when it wasn't running.
+    pub async fn stop_running_execution(
+        &self,
+        execution_id: Uuid,
+    ) -> Result<Option<StopSignal>, Box<dyn std::error::Error + Send + Sync>> {
+        let grace_period =
+            Duration::from_secs(self.config.read().await.graceful_shutdown_secs.into());
+        let pid = {
+            let mut executions = self.running_executions.lock().await;
+            let Some(exec) = executions.get_mut(&execution_id) else {
+                return Ok(None);
+            };
+            // A stopped process only handles SIGINT and SIGTERM once it runs again
+            if exec.paused {
+                let priority = exec
+                    .execution_type
+                    .priority(&self.config.read().await.process_priorities);
+                exec.set_paused(false, priority);
+            }
+            self.stopping_executions
+                .lock()
+                .unwrap()
+                .insert(execution_id);
+            exec.child.id()
         };
 
-        // hit the whole process group, not just the leader
-        #[cfg(unix)]
+        // The executions aren't locked while waiting, so a long grace period doesn't hold up
+        // everything else
+        let result = self.escalate_stop(execution_id, pid, grace_period).await;
+
         {
-            use nix::{sys::signal::killpg, unistd::getpgid};
-
-            let pgid = getpgid(Some(Pid::from_raw(exec.child.id().unwrap() as i32)))?;
-            for sig in [Signal::SIGINT, Signal::SIGTERM, Signal::SIGKILL] {
-                killpg(pgid, sig)?;
-                tokio::time::sleep(Duration::from_secs(2)).await;
-                if exec.child.try_wait()?.is_some() {
-                    break; // gone!
+            let mut executions = self.running_executions.lock().await;
+            if let Some(mut exec) = executions.remove(&execution_id) {
+                // final fallback – command_group already targets the group
+                exec.child.kill().await.ok();
+                exec.child.wait().await.ok(); // reap
+            }
+        }
+        self.stopping_executions
+            .lock()
+            .unwrap()
+            .remove(&execution_id);
+        self.release_dev_server(execution_id).await;
+        self.network_observers.lock().await.remove(&execution_id);
+        result.map(Some)
+    }
+
+    /// Signal the process group of execution `pid` until it exits. A leader that already
+    /// exited and only waits to be reaped can't react anymore, so what's left of its group is
+    /// killed right away.
+    #[cfg(unix)]
+    async fn escalate_stop(
+        &self,
+        execution_id: Uuid,
+        pid: Option<u32>,
+        grace_period: Duration,
+    ) -> Result<StopSignal, Box<dyn std::error::Error + Send + Sync>> {
+        use nix::{errno::Errno, sys::signal::killpg, unistd::getpgid};
+
+        let Some(pid) = pid else {
+            return Ok(StopSignal::Kill); // already reaped
+        };
+        // hit the whole process group, not just the leader
+        let pgid = getpgid(Some(Pid::from_raw(pid as i32)))?;
+        let signals: &[StopSignal] = if self.execution_exited(execution_id).await? {
+            &[StopSignal::Kill]
+        } else {
+            &[
+                StopSignal::Interrupt,
+                StopSignal::Terminate,
+                StopSignal::Kill,
+            ]
+        };
+
+        for &signal in signals {
+            match killpg(pgid, Signal::from(signal)) {
+                // The whole group is gone already
+                Ok(()) | Err(Errno::ESRCH) => {}
+                Err(e) => return Err(e.into()),
+            }
+            if signal == StopSignal::Kill {
+                break;
+            }
+            let deadline = tokio::time::Instant::now() + grace_period;
+            while tokio::time::Instant::now() < deadline {
+                tokio::time::sleep(STOP_POLL_INTERVAL.min(grace_period)).await;
+                if self.execution_exited(execution_id).await? {
+                    return Ok(signal);
                 }
             }
         }
+        Ok(StopSignal::Kill)
+    }
+
+    /// Windows has no signals to escalate through, so the process tree is killed outright.
+    /// `taskkill /T` follows parent links, which catches descendants that broke away from
+    /// the execution's job object; terminating the job object, which command_group assigned
+    /// the tree to, catches descendants whose parent already exited.
+    #[cfg(windows)]
+    async fn escalate_stop(
+        &self,
+        execution_id: Uuid,
+        pid: Option<u32>,
+        grace_period: Duration,
+    ) -> Result<StopSignal, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(pid) = pid else {
+            return Ok(StopSignal::Kill); // already reaped
+        };
+
+        // Runs first, while the parent links it follows are still intact. It fails when the
+        // tree is gone already.
+        let _ = tokio::process::Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .await;
+
+        if let Some(exec) = self.running_executions.lock().await.get_mut(&execution_id) {
+            // command_group terminates the whole job, not just the leader
+            if let Err(e) = exec.child.kill().await {
+                tracing::debug!(
+                    "Failed to terminate job of execution {}: {}",
+                    execution_id,
+                    e
+                );
+            }
+        }
+
+        let deadline = tokio::time::Instant::now() + grace_period;
+        while tokio::time::Instant::now() < deadline {
+            if self.execution_exited(execution_id).await? {
+                break;
+            }
+            tokio::time::sleep(STOP_POLL_INTERVAL.min(grace_period)).await;
+        }
+        Ok(StopSignal::Kill)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    async fn escalate_stop(
+        &self,
+        _execution_id: Uuid,
+        _pid: Option<u32>,
+        _grace_period: Duration,
+    ) -> Result<StopSignal, Box<dyn std::error::Error + Send + Sync>> {
+        // Killed by the fallback, which command_group applies to the whole job
+        Ok(StopSignal::Kill)
+    }
+
+    async fn execution_exited(&self, execution_id: Uuid) -> std::io::Result<bool> {
+        match self.running_executions.lock().await.get_mut(&execution_id) {
+            Some(exec) => Ok(exec.child.try_wait()?.is_some()),
+            None => Ok(true),
+        }
+    }
+
+    /// Keep an execution's logging proxy running until the execution ends
+    pub async fn add_network_observer(&self, execution_id: Uuid, observer: NetworkObserver) {
+        self.network_observers
+            .lock()
+            .await
+            .insert(execution_id, observer);
+    }
+
+    /// Claim a dev server slot for `execution_id` before spawning it. Fails with the
+    /// configured `max_concurrent_dev_servers` when that many are already running.
+    pub async fn try_reserve_dev_server(&self, execution_id: Uuid) -> Result<(), u32> {
+        let limit = self.config.read().await.max_concurrent_dev_servers;
+        let mut dev_servers = self.running_dev_servers.lock().await;
+
+        if let Some(limit) = limit {
+            if dev_servers.len() >= limit as usize {
+                return Err(limit);
+            }
+        }
+
+        dev_servers.insert(execution_id);
+        Ok(())
+    }
+
+    /// Give back a dev server slot; a no-op for executions that aren't dev servers
+    pub async fn release_dev_server(&self, execution_id: Uuid) {
+        self.running_dev_servers.lock().await.remove(&execution_id);
+    }
+
+    pub async fn running_dev_server_count(&self) -> usize {
+        self.running_dev_servers.lock().await.len()
+    }
+
+    /// Claim a slot in the attempt's project before starting it. When the project already runs
+    /// `max_concurrent_per_project` attempts, the attempt is queued instead and `false`
+    /// returned; `admit_queued_attempts` hands it a slot once one frees up. Report how the
+    /// start went with `project_slot_started`.
+    pub async fn try_reserve_project_slot(&self, attempt: QueuedAttempt) -> bool {
+        let limit = self.config.read().await.max_concurrent_per_project;
+        let mut slots = self.project_slots.lock().await;
+
+        let holds_slot = slots
+            .running
+            .get(&attempt.project_id)
+            .is_some_and(|attempts| attempts.contains(&attempt.attempt_id));
+        if holds_slot {
+            return true;
+        }
+        // Attempts queued earlier in the project go first
+        let queue_ahead = slots
+            .queued
+            .iter()
+            .any(|queued| queued.project_id == attempt.project_id);
+        if queue_ahead || !slots.has_room(attempt.project_id, limit) {
+            if !slots.queued.contains(&attempt) {
+                slots.queued.push_back(attempt);
+                drop(slots);
+                self.persist_queued(attempt.attempt_id, true).await;
+            }
+            return false;
+        }
+
+        slots.take(attempt);
+        true
+    }
+
+    /// Record that an attempt given a slot has started its first process, or failed to start,
+    /// in which case its slot is given back
+    pub async fn project_slot_started(&self, attempt_id: Uuid, started: bool) {
+        let mut slots = self.project_slots.lock().await;
+        slots.starting.remove(&attempt_id);
+        if !started {
+            slots.release(attempt_id);
+        }
+    }
+
+    /// Free the slots of attempts that have nothing running anymore, then give freed slots to
+    /// queued attempts, oldest first. Returns the attempts to start now; report how each start
+    /// went with `project_slot_started`.
+    pub async fn admit_queued_attempts(&self) -> Vec<QueuedAttempt> {
+        let limit = self.config.read().await.max_concurrent_per_project;
+        // Dev servers run alongside review rather than as part of the attempt's run
+        let busy: HashSet<Uuid> = self
+            .running_executions
+            .lock()
+            .await
+            .values()
+            .filter(|execution| !matches!(execution.execution_type, ExecutionType::DevServer))
+            .map(|execution| execution.task_attempt_id)
+            .collect();
+
+        let mut slots = self.project_slots.lock().await;
+        let finished: Vec<Uuid> = slots
+            .running
+            .values()
+            .flatten()
+            .filter(|attempt_id| !busy.contains(attempt_id) && !slots.starting.contains(attempt_id))
+            .copied()
+            .collect();
+        for attempt_id in finished {
+            slots.release(attempt_id);
+        }
+
+        let mut admitted = Vec::new();
+        let mut waiting = VecDeque::new();
+        while let Some(attempt) = slots.queued.pop_front() {
+            let queue_ahead = waiting
+                .iter()
+                .any(|queued: &QueuedAttempt| queued.project_id == attempt.project_id);
+            if !queue_ahead && slots.has_room(attempt.project_id, limit) {
+                slots.take(attempt);
+                admitted.push(attempt);
+            } else {
+                waiting.push_back(attempt);
+            }
+        }
+        slots.queued = waiting;
+        drop(slots);
 
-        // final fallback – command_group already targets the group
-        exec.child.kill().await.ok();
-        exec.child.wait().await.ok(); // reap
+        for attempt in &admitted {
+            self.persist_queued(attempt.attempt_id, false).await;
+        }
+        admitted
+    }
+
+    /// Keep the attempt's next stages, like the coding agent after its setup script, from
+    /// starting, and take it out of its project's queue. Returns whether it was queued.
+    pub async fn cancel_attempt(&self, attempt_id: Uuid) -> bool {
+        self.cancelled_attempts.lock().unwrap().insert(attempt_id);
+        let mut slots = self.project_slots.lock().await;
+        let queued = slots.queued.len();
+        slots
+            .queued
+            .retain(|queued| queued.attempt_id != attempt_id);
+        let was_queued = queued != slots.queued.len();
+        drop(slots);
+
+        if was_queued {
+            self.persist_queued(attempt_id, false).await;
+        }
+        was_queued
+    }
+
+    pub fn is_attempt_cancelled(&self, attempt_id: Uuid) -> bool {
+        self.cancelled_attempts
+            .lock()
+            .unwrap()
+            .contains(&attempt_id)
+    }
+
+    /// Let a cancelled attempt run again, when the user starts it themselves
+    pub fn clear_attempt_cancellation(&self, attempt_id: Uuid) {
+        self.cancelled_attempts.lock().unwrap().remove(&attempt_id);
+    }
 
-        // only NOW remove it
-        executions.remove(&execution_id);
-        Ok(true)
+    /// Whether the attempt is waiting for a slot in its project
+    pub async fn is_attempt_queued(&self, attempt_id: Uuid) -> bool {
+        self.project_slots
+            .lock()
+            .await
+            .queued
+            .iter()
+            .any(|queued| queued.attempt_id == attempt_id)
+    }
+
+    /// Claim one of the `MAX_DIFF_STREAMS_PER_ATTEMPT` diff-stream slots for an attempt.
+    /// Returns `None` when they are all taken.
+    pub fn try_acquire_diff_stream(&self, attempt_id: Uuid) -> Option<AttemptSlot> {
+        AttemptSlot::try_acquire(&self.diff_streams, attempt_id, MAX_DIFF_STREAMS_PER_ATTEMPT)
+    }
+
+    /// Claim one of the `MAX_LOG_STREAMS_PER_ATTEMPT` log-stream slots for an attempt.
+    /// Returns `None` when they are all taken.
+    pub fn try_acquire_log_stream(&self, attempt_id: Uuid) -> Option<AttemptSlot> {
+        AttemptSlot::try_acquire(&self.log_streams, attempt_id, MAX_LOG_STREAMS_PER_ATTEMPT)
+    }
+
+    /// Claim one of the `MAX_WAITERS_PER_ATTEMPT` slots for waiting on an attempt. Returns
+    /// `None` when they are all taken.
+    pub fn try_acquire_attempt_waiter(&self, attempt_id: Uuid) -> Option<AttemptSlot> {
+        AttemptSlot::try_acquire(&self.attempt_waiters, attempt_id, MAX_WAITERS_PER_ATTEMPT)
+    }
+
+    /// When focus mode resumes paused executions, while it's on
+    pub fn focus_mode_until(&self) -> Option<DateTime<Utc>> {
+        *self.focus_until.lock().unwrap()
+    }
+
+    /// Pause every running execution except dev servers until `until`, extending focus mode
+    /// when it's already on
+    pub async fn pause_executions_until(&self, until: DateTime<Utc>) {
+        let priorities = self.config.read().await.process_priorities.clone();
+        let mut executions = self.running_executions.lock().await;
+        *self.focus_until.lock().unwrap() = Some(until);
+        let stopping = self.stopping_executions.lock().unwrap().clone();
+        for (execution_id, execution) in executions.iter_mut() {
+            if execution.execution_type.pausable()
+                && !execution.paused
+                && !stopping.contains(execution_id)
+            {
+                let priority = execution.execution_type.priority(&priorities);
+                execution.set_paused(true, priority);
+            }
+        }
+    }
+
+    /// End focus mode, resuming the executions it paused
+    pub async fn resume_paused_executions(&self) {
+        let priorities = self.config.read().await.process_priorities.clone();
+        let mut executions = self.running_executions.lock().await;
+        *self.focus_until.lock().unwrap() = None;
+        for execution in executions.values_mut() {
+            if execution.paused {
+                let priority = execution.execution_type.priority(&priorities);
+                execution.set_paused(false, priority);
+            }
+        }
+    }
+
+    pub async fn focus_mode_status(&self) -> FocusModeStatus {
+        let executions = self.running_executions.lock().await;
+        let until = self.focus_mode_until();
+        FocusModeStatus {
+            active: until.is_some(),
+            until,
+            paused_process_ids: executions
+                .iter()
+                .filter(|(_, execution)| execution.paused)
+                .map(|(execution_id, _)| *execution_id)
+                .collect(),
+        }
+    }
+
+    /// When focus mode resumes the attempt, if it paused any of its executions
+    pub async fn attempt_paused_until(&self, attempt_id: Uuid) -> Option<DateTime<Utc>> {
+        let executions = self.running_executions.lock().await;
+        executions
+            .values()
+            .any(|execution| execution.task_attempt_id == attempt_id && execution.paused)
+            .then(|| self.focus_mode_until())
+            .flatten()
+    }
+
+    /// Stop every running execution, recording `reason` on each stopped process. Returns the
+    /// ids of the executions that were actually stopped, and what went wrong along the way;
+    /// a failure with one execution doesn't keep the others from being stopped.
+    pub async fn stop_all_running_executions(&self, reason: &str) -> (Vec<Uuid>, Vec<String>) {
+        let execution_ids: Vec<Uuid> = {
+            let executions = self.running_executions.lock().await;
+            executions.keys().copied().collect()
+        };
+
+        let mut stopped = Vec::new();
+        let mut errors = Vec::new();
+        for execution_id in execution_ids {
+            match self.stop_running_execution_by_id(execution_id).await {
+                Ok(true) => {
+                    stopped.push(execution_id);
+                    if let Err(e) =
+                        crate::models::execution_process::ExecutionProcess::mark_stopped(
+                            &self.db_pool,
+                            execution_id,
+                            reason,
+                        )
+                        .await
+                    {
+                        tracing::error!(
+                            "Failed to record stop of execution {}: {}",
+                            execution_id,
+                            e
+                        );
+                        errors.push(format!(
+                            "Failed to record stop of process {}: {}",
+                            execution_id, e
+                        ));
+                    }
+                }
+                Ok(false) => {
+                    // Finished on its own while we were stopping the others
+                }
+                Err(e) => {
+                    tracing::error!("Failed to stop execution {}: {}", execution_id, e);
+                    errors.push(format!("Failed to stop process {}: {}", execution_id, e));
+                }
+            }
+        }
+
+        (stopped, errors)
     }
 
     // Config getters
@@ -185,6 +951,11 @@ impl AppState {
         // Note: Analytics are force-disabled in analytics.rs - this will always skip sending data
         let analytics = self.analytics.read().await;
         if analytics.is_enabled() {
+            let include_hashes = self.config.read().await.analytics_content_hashes;
+            let properties = properties.map(|mut props| {
+                redact_content_properties(&mut props, &self.user_id, include_hashes);
+                props
+            });
             analytics.track_event(&self.user_id, event_name, properties);
         } else {
             tracing::debug!("Analytics disabled, skipping event: {}", event_name);
@@ -216,3 +987,534 @@ impl AppState {
         });
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use command_group::AsyncCommandGroup;
+
+    use super::*;
+    use crate::{
+        models::{
+            config::Config,
+            execution_process::{
+                CreateExecutionProcess, ExecutionProcess, ExecutionProcessStatus,
+                ExecutionProcessType,
+            },
+        },
+        test_utils::{insert_attempt, insert_project, insert_task, memory_pool},
+    };
+
+    async fn test_state() -> AppState {
+        test_state_with_config(Config::default()).await
+    }
+
+    async fn test_state_with_config(config: Config) -> AppState {
+        let pool = memory_pool().await;
+
+        AppState::new(pool, Arc::new(TokioRwLock::new(config))).await
+    }
+
+    /// An attempt in a new project and task, for processes to belong to
+    async fn seed_attempt(pool: &sqlx::SqlitePool) -> Uuid {
+        let project_id = insert_project(pool, "/tmp/p").await;
+        let task_id = insert_task(pool, project_id, "t").await;
+        insert_attempt(pool, task_id, "/tmp/w", "b").await
+    }
+
+    #[tokio::test]
+    async fn test_stop_all_running_executions_records_reason() {
+        let state = test_state().await;
+        let attempt_id = seed_attempt(&state.db_pool).await;
+
+        let mut process_ids = Vec::new();
+        for _ in 0..2 {
+            let process_id = Uuid::new_v4();
+            ExecutionProcess::create(
+                &state.db_pool,
+                &CreateExecutionProcess {
+                    task_attempt_id: attempt_id,
+                    process_type: ExecutionProcessType::CodingAgent,
+                    executor_type: Some("echo".to_string()),
+                    command: "sleep".to_string(),
+                    args: None,
+                    working_directory: "/tmp".to_string(),
+                },
+                process_id,
+            )
+            .await
+            .unwrap();
+
+            let child = tokio::process::Command::new("sleep")
+                .arg("30")
+                .group_spawn()
+                .unwrap();
+            state
+                .add_running_execution(
+                    process_id,
+                    RunningExecution {
+                        task_attempt_id: attempt_id,
+                        execution_type: ExecutionType::CodingAgent,
+                        paused: false,
+                        child,
+                    },
+                )
+                .await;
+            process_ids.push(process_id);
+        }
+
+        let (stopped, errors) = state.stop_all_running_executions("operator stop").await;
+
+        assert_eq!(stopped.len(), 2);
+        assert!(errors.is_empty());
+        assert!(!state.has_running_execution(attempt_id).await);
+        for process_id in process_ids {
+            let process = ExecutionProcess::find_by_id(&state.db_pool, process_id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(process.status, ExecutionProcessStatus::Killed);
+            assert_eq!(process.stop_reason.as_deref(), Some("operator stop"));
+            assert!(process.completed_at.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_all_keeps_stopping_when_recording_a_stop_fails() {
+        let state = test_state().await;
+        let mut process_ids = Vec::new();
+        for _ in 0..2 {
+            let process_id = Uuid::new_v4();
+            let child = tokio::process::Command::new("sleep")
+                .arg("30")
+                .group_spawn()
+                .unwrap();
+            state
+                .add_running_execution(
+                    process_id,
+                    RunningExecution {
+                        task_attempt_id: Uuid::new_v4(),
+                        execution_type: ExecutionType::CodingAgent,
+                        paused: false,
+                        child,
+                    },
+                )
+                .await;
+            process_ids.push(process_id);
+        }
+        // Every stop fails to be recorded
+        state.db_pool.close().await;
+
+        let (mut stopped, errors) = state.stop_all_running_executions("operator stop").await;
+
+        stopped.sort();
+        process_ids.sort();
+        assert_eq!(stopped, process_ids);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("Failed to record stop of process"));
+        assert_eq!(state.running_executions.lock().await.len(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_reports_the_signal_the_process_exited_on() {
+        let state = test_state_with_config(Config {
+            graceful_shutdown_secs: 1,
+            ..Config::default()
+        })
+        .await;
+        let run = |script: &str| {
+            let state = state.clone();
+            let script = script.to_string();
+            async move {
+                let execution_id = Uuid::new_v4();
+                let child = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(script)
+                    .group_spawn()
+                    .unwrap();
+                state
+                    .add_running_execution(
+                        execution_id,
+                        RunningExecution {
+                            task_attempt_id: Uuid::new_v4(),
+                            execution_type: ExecutionType::SetupScript,
+                            paused: false,
+                            child,
+                        },
+                    )
+                    .await;
+                execution_id
+            }
+        };
+
+        let interruptible = run("sleep 30").await;
+        assert_eq!(
+            state.stop_running_execution(interruptible).await.unwrap(),
+            Some(StopSignal::Interrupt)
+        );
+
+        let ignores_interrupts = run("trap '' INT; while true; do sleep 0.1; done").await;
+        assert_eq!(
+            state
+                .stop_running_execution(ignores_interrupts)
+                .await
+                .unwrap(),
+            Some(StopSignal::Terminate)
+        );
+
+        // A leader that already exited goes straight to SIGKILL for the rest of its group
+        let exited = run("true").await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(
+            state.stop_running_execution(exited).await.unwrap(),
+            Some(StopSignal::Kill)
+        );
+        assert_eq!(state.stop_running_execution(exited).await.unwrap(), None);
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn test_stop_kills_the_whole_process_tree() {
+        fn child_pids(parent: u32) -> Vec<u32> {
+            let output = std::process::Command::new("powershell")
+                .args([
+                    "-NoProfile",
+                    "-Command",
+                    &format!(
+                        "Get-CimInstance Win32_Process -Filter 'ParentProcessId={}' | ForEach-Object {{ $_.ProcessId }}",
+                        parent
+                    ),
+                ])
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect()
+        }
+        fn is_running(pid: u32) -> bool {
+            let output = std::process::Command::new("tasklist")
+                .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+        }
+
+        let state = test_state_with_config(Config {
+            graceful_shutdown_secs: 1,
+            ..Config::default()
+        })
+        .await;
+        let child = tokio::process::Command::new("cmd")
+            .args([
+                "/C",
+                "start /B ping -n 60 127.0.0.1 >NUL & ping -n 60 127.0.0.1 >NUL",
+            ])
+            .group_spawn()
+            .unwrap();
+        let leader = child.id().unwrap();
+        let execution_id = Uuid::new_v4();
+        state
+            .add_running_execution(
+                execution_id,
+                RunningExecution {
+                    task_attempt_id: Uuid::new_v4(),
+                    execution_type: ExecutionType::SetupScript,
+                    paused: false,
+                    child,
+                },
+            )
+            .await;
+
+        let mut sleepers = Vec::new();
+        for _ in 0..50 {
+            sleepers = child_pids(leader);
+            if sleepers.len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(!sleepers.is_empty());
+
+        assert_eq!(
+            state.stop_running_execution(execution_id).await.unwrap(),
+            Some(StopSignal::Kill)
+        );
+        assert!(!is_running(leader));
+        for sleeper in sleepers {
+            assert!(!is_running(sleeper), "child {} survived the stop", sleeper);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_second_dev_server_blocked_when_cap_is_one() {
+        let state = test_state_with_config(Config {
+            max_concurrent_dev_servers: Some(1),
+            ..Config::default()
+        })
+        .await;
+        let attempt_id = seed_attempt(&state.db_pool).await;
+
+        let first_dev_server = Uuid::new_v4();
+        assert_eq!(state.try_reserve_dev_server(first_dev_server).await, Ok(()));
+
+        let child = tokio::process::Command::new("sleep")
+            .arg("30")
+            .group_spawn()
+            .unwrap();
+        state
+            .add_running_execution(
+                first_dev_server,
+                RunningExecution {
+                    task_attempt_id: attempt_id,
+                    execution_type: ExecutionType::DevServer,
+                    paused: false,
+                    child,
+                },
+            )
+            .await;
+
+        let second_dev_server = Uuid::new_v4();
+        assert_eq!(
+            state.try_reserve_dev_server(second_dev_server).await,
+            Err(1)
+        );
+        assert_eq!(state.running_dev_server_count().await, 1);
+
+        // Stopping the first dev server frees its slot
+        assert!(state
+            .stop_running_execution_by_id(first_dev_server)
+            .await
+            .unwrap());
+        assert_eq!(state.running_dev_server_count().await, 0);
+        assert_eq!(
+            state.try_reserve_dev_server(second_dev_server).await,
+            Ok(())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_second_attempt_in_a_project_queues_while_other_projects_start() {
+        let state = test_state_with_config(Config {
+            max_concurrent_per_project: Some(1),
+            ..Config::default()
+        })
+        .await;
+        let busy_project = Uuid::new_v4();
+        let attempt = |project_id| QueuedAttempt {
+            attempt_id: Uuid::new_v4(),
+            task_id: Uuid::new_v4(),
+            project_id,
+        };
+
+        let first = attempt(busy_project);
+        assert!(state.try_reserve_project_slot(first).await);
+        let child = tokio::process::Command::new("sleep")
+            .arg("30")
+            .group_spawn()
+            .unwrap();
+        let first_process = Uuid::new_v4();
+        state
+            .add_running_execution(
+                first_process,
+                RunningExecution {
+                    task_attempt_id: first.attempt_id,
+                    execution_type: ExecutionType::CodingAgent,
+                    paused: false,
+                    child,
+                },
+            )
+            .await;
+        state.project_slot_started(first.attempt_id, true).await;
+
+        let second = attempt(busy_project);
+        assert!(!state.try_reserve_project_slot(second).await);
+        assert!(state.is_attempt_queued(second.attempt_id).await);
+
+        // Another project isn't held up by the busy one
+        let other = attempt(Uuid::new_v4());
+        assert!(state.try_reserve_project_slot(other).await);
+        state.project_slot_started(other.attempt_id, false).await;
+
+        // The queued attempt waits while the first one runs, and starts once it's done
+        assert!(state.admit_queued_attempts().await.is_empty());
+        assert!(state
+            .stop_running_execution_by_id(first_process)
+            .await
+            .unwrap());
+        assert_eq!(state.admit_queued_attempts().await, vec![second]);
+        assert!(!state.is_attempt_queued(second.attempt_id).await);
+        assert!(!state.try_reserve_project_slot(attempt(busy_project)).await);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_attempt_leaves_the_queue() {
+        let state = test_state_with_config(Config {
+            max_concurrent_per_project: Some(1),
+            ..Config::default()
+        })
+        .await;
+        let project_id = Uuid::new_v4();
+        let attempt = || QueuedAttempt {
+            attempt_id: Uuid::new_v4(),
+            task_id: Uuid::new_v4(),
+            project_id,
+        };
+
+        let (first, second, third) = (attempt(), attempt(), attempt());
+        assert!(state.try_reserve_project_slot(first).await);
+        state.project_slot_started(first.attempt_id, true).await;
+        assert!(!state.try_reserve_project_slot(second).await);
+        assert!(!state.try_reserve_project_slot(third).await);
+
+        assert!(state.cancel_attempt(second.attempt_id).await);
+        assert!(state.is_attempt_cancelled(second.attempt_id));
+        assert!(!state.cancel_attempt(first.attempt_id).await);
+
+        // The first attempt has no process, so its slot frees up for the one behind the
+        // cancelled attempt
+        assert_eq!(state.admit_queued_attempts().await, vec![third]);
+        state.clear_attempt_cancellation(second.attempt_id);
+        assert!(!state.is_attempt_cancelled(second.attempt_id));
+    }
+
+    #[tokio::test]
+    async fn test_queued_attempts_are_rebuilt_after_a_restart() {
+        let pool = memory_pool().await;
+        let config = Arc::new(TokioRwLock::new(Config {
+            max_concurrent_per_project: Some(1),
+            ..Config::default()
+        }));
+        let project_id = insert_project(&pool, "/tmp/p").await;
+        let mut attempts = Vec::new();
+        for name in ["first", "second", "third"] {
+            let task_id = insert_task(&pool, project_id, name).await;
+            let attempt_id = insert_attempt(&pool, task_id, &format!("/tmp/{}", name), name).await;
+            attempts.push(QueuedAttempt {
+                attempt_id,
+                task_id,
+                project_id,
+            });
+        }
+        let (first, second, third) = (attempts[0], attempts[1], attempts[2]);
+
+        let state = AppState::new(pool.clone(), config.clone()).await;
+        assert!(state.try_reserve_project_slot(first).await);
+        state.project_slot_started(first.attempt_id, true).await;
+        assert!(!state.try_reserve_project_slot(second).await);
+        assert!(!state.try_reserve_project_slot(third).await);
+        assert!(state.cancel_attempt(third.attempt_id).await);
+
+        // After a restart the attempt still queued is back in line, and starts once there's room
+        let restarted = AppState::new(pool.clone(), config).await;
+        assert!(restarted.is_attempt_queued(second.attempt_id).await);
+        assert!(!restarted.is_attempt_queued(third.attempt_id).await);
+        assert_eq!(restarted.admit_queued_attempts().await, vec![second]);
+        assert!(TaskAttempt::find_queued(&pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_focus_mode_pauses_agents_but_not_dev_servers() {
+        let state = test_state().await;
+        let attempt_id = seed_attempt(&state.db_pool).await;
+        let spawn = |execution_type| RunningExecution {
+            task_attempt_id: attempt_id,
+            execution_type,
+            child: tokio::process::Command::new("sleep")
+                .arg("30")
+                .group_spawn()
+                .unwrap(),
+            paused: false,
+        };
+
+        let agent = Uuid::new_v4();
+        let dev_server = Uuid::new_v4();
+        state
+            .add_running_execution(agent, spawn(ExecutionType::CodingAgent))
+            .await;
+        state
+            .add_running_execution(dev_server, spawn(ExecutionType::DevServer))
+            .await;
+
+        let until = Utc::now() + chrono::Duration::minutes(5);
+        state.pause_executions_until(until).await;
+        let status = state.focus_mode_status().await;
+        assert!(status.active);
+        assert_eq!(status.paused_process_ids, vec![agent]);
+        assert_eq!(state.attempt_paused_until(attempt_id).await, Some(until));
+
+        // Processes started during focus mode are paused too
+        let late_agent = Uuid::new_v4();
+        state
+            .add_running_execution(late_agent, spawn(ExecutionType::SetupScript))
+            .await;
+        assert_eq!(state.focus_mode_status().await.paused_process_ids.len(), 2);
+
+        // Stopping a paused execution resumes it first so it can shut down
+        assert!(state.stop_running_execution_by_id(agent).await.unwrap());
+        assert_eq!(
+            state.focus_mode_status().await.paused_process_ids,
+            vec![late_agent]
+        );
+
+        state.resume_paused_executions().await;
+        let status = state.focus_mode_status().await;
+        assert!(!status.active);
+        assert!(status.paused_process_ids.is_empty());
+        assert_eq!(state.attempt_paused_until(attempt_id).await, None);
+
+        state.stop_all_running_executions("test over").await;
+    }
+
+    #[tokio::test]
+    async fn test_diff_stream_slots_are_limited_per_attempt() {
+        let state = test_state().await;
+        let attempt_id = Uuid::new_v4();
+
+        let mut slots: Vec<_> = (0..MAX_DIFF_STREAMS_PER_ATTEMPT)
+            .map(|_| state.try_acquire_diff_stream(attempt_id).unwrap())
+            .collect();
+        assert!(state.try_acquire_diff_stream(attempt_id).is_none());
+        // Other attempts have their own slots
+        assert!(state.try_acquire_diff_stream(Uuid::new_v4()).is_some());
+
+        // A closed connection frees its slot
+        slots.pop();
+        assert!(state.try_acquire_diff_stream(attempt_id).is_some());
+    }
+}
+
+#[cfg(test)]
+mod config_listener_tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::test_utils::memory_pool;
+
+    #[tokio::test]
+    async fn test_config_change_listeners_see_old_and_new_config() {
+        let pool = memory_pool().await;
+        let config = Config {
+            analytics_enabled: Some(false),
+            ..Config::default()
+        };
+        let app_state = AppState::new(pool, Arc::new(TokioRwLock::new(config.clone()))).await;
+
+        let threshold_changes = Arc::new(AtomicU32::new(0));
+        let changes = threshold_changes.clone();
+        app_state.on_config_change(move |old, new| {
+            if old.diff_rename_threshold != new.diff_rename_threshold {
+                changes.fetch_add(1, Ordering::SeqCst);
+            }
+            async {}
+        });
+
+        let updated = Config {
+            diff_rename_threshold: 80,
+            ..config.clone()
+        };
+        app_state.notify_config_changed(&config, &updated).await;
+        app_state.notify_config_changed(&updated, &updated).await;
+        assert_eq!(threshold_changes.load(Ordering::SeqCst), 1);
+    }
+}
@@ -2,17 +2,39 @@ use std::{env, fs, path::Path};
 
 use ts_rs::TS; // in [build-dependencies]
 
+/// Executor names and labels, from the agents registered with the executor registry
+fn generate_executor_constants() -> String {
+    let executors: Vec<_> = codecommand::executors::registry::available_executors()
+        .into_iter()
+        .filter_map(|name| {
+            let factory = codecommand::executors::registry::executor_factory(&name)?;
+            Some((name, factory.display_name()))
+        })
+        .collect();
+    let types: Vec<_> = executors
+        .iter()
+        .map(|(name, _)| format!("    \"{}\"", name))
+        .collect();
+    let labels: Vec<_> = executors
+        .iter()
+        .map(|(name, label)| format!("    \"{}\": \"{}\"", name, label))
+        .collect();
+    format!(
+        "export const EXECUTOR_TYPES: string[] = [\n{}\n];\n\nexport const EXECUTOR_LABELS: Record<string, string> = {{\n{}\n}};",
+        types.join(",\n"),
+        labels.join(",\n")
+    )
+}
+
 fn generate_constants() -> String {
-    r#"// Generated constants
-export const EXECUTOR_TYPES: string[] = [
-    "echo",
-    "claude",
-    "amp",
-    "gemini",
-    "opencode"
-];
+    format!(
+        "// Generated constants\n{}\n\n{}",
+        generate_executor_constants(),
+        OTHER_CONSTANTS
+    )
+}
 
-export const EDITOR_TYPES: EditorType[] = [
+const OTHER_CONSTANTS: &str = r#"export const EDITOR_TYPES: EditorType[] = [
     "vscode",
     "cursor", 
     "windsurf",
@@ -21,14 +43,6 @@ export const EDITOR_TYPES: EditorType[] = [
     "custom"
 ];
 
-export const EXECUTOR_LABELS: Record<string, string> = {
-    "echo": "Echo (Test Mode)",
-    "claude": "Claude",
-    "amp": "Amp",
-    "gemini": "Gemini",
-    "opencode": "OpenCode"
-};
-
 export const EDITOR_LABELS: Record<string, string> = {
     "vscode": "VS Code",
     "cursor": "Cursor",
@@ -56,9 +70,7 @@ export const SOUND_LABELS: Record<string, string> = {
     "cow-mooing": "Cow Mooing",
     "phone-vibration": "Phone Vibration",
     "rooster": "Rooster Call"
-};"#
-    .to_string()
-}
+};"#;
 
 fn main() {
     // 1. Make sure ../shared exists
@@ -74,6 +86,12 @@ fn main() {
     let decls = [
         codecommand::models::ApiResponse::<()>::decl(),
         codecommand::models::config::Config::decl(),
+        codecommand::models::config::ProcessPriority::decl(),
+        codecommand::models::config::ProcessPriorities::decl(),
+        codecommand::models::config::ProvenanceMode::decl(),
+        codecommand::models::config::AttemptStartHook::decl(),
+        codecommand::models::config::AssetQuotas::decl(),
+        codecommand::models::config::ClaudeSettings::decl(),
         codecommand::models::config::ThemeMode::decl(),
         codecommand::models::config::EditorConfig::decl(),
         codecommand::models::config::GitHubConfig::decl(),
@@ -83,13 +101,26 @@ fn main() {
         codecommand::models::config::SoundConstants::decl(),
         codecommand::routes::config::ConfigConstants::decl(),
         codecommand::executor::ExecutorConfig::decl(),
+        codecommand::executor::OutputFormat::decl(),
         codecommand::executor::ExecutorConstants::decl(),
+        codecommand::models::project::ProjectType::decl(),
+        codecommand::models::project::FormatCommitMode::decl(),
+        codecommand::models::project::ProjectCapabilities::decl(),
         codecommand::models::project::CreateProject::decl(),
         codecommand::models::project::Project::decl(),
+        codecommand::models::project::HeadState::decl(),
+        codecommand::services::repo_config::RepoConfigStatus::decl(),
         codecommand::models::project::ProjectWithBranch::decl(),
+        codecommand::models::project::ProjectStats::decl(),
         codecommand::models::project::UpdateProject::decl(),
         codecommand::models::project::SearchResult::decl(),
         codecommand::models::project::SearchMatchType::decl(),
+        codecommand::models::project::SearchResultType::decl(),
+        codecommand::models::project::SearchType::decl(),
+        codecommand::models::project::SearchResultPage::decl(),
+        codecommand::models::activity_heatmap::HeatmapBucketSize::decl(),
+        codecommand::models::activity_heatmap::ActivityHeatmapBucket::decl(),
+        codecommand::models::activity_heatmap::ActivityHeatmap::decl(),
         codecommand::models::project::GitBranch::decl(),
         codecommand::models::project::CreateBranch::decl(),
         codecommand::models::task::CreateTask::decl(),
@@ -98,6 +129,11 @@ fn main() {
         codecommand::models::task::Task::decl(),
         codecommand::models::task::TaskWithAttemptStatus::decl(),
         codecommand::models::task::UpdateTask::decl(),
+        codecommand::models::task::TaskField::decl(),
+        codecommand::models::task::TaskConflict::decl(),
+        codecommand::models::task::TaskEstimateReport::decl(),
+        codecommand::models::task_dependency::TaskDependency::decl(),
+        codecommand::models::task_dependency::CreateTaskDependency::decl(),
         codecommand::models::task_attempt::TaskAttemptStatus::decl(),
         codecommand::models::task_attempt::TaskAttempt::decl(),
         codecommand::models::task_attempt::CreateTaskAttempt::decl(),
@@ -106,28 +142,149 @@ fn main() {
         codecommand::models::task_attempt_activity::TaskAttemptActivity::decl(),
         codecommand::models::task_attempt_activity::TaskAttemptActivityWithPrompt::decl(),
         codecommand::models::task_attempt_activity::CreateTaskAttemptActivity::decl(),
+        codecommand::models::user_preferences::TaskSortOrder::decl(),
+        codecommand::models::user_preferences::DiffGranularity::decl(),
+        codecommand::models::user_preferences::UserPreferences::decl(),
+        codecommand::models::user_preferences::UpdateUserPreferences::decl(),
         codecommand::routes::filesystem::DirectoryEntry::decl(),
+        codecommand::routes::filesystem::MovePathRequest::decl(),
+        codecommand::routes::filesystem::MovePathResponse::decl(),
+        codecommand::routes::executions::StopAllExecutionsRequest::decl(),
+        codecommand::routes::executions::StopAllExecutionsResponse::decl(),
+        codecommand::routes::executions::StartFocusMode::decl(),
+        codecommand::services::focus_mode::FocusModeStatus::decl(),
+        codecommand::services::provenance::ProvenanceSource::decl(),
+        codecommand::services::provenance::ProvenanceResolution::decl(),
+        codecommand::services::start_hook::AttemptStartContext::decl(),
+        codecommand::routes::executions::RenormalizeRequest::decl(),
+        codecommand::services::log_normalization::RenormalizeResult::decl(),
+        codecommand::routes::executions::RenormalizeResponse::decl(),
+        codecommand::routes::executions::PromoteNormalizationRequest::decl(),
+        codecommand::routes::executions::PromotionReport::decl(),
+        codecommand::models::normalized_log_snapshot::SnapshotVersion::decl(),
+        codecommand::services::log_normalization::NormalizedLogDiff::decl(),
+        codecommand::services::log_filter::FilteredConversation::decl(),
+        codecommand::services::job_scheduler::JobStatus::decl(),
+        codecommand::models::annotation::AnnotationType::decl(),
+        codecommand::models::annotation::Annotation::decl(),
+        codecommand::models::annotation::CreateAnnotation::decl(),
+        codecommand::models::glossary::GlossaryEntryStatus::decl(),
+        codecommand::models::glossary::GlossaryEntry::decl(),
+        codecommand::models::glossary::CreateGlossaryEntry::decl(),
+        codecommand::models::glossary::UpdateGlossaryEntry::decl(),
+        codecommand::models::benchmark::BenchmarkRunStatus::decl(),
+        codecommand::models::benchmark::BenchmarkAttemptStatus::decl(),
+        codecommand::models::benchmark::BenchmarkRun::decl(),
+        codecommand::models::benchmark::BenchmarkAttempt::decl(),
+        codecommand::models::benchmark::CreateBenchmarkRun::decl(),
+        codecommand::models::benchmark::ExecutorBenchmarkSummary::decl(),
+        codecommand::models::benchmark::BenchmarkTaskResult::decl(),
+        codecommand::models::benchmark::BenchmarkReport::decl(),
+        codecommand::services::attempt_overlap::OverlapSeverity::decl(),
+        codecommand::services::attempt_overlap::FileOverlap::decl(),
+        codecommand::services::attempt_overlap::AttemptOverlap::decl(),
+        codecommand::services::attempt_overlap::AttemptOverlapSummary::decl(),
+        codecommand::services::attempt_overlap::AttemptOverlapReport::decl(),
+        codecommand::routes::jobs::UpdateJob::decl(),
         codecommand::models::task_attempt::DiffChunkType::decl(),
         codecommand::models::task_attempt::DiffChunk::decl(),
         codecommand::models::task_attempt::FileDiff::decl(),
         codecommand::models::task_attempt::WorktreeDiff::decl(),
         codecommand::models::task_attempt::BranchStatus::decl(),
+        codecommand::services::branch_status::CachedBranchStatus::decl(),
+        codecommand::models::untracked_change_journal::UntrackedChangeKind::decl(),
+        codecommand::models::untracked_change_journal::UntrackedChange::decl(),
+        codecommand::models::untracked_change_journal::UntrackedChangeReport::decl(),
+        codecommand::models::webhook::WebhookEventType::decl(),
+        codecommand::models::webhook::WebhookEndpoint::decl(),
+        codecommand::models::webhook::CreateWebhookEndpoint::decl(),
+        codecommand::models::webhook::UpdateWebhookEndpoint::decl(),
+        codecommand::models::webhook::WebhookDeliveryStatus::decl(),
+        codecommand::models::webhook::WebhookDelivery::decl(),
+        codecommand::services::webhooks::WebhookPayload::decl(),
+        codecommand::models::bisect_run::BisectRunStatus::decl(),
+        codecommand::models::bisect_run::BisectFileChange::decl(),
+        codecommand::models::bisect_run::BisectCommit::decl(),
+        codecommand::models::bisect_run::BisectRun::decl(),
+        codecommand::routes::bisect::StartBisect::decl(),
+        codecommand::models::format_run::FormatRunStatus::decl(),
+        codecommand::models::format_run::FormattingDiff::decl(),
+        codecommand::models::format_run::FormatRun::decl(),
+        codecommand::models::network_policy::NetworkPolicy::decl(),
+        codecommand::models::network_policy::UpdateNetworkPolicy::decl(),
+        codecommand::models::network_policy::NetworkVerdict::decl(),
+        codecommand::models::network_policy::NetworkContact::decl(),
+        codecommand::models::network_policy::AttemptNetworkReport::decl(),
         codecommand::models::task_attempt::ExecutionState::decl(),
         codecommand::models::task_attempt::TaskAttemptState::decl(),
+        codecommand::models::task_attempt::WorktreeStatus::decl(),
+        codecommand::models::task_attempt::WorktreeRelocation::decl(),
+        codecommand::models::task_attempt::WorktreeRepairReport::decl(),
+        codecommand::models::task_attempt::AttemptRunStatus::decl(),
+        codecommand::models::task_attempt::TaskAttemptListItem::decl(),
+        codecommand::models::task_attempt::TaskAttemptListPage::decl(),
+        codecommand::routes::task_attempts::RelocateWorktree::decl(),
+        codecommand::services::attempt_stop::StopTaskAttempt::decl(),
+        codecommand::services::attempt_stop::RestartAfterStop::decl(),
+        codecommand::services::attempt_stop::StopAttemptResult::decl(),
+        codecommand::services::attempt_stop::CancelAttemptResult::decl(),
+        codecommand::services::attempt_wait::WaitCondition::decl(),
+        codecommand::services::attempt_wait::AttemptWaitResult::decl(),
+        codecommand::routes::task_attempts::StopLinkDetails::decl(),
+        codecommand::routes::task_attempts::CreatedStopLink::decl(),
+        codecommand::services::orphan_branches::UnknownAttemptBranch::decl(),
+        codecommand::services::orphan_branches::AdoptedBranch::decl(),
+        codecommand::routes::projects::UnknownAttemptBranchAction::decl(),
+        codecommand::services::attempt_export::ExportFormat::decl(),
+        codecommand::services::attempt_export::AttemptExportManifest::decl(),
+        codecommand::services::attempt_export::PatchApplyResult::decl(),
+        codecommand::routes::task_attempts::ImportPatchSeries::decl(),
+        codecommand::routes::task_attempts::PatchImportResult::decl(),
+        codecommand::services::attempt_export::DiffApplyResult::decl(),
+        codecommand::routes::task_attempts::ApplyAttemptToBranch::decl(),
+        codecommand::routes::task_attempts::BranchApplyResult::decl(),
         codecommand::models::execution_process::ExecutionProcess::decl(),
         codecommand::models::execution_process::ExecutionProcessSummary::decl(),
         codecommand::models::execution_process::ExecutionProcessStatus::decl(),
         codecommand::models::execution_process::ExecutionProcessType::decl(),
         codecommand::models::execution_process::CreateExecutionProcess::decl(),
         codecommand::models::execution_process::UpdateExecutionProcess::decl(),
+        codecommand::services::prompt_budget::PromptSectionKind::decl(),
+        codecommand::services::prompt_budget::TrimAction::decl(),
+        codecommand::services::prompt_budget::TrimDecision::decl(),
+        codecommand::services::prompt_budget::PromptBudgetReport::decl(),
+        codecommand::routes::task_attempts::FollowUpPreview::decl(),
         codecommand::models::executor_session::ExecutorSession::decl(),
+        codecommand::models::executor_session::ExecutorSessionWithDuration::decl(),
         codecommand::models::executor_session::CreateExecutorSession::decl(),
         codecommand::models::executor_session::UpdateExecutorSession::decl(),
         codecommand::executor::NormalizedConversation::decl(),
         codecommand::executor::NormalizedEntry::decl(),
         codecommand::executor::NormalizedEntryType::decl(),
         codecommand::executor::ActionType::decl(),
+        codecommand::services::live_normalization::LiveLogMessage::decl(),
+        codecommand::services::project_events::ProjectChange::decl(),
+        codecommand::services::asset_usage::AssetCategory::decl(),
+        codecommand::services::asset_usage::CategoryUsage::decl(),
+        codecommand::services::asset_usage::AssetUsage::decl(),
+        codecommand::services::asset_usage::OrphanedAsset::decl(),
+        codecommand::services::asset_usage::MissingAsset::decl(),
+        codecommand::services::asset_usage::AssetScanReport::decl(),
+        codecommand::services::asset_usage::AssetCleanupRequest::decl(),
+        codecommand::services::asset_usage::AssetCleanupResult::decl(),
+        codecommand::routes::health::HealthStatus::decl(),
+        codecommand::services::search::SearchResultType::decl(),
+        codecommand::services::search::SearchResult::decl(),
+        codecommand::services::search::SearchResponse::decl(),
     ];
+    #[cfg(feature = "self-update")]
+    let decls = decls
+        .into_iter()
+        .chain([
+            codecommand::services::self_update::AvailableUpdate::decl(),
+            codecommand::services::self_update::UpdateStatus::decl(),
+        ])
+        .collect::<Vec<_>>();
 
     // 4. Friendly banner
     const HEADER: &str =
@@ -1,9 +1,35 @@
 use std::str::FromStr;
 
+use codecommand::{
+    mcp::task_server::TaskServer,
+    models::{config::Config, project::Project},
+    sentry_layer,
+    utils::{asset_dir, config_path},
+};
 use rmcp::{transport::stdio, ServiceExt};
 use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
 use tracing_subscriber::{prelude::*, EnvFilter};
-use codecommand::{mcp::task_server::TaskServer, sentry_layer, utils::asset_dir};
+use uuid::Uuid;
+
+/// Project to scope the server to, from `--project-id <id>` or `CODECOMMAND_PROJECT_ID`
+fn project_scope() -> anyhow::Result<Option<Uuid>> {
+    let mut args = std::env::args().skip(1);
+    let mut value = None;
+    while let Some(arg) = args.next() {
+        if arg == "--project-id" {
+            value = args.next();
+        } else if let Some(id) = arg.strip_prefix("--project-id=") {
+            value = Some(id.to_string());
+        }
+    }
+
+    match value.or_else(|| std::env::var("CODECOMMAND_PROJECT_ID").ok()) {
+        Some(id) => Ok(Some(Uuid::parse_str(id.trim()).map_err(|_| {
+            anyhow::anyhow!("Invalid project id '{}', expected a UUID", id)
+        })?)),
+        None => Ok(None),
+    }
+}
 
 fn main() -> anyhow::Result<()> {
     let environment = if cfg!(debug_assertions) {
@@ -11,13 +37,13 @@ fn main() -> anyhow::Result<()> {
     } else {
         "production"
     };
-    
+
     // Force disable Sentry - use dummy configuration
     let _guard = sentry::init(sentry::ClientOptions {
         dsn: None, // Disable Sentry by setting DSN to None
         ..Default::default()
     });
-    
+
     /*
     let _guard = sentry::init(("https://1065a1d276a581316999a07d5dffee26@o4509603705192449.ingest.de.sentry.io/4509605576441937", sentry::ClientOptions {
         release: sentry::release_name!(),
@@ -25,7 +51,7 @@ fn main() -> anyhow::Result<()> {
         ..Default::default()
     }));
     */
-    
+
     sentry::configure_scope(|scope| {
         scope.set_tag("source", "mcp");
     });
@@ -54,13 +80,29 @@ fn main() -> anyhow::Result<()> {
             let options = SqliteConnectOptions::from_str(&database_url)?.create_if_missing(false);
             let pool = SqlitePool::connect_with(options).await?;
 
-            let service = TaskServer::new(pool)
-                .serve(stdio())
-                .await
-                .inspect_err(|e| {
-                    tracing::error!("serving error: {:?}", e);
-                    sentry::capture_error(e);
-                })?;
+            let server = match project_scope()? {
+                Some(project_id) => {
+                    if !Project::exists(&pool, project_id).await? {
+                        anyhow::bail!("Project {} not found", project_id);
+                    }
+                    tracing::debug!("[MCP] Scoped to project {}", project_id);
+                    TaskServer::scoped(pool, project_id)
+                }
+                None => TaskServer::new(pool),
+            };
+            let require_confirmation = match Config::read(&config_path()) {
+                Ok(config) => config.mcp_require_confirmation,
+                Err(e) => {
+                    tracing::warn!("[MCP] Failed to read config, using defaults: {}", e);
+                    false
+                }
+            };
+            let server = server.requiring_confirmation(require_confirmation);
+
+            let service = server.serve(stdio()).await.inspect_err(|e| {
+                tracing::error!("serving error: {:?}", e);
+                sentry::capture_error(e);
+            })?;
 
             service.waiting().await?;
             Ok(())
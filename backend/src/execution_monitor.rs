@@ -4,12 +4,18 @@ use uuid::Uuid;
 use crate::{
     app_state::AppState,
     models::{
+        bisect_run::BisectRun,
+        config::ProvenanceMode,
         execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
+        project::{Project, ProjectType},
         task::{Task, TaskStatus},
         task_attempt::{TaskAttempt, TaskAttemptStatus},
         task_attempt_activity::{CreateTaskAttemptActivity, TaskAttemptActivity},
     },
-    services::{NotificationConfig, NotificationService, ProcessService},
+    services::{
+        bisect, execution_diff, formatting, provenance::Provenance, NotificationConfig,
+        NotificationMessage, NotificationService, ProcessService,
+    },
     utils::worktree_manager::WorktreeManager,
 };
 
@@ -94,6 +100,11 @@ async fn handle_setup_delegation(app_state: &AppState, delegation_context: Deleg
                 .and_then(|a| a.get("prompt"))
                 .and_then(|p| p.as_str())
                 .unwrap_or("");
+            let prompt_budget = params
+                .additional
+                .as_ref()
+                .and_then(|a| a.get("prompt_budget"))
+                .and_then(|b| serde_json::from_value(b.clone()).ok());
 
             ProcessService::start_followup_execution_direct(
                 &app_state.db_pool,
@@ -102,6 +113,7 @@ async fn handle_setup_delegation(app_state: &AppState, delegation_context: Deleg
                 task_id,
                 project_id,
                 prompt,
+                prompt_budget,
             )
             .await
             .map(|_| ())
@@ -132,12 +144,10 @@ async fn handle_setup_delegation(app_state: &AppState, delegation_context: Deleg
 /// Commit any unstaged changes in the worktree after execution completion
 async fn commit_execution_changes(
     worktree_path: &str,
-    attempt_id: Uuid,
-    summary: Option<&str>,
+    commit_message: String,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Run git operations in a blocking task since git2 is synchronous
     let worktree_path = worktree_path.to_string();
-    let summary = summary.map(|s| s.to_string());
     tokio::task::spawn_blocking(move || {
         let worktree_repo = Repository::open(&worktree_path)?;
 
@@ -173,11 +183,6 @@ async fn commit_execution_changes(
         let tree = worktree_repo.find_tree(tree_id)?;
 
         // Create commit for the changes
-        let commit_message = if let Some(ref summary_msg) = summary {
-            summary_msg.clone()
-        } else {
-            format!("Task attempt {} - Final changes", attempt_id)
-        };
         worktree_repo.commit(
             Some("HEAD"),
             &signature,
@@ -194,6 +199,52 @@ async fn commit_execution_changes(
     Ok(())
 }
 
+/// Snapshot the worktree as an execution left it, so its own changes can be diffed later
+pub async fn record_execution_snapshot(
+    app_state: &AppState,
+    task_attempt: &TaskAttempt,
+    execution_process_id: Uuid,
+) {
+    if let Err(e) =
+        execution_diff::record_snapshot(&app_state.db_pool, task_attempt, execution_process_id)
+            .await
+    {
+        tracing::warn!(
+            "Failed to snapshot the worktree of attempt {} after execution {}: {}",
+            task_attempt.id,
+            execution_process_id,
+            e
+        );
+    }
+}
+
+/// Message for the commit of an execution's changes: the agent's summary, stamped with the
+/// attempt's provenance
+async fn execution_commit_message(
+    app_state: &AppState,
+    task_attempt: &TaskAttempt,
+    summary: Option<&str>,
+) -> String {
+    let message = summary
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| format!("Task attempt {} - Final changes", task_attempt.id));
+    let mode = app_state.get_config().read().await.provenance;
+    if mode == ProvenanceMode::Off {
+        return message;
+    }
+    match Provenance::load(&app_state.db_pool, task_attempt).await {
+        Ok(provenance) => provenance.stamp_commit_message(&message, mode),
+        Err(e) => {
+            tracing::warn!(
+                "Committing changes of attempt {} without provenance: {}",
+                task_attempt.id,
+                e
+            );
+            message
+        }
+    }
+}
+
 /// Check if worktree has uncommitted changes and warn if so
 fn check_uncommitted_changes(worktree_path: &str) {
     if let Ok(repo) = Repository::open(worktree_path) {
@@ -306,58 +357,38 @@ pub async fn cleanup_task_worktrees(
     Ok(())
 }
 
-/// Defensively check for externally deleted worktrees and mark them as deleted in the database
+/// Defensively check for externally deleted worktrees. They are left flagged as active so
+/// that the next use reports `worktree_missing` and the user can recreate the worktree.
 async fn check_externally_deleted_worktrees(pool: &sqlx::SqlitePool) {
-    let active_attempts = match sqlx::query!(
-        r#"SELECT id as "id!: Uuid", worktree_path FROM task_attempts WHERE worktree_deleted = FALSE"#
-    )
-    .fetch_all(pool)
-    .await
-    {
-        Ok(attempts) => attempts,
+    let statuses = match TaskAttempt::find_worktree_statuses(pool).await {
+        Ok(statuses) => statuses,
         Err(e) => {
-            tracing::error!("Failed to query active task attempts for external deletion check: {}", e);
+            tracing::error!(
+                "Failed to query task attempt worktrees for external deletion check: {}",
+                e
+            );
             return;
         }
     };
 
     tracing::debug!(
-        "Checking {} active worktrees for external deletion...",
-        active_attempts.len()
+        "Checking {} worktrees for external deletion...",
+        statuses.len()
     );
 
-    let mut externally_deleted_count = 0;
-    for record in active_attempts {
-        let attempt_id = record.id;
-        let worktree_path = &record.worktree_path;
-
-        // Check if worktree directory exists
-        if !std::path::Path::new(worktree_path).exists() {
-            // Worktree was deleted externally, mark as deleted in database
-            if let Err(e) =
-                crate::models::task_attempt::TaskAttempt::mark_worktree_deleted(pool, attempt_id)
-                    .await
-            {
-                tracing::error!(
-                    "Failed to mark externally deleted worktree as deleted for attempt {}: {}",
-                    attempt_id,
-                    e
-                );
-            } else {
-                externally_deleted_count += 1;
-                tracing::debug!(
-                    "Marked externally deleted worktree as deleted for attempt {} (path: {})",
-                    attempt_id,
-                    worktree_path
-                );
-            }
-        }
+    let missing: Vec<_> = statuses.iter().filter(|status| status.missing).collect();
+    for status in &missing {
+        tracing::debug!(
+            "Worktree for attempt {} was deleted externally (path: {})",
+            status.attempt_id,
+            status.worktree_path
+        );
     }
 
-    if externally_deleted_count > 0 {
+    if !missing.is_empty() {
         tracing::info!(
-            "Found and marked {} externally deleted worktrees",
-            externally_deleted_count
+            "Found {} externally deleted worktrees awaiting recreation",
+            missing.len()
         );
     } else {
         tracing::debug!("No externally deleted worktrees found");
@@ -436,6 +467,20 @@ async fn cleanup_orphaned_worktrees(pool: &sqlx::SqlitePool) {
             }
         };
 
+        // A running bisect's worktree isn't an attempt's; once the run ends it's an orphan
+        let exists_in_db = exists_in_db
+            || match BisectRun::uses_worktree(pool, &worktree_path_str).await {
+                Ok(used) => used,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to check bisect runs for worktree path {}: {}",
+                        worktree_path_str,
+                        e
+                    );
+                    continue;
+                }
+            };
+
         if !exists_in_db {
             // This is an orphaned worktree - delete it
             tracing::info!("Found orphaned worktree: {}", worktree_path_str);
@@ -512,256 +557,286 @@ async fn cleanup_orphaned_worktree_directory(
     Ok(())
 }
 
-pub async fn execution_monitor(app_state: AppState) {
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
-    let mut cleanup_interval = tokio::time::interval(tokio::time::Duration::from_secs(1800)); // 30 minutes
-
-    loop {
-        tokio::select! {
-            _ = interval.tick() => {
-                // Check for completed processes FIRST to avoid race conditions
-                let completed_executions = app_state.get_running_executions_for_monitor().await;
-
-                // Handle completed executions
-                for (execution_process_id, task_attempt_id, success, exit_code) in completed_executions {
-                    let status_text = if success {
-                        "completed successfully"
-                    } else {
-                        "failed"
-                    };
-                    let exit_text = if let Some(code) = exit_code {
-                        format!(" with exit code {}", code)
-                    } else {
-                        String::new()
-                    };
+/// Handle executions that finished since the last sweep, then fail the ones lost to a server
+/// restart or crash. Run by the job scheduler every few seconds.
+pub async fn reconcile_executions(app_state: &AppState) -> Result<(), String> {
+    // Check for completed processes FIRST to avoid race conditions
+    let completed_executions = app_state.get_running_executions_for_monitor().await;
 
-                    tracing::info!(
-                        "Execution {} {}{}",
-                        execution_process_id,
-                        status_text,
-                        exit_text
-                    );
+    // Handle completed executions
+    for (execution_process_id, task_attempt_id, success, exit_code) in completed_executions {
+        let status_text = if success {
+            "completed successfully"
+        } else {
+            "failed"
+        };
+        let exit_text = if let Some(code) = exit_code {
+            format!(" with exit code {}", code)
+        } else {
+            String::new()
+        };
 
-                    // Update the execution process record
-                    let execution_status = if success {
-                        ExecutionProcessStatus::Completed
-                    } else {
-                        ExecutionProcessStatus::Failed
-                    };
+        tracing::info!(
+            "Execution {} {}{}",
+            execution_process_id,
+            status_text,
+            exit_text
+        );
 
-                    if let Err(e) = ExecutionProcess::update_completion(
-                        &app_state.db_pool,
+        // Update the execution process record
+        let execution_status = if success {
+            ExecutionProcessStatus::Completed
+        } else {
+            ExecutionProcessStatus::Failed
+        };
+
+        if let Err(e) = ExecutionProcess::update_completion(
+            &app_state.db_pool,
+            execution_process_id,
+            execution_status,
+            exit_code,
+        )
+        .await
+        {
+            tracing::error!(
+                "Failed to update execution process {} completion: {}",
+                execution_process_id,
+                e
+            );
+        }
+
+        // Get the execution process to determine next steps
+        if let Ok(Some(execution_process)) =
+            ExecutionProcess::find_by_id(&app_state.db_pool, execution_process_id).await
+        {
+            match execution_process.process_type {
+                ExecutionProcessType::SetupScript => {
+                    handle_setup_completion(
+                        app_state,
+                        task_attempt_id,
                         execution_process_id,
-                        execution_status,
+                        execution_process,
+                        success,
                         exit_code,
                     )
-                    .await
-                    {
-                        tracing::error!(
-                            "Failed to update execution process {} completion: {}",
-                            execution_process_id,
-                            e
-                        );
-                    }
-
-                    // Get the execution process to determine next steps
-                    if let Ok(Some(execution_process)) =
-                        ExecutionProcess::find_by_id(&app_state.db_pool, execution_process_id).await
-                    {
-                        match execution_process.process_type {
-                            ExecutionProcessType::SetupScript => {
-                                handle_setup_completion(
-                                    &app_state,
-                                    task_attempt_id,
-                                    execution_process_id,
-                                    execution_process,
-                                    success,
-                                    exit_code,
-                                )
-                                .await;
-                            }
-                            ExecutionProcessType::CodingAgent => {
-                                handle_coding_agent_completion(
-                                    &app_state,
-                                    task_attempt_id,
-                                    execution_process_id,
-                                    execution_process,
-                                    success,
-                                    exit_code,
-                                )
-                                .await;
-                            }
-                            ExecutionProcessType::DevServer => {
-                                handle_dev_server_completion(
-                                    &app_state,
-                                    task_attempt_id,
-                                    execution_process_id,
-                                    execution_process,
-                                    success,
-                                    exit_code,
-                                )
-                                .await;
-                            }
-                        }
-                    } else {
-                        tracing::error!(
-                            "Failed to find execution process {} for completion handling",
-                            execution_process_id
-                        );
-                    }
+                    .await;
+                }
+                ExecutionProcessType::CodingAgent => {
+                    handle_coding_agent_completion(
+                        app_state,
+                        task_attempt_id,
+                        execution_process_id,
+                        execution_process,
+                        success,
+                        exit_code,
+                    )
+                    .await;
                 }
+                ExecutionProcessType::DevServer => {
+                    handle_dev_server_completion(
+                        app_state,
+                        task_attempt_id,
+                        execution_process_id,
+                        execution_process,
+                        success,
+                        exit_code,
+                    )
+                    .await;
+                }
+                ExecutionProcessType::Bisect => {
+                    bisect::finish_run(&app_state.db_pool, execution_process_id).await;
+                }
+                ExecutionProcessType::FormatScript => {
+                    formatting::finish_run(app_state, execution_process_id).await;
+                }
+            }
+        } else {
+            tracing::error!(
+                "Failed to find execution process {} for completion handling",
+                execution_process_id
+            );
+        }
+    }
 
-                // Check for orphaned execution processes AFTER handling completions
-                // Add a small delay to ensure completed processes are properly handled first
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    // Check for orphaned execution processes AFTER handling completions
+    // Add a small delay to ensure completed processes are properly handled first
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-                let running_processes = match ExecutionProcess::find_running(&app_state.db_pool).await {
-                    Ok(processes) => processes,
-                    Err(e) => {
-                        tracing::error!("Failed to query running execution processes: {}", e);
-                        continue;
-                    }
+    let running_processes = ExecutionProcess::find_running(&app_state.db_pool)
+        .await
+        .map_err(|e| format!("Failed to query running execution processes: {}", e))?;
+
+    for process in running_processes {
+        // Check if this process is not actually running in the app state
+        if !app_state
+            .has_running_execution(process.task_attempt_id)
+            .await
+        {
+            // Additional check: if the process was recently updated, skip it to prevent race conditions
+            let now = chrono::Utc::now();
+            let time_since_update = now - process.updated_at;
+            if time_since_update.num_seconds() < 10 {
+                // Process was updated within last 10 seconds, likely just completed
+                tracing::debug!(
+                    "Skipping recently updated orphaned process {} (updated {} seconds ago)",
+                    process.id,
+                    time_since_update.num_seconds()
+                );
+                continue;
+            }
+
+            // This is truly an orphaned execution process - mark it as failed
+            tracing::info!(
+                "Found orphaned execution process {} for task attempt {}",
+                process.id,
+                process.task_attempt_id
+            );
+            // This is truly an orphaned execution process - mark it as failed
+            tracing::info!(
+                "Found orphaned execution process {} for task attempt {}",
+                process.id,
+                process.task_attempt_id
+            );
+
+            // Update the execution process status first
+            if let Err(e) = ExecutionProcess::update_completion(
+                &app_state.db_pool,
+                process.id,
+                ExecutionProcessStatus::Failed,
+                None, // No exit code for orphaned processes
+            )
+            .await
+            {
+                tracing::error!(
+                    "Failed to update orphaned execution process {} status: {}",
+                    process.id,
+                    e
+                );
+                continue;
+            }
+
+            // Create task attempt activity for the attempt's own stages
+            if process.process_type.is_attempt_stage() {
+                let activity_id = Uuid::new_v4();
+                let create_activity = CreateTaskAttemptActivity {
+                    execution_process_id: process.id,
+                    status: Some(TaskAttemptStatus::ExecutorFailed),
+                    note: Some("Execution lost (server restart or crash)".to_string()),
                 };
 
-                for process in running_processes {
-                    // Check if this process is not actually running in the app state
-                    if !app_state.has_running_execution(process.task_attempt_id).await {
-                        // Additional check: if the process was recently updated, skip it to prevent race conditions
-                        let now = chrono::Utc::now();
-                        let time_since_update = now - process.updated_at;
-                        if time_since_update.num_seconds() < 10 {
-                            // Process was updated within last 10 seconds, likely just completed
-                            tracing::debug!(
-                                "Skipping recently updated orphaned process {} (updated {} seconds ago)",
-                                process.id,
-                                time_since_update.num_seconds()
-                            );
-                            continue;
-                        }
+                if let Err(e) = TaskAttemptActivity::create(
+                    &app_state.db_pool,
+                    &create_activity,
+                    activity_id,
+                    TaskAttemptStatus::ExecutorFailed,
+                )
+                .await
+                {
+                    tracing::error!(
+                        "Failed to create failed activity for orphaned process: {}",
+                        e
+                    );
+                    continue;
+                }
+            }
 
-                        // This is truly an orphaned execution process - mark it as failed
-                        tracing::info!(
-                            "Found orphaned execution process {} for task attempt {}",
-                            process.id,
-                            process.task_attempt_id
-                        );
-                        // This is truly an orphaned execution process - mark it as failed
-                        tracing::info!(
-                            "Found orphaned execution process {} for task attempt {}",
-                            process.id,
-                            process.task_attempt_id
-                        );
+            tracing::info!("Marked orphaned execution process {} as failed", process.id);
 
-                        // Update the execution process status first
-                        if let Err(e) = ExecutionProcess::update_completion(
-                            &app_state.db_pool,
-                            process.id,
-                            ExecutionProcessStatus::Failed,
-                            None, // No exit code for orphaned processes
-                        )
-                        .await
+            // Update task status to InReview for coding agent and setup script failures
+            if matches!(
+                process.process_type,
+                ExecutionProcessType::CodingAgent | ExecutionProcessType::SetupScript
+            ) {
+                if let Ok(Some(task_attempt)) =
+                    TaskAttempt::find_by_id(&app_state.db_pool, process.task_attempt_id).await
+                {
+                    if task_attempt.benchmark_run_id.is_some() {
+                        continue;
+                    }
+                    if let Ok(Some(task)) =
+                        Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await
+                    {
+                        app_state.project_events.attempt_finished(
+                            task.project_id,
+                            task.id,
+                            task_attempt.id,
+                            false,
+                        );
+                        if let Err(e) = app_state
+                            .project_events
+                            .update_task_status(
+                                &app_state.db_pool,
+                                task.id,
+                                task.project_id,
+                                TaskStatus::InReview,
+                            )
+                            .await
                         {
                             tracing::error!(
-                                "Failed to update orphaned execution process {} status: {}",
-                                process.id,
+                                "Failed to update task status to InReview for orphaned attempt: {}",
                                 e
                             );
-                            continue;
                         }
+                    }
+                }
+            }
+        }
+    }
 
-                        // Create task attempt activity for non-dev server processes
-                        if process.process_type != ExecutionProcessType::DevServer {
-                            let activity_id = Uuid::new_v4();
-                            let create_activity = CreateTaskAttemptActivity {
-                                execution_process_id: process.id,
-                                status: Some(TaskAttemptStatus::ExecutorFailed),
-                                note: Some("Execution lost (server restart or crash)".to_string()),
-                            };
+    // Bisect runs that were stopped or lost still need their outcome and worktree settled
+    bisect::finish_stale_runs(&app_state.db_pool).await;
+    // Format runs that were stopped or lost still hold back their attempt's review
+    formatting::finish_stale_runs(app_state).await;
 
-                            if let Err(e) = TaskAttemptActivity::create(
-                                &app_state.db_pool,
-                                &create_activity,
-                                activity_id,
-                                TaskAttemptStatus::ExecutorFailed,
-                            )
-                            .await
-                            {
-                                tracing::error!(
-                                    "Failed to create failed activity for orphaned process: {}",
-                                    e
-                                );
-                                continue;
-                            }
-                        }
+    start_queued_attempts(app_state).await;
 
-                        tracing::info!("Marked orphaned execution process {} as failed", process.id);
+    Ok(())
+}
 
-                        // Update task status to InReview for coding agent and setup script failures
-                        if matches!(
-                            process.process_type,
-                            ExecutionProcessType::CodingAgent | ExecutionProcessType::SetupScript
-                        ) {
-                            if let Ok(Some(task_attempt)) =
-                                TaskAttempt::find_by_id(&app_state.db_pool, process.task_attempt_id).await
-                            {
-                                if let Ok(Some(task)) =
-                                    Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await
-                                {
-                                    if let Err(e) = Task::update_status(
-                                        &app_state.db_pool,
-                                        task.id,
-                                        task.project_id,
-                                        TaskStatus::InReview,
-                                    )
-                                    .await
-                                    {
-                                        tracing::error!("Failed to update task status to InReview for orphaned attempt: {}", e);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ = cleanup_interval.tick() => {
-                tracing::info!("Starting periodic worktree cleanup...");
-
-                // First, defensively check for externally deleted worktrees
-                check_externally_deleted_worktrees(&app_state.db_pool).await;
-
-                // Then, find and delete orphaned worktrees that don't belong to any task
-                cleanup_orphaned_worktrees(&app_state.db_pool).await;
-
-                // Then, proceed with normal expired worktree cleanup
-                match TaskAttempt::find_expired_for_cleanup(&app_state.db_pool).await {
-                    Ok(expired_attempts) => {
-                        if expired_attempts.is_empty() {
-                            tracing::debug!("No expired worktrees found");
-                        } else {
-                            tracing::info!("Found {} expired worktrees to clean up", expired_attempts.len());
-                            for (attempt_id, worktree_path, git_repo_path) in expired_attempts {
-                                if let Err(e) = delete_worktree(&worktree_path, &git_repo_path, attempt_id).await {
-                                    tracing::error!("Failed to cleanup expired worktree {}: {}", attempt_id, e);
-                                } else {
-                                    // Mark worktree as deleted in database after successful cleanup
-                                    if let Err(e) = crate::models::task_attempt::TaskAttempt::mark_worktree_deleted(&app_state.db_pool, attempt_id).await {
-                                        tracing::error!("Failed to mark worktree as deleted in database for attempt {}: {}", attempt_id, e);
-                                    } else {
-                                        tracing::info!("Successfully marked worktree as deleted for attempt {}", attempt_id);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to query expired task attempts: {}", e);
-                    }
-                }
-            }
+/// Start the attempts that were waiting for a slot in their project and got one, now that
+/// finished attempts have given theirs back
+async fn start_queued_attempts(app_state: &AppState) {
+    for attempt in app_state.admit_queued_attempts().await {
+        tracing::info!(
+            "Starting queued task attempt {} in project {}",
+            attempt.attempt_id,
+            attempt.project_id
+        );
+        let result = ProcessService::start_admitted_execution(
+            &app_state.db_pool,
+            app_state,
+            attempt.attempt_id,
+            attempt.task_id,
+            attempt.project_id,
+        )
+        .await;
+        app_state
+            .project_slot_started(attempt.attempt_id, result.is_ok())
+            .await;
+        if let Err(e) = result {
+            tracing::error!(
+                "Failed to start queued task attempt {}: {}",
+                attempt.attempt_id,
+                e
+            );
         }
     }
 }
 
+/// Drop records of worktrees deleted outside the app and remove orphaned worktree directories
+pub async fn maintain_worktrees(app_state: &AppState) -> Result<(), String> {
+    tracing::info!("Starting periodic worktree cleanup...");
+
+    // First, defensively check for externally deleted worktrees
+    check_externally_deleted_worktrees(&app_state.db_pool).await;
+
+    // Then, find and delete orphaned worktrees that don't belong to any task
+    cleanup_orphaned_worktrees(&app_state.db_pool).await;
+
+    // Expired worktrees are left to TaskAttemptCleanupService
+    Ok(())
+}
+
 /// Handle setup script completion
 async fn handle_setup_completion(
     app_state: &AppState,
@@ -865,18 +940,27 @@ async fn handle_setup_completion(
         }
 
         // Update task status to InReview since setup failed
-        if let Ok(Some(task_attempt)) =
-            TaskAttempt::find_by_id(&app_state.db_pool, task_attempt_id).await
+        if let Ok(Some(task_attempt)) = TaskAttempt::find_by_id(&app_state.db_pool, task_attempt_id)
+            .await
+            .map(|attempt| attempt.filter(|a| a.benchmark_run_id.is_none()))
         {
             if let Ok(Some(task)) = Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await
             {
-                if let Err(e) = Task::update_status(
-                    &app_state.db_pool,
-                    task.id,
+                app_state.project_events.attempt_finished(
                     task.project_id,
-                    TaskStatus::InReview,
-                )
-                .await
+                    task.id,
+                    task_attempt_id,
+                    false,
+                );
+                if let Err(e) = app_state
+                    .project_events
+                    .update_task_status(
+                        &app_state.db_pool,
+                        task.id,
+                        task.project_id,
+                        TaskStatus::InReview,
+                    )
+                    .await
                 {
                     tracing::error!(
                         "Failed to update task status to InReview after setup failure: {}",
@@ -888,6 +972,17 @@ async fn handle_setup_completion(
     }
 }
 
+/// Notification title and message used when the task details can't be loaded
+fn generic_completion_notification(success: bool, language: Option<&str>) -> (String, String) {
+    let title = NotificationMessage::TaskCompleteTitleGeneric.render(language, &[]);
+    let message = if success {
+        NotificationMessage::TaskSucceededGeneric.render(language, &[])
+    } else {
+        NotificationMessage::TaskFailedGeneric.render(language, &[])
+    };
+    (title, message)
+}
+
 /// Handle coding agent completion
 async fn handle_coding_agent_completion(
     app_state: &AppState,
@@ -897,12 +992,6 @@ async fn handle_coding_agent_completion(
     success: bool,
     exit_code: Option<i64>,
 ) {
-    let exit_text = if let Some(code) = exit_code {
-        format!(" with exit code {}", code)
-    } else {
-        String::new()
-    };
-
     // Extract and store assistant message from execution logs
     let summary = if let Some(stdout) = &execution_process.stdout {
         if let Some(assistant_message) = crate::executor::parse_assistant_message_from_logs(stdout)
@@ -934,11 +1023,18 @@ async fn handle_coding_agent_completion(
         None
     };
 
+    // Benchmark runs can finish many attempts unattended; they report through their own
+    // results instead of notifications, analytics and task status
+    let is_benchmark = matches!(
+        TaskAttempt::find_by_id(&app_state.db_pool, task_attempt_id).await,
+        Ok(Some(ref attempt)) if attempt.benchmark_run_id.is_some()
+    );
+
     // Send notifications if enabled
     let sound_enabled = app_state.get_sound_alerts_enabled().await;
     let push_enabled = app_state.get_push_notifications_enabled().await;
 
-    if sound_enabled || push_enabled {
+    if (sound_enabled || push_enabled) && !is_benchmark {
         let sound_file = app_state.get_sound_file().await;
         let notification_config = NotificationConfig {
             sound_enabled,
@@ -953,42 +1049,34 @@ async fn handle_coding_agent_completion(
         {
             if let Ok(Some(task)) = Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await
             {
-                let title = format!("Task Complete: {}", task.title);
+                let language =
+                    crate::executor::resolve_response_language(&app_state.db_pool, task.project_id)
+                        .await;
+                let args = [
+                    ("title", task.title.as_str()),
+                    ("branch", task_attempt.branch.as_str()),
+                    (
+                        "executor",
+                        task_attempt.executor.as_deref().unwrap_or("default"),
+                    ),
+                ];
+                let title =
+                    NotificationMessage::TaskCompleteTitle.render(language.as_deref(), &args);
                 let message = if success {
-                    format!(
-                        "✅ '{}' completed successfully\nBranch: {}\nExecutor: {}",
-                        task.title,
-                        task_attempt.branch,
-                        task_attempt.executor.as_deref().unwrap_or("default")
-                    )
+                    NotificationMessage::TaskSucceeded.render(language.as_deref(), &args)
                 } else {
-                    format!(
-                        "❌ '{}' execution failed\nBranch: {}\nExecutor: {}",
-                        task.title,
-                        task_attempt.branch,
-                        task_attempt.executor.as_deref().unwrap_or("default")
-                    )
+                    NotificationMessage::TaskFailed.render(language.as_deref(), &args)
                 };
                 (title, message)
             } else {
                 // Fallback if task not found
-                let title = "Task Complete";
-                let message = if success {
-                    "Task execution completed successfully"
-                } else {
-                    "Task execution failed"
-                };
-                (title.to_string(), message.to_string())
+                let config = app_state.get_config().read().await;
+                generic_completion_notification(success, config.response_language.as_deref())
             }
         } else {
             // Fallback if task attempt not found
-            let title = "Task Complete";
-            let message = if success {
-                "Task execution completed successfully"
-            } else {
-                "Task execution failed"
-            };
-            (title.to_string(), message.to_string())
+            let config = app_state.get_config().read().await;
+            generic_completion_notification(success, config.response_language.as_deref())
         };
 
         notification_service
@@ -1000,11 +1088,20 @@ async fn handle_coding_agent_completion(
     if let Ok(Some(task_attempt)) =
         TaskAttempt::find_by_id(&app_state.db_pool, task_attempt_id).await
     {
-        // Commit any unstaged changes after execution completion
-        if let Err(e) = commit_execution_changes(
+        // Commit any unstaged changes after execution completion; scratch projects have no
+        // repository to commit to
+        let is_scratch = matches!(
+            Project::find_type_by_attempt_id(&app_state.db_pool, task_attempt_id).await,
+            Ok(Some(ProjectType::Scratch))
+        );
+        if is_scratch {
+            tracing::debug!(
+                "Skipping commit for attempt {} of a scratch project",
+                task_attempt_id
+            );
+        } else if let Err(e) = commit_execution_changes(
             &task_attempt.worktree_path,
-            task_attempt_id,
-            summary.as_deref(),
+            execution_commit_message(app_state, &task_attempt, summary.as_deref()).await,
         )
         .await
         {
@@ -1019,63 +1116,76 @@ async fn handle_coding_agent_completion(
                 task_attempt_id
             );
         }
+        if !is_scratch {
+            record_execution_snapshot(app_state, &task_attempt, execution_process_id).await;
+        }
 
-        // Create task attempt activity with appropriate completion status
-        let activity_id = Uuid::new_v4();
-        let status = if success {
-            TaskAttemptStatus::ExecutorComplete
-        } else {
-            TaskAttemptStatus::ExecutorFailed
-        };
-        let create_activity = CreateTaskAttemptActivity {
-            execution_process_id,
-            status: Some(status.clone()),
-            note: Some(format!("Coding agent execution completed{}", exit_text)),
-        };
-
-        if let Err(e) =
-            TaskAttemptActivity::create(&app_state.db_pool, &create_activity, activity_id, status)
-                .await
+        // Attempts without a baseline weren't journaled and are skipped
+        let ignored = app_state.get_config().read().await.path_ignore();
+        match crate::services::untracked_changes::record_changes(
+            &app_state.db_pool,
+            task_attempt_id,
+            &task_attempt.worktree_path,
+            ignored,
+        )
+        .await
         {
-            tracing::error!("Failed to create executor completion activity: {}", e);
-        } else {
-            tracing::info!(
-                "Task attempt {} set to paused after coding agent completion",
-                task_attempt_id
-            );
+            Ok(Some(count)) if count > 0 => tracing::info!(
+                "Attempt {} changed {} file(s) git doesn't track",
+                task_attempt_id,
+                count
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::warn!(
+                "Failed to journal untracked changes for attempt {}: {}",
+                task_attempt_id,
+                e
+            ),
+        }
 
-            // Get task to access task_id and project_id for status update
-            if let Ok(Some(task)) = Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await
+        // The project's formatters get the agent's changes before they're up for review; the
+        // run finishes the attempt's coding agent stage once it ends
+        let mut formatting_note = None;
+        if !is_scratch {
+            match formatting::start(
+                app_state,
+                &task_attempt,
+                execution_process_id,
+                success,
+                exit_code,
+            )
+            .await
             {
-                app_state
-                    .track_analytics_event(
-                        "task_attempt_finished",
-                        Some(serde_json::json!({
-                            "task_id": task.id.to_string(),
-                            "project_id": task.project_id.to_string(),
-                            "attempt_id": task_attempt_id.to_string(),
-                            "execution_success": success,
-                            "exit_code": exit_code,
-                        })),
-                    )
-                    .await;
-
-                // Update task status to InReview
-                if let Err(e) = Task::update_status(
-                    &app_state.db_pool,
-                    task.id,
-                    task.project_id,
-                    TaskStatus::InReview,
-                )
-                .await
-                {
+                Ok(Some(run)) => {
+                    tracing::info!(
+                        "Formatting {} file(s) changed by attempt {} in run {}",
+                        run.files.len(),
+                        task_attempt_id,
+                        run.id
+                    );
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => {
                     tracing::error!(
-                        "Failed to update task status to InReview for completed attempt: {}",
+                        "Failed to start formatting changes of attempt {}: {}",
+                        task_attempt_id,
                         e
                     );
+                    formatting_note = Some(format!("formatting couldn't start: {}", e));
                 }
             }
         }
+
+        finish_coding_agent(
+            app_state,
+            &task_attempt,
+            execution_process_id,
+            success,
+            exit_code,
+            formatting_note.as_deref(),
+        )
+        .await;
     } else {
         tracing::error!(
             "Failed to find task attempt {} for coding agent completion",
@@ -1084,6 +1194,99 @@ async fn handle_coding_agent_completion(
     }
 }
 
+/// Record the attempt's coding agent stage as finished and put its task up for review. Called
+/// once the agent's changes are committed, and formatted when the project has formatters, with
+/// how that went in `formatting_note`.
+pub async fn finish_coding_agent(
+    app_state: &AppState,
+    task_attempt: &TaskAttempt,
+    execution_process_id: Uuid,
+    success: bool,
+    exit_code: Option<i64>,
+    formatting_note: Option<&str>,
+) {
+    let task_attempt_id = task_attempt.id;
+    let exit_text = if let Some(code) = exit_code {
+        format!(" with exit code {}", code)
+    } else {
+        String::new()
+    };
+    let mut note = format!("Coding agent execution completed{}", exit_text);
+    if let Some(formatting_note) = formatting_note {
+        note.push_str("; ");
+        note.push_str(formatting_note);
+    }
+
+    // Create task attempt activity with appropriate completion status
+    let activity_id = Uuid::new_v4();
+    let status = if success {
+        TaskAttemptStatus::ExecutorComplete
+    } else {
+        TaskAttemptStatus::ExecutorFailed
+    };
+    let create_activity = CreateTaskAttemptActivity {
+        execution_process_id,
+        status: Some(status.clone()),
+        note: Some(note),
+    };
+
+    if let Err(e) =
+        TaskAttemptActivity::create(&app_state.db_pool, &create_activity, activity_id, status).await
+    {
+        tracing::error!("Failed to create executor completion activity: {}", e);
+        return;
+    }
+    tracing::info!(
+        "Task attempt {} set to paused after coding agent completion",
+        task_attempt_id
+    );
+
+    // Benchmark runs can finish many attempts unattended
+    if task_attempt.benchmark_run_id.is_some() {
+        return;
+    }
+
+    // Get task to access task_id and project_id for status update
+    if let Ok(Some(task)) = Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await {
+        app_state
+            .track_analytics_event(
+                "task_attempt_finished",
+                Some(serde_json::json!({
+                    "task_id": task.id.to_string(),
+                    "project_id": task.project_id.to_string(),
+                    "attempt_id": task_attempt_id.to_string(),
+                    "execution_success": success,
+                    "exit_code": exit_code,
+                })),
+            )
+            .await;
+
+        app_state.project_events.attempt_finished(
+            task.project_id,
+            task.id,
+            task_attempt_id,
+            success,
+        );
+
+        // Update task status to InReview
+        if let Err(e) = app_state
+            .project_events
+            .update_task_status(
+                &app_state.db_pool,
+                task.id,
+                task.project_id,
+                TaskStatus::InReview,
+            )
+            .await
+        {
+            tracing::error!(
+                "Failed to update task status to InReview for completed attempt: {}",
+                e
+            );
+        }
+    }
+}
+
 /// Handle dev server completion (future functionality)
 async fn handle_dev_server_completion(
     app_state: &AppState,
@@ -1,14 +1,21 @@
-use std::str::FromStr;
+use std::{collections::HashMap, future::Future, str::FromStr};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::AsyncReadExt;
 use ts_rs::TS;
 use uuid::Uuid;
 
-use crate::executors::{
-    AmpExecutor, ClaudeExecutor, EchoExecutor, GeminiExecutor, OpencodeExecutor,
-    SetupScriptExecutor,
+use crate::{
+    executors::{
+        registry::{self, ExecutorFactory, UnregisteredExecutor},
+        SetupScriptExecutor,
+    },
+    services::live_normalization,
+    utils::{
+        executable::ExecutableLookup,
+        output_capture::{OutputCapture, READ_CHUNK_SIZE},
+    },
 };
 
 // Constants for database streaming
@@ -26,10 +33,45 @@ pub struct NormalizedConversation {
     pub summary: Option<String>,
 }
 
+impl NormalizedConversation {
+    /// Content of the first user message: the prompt the executor was started with
+    pub fn first_user_message(entries: &[NormalizedEntry]) -> Option<String> {
+        entries
+            .iter()
+            .find(|entry| matches!(entry.entry_type, NormalizedEntryType::UserMessage))
+            .map(|entry| entry.content.clone())
+    }
+
+    /// Number the entries in the order they are in, which is the order they were read in
+    pub fn assign_sequence(entries: &mut [NormalizedEntry]) {
+        for (sequence, entry) in entries.iter_mut().enumerate() {
+            entry.sequence = sequence as u32;
+        }
+    }
+
+    /// Trim the metadata of every entry to `max_bytes`, see `NormalizedEntry::cap_metadata`
+    pub fn cap_metadata(entries: &mut [NormalizedEntry], max_bytes: usize) {
+        for entry in entries {
+            entry.cap_metadata(max_bytes);
+        }
+    }
+}
+
+/// The configured cap on the metadata of each normalized entry
+pub fn metadata_cap() -> usize {
+    crate::models::config::Config::read(&crate::utils::config_path())
+        .map(|config| config.max_entry_metadata_bytes)
+        .unwrap_or(crate::models::config::DEFAULT_MAX_ENTRY_METADATA_BYTES) as usize
+}
+
 /// Individual entry in a normalized conversation
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct NormalizedEntry {
+    /// Position of the entry in its conversation, strictly increasing. Clients sort by it:
+    /// not every executor reports timestamps, and stdout and stderr interleave.
+    #[serde(default)]
+    pub sequence: u32,
     pub timestamp: Option<String>,
     pub entry_type: NormalizedEntryType,
     pub content: String,
@@ -37,6 +79,30 @@ pub struct NormalizedEntry {
     pub metadata: Option<serde_json::Value>,
 }
 
+impl NormalizedEntry {
+    /// Replace metadata whose JSON is over `max_bytes` by `{"truncated": true,
+    /// "original_bytes": n, "preview": "..."}`, where the preview is the start of that JSON.
+    /// Large tool inputs otherwise end up stored in full next to their summary in `content`.
+    pub fn cap_metadata(&mut self, max_bytes: usize) {
+        let Some(metadata) = &self.metadata else {
+            return;
+        };
+        let json = metadata.to_string();
+        if json.len() <= max_bytes {
+            return;
+        }
+        let mut end = max_bytes;
+        while !json.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.metadata = Some(serde_json::json!({
+            "truncated": true,
+            "original_bytes": json.len(),
+            "preview": &json[..end],
+        }));
+    }
+}
+
 /// Types of entries in a normalized conversation
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -54,7 +120,7 @@ pub enum NormalizedEntryType {
 }
 
 /// Types of tool actions that can be performed
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
 #[serde(tag = "action", rename_all = "snake_case")]
 #[ts(export)]
 pub enum ActionType {
@@ -146,6 +212,11 @@ pub enum ExecutorError {
     DatabaseError(sqlx::Error),
     ContextCollectionFailed(String),
     GitError(String),
+    IoError(std::io::Error),
+    /// No agent is registered under this name
+    UnknownExecutor(String),
+    /// The executor needs settings that haven't been configured
+    NotConfigured(String),
 }
 
 impl std::fmt::Display for ExecutorError {
@@ -184,11 +255,29 @@ impl std::fmt::Display for ExecutorError {
                 write!(f, "Context collection failed: {}", msg)
             }
             ExecutorError::GitError(msg) => write!(f, "Git operation error: {}", msg),
+            ExecutorError::IoError(e) => write!(f, "IO error: {}", e),
+            ExecutorError::UnknownExecutor(name) => {
+                write!(f, "No executor is registered as '{}'", name)
+            }
+            ExecutorError::NotConfigured(msg) => write!(f, "Executor not configured: {}", msg),
         }
     }
 }
 
-impl std::error::Error for ExecutorError {}
+impl std::error::Error for ExecutorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExecutorError::SpawnFailed { error, .. } => Some(error),
+            ExecutorError::IoError(e) => Some(e),
+            ExecutorError::DatabaseError(e) => Some(e),
+            ExecutorError::TaskNotFound
+            | ExecutorError::ContextCollectionFailed(_)
+            | ExecutorError::GitError(_)
+            | ExecutorError::UnknownExecutor(_)
+            | ExecutorError::NotConfigured(_) => None,
+        }
+    }
+}
 
 impl From<sqlx::Error> for ExecutorError {
     fn from(err: sqlx::Error) -> Self {
@@ -196,6 +285,12 @@ impl From<sqlx::Error> for ExecutorError {
     }
 }
 
+impl From<std::io::Error> for ExecutorError {
+    fn from(err: std::io::Error) -> Self {
+        ExecutorError::IoError(err)
+    }
+}
+
 impl From<crate::models::task_attempt::TaskAttemptError> for ExecutorError {
     fn from(err: crate::models::task_attempt::TaskAttemptError) -> Self {
         match err {
@@ -277,6 +372,13 @@ pub trait Executor: Send + Sync {
         })
     }
 
+    /// Normalizer of the executor's output line by line as it arrives. `None` for executors
+    /// whose entries can depend on later lines; their whole output is normalized again
+    /// instead.
+    fn log_normalizer(&self, _worktree_path: &str) -> Option<Box<dyn LogNormalizer>> {
+        None
+    }
+
     /// Execute the command and stream output to database in real-time
     async fn execute_streaming(
         &self,
@@ -328,11 +430,24 @@ pub trait Executor: Send + Sync {
 pub enum ExecutorType {
     SetupScript(String),
     DevServer(String),
+    /// `git bisect run` of `command` from the good to the bad commit
+    Bisect {
+        good_commit: String,
+        bad_commit: String,
+        command: String,
+    },
+    /// The project's format script over `files`, the ones the coding agent changed
+    FormatScript {
+        script: String,
+        files: Vec<String>,
+    },
     CodingAgent(ExecutorConfig),
     FollowUpCodingAgent {
         config: ExecutorConfig,
         session_id: Option<String>,
         prompt: String,
+        /// How the prompt was fitted into the executor's token budget, recorded on the session
+        prompt_budget: Option<crate::services::prompt_budget::PromptBudgetReport>,
     },
 }
 
@@ -346,10 +461,54 @@ pub enum ExecutorConfig {
     Amp,
     Gemini,
     Opencode,
-    SetupScript { script: String },
-    // Future executors can be added here
-    // Shell { command: String },
-    // Docker { image: String, command: String },
+    Aider,
+    Codex,
+    /// The user's own command, from `Config::custom_executor_command`
+    Custom,
+    SetupScript {
+        script: String,
+    },
+    /// An agent added through `executors::registry` rather than built in
+    Registered {
+        name: String,
+    },
+}
+
+/// Flag recorded in a process's args when it ran with text output
+const TEXT_OUTPUT_ARG: &str = "--output-format=text";
+
+/// How a coding agent prints its progress. Plain text is for troubleshooting the JSON
+/// parsers: its lines are shown as messages as-is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Text,
+}
+
+impl OutputFormat {
+    /// Args to record on the execution process, so its logs are parsed in the format they were
+    /// written in even after the setting changes
+    pub fn process_args(self) -> Option<String> {
+        match self {
+            OutputFormat::Json => None,
+            OutputFormat::Text => Some(serde_json::to_string(&[TEXT_OUTPUT_ARG]).unwrap()),
+        }
+    }
+
+    /// The format a process ran with, from its recorded args
+    pub fn from_process_args(args: Option<&str>) -> Self {
+        let args: Vec<String> = args
+            .and_then(|args| serde_json::from_str(args).ok())
+            .unwrap_or_default();
+        if args.iter().any(|arg| arg == TEXT_OUTPUT_ARG) {
+            OutputFormat::Text
+        } else {
+            OutputFormat::Json
+        }
+    }
 }
 
 // Constants for frontend
@@ -360,35 +519,78 @@ pub struct ExecutorConstants {
     pub executor_labels: Vec<String>,
 }
 
+impl ExecutorConstants {
+    pub fn new(executors: Vec<ExecutorConfig>) -> Self {
+        Self {
+            executor_labels: executors
+                .iter()
+                .map(|executor| executor.display_name().to_string())
+                .collect(),
+            executor_types: executors,
+        }
+    }
+}
+
 impl FromStr for ExecutorConfig {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "echo" => Ok(ExecutorConfig::Echo),
-            "claude" => Ok(ExecutorConfig::Claude),
-            "amp" => Ok(ExecutorConfig::Amp),
-            "gemini" => Ok(ExecutorConfig::Gemini),
-            "opencode" => Ok(ExecutorConfig::Opencode),
-            "setup_script" => Ok(ExecutorConfig::SetupScript {
+        if s == "setup_script" {
+            return Ok(ExecutorConfig::SetupScript {
                 script: "setup script".to_string(),
-            }),
-            _ => Err(format!("Unknown executor type: {}", s)),
+            });
         }
+        if registry::executor_factory(s).is_none() {
+            return Err(format!("Unknown executor type: {}", s));
+        }
+        Ok(match s {
+            "echo" => ExecutorConfig::Echo,
+            "claude" => ExecutorConfig::Claude,
+            "amp" => ExecutorConfig::Amp,
+            "gemini" => ExecutorConfig::Gemini,
+            "opencode" => ExecutorConfig::Opencode,
+            "aider" => ExecutorConfig::Aider,
+            "codex" => ExecutorConfig::Codex,
+            "custom" => ExecutorConfig::Custom,
+            name => ExecutorConfig::Registered {
+                name: name.to_string(),
+            },
+        })
     }
 }
 
 impl ExecutorConfig {
-    pub fn create_executor(&self) -> Box<dyn Executor> {
+    /// The coding agents attempts can run with, in the order they're offered to users
+    pub fn agents() -> Vec<ExecutorConfig> {
+        registry::available_executors()
+            .iter()
+            .filter_map(|name| name.parse().ok())
+            .collect()
+    }
+
+    /// Factory of the agent, `None` for setup scripts and agents no longer registered
+    pub fn factory(&self) -> Option<std::sync::Arc<dyn ExecutorFactory>> {
         match self {
-            ExecutorConfig::Echo => Box::new(EchoExecutor),
-            ExecutorConfig::Claude => Box::new(ClaudeExecutor),
-            ExecutorConfig::Amp => Box::new(AmpExecutor),
-            ExecutorConfig::Gemini => Box::new(GeminiExecutor),
-            ExecutorConfig::Opencode => Box::new(OpencodeExecutor),
-            ExecutorConfig::SetupScript { script } => {
-                Box::new(SetupScriptExecutor::new(script.clone()))
-            }
+            ExecutorConfig::SetupScript { .. } => None,
+            agent => registry::executor_factory(&agent.to_string()),
+        }
+    }
+
+    pub fn create_executor(&self) -> Box<dyn Executor> {
+        self.create_executor_with_output_format(OutputFormat::Json)
+    }
+
+    /// Executors without a text mode ignore `output_format`
+    pub fn create_executor_with_output_format(
+        &self,
+        output_format: OutputFormat,
+    ) -> Box<dyn Executor> {
+        if let ExecutorConfig::SetupScript { script } = self {
+            return Box::new(SetupScriptExecutor::new(script.clone()));
+        }
+        match self.factory() {
+            Some(factory) => factory.create(output_format),
+            None => Box::new(UnregisteredExecutor(self.to_string())),
         }
     }
 
@@ -403,7 +605,12 @@ impl ExecutorConfig {
             ExecutorConfig::Gemini => {
                 dirs::home_dir().map(|home| home.join(".gemini").join("settings.json"))
             }
-            ExecutorConfig::SetupScript { .. } => None,
+            // Aider doesn't support MCP, and Codex keeps its servers in TOML
+            ExecutorConfig::Aider
+            | ExecutorConfig::Codex
+            | ExecutorConfig::Custom
+            | ExecutorConfig::SetupScript { .. }
+            | ExecutorConfig::Registered { .. } => None,
         }
     }
 
@@ -416,6 +623,10 @@ impl ExecutorConfig {
             ExecutorConfig::Amp => Some(vec!["amp", "mcpServers"]), // Nested path for Amp
             ExecutorConfig::Gemini => Some(vec!["mcpServers"]),
             ExecutorConfig::SetupScript { .. } => None, // Setup scripts don't support MCP
+            ExecutorConfig::Aider
+            | ExecutorConfig::Codex
+            | ExecutorConfig::Custom
+            | ExecutorConfig::Registered { .. } => None,
         }
     }
 
@@ -423,21 +634,123 @@ impl ExecutorConfig {
     pub fn supports_mcp(&self) -> bool {
         !matches!(
             self,
-            ExecutorConfig::Echo | ExecutorConfig::SetupScript { .. }
+            ExecutorConfig::Echo
+                | ExecutorConfig::Aider
+                | ExecutorConfig::Codex
+                | ExecutorConfig::Custom
+                | ExecutorConfig::SetupScript { .. }
+                | ExecutorConfig::Registered { .. }
         )
     }
 
+    /// Program of the executor's own CLI, run directly when it's installed or its path is set
+    /// in `Config::executable_paths`
+    pub fn cli_program(&self) -> Option<&'static str> {
+        match self {
+            ExecutorConfig::Claude => Some("claude"),
+            ExecutorConfig::Gemini => Some("gemini"),
+            ExecutorConfig::Opencode => Some("opencode"),
+            _ => None,
+        }
+    }
+
+    /// Check that the executor's CLI can be launched, so a doomed attempt fails before its
+    /// worktree is created
+    pub fn check_available(&self, lookup: &ExecutableLookup) -> Result<(), String> {
+        if let Some(program) = self.cli_program() {
+            let config = crate::models::config::Config::read(&crate::utils::config_path())
+                .unwrap_or_default();
+            if let Some(path) = config.executable_path(self) {
+                return match std::path::Path::new(path).is_file() {
+                    true => Ok(()),
+                    false => Err(format!(
+                        "{} is not available: the configured CLI {} doesn't exist",
+                        self.display_name(),
+                        path
+                    )),
+                };
+            }
+            // A pinned Claude version is run through npx, whatever is installed
+            let pinned =
+                matches!(self, ExecutorConfig::Claude) && config.claude.pinned_version().is_some();
+            if !pinned && lookup.find(program).is_some() {
+                return Ok(());
+            }
+        }
+
+        let package = match self {
+            ExecutorConfig::Echo | ExecutorConfig::SetupScript { .. } => return Ok(()),
+            // The command's own program is only known to the shell running it
+            ExecutorConfig::Custom => {
+                return match crate::executors::custom::configured_command() {
+                    Some(_) => Ok(()),
+                    None => Err(format!(
+                        "{} is not available: no command has been configured",
+                        self.display_name()
+                    )),
+                };
+            }
+            // Registered agents check their own requirements when spawned
+            ExecutorConfig::Registered { name } => {
+                return match self.factory() {
+                    Some(_) => Ok(()),
+                    None => Err(format!("No executor is registered as '{}'", name)),
+                };
+            }
+            ExecutorConfig::Opencode | ExecutorConfig::Aider => {
+                let program = self.to_string();
+                return match lookup.find(&program) {
+                    Some(_) => Ok(()),
+                    None => Err(format!(
+                        "{} is not available: `{}` was not found on PATH",
+                        self.display_name(),
+                        program
+                    )),
+                };
+            }
+            ExecutorConfig::Claude => "@anthropic-ai/claude-code",
+            ExecutorConfig::Amp => "@sourcegraph/amp",
+            ExecutorConfig::Gemini => "@google/gemini-cli",
+            ExecutorConfig::Codex => "@openai/codex",
+        };
+
+        if lookup.find("npx").is_none() {
+            return Err(format!(
+                "{} is not available: `npx` was not found on PATH. Install Node.js to use it.",
+                self.display_name()
+            ));
+        }
+        if lookup.npm_offline() && !lookup.npx_cached(package) {
+            return Err(format!(
+                "{} is not available offline: npx has not downloaded {} yet",
+                self.display_name(),
+                package
+            ));
+        }
+        Ok(())
+    }
+
     /// Get the display name for this executor
     pub fn display_name(&self) -> &'static str {
-        match self {
-            ExecutorConfig::Echo => "Echo (Test Mode)",
-            ExecutorConfig::Opencode => "Opencode",
-            ExecutorConfig::Claude => "Claude",
-            ExecutorConfig::Amp => "Amp",
-            ExecutorConfig::Gemini => "Gemini",
-            ExecutorConfig::SetupScript { .. } => "Setup Script",
+        match (self, self.factory()) {
+            (ExecutorConfig::SetupScript { .. }, _) => "Setup Script",
+            (_, Some(factory)) => factory.display_name(),
+            (_, None) => "Unknown executor",
         }
     }
+
+    /// Whether the executor can print plain text instead of JSON
+    pub fn supports_text_output(&self) -> bool {
+        self.factory()
+            .is_some_and(|factory| factory.supports_text_output())
+    }
+
+    /// Estimated tokens auto-composed prompts may use, leaving room in the model's context
+    /// for the agent's own work
+    pub fn default_prompt_token_budget(&self) -> u32 {
+        self.factory()
+            .map_or(100_000, |factory| factory.default_prompt_token_budget())
+    }
 }
 
 impl std::fmt::Display for ExecutorConfig {
@@ -448,12 +761,224 @@ impl std::fmt::Display for ExecutorConfig {
             ExecutorConfig::Amp => "amp",
             ExecutorConfig::Gemini => "gemini",
             ExecutorConfig::Opencode => "opencode",
+            ExecutorConfig::Aider => "aider",
+            ExecutorConfig::Codex => "codex",
+            ExecutorConfig::Custom => "custom",
             ExecutorConfig::SetupScript { .. } => "setup_script",
+            ExecutorConfig::Registered { name } => name.as_str(),
         };
         write!(f, "{}", s)
     }
 }
 
+tokio::task_local! {
+    /// Variables set for the attempt whose process is being spawned
+    static ATTEMPT_ENV: HashMap<String, String>;
+}
+
+/// Run `spawn` with `env` as the attempt's environment, which executors add to the commands
+/// they spawn through `apply_attempt_env`
+pub async fn with_attempt_env<F: Future>(env: HashMap<String, String>, spawn: F) -> F::Output {
+    ATTEMPT_ENV.scope(env, spawn).await
+}
+
+/// Add the environment of the attempt being spawned, if any, to a command. Called last so the
+/// attempt's variables win over the executor's own.
+pub fn apply_attempt_env(command: &mut tokio::process::Command) {
+    let _ = ATTEMPT_ENV.try_with(|env| {
+        command.envs(env);
+    });
+}
+
+/// Resolve the language agents should respond in for a project.
+/// The project's own setting wins over the global config.
+pub async fn resolve_response_language(
+    pool: &sqlx::SqlitePool,
+    project_id: Uuid,
+) -> Option<String> {
+    let project_language = crate::models::project::Project::find_by_id(pool, project_id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|project| project.response_language);
+
+    project_language
+        .or_else(|| {
+            crate::models::config::Config::read(&crate::utils::config_path())
+                .ok()
+                .and_then(|config| config.response_language)
+        })
+        .filter(|language| !language.trim().is_empty())
+}
+
+/// Prefix a prompt with an instruction to respond in the given language
+pub fn with_response_language(prompt: &str, response_language: Option<&str>) -> String {
+    match response_language {
+        Some(language) => format!(
+            "Respond in the language identified by the tag '{}' for all messages, summaries and explanations. Keep code, identifiers and commit messages as they would normally be written.\n\n{}",
+            language, prompt
+        ),
+        None => prompt.to_string(),
+    }
+}
+
+/// Which kind of spawn a prompt is for, choosing the configured template that frames it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Initial,
+    FollowUp,
+}
+
+/// Place a prompt at the template's `{prompt}` placeholder, or after the template when it has
+/// none. Without a template, or with a blank one, the prompt is used as it is.
+pub fn render_prompt_template(template: Option<&str>, prompt: &str) -> String {
+    match template.filter(|template| !template.trim().is_empty()) {
+        Some(template) if template.contains("{prompt}") => template.replace("{prompt}", prompt),
+        Some(template) => format!("{}\n\n{}", template.trim_end(), prompt),
+        None => prompt.to_string(),
+    }
+}
+
+/// Frame a prompt with the template configured for its kind
+pub fn with_prompt_template(prompt: &str, kind: PromptKind) -> String {
+    let config = crate::models::config::Config::read(&crate::utils::config_path()).ok();
+    render_prompt_template(
+        config
+            .as_ref()
+            .and_then(|config| config.prompt_template(kind)),
+        prompt,
+    )
+}
+
+/// Frame a task prompt with the initial prompt template, then prefix it with the project's
+/// glossary and response language
+pub async fn with_project_preamble(
+    pool: &sqlx::SqlitePool,
+    project_id: Uuid,
+    prompt: &str,
+) -> String {
+    let prompt = with_prompt_template(prompt, PromptKind::Initial);
+    let glossary = crate::services::glossary::prompt_section(pool, project_id).await;
+    let prompt = crate::services::glossary::with_glossary(&prompt, glossary.as_deref());
+    let response_language = resolve_response_language(pool, project_id).await;
+    with_response_language(&prompt, response_language.as_deref())
+}
+
+/// Stateful normalization of an executor's output, fed one complete line at a time and
+/// keeping whatever it needs from earlier lines, so a growing log isn't parsed from the start
+/// again for every new line
+pub trait LogNormalizer: Send {
+    /// Normalize the next line of output, given without its newline
+    fn normalize_line(&mut self, line: &str) -> Vec<NormalizedEntry>;
+
+    /// The executor's session id, once the output revealed it
+    fn session_id(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Normalizes an executor's output as it is read, so conversation entries are available
+/// while the process runs rather than only from the stored stdout. Executors with a
+/// `LogNormalizer` only parse each line once. Every other executor works too, by running its
+/// `normalize_logs` over all the output so far whenever a line completes; those whose
+/// entries depend on later lines (like Amp's streamed messages) simply produce them once the
+/// deciding line arrives.
+pub struct IncrementalNormalizer {
+    executor: Box<dyn Executor>,
+    line_normalizer: Option<Box<dyn LogNormalizer>>,
+    worktree_path: String,
+    /// Complete lines read so far, kept only to normalize them again without a
+    /// `LogNormalizer`
+    logs: String,
+    /// Output after the last newline, held back until its line is complete
+    partial_line: String,
+    entries: Vec<NormalizedEntry>,
+    max_metadata_bytes: usize,
+}
+
+impl IncrementalNormalizer {
+    pub fn new(executor: Box<dyn Executor>, worktree_path: impl Into<String>) -> Self {
+        let worktree_path = worktree_path.into();
+        Self {
+            line_normalizer: executor.log_normalizer(&worktree_path),
+            executor,
+            worktree_path,
+            logs: String::new(),
+            partial_line: String::new(),
+            entries: Vec::new(),
+            max_metadata_bytes: metadata_cap(),
+        }
+    }
+
+    /// Add a chunk of output, returning the entries it completed
+    pub fn push(&mut self, chunk: &str) -> Vec<NormalizedEntry> {
+        self.partial_line.push_str(chunk);
+        let Some(end) = self.partial_line.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.partial_line.drain(..=end).collect();
+
+        match self.line_normalizer.as_mut() {
+            Some(line_normalizer) => {
+                let mut new_entries: Vec<_> = complete
+                    .lines()
+                    .flat_map(|line| line_normalizer.normalize_line(line))
+                    .collect();
+                for (offset, entry) in new_entries.iter_mut().enumerate() {
+                    entry.sequence = (self.entries.len() + offset) as u32;
+                    entry.cap_metadata(self.max_metadata_bytes);
+                }
+                self.entries.extend(new_entries.iter().cloned());
+                new_entries
+            }
+            None => {
+                self.logs.push_str(&complete);
+                self.normalize()
+            }
+        }
+    }
+
+    /// Normalize a last line that never got its newline, once the output has ended
+    pub fn finish(&mut self) -> Vec<NormalizedEntry> {
+        if self.partial_line.is_empty() {
+            return Vec::new();
+        }
+        self.push("\n")
+    }
+
+    /// All entries normalized so far
+    pub fn entries(&self) -> &[NormalizedEntry] {
+        &self.entries
+    }
+
+    fn normalize(&mut self) -> Vec<NormalizedEntry> {
+        match self
+            .executor
+            .normalize_logs(&self.logs, &self.worktree_path)
+        {
+            Ok(mut conversation) => {
+                NormalizedConversation::assign_sequence(&mut conversation.entries);
+                NormalizedConversation::cap_metadata(
+                    &mut conversation.entries,
+                    self.max_metadata_bytes,
+                );
+                let new_entries = conversation
+                    .entries
+                    .get(self.entries.len()..)
+                    .map(<[NormalizedEntry]>::to_vec)
+                    .unwrap_or_default();
+                self.entries = conversation.entries;
+                new_entries
+            }
+            Err(e) => {
+                // Output may not parse until more of it arrives; keep the last good entries
+                tracing::debug!("Incremental normalization failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
 /// Stream output from a child process to the database
 pub async fn stream_output_to_db(
     output: impl tokio::io::AsyncRead + Unpin,
@@ -471,80 +996,85 @@ pub async fn stream_output_to_db(
 
 /// Stream stdout from a child process to the database (immediate updates)
 async fn stream_stdout_to_db(
-    output: impl tokio::io::AsyncRead + Unpin,
+    mut output: impl tokio::io::AsyncRead + Unpin,
     pool: sqlx::SqlitePool,
     attempt_id: Uuid,
     execution_process_id: Uuid,
 ) {
     use crate::models::{execution_process::ExecutionProcess, executor_session::ExecutorSession};
 
-    let mut reader = BufReader::new(output);
-    let mut line = String::new();
+    let mut capture = OutputCapture::default();
+    let mut buffer = vec![0; READ_CHUNK_SIZE];
     let mut accumulated_output = String::new();
     let mut update_counter = 0;
     let mut session_id_parsed = false;
+    live_normalization::start(&pool, execution_process_id).await;
 
     loop {
-        line.clear();
-        match reader.read_line(&mut line).await {
-            Ok(0) => break, // EOF
-            Ok(_) => {
-                // Parse session ID from the first JSONL line
-                if !session_id_parsed {
-                    if let Some(external_session_id) = parse_session_id_from_line(&line) {
-                        if let Err(e) = ExecutorSession::update_session_id(
-                            &pool,
-                            execution_process_id,
-                            &external_session_id,
-                        )
-                        .await
-                        {
-                            tracing::error!(
-                                "Failed to update session ID for execution process {}: {}",
-                                execution_process_id,
-                                e
-                            );
-                        } else {
-                            tracing::info!(
-                                "Updated session ID {} for execution process {}",
-                                external_session_id,
-                                execution_process_id
-                            );
-                        }
-                        session_id_parsed = true;
-                    }
-                }
+        let (lines, eof) = match output.read(&mut buffer).await {
+            Ok(0) => (capture.finish(), true), // EOF
+            Ok(n) => (capture.push(&buffer[..n]), false),
+            Err(e) => {
+                tracing::error!("Error reading stdout for attempt {}: {}", attempt_id, e);
+                (capture.finish(), true)
+            }
+        };
 
-                accumulated_output.push_str(&line);
-                update_counter += 1;
+        for line in lines {
+            live_normalization::push(execution_process_id, &line);
 
-                // Update database every threshold lines or when we have a significant amount of data
-                if update_counter >= STDOUT_UPDATE_THRESHOLD
-                    || accumulated_output.len() > BUFFER_SIZE_THRESHOLD
-                {
-                    if let Err(e) = ExecutionProcess::append_output(
+            // Parse session ID from the first JSONL line
+            if !session_id_parsed {
+                if let Some(external_session_id) = parse_session_id_from_line(&line) {
+                    if let Err(e) = ExecutorSession::update_session_id(
                         &pool,
                         execution_process_id,
-                        Some(&accumulated_output),
-                        None,
+                        &external_session_id,
                     )
                     .await
                     {
                         tracing::error!(
-                            "Failed to update stdout for attempt {}: {}",
-                            attempt_id,
+                            "Failed to update session ID for execution process {}: {}",
+                            execution_process_id,
                             e
                         );
+                    } else {
+                        tracing::info!(
+                            "Updated session ID {} for execution process {}",
+                            external_session_id,
+                            execution_process_id
+                        );
                     }
-                    accumulated_output.clear();
-                    update_counter = 0;
+                    session_id_parsed = true;
                 }
             }
-            Err(e) => {
-                tracing::error!("Error reading stdout for attempt {}: {}", attempt_id, e);
-                break;
+
+            accumulated_output.push_str(&line);
+            update_counter += 1;
+
+            // Update database every threshold lines or when we have a significant amount of data
+            if update_counter >= STDOUT_UPDATE_THRESHOLD
+                || accumulated_output.len() > BUFFER_SIZE_THRESHOLD
+            {
+                if let Err(e) = ExecutionProcess::append_output(
+                    &pool,
+                    execution_process_id,
+                    Some(&accumulated_output),
+                    None,
+                )
+                .await
+                {
+                    tracing::error!("Failed to update stdout for attempt {}: {}", attempt_id, e);
+                }
+                accumulated_output.clear();
+                update_counter = 0;
             }
         }
+        record_replaced_bytes(&pool, execution_process_id, &mut capture).await;
+
+        if eof {
+            break;
+        }
     }
 
     // Flush any remaining output
@@ -560,37 +1090,40 @@ async fn stream_stdout_to_db(
             tracing::error!("Failed to flush stdout for attempt {}: {}", attempt_id, e);
         }
     }
+    // All of stdout is stored now, so readers can go back to parsing it
+    live_normalization::finish(execution_process_id);
 }
 
 /// Stream stderr from a child process to the database (buffered with timeout)
 async fn stream_stderr_to_db(
-    output: impl tokio::io::AsyncRead + Unpin,
+    mut output: impl tokio::io::AsyncRead + Unpin,
     pool: sqlx::SqlitePool,
     attempt_id: Uuid,
     execution_process_id: Uuid,
 ) {
     use tokio::time::{timeout, Duration};
 
-    let mut reader = BufReader::new(output);
-    let mut line = String::new();
+    let mut capture = OutputCapture::default();
+    let mut buffer = vec![0; READ_CHUNK_SIZE];
     let mut accumulated_output = String::new();
     const STDERR_FLUSH_TIMEOUT_MS: u64 = 1000;
     const STDERR_FLUSH_TIMEOUT: Duration = Duration::from_millis(STDERR_FLUSH_TIMEOUT_MS); // 1000ms timeout
 
     loop {
-        line.clear();
-
-        // Try to read a line with a timeout
-        let read_result = timeout(STDERR_FLUSH_TIMEOUT, reader.read_line(&mut line)).await;
+        // Try to read with a timeout; reads are cancel safe, so nothing is lost on a timeout
+        let read_result = timeout(STDERR_FLUSH_TIMEOUT, output.read(&mut buffer)).await;
 
         match read_result {
             Ok(Ok(0)) => {
                 // EOF - flush remaining output and break
                 break;
             }
-            Ok(Ok(_)) => {
-                // Successfully read a line - just accumulate it
-                accumulated_output.push_str(&line);
+            Ok(Ok(n)) => {
+                // Successfully read some output - just accumulate its complete lines
+                for line in capture.push(&buffer[..n]) {
+                    live_normalization::push_stderr(execution_process_id, &line);
+                    accumulated_output.push_str(&line);
+                }
             }
             Ok(Err(e)) => {
                 tracing::error!("Error reading stderr for attempt {}: {}", attempt_id, e);
@@ -608,14 +1141,43 @@ async fn stream_stderr_to_db(
                     .await;
                     accumulated_output.clear();
                 }
+                record_replaced_bytes(&pool, execution_process_id, &mut capture).await;
             }
         }
     }
 
     // Final flush for any remaining output
+    for line in capture.finish() {
+        live_normalization::push_stderr(execution_process_id, &line);
+        accumulated_output.push_str(&line);
+    }
     if !accumulated_output.is_empty() {
         flush_stderr_chunk(&pool, execution_process_id, &accumulated_output, attempt_id).await;
     }
+    record_replaced_bytes(&pool, execution_process_id, &mut capture).await;
+}
+
+/// Count the output bytes the capture replaced or dropped since it was last recorded
+async fn record_replaced_bytes(
+    pool: &sqlx::SqlitePool,
+    execution_process_id: Uuid,
+    capture: &mut OutputCapture,
+) {
+    use crate::models::execution_process::ExecutionProcess;
+
+    let count = capture.take_replaced_bytes();
+    if count == 0 {
+        return;
+    }
+    if let Err(e) =
+        ExecutionProcess::add_replaced_output_bytes(pool, execution_process_id, count).await
+    {
+        tracing::error!(
+            "Failed to record replaced output bytes for process {}: {}",
+            execution_process_id,
+            e
+        );
+    }
 }
 
 /// Flush a chunk of stderr output to the database
@@ -741,7 +1303,8 @@ pub fn parse_assistant_message_from_logs(logs: &str) -> Option<String> {
     last_assistant_message
 }
 
-/// Parse session_id from Claude or thread_id from Amp from the first JSONL line
+/// Parse session_id from Claude or thread_id from Amp from the first JSONL line, or Codex's
+/// session_id from its session_configured event
 fn parse_session_id_from_line(line: &str) -> Option<String> {
     use serde_json::Value;
 
@@ -761,6 +1324,17 @@ fn parse_session_id_from_line(line: &str) -> Option<String> {
         if let Some(thread_id) = json.get("threadID").and_then(|v| v.as_str()) {
             return Some(thread_id.to_string());
         }
+
+        // Check for Codex session_configured
+        let msg = json.get("msg");
+        if msg.and_then(|m| m.get("type")).and_then(|t| t.as_str()) == Some("session_configured") {
+            if let Some(session_id) = msg
+                .and_then(|m| m.get("session_id"))
+                .and_then(|v| v.as_str())
+            {
+                return Some(session_id.to_string());
+            }
+        }
     }
 
     None
@@ -769,7 +1343,113 @@ fn parse_session_id_from_line(line: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::executors::{AmpExecutor, ClaudeExecutor};
+    use crate::executors::{
+        claude::ClaudeLogNormalizer, AmpExecutor, ClaudeExecutor, GeminiExecutor,
+    };
+
+    /// Stream recorded output through an `IncrementalNormalizer` in chunks that split lines,
+    /// as reads from a pipe do, and check the entries arrive while reading and add up to what
+    /// normalizing the whole output gives
+    fn assert_streams_incrementally(executor: Box<dyn Executor>, logs: &str) {
+        let expected: Vec<_> = executor
+            .normalize_logs(logs, "/tmp/test-worktree")
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|entry| entry.content)
+            .collect();
+        assert!(expected.len() > 1);
+
+        let mut normalizer = IncrementalNormalizer::new(executor, "/tmp/test-worktree");
+        let output: Vec<char> = format!("{}\n", logs).chars().collect();
+        let mut streamed = Vec::new();
+        let mut reads_with_entries = 0;
+        for chunk in output.chunks(37) {
+            let new_entries = normalizer.push(&chunk.iter().collect::<String>());
+            if !new_entries.is_empty() {
+                reads_with_entries += 1;
+            }
+            streamed.extend(new_entries.into_iter().map(|entry| entry.content));
+        }
+
+        assert_eq!(streamed, expected);
+        assert!(reads_with_entries > 1);
+        assert_eq!(normalizer.entries().len(), expected.len());
+    }
+
+    #[test]
+    fn test_claude_output_normalizes_incrementally() {
+        let logs = r#"{"type":"system","subtype":"init","cwd":"/tmp/test-worktree","session_id":"s-1","tools":[],"model":"claude-sonnet-4-20250514"}
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Let me look at the entry point."}]},"session_id":"s-1"}
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Read","input":{"file_path":"/tmp/test-worktree/src/main.rs"}}]},"session_id":"s-1"}
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"The off-by-one is fixed."}]},"session_id":"s-1"}
+{"type":"result","subtype":"success","is_error":false,"duration_ms":1200,"result":"done"}"#;
+
+        assert_streams_incrementally(Box::new(ClaudeExecutor::default()), logs);
+    }
+
+    #[test]
+    fn test_amp_output_normalizes_incrementally() {
+        let logs = r#"{"type":"initial","threadID":"T-1"}
+{"type":"messages","messages":[[0,{"role":"user","content":[{"type":"text","text":"Task title: Fix the off-by-one"}],"meta":{"sentAt":1751544747623}}]],"toolResults":[]}
+{"type":"messages","messages":[[1,{"role":"assistant","content":[{"type":"text","text":"Looking at the loop"}],"state":{"type":"streaming"}}]],"toolResults":[]}
+{"type":"messages","messages":[[1,{"role":"assistant","content":[{"type":"text","text":"Looking at the loop bounds now."}],"state":{"type":"complete","stopReason":"end_turn"}}]],"toolResults":[]}
+{"type":"messages","messages":[[2,{"role":"assistant","content":[{"type":"text","text":"Fixed."}],"state":{"type":"complete","stopReason":"end_turn"}}]],"toolResults":[]}
+{"type":"state","state":"idle"}"#;
+
+        assert_streams_incrementally(Box::new(AmpExecutor), logs);
+    }
+
+    #[test]
+    fn test_gemini_output_normalizes_incrementally() {
+        let logs = r#"{"timestamp":"2025-07-21T10:00:00+00:00","entry_type":{"type":"user_message"},"content":"Task title: Fix the off-by-one","metadata":null}
+{"timestamp":"2025-07-21T10:00:01+00:00","entry_type":{"type":"assistant_message"},"content":"Looking at the loop bounds.","metadata":null}
+{"timestamp":"2025-07-21T10:00:02+00:00","entry_type":{"type":"assistant_message"},"content":"Fixed the comparison.","metadata":null}"#;
+
+        assert_streams_incrementally(Box::new(GeminiExecutor), logs);
+    }
+
+    /// Counts the lines it is given on top of normalizing them as Claude output
+    struct CountingNormalizer {
+        inner: ClaudeLogNormalizer,
+        lines: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl LogNormalizer for CountingNormalizer {
+        fn normalize_line(&mut self, line: &str) -> Vec<NormalizedEntry> {
+            self.lines
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            self.inner.normalize_line(line)
+        }
+    }
+
+    #[test]
+    fn test_line_normalizers_parse_each_line_once() {
+        let lines = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut normalizer =
+            IncrementalNormalizer::new(Box::new(ClaudeExecutor::default()), "/tmp/test-worktree");
+        normalizer.line_normalizer = Some(Box::new(CountingNormalizer {
+            inner: ClaudeLogNormalizer::new(OutputFormat::Json, "/tmp/test-worktree"),
+            lines: lines.clone(),
+        }));
+
+        let line = r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Still working."}]},"session_id":"s-1"}"#;
+        for count in 1..=2000 {
+            // Split mid-line, as reads from a pipe do
+            let (head, tail) = line.split_at(20);
+            assert!(normalizer.push(head).is_empty());
+            let new_entries = normalizer.push(&format!("{}\n", tail));
+            assert_eq!(new_entries.len(), 1);
+            assert_eq!(new_entries[0].sequence as usize, count - 1);
+            assert_eq!(
+                lines.load(std::sync::atomic::Ordering::Relaxed),
+                count,
+                "earlier lines were parsed again"
+            );
+        }
+        assert!(normalizer.logs.is_empty());
+        assert_eq!(normalizer.entries().len(), 2000);
+    }
 
     #[test]
     fn test_parse_claude_session_id() {
@@ -791,6 +1471,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_codex_session_id() {
+        let config_line = r#"{"model":"codex-mini-latest","sandbox":"danger-full-access","workdir":"/tmp/test-worktree"}"#;
+        let codex_line = r#"{"id":"0","msg":{"type":"session_configured","session_id":"0198a4c2-5e8f-7d31-9b1e-2c4f6a8d0e13","model":"codex-mini-latest"}}"#;
+
+        assert_eq!(parse_session_id_from_line(config_line), None);
+        assert_eq!(
+            parse_session_id_from_line(codex_line),
+            Some("0198a4c2-5e8f-7d31-9b1e-2c4f6a8d0e13".to_string())
+        );
+    }
+
     #[test]
     fn test_parse_invalid_json() {
         let invalid_line = "not json at all";
@@ -809,6 +1501,60 @@ mod tests {
         assert_eq!(parse_session_id_from_line("   "), None);
     }
 
+    #[test]
+    fn test_prompt_templates_frame_their_own_kind_of_prompt() {
+        let mut config = crate::models::config::Config::default();
+        let render = |config: &crate::models::config::Config, kind| {
+            render_prompt_template(config.prompt_template(kind), "Fix the login bug")
+        };
+        // Unset templates leave both kinds of prompt as they were
+        assert_eq!(render(&config, PromptKind::Initial), "Fix the login bug");
+        assert_eq!(render(&config, PromptKind::FollowUp), "Fix the login bug");
+
+        config.initial_prompt_template = Some("Start from a clean slate.\n\n{prompt}".to_string());
+        config.followup_prompt_template =
+            Some("Continue from the prior work: {prompt}".to_string());
+        assert_eq!(
+            render(&config, PromptKind::Initial),
+            "Start from a clean slate.\n\nFix the login bug"
+        );
+        assert_eq!(
+            render(&config, PromptKind::FollowUp),
+            "Continue from the prior work: Fix the login bug"
+        );
+
+        // Without a placeholder the prompt follows the template; a blank one is ignored
+        config.followup_prompt_template = Some("Continue from the prior work.\n".to_string());
+        assert_eq!(
+            render(&config, PromptKind::FollowUp),
+            "Continue from the prior work.\n\nFix the login bug"
+        );
+        config.initial_prompt_template = Some("  ".to_string());
+        assert_eq!(render(&config, PromptKind::Initial), "Fix the login bug");
+    }
+
+    #[test]
+    fn test_executor_error_source_chain() {
+        use std::error::Error;
+
+        let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "npx not found");
+        let err = ExecutorError::spawn_failed(
+            io_err,
+            SpawnContext::from_command(&tokio::process::Command::new("npx"), "Claude"),
+        );
+        let source = err
+            .source()
+            .expect("SpawnFailed should expose its io error");
+        assert_eq!(source.to_string(), "npx not found");
+
+        let err: ExecutorError =
+            std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied").into();
+        assert!(matches!(err, ExecutorError::IoError(_)));
+        assert!(err.source().is_some());
+
+        assert!(ExecutorError::TaskNotFound.source().is_none());
+    }
+
     #[test]
     fn test_parse_assistant_message_from_logs() {
         // Test AMP format
@@ -903,7 +1649,7 @@ mod tests {
 
     #[test]
     fn test_claude_log_normalization() {
-        let claude_executor = ClaudeExecutor;
+        let claude_executor = ClaudeExecutor::default();
         let claude_logs = r#"{"type":"system","subtype":"init","cwd":"/private/tmp/mission-control-worktree-8ff34214-7bb4-4a5a-9f47-bfdf79e20368","session_id":"499dcce4-04aa-4a3e-9e0c-ea0228fa87c9","tools":["Task","Bash","Glob","Grep","LS","exit_plan_mode","Read","Edit","MultiEdit","Write","NotebookRead","NotebookEdit","WebFetch","TodoRead","TodoWrite","WebSearch"],"mcp_servers":[],"model":"claude-sonnet-4-20250514","permissionMode":"bypassPermissions","apiKeySource":"none"}
 {"type":"assistant","message":{"id":"msg_014xUHgkAhs6cRx5WVT3s7if","type":"message","role":"assistant","model":"claude-sonnet-4-20250514","content":[{"type":"text","text":"I'll help you list your projects using codecommand. Let me first explore the codebase to understand how codecommand works and find your projects."}],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":4,"cache_creation_input_tokens":13497,"cache_read_input_tokens":0,"output_tokens":1,"service_tier":"standard"}},"parent_tool_use_id":null,"session_id":"499dcce4-04aa-4a3e-9e0c-ea0228fa87c9"}
 {"type":"assistant","message":{"id":"msg_014xUHgkAhs6cRx5WVT3s7if","type":"message","role":"assistant","model":"claude-sonnet-4-20250514","content":[{"type":"tool_use","id":"toolu_01Br3TvXdmW6RPGpB5NihTHh","name":"Task","input":{"description":"Find codecommand projects","prompt":"I need to find and list projects using codecommand."}}],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":4,"cache_creation_input_tokens":13497,"cache_read_input_tokens":0,"output_tokens":1,"service_tier":"standard"}},"parent_tool_use_id":null,"session_id":"499dcce4-04aa-4a3e-9e0c-ea0228fa87c9"}"#;
@@ -951,4 +1697,159 @@ mod tests {
         // Should be the task description, not "Tool: Task with input: ..."
         assert_eq!(task_tool_use.content, "Find codecommand projects");
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_attempt_env_overrides_inherited_env_in_spawned_process() {
+        use tokio::io::AsyncReadExt;
+
+        use crate::{
+            executors::SetupScriptExecutor,
+            test_utils::{insert_project, insert_task, memory_pool},
+        };
+
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, "/tmp/p").await;
+        let task_id = insert_task(&pool, project_id, "t").await;
+
+        std::env::set_var("CODECOMMAND_TEST_FEATURE_FLAG", "inherited");
+        let executor = SetupScriptExecutor {
+            script: "echo \"$CODECOMMAND_TEST_FEATURE_FLAG $CODECOMMAND_TEST_ONLY_ATTEMPT\""
+                .to_string(),
+        };
+        let run = |env: HashMap<String, String>| {
+            let pool = pool.clone();
+            let executor = &executor;
+            async move {
+                let mut child = with_attempt_env(env, executor.spawn(&pool, task_id, "/tmp"))
+                    .await
+                    .unwrap();
+                let mut output = String::new();
+                child
+                    .inner()
+                    .stdout
+                    .take()
+                    .unwrap()
+                    .read_to_string(&mut output)
+                    .await
+                    .unwrap();
+                child.wait().await.unwrap();
+                output.trim().to_string()
+            }
+        };
+
+        assert_eq!(run(HashMap::new()).await, "inherited");
+        let env = HashMap::from([
+            (
+                "CODECOMMAND_TEST_FEATURE_FLAG".to_string(),
+                "attempt".to_string(),
+            ),
+            (
+                "CODECOMMAND_TEST_ONLY_ATTEMPT".to_string(),
+                "on".to_string(),
+            ),
+        ]);
+        assert_eq!(run(env).await, "attempt on");
+    }
+
+    #[tokio::test]
+    async fn test_captured_output_survives_invalid_utf8_and_runaway_lines() {
+        use crate::{
+            models::execution_process::{
+                CreateExecutionProcess, ExecutionProcess, ExecutionProcessStatus,
+                ExecutionProcessType,
+            },
+            services::log_normalization::normalize_execution_process,
+            test_utils::{seeded_pool, Seeded},
+            utils::output_capture::MAX_LINE_BYTES,
+        };
+
+        let (pool, Seeded { attempt_id, .. }) = seeded_pool().await;
+        let process_id = Uuid::new_v4();
+        ExecutionProcess::create(
+            &pool,
+            &CreateExecutionProcess {
+                task_attempt_id: attempt_id,
+                process_type: ExecutionProcessType::CodingAgent,
+                executor_type: Some("claude".to_string()),
+                command: "claude".to_string(),
+                args: None,
+                working_directory: "/tmp/w".to_string(),
+            },
+            process_id,
+        )
+        .await
+        .unwrap();
+
+        // CRLF line endings, ANSI colours around a Windows-1252 "é" and a NUL, a UTF-8 "é"
+        // split across two reads, then 10 MB without a newline
+        let first = b"{\"type\":\"system\",\"subtype\":\"init\",\"session_id\":\"s-1\"}\r\n\
+            \x1b[1m\x1b[32mCompiling\x1b[0m caf\xe9\x00 \x1b[2K\r\n\
+            {\"type\":\"assistant\",\"message\":{\"role\":\"assistant\",\"content\":[{\"type\":\"text\",\"text\":\"R\xc3"
+            .to_vec();
+        let second = b"\xa9sum\xc3\xa9 ready\"}]},\"session_id\":\"s-1\"}\r\n".to_vec();
+        let runaway = vec![b'x'; 10 * 1024 * 1024];
+        let output = (&first[..]).chain(&second[..]).chain(&runaway[..]);
+        stream_output_to_db(output, pool.clone(), attempt_id, process_id, true).await;
+        ExecutionProcess::update_completion(
+            &pool,
+            process_id,
+            ExecutionProcessStatus::Completed,
+            Some(0),
+        )
+        .await
+        .unwrap();
+
+        let process = ExecutionProcess::find_by_id(&pool, process_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let stdout = process.stdout.as_deref().unwrap();
+        assert!(stdout.contains("Compiling\x1b[0m caf\u{fffd} \x1b[2K\r\n"));
+        assert!(stdout.contains("Résumé ready"));
+        assert!(!stdout.contains('\0'));
+        assert_eq!(process.replaced_output_bytes, 2);
+        assert_eq!(stdout.matches('x').count(), 10 * 1024 * 1024);
+        assert!(stdout.lines().all(|line| line.len() <= MAX_LINE_BYTES));
+
+        // What the raw endpoint returns serializes and parses back as JSON
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&process).unwrap()).unwrap();
+        assert_eq!(json["stdout"].as_str(), Some(stdout));
+
+        let conversation = normalize_execution_process(&pool, &process).await.unwrap();
+        assert_eq!(conversation.session_id.as_deref(), Some("s-1"));
+        assert!(conversation
+            .entries
+            .iter()
+            .any(|entry| entry.content == "Résumé ready"));
+        assert!(conversation
+            .entries
+            .iter()
+            .any(|entry| entry.content.contains("caf\u{fffd}")));
+    }
+
+    #[test]
+    fn test_oversized_metadata_is_trimmed_and_flagged() {
+        let entry = |metadata: serde_json::Value| NormalizedEntry {
+            sequence: 0,
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: "Wrote `big.txt`".to_string(),
+            metadata: Some(metadata),
+        };
+        let small = serde_json::json!({"type": "tool_use", "input": {"path": "big.txt"}});
+        let large = serde_json::json!({"type": "tool_use", "input": {"content": "é".repeat(1000)}});
+        let mut entries = vec![entry(small.clone()), entry(large.clone())];
+
+        NormalizedConversation::cap_metadata(&mut entries, 101);
+
+        assert_eq!(entries[0].metadata, Some(small));
+        let trimmed = entries[1].metadata.as_ref().unwrap();
+        assert_eq!(trimmed["truncated"], true);
+        assert_eq!(trimmed["original_bytes"], large.to_string().len());
+        let preview = trimmed["preview"].as_str().unwrap();
+        assert!(preview.len() <= 101 && large.to_string().starts_with(preview));
+        assert_eq!(entries[1].content, "Wrote `big.txt`");
+    }
 }
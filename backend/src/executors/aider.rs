@@ -0,0 +1,440 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::{
+    executor::{
+        stream_output_to_db, with_project_preamble, ActionType, Executor, ExecutorError,
+        NormalizedConversation, NormalizedEntry, NormalizedEntryType, OutputFormat, SpawnContext,
+    },
+    executors::registry::{ExecutorFactory, FollowUp},
+    models::{executor_session::ExecutorSession, task::Task},
+    utils::{asset_dir, shell::get_shell_command},
+};
+
+/// An executor that uses Aider to process tasks. Each run gets a session whose chat history
+/// is kept outside the worktree, so follow-ups can restore it.
+pub struct AiderExecutor;
+
+/// An executor that continues an Aider session from its chat history
+pub struct AiderFollowupExecutor {
+    pub session_id: String,
+    pub prompt: String,
+}
+
+pub struct AiderFactory;
+
+impl ExecutorFactory for AiderFactory {
+    fn display_name(&self) -> &'static str {
+        "Aider"
+    }
+
+    fn create(&self, _output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(AiderExecutor)
+    }
+
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(Box::new(AiderFollowupExecutor {
+            session_id: follow_up.session_id?,
+            prompt: follow_up.prompt,
+        }))
+    }
+}
+
+/// Where Aider sessions keep their chat history and prompts
+fn sessions_dir() -> PathBuf {
+    asset_dir().join("aider")
+}
+
+/// Start Aider in `worktree_path` on `prompt`, appending to the chat history of
+/// `session_id`, and restoring it first when `restore` is set. The prompt is passed in a file
+/// instead of the command line to avoid shell escaping issues. Aider doesn't commit: changes
+/// are committed like those of every other executor.
+async fn spawn_aider(
+    session_id: &str,
+    prompt: &str,
+    restore: bool,
+    worktree_path: &str,
+    context: impl FnOnce(SpawnContext) -> SpawnContext,
+) -> Result<AsyncGroupChild, ExecutorError> {
+    let dir = sessions_dir();
+    tokio::fs::create_dir_all(&dir).await?;
+    let prompt_path = dir.join(format!("{}.prompt.md", session_id));
+    tokio::fs::write(&prompt_path, prompt).await?;
+
+    let mut aider_command = format!(
+        "aider --yes-always --no-pretty --no-stream --no-auto-commits --no-check-update --chat-history-file \"{}\" --input-history-file \"{}\" --message-file \"{}\"",
+        dir.join(format!("{}.chat.md", session_id)).display(),
+        dir.join(format!("{}.input", session_id)).display(),
+        prompt_path.display()
+    );
+    if restore {
+        aider_command.push_str(" --restore-chat-history");
+    }
+
+    let (shell_cmd, shell_arg) = get_shell_command();
+    let mut command = Command::new(shell_cmd);
+    command
+        .kill_on_drop(true)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .current_dir(worktree_path)
+        .arg(shell_arg)
+        .arg(&aider_command);
+
+    crate::executor::apply_attempt_env(&mut command);
+
+    command
+        .group_spawn() // Create new process group so we can kill entire tree
+        .map_err(|e| context(SpawnContext::from_command(&command, "Aider")).spawn_error(e))
+}
+
+/// Record the session on the execution process, then stream its output like other executors
+async fn stream_aider(
+    mut child: AsyncGroupChild,
+    session_id: &str,
+    pool: &sqlx::SqlitePool,
+    attempt_id: Uuid,
+    execution_process_id: Uuid,
+) -> AsyncGroupChild {
+    if let Err(e) = ExecutorSession::update_session_id(pool, execution_process_id, session_id).await
+    {
+        tracing::error!(
+            "Failed to update session ID for Aider execution process {}: {}",
+            execution_process_id,
+            e
+        );
+    }
+
+    let stdout = child
+        .inner()
+        .stdout
+        .take()
+        .expect("Failed to take stdout from child process");
+    let stderr = child
+        .inner()
+        .stderr
+        .take()
+        .expect("Failed to take stderr from child process");
+    tokio::spawn(stream_output_to_db(
+        stdout,
+        pool.clone(),
+        attempt_id,
+        execution_process_id,
+        true,
+    ));
+    tokio::spawn(stream_output_to_db(
+        stderr,
+        pool.clone(),
+        attempt_id,
+        execution_process_id,
+        false,
+    ));
+    child
+}
+
+impl AiderExecutor {
+    async fn task_prompt(
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+    ) -> Result<(Task, String), ExecutorError> {
+        let task = Task::find_by_id(pool, task_id)
+            .await?
+            .ok_or(ExecutorError::TaskNotFound)?;
+
+        let prompt = if let Some(task_description) = &task.description {
+            format!(
+                r#"project_id: {}
+
+Task title: {}
+Task description: {}"#,
+                task.project_id, task.title, task_description
+            )
+        } else {
+            format!(
+                r#"project_id: {}
+
+Task title: {}"#,
+                task.project_id, task.title
+            )
+        };
+
+        let prompt = with_project_preamble(pool, task.project_id, &prompt).await;
+        Ok((task, prompt))
+    }
+
+    async fn spawn_session(
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+        session_id: &str,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        let (task, prompt) = Self::task_prompt(pool, task_id).await?;
+        spawn_aider(session_id, &prompt, false, worktree_path, |context| {
+            context
+                .with_task(task_id, Some(task.title.clone()))
+                .with_context("Aider execution for new task")
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Executor for AiderExecutor {
+    async fn spawn(
+        &self,
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        Self::spawn_session(pool, task_id, &Uuid::new_v4().to_string(), worktree_path).await
+    }
+
+    async fn execute_streaming(
+        &self,
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+        attempt_id: Uuid,
+        execution_process_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        let session_id = Uuid::new_v4().to_string();
+        let child = Self::spawn_session(pool, task_id, &session_id, worktree_path).await?;
+        Ok(stream_aider(child, &session_id, pool, attempt_id, execution_process_id).await)
+    }
+
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        _worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        Ok(normalize_aider_logs(logs))
+    }
+}
+
+impl AiderFollowupExecutor {
+    async fn spawn_followup(&self, worktree_path: &str) -> Result<AsyncGroupChild, ExecutorError> {
+        spawn_aider(
+            &self.session_id,
+            &self.prompt,
+            true,
+            worktree_path,
+            |context| {
+                context.with_context(format!(
+                    "Aider followup execution for session {}",
+                    self.session_id
+                ))
+            },
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl Executor for AiderFollowupExecutor {
+    async fn spawn(
+        &self,
+        _pool: &sqlx::SqlitePool,
+        _task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        self.spawn_followup(worktree_path).await
+    }
+
+    async fn execute_streaming(
+        &self,
+        pool: &sqlx::SqlitePool,
+        _task_id: Uuid,
+        attempt_id: Uuid,
+        execution_process_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        let child = self.spawn_followup(worktree_path).await?;
+        // The follow-up continues the same chat history, so later follow-ups restore it too
+        Ok(stream_aider(
+            child,
+            &self.session_id,
+            pool,
+            attempt_id,
+            execution_process_id,
+        )
+        .await)
+    }
+
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        _worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        Ok(normalize_aider_logs(logs))
+    }
+}
+
+/// Start of the lines Aider prints about itself and its setup rather than the task
+const SYSTEM_PREFIXES: &[&str] = &[
+    "Aider v",
+    "Main model:",
+    "Model:",
+    "Weak model:",
+    "Editor model:",
+    "Git repo:",
+    "Repo-map:",
+    "Use /help",
+    "Tokens:",
+    "Restored previous conversation history",
+];
+
+fn tool_entry(tool_name: &str, action_type: ActionType, content: String) -> NormalizedEntry {
+    NormalizedEntry {
+        sequence: 0,
+        timestamp: None,
+        entry_type: NormalizedEntryType::ToolUse {
+            tool_name: tool_name.to_string(),
+            action_type,
+        },
+        content,
+        metadata: None,
+    }
+}
+
+/// Parse Aider's plain output. Edits it applied and shell commands it ran become tool uses,
+/// its setup and token reports system messages, and the text in between assistant messages.
+fn normalize_aider_logs(logs: &str) -> NormalizedConversation {
+    let mut entries = Vec::new();
+    let mut message: Vec<&str> = Vec::new();
+
+    let flush = |message: &mut Vec<&str>, entries: &mut Vec<NormalizedEntry>| {
+        let content = message.join("\n").trim().to_string();
+        message.clear();
+        if !content.is_empty() {
+            entries.push(NormalizedEntry {
+                sequence: 0,
+                timestamp: None,
+                entry_type: NormalizedEntryType::AssistantMessage,
+                content,
+                metadata: None,
+            });
+        }
+    };
+
+    for line in logs.lines() {
+        let trimmed = line.trim();
+        let entry = if let Some(path) = trimmed.strip_prefix("Applied edit to ") {
+            Some(tool_entry(
+                "edit",
+                ActionType::FileWrite {
+                    path: path.to_string(),
+                },
+                format!("`{}`", path),
+            ))
+        } else if let Some(command) = trimmed.strip_prefix("Running ") {
+            Some(tool_entry(
+                "shell",
+                ActionType::CommandRun {
+                    command: command.to_string(),
+                },
+                format!("`{}`", command),
+            ))
+        } else if let Some(path) = trimmed
+            .strip_prefix("Added ")
+            .and_then(|rest| rest.strip_suffix(" to the chat"))
+        {
+            Some(tool_entry(
+                "add",
+                ActionType::FileRead {
+                    path: path.to_string(),
+                },
+                format!("`{}`", path),
+            ))
+        } else if SYSTEM_PREFIXES
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix))
+        {
+            Some(NormalizedEntry {
+                sequence: 0,
+                timestamp: None,
+                entry_type: NormalizedEntryType::SystemMessage,
+                content: trimmed.to_string(),
+                metadata: None,
+            })
+        } else if trimmed.starts_with("Error") {
+            Some(NormalizedEntry {
+                sequence: 0,
+                timestamp: None,
+                entry_type: NormalizedEntryType::ErrorMessage,
+                content: trimmed.to_string(),
+                metadata: None,
+            })
+        } else {
+            None
+        };
+
+        match entry {
+            Some(entry) => {
+                flush(&mut message, &mut entries);
+                entries.push(entry);
+            }
+            None => message.push(line.trim_end()),
+        }
+    }
+    flush(&mut message, &mut entries);
+
+    NormalizedConversation {
+        entries,
+        session_id: None,
+        executor_type: "aider".to_string(),
+        prompt: None,
+        summary: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_aider_edits_and_commands() {
+        let logs = "Aider v0.82.0\nMain model: sonnet with diff edit format\nGit repo: .git with 42 files\nAdded src/theme.rs to the chat\n\nI'll add a dark variant to the theme.\n\nsrc/theme.rs\n```rust\npub const DARK: &str = \"dark\";\n```\nApplied edit to src/theme.rs\nRunning cargo test theme\nTokens: 2.1k sent, 180 received. Cost: $0.01 message, $0.01 session.\n";
+
+        let conversation = normalize_aider_logs(logs);
+        let entries = &conversation.entries;
+        assert_eq!(conversation.executor_type, "aider");
+        assert_eq!(entries.len(), 8);
+        assert!(matches!(
+            entries[3].entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileRead { ref path },
+                ..
+            } if path == "src/theme.rs"
+        ));
+        assert!(matches!(
+            entries[4].entry_type,
+            NormalizedEntryType::AssistantMessage
+        ));
+        assert!(entries[4]
+            .content
+            .starts_with("I'll add a dark variant to the theme."));
+        assert!(entries[4].content.ends_with("```"));
+        assert!(matches!(
+            entries[5].entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileWrite { ref path },
+                ..
+            } if path == "src/theme.rs"
+        ));
+        assert!(matches!(
+            entries[6].entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::CommandRun { ref command },
+                ..
+            } if command == "cargo test theme"
+        ));
+        assert!(matches!(
+            entries[7].entry_type,
+            NormalizedEntryType::SystemMessage
+        ));
+    }
+}
@@ -7,9 +7,10 @@ use uuid::Uuid;
 
 use crate::{
     executor::{
-        ActionType, Executor, ExecutorError, NormalizedConversation, NormalizedEntry,
-        NormalizedEntryType,
+        with_project_preamble, ActionType, Executor, ExecutorError, NormalizedConversation,
+        NormalizedEntry, NormalizedEntryType, OutputFormat,
     },
+    executors::registry::{ExecutorFactory, FollowUp},
     models::task::Task,
     utils::shell::get_shell_command,
 };
@@ -23,6 +24,25 @@ pub struct AmpFollowupExecutor {
     pub prompt: String,
 }
 
+pub struct AmpFactory;
+
+impl ExecutorFactory for AmpFactory {
+    fn display_name(&self) -> &'static str {
+        "Amp"
+    }
+
+    fn create(&self, _output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(AmpExecutor)
+    }
+
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(Box::new(AmpFollowupExecutor {
+            thread_id: follow_up.session_id?,
+            prompt: follow_up.prompt,
+        }))
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "type")]
 pub enum AmpJson {
@@ -160,6 +180,7 @@ impl AmpContentItem {
                     _ => return None,
                 };
                 Some(NormalizedEntry {
+                    sequence: 0,
                     timestamp,
                     entry_type,
                     content: text.clone(),
@@ -167,6 +188,7 @@ impl AmpContentItem {
                 })
             }
             AmpContentItem::Thinking { thinking } => Some(NormalizedEntry {
+                sequence: 0,
                 timestamp,
                 entry_type: NormalizedEntryType::Thinking,
                 content: thinking.clone(),
@@ -178,6 +200,7 @@ impl AmpContentItem {
                     executor.generate_concise_content(name, input, &action_type, worktree_path);
 
                 Some(NormalizedEntry {
+                    sequence: 0,
                     timestamp,
                     entry_type: NormalizedEntryType::ToolUse {
                         tool_name: name.clone(),
@@ -226,6 +249,8 @@ Task title: {}"#,
             )
         };
 
+        let prompt = with_project_preamble(pool, task.project_id, &prompt).await;
+
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
         // --format=jsonl is deprecated in latest versions of Amp CLI
@@ -241,6 +266,8 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(amp_command);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -279,6 +306,7 @@ Task title: {}"#,
                 Err(_) => {
                     // If line isn't valid JSON, add it as raw text
                     entries.push(NormalizedEntry {
+                        sequence: 0,
                         timestamp: None,
                         entry_type: NormalizedEntryType::SystemMessage,
                         content: format!("Raw output: {}", trimmed),
@@ -302,11 +330,12 @@ Task title: {}"#,
             }
         }
 
+        let prompt = NormalizedConversation::first_user_message(&entries);
         Ok(NormalizedConversation {
             entries,
             session_id,
             executor_type: "amp".to_string(),
-            prompt: None,
+            prompt,
             summary: None,
         })
     }
@@ -618,6 +647,8 @@ impl Executor for AmpFollowupExecutor {
             .arg(shell_arg)
             .arg(&amp_command);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
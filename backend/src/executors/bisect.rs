@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::{
+    executor::{Executor, ExecutorError},
+    models::task::Task,
+    utils::shell::get_shell_command,
+};
+
+/// Exit code of a run whose command already passes at the bad commit
+pub const BAD_COMMIT_PASSES_EXIT_CODE: i64 = 90;
+/// Exit code of a run whose command already fails at the good commit
+pub const GOOD_COMMIT_FAILS_EXIT_CODE: i64 = 91;
+
+/// Checks both ends of the range first, since `git bisect run` trusts them and would blame
+/// the bad commit for a command that never fails. Exits with the codes above when they're
+/// wrong.
+const BISECT_SCRIPT: &str = r#"
+run_test() { "$CODECOMMAND_BISECT_SHELL" -c "$CODECOMMAND_BISECT_COMMAND"; }
+
+echo "Checking that the test command fails at $CODECOMMAND_BISECT_BAD"
+git checkout --quiet --detach "$CODECOMMAND_BISECT_BAD" || exit 1
+if run_test; then
+    echo "The test command passes at $CODECOMMAND_BISECT_BAD, so there is nothing to bisect"
+    exit 90
+fi
+
+echo "Checking that the test command passes at $CODECOMMAND_BISECT_GOOD"
+git checkout --quiet --detach "$CODECOMMAND_BISECT_GOOD" || exit 1
+if ! run_test; then
+    echo "The test command already fails at $CODECOMMAND_BISECT_GOOD"
+    exit 91
+fi
+
+git bisect start "$CODECOMMAND_BISECT_BAD" "$CODECOMMAND_BISECT_GOOD" || exit 1
+git bisect run "$CODECOMMAND_BISECT_SHELL" -c "$CODECOMMAND_BISECT_COMMAND"
+"#;
+
+/// Executor running `git bisect` between two commits, with a command as the predicate. It
+/// checks out commits, so it runs in a worktree of its own.
+pub struct BisectExecutor {
+    pub good_commit: String,
+    pub bad_commit: String,
+    pub command: String,
+}
+
+#[async_trait]
+impl Executor for BisectExecutor {
+    async fn spawn(
+        &self,
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        let task = Task::find_by_id(pool, task_id)
+            .await?
+            .ok_or(ExecutorError::TaskNotFound)?;
+
+        let (shell_cmd, _) = get_shell_command();
+        let mut command = Command::new("sh");
+        command
+            .kill_on_drop(true)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .arg("-c")
+            .arg(BISECT_SCRIPT)
+            .env("CODECOMMAND_BISECT_SHELL", shell_cmd)
+            .env("CODECOMMAND_BISECT_COMMAND", &self.command)
+            .env("CODECOMMAND_BISECT_GOOD", &self.good_commit)
+            .env("CODECOMMAND_BISECT_BAD", &self.bad_commit)
+            .current_dir(worktree_path);
+
+        crate::executor::apply_attempt_env(&mut command);
+
+        let child = command.group_spawn().map_err(|e| {
+            crate::executor::SpawnContext::from_command(&command, "Bisect")
+                .with_task(task_id, Some(task.title.clone()))
+                .with_context("Bisect run")
+                .spawn_error(e)
+        })?;
+
+        Ok(child)
+    }
+}
@@ -7,20 +7,106 @@ use uuid::Uuid;
 
 use crate::{
     executor::{
-        ActionType, Executor, ExecutorError, NormalizedConversation, NormalizedEntry,
-        NormalizedEntryType,
+        with_project_preamble, ActionType, Executor, ExecutorConfig, ExecutorError, LogNormalizer,
+        NormalizedConversation, NormalizedEntry, NormalizedEntryType, OutputFormat,
+    },
+    executors::registry::{ExecutorFactory, FollowUp},
+    models::{
+        config::{ClaudeSettings, Config},
+        task::Task,
+    },
+    utils::{
+        executable::ExecutableLookup,
+        shell::{get_shell_command, shell_quote},
     },
-    models::task::Task,
-    utils::shell::get_shell_command,
 };
 
 /// An executor that uses Claude CLI to process tasks
-pub struct ClaudeExecutor;
+#[derive(Default)]
+pub struct ClaudeExecutor {
+    pub output_format: OutputFormat,
+}
 
 /// An executor that resumes a Claude session
 pub struct ClaudeFollowupExecutor {
     pub session_id: String,
     pub prompt: String,
+    pub output_format: OutputFormat,
+}
+
+pub struct ClaudeFactory;
+
+impl ExecutorFactory for ClaudeFactory {
+    fn display_name(&self) -> &'static str {
+        "Claude"
+    }
+
+    fn create(&self, output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(ClaudeExecutor { output_format })
+    }
+
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(Box::new(ClaudeFollowupExecutor {
+            session_id: follow_up.session_id?,
+            prompt: follow_up.prompt,
+            output_format: follow_up.output_format,
+        }))
+    }
+
+    fn supports_text_output(&self) -> bool {
+        true
+    }
+}
+
+/// The Claude CLI command line as configured, or with the defaults when the config can't be
+/// read
+fn configured_command(output_format: OutputFormat, session_id: Option<&str>) -> String {
+    let config = Config::read(&crate::utils::config_path()).unwrap_or_default();
+    let cli = claude_cli(&config, &ExecutableLookup::from_env());
+    claude_command(&cli, &config.claude, output_format, session_id)
+}
+
+/// How the Claude CLI is started: the configured or installed `claude`, else the configured
+/// package version through npx. A pinned version skips the installed `claude`.
+fn claude_cli(config: &Config, lookup: &ExecutableLookup) -> String {
+    let pinned_version = config.claude.pinned_version();
+    let npx_command = format!(
+        "npx -y {}",
+        shell_quote(&format!(
+            "@anthropic-ai/claude-code@{}",
+            pinned_version.unwrap_or("latest")
+        ))
+    );
+    match config.executable_path(&ExecutorConfig::Claude) {
+        None if pinned_version.is_some() => npx_command,
+        configured => lookup.cli_command(configured, "claude", &npx_command),
+    }
+}
+
+/// The Claude CLI command line, resuming `session_id` when given. The prompt is passed via
+/// stdin instead of the command line to avoid shell escaping issues.
+fn claude_command(
+    cli: &str,
+    settings: &ClaudeSettings,
+    output_format: OutputFormat,
+    session_id: Option<&str>,
+) -> String {
+    let mut command = format!("{} -p", cli);
+    if settings.skip_permissions {
+        command.push_str(" --dangerously-skip-permissions");
+    }
+    command.push_str(" --verbose");
+    let model = settings.model.as_deref().map(str::trim);
+    if let Some(model) = model.filter(|model| !model.is_empty()) {
+        command.push_str(&format!(" --model {}", shell_quote(model)));
+    }
+    if output_format == OutputFormat::Json {
+        command.push_str(" --output-format=stream-json");
+    }
+    if let Some(session_id) = session_id {
+        command.push_str(&format!(" --resume={}", session_id));
+    }
+    command
 }
 
 #[async_trait]
@@ -53,10 +139,11 @@ Task title: {}"#,
             )
         };
 
+        let prompt = with_project_preamble(pool, task.project_id, &prompt).await;
+
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
-        // Pass prompt via stdin instead of command line to avoid shell escaping issues
-        let claude_command = "npx -y @anthropic-ai/claude-code@latest -p --dangerously-skip-permissions --verbose --output-format=stream-json";
+        let claude_command = configured_command(self.output_format, None);
 
         let mut command = Command::new(shell_cmd);
         command
@@ -66,9 +153,11 @@ Task title: {}"#,
             .stderr(std::process::Stdio::piped())
             .current_dir(worktree_path)
             .arg(shell_arg)
-            .arg(claude_command)
+            .arg(&claude_command)
             .env("NODE_NO_WARNINGS", "1");
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -108,179 +197,235 @@ Task title: {}"#,
         logs: &str,
         worktree_path: &str,
     ) -> Result<NormalizedConversation, String> {
+        let mut normalizer = ClaudeLogNormalizer::new(self.output_format, worktree_path);
+        let entries: Vec<_> = logs
+            .lines()
+            .flat_map(|line| normalizer.normalize_line(line))
+            .collect();
+
+        let prompt = NormalizedConversation::first_user_message(&entries);
+        Ok(NormalizedConversation {
+            entries,
+            session_id: normalizer.session_id(),
+            executor_type: "claude".to_string(),
+            prompt,
+            summary: None,
+        })
+    }
+
+    fn log_normalizer(&self, worktree_path: &str) -> Option<Box<dyn LogNormalizer>> {
+        Some(Box::new(ClaudeLogNormalizer::new(
+            self.output_format,
+            worktree_path,
+        )))
+    }
+}
+
+/// Claude's output normalized line by line as it arrives. Stream JSON has one message per
+/// line, and only the session id carries over from earlier lines.
+pub struct ClaudeLogNormalizer {
+    executor: ClaudeExecutor,
+    worktree_path: String,
+    session_id: Option<String>,
+}
+
+impl ClaudeLogNormalizer {
+    pub fn new(output_format: OutputFormat, worktree_path: &str) -> Self {
+        Self {
+            executor: ClaudeExecutor { output_format },
+            worktree_path: worktree_path.to_string(),
+            session_id: None,
+        }
+    }
+
+    fn normalize_json_line(&mut self, trimmed: &str) -> Vec<NormalizedEntry> {
         use serde_json::Value;
 
         let mut entries = Vec::new();
-        let mut session_id = None;
 
-        for line in logs.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
+        // Try to parse as JSON
+        let json: Value = match serde_json::from_str(trimmed) {
+            Ok(json) => json,
+            Err(_) => {
+                // If line isn't valid JSON, add it as raw text
+                entries.push(NormalizedEntry {
+                    sequence: 0,
+                    timestamp: None,
+                    entry_type: NormalizedEntryType::SystemMessage,
+                    content: format!("Raw output: {}", trimmed),
+                    metadata: None,
+                });
+                return entries;
             }
+        };
 
-            // Try to parse as JSON
-            let json: Value = match serde_json::from_str(trimmed) {
-                Ok(json) => json,
-                Err(_) => {
-                    // If line isn't valid JSON, add it as raw text
-                    entries.push(NormalizedEntry {
-                        timestamp: None,
-                        entry_type: NormalizedEntryType::SystemMessage,
-                        content: format!("Raw output: {}", trimmed),
-                        metadata: None,
-                    });
-                    continue;
-                }
-            };
-
-            // Extract session ID
-            if session_id.is_none() {
-                if let Some(sess_id) = json.get("session_id").and_then(|v| v.as_str()) {
-                    session_id = Some(sess_id.to_string());
-                }
+        // Extract session ID
+        if self.session_id.is_none() {
+            if let Some(sess_id) = json.get("session_id").and_then(|v| v.as_str()) {
+                self.session_id = Some(sess_id.to_string());
             }
+        }
 
-            // Process different message types
-            let processed = if let Some(msg_type) = json.get("type").and_then(|t| t.as_str()) {
-                match msg_type {
-                    "assistant" => {
-                        if let Some(message) = json.get("message") {
-                            if let Some(content) = message.get("content").and_then(|c| c.as_array())
-                            {
-                                for content_item in content {
-                                    if let Some(content_type) =
-                                        content_item.get("type").and_then(|t| t.as_str())
-                                    {
-                                        match content_type {
-                                            "text" => {
-                                                if let Some(text) = content_item
-                                                    .get("text")
-                                                    .and_then(|t| t.as_str())
-                                                {
-                                                    entries.push(NormalizedEntry {
-                                                        timestamp: None,
-                                                        entry_type:
-                                                            NormalizedEntryType::AssistantMessage,
-                                                        content: text.to_string(),
-                                                        metadata: Some(content_item.clone()),
-                                                    });
-                                                }
+        // Process different message types
+        let processed = if let Some(msg_type) = json.get("type").and_then(|t| t.as_str()) {
+            match msg_type {
+                "assistant" => {
+                    if let Some(message) = json.get("message") {
+                        if let Some(content) = message.get("content").and_then(|c| c.as_array()) {
+                            for content_item in content {
+                                if let Some(content_type) =
+                                    content_item.get("type").and_then(|t| t.as_str())
+                                {
+                                    match content_type {
+                                        "text" => {
+                                            if let Some(text) =
+                                                content_item.get("text").and_then(|t| t.as_str())
+                                            {
+                                                entries.push(NormalizedEntry {
+                                                    sequence: 0,
+                                                    timestamp: None,
+                                                    entry_type:
+                                                        NormalizedEntryType::AssistantMessage,
+                                                    content: text.to_string(),
+                                                    metadata: Some(content_item.clone()),
+                                                });
                                             }
-                                            "tool_use" => {
-                                                if let Some(tool_name) = content_item
-                                                    .get("name")
-                                                    .and_then(|n| n.as_str())
-                                                {
-                                                    let input = content_item
-                                                        .get("input")
-                                                        .unwrap_or(&Value::Null);
-                                                    let action_type = self.extract_action_type(
+                                        }
+                                        "tool_use" => {
+                                            if let Some(tool_name) =
+                                                content_item.get("name").and_then(|n| n.as_str())
+                                            {
+                                                let input = content_item
+                                                    .get("input")
+                                                    .unwrap_or(&Value::Null);
+                                                let action_type =
+                                                    self.executor.extract_action_type(
                                                         tool_name,
                                                         input,
-                                                        worktree_path,
+                                                        &self.worktree_path,
                                                     );
-                                                    let content = self.generate_concise_content(
+                                                let content =
+                                                    self.executor.generate_concise_content(
                                                         tool_name,
                                                         input,
                                                         &action_type,
-                                                        worktree_path,
+                                                        &self.worktree_path,
                                                     );
 
-                                                    entries.push(NormalizedEntry {
-                                                        timestamp: None,
-                                                        entry_type: NormalizedEntryType::ToolUse {
-                                                            tool_name: tool_name.to_string(),
-                                                            action_type,
-                                                        },
-                                                        content,
-                                                        metadata: Some(content_item.clone()),
-                                                    });
-                                                }
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        true
-                    }
-                    "user" => {
-                        if let Some(message) = json.get("message") {
-                            if let Some(content) = message.get("content").and_then(|c| c.as_array())
-                            {
-                                for content_item in content {
-                                    if let Some(content_type) =
-                                        content_item.get("type").and_then(|t| t.as_str())
-                                    {
-                                        if content_type == "text" {
-                                            if let Some(text) =
-                                                content_item.get("text").and_then(|t| t.as_str())
-                                            {
                                                 entries.push(NormalizedEntry {
+                                                    sequence: 0,
                                                     timestamp: None,
-                                                    entry_type: NormalizedEntryType::UserMessage,
-                                                    content: text.to_string(),
+                                                    entry_type: NormalizedEntryType::ToolUse {
+                                                        tool_name: tool_name.to_string(),
+                                                        action_type,
+                                                    },
+                                                    content,
                                                     metadata: Some(content_item.clone()),
                                                 });
                                             }
                                         }
+                                        _ => {}
                                     }
                                 }
                             }
                         }
-                        true
                     }
-                    "system" => {
-                        if let Some(subtype) = json.get("subtype").and_then(|s| s.as_str()) {
-                            if subtype == "init" {
-                                entries.push(NormalizedEntry {
-                                    timestamp: None,
-                                    entry_type: NormalizedEntryType::SystemMessage,
-                                    content: format!(
-                                        "System initialized with model: {}",
-                                        json.get("model")
-                                            .and_then(|m| m.as_str())
-                                            .unwrap_or("unknown")
-                                    ),
-                                    metadata: Some(json.clone()),
-                                });
+                    true
+                }
+                "user" => {
+                    if let Some(message) = json.get("message") {
+                        if let Some(content) = message.get("content").and_then(|c| c.as_array()) {
+                            for content_item in content {
+                                if let Some(content_type) =
+                                    content_item.get("type").and_then(|t| t.as_str())
+                                {
+                                    if content_type == "text" {
+                                        if let Some(text) =
+                                            content_item.get("text").and_then(|t| t.as_str())
+                                        {
+                                            entries.push(NormalizedEntry {
+                                                sequence: 0,
+                                                timestamp: None,
+                                                entry_type: NormalizedEntryType::UserMessage,
+                                                content: text.to_string(),
+                                                metadata: Some(content_item.clone()),
+                                            });
+                                        }
+                                    }
+                                }
                             }
                         }
-                        true
                     }
-                    _ => false,
+                    true
                 }
-            } else {
-                false
-            };
-
-            // If JSON didn't match expected patterns, add it as unrecognized JSON
-            // Skip JSON with type "result" as requested
-            if !processed {
-                if let Some(msg_type) = json.get("type").and_then(|t| t.as_str()) {
-                    if msg_type == "result" {
-                        // Skip result entries
-                        continue;
+                "system" => {
+                    if let Some(subtype) = json.get("subtype").and_then(|s| s.as_str()) {
+                        if subtype == "init" {
+                            entries.push(NormalizedEntry {
+                                sequence: 0,
+                                timestamp: None,
+                                entry_type: NormalizedEntryType::SystemMessage,
+                                content: format!(
+                                    "System initialized with model: {}",
+                                    json.get("model")
+                                        .and_then(|m| m.as_str())
+                                        .unwrap_or("unknown")
+                                ),
+                                metadata: Some(json.clone()),
+                            });
+                        }
                     }
+                    true
+                }
+                _ => false,
+            }
+        } else {
+            false
+        };
+
+        // If JSON didn't match expected patterns, add it as unrecognized JSON
+        // Skip JSON with type "result" as requested
+        if !processed {
+            if let Some(msg_type) = json.get("type").and_then(|t| t.as_str()) {
+                if msg_type == "result" {
+                    // Skip result entries
+                    return entries;
                 }
-                entries.push(NormalizedEntry {
-                    timestamp: None,
-                    entry_type: NormalizedEntryType::SystemMessage,
-                    content: format!("Unrecognized JSON: {}", trimmed),
-                    metadata: Some(json),
-                });
             }
+            entries.push(NormalizedEntry {
+                sequence: 0,
+                timestamp: None,
+                entry_type: NormalizedEntryType::SystemMessage,
+                content: format!("Unrecognized JSON: {}", trimmed),
+                metadata: Some(json),
+            });
         }
 
-        Ok(NormalizedConversation {
-            entries,
-            session_id,
-            executor_type: "claude".to_string(),
-            prompt: None,
-            summary: None,
-        })
+        entries
+    }
+}
+
+impl LogNormalizer for ClaudeLogNormalizer {
+    fn normalize_line(&mut self, line: &str) -> Vec<NormalizedEntry> {
+        if line.trim().is_empty() {
+            return Vec::new();
+        }
+        // Plain text output has no structure to parse: every line is taken as something the
+        // assistant said, and there's no session to resume
+        if self.executor.output_format == OutputFormat::Text {
+            return vec![NormalizedEntry {
+                sequence: 0,
+                timestamp: None,
+                entry_type: NormalizedEntryType::AssistantMessage,
+                content: line.trim_end().to_string(),
+                metadata: None,
+            }];
+        }
+        self.normalize_json_line(line.trim())
+    }
+
+    fn session_id(&self) -> Option<String> {
+        self.session_id.clone()
     }
 }
 
@@ -507,11 +652,8 @@ impl Executor for ClaudeFollowupExecutor {
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
-        // Pass prompt via stdin instead of command line to avoid shell escaping issues
-        let claude_command = format!(
-            "npx -y @anthropic-ai/claude-code@latest -p --dangerously-skip-permissions --verbose --output-format=stream-json --resume={}",
-            self.session_id
-        );
+        // Same settings as the session started with, unless they were changed since
+        let claude_command = configured_command(self.output_format, Some(&self.session_id));
 
         let mut command = Command::new(shell_cmd);
         command
@@ -523,6 +665,8 @@ impl Executor for ClaudeFollowupExecutor {
             .arg(shell_arg)
             .arg(&claude_command);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -569,9 +713,18 @@ impl Executor for ClaudeFollowupExecutor {
         worktree_path: &str,
     ) -> Result<NormalizedConversation, String> {
         // Reuse the same logic as the main ClaudeExecutor
-        let main_executor = ClaudeExecutor;
+        let main_executor = ClaudeExecutor {
+            output_format: self.output_format,
+        };
         main_executor.normalize_logs(logs, worktree_path)
     }
+
+    fn log_normalizer(&self, worktree_path: &str) -> Option<Box<dyn LogNormalizer>> {
+        Some(Box::new(ClaudeLogNormalizer::new(
+            self.output_format,
+            worktree_path,
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -580,7 +733,7 @@ mod tests {
 
     #[test]
     fn test_normalize_logs_ignores_result_type() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
         let logs = r#"{"type":"system","subtype":"init","cwd":"/private/tmp","session_id":"e988eeea-3712-46a1-82d4-84fbfaa69114","tools":[],"model":"claude-sonnet-4-20250514"}
 {"type":"assistant","message":{"id":"msg_123","type":"message","role":"assistant","model":"claude-sonnet-4-20250514","content":[{"type":"text","text":"Hello world"}],"stop_reason":null},"session_id":"e988eeea-3712-46a1-82d4-84fbfaa69114"}
 {"type":"result","subtype":"success","is_error":false,"duration_ms":6059,"result":"Final result"}
@@ -604,9 +757,175 @@ mod tests {
             .any(|e| e.content.contains("Unrecognized JSON")));
     }
 
+    #[test]
+    fn test_normalize_logs_takes_prompt_from_first_user_message() {
+        let executor = ClaudeExecutor::default();
+        let logs = r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Ready"}]},"session_id":"abc"}
+{"type":"user","message":{"role":"user","content":[{"type":"text","text":"Fix the login form"}]},"session_id":"abc"}
+{"type":"user","message":{"role":"user","content":[{"type":"text","text":"Also add a test"}]},"session_id":"abc"}"#;
+
+        let result = executor.normalize_logs(logs, "/tmp/test-worktree").unwrap();
+        assert_eq!(result.prompt.as_deref(), Some("Fix the login form"));
+
+        let without_user = executor
+            .normalize_logs(
+                r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Hi"}]}}"#,
+                "/tmp/test-worktree",
+            )
+            .unwrap();
+        assert_eq!(without_user.prompt, None);
+    }
+
+    /// A lookup that finds nothing but what's in `dir`
+    fn lookup_in(dir: &tempfile::TempDir) -> ExecutableLookup {
+        ExecutableLookup::new(Some(dir.path().as_os_str().to_os_string()), false, None)
+    }
+
+    #[test]
+    fn test_command_follows_settings_for_new_and_resumed_sessions() {
+        let empty = tempfile::TempDir::new().unwrap();
+        let command = |config: &Config, session_id: Option<&str>| {
+            let cli = claude_cli(config, &lookup_in(&empty));
+            claude_command(&cli, &config.claude, OutputFormat::Json, session_id)
+        };
+        let package =
+            |version: &str| shell_quote(&format!("@anthropic-ai/claude-code@{}", version));
+
+        let mut config = Config::default();
+        let default = command(&config, None);
+        assert!(default.starts_with(&format!("npx -y {} -p", package("latest"))));
+        assert!(default.contains(" --dangerously-skip-permissions"));
+        assert!(!default.contains("--model"));
+
+        config.claude = ClaudeSettings {
+            package_version: "1.0.60".to_string(),
+            model: Some("claude-sonnet-4-20250514".to_string()),
+            skip_permissions: false,
+        };
+        for session_id in [None, Some("abc")] {
+            let command = command(&config, session_id);
+            assert!(command.starts_with(&format!("npx -y {} -p", package("1.0.60"))));
+            assert!(command.contains(&format!(
+                " --model {}",
+                shell_quote("claude-sonnet-4-20250514")
+            )));
+            assert!(!command.contains("--dangerously-skip-permissions"));
+        }
+
+        // A blank version or model falls back to the defaults
+        config.claude = ClaudeSettings {
+            package_version: " ".to_string(),
+            model: Some(String::new()),
+            skip_permissions: true,
+        };
+        assert_eq!(command(&config, None), default);
+    }
+
+    #[test]
+    fn test_installed_cli_is_preferred_over_npx() {
+        let empty = tempfile::TempDir::new().unwrap();
+        let installed = tempfile::TempDir::new().unwrap();
+        let program = if cfg!(windows) {
+            "claude.exe"
+        } else {
+            "claude"
+        };
+        std::fs::write(installed.path().join(program), "").unwrap();
+        let mut config = Config::default();
+
+        assert!(claude_cli(&config, &lookup_in(&empty)).starts_with("npx -y "));
+        assert_eq!(claude_cli(&config, &lookup_in(&installed)), "claude");
+
+        // A configured path wins over both, whether or not `claude` is on PATH
+        config.executable_paths.insert(
+            "claude".to_string(),
+            "/opt/claude code/bin/claude".to_string(),
+        );
+        for lookup in [lookup_in(&empty), lookup_in(&installed)] {
+            assert_eq!(
+                claude_cli(&config, &lookup),
+                shell_quote("/opt/claude code/bin/claude")
+            );
+        }
+        // Installed CLIs are run with the same arguments
+        let command = claude_command(
+            &claude_cli(&config, &lookup_in(&empty)),
+            &config.claude,
+            OutputFormat::Json,
+            Some("abc"),
+        );
+        assert!(command.ends_with(
+            " -p --dangerously-skip-permissions --verbose --output-format=stream-json --resume=abc"
+        ));
+    }
+
+    #[test]
+    fn test_pinned_version_runs_through_npx_even_when_installed() {
+        let installed = tempfile::TempDir::new().unwrap();
+        let program = if cfg!(windows) {
+            "claude.exe"
+        } else {
+            "claude"
+        };
+        std::fs::write(installed.path().join(program), "").unwrap();
+        let mut config = Config::default();
+
+        for version in ["latest", " "] {
+            config.claude.package_version = version.to_string();
+            assert_eq!(claude_cli(&config, &lookup_in(&installed)), "claude");
+        }
+        config.claude.package_version = "1.0.60".to_string();
+        assert_eq!(
+            claude_cli(&config, &lookup_in(&installed)),
+            format!("npx -y {}", shell_quote("@anthropic-ai/claude-code@1.0.60"))
+        );
+
+        // A configured path is still run as is
+        config.executable_paths.insert(
+            "claude".to_string(),
+            "/opt/claude code/bin/claude".to_string(),
+        );
+        assert_eq!(
+            claude_cli(&config, &lookup_in(&installed)),
+            shell_quote("/opt/claude code/bin/claude")
+        );
+    }
+
+    #[test]
+    fn test_text_output_omits_json_flag_and_parses_lines_as_messages() {
+        let settings = ClaudeSettings::default();
+        let json = claude_command("claude", &settings, OutputFormat::Json, None);
+        assert!(json.contains("--output-format=stream-json"));
+
+        let text = claude_command("claude", &settings, OutputFormat::Text, Some("abc"));
+        assert!(!text.contains("--output-format"));
+        assert!(text.ends_with("--resume=abc"));
+
+        let executor = ClaudeExecutor {
+            output_format: OutputFormat::Text,
+        };
+        let logs = "I'll fix the login form.\n\n{\"type\":\"assistant\"}\nDone.\n";
+        let result = executor.normalize_logs(logs, "/tmp/test-worktree").unwrap();
+
+        let contents: Vec<_> = result.entries.iter().map(|e| e.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "I'll fix the login form.",
+                "{\"type\":\"assistant\"}",
+                "Done."
+            ]
+        );
+        assert!(result
+            .entries
+            .iter()
+            .all(|e| matches!(e.entry_type, NormalizedEntryType::AssistantMessage)));
+        assert_eq!(result.session_id, None);
+    }
+
     #[test]
     fn test_make_path_relative() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
 
         // Test with relative path (should remain unchanged)
         assert_eq!(
@@ -623,7 +942,7 @@ mod tests {
 
     #[test]
     fn test_todo_tool_content_extraction() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
 
         // Test TodoWrite with actual todo list
         let todo_input = serde_json::json!({
@@ -666,7 +985,7 @@ mod tests {
 
     #[test]
     fn test_todo_tool_empty_list() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
 
         // Test TodoWrite with empty todo list
         let empty_input = serde_json::json!({
@@ -687,7 +1006,7 @@ mod tests {
 
     #[test]
     fn test_todo_tool_no_todos_field() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
 
         // Test TodoWrite with no todos field
         let no_todos_input = serde_json::json!({
@@ -708,7 +1027,7 @@ mod tests {
 
     #[test]
     fn test_glob_tool_content_extraction() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
 
         // Test Glob with pattern and path
         let glob_input = serde_json::json!({
@@ -730,7 +1049,7 @@ mod tests {
 
     #[test]
     fn test_glob_tool_pattern_only() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
 
         // Test Glob with pattern only
         let glob_input = serde_json::json!({
@@ -751,7 +1070,7 @@ mod tests {
 
     #[test]
     fn test_ls_tool_content_extraction() {
-        let executor = ClaudeExecutor;
+        let executor = ClaudeExecutor::default();
 
         // Test LS with path
         let ls_input = serde_json::json!({
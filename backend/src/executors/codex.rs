@@ -0,0 +1,562 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use serde_json::Value;
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::{
+    executor::{
+        with_project_preamble, ActionType, Executor, ExecutorError, NormalizedConversation,
+        NormalizedEntry, NormalizedEntryType, OutputFormat, SpawnContext,
+    },
+    executors::registry::{ExecutorFactory, FollowUp},
+    models::task::Task,
+    utils::shell::get_shell_command,
+};
+
+/// An executor that uses the Codex CLI to process tasks
+pub struct CodexExecutor;
+
+/// An executor that resumes a Codex session
+pub struct CodexFollowupExecutor {
+    pub session_id: String,
+    pub prompt: String,
+}
+
+pub struct CodexFactory;
+
+impl ExecutorFactory for CodexFactory {
+    fn display_name(&self) -> &'static str {
+        "Codex"
+    }
+
+    fn create(&self, _output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(CodexExecutor)
+    }
+
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(Box::new(CodexFollowupExecutor {
+            session_id: follow_up.session_id?,
+            prompt: follow_up.prompt,
+        }))
+    }
+}
+
+/// The Codex CLI command line, resuming `session_id` when given. Codex reads the prompt from
+/// stdin when it isn't given one on the command line, which avoids shell escaping issues.
+fn codex_command(session_id: Option<&str>) -> String {
+    let mut command = "npx -y @openai/codex@latest exec --json --dangerously-bypass-approvals-and-sandbox --skip-git-repo-check".to_string();
+    if let Some(session_id) = session_id {
+        command.push_str(&format!(" resume {}", session_id));
+    }
+    command
+}
+
+/// Start Codex in `worktree_path` and write `prompt` to its stdin
+async fn spawn_codex(
+    prompt: &str,
+    session_id: Option<&str>,
+    worktree_path: &str,
+    context: impl Fn(SpawnContext) -> SpawnContext,
+) -> Result<AsyncGroupChild, ExecutorError> {
+    let (shell_cmd, shell_arg) = get_shell_command();
+    let mut command = Command::new(shell_cmd);
+    command
+        .kill_on_drop(true)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .current_dir(worktree_path)
+        .arg(shell_arg)
+        .arg(codex_command(session_id))
+        .env("NODE_NO_WARNINGS", "1");
+
+    crate::executor::apply_attempt_env(&mut command);
+
+    let mut child = command
+        .group_spawn() // Create new process group so we can kill entire tree
+        .map_err(|e| context(SpawnContext::from_command(&command, "Codex")).spawn_error(e))?;
+
+    if let Some(mut stdin) = child.inner().stdin.take() {
+        use tokio::io::AsyncWriteExt;
+        stdin.write_all(prompt.as_bytes()).await.map_err(|e| {
+            let context = context(SpawnContext::from_command(&command, "Codex"))
+                .with_context("Failed to write prompt to Codex CLI stdin");
+            ExecutorError::spawn_failed(e, context)
+        })?;
+        stdin.shutdown().await.map_err(|e| {
+            let context = context(SpawnContext::from_command(&command, "Codex"))
+                .with_context("Failed to close Codex CLI stdin");
+            ExecutorError::spawn_failed(e, context)
+        })?;
+    }
+
+    Ok(child)
+}
+
+#[async_trait]
+impl Executor for CodexExecutor {
+    async fn spawn(
+        &self,
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        // Get the task to fetch its description
+        let task = Task::find_by_id(pool, task_id)
+            .await?
+            .ok_or(ExecutorError::TaskNotFound)?;
+
+        let prompt = if let Some(task_description) = &task.description {
+            format!(
+                r#"project_id: {}
+
+Task title: {}
+Task description: {}"#,
+                task.project_id, task.title, task_description
+            )
+        } else {
+            format!(
+                r#"project_id: {}
+
+Task title: {}"#,
+                task.project_id, task.title
+            )
+        };
+
+        let prompt = with_project_preamble(pool, task.project_id, &prompt).await;
+        tracing::debug!(
+            "Writing prompt to Codex stdin for task {}: {:?}",
+            task_id,
+            prompt
+        );
+
+        spawn_codex(&prompt, None, worktree_path, |context| {
+            context
+                .with_task(task_id, Some(task.title.clone()))
+                .with_context("Codex CLI execution for new task")
+        })
+        .await
+    }
+
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        Ok(normalize_codex_logs(logs, worktree_path))
+    }
+}
+
+#[async_trait]
+impl Executor for CodexFollowupExecutor {
+    async fn spawn(
+        &self,
+        _pool: &sqlx::SqlitePool,
+        _task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        tracing::debug!(
+            "Writing prompt to Codex stdin for session {}: {:?}",
+            self.session_id,
+            self.prompt
+        );
+        spawn_codex(
+            &self.prompt,
+            Some(&self.session_id),
+            worktree_path,
+            |context| {
+                context.with_context(format!(
+                    "Codex CLI followup execution for session {}",
+                    self.session_id
+                ))
+            },
+        )
+        .await
+    }
+
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        Ok(normalize_codex_logs(logs, worktree_path))
+    }
+}
+
+/// Events that only report progress of something already shown, or bookkeeping
+const SKIPPED_EVENTS: &[&str] = &[
+    "task_started",
+    "task_complete",
+    "token_count",
+    "agent_message_delta",
+    "agent_reasoning_delta",
+    "exec_command_output_delta",
+    "exec_command_end",
+    "patch_apply_end",
+    "mcp_tool_call_end",
+    "web_search_end",
+    "turn_diff",
+];
+
+/// Convert absolute paths to relative paths based on worktree path, as Claude's parser does
+pub(crate) fn make_path_relative(path: &str, worktree_path: &str) -> String {
+    let path_obj = Path::new(path);
+
+    // If path is already relative, return as is
+    if path_obj.is_relative() {
+        return path.to_string();
+    }
+
+    // Try to make path relative to the worktree path
+    if let Ok(relative_path) = path_obj.strip_prefix(Path::new(worktree_path)) {
+        return relative_path.to_string_lossy().to_string();
+    }
+
+    // If we can't make it relative, return the original path
+    path.to_string()
+}
+
+/// The command a shell was asked to run, rather than the `bash -lc` wrapping it
+fn command_line(command: &Value) -> Option<String> {
+    let args: Vec<&str> = command
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    match args.as_slice() {
+        [shell, flag, script] if matches!(*flag, "-c" | "-lc") && shell.ends_with("sh") => {
+            Some(script.to_string())
+        }
+        [] => None,
+        args => Some(args.join(" ")),
+    }
+}
+
+/// What a shell command does, from the `parsed_cmd` Codex sends along with it. Only
+/// commands that do one thing Codex recognized get more than `CommandRun`.
+fn command_action(msg: &Value, command: String, worktree_path: &str) -> ActionType {
+    let parsed = match msg.get("parsed_cmd").and_then(Value::as_array) {
+        Some(parsed) if parsed.len() == 1 => &parsed[0],
+        _ => return ActionType::CommandRun { command },
+    };
+    let field = |key: &str| parsed.get(key).and_then(Value::as_str);
+
+    match parsed.get("type").and_then(Value::as_str) {
+        Some("read") => match field("path").or_else(|| field("name")) {
+            Some(path) => ActionType::FileRead {
+                path: make_path_relative(path, worktree_path),
+            },
+            None => ActionType::CommandRun { command },
+        },
+        Some("search") => match field("query") {
+            Some(query) => ActionType::Search {
+                query: query.to_string(),
+            },
+            None => ActionType::CommandRun { command },
+        },
+        _ => ActionType::CommandRun { command },
+    }
+}
+
+fn entry(entry_type: NormalizedEntryType, content: String, metadata: &Value) -> NormalizedEntry {
+    NormalizedEntry {
+        sequence: 0,
+        timestamp: None,
+        entry_type,
+        content,
+        metadata: Some(metadata.clone()),
+    }
+}
+
+/// Parse the JSONL `codex exec --json` prints: a summary of its config, the prompt, then an
+/// `{"id", "msg"}` object per event
+fn normalize_codex_logs(logs: &str, worktree_path: &str) -> NormalizedConversation {
+    let mut entries = Vec::new();
+    let mut session_id = None;
+
+    for line in logs.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let json: Value = match serde_json::from_str(trimmed) {
+            Ok(json) => json,
+            Err(_) => {
+                entries.push(NormalizedEntry {
+                    sequence: 0,
+                    timestamp: None,
+                    entry_type: NormalizedEntryType::SystemMessage,
+                    content: format!("Raw output: {}", trimmed),
+                    metadata: None,
+                });
+                continue;
+            }
+        };
+
+        let Some(msg) = json.get("msg") else {
+            if let Some(prompt) = json.get("prompt").and_then(Value::as_str) {
+                entries.push(entry(
+                    NormalizedEntryType::UserMessage,
+                    prompt.to_string(),
+                    &json,
+                ));
+            }
+            // Anything else before the events is the config summary
+            continue;
+        };
+        let text = |key: &str| msg.get(key).and_then(Value::as_str).map(str::to_string);
+
+        match msg.get("type").and_then(Value::as_str).unwrap_or_default() {
+            "session_configured" => {
+                session_id = session_id.or_else(|| text("session_id"));
+                entries.push(entry(
+                    NormalizedEntryType::SystemMessage,
+                    format!(
+                        "System initialized with model: {}",
+                        text("model").as_deref().unwrap_or("unknown")
+                    ),
+                    msg,
+                ));
+            }
+            "agent_message" => {
+                if let Some(message) = text("message") {
+                    entries.push(entry(NormalizedEntryType::AssistantMessage, message, msg));
+                }
+            }
+            "agent_reasoning" => {
+                if let Some(reasoning) = text("text") {
+                    entries.push(entry(NormalizedEntryType::Thinking, reasoning, msg));
+                }
+            }
+            "exec_command_begin" => {
+                if let Some(command) = msg.get("command").and_then(command_line) {
+                    let content = format!("`{}`", command);
+                    entries.push(entry(
+                        NormalizedEntryType::ToolUse {
+                            tool_name: "exec_command".to_string(),
+                            action_type: command_action(msg, command, worktree_path),
+                        },
+                        content,
+                        msg,
+                    ));
+                }
+            }
+            "mcp_tool_call_begin" => {
+                let invocation = msg.get("invocation").unwrap_or(msg);
+                let name = |key: &str| invocation.get(key).and_then(Value::as_str);
+                if let Some(tool) = name("tool") {
+                    let tool_name = match name("server") {
+                        Some(server) => format!("{}.{}", server, tool),
+                        None => tool.to_string(),
+                    };
+                    entries.push(entry(
+                        NormalizedEntryType::ToolUse {
+                            tool_name: tool_name.clone(),
+                            action_type: ActionType::Other {
+                                description: format!("MCP tool {}", tool_name),
+                            },
+                        },
+                        format!("`{}`", tool_name),
+                        msg,
+                    ));
+                }
+            }
+            "web_search_begin" => {
+                if let Some(query) = text("query") {
+                    entries.push(entry(
+                        NormalizedEntryType::ToolUse {
+                            tool_name: "web_search".to_string(),
+                            action_type: ActionType::Search {
+                                query: query.clone(),
+                            },
+                        },
+                        format!("`{}`", query),
+                        msg,
+                    ));
+                }
+            }
+            "patch_apply_begin" => {
+                let paths = msg
+                    .get("changes")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flat_map(|changes| changes.keys());
+                for path in paths {
+                    let path = make_path_relative(path, worktree_path);
+                    entries.push(entry(
+                        NormalizedEntryType::ToolUse {
+                            tool_name: "apply_patch".to_string(),
+                            action_type: ActionType::FileWrite { path: path.clone() },
+                        },
+                        format!("`{}`", path),
+                        msg,
+                    ));
+                }
+            }
+            "error" | "stream_error" => {
+                entries.push(entry(
+                    NormalizedEntryType::ErrorMessage,
+                    text("message").unwrap_or_else(|| trimmed.to_string()),
+                    msg,
+                ));
+            }
+            event if SKIPPED_EVENTS.contains(&event) => {}
+            _ => entries.push(entry(
+                NormalizedEntryType::SystemMessage,
+                format!("Unrecognized JSON: {}", trimmed),
+                &json,
+            )),
+        }
+    }
+
+    let prompt = NormalizedConversation::first_user_message(&entries);
+    NormalizedConversation {
+        entries,
+        session_id,
+        executor_type: "codex".to_string(),
+        prompt,
+        summary: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output of `codex exec --json` adding a test and running it
+    const CODEX_LOGS: &str = r#"{"model":"codex-mini-latest","provider":"openai","approval":"never","sandbox":"danger-full-access","workdir":"/tmp/test-worktree","reasoning effort":"medium"}
+{"prompt":"Add a test for parse_port"}
+{"id":"0","msg":{"type":"session_configured","session_id":"0198a4c2-5e8f-7d31-9b1e-2c4f6a8d0e13","model":"codex-mini-latest","history_log_id":0,"history_entry_count":0}}
+{"id":"1","msg":{"type":"task_started"}}
+{"id":"1","msg":{"type":"agent_reasoning","text":"**Looking for parse_port**"}}
+{"id":"1","msg":{"type":"exec_command_begin","call_id":"call_1","command":["bash","-lc","rg -n parse_port src"],"cwd":"/tmp/test-worktree"}}
+{"id":"1","msg":{"type":"exec_command_end","call_id":"call_1","stdout":"src/config.rs:12:pub fn parse_port","stderr":"","exit_code":0}}
+{"id":"1","msg":{"type":"patch_apply_begin","call_id":"call_2","auto_approved":true,"changes":{"/tmp/test-worktree/src/config.rs":{"update":{"unified_diff":"@@ -30 +30,6 @@\n+#[test]\n","move_path":null}}}}}
+{"id":"1","msg":{"type":"patch_apply_end","call_id":"call_2","stdout":"Success. Updated the following files:\nM src/config.rs\n","stderr":"","success":true}}
+{"id":"1","msg":{"type":"exec_command_begin","call_id":"call_3","command":["cargo","test","parse_port"],"cwd":"/tmp/test-worktree"}}
+{"id":"1","msg":{"type":"agent_message","message":"Added a test covering out of range ports."}}
+{"id":"1","msg":{"type":"token_count","input_tokens":5120,"output_tokens":240,"total_tokens":5360}}
+{"id":"1","msg":{"type":"task_complete","last_agent_message":"Added a test covering out of range ports."}}"#;
+
+    #[test]
+    fn test_normalize_codex_commands_patches_and_messages() {
+        let result = CodexExecutor
+            .normalize_logs(CODEX_LOGS, "/tmp/test-worktree")
+            .unwrap();
+
+        assert_eq!(result.executor_type, "codex");
+        assert_eq!(
+            result.session_id.as_deref(),
+            Some("0198a4c2-5e8f-7d31-9b1e-2c4f6a8d0e13")
+        );
+        assert_eq!(result.prompt.as_deref(), Some("Add a test for parse_port"));
+
+        let entries = &result.entries;
+        assert_eq!(entries.len(), 7);
+        assert!(matches!(
+            entries[1].entry_type,
+            NormalizedEntryType::SystemMessage
+        ));
+        assert!(matches!(
+            entries[2].entry_type,
+            NormalizedEntryType::Thinking
+        ));
+        assert!(matches!(
+            entries[3].entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::CommandRun { ref command },
+                ..
+            } if command == "rg -n parse_port src"
+        ));
+        assert!(matches!(
+            entries[4].entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileWrite { ref path },
+                ..
+            } if path == "src/config.rs"
+        ));
+        assert!(matches!(
+            entries[5].entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::CommandRun { ref command },
+                ..
+            } if command == "cargo test parse_port"
+        ));
+        assert!(matches!(
+            entries[6].entry_type,
+            NormalizedEntryType::AssistantMessage
+        ));
+        assert_eq!(
+            entries[6].content,
+            "Added a test covering out of range ports."
+        );
+    }
+
+    #[test]
+    fn test_tool_calls_map_to_their_actions() {
+        let logs = r#"{"id":"1","msg":{"type":"exec_command_begin","call_id":"call_1","command":["bash","-lc","sed -n '1,80p' src/config.rs"],"cwd":"/tmp/test-worktree","parsed_cmd":[{"type":"read","cmd":"sed -n '1,80p' src/config.rs","name":"config.rs","path":"/tmp/test-worktree/src/config.rs"}]}}
+{"id":"1","msg":{"type":"exec_command_begin","call_id":"call_2","command":["bash","-lc","rg -n parse_port src"],"cwd":"/tmp/test-worktree","parsed_cmd":[{"type":"search","cmd":"rg -n parse_port src","query":"parse_port","path":"src"}]}}
+{"id":"1","msg":{"type":"exec_command_begin","call_id":"call_3","command":["bash","-lc","cat a.rs && cat b.rs"],"cwd":"/tmp/test-worktree","parsed_cmd":[{"type":"read","cmd":"cat a.rs","name":"a.rs"},{"type":"read","cmd":"cat b.rs","name":"b.rs"}]}}
+{"id":"1","msg":{"type":"mcp_tool_call_begin","call_id":"call_4","invocation":{"server":"codecommand","tool":"list_tasks","arguments":{"project_id":"p"}}}}
+{"id":"1","msg":{"type":"mcp_tool_call_end","call_id":"call_4","result":{"Ok":{"content":[]}}}}
+{"id":"1","msg":{"type":"web_search_begin","call_id":"call_5","query":"rust u16 parse overflow"}}"#;
+        let entries = CodexExecutor
+            .normalize_logs(logs, "/tmp/test-worktree")
+            .unwrap()
+            .entries;
+
+        let actions: Vec<_> = entries
+            .iter()
+            .map(|entry| match &entry.entry_type {
+                NormalizedEntryType::ToolUse { action_type, .. } => action_type.clone(),
+                other => panic!("expected a tool use, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ActionType::FileRead {
+                    path: "src/config.rs".to_string()
+                },
+                ActionType::Search {
+                    query: "parse_port".to_string()
+                },
+                ActionType::CommandRun {
+                    command: "cat a.rs && cat b.rs".to_string()
+                },
+                ActionType::Other {
+                    description: "MCP tool codecommand.list_tasks".to_string()
+                },
+                ActionType::Search {
+                    query: "rust u16 parse overflow".to_string()
+                },
+            ]
+        );
+        assert_eq!(entries[3].content, "`codecommand.list_tasks`");
+    }
+
+    #[test]
+    fn test_followups_resume_the_session() {
+        assert!(!codex_command(None).contains("resume"));
+        assert!(codex_command(Some("abc")).ends_with(" resume abc"));
+
+        let result = CodexFollowupExecutor {
+            session_id: "abc".to_string(),
+            prompt: "Also cover port 0".to_string(),
+        }
+        .normalize_logs(
+            "{\"id\":\"0\",\"msg\":{\"type\":\"error\",\"message\":\"stream disconnected\"}}\nnot json",
+            "/tmp/test-worktree",
+        )
+        .unwrap();
+        assert!(matches!(
+            result.entries[0].entry_type,
+            NormalizedEntryType::ErrorMessage
+        ));
+        assert_eq!(result.entries[1].content, "Raw output: not json");
+    }
+}
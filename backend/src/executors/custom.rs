@@ -0,0 +1,259 @@
+use async_trait::async_trait;
+use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::{
+    executor::{
+        with_project_preamble, Executor, ExecutorError, LogNormalizer, NormalizedConversation,
+        NormalizedEntry, NormalizedEntryType, OutputFormat,
+    },
+    executors::{
+        claude::{ClaudeExecutor, ClaudeLogNormalizer},
+        registry::{ExecutorFactory, FollowUp},
+    },
+    models::{config::Config, task::Task},
+    utils::shell::{get_shell_command, shell_quote},
+};
+
+/// An executor that runs the user's own command from `Config::custom_executor_command`
+pub struct CustomExecutor {
+    /// Follow-up prompt; the task's own prompt when `None`
+    pub prompt: Option<String>,
+    pub output_format: OutputFormat,
+}
+
+/// The command has no session to resume, so follow-ups run it again with the new prompt
+pub struct CustomFactory;
+
+impl ExecutorFactory for CustomFactory {
+    fn display_name(&self) -> &'static str {
+        "Custom Command"
+    }
+
+    fn create(&self, output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(CustomExecutor {
+            prompt: None,
+            output_format,
+        })
+    }
+
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(Box::new(CustomExecutor {
+            prompt: Some(follow_up.prompt),
+            output_format: follow_up.output_format,
+        }))
+    }
+
+    fn supports_text_output(&self) -> bool {
+        true
+    }
+}
+
+/// The configured command template, if one is set
+pub fn configured_command() -> Option<String> {
+    Config::read(&crate::utils::config_path())
+        .ok()
+        .and_then(|config| config.custom_executor_command)
+        .filter(|command| !command.trim().is_empty())
+}
+
+/// Values substituted into a command template
+struct Placeholders<'a> {
+    prompt: &'a str,
+    task_id: Uuid,
+    project_id: Uuid,
+    worktree: &'a str,
+}
+
+/// Replace the template's placeholders with their shell-quoted values in a single pass, so
+/// placeholders inside a value (say, a prompt mentioning `{worktree}`) are left alone.
+/// Unknown placeholders are kept as written.
+fn render_command(template: &str, values: &Placeholders) -> String {
+    let mut command = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        command.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let value = match &rest[1..end] {
+                "prompt" => values.prompt.to_string(),
+                "task_id" => values.task_id.to_string(),
+                "project_id" => values.project_id.to_string(),
+                "worktree" => values.worktree.to_string(),
+                _ => return None,
+            };
+            Some((shell_quote(&value), end))
+        });
+        match value {
+            Some((value, end)) => {
+                command.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                command.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    command.push_str(rest);
+    command
+}
+
+#[async_trait]
+impl Executor for CustomExecutor {
+    async fn spawn(
+        &self,
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        let template = configured_command().ok_or_else(|| {
+            ExecutorError::NotConfigured("no custom executor command is set".to_string())
+        })?;
+
+        // Get the task to fetch its description
+        let task = Task::find_by_id(pool, task_id)
+            .await?
+            .ok_or(ExecutorError::TaskNotFound)?;
+
+        let prompt = match &self.prompt {
+            Some(prompt) => prompt.clone(),
+            None => {
+                let prompt = match &task.description {
+                    Some(task_description) => format!(
+                        "project_id: {}\n\nTask title: {}\nTask description: {}",
+                        task.project_id, task.title, task_description
+                    ),
+                    None => format!(
+                        "project_id: {}\n\nTask title: {}",
+                        task.project_id, task.title
+                    ),
+                };
+                with_project_preamble(pool, task.project_id, &prompt).await
+            }
+        };
+
+        let custom_command = render_command(
+            &template,
+            &Placeholders {
+                prompt: &prompt,
+                task_id,
+                project_id: task.project_id,
+                worktree: worktree_path,
+            },
+        );
+
+        let (shell_cmd, shell_arg) = get_shell_command();
+        let mut command = Command::new(shell_cmd);
+        command
+            .kill_on_drop(true)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .current_dir(worktree_path)
+            .arg(shell_arg)
+            .arg(&custom_command);
+
+        crate::executor::apply_attempt_env(&mut command);
+
+        let mut child = command
+            .group_spawn() // Create new process group so we can kill entire tree
+            .map_err(|e| {
+                crate::executor::SpawnContext::from_command(&command, "Custom")
+                    .with_task(task_id, Some(task.title.clone()))
+                    .with_context("Custom executor command")
+                    .spawn_error(e)
+            })?;
+
+        // The prompt goes to stdin too, for commands that don't use the placeholder. Commands
+        // that never read it just close the pipe, so write errors aren't fatal.
+        if let Some(mut stdin) = child.inner().stdin.take() {
+            use tokio::io::AsyncWriteExt;
+            if let Err(e) = stdin.write_all(prompt.as_bytes()).await {
+                tracing::debug!(
+                    "Custom command for task {} didn't read stdin: {}",
+                    task_id,
+                    e
+                );
+            }
+            drop(stdin);
+        }
+
+        Ok(child)
+    }
+
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        if self.output_format == OutputFormat::Json {
+            let mut conversation = ClaudeExecutor {
+                output_format: OutputFormat::Json,
+            }
+            .normalize_logs(logs, worktree_path)?;
+            conversation.executor_type = "custom".to_string();
+            return Ok(conversation);
+        }
+
+        // Plain output: every line is something the assistant said
+        let entries = logs
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| NormalizedEntry {
+                sequence: 0,
+                timestamp: None,
+                entry_type: NormalizedEntryType::AssistantMessage,
+                content: line.to_string(),
+                metadata: None,
+            })
+            .collect();
+        Ok(NormalizedConversation {
+            entries,
+            session_id: None,
+            executor_type: "custom".to_string(),
+            prompt: None,
+            summary: None,
+        })
+    }
+
+    /// Plain lines are taken the same way Claude's are, and JSON is Claude's stream format
+    fn log_normalizer(&self, worktree_path: &str) -> Option<Box<dyn LogNormalizer>> {
+        Some(Box::new(ClaudeLogNormalizer::new(
+            self.output_format,
+            worktree_path,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_are_quoted_and_substituted_once() {
+        let task_id = Uuid::new_v4();
+        let project_id = Uuid::new_v4();
+        let command = render_command(
+            "my-agent --task {task_id} --project={project_id} -C {worktree} {prompt} {other}",
+            &Placeholders {
+                prompt: "Fix it's {worktree}",
+                task_id,
+                project_id,
+                worktree: "/tmp/work tree",
+            },
+        );
+        if cfg!(windows) {
+            return;
+        }
+        assert_eq!(
+            command,
+            format!(
+                "my-agent --task '{}' --project='{}' -C '/tmp/work tree' 'Fix it'\\''s {{worktree}}' {{other}}",
+                task_id, project_id
+            )
+        );
+    }
+}
@@ -4,7 +4,9 @@ use tokio::process::Command;
 use uuid::Uuid;
 
 use crate::{
-    executor::{Executor, ExecutorError},
+    executor::{
+        Executor, ExecutorError, NormalizedConversation, NormalizedEntry, NormalizedEntryType,
+    },
     models::{project::Project, task::Task},
     utils::shell::get_shell_command,
 };
@@ -41,6 +43,8 @@ impl Executor for DevServerExecutor {
             .arg(&self.script)
             .current_dir(worktree_path);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, "DevServer")
                 .with_task(task_id, Some(task.title.clone()))
@@ -50,4 +54,31 @@ impl Executor for DevServerExecutor {
 
         Ok(child)
     }
+
+    /// Each line the dev server printed, as it is
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        _worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        let entries = logs
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| NormalizedEntry {
+                sequence: 0,
+                timestamp: None,
+                entry_type: NormalizedEntryType::SystemMessage,
+                content: line.to_string(),
+                metadata: None,
+            })
+            .collect();
+
+        Ok(NormalizedConversation {
+            entries,
+            session_id: None,
+            executor_type: "dev_server".to_string(),
+            prompt: Some(self.script.clone()),
+            summary: None,
+        })
+    }
 }
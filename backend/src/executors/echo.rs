@@ -4,7 +4,8 @@ use tokio::process::Command;
 use uuid::Uuid;
 
 use crate::{
-    executor::{Executor, ExecutorError},
+    executor::{Executor, ExecutorError, OutputFormat},
+    executors::registry::ExecutorFactory,
     models::task::Task,
     utils::shell::get_shell_command,
 };
@@ -12,6 +13,19 @@ use crate::{
 /// A dummy executor that echoes the task title and description
 pub struct EchoExecutor;
 
+/// Echo has no sessions, so follow-ups echo the task again
+pub struct EchoFactory;
+
+impl ExecutorFactory for EchoFactory {
+    fn display_name(&self) -> &'static str {
+        "Echo (Test Mode)"
+    }
+
+    fn create(&self, _output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(EchoExecutor)
+    }
+}
+
 #[async_trait]
 impl Executor for EchoExecutor {
     async fn spawn(
@@ -65,6 +79,8 @@ echo "Task completed: {}""#,
             .arg(shell_arg)
             .arg(&script);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
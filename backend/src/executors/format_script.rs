@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::{
+    executor::{Executor, ExecutorError},
+    models::task::Task,
+};
+
+/// Executor running a project's formatters over the files an attempt changed. Each line of
+/// the script is a command, given the files as arguments; all of them run even when one
+/// fails, and the run fails with the exit code of the last one that did.
+pub struct FormatScriptExecutor {
+    pub script: String,
+    pub files: Vec<String>,
+}
+
+/// Shell script running each command of a format script with the positional parameters,
+/// which carry the file paths so they never need quoting. Blank lines and comments are
+/// skipped.
+pub fn format_commands(script: &str) -> String {
+    let mut commands = String::from("status=0\n");
+    for line in script.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        commands.push_str(line);
+        commands.push_str(" \"$@\" || status=$?\n");
+    }
+    commands.push_str("exit $status\n");
+    commands
+}
+
+#[async_trait]
+impl Executor for FormatScriptExecutor {
+    async fn spawn(
+        &self,
+        pool: &sqlx::SqlitePool,
+        task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        let task = Task::find_by_id(pool, task_id)
+            .await?
+            .ok_or(ExecutorError::TaskNotFound)?;
+
+        let mut command = Command::new("sh");
+        command
+            .kill_on_drop(true)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .arg("-c")
+            .arg(format_commands(&self.script))
+            .arg("codecommand-format")
+            .args(&self.files)
+            .current_dir(worktree_path);
+
+        crate::executor::apply_attempt_env(&mut command);
+
+        let child = command.group_spawn().map_err(|e| {
+            crate::executor::SpawnContext::from_command(&command, "FormatScript")
+                .with_task(task_id, Some(task.title.clone()))
+                .with_context("Format script execution")
+                .spawn_error(e)
+        })?;
+
+        Ok(child)
+    }
+}
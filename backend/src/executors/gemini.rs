@@ -7,15 +7,30 @@ use uuid::Uuid;
 
 use crate::{
     executor::{
-        Executor, ExecutorError, NormalizedConversation, NormalizedEntry, NormalizedEntryType,
+        with_project_preamble, Executor, ExecutorConfig, ExecutorError, NormalizedConversation,
+        NormalizedEntry, NormalizedEntryType, OutputFormat,
     },
-    models::{execution_process::ExecutionProcess, task::Task},
-    utils::shell::get_shell_command,
+    executors::registry::{ExecutorFactory, FollowUp},
+    models::{config::Config, execution_process::ExecutionProcess, task::Task},
+    services::live_normalization,
+    utils::{executable::ExecutableLookup, output_capture::Utf8Decoder, shell::get_shell_command},
 };
 
 // Constants for configuration
 const PATTERN_BREAK_TIMEOUT_SECS: u64 = 5;
 
+/// The Gemini CLI command line: the configured or installed `gemini`, else the latest
+/// release through npx
+fn gemini_command() -> String {
+    let config = Config::read(&crate::utils::config_path()).unwrap_or_default();
+    let cli = ExecutableLookup::from_env().cli_command(
+        config.executable_path(&ExecutorConfig::Gemini),
+        "gemini",
+        "npx @google/gemini-cli@latest",
+    );
+    format!("{} --yolo", cli)
+}
+
 /// An executor that uses Gemini CLI to process tasks
 pub struct GeminiExecutor;
 
@@ -25,6 +40,31 @@ pub struct GeminiFollowupExecutor {
     pub prompt: String,
 }
 
+pub struct GeminiFactory;
+
+impl ExecutorFactory for GeminiFactory {
+    fn display_name(&self) -> &'static str {
+        "Gemini"
+    }
+
+    fn create(&self, _output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(GeminiExecutor)
+    }
+
+    /// Gemini has no sessions to resume: the follow-up is given the attempt's earlier
+    /// conversation instead
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(Box::new(GeminiFollowupExecutor {
+            attempt_id: follow_up.attempt_id,
+            prompt: follow_up.prompt,
+        }))
+    }
+
+    fn default_prompt_token_budget(&self) -> u32 {
+        600_000
+    }
+}
+
 #[async_trait]
 impl Executor for GeminiExecutor {
     async fn spawn(
@@ -38,26 +78,12 @@ impl Executor for GeminiExecutor {
             .await?
             .ok_or(ExecutorError::TaskNotFound)?;
 
-        let prompt = if let Some(task_description) = task.description {
-            format!(
-                r#"project_id: {}
-            
-Task title: {}
-Task description: {}"#,
-                task.project_id, task.title, task_description
-            )
-        } else {
-            format!(
-                r#"project_id: {}
-            
-Task title: {}"#,
-                task.project_id, task.title
-            )
-        };
+        let prompt = Self::task_prompt(&task);
+        let prompt = with_project_preamble(pool, task.project_id, &prompt).await;
 
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
-        let gemini_command = "npx @google/gemini-cli@latest --yolo";
+        let gemini_command = gemini_command();
 
         let mut command = Command::new(shell_cmd);
         command
@@ -70,6 +96,8 @@ Task title: {}"#,
             .arg(gemini_command)
             .env("NODE_NO_WARNINGS", "1");
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -150,6 +178,12 @@ Task title: {}"#,
             child.inner().id()
         );
 
+        // Gemini doesn't echo its input, so record the prompt as the first conversation entry
+        live_normalization::start(pool, execution_process_id).await;
+        if let Some(task) = Task::find_by_id(pool, task_id).await? {
+            Self::emit_user_message(pool, execution_process_id, &Self::task_prompt(&task)).await;
+        }
+
         // Take stdout and stderr pipes for streaming
         let stdout = child
             .inner()
@@ -216,6 +250,7 @@ Task title: {}"#,
 
                         // Create a fallback entry for unrecognized JSON
                         let fallback_entry = NormalizedEntry {
+                            sequence: 0,
                             timestamp: Some(chrono::Utc::now().to_rfc3339()),
                             entry_type: NormalizedEntryType::SystemMessage,
                             content: format!("Raw output: {}", trimmed),
@@ -227,6 +262,7 @@ Task title: {}"#,
             } else {
                 // For non-JSON lines, treat as plain text content
                 let text_entry = NormalizedEntry {
+                    sequence: 0,
                     timestamp: Some(chrono::Utc::now().to_rfc3339()),
                     entry_type: NormalizedEntryType::AssistantMessage,
                     content: trimmed.to_string(),
@@ -250,11 +286,12 @@ Task title: {}"#,
             entries.len()
         );
 
+        let prompt = NormalizedConversation::first_user_message(&entries);
         Ok(NormalizedConversation {
             entries,
             session_id: None, // Session ID is managed directly via database, not extracted from logs
             executor_type: "gemini".to_string(),
-            prompt: None,
+            prompt,
             summary: None,
         })
     }
@@ -297,7 +334,9 @@ impl GeminiExecutor {
             attempt_id
         );
 
-        let mut buffer = [0; 1024]; // Read in chunks for performance and UTF-8 safety
+        let mut buffer = [0; 1024]; // Read in chunks for performance
+                                    // Characters split across chunks are held back until the rest of their bytes arrive
+        let mut decoder = Utf8Decoder::default();
         loop {
             // First, drain any pending segments from the queue
             while let Some(segment_content) = segment_queue.pop_front() {
@@ -328,7 +367,7 @@ impl GeminiExecutor {
                     break;
                 }
                 Ok(n) => {
-                    let chunk_str = String::from_utf8_lossy(&buffer[..n]);
+                    let chunk_str = decoder.decode(&buffer[..n]);
                     tracing::debug!(
                         "Gemini stdout chunk received for attempt {} ({} bytes): {:?}",
                         attempt_id,
@@ -358,6 +397,7 @@ impl GeminiExecutor {
         }
 
         // Process any remaining incomplete line at EOF
+        incomplete_line_buffer.push_str(&decoder.finish());
         if !incomplete_line_buffer.is_empty() {
             let segments =
                 Self::split_by_pattern_breaks(&incomplete_line_buffer, &mut last_emit_time);
@@ -400,6 +440,24 @@ impl GeminiExecutor {
             full_raw_output.len()
         );
 
+        let replaced_bytes = decoder.take_replaced_bytes();
+        if replaced_bytes > 0 {
+            if let Err(e) = ExecutionProcess::add_replaced_output_bytes(
+                &pool,
+                execution_process_id,
+                replaced_bytes,
+            )
+            .await
+            {
+                tracing::error!(
+                    "Failed to record replaced output bytes for attempt {}: {}",
+                    attempt_id,
+                    e
+                );
+            }
+        }
+
+        live_normalization::finish(execution_process_id);
         tracing::info!(
             "Gemini line-based stdout streaming ended for attempt {}",
             attempt_id
@@ -486,6 +544,42 @@ impl GeminiExecutor {
         segments
     }
 
+    /// The task as Gemini is asked to work on it, before the project preamble
+    fn task_prompt(task: &Task) -> String {
+        if let Some(task_description) = &task.description {
+            format!(
+                r#"project_id: {}
+            
+Task title: {}
+Task description: {}"#,
+                task.project_id, task.title, task_description
+            )
+        } else {
+            format!(
+                r#"project_id: {}
+            
+Task title: {}"#,
+                task.project_id, task.title
+            )
+        }
+    }
+
+    /// Emits the prompt given to Gemini as a user message to the database stdout stream.
+    async fn emit_user_message(pool: &sqlx::SqlitePool, execution_process_id: Uuid, content: &str) {
+        if content.is_empty() {
+            return;
+        }
+
+        let entry = NormalizedEntry {
+            sequence: 0,
+            timestamp: Some(chrono::Utc::now().to_rfc3339()),
+            entry_type: NormalizedEntryType::UserMessage,
+            content: content.to_string(),
+            metadata: None,
+        };
+        Self::store_normalized_entry(pool, execution_process_id, &entry).await;
+    }
+
     /// Emits a normalized message to the database stdout stream.
     async fn emit_normalized_message(
         pool: &sqlx::SqlitePool,
@@ -498,13 +592,26 @@ impl GeminiExecutor {
         }
 
         let entry = NormalizedEntry {
+            sequence: 0,
             timestamp: Some(chrono::Utc::now().to_rfc3339()),
             entry_type: NormalizedEntryType::AssistantMessage,
             content: content.to_string(),
             metadata: None,
         };
 
-        match serde_json::to_string(&entry) {
+        if Self::store_normalized_entry(pool, execution_process_id, &entry).await {
+            *last_emit_time = Instant::now();
+        }
+    }
+
+    /// Appends an entry as a JSONL line to the stdout of the execution process, returning
+    /// whether it was stored
+    async fn store_normalized_entry(
+        pool: &sqlx::SqlitePool,
+        execution_process_id: Uuid,
+        entry: &NormalizedEntry,
+    ) -> bool {
+        match serde_json::to_string(entry) {
             Ok(jsonl_line) => {
                 let formatted_line = format!("{}\n", jsonl_line);
 
@@ -520,17 +627,20 @@ impl GeminiExecutor {
                         .await
                 {
                     tracing::error!("Failed to emit normalized message: {}", e);
+                    false
                 } else {
-                    *last_emit_time = Instant::now();
+                    live_normalization::push(execution_process_id, &formatted_line);
                     tracing::debug!("Successfully stored normalized message to DB");
+                    true
                 }
             }
             Err(e) => {
                 tracing::error!(
                     "Failed to serialize normalized entry for content: {:?} - Error: {}",
-                    content,
+                    entry.content,
                     e
                 );
+                false
             }
         }
     }
@@ -620,7 +730,7 @@ You are continuing work on the above task. The execution history shows what has
         comprehensive_prompt: &str,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         let (shell_cmd, shell_arg) = get_shell_command();
-        let gemini_command = "npx @google/gemini-cli@latest --yolo";
+        let gemini_command = gemini_command();
 
         tracing::info!(
             "Spawning Gemini followup execution for attempt {} with resume context ({} chars)",
@@ -639,6 +749,8 @@ You are continuing work on the above task. The execution history shows what has
             .arg(gemini_command)
             .env("NODE_NO_WARNINGS", "1");
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let mut child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, "Gemini")
                 .with_context(format!(
@@ -755,6 +867,9 @@ impl Executor for GeminiFollowupExecutor {
             child.inner().id()
         );
 
+        live_normalization::start(pool, execution_process_id).await;
+        GeminiExecutor::emit_user_message(pool, execution_process_id, &self.prompt).await;
+
         // Take stdout and stderr pipes for streaming
         let stdout = child
             .inner()
@@ -1,15 +1,26 @@
+pub mod aider;
 pub mod amp;
+pub mod bisect;
 pub mod claude;
+pub mod codex;
+pub mod custom;
 pub mod dev_server;
 pub mod echo;
+pub mod format_script;
 pub mod gemini;
 pub mod opencode;
+pub mod registry;
 pub mod setup_script;
 
+pub use aider::{AiderExecutor, AiderFollowupExecutor};
 pub use amp::{AmpExecutor, AmpFollowupExecutor};
+pub use bisect::BisectExecutor;
 pub use claude::{ClaudeExecutor, ClaudeFollowupExecutor};
+pub use codex::{CodexExecutor, CodexFollowupExecutor};
+pub use custom::CustomExecutor;
 pub use dev_server::DevServerExecutor;
 pub use echo::EchoExecutor;
+pub use format_script::FormatScriptExecutor;
 pub use gemini::{GeminiExecutor, GeminiFollowupExecutor};
 pub use opencode::{OpencodeExecutor, OpencodeFollowupExecutor};
 pub use setup_script::SetupScriptExecutor;
@@ -1,11 +1,19 @@
 use async_trait::async_trait;
 use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
-    executor::{Executor, ExecutorError},
-    models::task::Task,
-    utils::shell::get_shell_command,
+    executor::{
+        with_project_preamble, ActionType, Executor, ExecutorConfig, ExecutorError,
+        NormalizedConversation, NormalizedEntry, NormalizedEntryType, OutputFormat,
+    },
+    executors::{
+        codex::make_path_relative,
+        registry::{ExecutorFactory, FollowUp},
+    },
+    models::{config::Config, task::Task},
+    utils::shell::{get_shell_command, shell_quote},
 };
 
 /// An executor that uses OpenCode to process tasks
@@ -17,6 +25,43 @@ pub struct OpencodeFollowupExecutor {
     pub prompt: String,
 }
 
+pub struct OpencodeFactory;
+
+/// The OpenCode CLI command line for `prompt`. OpenCode isn't run through npx, so it's the
+/// configured `opencode` or the one on PATH.
+fn opencode_command(prompt: &str) -> String {
+    let config = Config::read(&crate::utils::config_path()).unwrap_or_default();
+    let cli = config
+        .executable_path(&ExecutorConfig::Opencode)
+        .map_or_else(|| "opencode".to_string(), shell_quote);
+    format!(
+        "{} -p \"{}\" --output-format=json",
+        cli,
+        prompt.replace('"', "\\\"")
+    )
+}
+
+impl ExecutorFactory for OpencodeFactory {
+    fn display_name(&self) -> &'static str {
+        "OpenCode"
+    }
+
+    fn create(&self, _output_format: OutputFormat) -> Box<dyn Executor> {
+        Box::new(OpencodeExecutor)
+    }
+
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(Box::new(OpencodeFollowupExecutor {
+            session_id: follow_up.session_id?,
+            prompt: follow_up.prompt,
+        }))
+    }
+
+    fn default_prompt_token_budget(&self) -> u32 {
+        64_000
+    }
+}
+
 #[async_trait]
 impl Executor for OpencodeExecutor {
     async fn spawn(
@@ -51,12 +96,11 @@ Task title: {}"#,
             )
         };
 
+        let prompt = with_project_preamble(pool, task.project_id, &prompt).await;
+
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
-        let opencode_command = format!(
-            "opencode -p \"{}\" --output-format=json",
-            prompt.replace('"', "\\\"")
-        );
+        let opencode_command = opencode_command(&prompt);
 
         let mut command = Command::new(shell_cmd);
         command
@@ -67,6 +111,8 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(opencode_command);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -78,6 +124,14 @@ Task title: {}"#,
 
         Ok(child)
     }
+
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        Ok(normalize_opencode_logs(logs, worktree_path))
+    }
 }
 
 #[async_trait]
@@ -94,10 +148,7 @@ impl Executor for OpencodeFollowupExecutor {
 
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
-        let opencode_command = format!(
-            "opencode -p \"{}\" --output-format=json",
-            self.prompt.replace('"', "\\\"")
-        );
+        let opencode_command = opencode_command(&self.prompt);
 
         let mut command = Command::new(shell_cmd);
         command
@@ -108,6 +159,8 @@ impl Executor for OpencodeFollowupExecutor {
             .arg(shell_arg)
             .arg(&opencode_command);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -121,4 +174,300 @@ impl Executor for OpencodeFollowupExecutor {
 
         Ok(child)
     }
+
+    fn normalize_logs(
+        &self,
+        logs: &str,
+        worktree_path: &str,
+    ) -> Result<NormalizedConversation, String> {
+        Ok(normalize_opencode_logs(logs, worktree_path))
+    }
+}
+
+/// Events that only mark where a step of the agent's loop begins or ends
+const SKIPPED_EVENTS: &[&str] = &["step_start", "step_finish"];
+
+/// What one of OpenCode's tools does, mirroring Claude's mapping. OpenCode names the same
+/// tools differently across versions (`read` or `view`, `list` or `ls`) and spells its input
+/// fields either in camel case or in snake case.
+fn tool_action(tool: &str, input: &Value, worktree_path: &str) -> ActionType {
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| input.get(*key).and_then(Value::as_str))
+    };
+    let path = || {
+        field(&["filePath", "file_path", "path"])
+            .map(|path| make_path_relative(path, worktree_path))
+    };
+    let other = |description: &str| ActionType::Other {
+        description: description.to_string(),
+    };
+
+    match tool.to_lowercase().as_str() {
+        "read" | "view" => match path() {
+            Some(path) => ActionType::FileRead { path },
+            None => other("File read operation"),
+        },
+        "edit" | "write" | "multiedit" | "patch" => match path() {
+            Some(path) => ActionType::FileWrite { path },
+            None => other("File write operation"),
+        },
+        "bash" => match field(&["command"]) {
+            Some(command) => ActionType::CommandRun {
+                command: command.to_string(),
+            },
+            None => other("Command execution"),
+        },
+        "grep" | "sourcegraph" => match field(&["pattern", "query"]) {
+            Some(query) => ActionType::Search {
+                query: query.to_string(),
+            },
+            None => other("Search operation"),
+        },
+        "glob" => match field(&["pattern"]) {
+            Some(pattern) => other(&format!("Find files: {}", pattern)),
+            None => other("File pattern search"),
+        },
+        "list" | "ls" => match path() {
+            Some(path) => other(&format!("List directory: {}", path)),
+            None => other("List directory"),
+        },
+        "webfetch" | "fetch" => match field(&["url"]) {
+            Some(url) => ActionType::WebFetch {
+                url: url.to_string(),
+            },
+            None => other("Web fetch operation"),
+        },
+        "task" | "agent" => match field(&["description", "prompt"]) {
+            Some(description) => ActionType::TaskCreate {
+                description: description.to_string(),
+            },
+            None => other("Task creation"),
+        },
+        _ => other(&format!("Tool: {}", tool)),
+    }
+}
+
+/// Short content for a tool use entry: what the action is about, or the tool's name
+fn tool_content(tool: &str, action_type: &ActionType) -> String {
+    match action_type {
+        ActionType::FileRead { path } | ActionType::FileWrite { path } => format!("`{}`", path),
+        ActionType::CommandRun { command } => format!("`{}`", command),
+        ActionType::Search { query } => format!("`{}`", query),
+        ActionType::WebFetch { url } => format!("`{}`", url),
+        ActionType::TaskCreate { description } => description.clone(),
+        ActionType::Other { .. } => format!("`{}`", tool),
+    }
+}
+
+fn entry(entry_type: NormalizedEntryType, content: String, metadata: &Value) -> NormalizedEntry {
+    NormalizedEntry {
+        sequence: 0,
+        timestamp: None,
+        entry_type,
+        content,
+        metadata: Some(metadata.clone()),
+    }
+}
+
+fn conversation(
+    entries: Vec<NormalizedEntry>,
+    session_id: Option<String>,
+) -> NormalizedConversation {
+    let prompt = NormalizedConversation::first_user_message(&entries);
+    NormalizedConversation {
+        entries,
+        session_id,
+        executor_type: "opencode".to_string(),
+        prompt,
+        summary: None,
+    }
+}
+
+/// Parse OpenCode's JSON output: an event per line, each with the `part` of the message it
+/// adds, or a single `{"response"}` object when only the final answer is printed
+fn normalize_opencode_logs(logs: &str, worktree_path: &str) -> NormalizedConversation {
+    if let Ok(json) = serde_json::from_str::<Value>(logs.trim()) {
+        if let Some(response) = json.get("response").and_then(Value::as_str) {
+            let response = entry(
+                NormalizedEntryType::AssistantMessage,
+                response.to_string(),
+                &json,
+            );
+            return conversation(vec![response], None);
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut session_id = None;
+    for line in logs.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let json: Value = match serde_json::from_str(trimmed) {
+            Ok(json) => json,
+            Err(_) => {
+                entries.push(NormalizedEntry {
+                    sequence: 0,
+                    timestamp: None,
+                    entry_type: NormalizedEntryType::SystemMessage,
+                    content: format!("Raw output: {}", trimmed),
+                    metadata: None,
+                });
+                continue;
+            }
+        };
+        session_id = session_id.or_else(|| {
+            json.get("sessionID")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+        let part = json.get("part").unwrap_or(&Value::Null);
+        let text = part.get("text").and_then(Value::as_str).map(str::to_string);
+
+        match json.get("type").and_then(Value::as_str).unwrap_or_default() {
+            "text" => {
+                if let Some(text) = text {
+                    entries.push(entry(NormalizedEntryType::AssistantMessage, text, part));
+                }
+            }
+            "reasoning" => {
+                if let Some(text) = text {
+                    entries.push(entry(NormalizedEntryType::Thinking, text, part));
+                }
+            }
+            "tool_use" => {
+                if let Some(tool) = part.get("tool").and_then(Value::as_str) {
+                    let input = part
+                        .get("state")
+                        .and_then(|state| state.get("input"))
+                        .unwrap_or(&Value::Null);
+                    let action_type = tool_action(tool, input, worktree_path);
+                    let content = tool_content(tool, &action_type);
+                    entries.push(entry(
+                        NormalizedEntryType::ToolUse {
+                            tool_name: tool.to_string(),
+                            action_type,
+                        },
+                        content,
+                        part,
+                    ));
+                }
+            }
+            "error" => {
+                let error = json.get("error").unwrap_or(&Value::Null);
+                let message = error
+                    .pointer("/data/message")
+                    .or_else(|| error.get("message"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                entries.push(entry(
+                    NormalizedEntryType::ErrorMessage,
+                    message.unwrap_or_else(|| trimmed.to_string()),
+                    &json,
+                ));
+            }
+            event if SKIPPED_EVENTS.contains(&event) => {}
+            _ => entries.push(entry(
+                NormalizedEntryType::SystemMessage,
+                format!("Unrecognized JSON: {}", trimmed),
+                &json,
+            )),
+        }
+    }
+
+    conversation(entries, session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `opencode --output-format=json` events for a run reading, editing and testing a file
+    const OPENCODE_LOGS: &str = r#"{"type":"step_start","timestamp":1754312400000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"step-start"}}
+{"type":"text","timestamp":1754312401000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"text","text":"Let me look at the config parser."}}
+{"type":"tool_use","timestamp":1754312402000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"tool","tool":"read","callID":"call_1","state":{"status":"completed","input":{"filePath":"/tmp/test-worktree/src/config.rs"},"output":"..."}}}
+{"type":"tool_use","timestamp":1754312403000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"tool","tool":"grep","callID":"call_2","state":{"status":"completed","input":{"pattern":"parse_port","path":"src"}}}}
+{"type":"tool_use","timestamp":1754312404000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"tool","tool":"edit","callID":"call_3","state":{"status":"completed","input":{"filePath":"/tmp/test-worktree/src/config.rs","oldString":"u32","newString":"u16"}}}}
+{"type":"tool_use","timestamp":1754312405000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"tool","tool":"bash","callID":"call_4","state":{"status":"completed","input":{"command":"cargo test parse_port","description":"Run the parser tests"}}}}
+{"type":"tool_use","timestamp":1754312406000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"tool","tool":"view","callID":"call_5","state":{"status":"completed","input":{"file_path":"README.md"}}}}
+{"type":"tool_use","timestamp":1754312407000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"tool","tool":"todowrite","callID":"call_6","state":{"status":"completed","input":{"todos":[]}}}}
+{"type":"step_finish","timestamp":1754312408000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"step-finish"}}
+{"type":"text","timestamp":1754312409000,"sessionID":"ses_6c1f0a2b3d4e","part":{"type":"text","text":"The port now parses as a u16."}}"#;
+
+    #[test]
+    fn test_tool_calls_map_to_their_actions() {
+        let conversation = OpencodeExecutor
+            .normalize_logs(OPENCODE_LOGS, "/tmp/test-worktree")
+            .unwrap();
+        assert_eq!(conversation.session_id.as_deref(), Some("ses_6c1f0a2b3d4e"));
+        assert_eq!(conversation.entries.len(), 8);
+        assert!(matches!(
+            conversation.entries[0].entry_type,
+            NormalizedEntryType::AssistantMessage
+        ));
+
+        let actions: Vec<_> = conversation
+            .entries
+            .iter()
+            .filter_map(|entry| match &entry.entry_type {
+                NormalizedEntryType::ToolUse { action_type, .. } => Some(action_type.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ActionType::FileRead {
+                    path: "src/config.rs".to_string()
+                },
+                ActionType::Search {
+                    query: "parse_port".to_string()
+                },
+                ActionType::FileWrite {
+                    path: "src/config.rs".to_string()
+                },
+                ActionType::CommandRun {
+                    command: "cargo test parse_port".to_string()
+                },
+                ActionType::FileRead {
+                    path: "README.md".to_string()
+                },
+                ActionType::Other {
+                    description: "Tool: todowrite".to_string()
+                },
+            ]
+        );
+        assert_eq!(conversation.entries[4].content, "`cargo test parse_port`");
+    }
+
+    #[test]
+    fn test_final_response_and_errors() {
+        let conversation = OpencodeFollowupExecutor {
+            session_id: "ses_6c1f0a2b3d4e".to_string(),
+            prompt: "Also cover port 0".to_string(),
+        }
+        .normalize_logs(
+            "{\n  \"response\": \"Port 0 is now rejected.\"\n}\n",
+            "/tmp/test-worktree",
+        )
+        .unwrap();
+        assert_eq!(conversation.entries.len(), 1);
+        assert_eq!(conversation.entries[0].content, "Port 0 is now rejected.");
+
+        let conversation = OpencodeExecutor
+            .normalize_logs(
+                "{\"type\":\"error\",\"error\":{\"name\":\"APIError\",\"data\":{\"message\":\"rate limited\"}}}\nnot json",
+                "/tmp/test-worktree",
+            )
+            .unwrap();
+        assert!(matches!(
+            conversation.entries[0].entry_type,
+            NormalizedEntryType::ErrorMessage
+        ));
+        assert_eq!(conversation.entries[0].content, "rate limited");
+        assert_eq!(conversation.entries[1].content, "Raw output: not json");
+    }
 }
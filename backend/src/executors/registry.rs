@@ -0,0 +1,165 @@
+//! The coding agents attempts can run with. Each agent's module provides an
+//! `ExecutorFactory`; the API, the config checks and the log parsers look agents up here by
+//! name, so a new agent only has to be registered to be offered to users.
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use command_group::AsyncGroupChild;
+use uuid::Uuid;
+
+use crate::{
+    executor::{Executor, ExecutorError, OutputFormat},
+    executors::{
+        aider::AiderFactory, amp::AmpFactory, claude::ClaudeFactory, codex::CodexFactory,
+        custom::CustomFactory, echo::EchoFactory, gemini::GeminiFactory, opencode::OpencodeFactory,
+    },
+};
+
+/// What a follow-up of an attempt continues from
+pub struct FollowUp {
+    pub attempt_id: Uuid,
+    /// Session the agent recorded in its first run, if it did
+    pub session_id: Option<String>,
+    pub prompt: String,
+    pub output_format: OutputFormat,
+}
+
+/// Creates the executors of one coding agent
+pub trait ExecutorFactory: Send + Sync {
+    /// Name shown to users
+    fn display_name(&self) -> &'static str;
+
+    /// Executor of an attempt's first run. Agents without a text mode ignore `output_format`.
+    fn create(&self, output_format: OutputFormat) -> Box<dyn Executor>;
+
+    /// Executor continuing the agent's session with a follow-up prompt, or `None` when the
+    /// session can't be resumed. Agents without sessions start over by default.
+    fn create_followup(&self, follow_up: FollowUp) -> Option<Box<dyn Executor>> {
+        Some(self.create(follow_up.output_format))
+    }
+
+    /// Whether the agent can print plain text instead of JSON
+    fn supports_text_output(&self) -> bool {
+        false
+    }
+
+    /// Estimated tokens auto-composed prompts may use
+    fn default_prompt_token_budget(&self) -> u32 {
+        100_000
+    }
+}
+
+/// Registered agents, in the order they're offered to users
+type Registry = Vec<(String, Arc<dyn ExecutorFactory>)>;
+
+fn builtin_executors() -> Registry {
+    vec![
+        (
+            "echo".to_string(),
+            Arc::new(EchoFactory) as Arc<dyn ExecutorFactory>,
+        ),
+        ("claude".to_string(), Arc::new(ClaudeFactory)),
+        ("amp".to_string(), Arc::new(AmpFactory)),
+        ("gemini".to_string(), Arc::new(GeminiFactory)),
+        ("opencode".to_string(), Arc::new(OpencodeFactory)),
+        ("aider".to_string(), Arc::new(AiderFactory)),
+        ("codex".to_string(), Arc::new(CodexFactory)),
+        ("custom".to_string(), Arc::new(CustomFactory)),
+    ]
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(builtin_executors());
+}
+
+/// Make an agent available under `name`, replacing the agent registered under it, if any
+#[allow(dead_code)]
+pub fn register_executor(name: &str, factory: Box<dyn ExecutorFactory>) {
+    let mut registry = REGISTRY.write().unwrap();
+    let factory: Arc<dyn ExecutorFactory> = Arc::from(factory);
+    match registry
+        .iter_mut()
+        .find(|(registered, _)| registered == name)
+    {
+        Some(entry) => entry.1 = factory,
+        None => registry.push((name.to_string(), factory)),
+    }
+}
+
+/// Names of the registered agents, in the order they're offered to users
+pub fn available_executors() -> Vec<String> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+pub fn executor_factory(name: &str) -> Option<Arc<dyn ExecutorFactory>> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .iter()
+        .find(|(registered, _)| registered == name)
+        .map(|(_, factory)| factory.clone())
+}
+
+/// Stands in for an agent that is no longer registered, e.g. one still named in the config
+pub(crate) struct UnregisteredExecutor(pub String);
+
+#[async_trait]
+impl Executor for UnregisteredExecutor {
+    async fn spawn(
+        &self,
+        _pool: &sqlx::SqlitePool,
+        _task_id: Uuid,
+        _worktree_path: &str,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        Err(ExecutorError::UnknownExecutor(self.0.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::ExecutorConfig;
+
+    struct ReviewBotFactory;
+
+    impl ExecutorFactory for ReviewBotFactory {
+        fn display_name(&self) -> &'static str {
+            "Review Bot"
+        }
+
+        fn create(&self, _output_format: OutputFormat) -> Box<dyn Executor> {
+            EchoFactory.create(OutputFormat::Json)
+        }
+    }
+
+    #[test]
+    fn test_registered_executors_are_offered_and_parsed() {
+        assert!(!available_executors().contains(&"review-bot".to_string()));
+        assert!("review-bot".parse::<ExecutorConfig>().is_err());
+
+        register_executor("review-bot", Box::new(ReviewBotFactory));
+        let builtins: Vec<String> = builtin_executors()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(available_executors()[..builtins.len()], builtins);
+        assert!(available_executors().contains(&"review-bot".to_string()));
+
+        let config: ExecutorConfig = "review-bot".parse().unwrap();
+        assert_eq!(config.to_string(), "review-bot");
+        assert_eq!(config.display_name(), "Review Bot");
+        assert!(ExecutorConfig::agents()
+            .iter()
+            .any(|agent| agent.to_string() == "review-bot"));
+        assert!(matches!(
+            "claude".parse::<ExecutorConfig>(),
+            Ok(ExecutorConfig::Claude)
+        ));
+    }
+}
@@ -47,6 +47,8 @@ impl Executor for SetupScriptExecutor {
             .arg(&self.script)
             .current_dir(worktree_path);
 
+        crate::executor::apply_attempt_env(&mut command);
+
         let child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, "SetupScript")
                 .with_task(task_id, Some(task.title.clone()))
@@ -67,6 +69,7 @@ impl Executor for SetupScriptExecutor {
 
         // Add script command as first entry
         entries.push(crate::executor::NormalizedEntry {
+            sequence: 0,
             timestamp: None,
             entry_type: crate::executor::NormalizedEntryType::SystemMessage,
             content: format!("Executing setup script:\n{}", self.script),
@@ -98,6 +101,7 @@ impl Executor for SetupScriptExecutor {
                     };
 
                     entries.push(crate::executor::NormalizedEntry {
+                        sequence: 0,
                         timestamp: Some(chrono::Utc::now().to_rfc3339()),
                         entry_type,
                         content: current_chunk.trim().to_string(),
@@ -111,6 +115,7 @@ impl Executor for SetupScriptExecutor {
             // Add any remaining content
             if !current_chunk.trim().is_empty() {
                 entries.push(crate::executor::NormalizedEntry {
+                    sequence: 0,
                     timestamp: Some(chrono::Utc::now().to_rfc3339()),
                     entry_type: crate::executor::NormalizedEntryType::SystemMessage,
                     content: current_chunk.trim().to_string(),
@@ -10,6 +10,8 @@ pub mod mcp;
 pub mod models;
 pub mod routes;
 pub mod services;
+#[cfg(test)]
+pub mod test_utils;
 pub mod utils;
 
 #[derive(RustEmbed)]
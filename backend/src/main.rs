@@ -1,4 +1,4 @@
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 use axum::{
     body::Body,
@@ -8,13 +8,13 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
+use codecommand::{sentry_layer, Assets, ScriptAssets, SoundAssets};
 use sentry_tower::NewSentryLayer;
 use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
 use strip_ansi_escapes::strip;
 use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::{filter::LevelFilter, prelude::*};
-use codecommand::{sentry_layer, Assets, ScriptAssets, SoundAssets};
 
 mod app_state;
 mod execution_monitor;
@@ -24,13 +24,178 @@ mod mcp;
 mod models;
 mod routes;
 mod services;
+#[cfg(test)]
+mod test_utils;
 mod utils;
 
 use app_state::AppState;
-use execution_monitor::execution_monitor;
+use execution_monitor::{maintain_worktrees, reconcile_executions};
 use models::{ApiResponse, Config};
-use routes::{auth, config, filesystem, health, projects, task_attempts, tasks};
-use services::PrMonitorService;
+use routes::{
+    auth, benchmarks, bisect, config, executions, filesystem, glossary, health, idempotency, jobs,
+    maintenance, preferences, projects, search, task_attempts, tasks, updates, webhooks,
+};
+#[cfg(feature = "self-update")]
+use services::self_update;
+use services::{
+    asset_usage,
+    cleanup::CLEANUP_INTERVAL,
+    project_events, server_discovery,
+    webhooks::{WebhookDispatcher, DELIVERY_JOB, DELIVERY_POLL_INTERVAL},
+    PrMonitorService, Schedule, TaskAttemptCleanupService,
+};
+
+/// Background loops run as scheduled jobs so they can be inspected and triggered via /api/jobs
+async fn register_background_jobs(app_state: &AppState, config: Arc<RwLock<Config>>) {
+    let jobs = &app_state.jobs;
+
+    // Check for init status and spawn processes
+    let state = app_state.clone();
+    let reconciliation = jobs.register(
+        "execution_reconciliation",
+        Schedule::Interval(Duration::from_secs(5)),
+        move || {
+            let state = state.clone();
+            async move { reconcile_executions(&state).await }
+        },
+    );
+
+    let state = app_state.clone();
+    let maintenance = jobs.register(
+        "worktree_maintenance",
+        Schedule::Interval(Duration::from_secs(1800)),
+        move || {
+            let state = state.clone();
+            async move { maintain_worktrees(&state).await }
+        },
+    );
+
+    let pr_monitor = Arc::new(PrMonitorService::new(app_state.db_pool.clone()));
+    let pr_monitor_interval = pr_monitor.poll_interval();
+    let pr_monitoring = jobs.register(
+        "pr_monitor",
+        Schedule::Interval(pr_monitor_interval),
+        move || {
+            let pr_monitor = pr_monitor.clone();
+            let config = config.clone();
+            async move { pr_monitor.check_with_config(&config).await }
+        },
+    );
+
+    let scheduler = jobs.clone();
+    app_state.on_config_change(move |old, new| {
+        let needs_check = PrMonitorService::needs_check_after_config_change(old, new);
+        let scheduler = scheduler.clone();
+        async move {
+            if needs_check {
+                if let Err(e) = scheduler.trigger("pr_monitor").await {
+                    tracing::debug!("PR check after config change not started: {}", e);
+                }
+            }
+        }
+    });
+
+    let cleanup_service = Arc::new(TaskAttemptCleanupService::new(app_state.clone()));
+    let cleanup = jobs.register(
+        "worktree_cleanup",
+        Schedule::Interval(CLEANUP_INTERVAL),
+        move || {
+            let cleanup_service = cleanup_service.clone();
+            async move {
+                cleanup_service
+                    .run()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+        },
+    );
+
+    let webhook_dispatcher = Arc::new(WebhookDispatcher::new(app_state.db_pool.clone()));
+    let webhook_delivery = jobs.register(
+        DELIVERY_JOB,
+        Schedule::Interval(DELIVERY_POLL_INTERVAL),
+        move || {
+            let webhook_dispatcher = webhook_dispatcher.clone();
+            async move { webhook_dispatcher.deliver_due().await }
+        },
+    );
+
+    let project_events = app_state.project_events.clone();
+    let pool = app_state.db_pool.clone();
+    let project_event_sync = jobs.register(
+        project_events::SYNC_JOB,
+        Schedule::Interval(project_events::SYNC_INTERVAL),
+        move || {
+            let project_events = project_events.clone();
+            let pool = pool.clone();
+            async move {
+                project_events
+                    .sync_tasks(&pool)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+        },
+    );
+
+    let config = app_state.get_config().clone();
+    let asset_accounting = jobs.register(
+        asset_usage::ACCOUNTING_JOB,
+        Schedule::Interval(asset_usage::ACCOUNTING_INTERVAL),
+        move || {
+            let config = config.clone();
+            async move {
+                let quotas = config.read().await.asset_quotas.clone();
+                let usage = asset_usage::account(&utils::asset_dir(), &quotas, true)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !usage.evicted.is_empty() {
+                    tracing::info!(
+                        "Evicted {} files to keep the asset directory under quota",
+                        usage.evicted.len()
+                    );
+                }
+                // Only some kinds of files can be evicted; the rest need a hand
+                match usage.quota_errors() {
+                    errors if errors.is_empty() => Ok(()),
+                    errors => Err(errors.join("; ")),
+                }
+            }
+        },
+    );
+
+    #[cfg(feature = "self-update")]
+    {
+        let updates = app_state.updates.clone();
+        let config = app_state.get_config().clone();
+        let update_check = jobs.register(
+            self_update::UPDATE_CHECK_JOB,
+            Schedule::Interval(self_update::UPDATE_CHECK_INTERVAL),
+            move || {
+                let updates = updates.clone();
+                let config = config.clone();
+                async move { updates.check(&config).await }
+            },
+        );
+        if let Err(e) = update_check.await {
+            tracing::error!("Failed to register background job: {}", e);
+        }
+    }
+
+    for result in [
+        reconciliation.await,
+        maintenance.await,
+        pr_monitoring.await,
+        cleanup.await,
+        webhook_delivery.await,
+        project_event_sync.await,
+        asset_accounting.await,
+    ] {
+        if let Err(e) = result {
+            tracing::error!("Failed to register background job: {}", e);
+        }
+    }
+}
 
 async fn echo_handler(
     Json(payload): Json<serde_json::Value>,
@@ -119,19 +284,206 @@ async fn serve_sound_file(
     }
 }
 
+/// Start the binary that was just installed in place of this process. `exe` is resolved at
+/// startup: once the binary is replaced, the running one's path may no longer lead to it.
+#[cfg(feature = "self-update")]
+fn restart_into_update(exe: &std::path::Path, port: u16) -> anyhow::Result<()> {
+    let mut command = std::process::Command::new(exe);
+    command
+        .args(std::env::args_os().skip(1))
+        // Keep the address open browser tabs are using
+        .env("BACKEND_PORT", port.to_string());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        Err(command.exec().into())
+    }
+    #[cfg(not(unix))]
+    {
+        command.spawn()?;
+        Ok(())
+    }
+}
+
+/// `codecommand self-update`: have the running server install the latest release and restart
+/// into it, or install it directly when no server is running. `--token` prints the token
+/// `POST /api/updates/apply` requires instead.
+#[cfg(feature = "self-update")]
+fn self_update_command() -> anyhow::Result<()> {
+    if std::env::args().skip(2).any(|arg| arg == "--token") {
+        println!("{}", self_update::apply_token()?);
+        return Ok(());
+    }
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(async {
+            let port = server_discovery::discover().await.map(|server| server.port);
+            if let Some(port) = port {
+                if let Some(response) = request_server_update(port).await {
+                    let message = response["message"].as_str().unwrap_or_default().to_string();
+                    if response["success"].as_bool() == Some(true) {
+                        println!("{}", message);
+                        return Ok(());
+                    }
+                    anyhow::bail!(message);
+                }
+                eprintln!("The running server didn't take the update, installing it directly");
+            }
+
+            match self_update::install_latest().await {
+                Ok(version) => {
+                    println!("Updated codecommand to {}", version);
+                    if port.is_some() {
+                        println!("Restart the running codecommand to use it");
+                    }
+                    Ok(())
+                }
+                Err(e @ self_update::UpdateError::UpToDate(_)) => {
+                    println!("{}", e);
+                    Ok(())
+                }
+                Err(e) => Err(anyhow::anyhow!("{}; the installed binary is unchanged", e)),
+            }
+        })
+}
+
+/// Ask the server listening on `port` to update itself; `None` when it can't, e.g. because it
+/// predates self-updates
+#[cfg(feature = "self-update")]
+async fn request_server_update(port: u16) -> Option<serde_json::Value> {
+    let token = self_update::apply_token().ok()?;
+    let response = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{}/api/updates/apply", port))
+        .bearer_auth(token)
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    response.json().await.ok()
+}
+
+/// `codecommand status`: report the running server, found through its announcement file
+fn status_command() -> anyhow::Result<()> {
+    let server = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(server_discovery::discover());
+    match server {
+        Some(server) => {
+            println!(
+                "codecommand {} is running at {} (pid {}, started {})",
+                server.version,
+                server.url(),
+                server.pid,
+                server.started_at.to_rfc3339()
+            );
+            Ok(())
+        }
+        None => anyhow::bail!("codecommand is not running"),
+    }
+}
+
+/// `codecommand attempt wait <attempt-id> [--timeout <seconds>] [--until <condition>]`: block
+/// until an attempt of the running server is finished (or `review_ready`, or `merged`), then
+/// print its state as JSON. Fails when the wait times out, so scripts can run e.g.
+/// `codecommand attempt wait "$ATTEMPT" --until review_ready && open-review "$ATTEMPT"`.
+fn attempt_command() -> anyhow::Result<()> {
+    const USAGE: &str =
+        "usage: codecommand attempt wait <attempt-id> [--timeout <seconds>] [--until finished|review_ready|merged]";
+
+    let mut args = std::env::args().skip(2);
+    if args.next().as_deref() != Some("wait") {
+        anyhow::bail!(USAGE);
+    }
+    let mut attempt_id = None;
+    let mut query = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--timeout" | "--until" => {
+                let value = args.next().ok_or_else(|| anyhow::anyhow!(USAGE))?;
+                query.push((arg.trim_start_matches("--").to_string(), value));
+            }
+            _ if attempt_id.is_none() => attempt_id = Some(arg.parse::<uuid::Uuid>()?),
+            _ => anyhow::bail!(USAGE),
+        }
+    }
+    let attempt_id = attempt_id.ok_or_else(|| anyhow::anyhow!(USAGE))?;
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(async {
+            let server = server_discovery::discover()
+                .await
+                .ok_or_else(|| anyhow::anyhow!("codecommand is not running"))?;
+            let response = reqwest::Client::new()
+                .get(format!(
+                    "{}/api/task-attempts/{}/wait",
+                    server.url(),
+                    attempt_id
+                ))
+                .query(&query)
+                .send()
+                .await?;
+            match response.status() {
+                // Axum's own rejection, e.g. for an unknown `--until`
+                reqwest::StatusCode::BAD_REQUEST => anyhow::bail!(response.text().await?),
+                reqwest::StatusCode::NOT_FOUND => anyhow::bail!("Task attempt not found"),
+                reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                    anyhow::bail!("Too many clients are already waiting on this attempt")
+                }
+                _ => {}
+            }
+            let body: serde_json::Value = response.json().await?;
+            if body["success"].as_bool() != Some(true) {
+                anyhow::bail!(body["message"]
+                    .as_str()
+                    .unwrap_or("Wait failed")
+                    .to_string());
+            }
+
+            let result = &body["data"];
+            println!("{}", serde_json::to_string_pretty(result)?);
+            if result["timed_out"].as_bool() == Some(true) {
+                anyhow::bail!("Timed out waiting for the attempt");
+            }
+            Ok(())
+        })
+}
+
+#[cfg(not(feature = "self-update"))]
+fn self_update_command() -> anyhow::Result<()> {
+    anyhow::bail!(
+        "This build of codecommand doesn't update itself; update it through the package manager it came from"
+    )
+}
+
 fn main() -> anyhow::Result<()> {
+    match std::env::args().nth(1).as_deref() {
+        Some("self-update") => return self_update_command(),
+        Some("status") => return status_command(),
+        Some("attempt") => return attempt_command(),
+        _ => {}
+    }
+    #[cfg(feature = "self-update")]
+    let exe = std::env::current_exe()?;
+
     let _environment = if cfg!(debug_assertions) {
         "dev"
     } else {
         "production"
     };
-    
+
     // Force disable Sentry - use dummy configuration
     let _guard = sentry::init(sentry::ClientOptions {
         dsn: None, // Disable Sentry by setting DSN to None
         ..Default::default()
     });
-    
+
     /*
     let _guard = sentry::init(("https://1065a1d276a581316999a07d5dffee26@o4509603705192449.ingest.de.sentry.io/4509605576441937", sentry::ClientOptions {
         release: sentry::release_name!(),
@@ -140,11 +492,11 @@ fn main() -> anyhow::Result<()> {
         ..Default::default()
     }));
     */
-    
+
     sentry::configure_scope(|scope| {
         scope.set_tag("source", "server");
     });
-    tokio::runtime::Builder::new_multi_thread()
+    let restart_port = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .unwrap()
@@ -169,6 +521,11 @@ fn main() -> anyhow::Result<()> {
             let pool = SqlitePool::connect_with(options).await?;
             sqlx::migrate!("./migrations").run(&pool).await?;
 
+            // Requests that were in flight when the server stopped will never complete
+            if let Err(e) = models::idempotency_key::IdempotencyKey::release_pending(&pool).await {
+                tracing::warn!("Failed to release pending idempotency keys: {}", e);
+            }
+
             // Load configuration
             let config_path = utils::config_path();
             let config = Config::load(&config_path)?;
@@ -181,19 +538,10 @@ fn main() -> anyhow::Result<()> {
 
             // Track session start event
             app_state.track_analytics_event("session_start", None).await;
-            // Start background task to check for init status and spawn processes
-            let state_clone = app_state.clone();
-            tokio::spawn(async move {
-                execution_monitor(state_clone).await;
-            });
-
-            // Start PR monitoring service
-            let pr_monitor = PrMonitorService::new(pool.clone());
-            let config_for_monitor = config_arc.clone();
-
-            tokio::spawn(async move {
-                pr_monitor.start_with_config(config_for_monitor).await;
-            });
+            register_background_jobs(&app_state, config_arc.clone()).await;
+            if let Err(e) = services::benchmark::resume_runs(&app_state).await {
+                tracing::warn!("Failed to resume benchmark runs: {}", e);
+            }
 
             // Public routes (no auth required)
             let public_routes = Router::new()
@@ -208,10 +556,21 @@ fn main() -> anyhow::Result<()> {
                         .merge(projects::projects_router())
                         .merge(tasks::tasks_router())
                         .merge(task_attempts::task_attempts_router())
+                        .merge(executions::executions_router())
                         .merge(filesystem::filesystem_router())
                         .merge(config::config_router())
+                        .merge(preferences::preferences_router())
                         .merge(auth::auth_router())
+                        .merge(jobs::jobs_router())
+                        .merge(maintenance::maintenance_router())
+                        .merge(glossary::glossary_router())
+                        .merge(benchmarks::benchmarks_router())
+                        .merge(bisect::bisect_router())
+                        .merge(updates::updates_router())
+                        .merge(webhooks::webhooks_router())
+                        .merge(search::search_router())
                         .route("/sounds/:filename", get(serve_sound_file))
+                        .layer(from_fn_with_state(app_state.clone(), idempotency::idempotency_middleware))
                         .layer(from_fn_with_state(app_state.clone(), auth::sentry_user_context_middleware)),
                 );
 
@@ -221,7 +580,7 @@ fn main() -> anyhow::Result<()> {
                 // Static file serving routes
                 .route("/", get(index_handler))
                 .route("/*path", get(static_handler))
-                .with_state(app_state)
+                .with_state(app_state.clone())
                 .layer(CorsLayer::permissive())
                 .layer(NewSentryLayer::new_from_top());
 
@@ -243,6 +602,12 @@ fn main() -> anyhow::Result<()> {
             let actual_port = listener.local_addr()?.port(); // get → 53427 (example)
 
             tracing::info!("Server running on http://0.0.0.0:{actual_port}");
+            if let Err(e) = utils::write_port_file(actual_port) {
+                tracing::warn!("Failed to write port file: {}", e);
+            }
+            let announcement = server_discovery::announce(actual_port)
+                .map_err(|e| tracing::warn!("Failed to announce the server: {}", e))
+                .ok();
 
             if !cfg!(debug_assertions) {
                 tracing::info!("Opening browser...");
@@ -251,8 +616,70 @@ fn main() -> anyhow::Result<()> {
                 }
             }
 
-            axum::serve(listener, app).await?;
+            #[cfg(feature = "self-update")]
+            let restart = {
+                let updates = app_state.updates.clone();
+                async move { updates.restart_requested().await }
+            };
+            #[cfg(not(feature = "self-update"))]
+            let restart = std::future::pending::<()>();
 
-            Ok(())
-        })
+            let served = axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_signal(restart))
+                .await;
+            utils::remove_port_file();
+            if let Some(announcement) = &announcement {
+                server_discovery::withdraw(announcement);
+            }
+            served?;
+
+            #[cfg(feature = "self-update")]
+            if app_state.updates.restart_pending() {
+                return Ok(Some(actual_port));
+            }
+            Ok::<_, anyhow::Error>(None)
+        })?;
+
+    #[cfg(feature = "self-update")]
+    if let Some(port) = restart_port {
+        tracing::info!("Restarting into the installed update");
+        restart_into_update(&exe, port)?;
+    }
+    #[cfg(not(feature = "self-update"))]
+    let _ = restart_port;
+
+    Ok(())
+}
+
+/// Resolves on Ctrl+C, SIGTERM on Unix, or `restart`, so cleanup after `axum::serve` gets to
+/// run
+async fn shutdown_signal(restart: impl std::future::Future<Output = ()>) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+        _ = restart => {},
+    }
+    tracing::info!("Shutting down");
 }
@@ -1,27 +1,61 @@
+use std::time::Instant;
+
 use rmcp::{
     model::{
-        CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo,
+        AnnotateAble, CallToolResult, Content, Implementation, ListResourcesResult,
+        PaginatedRequestParam, ProtocolVersion, RawResource, ReadResourceRequestParam,
+        ReadResourceResult, Resource, ResourceContents, ServerCapabilities, ServerInfo,
     },
-    schemars, tool, Error as RmcpError, ServerHandler,
+    schemars,
+    service::RequestContext,
+    tool, Error as RmcpError, RoleServer, ServerHandler,
 };
 use serde::{Deserialize, Serialize};
 use serde_json;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use crate::models::{
-    project::Project,
-    task::{CreateTask, Task, TaskStatus},
+use crate::{
+    executor::NormalizedEntryType,
+    executors::registry::available_executors,
+    models::{
+        execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
+        glossary::{GlossaryEntry, GlossaryEntryStatus},
+        project::Project,
+        task::{
+            is_valid_estimate, CreateTask, Task, TaskConflict, TaskStatus, TaskUpdateOutcome,
+            TaskUpdateSource, UpdateTask,
+        },
+        task_attempt::TaskAttempt,
+    },
+    services::{log_normalization, server_discovery},
 };
 
+/// Entries returned by `get_execution_logs` when no limit is given
+const DEFAULT_LOG_ENTRIES: usize = 100;
+
+const SERVER_NAME: &str = "codecommand";
+const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::V_2024_11_05;
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct CreateTaskRequest {
-    #[schemars(description = "The ID of the project to create the task in. This is required!")]
-    pub project_id: String,
+    #[schemars(
+        description = "The ID of the project. Required unless the server is scoped to a project"
+    )]
+    #[serde(default)]
+    pub project_id: Option<String>,
     #[schemars(description = "The title of the task")]
     pub title: String,
     #[schemars(description = "Optional description of the task")]
     pub description: Option<String>,
+    #[schemars(description = "Optional estimated effort (e.g. story points or minutes)")]
+    pub estimate: Option<f64>,
+    #[schemars(
+        description = "Optional executor the task's attempts start with, one of the `executors` listed by `ping`"
+    )]
+    #[serde(default)]
+    pub executor: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -29,6 +63,30 @@ pub struct ListProjectsRequest {
     // Empty for now, but we can add filtering options later
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PingRequest {}
+
+#[derive(Debug, Serialize)]
+pub struct PingResponse {
+    pub success: bool,
+    pub server: String,
+    pub version: String,
+    pub protocol_version: ProtocolVersion,
+    pub uptime_seconds: u64,
+    /// Coding agents attempts can run with
+    pub executors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FindServerRequest {}
+
+#[derive(Debug, Serialize)]
+pub struct FindServerResponse {
+    pub success: bool,
+    /// Web UI of the running codecommand server, if one is running
+    pub server_url: Option<String>,
+}
+
 #[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct CreateTaskResponse {
     pub success: bool,
@@ -50,6 +108,8 @@ pub struct ProjectSummary {
     pub dev_script: Option<String>,
     #[schemars(description = "Current git branch (if available)")]
     pub current_branch: Option<String>,
+    #[schemars(description = "Number of todo tasks that no attempt is working on")]
+    pub pending_task_count: u32,
     #[schemars(description = "When the project was created")]
     pub created_at: String,
     #[schemars(description = "When the project was last updated")]
@@ -65,8 +125,11 @@ pub struct ListProjectsResponse {
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct ListTasksRequest {
-    #[schemars(description = "The ID of the project to list tasks from")]
-    pub project_id: String,
+    #[schemars(
+        description = "The ID of the project. Required unless the server is scoped to a project"
+    )]
+    #[serde(default)]
+    pub project_id: Option<String>,
     #[schemars(
         description = "Optional status filter: 'todo', 'inprogress', 'inreview', 'done', 'cancelled'"
     )]
@@ -85,16 +148,26 @@ pub struct TaskSummary {
     pub description: Option<String>,
     #[schemars(description = "Current status of the task")]
     pub status: String,
+    #[schemars(description = "Estimated effort for the task, if set")]
+    pub estimate: Option<f64>,
     #[schemars(description = "When the task was created")]
     pub created_at: String,
     #[schemars(description = "When the task was last updated")]
     pub updated_at: String,
+    #[schemars(
+        description = "Version of the task, increased by every change. Pass it as `expected_version` to update or delete the task only if nobody changed it since"
+    )]
+    pub version: i64,
     #[schemars(description = "Whether the task has an in-progress execution attempt")]
     pub has_in_progress_attempt: Option<bool>,
     #[schemars(description = "Whether the task has a merged execution attempt")]
     pub has_merged_attempt: Option<bool>,
     #[schemars(description = "Whether the task has a failed execution attempt")]
     pub has_failed_attempt: Option<bool>,
+    #[schemars(
+        description = "IDs of tasks this task depends on that aren't done yet. The task can't be started until they are"
+    )]
+    pub blocked_by: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, schemars::JsonSchema)]
@@ -134,10 +207,44 @@ fn task_status_to_string(status: &TaskStatus) -> String {
     }
 }
 
+fn task_summary(task: Task) -> TaskSummary {
+    TaskSummary {
+        id: task.id.to_string(),
+        title: task.title,
+        description: task.description,
+        status: task_status_to_string(&task.status),
+        estimate: task.estimate,
+        created_at: task.created_at.to_rfc3339(),
+        updated_at: task.updated_at.to_rfc3339(),
+        version: task.version,
+        has_in_progress_attempt: None,
+        has_merged_attempt: None,
+        has_failed_attempt: None,
+        blocked_by: None,
+    }
+}
+
+/// The task as it is now, for the agent to merge its change into and retry
+fn conflict_result(conflict: TaskConflict) -> CallToolResult {
+    let error_response = serde_json::json!({
+        "success": false,
+        "error": "conflict",
+        "conflicting_fields": conflict.conflicting_fields,
+        "current": task_summary(conflict.current),
+        "hint": "The task changed since expected_version. Merge your change into `current` and retry with its version"
+    });
+    CallToolResult::error(vec![Content::text(
+        serde_json::to_string_pretty(&error_response).unwrap(),
+    )])
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct UpdateTaskRequest {
-    #[schemars(description = "The ID of the project containing the task")]
-    pub project_id: String,
+    #[schemars(
+        description = "The ID of the project. Required unless the server is scoped to a project"
+    )]
+    #[serde(default)]
+    pub project_id: Option<String>,
     #[schemars(description = "The ID of the task to update")]
     pub task_id: String,
     #[schemars(description = "New title for the task")]
@@ -146,6 +253,13 @@ pub struct UpdateTaskRequest {
     pub description: Option<String>,
     #[schemars(description = "New status: 'todo', 'inprogress', 'inreview', 'done', 'cancelled'")]
     pub status: Option<String>,
+    #[schemars(description = "New estimated effort (e.g. story points or minutes)")]
+    pub estimate: Option<f64>,
+    #[schemars(
+        description = "The task's `version` when you read it. If someone changed the task since, the update is merged when it touches other fields and rejected as a conflict otherwise"
+    )]
+    #[serde(default)]
+    pub expected_version: Option<i64>,
 }
 
 #[derive(Debug, Serialize, schemars::JsonSchema)]
@@ -153,14 +267,29 @@ pub struct UpdateTaskResponse {
     pub success: bool,
     pub message: String,
     pub task: Option<TaskSummary>,
+    /// Whether the update was merged with changes made after `expected_version`
+    pub merged: bool,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct DeleteTaskRequest {
-    #[schemars(description = "The ID of the project containing the task")]
-    pub project_id: String,
+    #[schemars(
+        description = "The ID of the project. Required unless the server is scoped to a project"
+    )]
+    #[serde(default)]
+    pub project_id: Option<String>,
     #[schemars(description = "The ID of the task to delete")]
     pub task_id: String,
+    #[schemars(
+        description = "Set to true to confirm the deletion. Required when the server is configured to confirm destructive tools"
+    )]
+    #[serde(default)]
+    pub confirm: bool,
+    #[schemars(
+        description = "The task's `version` when you read it. The deletion is rejected as a conflict if someone changed the task since"
+    )]
+    #[serde(default)]
+    pub expected_version: Option<i64>,
 }
 
 #[derive(Debug, Serialize, schemars::JsonSchema)]
@@ -180,8 +309,11 @@ pub struct SimpleTaskResponse {
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct GetTaskRequest {
-    #[schemars(description = "The ID of the project containing the task")]
-    pub project_id: String,
+    #[schemars(
+        description = "The ID of the project. Required unless the server is scoped to a project"
+    )]
+    #[serde(default)]
+    pub project_id: Option<String>,
     #[schemars(description = "The ID of the task to retrieve")]
     pub task_id: String,
 }
@@ -193,22 +325,264 @@ pub struct GetTaskResponse {
     pub project_name: Option<String>,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetProjectConventionsRequest {
+    #[schemars(
+        description = "The ID of the project. Required unless the server is scoped to a project"
+    )]
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct ConventionEntry {
+    pub term: String,
+    pub definition: String,
+    #[schemars(description = "Repository paths where the term is implemented or used")]
+    pub file_refs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct GetProjectConventionsResponse {
+    pub success: bool,
+    pub project_id: String,
+    pub project_name: String,
+    #[schemars(description = "Approved glossary entries, most referenced first")]
+    pub entries: Vec<ConventionEntry>,
+    pub count: usize,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetExecutionLogsRequest {
+    #[schemars(
+        description = "The ID of the project. Required unless the server is scoped to a project"
+    )]
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[schemars(description = "The ID of the task whose agent run to read")]
+    pub task_id: String,
+    #[schemars(description = "Optional attempt ID; defaults to the latest attempt of the task")]
+    #[serde(default)]
+    pub attempt_id: Option<String>,
+    #[schemars(description = "Optional number of most recent entries to return (default 100)")]
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct ExecutionLogEntry {
+    #[schemars(
+        description = "user_message, assistant_message, tool_use, system_message, error_message or thinking"
+    )]
+    pub entry_type: String,
+    pub tool_name: Option<String>,
+    pub content: String,
+    pub timestamp: Option<String>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct GetExecutionLogsResponse {
+    pub success: bool,
+    pub attempt_id: String,
+    pub execution_process_id: String,
+    pub executor_type: String,
+    pub status: String,
+    #[schemars(description = "The prompt the coding agent was started with")]
+    pub prompt: Option<String>,
+    pub summary: Option<String>,
+    pub entries: Vec<ExecutionLogEntry>,
+    #[schemars(description = "Entries in the whole conversation, before applying the limit")]
+    pub total_entries: usize,
+}
+
+fn process_status_to_string(status: &ExecutionProcessStatus) -> String {
+    match status {
+        ExecutionProcessStatus::Running => "running".to_string(),
+        ExecutionProcessStatus::Completed => "completed".to_string(),
+        ExecutionProcessStatus::Failed => "failed".to_string(),
+        ExecutionProcessStatus::Killed => "killed".to_string(),
+    }
+}
+
+fn log_entry_type(entry_type: &NormalizedEntryType) -> (String, Option<String>) {
+    match entry_type {
+        NormalizedEntryType::UserMessage => ("user_message".to_string(), None),
+        NormalizedEntryType::AssistantMessage => ("assistant_message".to_string(), None),
+        NormalizedEntryType::ToolUse { tool_name, .. } => {
+            ("tool_use".to_string(), Some(tool_name.clone()))
+        }
+        NormalizedEntryType::SystemMessage => ("system_message".to_string(), None),
+        NormalizedEntryType::ErrorMessage => ("error_message".to_string(), None),
+        NormalizedEntryType::Thinking => ("thinking".to_string(), None),
+    }
+}
+
+fn tool_error(message: &str, details: Option<String>) -> CallToolResult {
+    let error_response = serde_json::json!({
+        "success": false,
+        "error": message,
+        "details": details
+    });
+    CallToolResult::error(vec![Content::text(
+        serde_json::to_string_pretty(&error_response).unwrap(),
+    )])
+}
+
+/// URI of a project's conventions resource
+fn conventions_uri(project_id: Uuid) -> String {
+    format!("codecommand://projects/{}/conventions", project_id)
+}
+
+fn parse_conventions_uri(uri: &str) -> Option<Uuid> {
+    uri.strip_prefix("codecommand://projects/")?
+        .strip_suffix("/conventions")
+        .and_then(|id| Uuid::parse_str(id).ok())
+}
+
+/// The glossary as a markdown document for the conventions resource
+fn conventions_markdown(project: &Project, entries: &[GlossaryEntry]) -> String {
+    let mut document = format!("# {} glossary\n", project.name);
+    if entries.is_empty() {
+        document.push_str("\nNo terms have been defined for this project yet.\n");
+    }
+    for entry in entries {
+        document.push_str(&format!("\n## {}\n\n{}\n", entry.term, entry.definition));
+        if !entry.file_refs.is_empty() {
+            document.push_str(&format!("\nSee: {}\n", entry.file_refs.join(", ")));
+        }
+    }
+    document
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskServer {
     pub pool: SqlitePool,
+    /// When set, every tool works on this project and `project_id` can be omitted
+    pub project_id: Option<Uuid>,
+    /// Destructive tools fail unless called with `confirm: true`
+    pub require_confirmation: bool,
+    pub started_at: Instant,
 }
 
 impl TaskServer {
     #[allow(dead_code)]
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            project_id: None,
+            require_confirmation: false,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// A server whose tools are limited to one project
+    pub fn scoped(pool: SqlitePool, project_id: Uuid) -> Self {
+        Self {
+            pool,
+            project_id: Some(project_id),
+            require_confirmation: false,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Make destructive tools fail unless called with `confirm: true`
+    #[allow(dead_code)]
+    pub fn requiring_confirmation(mut self, require_confirmation: bool) -> Self {
+        self.require_confirmation = require_confirmation;
+        self
+    }
+
+    /// Reject a destructive tool call that wasn't confirmed, when confirmation is required
+    fn check_confirmed(&self, tool: &str, confirm: bool) -> Result<(), CallToolResult> {
+        if !self.require_confirmation || confirm {
+            return Ok(());
+        }
+        let error_response = serde_json::json!({
+            "success": false,
+            "error": format!(
+                "{} is destructive and this server requires confirmation. Call it again with `confirm: true` once the user has agreed.",
+                tool
+            )
+        });
+        Err(CallToolResult::error(vec![Content::text(
+            serde_json::to_string_pretty(&error_response).unwrap(),
+        )]))
+    }
+
+    /// The project a tool call applies to: the scoped project, or the `project_id` the caller
+    /// passed. A scoped server rejects any other project.
+    fn resolve_project_id(&self, project_id: Option<&str>) -> Result<Uuid, CallToolResult> {
+        let error = |message: &str| {
+            let error_response = serde_json::json!({
+                "success": false,
+                "error": message,
+                "project_id": project_id
+            });
+            CallToolResult::error(vec![Content::text(
+                serde_json::to_string_pretty(&error_response)
+                    .unwrap_or_else(|_| message.to_string()),
+            )])
+        };
+
+        let requested = match project_id {
+            Some(project_id) => match Uuid::parse_str(project_id) {
+                Ok(uuid) => Some(uuid),
+                Err(_) => return Err(error("Invalid project ID format. Must be a valid UUID.")),
+            },
+            None => None,
+        };
+
+        match (self.project_id, requested) {
+            (Some(scope), Some(requested)) if scope != requested => Err(error(&format!(
+                "This server is scoped to project {}",
+                scope
+            ))),
+            (Some(scope), _) => Ok(scope),
+            (None, Some(requested)) => Ok(requested),
+            (None, None) => Err(error("`project_id` is required")),
+        }
     }
 }
 
 #[tool(tool_box)]
 impl TaskServer {
     #[tool(
-        description = "Create a new task/ticket in a project. Always pass the `project_id` of the project you want to create the task in - it is required!"
+        description = "Check the connection to the server. Returns its version, uptime, protocol version and the coding agents attempts can run with, without touching any data."
+    )]
+    async fn ping(&self, #[tool(aggr)] _request: PingRequest) -> Result<CallToolResult, RmcpError> {
+        let response = PingResponse {
+            success: true,
+            server: SERVER_NAME.to_string(),
+            version: SERVER_VERSION.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            uptime_seconds: self.started_at.elapsed().as_secs(),
+            executors: available_executors(),
+        };
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&response).unwrap(),
+        )]))
+    }
+
+    #[tool(
+        description = "Find the web UI of the running codecommand server, to link the user to it. Returns `server_url`, or null when no server is running. Checks each announced server answers and removes announcements left behind by servers that exited."
+    )]
+    async fn find_server(
+        &self,
+        #[tool(aggr)] _request: FindServerRequest,
+    ) -> Result<CallToolResult, RmcpError> {
+        let response = FindServerResponse {
+            success: true,
+            server_url: server_discovery::discover()
+                .await
+                .map(|server| server.url()),
+        };
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&response).unwrap(),
+        )]))
+    }
+
+    #[tool(
+        description = "Create a new task/ticket in a project. Pass the `project_id` of the project you want to create the task in unless the server is scoped to a project."
     )]
     async fn create_task(
         &self,
@@ -216,23 +590,42 @@ impl TaskServer {
             project_id,
             title,
             description,
+            estimate,
+            executor,
         }: CreateTaskRequest,
     ) -> Result<CallToolResult, RmcpError> {
-        // Parse project_id from string to UUID
-        let project_uuid = match Uuid::parse_str(&project_id) {
-            Ok(uuid) => uuid,
-            Err(_) => {
+        if !is_valid_estimate(estimate) {
+            let error_response = serde_json::json!({
+                "success": false,
+                "error": "Estimate must be a non-negative number",
+                "estimate": estimate
+            });
+            return Ok(CallToolResult::error(vec![Content::text(
+                serde_json::to_string_pretty(&error_response)
+                    .unwrap_or_else(|_| "Invalid estimate".to_string()),
+            )]));
+        }
+
+        if let Some(executor) = &executor {
+            let executors = available_executors();
+            if !executors.contains(executor) {
                 let error_response = serde_json::json!({
                     "success": false,
-                    "error": "Invalid project ID format. Must be a valid UUID.",
-                    "project_id": project_id
+                    "error": format!("Unknown executor '{}'", executor),
+                    "executors": executors
                 });
                 return Ok(CallToolResult::error(vec![Content::text(
                     serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Invalid project ID format".to_string()),
+                        .unwrap_or_else(|_| "Unknown executor".to_string()),
                 )]));
             }
+        }
+
+        let project_uuid = match self.resolve_project_id(project_id.as_deref()) {
+            Ok(uuid) => uuid,
+            Err(error) => return Ok(error),
         };
+        let project_id = project_uuid.to_string();
 
         // Check if project exists
         match Project::exists(&self.pool, project_uuid).await {
@@ -267,10 +660,20 @@ impl TaskServer {
             project_id: project_uuid,
             title: title.clone(),
             description: description.clone(),
+            estimate,
         };
 
-        match Task::create(&self.pool, &create_task_data, task_id).await {
-            Ok(_task) => {
+        let created = match Task::create(&self.pool, &create_task_data, task_id).await {
+            Ok(_task) => match executor.as_deref() {
+                Some(executor) => {
+                    Task::set_preferred_executor(&self.pool, task_id, Some(executor)).await
+                }
+                None => Ok(()),
+            },
+            Err(e) => Err(e),
+        };
+        match created {
+            Ok(()) => {
                 let success_response = CreateTaskResponse {
                     success: true,
                     task_id: task_id.to_string(),
@@ -297,17 +700,30 @@ impl TaskServer {
         }
     }
 
-    #[tool(description = "List all the available projects")]
+    #[tool(description = "List all the available projects, or only the scoped project")]
     async fn list_projects(
         &self,
         #[tool(aggr)] _request: ListProjectsRequest,
     ) -> Result<CallToolResult, RmcpError> {
-        match Project::find_all(&self.pool).await {
-            Ok(projects) => {
+        let projects = match Project::find_all(&self.pool).await {
+            Ok(projects) => Project::pending_task_counts(&self.pool)
+                .await
+                .map(|pending| (projects, pending)),
+            Err(e) => Err(e),
+        };
+
+        match projects {
+            Ok((projects, pending_task_counts)) => {
+                let projects: Vec<_> = projects
+                    .into_iter()
+                    .filter(|project| self.project_id.is_none_or(|scope| project.id == scope))
+                    .collect();
                 let count = projects.len();
                 let project_summaries: Vec<ProjectSummary> = projects
                     .into_iter()
                     .map(|project| {
+                        let pending_task_count =
+                            pending_task_counts.get(&project.id).copied().unwrap_or(0);
                         let project_with_branch = project.with_branch_info();
                         ProjectSummary {
                             id: project_with_branch.id.to_string(),
@@ -316,6 +732,7 @@ impl TaskServer {
                             setup_script: project_with_branch.setup_script,
                             dev_script: project_with_branch.dev_script,
                             current_branch: project_with_branch.current_branch,
+                            pending_task_count,
                             created_at: project_with_branch.created_at.to_rfc3339(),
                             updated_at: project_with_branch.updated_at.to_rfc3339(),
                         }
@@ -348,7 +765,7 @@ impl TaskServer {
     }
 
     #[tool(
-        description = "List all the task/tickets in a project with optional filtering and execution status. `project_id` is required!"
+        description = "List all the task/tickets in a project with optional filtering and execution status. `project_id` is required unless the server is scoped to a project."
     )]
     async fn list_tasks(
         &self,
@@ -358,20 +775,11 @@ impl TaskServer {
             limit,
         }: ListTasksRequest,
     ) -> Result<CallToolResult, RmcpError> {
-        let project_uuid = match Uuid::parse_str(&project_id) {
+        let project_uuid = match self.resolve_project_id(project_id.as_deref()) {
             Ok(uuid) => uuid,
-            Err(_) => {
-                let error_response = serde_json::json!({
-                    "success": false,
-                    "error": "Invalid project ID format. Must be a valid UUID.",
-                    "project_id": project_id
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Invalid project ID format".to_string()),
-                )]));
-            }
+            Err(error) => return Ok(error),
         };
+        let project_id = project_uuid.to_string();
 
         let status_filter = if let Some(ref status_str) = status {
             match parse_task_status(status_str) {
@@ -445,11 +853,14 @@ impl TaskServer {
                         title: task.title,
                         description: task.description,
                         status: task_status_to_string(&task.status),
+                        estimate: task.estimate,
                         created_at: task.created_at.to_rfc3339(),
                         updated_at: task.updated_at.to_rfc3339(),
+                        version: task.version,
                         has_in_progress_attempt: Some(task.has_in_progress_attempt),
                         has_merged_attempt: Some(task.has_merged_attempt),
                         has_failed_attempt: Some(task.has_failed_attempt),
+                        blocked_by: Some(task.blocked_by.iter().map(|id| id.to_string()).collect()),
                     })
                     .collect();
 
@@ -487,7 +898,7 @@ impl TaskServer {
     }
 
     #[tool(
-        description = "Update an existing task/ticket's title, description, or status. `project_id` and `task_id` are required! `title`, `description`, and `status` are optional."
+        description = "Update an existing task/ticket's title, description, status, or estimate. `task_id` is required, as is `project_id` unless the server is scoped to a project. `title`, `description`, `status`, and `estimate` are optional. Pass the `version` you read as `expected_version` to avoid overwriting someone else's changes: a conflicting update is rejected with the current task, to merge into and retry."
     )]
     async fn update_task(
         &self,
@@ -497,21 +908,15 @@ impl TaskServer {
             title,
             description,
             status,
+            estimate,
+            expected_version,
         }: UpdateTaskRequest,
     ) -> Result<CallToolResult, RmcpError> {
-        let project_uuid = match Uuid::parse_str(&project_id) {
+        let project_uuid = match self.resolve_project_id(project_id.as_deref()) {
             Ok(uuid) => uuid,
-            Err(_) => {
-                let error_response = serde_json::json!({
-                    "success": false,
-                    "error": "Invalid project ID format. Must be a valid UUID.",
-                    "project_id": project_id
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response).unwrap(),
-                )]));
-            }
+            Err(error) => return Ok(error),
         };
+        let project_id = project_uuid.to_string();
 
         let task_uuid = match Uuid::parse_str(&task_id) {
             Ok(uuid) => uuid,
@@ -545,69 +950,62 @@ impl TaskServer {
             None
         };
 
-        let current_task =
-            match Task::find_by_id_and_project_id(&self.pool, task_uuid, project_uuid).await {
-                Ok(Some(task)) => task,
-                Ok(None) => {
-                    let error_response = serde_json::json!({
-                        "success": false,
-                        "error": "Task not found in the specified project",
-                        "task_id": task_id,
-                        "project_id": project_id
-                    });
-                    return Ok(CallToolResult::error(vec![Content::text(
-                        serde_json::to_string_pretty(&error_response).unwrap(),
-                    )]));
-                }
-                Err(e) => {
-                    let error_response = serde_json::json!({
-                        "success": false,
-                        "error": "Failed to retrieve task",
-                        "details": e.to_string()
-                    });
-                    return Ok(CallToolResult::error(vec![Content::text(
-                        serde_json::to_string_pretty(&error_response).unwrap(),
-                    )]));
-                }
-            };
-
-        let new_title = title.unwrap_or(current_task.title);
-        let new_description = description.or(current_task.description);
-        let new_status = status_enum.unwrap_or(current_task.status);
+        if !is_valid_estimate(estimate) {
+            let error_response = serde_json::json!({
+                "success": false,
+                "error": "Estimate must be a non-negative number",
+                "estimate": estimate
+            });
+            return Ok(CallToolResult::error(vec![Content::text(
+                serde_json::to_string_pretty(&error_response).unwrap(),
+            )]));
+        }
 
-        match Task::update(
+        let update = UpdateTask {
+            title,
+            description,
+            status: status_enum,
+            estimate,
+            expected_version,
+        };
+        match Task::update_checked(
             &self.pool,
             task_uuid,
             project_uuid,
-            new_title,
-            new_description,
-            new_status,
+            &update,
+            TaskUpdateSource::Mcp,
         )
         .await
         {
-            Ok(updated_task) => {
-                let task_summary = TaskSummary {
-                    id: updated_task.id.to_string(),
-                    title: updated_task.title,
-                    description: updated_task.description,
-                    status: task_status_to_string(&updated_task.status),
-                    created_at: updated_task.created_at.to_rfc3339(),
-                    updated_at: updated_task.updated_at.to_rfc3339(),
-                    has_in_progress_attempt: None,
-                    has_merged_attempt: None,
-                    has_failed_attempt: None,
+            Ok(Some(TaskUpdateOutcome::Updated { task, merged })) => {
+                let message = if merged {
+                    "Task updated, merged with changes made since expected_version"
+                } else {
+                    "Task updated successfully"
                 };
-
                 let response = UpdateTaskResponse {
                     success: true,
-                    message: "Task updated successfully".to_string(),
-                    task: Some(task_summary),
+                    message: message.to_string(),
+                    task: Some(task_summary(task)),
+                    merged,
                 };
 
                 Ok(CallToolResult::success(vec![Content::text(
                     serde_json::to_string_pretty(&response).unwrap(),
                 )]))
             }
+            Ok(Some(TaskUpdateOutcome::Conflict(conflict))) => Ok(conflict_result(conflict)),
+            Ok(None) => {
+                let error_response = serde_json::json!({
+                    "success": false,
+                    "error": "Task not found in the specified project",
+                    "task_id": task_id,
+                    "project_id": project_id
+                });
+                Ok(CallToolResult::error(vec![Content::text(
+                    serde_json::to_string_pretty(&error_response).unwrap(),
+                )]))
+            }
             Err(e) => {
                 let error_response = serde_json::json!({
                     "success": false,
@@ -622,26 +1020,23 @@ impl TaskServer {
     }
 
     #[tool(
-        description = "Delete a task/ticket from a project. `project_id` and `task_id` are required!"
+        description = "Delete a task/ticket from a project. `task_id` is required, as is `project_id` unless the server is scoped to a project. Pass `confirm: true` when the server requires confirmation for destructive tools, and the `version` you read as `expected_version` to keep from deleting a task someone changed since."
     )]
     async fn delete_task(
         &self,
         #[tool(aggr)] DeleteTaskRequest {
             project_id,
             task_id,
+            confirm,
+            expected_version,
         }: DeleteTaskRequest,
     ) -> Result<CallToolResult, RmcpError> {
-        let project_uuid = match Uuid::parse_str(&project_id) {
+        if let Err(error) = self.check_confirmed("delete_task", confirm) {
+            return Ok(error);
+        }
+        let project_uuid = match self.resolve_project_id(project_id.as_deref()) {
             Ok(uuid) => uuid,
-            Err(_) => {
-                let error_response = serde_json::json!({
-                    "success": false,
-                    "error": "Invalid project ID format"
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response).unwrap(),
-                )]));
-            }
+            Err(error) => return Ok(error),
         };
 
         let task_uuid = match Uuid::parse_str(&task_id) {
@@ -657,9 +1052,30 @@ impl TaskServer {
             }
         };
 
-        match Task::exists(&self.pool, task_uuid, project_uuid).await {
-            Ok(true) => {
-                // Delete the task
+        match Task::find_by_id_and_project_id(&self.pool, task_uuid, project_uuid).await {
+            Ok(Some(task)) => {
+                match Task::version_conflict(
+                    &self.pool,
+                    &task,
+                    expected_version,
+                    TaskUpdateSource::Mcp,
+                )
+                .await
+                {
+                    Ok(Some(conflict)) => return Ok(conflict_result(conflict)),
+                    Ok(None) => {}
+                    Err(e) => {
+                        let error_response = serde_json::json!({
+                            "success": false,
+                            "error": "Failed to check the task's version",
+                            "details": e.to_string()
+                        });
+                        return Ok(CallToolResult::error(vec![Content::text(
+                            serde_json::to_string_pretty(&error_response).unwrap(),
+                        )]));
+                    }
+                }
+
                 match Task::delete(&self.pool, task_uuid, project_uuid).await {
                     Ok(rows_affected) => {
                         if rows_affected > 0 {
@@ -693,7 +1109,7 @@ impl TaskServer {
                     }
                 }
             }
-            Ok(false) => {
+            Ok(None) => {
                 let error_response = serde_json::json!({
                     "success": false,
                     "error": "Task not found in the specified project"
@@ -716,21 +1132,34 @@ impl TaskServer {
     }
 
     #[tool(
-        description = "Get detailed information about a specific task/ticket. `project_id` and `task_id` are required!"
+        description = "Get the project's glossary: internal terms and what they mean in this codebase, with the files they relate to. Check it before working with unfamiliar project jargon. `project_id` is required unless the server is scoped to a project."
     )]
-    async fn get_task(
+    async fn get_project_conventions(
         &self,
-        #[tool(aggr)] GetTaskRequest {
-            project_id,
-            task_id,
-        }: GetTaskRequest,
+        #[tool(aggr)] GetProjectConventionsRequest { project_id }: GetProjectConventionsRequest,
     ) -> Result<CallToolResult, RmcpError> {
-        let project_uuid = match Uuid::parse_str(&project_id) {
+        let project_uuid = match self.resolve_project_id(project_id.as_deref()) {
             Ok(uuid) => uuid,
-            Err(_) => {
+            Err(error) => return Ok(error),
+        };
+
+        let project = match Project::find_by_id(&self.pool, project_uuid).await {
+            Ok(Some(project)) => project,
+            Ok(None) => {
+                let error_response = serde_json::json!({
+                    "success": false,
+                    "error": "Project not found",
+                    "project_id": project_uuid.to_string()
+                });
+                return Ok(CallToolResult::error(vec![Content::text(
+                    serde_json::to_string_pretty(&error_response).unwrap(),
+                )]));
+            }
+            Err(e) => {
                 let error_response = serde_json::json!({
                     "success": false,
-                    "error": "Invalid project ID format"
+                    "error": "Failed to retrieve project",
+                    "details": e.to_string()
                 });
                 return Ok(CallToolResult::error(vec![Content::text(
                     serde_json::to_string_pretty(&error_response).unwrap(),
@@ -738,6 +1167,62 @@ impl TaskServer {
             }
         };
 
+        match GlossaryEntry::find_by_project_id(
+            &self.pool,
+            project_uuid,
+            Some(GlossaryEntryStatus::Approved),
+        )
+        .await
+        {
+            Ok(entries) => {
+                let entries: Vec<_> = entries
+                    .into_iter()
+                    .map(|entry| ConventionEntry {
+                        term: entry.term,
+                        definition: entry.definition,
+                        file_refs: entry.file_refs,
+                    })
+                    .collect();
+                let response = GetProjectConventionsResponse {
+                    success: true,
+                    project_id: project.id.to_string(),
+                    project_name: project.name,
+                    count: entries.len(),
+                    entries,
+                };
+                Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string_pretty(&response)
+                        .unwrap_or_else(|_| "Failed to serialize conventions".to_string()),
+                )]))
+            }
+            Err(e) => {
+                let error_response = serde_json::json!({
+                    "success": false,
+                    "error": "Failed to retrieve the project glossary",
+                    "details": e.to_string()
+                });
+                Ok(CallToolResult::error(vec![Content::text(
+                    serde_json::to_string_pretty(&error_response).unwrap(),
+                )]))
+            }
+        }
+    }
+
+    #[tool(
+        description = "Get detailed information about a specific task/ticket. `task_id` is required, as is `project_id` unless the server is scoped to a project."
+    )]
+    async fn get_task(
+        &self,
+        #[tool(aggr)] GetTaskRequest {
+            project_id,
+            task_id,
+        }: GetTaskRequest,
+    ) -> Result<CallToolResult, RmcpError> {
+        let project_uuid = match self.resolve_project_id(project_id.as_deref()) {
+            Ok(uuid) => uuid,
+            Err(error) => return Ok(error),
+        };
+
         let task_uuid = match Uuid::parse_str(&task_id) {
             Ok(uuid) => uuid,
             Err(_) => {
@@ -757,17 +1242,7 @@ impl TaskServer {
 
         match (task_result, project_result) {
             (Ok(Some(task)), Ok(Some(project))) => {
-                let task_summary = TaskSummary {
-                    id: task.id.to_string(),
-                    title: task.title,
-                    description: task.description,
-                    status: task_status_to_string(&task.status),
-                    created_at: task.created_at.to_rfc3339(),
-                    updated_at: task.updated_at.to_rfc3339(),
-                    has_in_progress_attempt: None,
-                    has_merged_attempt: None,
-                    has_failed_attempt: None,
-                };
+                let task_summary = task_summary(task);
 
                 let response = GetTaskResponse {
                     success: true,
@@ -800,21 +1275,429 @@ impl TaskServer {
             }
         }
     }
+
+    #[tool(
+        description = "Get the conversation of the latest coding agent run of a task: the prompt it was started with and its messages and tool calls. `task_id` is required, as is `project_id` unless the server is scoped to a project. Pass `attempt_id` to read an older attempt."
+    )]
+    async fn get_execution_logs(
+        &self,
+        #[tool(aggr)] GetExecutionLogsRequest {
+            project_id,
+            task_id,
+            attempt_id,
+            limit,
+        }: GetExecutionLogsRequest,
+    ) -> Result<CallToolResult, RmcpError> {
+        let project_uuid = match self.resolve_project_id(project_id.as_deref()) {
+            Ok(uuid) => uuid,
+            Err(error) => return Ok(error),
+        };
+        let Ok(task_uuid) = Uuid::parse_str(&task_id) else {
+            return Ok(tool_error("Invalid task ID format", None));
+        };
+        let attempt_uuid = match attempt_id.as_deref().map(Uuid::parse_str) {
+            Some(Ok(uuid)) => Some(uuid),
+            Some(Err(_)) => return Ok(tool_error("Invalid attempt ID format", None)),
+            None => None,
+        };
+
+        match Task::find_by_id_and_project_id(&self.pool, task_uuid, project_uuid).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return Ok(tool_error("Task not found in this project", None)),
+            Err(e) => return Ok(tool_error("Failed to retrieve task", Some(e.to_string()))),
+        }
+
+        // Attempts come newest first
+        let attempts = match TaskAttempt::find_by_task_id(&self.pool, task_uuid).await {
+            Ok(attempts) => attempts,
+            Err(e) => {
+                return Ok(tool_error(
+                    "Failed to retrieve task attempts",
+                    Some(e.to_string()),
+                ))
+            }
+        };
+        let attempt = match attempt_uuid {
+            Some(attempt_uuid) => attempts.into_iter().find(|a| a.id == attempt_uuid),
+            None => attempts.into_iter().next(),
+        };
+        let Some(attempt) = attempt else {
+            return Ok(tool_error("No attempt found for this task", None));
+        };
+
+        let processes =
+            match ExecutionProcess::find_by_task_attempt_id(&self.pool, attempt.id).await {
+                Ok(processes) => processes,
+                Err(e) => {
+                    return Ok(tool_error(
+                        "Failed to retrieve execution processes",
+                        Some(e.to_string()),
+                    ))
+                }
+            };
+        let Some(process) = processes
+            .into_iter()
+            .rev()
+            .find(|p| p.process_type == ExecutionProcessType::CodingAgent)
+        else {
+            return Ok(tool_error("The attempt has no coding agent run yet", None));
+        };
+
+        let conversation =
+            match log_normalization::current_normalization(&self.pool, &process).await {
+                Ok(conversation) => conversation,
+                Err(e) => {
+                    return Ok(tool_error(
+                        "Failed to read the execution logs",
+                        Some(e.to_string()),
+                    ))
+                }
+            };
+
+        let total_entries = conversation.entries.len();
+        let skip = total_entries.saturating_sub(limit.unwrap_or(DEFAULT_LOG_ENTRIES));
+        let entries = conversation
+            .entries
+            .into_iter()
+            .skip(skip)
+            .map(|entry| {
+                let (entry_type, tool_name) = log_entry_type(&entry.entry_type);
+                ExecutionLogEntry {
+                    entry_type,
+                    tool_name,
+                    content: entry.content,
+                    timestamp: entry.timestamp,
+                }
+            })
+            .collect();
+
+        let response = GetExecutionLogsResponse {
+            success: true,
+            attempt_id: attempt.id.to_string(),
+            execution_process_id: process.id.to_string(),
+            executor_type: conversation.executor_type,
+            status: process_status_to_string(&process.status),
+            prompt: conversation.prompt,
+            summary: conversation.summary,
+            entries,
+            total_entries,
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&response).unwrap(),
+        )]))
+    }
 }
 
 #[tool(tool_box)]
 impl ServerHandler for TaskServer {
     fn get_info(&self) -> ServerInfo {
+        let instructions = match self.project_id {
+            Some(project_id) => format!("A task management server scoped to project {}. If you need to create or update tickets or tasks then use these tools. They all work on this project, so `project_id` can be omitted. TOOLS: 'ping', 'find_server', 'list_projects', 'list_tasks', 'create_task', 'get_task', 'update_task', 'delete_task', 'get_project_conventions', 'get_execution_logs'. Make sure to pass `task_id` where required. You can use list_tasks to get the available ids. The project glossary is also available as the resource codecommand://projects/{}/conventions.", project_id, project_id),
+            None => "A task and project management server. If you need to create or update tickets or tasks then use these tools. Most of them absolutely require that you pass the `project_id` of the project that you are currently working on. This should be provided to you. TOOLS: 'ping', 'find_server', 'list_projects', 'list_tasks', 'create_task', 'get_task', 'update_task', 'delete_task', 'get_project_conventions', 'get_execution_logs'. Make sure to pass `project_id` or `task_id` where required. You can use list tools to get the available ids. Each project's glossary is also available as the resource codecommand://projects/<project_id>/conventions.".to_string(),
+        };
+
         ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
+            protocol_version: PROTOCOL_VERSION,
             capabilities: ServerCapabilities::builder()
+                .enable_resources()
                 .enable_tools()
                 .build(),
             server_info: Implementation {
-                name: "codecommand".to_string(),
-                version: "1.0.0".to_string(),
+                name: SERVER_NAME.to_string(),
+                version: SERVER_VERSION.to_string(),
             },
-            instructions: Some("A task and project management server. If you need to create or update tickets or tasks then use these tools. Most of them absolutely require that you pass the `project_id` of the project that you are currently working on. This should be provided to you. TOOLS: 'list_projects', 'list_tasks', 'create_task', 'get_task', 'update_task', 'delete_task'. Make sure to pass `project_id` or `task_id` where required. You can use list tools to get the available ids.".to_string()),
+            instructions: Some(instructions),
         }
     }
+
+    /// One conventions document per visible project
+    async fn list_resources(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, RmcpError> {
+        let projects = Project::find_all(&self.pool)
+            .await
+            .map_err(|e| RmcpError::internal_error(e.to_string(), None))?;
+
+        let resources: Vec<Resource> = projects
+            .into_iter()
+            .filter(|project| self.project_id.is_none_or(|scope| project.id == scope))
+            .map(|project| {
+                let mut resource = RawResource::new(
+                    conventions_uri(project.id),
+                    format!("{} conventions", project.name),
+                );
+                resource.description = Some("Glossary of the project's internal terms".to_string());
+                resource.mime_type = Some("text/markdown".to_string());
+                resource.no_annotation()
+            })
+            .collect();
+
+        Ok(ListResourcesResult {
+            resources,
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, RmcpError> {
+        let not_found = || {
+            RmcpError::resource_not_found(
+                "resource_not_found",
+                Some(serde_json::json!({ "uri": uri })),
+            )
+        };
+        let project_id = parse_conventions_uri(&uri).ok_or_else(not_found)?;
+        if self.project_id.is_some_and(|scope| scope != project_id) {
+            return Err(not_found());
+        }
+
+        let project = Project::find_by_id(&self.pool, project_id)
+            .await
+            .map_err(|e| RmcpError::internal_error(e.to_string(), None))?
+            .ok_or_else(not_found)?;
+        let entries = GlossaryEntry::find_by_project_id(
+            &self.pool,
+            project_id,
+            Some(GlossaryEntryStatus::Approved),
+        )
+        .await
+        .map_err(|e| RmcpError::internal_error(e.to_string(), None))?;
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: uri.clone(),
+                mime_type: Some("text/markdown".to_string()),
+                text: conventions_markdown(&project, &entries),
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_project, memory_pool};
+
+    async fn setup() -> (SqlitePool, Uuid) {
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, "/tmp/scoped").await;
+        (pool, project_id)
+    }
+
+    fn is_error(result: &CallToolResult) -> bool {
+        result.is_error == Some(true)
+    }
+
+    #[tokio::test]
+    async fn test_scoped_server_injects_project_id() {
+        let (pool, project_id) = setup().await;
+        let server = TaskServer::scoped(pool.clone(), project_id);
+
+        let created = server
+            .create_task(CreateTaskRequest {
+                project_id: None,
+                title: "Scoped task".to_string(),
+                description: None,
+                estimate: None,
+                executor: Some("gemini".to_string()),
+            })
+            .await
+            .unwrap();
+        assert!(!is_error(&created));
+
+        let tasks = Task::find_by_project_id_with_attempt_status(&pool, project_id)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].title, "Scoped task");
+        assert_eq!(
+            Task::preferred_executor(&pool, tasks[0].id)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("gemini")
+        );
+
+        let listed = server
+            .list_tasks(ListTasksRequest {
+                project_id: None,
+                status: None,
+                limit: None,
+            })
+            .await
+            .unwrap();
+        assert!(!is_error(&listed));
+    }
+
+    #[tokio::test]
+    async fn test_pending_task_count_skips_started_and_running_tasks() {
+        let (pool, project_id) = setup().await;
+        let add_task = |status: &'static str| {
+            let pool = pool.clone();
+            async move {
+                let task_id = Uuid::new_v4();
+                sqlx::query(
+                    "INSERT INTO tasks (id, project_id, title, status) VALUES ($1, $2, 'task', $3)",
+                )
+                .bind(task_id)
+                .bind(project_id)
+                .bind(status)
+                .execute(&pool)
+                .await
+                .unwrap();
+                task_id
+            }
+        };
+
+        add_task("todo").await;
+        add_task("todo").await;
+        add_task("done").await;
+        let running = add_task("todo").await;
+        let attempt_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO task_attempts (id, task_id, worktree_path, branch) VALUES ($1, $2, '/tmp/wt', 'b')",
+        )
+        .bind(attempt_id)
+        .bind(running)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO execution_processes (id, task_attempt_id, command, working_directory) VALUES ($1, $2, 'claude', '/tmp/wt')",
+        )
+        .bind(Uuid::new_v4())
+        .bind(attempt_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let counts = Project::pending_task_counts(&pool).await.unwrap();
+        assert_eq!(counts.get(&project_id), Some(&2));
+    }
+
+    #[tokio::test]
+    async fn test_project_id_is_checked_against_scope() {
+        let (pool, project_id) = setup().await;
+        let list = |project_id: Option<String>| ListTasksRequest {
+            project_id,
+            status: None,
+            limit: None,
+        };
+
+        // An unscoped server still needs the project id
+        let unscoped = TaskServer::new(pool.clone());
+        assert!(is_error(&unscoped.list_tasks(list(None)).await.unwrap()));
+        assert!(!is_error(
+            &unscoped
+                .list_tasks(list(Some(project_id.to_string())))
+                .await
+                .unwrap()
+        ));
+
+        // A scoped server accepts its own project but no other
+        let scoped = TaskServer::scoped(pool, project_id);
+        assert!(!is_error(
+            &scoped
+                .list_tasks(list(Some(project_id.to_string())))
+                .await
+                .unwrap()
+        ));
+        assert!(is_error(
+            &scoped
+                .list_tasks(list(Some(Uuid::new_v4().to_string())))
+                .await
+                .unwrap()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timestamps_match_between_rest_and_mcp() {
+        let (pool, project_id) = setup().await;
+        let task_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO tasks (id, project_id, title, created_at, updated_at) VALUES ($1, $2, 'task', '2024-05-01 12:30:00', '2024-05-02 08:00:00')",
+        )
+        .bind(task_id)
+        .bind(project_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let task = Task::find_by_id(&pool, task_id).await.unwrap().unwrap();
+        let rest = serde_json::to_value(&task).unwrap();
+        assert_eq!(rest["created_at"], "2024-05-01T12:30:00+00:00");
+
+        let result = TaskServer::scoped(pool, project_id)
+            .get_task(GetTaskRequest {
+                project_id: None,
+                task_id: task_id.to_string(),
+            })
+            .await
+            .unwrap();
+        let mcp: serde_json::Value =
+            serde_json::from_str(&result.content[0].raw.as_text().unwrap().text).unwrap();
+        assert_eq!(mcp["task"]["created_at"], rest["created_at"]);
+        assert_eq!(mcp["task"]["updated_at"], rest["updated_at"]);
+
+        let parsed: Task = serde_json::from_value(rest).unwrap();
+        assert_eq!(parsed.created_at, task.created_at);
+    }
+
+    #[tokio::test]
+    async fn test_delete_task_requires_confirmation_when_configured() {
+        let (pool, project_id) = setup().await;
+        let server = TaskServer::scoped(pool.clone(), project_id).requiring_confirmation(true);
+        let task_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO tasks (id, project_id, title) VALUES ($1, $2, 'keep me')")
+            .bind(task_id)
+            .bind(project_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let delete = |confirm| DeleteTaskRequest {
+            project_id: None,
+            task_id: task_id.to_string(),
+            confirm,
+            expected_version: None,
+        };
+
+        let rejected = server.delete_task(delete(false)).await.unwrap();
+        assert!(is_error(&rejected));
+        assert!(Task::exists(&pool, task_id, project_id).await.unwrap());
+
+        let deleted = server.delete_task(delete(true)).await.unwrap();
+        assert!(!is_error(&deleted));
+        assert!(!Task::exists(&pool, task_id, project_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_ping_reports_server_info_without_database() {
+        // A lazy pool only opens a connection once something queries it
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let server = TaskServer::new(pool.clone());
+
+        let result = server.ping(PingRequest {}).await.unwrap();
+        assert!(!is_error(&result));
+        let ping: serde_json::Value =
+            serde_json::from_str(&result.content[0].raw.as_text().unwrap().text).unwrap();
+        let info = server.get_info();
+        assert_eq!(ping["server"], info.server_info.name);
+        assert_eq!(ping["version"], info.server_info.version);
+        assert_eq!(ping["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(ping["protocol_version"], "2024-11-05");
+        assert!(ping["uptime_seconds"].is_u64());
+        assert!(ping["executors"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("claude")));
+        // Looking for a running server is left to `find_server`
+        assert!(ping.get("server_url").is_none());
+        assert_eq!(pool.size(), 0);
+    }
 }
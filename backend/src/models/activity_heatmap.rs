@@ -0,0 +1,278 @@
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// Granularity of the heatmap grid
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum HeatmapBucketSize {
+    /// One cell per hour of day for each day of week (7 x 24)
+    #[default]
+    Hour,
+    /// One cell per day of week
+    Day,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ActivityHeatmapBucket {
+    /// 0 = Monday ... 6 = Sunday, in the configured timezone
+    pub day_of_week: u32,
+    /// Hour of day (0-23); `None` when bucketing by day
+    pub hour: Option<u32>,
+    /// Executions that started in this bucket
+    #[ts(type = "number")]
+    pub execution_count: u64,
+    /// Runtime that fell inside this bucket, across all executions
+    pub total_runtime_secs: f64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ActivityHeatmap {
+    pub project_id: Option<Uuid>,
+    pub timezone: String,
+    pub range_days: u32,
+    pub bucket: HeatmapBucketSize,
+    /// Every cell of the grid (including empty ones), ordered by day then hour
+    pub buckets: Vec<ActivityHeatmapBucket>,
+}
+
+/// Parse a range such as `30d`, `2w` or `48h` into whole days (at least 1, at most 365)
+pub fn parse_range_days(range: &str) -> Option<u32> {
+    let range = range.trim();
+    let unit = range.chars().last()?;
+    let value: u32 = range[..range.len() - unit.len_utf8()].parse().ok()?;
+    let days = match unit {
+        'd' => value,
+        'w' => value.checked_mul(7)?,
+        'h' => value.div_ceil(24),
+        _ => return None,
+    };
+
+    (1..=365).contains(&days).then_some(days)
+}
+
+/// Resolve an IANA timezone name, defaulting to UTC when none is configured
+pub fn parse_timezone(timezone: Option<&str>) -> Option<Tz> {
+    match timezone.map(str::trim).filter(|tz| !tz.is_empty()) {
+        Some(name) => name.parse().ok(),
+        None => Some(Tz::UTC),
+    }
+}
+
+fn bucket_index(bucket: HeatmapBucketSize, local: &DateTime<Tz>) -> usize {
+    let day = local.weekday().num_days_from_monday() as usize;
+    match bucket {
+        HeatmapBucketSize::Hour => day * 24 + local.hour() as usize,
+        HeatmapBucketSize::Day => day,
+    }
+}
+
+/// Bucket executions by local start time, splitting each execution's runtime across every
+/// local hour it overlaps. Hours are walked in UTC so repeated or skipped wall-clock hours
+/// around daylight-saving transitions are counted exactly once per real hour.
+pub fn build_buckets(
+    executions: &[(DateTime<Utc>, Option<DateTime<Utc>>)],
+    timezone: Tz,
+    bucket: HeatmapBucketSize,
+    now: DateTime<Utc>,
+) -> Vec<ActivityHeatmapBucket> {
+    let mut buckets: Vec<ActivityHeatmapBucket> = match bucket {
+        HeatmapBucketSize::Hour => (0..7)
+            .flat_map(|day| {
+                (0..24).map(move |hour| ActivityHeatmapBucket {
+                    day_of_week: day,
+                    hour: Some(hour),
+                    execution_count: 0,
+                    total_runtime_secs: 0.0,
+                })
+            })
+            .collect(),
+        HeatmapBucketSize::Day => (0..7)
+            .map(|day| ActivityHeatmapBucket {
+                day_of_week: day,
+                hour: None,
+                execution_count: 0,
+                total_runtime_secs: 0.0,
+            })
+            .collect(),
+    };
+
+    for (started_at, completed_at) in executions {
+        let start = *started_at;
+        // Still-running executions count up to now
+        let end = completed_at.unwrap_or(now).max(start);
+
+        buckets[bucket_index(bucket, &start.with_timezone(&timezone))].execution_count += 1;
+
+        let mut cursor = start;
+        while cursor < end {
+            let local = cursor.with_timezone(&timezone);
+            // Offsets are whole quarter-hours, so the next local hour starts when the
+            // local minutes and seconds roll over
+            let into_hour = Duration::seconds(i64::from(local.minute() * 60 + local.second()))
+                + Duration::nanoseconds(i64::from(local.nanosecond()));
+            let segment_end = (cursor + Duration::hours(1) - into_hour).min(end);
+
+            let secs = (segment_end - cursor).num_milliseconds() as f64 / 1000.0;
+            buckets[bucket_index(bucket, &local)].total_runtime_secs += secs;
+            cursor = segment_end;
+        }
+    }
+
+    buckets
+}
+
+impl ActivityHeatmap {
+    /// Build the heatmap for one project, or across all projects when `project_id` is `None`
+    pub async fn compute(
+        pool: &SqlitePool,
+        project_id: Option<Uuid>,
+        range_days: u32,
+        bucket: HeatmapBucketSize,
+        timezone: Tz,
+    ) -> Result<Self, sqlx::Error> {
+        let now = Utc::now();
+        let since = now - Duration::days(i64::from(range_days));
+
+        let records = sqlx::query!(
+            r#"SELECT ep.started_at   AS "started_at!: DateTime<Utc>",
+                      ep.completed_at AS "completed_at: DateTime<Utc>"
+               FROM execution_processes ep
+               JOIN task_attempts ta ON ta.id = ep.task_attempt_id
+               JOIN tasks t ON t.id = ta.task_id
+               WHERE datetime(ep.started_at) >= datetime($1)
+                 AND ($2 IS NULL OR t.project_id = $2)
+                 AND ta.benchmark_run_id IS NULL"#,
+            since,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let executions: Vec<_> = records
+            .into_iter()
+            .map(|record| (record.started_at, record.completed_at))
+            .collect();
+
+        Ok(Self {
+            project_id,
+            timezone: timezone.name().to_string(),
+            range_days,
+            bucket,
+            buckets: build_buckets(&executions, timezone, bucket, now),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn cell(
+        buckets: &[ActivityHeatmapBucket],
+        day_of_week: u32,
+        hour: u32,
+    ) -> &ActivityHeatmapBucket {
+        buckets
+            .iter()
+            .find(|b| b.day_of_week == day_of_week && b.hour == Some(hour))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_range_days() {
+        assert_eq!(parse_range_days("30d"), Some(30));
+        assert_eq!(parse_range_days("2w"), Some(14));
+        assert_eq!(parse_range_days("36h"), Some(2));
+        assert_eq!(parse_range_days("0d"), None);
+        assert_eq!(parse_range_days("400d"), None);
+        assert_eq!(parse_range_days("d"), None);
+        assert_eq!(parse_range_days("30x"), None);
+    }
+
+    #[test]
+    fn test_execution_spanning_hour_boundary_splits_runtime() {
+        // Wednesday 2025-07-02 09:45 - 10:15 UTC
+        let executions = vec![(utc(2025, 7, 2, 9, 45), Some(utc(2025, 7, 2, 10, 15)))];
+        let buckets = build_buckets(&executions, Tz::UTC, HeatmapBucketSize::Hour, Utc::now());
+
+        assert_eq!(buckets.len(), 7 * 24);
+        assert_eq!(cell(&buckets, 2, 9).execution_count, 1);
+        assert_eq!(cell(&buckets, 2, 9).total_runtime_secs, 900.0);
+        assert_eq!(cell(&buckets, 2, 10).execution_count, 0);
+        assert_eq!(cell(&buckets, 2, 10).total_runtime_secs, 900.0);
+    }
+
+    #[test]
+    fn test_execution_spanning_midnight_uses_local_days() {
+        // 2025-07-01 21:30 - 22:30 UTC is Tuesday 23:30 - Wednesday 00:30 in Berlin (UTC+2)
+        let executions = vec![(utc(2025, 7, 1, 21, 30), Some(utc(2025, 7, 1, 22, 30)))];
+        let buckets = build_buckets(
+            &executions,
+            chrono_tz::Europe::Berlin,
+            HeatmapBucketSize::Day,
+            Utc::now(),
+        );
+
+        assert_eq!(buckets.len(), 7);
+        assert_eq!(buckets[1].execution_count, 1);
+        assert_eq!(buckets[1].total_runtime_secs, 1800.0);
+        assert_eq!(buckets[2].execution_count, 0);
+        assert_eq!(buckets[2].total_runtime_secs, 1800.0);
+    }
+
+    #[test]
+    fn test_dst_fall_back_counts_repeated_hour_twice() {
+        // New York falls back on Sunday 2025-11-02: 01:00-02:00 local happens twice
+        // (05:00-06:00 UTC as EDT, then 06:00-07:00 UTC as EST)
+        let executions = vec![(utc(2025, 11, 2, 5, 0), Some(utc(2025, 11, 2, 7, 0)))];
+        let buckets = build_buckets(
+            &executions,
+            chrono_tz::America::New_York,
+            HeatmapBucketSize::Hour,
+            Utc::now(),
+        );
+
+        assert_eq!(cell(&buckets, 6, 1).execution_count, 1);
+        assert_eq!(cell(&buckets, 6, 1).total_runtime_secs, 7200.0);
+        assert_eq!(cell(&buckets, 6, 2).total_runtime_secs, 0.0);
+    }
+
+    #[test]
+    fn test_dst_spring_forward_skips_missing_hour() {
+        // New York springs forward on Sunday 2025-03-09: 02:00-03:00 local doesn't exist.
+        // 06:30-07:30 UTC is 01:30 EST - 03:30 EDT, one real hour
+        let executions = vec![(utc(2025, 3, 9, 6, 30), Some(utc(2025, 3, 9, 7, 30)))];
+        let buckets = build_buckets(
+            &executions,
+            chrono_tz::America::New_York,
+            HeatmapBucketSize::Hour,
+            Utc::now(),
+        );
+
+        assert_eq!(cell(&buckets, 6, 1).total_runtime_secs, 1800.0);
+        assert_eq!(cell(&buckets, 6, 2).total_runtime_secs, 0.0);
+        assert_eq!(cell(&buckets, 6, 3).total_runtime_secs, 1800.0);
+        let total: f64 = buckets.iter().map(|b| b.total_runtime_secs).sum();
+        assert_eq!(total, 3600.0);
+    }
+
+    #[test]
+    fn test_running_execution_counts_until_now() {
+        let now = utc(2025, 7, 2, 10, 30);
+        let executions = vec![(utc(2025, 7, 2, 10, 0), None)];
+        let buckets = build_buckets(&executions, Tz::UTC, HeatmapBucketSize::Hour, now);
+
+        assert_eq!(cell(&buckets, 2, 10).total_runtime_secs, 1800.0);
+    }
+}
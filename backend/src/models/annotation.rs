@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "annotation_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum AnnotationType {
+    Approved,
+    Rejected,
+    Question,
+}
+
+/// A reviewer's verdict on one entry of an attempt's log
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct Annotation {
+    pub id: Uuid,
+    pub attempt_id: Uuid,
+    pub entry_index: i32,
+    pub annotation_type: AnnotationType,
+    pub comment: Option<String>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateAnnotation {
+    pub entry_index: i32,
+    pub annotation_type: AnnotationType,
+    pub comment: Option<String>,
+}
+
+impl Annotation {
+    pub async fn create(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        data: &CreateAnnotation,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query_as!(
+            Annotation,
+            r#"INSERT INTO annotations (id, attempt_id, entry_index, annotation_type, comment)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING id              AS "id!: Uuid",
+                         attempt_id      AS "attempt_id!: Uuid",
+                         entry_index     AS "entry_index!: i32",
+                         annotation_type AS "annotation_type!: AnnotationType",
+                         comment,
+                         created_at      AS "created_at!: DateTime<Utc>""#,
+            id,
+            attempt_id,
+            data.entry_index,
+            data.annotation_type,
+            data.comment
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Annotations of an attempt in log order, oldest first for the same entry
+    pub async fn find_by_attempt_id(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Annotation,
+            r#"SELECT id              AS "id!: Uuid",
+                      attempt_id      AS "attempt_id!: Uuid",
+                      entry_index     AS "entry_index!: i32",
+                      annotation_type AS "annotation_type!: AnnotationType",
+                      comment,
+                      created_at      AS "created_at!: DateTime<Utc>"
+               FROM annotations
+               WHERE attempt_id = $1
+               ORDER BY entry_index ASC, created_at ASC"#,
+            attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn count_by_attempt_id(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<u32, sqlx::Error> {
+        let record = sqlx::query!(
+            r#"SELECT COUNT(*) AS "count!: i64" FROM annotations WHERE attempt_id = $1"#,
+            attempt_id
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(record.count as u32)
+    }
+}
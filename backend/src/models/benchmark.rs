@@ -0,0 +1,547 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// Tasks benchmarked when a run doesn't say how many
+pub const DEFAULT_BENCHMARK_TASK_COUNT: u32 = 5;
+pub const MAX_BENCHMARK_TASK_COUNT: u32 = 50;
+/// Time budget of each benchmark attempt when a run doesn't set one
+pub const DEFAULT_BENCHMARK_MAX_DURATION_SECS: u32 = 30 * 60;
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "benchmark_run_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum BenchmarkRunStatus {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "benchmark_attempt_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum BenchmarkAttemptStatus {
+    Pending,
+    Running,
+    Completed,
+    /// The attempt couldn't be created or the agent didn't finish
+    Failed,
+    /// Stopped after using up the run's time budget
+    TimedOut,
+}
+
+/// Replay of a project's merged tasks with several executors
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BenchmarkRun {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub executors: Vec<String>,
+    /// Run in each benchmark worktree once the agent is done; passing is a zero exit status
+    pub test_command: Option<String>,
+    /// Time budget of each benchmark attempt, setup script included
+    pub max_duration_secs: u32,
+    pub status: BenchmarkRunStatus,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// One merged task replayed with one executor, and how the result scored
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BenchmarkAttempt {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub task_id: Uuid,
+    pub executor: String,
+    /// The merged attempt whose result is the reference
+    pub source_attempt_id: Uuid,
+    pub base_commit: String,
+    pub reference_commit: String,
+    /// The attempt started for the benchmark, once it was created
+    pub task_attempt_id: Option<Uuid>,
+    pub status: BenchmarkAttemptStatus,
+    /// 0 to 1: how much of the merged change's added and removed lines the result shares
+    pub diff_similarity: Option<f64>,
+    /// Outcome of the run's test command; unset when there is none or it didn't get to run
+    pub tests_passed: Option<bool>,
+    /// Cost reported by the executor; only some executors report one
+    pub cost_usd: Option<f64>,
+    pub duration_secs: Option<f64>,
+    pub error: Option<String>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateBenchmarkRun {
+    /// Executor types to compare, e.g. `["claude", "amp"]`
+    pub executors: Vec<String>,
+    /// Benchmark the most recently merged tasks, this many of them
+    pub task_count: Option<u32>,
+    /// Benchmark these tasks instead; each needs a merged attempt
+    pub task_ids: Option<Vec<Uuid>>,
+    pub test_command: Option<String>,
+    pub max_duration_secs: Option<u32>,
+}
+
+/// Scores of one executor over a whole run
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ExecutorBenchmarkSummary {
+    pub executor: String,
+    pub attempts: u32,
+    pub completed: u32,
+    pub failed: u32,
+    pub timed_out: u32,
+    /// Mean over scored attempts; a failed or timed out attempt still counts with what it did
+    pub mean_diff_similarity: Option<f64>,
+    pub tests_run: u32,
+    pub tests_passed: u32,
+    /// Sum over attempts whose executor reported a cost
+    pub total_cost_usd: Option<f64>,
+    pub mean_duration_secs: Option<f64>,
+}
+
+/// Results of every executor on one benchmarked task
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BenchmarkTaskResult {
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub source_attempt_id: Uuid,
+    pub attempts: Vec<BenchmarkAttempt>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BenchmarkReport {
+    pub run: BenchmarkRun,
+    pub executors: Vec<ExecutorBenchmarkSummary>,
+    pub tasks: Vec<BenchmarkTaskResult>,
+}
+
+/// What a benchmark attempt is created from
+#[derive(Debug, Clone)]
+pub struct BenchmarkBase {
+    pub run_id: Uuid,
+    pub base_commit: String,
+}
+
+/// A task with a merged attempt that can be benchmarked
+#[derive(Debug, Clone)]
+pub struct BenchmarkCandidate {
+    pub task_id: Uuid,
+    pub source_attempt_id: Uuid,
+    pub merge_commit: String,
+    pub attempt_created_at: DateTime<Utc>,
+}
+
+/// Scores recorded for a finished benchmark attempt
+#[derive(Debug, Clone, Default)]
+pub struct BenchmarkScore {
+    pub diff_similarity: Option<f64>,
+    pub tests_passed: Option<bool>,
+    pub cost_usd: Option<f64>,
+    pub duration_secs: Option<f64>,
+    pub error: Option<String>,
+}
+
+struct BenchmarkRunRow {
+    id: Uuid,
+    project_id: Uuid,
+    executors: String,
+    test_command: Option<String>,
+    max_duration_secs: i64,
+    status: BenchmarkRunStatus,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<BenchmarkRunRow> for BenchmarkRun {
+    type Error = sqlx::Error;
+
+    fn try_from(row: BenchmarkRunRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            project_id: row.project_id,
+            executors: serde_json::from_str(&row.executors)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            test_command: row.test_command,
+            max_duration_secs: row.max_duration_secs.clamp(0, u32::MAX as i64) as u32,
+            status: row.status,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        })
+    }
+}
+
+impl BenchmarkRun {
+    pub async fn create(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        executors: &[String],
+        test_command: Option<&str>,
+        max_duration_secs: u32,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let executors =
+            serde_json::to_string(executors).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        let max_duration_secs = i64::from(max_duration_secs);
+        sqlx::query_as!(
+            BenchmarkRunRow,
+            r#"INSERT INTO benchmark_runs (id, project_id, executors, test_command, max_duration_secs)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING id                AS "id!: Uuid",
+                         project_id        AS "project_id!: Uuid",
+                         executors,
+                         test_command,
+                         max_duration_secs AS "max_duration_secs!: i64",
+                         status            AS "status!: BenchmarkRunStatus",
+                         created_at        AS "created_at!: DateTime<Utc>",
+                         completed_at      AS "completed_at: DateTime<Utc>""#,
+            id,
+            project_id,
+            executors,
+            test_command,
+            max_duration_secs
+        )
+        .fetch_one(pool)
+        .await?
+        .try_into()
+    }
+
+    pub async fn find_by_id(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            BenchmarkRunRow,
+            r#"SELECT id                AS "id!: Uuid",
+                      project_id        AS "project_id!: Uuid",
+                      executors,
+                      test_command,
+                      max_duration_secs AS "max_duration_secs!: i64",
+                      status            AS "status!: BenchmarkRunStatus",
+                      created_at        AS "created_at!: DateTime<Utc>",
+                      completed_at      AS "completed_at: DateTime<Utc>"
+               FROM benchmark_runs
+               WHERE id = $1 AND project_id = $2"#,
+            id,
+            project_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(Self::try_from)
+        .transpose()
+    }
+
+    /// Runs of a project, newest first
+    pub async fn find_by_project_id(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            BenchmarkRunRow,
+            r#"SELECT id                AS "id!: Uuid",
+                      project_id        AS "project_id!: Uuid",
+                      executors,
+                      test_command,
+                      max_duration_secs AS "max_duration_secs!: i64",
+                      status            AS "status!: BenchmarkRunStatus",
+                      created_at        AS "created_at!: DateTime<Utc>",
+                      completed_at      AS "completed_at: DateTime<Utc>"
+               FROM benchmark_runs
+               WHERE project_id = $1
+               ORDER BY created_at DESC"#,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+
+    /// Ids of runs that haven't finished, e.g. when the server stopped in the middle of one
+    pub async fn find_running_ids(pool: &SqlitePool) -> Result<Vec<Uuid>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT id AS "id!: Uuid" FROM benchmark_runs WHERE status = 'running'
+               ORDER BY created_at ASC"#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(records.into_iter().map(|r| r.id).collect())
+    }
+
+    pub async fn status(pool: &SqlitePool, id: Uuid) -> Result<BenchmarkRunStatus, sqlx::Error> {
+        let record = sqlx::query!(
+            r#"SELECT status AS "status!: BenchmarkRunStatus" FROM benchmark_runs WHERE id = $1"#,
+            id
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(record.status)
+    }
+
+    /// Move a running run to `status`; a finished run is left as it is
+    pub async fn finish(
+        pool: &SqlitePool,
+        id: Uuid,
+        status: BenchmarkRunStatus,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE benchmark_runs SET status = $2, completed_at = datetime('now', 'subsec')
+             WHERE id = $1 AND status = 'running'",
+            id,
+            status
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Latest merged attempt of each task in the project, most recently merged first.
+    /// Attempts of earlier benchmark runs are never used as a reference.
+    pub async fn find_candidates(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<BenchmarkCandidate>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT task_id      AS "task_id!: Uuid",
+                      attempt_id   AS "attempt_id!: Uuid",
+                      merge_commit AS "merge_commit!",
+                      created_at   AS "created_at!: DateTime<Utc>"
+               FROM (
+                   SELECT ta.task_id, ta.id AS attempt_id, ta.merge_commit, ta.created_at,
+                          ta.updated_at,
+                          ROW_NUMBER() OVER (PARTITION BY ta.task_id ORDER BY ta.updated_at DESC) AS rn
+                   FROM task_attempts ta
+                   JOIN tasks t ON t.id = ta.task_id
+                   WHERE t.project_id = $1
+                     AND ta.merge_commit IS NOT NULL
+                     AND ta.benchmark_run_id IS NULL
+               )
+               WHERE rn = 1
+               ORDER BY updated_at DESC"#,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|r| BenchmarkCandidate {
+                task_id: r.task_id,
+                source_attempt_id: r.attempt_id,
+                merge_commit: r.merge_commit,
+                attempt_created_at: r.created_at,
+            })
+            .collect())
+    }
+}
+
+impl BenchmarkAttempt {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        pool: &SqlitePool,
+        run_id: Uuid,
+        task_id: Uuid,
+        executor: &str,
+        source_attempt_id: Uuid,
+        base_commit: &str,
+        reference_commit: &str,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query_as!(
+            BenchmarkAttempt,
+            r#"INSERT INTO benchmark_attempts (id, run_id, task_id, executor, source_attempt_id, base_commit, reference_commit)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
+               RETURNING id                AS "id!: Uuid",
+                         run_id            AS "run_id!: Uuid",
+                         task_id           AS "task_id!: Uuid",
+                         executor,
+                         source_attempt_id AS "source_attempt_id!: Uuid",
+                         base_commit,
+                         reference_commit,
+                         task_attempt_id   AS "task_attempt_id: Uuid",
+                         status            AS "status!: BenchmarkAttemptStatus",
+                         diff_similarity,
+                         tests_passed      AS "tests_passed: bool",
+                         cost_usd,
+                         duration_secs,
+                         error,
+                         created_at        AS "created_at!: DateTime<Utc>",
+                         updated_at        AS "updated_at!: DateTime<Utc>""#,
+            id,
+            run_id,
+            task_id,
+            executor,
+            source_attempt_id,
+            base_commit,
+            reference_commit
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Attempts of a run in the order they are run: task by task, executors in turn
+    pub async fn find_by_run_id(pool: &SqlitePool, run_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            BenchmarkAttempt,
+            r#"SELECT id                AS "id!: Uuid",
+                      run_id            AS "run_id!: Uuid",
+                      task_id           AS "task_id!: Uuid",
+                      executor,
+                      source_attempt_id AS "source_attempt_id!: Uuid",
+                      base_commit,
+                      reference_commit,
+                      task_attempt_id   AS "task_attempt_id: Uuid",
+                      status            AS "status!: BenchmarkAttemptStatus",
+                      diff_similarity,
+                      tests_passed      AS "tests_passed: bool",
+                      cost_usd,
+                      duration_secs,
+                      error,
+                      created_at        AS "created_at!: DateTime<Utc>",
+                      updated_at        AS "updated_at!: DateTime<Utc>"
+               FROM benchmark_attempts
+               WHERE run_id = $1
+               ORDER BY created_at ASC, rowid ASC"#,
+            run_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn mark_running(
+        pool: &SqlitePool,
+        id: Uuid,
+        task_attempt_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE benchmark_attempts
+             SET status = 'running', task_attempt_id = $2, updated_at = datetime('now', 'subsec')
+             WHERE id = $1",
+            id,
+            task_attempt_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Fail attempts of a run left running, e.g. by a server restart, so the rest can go on
+    pub async fn fail_running(
+        pool: &SqlitePool,
+        run_id: Uuid,
+        error: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE benchmark_attempts
+             SET status = 'failed', error = $2, updated_at = datetime('now', 'subsec')
+             WHERE run_id = $1 AND status = 'running'",
+            run_id,
+            error
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn finish(
+        pool: &SqlitePool,
+        id: Uuid,
+        status: BenchmarkAttemptStatus,
+        score: &BenchmarkScore,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE benchmark_attempts
+             SET status = $2, diff_similarity = $3, tests_passed = $4, cost_usd = $5,
+                 duration_secs = $6, error = $7, updated_at = datetime('now', 'subsec')
+             WHERE id = $1",
+            id,
+            status,
+            score.diff_similarity,
+            score.tests_passed,
+            score.cost_usd,
+            score.duration_secs,
+            score.error
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl BenchmarkReport {
+    /// Per-executor summaries, in the run's executor order, and per-task results
+    pub fn new(
+        run: BenchmarkRun,
+        attempts: Vec<BenchmarkAttempt>,
+        task_titles: &std::collections::HashMap<Uuid, String>,
+    ) -> Self {
+        let executors = run
+            .executors
+            .iter()
+            .map(|executor| {
+                let own: Vec<_> = attempts
+                    .iter()
+                    .filter(|a| &a.executor == executor)
+                    .collect();
+                let count = |status| own.iter().filter(|a| a.status == status).count() as u32;
+                let mean = |values: Vec<f64>| {
+                    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+                };
+                let costs: Vec<f64> = own.iter().filter_map(|a| a.cost_usd).collect();
+                ExecutorBenchmarkSummary {
+                    executor: executor.clone(),
+                    attempts: own.len() as u32,
+                    completed: count(BenchmarkAttemptStatus::Completed),
+                    failed: count(BenchmarkAttemptStatus::Failed),
+                    timed_out: count(BenchmarkAttemptStatus::TimedOut),
+                    mean_diff_similarity: mean(
+                        own.iter().filter_map(|a| a.diff_similarity).collect(),
+                    ),
+                    tests_run: own.iter().filter(|a| a.tests_passed.is_some()).count() as u32,
+                    tests_passed: own.iter().filter(|a| a.tests_passed == Some(true)).count()
+                        as u32,
+                    total_cost_usd: (!costs.is_empty()).then(|| costs.iter().sum()),
+                    mean_duration_secs: mean(own.iter().filter_map(|a| a.duration_secs).collect()),
+                }
+            })
+            .collect();
+
+        let mut tasks: Vec<BenchmarkTaskResult> = Vec::new();
+        for attempt in attempts {
+            match tasks.iter_mut().find(|t| t.task_id == attempt.task_id) {
+                Some(task) => task.attempts.push(attempt),
+                None => tasks.push(BenchmarkTaskResult {
+                    task_id: attempt.task_id,
+                    task_title: task_titles
+                        .get(&attempt.task_id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    source_attempt_id: attempt.source_attempt_id,
+                    attempts: vec![attempt],
+                }),
+            }
+        }
+
+        Self {
+            run,
+            executors,
+            tasks,
+        }
+    }
+}
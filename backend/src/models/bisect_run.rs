@@ -0,0 +1,280 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "bisect_run_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum BisectRunStatus {
+    Running,
+    /// The first bad commit was found
+    Found,
+    /// Bisecting couldn't single out a commit, e.g. because the command passes at the head
+    Failed,
+    Cancelled,
+}
+
+/// A file changed by the first bad commit
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BisectFileChange {
+    pub path: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// The commit a bisect run blamed, with a summary of its diff
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BisectCommit {
+    pub id: String,
+    /// First line of the commit message
+    pub summary: String,
+    pub author: String,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub committed_at: DateTime<Utc>,
+    pub files: Vec<BisectFileChange>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// `git bisect` of an attempt's commits, from its merge base to its head. Its id is that of
+/// the execution process streaming its output.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BisectRun {
+    pub id: Uuid,
+    pub task_attempt_id: Uuid,
+    /// Merge base, expected to pass
+    pub good_commit: String,
+    /// Head of the attempt, expected to fail
+    pub bad_commit: String,
+    pub command: String,
+    pub worktree_path: String,
+    pub status: BisectRunStatus,
+    pub first_bad_commit: Option<BisectCommit>,
+    pub error: Option<String>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+struct BisectRunRow {
+    id: Uuid,
+    task_attempt_id: Uuid,
+    good_commit: String,
+    bad_commit: String,
+    command: String,
+    worktree_path: String,
+    status: BisectRunStatus,
+    first_bad_commit: Option<String>,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<BisectRunRow> for BisectRun {
+    type Error = sqlx::Error;
+
+    fn try_from(row: BisectRunRow) -> Result<Self, Self::Error> {
+        let first_bad_commit = row
+            .first_bad_commit
+            .map(|commit| serde_json::from_str(&commit))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        Ok(Self {
+            id: row.id,
+            task_attempt_id: row.task_attempt_id,
+            good_commit: row.good_commit,
+            bad_commit: row.bad_commit,
+            command: row.command,
+            worktree_path: row.worktree_path,
+            status: row.status,
+            first_bad_commit,
+            error: row.error,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        })
+    }
+}
+
+impl BisectRun {
+    pub async fn create(
+        pool: &SqlitePool,
+        id: Uuid,
+        task_attempt_id: Uuid,
+        good_commit: &str,
+        bad_commit: &str,
+        command: &str,
+        worktree_path: &str,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as!(
+            BisectRunRow,
+            r#"INSERT INTO bisect_runs
+                   (id, task_attempt_id, good_commit, bad_commit, command, worktree_path)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING id               AS "id!: Uuid",
+                         task_attempt_id  AS "task_attempt_id!: Uuid",
+                         good_commit,
+                         bad_commit,
+                         command,
+                         worktree_path,
+                         status           AS "status!: BisectRunStatus",
+                         first_bad_commit,
+                         error,
+                         created_at       AS "created_at!: DateTime<Utc>",
+                         completed_at     AS "completed_at: DateTime<Utc>""#,
+            id,
+            task_attempt_id,
+            good_commit,
+            bad_commit,
+            command,
+            worktree_path
+        )
+        .fetch_one(pool)
+        .await
+        .and_then(Self::try_from)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            BisectRunRow,
+            r#"SELECT id               AS "id!: Uuid",
+                      task_attempt_id  AS "task_attempt_id!: Uuid",
+                      good_commit,
+                      bad_commit,
+                      command,
+                      worktree_path,
+                      status           AS "status!: BisectRunStatus",
+                      first_bad_commit,
+                      error,
+                      created_at       AS "created_at!: DateTime<Utc>",
+                      completed_at     AS "completed_at: DateTime<Utc>"
+               FROM bisect_runs
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(Self::try_from)
+        .transpose()
+    }
+
+    /// Runs of an attempt, newest first
+    pub async fn find_by_task_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            BisectRunRow,
+            r#"SELECT id               AS "id!: Uuid",
+                      task_attempt_id  AS "task_attempt_id!: Uuid",
+                      good_commit,
+                      bad_commit,
+                      command,
+                      worktree_path,
+                      status           AS "status!: BisectRunStatus",
+                      first_bad_commit,
+                      error,
+                      created_at       AS "created_at!: DateTime<Utc>",
+                      completed_at     AS "completed_at: DateTime<Utc>"
+               FROM bisect_runs
+               WHERE task_attempt_id = $1
+               ORDER BY created_at DESC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+
+    /// Running runs whose process is no longer running: finished, stopped, or lost to a
+    /// restart
+    pub async fn find_stale(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            BisectRunRow,
+            r#"SELECT b.id               AS "id!: Uuid",
+                      b.task_attempt_id  AS "task_attempt_id!: Uuid",
+                      b.good_commit,
+                      b.bad_commit,
+                      b.command,
+                      b.worktree_path,
+                      b.status           AS "status!: BisectRunStatus",
+                      b.first_bad_commit,
+                      b.error,
+                      b.created_at       AS "created_at!: DateTime<Utc>",
+                      b.completed_at     AS "completed_at: DateTime<Utc>"
+               FROM bisect_runs b
+               LEFT JOIN execution_processes ep ON ep.id = b.id
+               WHERE b.status = 'running' AND (ep.id IS NULL OR ep.status != 'running')"#
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+
+    pub async fn has_running(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(
+                   SELECT 1 FROM bisect_runs WHERE task_attempt_id = $1 AND status = 'running'
+               ) AS "running!: bool""#,
+            task_attempt_id
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Whether a running run uses `worktree_path`, which must then be left alone
+    pub async fn uses_worktree(
+        pool: &SqlitePool,
+        worktree_path: &str,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(
+                   SELECT 1 FROM bisect_runs WHERE worktree_path = $1 AND status = 'running'
+               ) AS "used!: bool""#,
+            worktree_path
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Record how a running run ended. Returns false when it had already ended, so only one
+    /// caller cleans up after it.
+    pub async fn complete(
+        pool: &SqlitePool,
+        id: Uuid,
+        status: BisectRunStatus,
+        first_bad_commit: Option<&BisectCommit>,
+        error: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let first_bad_commit = first_bad_commit
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        let result = sqlx::query!(
+            r#"UPDATE bisect_runs
+               SET status = $2, first_bad_commit = $3, error = $4,
+                   completed_at = datetime('now', 'subsec'), updated_at = datetime('now', 'subsec')
+               WHERE id = $1 AND status = 'running'"#,
+            id,
+            status,
+            first_bad_commit,
+            error
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
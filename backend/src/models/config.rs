@@ -1,13 +1,18 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::executor::ExecutorConfig;
+use crate::{
+    executor::{ExecutorConfig, OutputFormat, PromptKind},
+    utils::path_ignore::{PathIgnore, DEFAULT_IGNORED_PATHS},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct Config {
+    /// Deprecated: the theme is a user preference now. Only read as the fallback for users
+    /// who haven't picked a theme through `/api/preferences`.
     pub theme: ThemeMode,
     pub executor: ExecutorConfig,
     pub disclaimer_acknowledged: bool,
@@ -19,6 +24,185 @@ pub struct Config {
     pub editor: EditorConfig,
     pub github: GitHubConfig,
     pub analytics_enabled: Option<bool>,
+    pub response_language: Option<String>,
+    /// Similarity percentage (1-100) at which git treats a delete/add pair as a rename or copy
+    pub diff_rename_threshold: u16,
+    /// Cap on dev servers running at once across all projects; `None` means unlimited
+    pub max_concurrent_dev_servers: Option<u32>,
+    /// Cap on attempts running at once within one project, so a busy project can't starve
+    /// the others; `None` means unlimited. Attempts over the cap wait for a slot.
+    pub max_concurrent_per_project: Option<u32>,
+    /// IANA timezone (e.g. `Europe/Berlin`) used for time-of-day reporting; UTC when unset
+    pub timezone: Option<String>,
+    /// Recreate a manually deleted worktree when a follow-up is sent instead of failing
+    pub auto_recreate_missing_worktrees: bool,
+    /// Send salted hashes of task titles and descriptions with analytics events so they can be
+    /// correlated. Raw task content is never sent either way.
+    pub analytics_content_hashes: bool,
+    /// Directory name for new attempt worktrees. Placeholders: `{attempt_id}` (required, so
+    /// attempts never share a directory), `{short_attempt_id}`, `{task_id}` and `{task_title}`.
+    pub worktree_name_pattern: String,
+    /// Executor types that can run attempts, e.g. `["claude"]`; all of them when unset
+    pub enabled_executors: Option<Vec<String>>,
+    /// Estimated tokens auto-composed follow-up prompts may use, by executor type. Context is
+    /// trimmed to fit; executors that aren't listed use their built-in default.
+    pub prompt_token_budgets: HashMap<String, u32>,
+    /// Reject destructive MCP tool calls, like `delete_task`, unless they pass `confirm: true`
+    pub mcp_require_confirmation: bool,
+    /// Refuse to start attempts while the project's repository has uncommitted changes,
+    /// instead of starting them with a warning
+    pub block_attempts_on_dirty_repo: bool,
+    /// Paths left out of attempt diffs and the untracked change journal, in .gitignore syntax
+    pub ignored_paths: Vec<String>,
+    /// Snapshot files git doesn't track after setup and report how each execution changed
+    /// them, so a merge doesn't silently depend on a local file
+    pub untracked_change_journal: bool,
+    /// Output format by executor type, for executors that can print plain text. Text output
+    /// is for troubleshooting: tool calls aren't parsed and follow-ups can't resume the session.
+    pub executor_output_formats: HashMap<String, OutputFormat>,
+    /// Check GitHub releases daily for a newer codecommand. Builds without the `self-update`
+    /// feature never check.
+    pub check_for_updates: bool,
+    /// CPU and IO priority processes are spawned with, by kind of process
+    pub process_priorities: ProcessPriorities,
+    /// Seconds a stopped process gets to exit after SIGINT, and again after SIGTERM, before
+    /// it's killed
+    pub graceful_shutdown_secs: u32,
+    /// Minutes focus mode keeps agents paused before resuming them on its own
+    pub focus_mode_minutes: u32,
+    /// What the commits and pull requests codecommand creates record about their attempt
+    pub provenance: ProvenanceMode,
+    /// Address the web UI is reachable at, for links in pull requests. The local server when
+    /// unset.
+    pub public_url: Option<String>,
+    /// Run when an attempt starts, once its worktree is ready and before anything is spawned
+    /// in it
+    pub attempt_start_hook: Option<AttemptStartHook>,
+    /// Shell command the custom executor runs, with the prompt on stdin. Placeholders:
+    /// `{prompt}`, `{task_id}`, `{project_id}` and `{worktree}`, substituted shell-quoted.
+    pub custom_executor_command: Option<String>,
+    /// Parse the custom command's output as Claude's stream JSON instead of plain lines
+    pub custom_executor_jsonl: bool,
+    /// Open pull requests in the local browser once codecommand has created them. Ignored
+    /// on machines without a display.
+    pub open_pr_in_browser: bool,
+    /// Disk space each kind of file in the asset directory may use
+    pub asset_quotas: AssetQuotas,
+    /// Frames the prompt an attempt starts with, placing it at `{prompt}`
+    pub initial_prompt_template: Option<String>,
+    /// Frames follow-up prompts sent to a resumed session, placing them at `{prompt}`
+    pub followup_prompt_template: Option<String>,
+    /// How the Claude CLI is run, for new attempts and resumed sessions alike
+    pub claude: ClaudeSettings,
+    /// Path of the CLI to run, by executor type, for executors whose CLI can be installed:
+    /// `claude`, `gemini` and `opencode`. Unset ones are looked up on PATH, and Claude and
+    /// Gemini are run through npx when they aren't installed.
+    pub executable_paths: HashMap<String, String>,
+    /// Bytes of raw executor JSON kept with each normalized log entry. Larger metadata is
+    /// replaced by a truncated copy flagged `truncated`; the entry's content is unaffected.
+    pub max_entry_metadata_bytes: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ClaudeSettings {
+    /// Version of `@anthropic-ai/claude-code` npx runs, or a dist tag like `latest`. Anything
+    /// but `latest` is run through npx even when `claude` is installed, as the installed CLI
+    /// may be another version; only a path set in `executable_paths` takes precedence.
+    pub package_version: String,
+    /// Passed as `--model`; the CLI's default model when unset
+    pub model: Option<String>,
+    /// Pass `--dangerously-skip-permissions`. Without it the CLI can't edit files or run
+    /// commands, as nobody is there to approve them.
+    pub skip_permissions: bool,
+}
+
+impl Default for ClaudeSettings {
+    fn default() -> Self {
+        Self {
+            package_version: "latest".to_string(),
+            model: None,
+            skip_permissions: true,
+        }
+    }
+}
+
+impl ClaudeSettings {
+    /// The version `package_version` pins, unless it's blank or `latest`
+    pub fn pinned_version(&self) -> Option<&str> {
+        match self.package_version.trim() {
+            "" | "latest" => None,
+            version => Some(version),
+        }
+    }
+}
+
+/// Megabytes each kind of file in the asset directory may use; `None` means unlimited. The
+/// oldest Aider sessions are deleted to stay under their quota, while a database over its
+/// quota fails the `asset_accounting` job instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AssetQuotas {
+    pub database_mb: Option<u32>,
+    pub aider_sessions_mb: Option<u32>,
+}
+
+/// External automation told about attempts as they start. See `services::start_hook`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AttemptStartHook {
+    /// POST the attempt's context as JSON to `url`
+    Webhook { url: String },
+    /// Run `command` with the shell in the worktree, with the context as JSON on stdin
+    Script { command: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvenanceMode {
+    Off,
+    /// `Codecommand-Attempt` and `Codecommand-Executor` trailers on commits
+    Trailers,
+    /// Trailers, plus a collapsed section with the task, attempt, executor, cost and a link
+    /// at the end of pull request descriptions
+    Full,
+}
+
+/// How much CPU and IO time a process gets relative to the rest of the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessPriority {
+    Normal,
+    /// `nice 10` on unix, plus the lowest best-effort IO priority on Linux. Below normal on
+    /// Windows.
+    Low,
+    /// Only runs when the machine is otherwise idle
+    Idle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ProcessPriorities {
+    /// Also used for format scripts
+    pub setup_script: ProcessPriority,
+    pub coding_agent: ProcessPriority,
+    pub dev_server: ProcessPriority,
+    pub bisect: ProcessPriority,
+}
+
+impl Default for ProcessPriorities {
+    fn default() -> Self {
+        Self {
+            setup_script: ProcessPriority::Low,
+            coding_agent: ProcessPriority::Low,
+            // Dev servers are what the user is looking at
+            dev_server: ProcessPriority::Normal,
+            bisect: ProcessPriority::Low,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -42,7 +226,7 @@ pub struct EditorConfig {
     pub custom_command: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Clone, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct GitHubConfig {
     pub pat: Option<String>,
@@ -50,6 +234,29 @@ pub struct GitHubConfig {
     pub username: Option<String>,
     pub primary_email: Option<String>,
     pub default_pr_base: Option<String>,
+    /// OAuth access token from the web sign-in flow, encrypted at rest. Stripped from
+    /// config API responses; clients only see `has_access_token`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(skip)]
+    pub access_token: Option<String>,
+    /// Whether an OAuth access token is stored. Clients clear it to sign out.
+    #[serde(default)]
+    pub has_access_token: bool,
+}
+
+impl std::fmt::Debug for GitHubConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redact = |secret: &Option<String>| secret.as_ref().map(|_| "[REDACTED]");
+        f.debug_struct("GitHubConfig")
+            .field("pat", &redact(&self.pat))
+            .field("token", &redact(&self.token))
+            .field("username", &self.username)
+            .field("primary_email", &self.primary_email)
+            .field("default_pr_base", &self.default_pr_base)
+            .field("access_token", &redact(&self.access_token))
+            .field("has_access_token", &self.has_access_token)
+            .finish()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -166,6 +373,40 @@ impl Default for Config {
             editor: EditorConfig::default(),
             github: GitHubConfig::default(),
             analytics_enabled: None,
+            response_language: None,
+            diff_rename_threshold: 50,
+            max_concurrent_dev_servers: None,
+            max_concurrent_per_project: None,
+            timezone: None,
+            auto_recreate_missing_worktrees: false,
+            analytics_content_hashes: false,
+            worktree_name_pattern: DEFAULT_WORKTREE_NAME_PATTERN.to_string(),
+            enabled_executors: None,
+            prompt_token_budgets: HashMap::new(),
+            mcp_require_confirmation: false,
+            block_attempts_on_dirty_repo: false,
+            ignored_paths: DEFAULT_IGNORED_PATHS
+                .iter()
+                .map(|path| path.to_string())
+                .collect(),
+            untracked_change_journal: false,
+            executor_output_formats: HashMap::new(),
+            check_for_updates: true,
+            process_priorities: ProcessPriorities::default(),
+            graceful_shutdown_secs: DEFAULT_GRACEFUL_SHUTDOWN_SECS,
+            focus_mode_minutes: 30,
+            provenance: ProvenanceMode::Trailers,
+            public_url: None,
+            attempt_start_hook: None,
+            custom_executor_command: None,
+            custom_executor_jsonl: false,
+            open_pr_in_browser: false,
+            asset_quotas: AssetQuotas::default(),
+            initial_prompt_template: None,
+            followup_prompt_template: None,
+            claude: ClaudeSettings::default(),
+            executable_paths: HashMap::new(),
+            max_entry_metadata_bytes: DEFAULT_MAX_ENTRY_METADATA_BYTES,
         }
     }
 }
@@ -187,10 +428,49 @@ impl Default for GitHubConfig {
             username: None,
             primary_email: None,
             default_pr_base: Some("main".to_string()),
+            access_token: None,
+            has_access_token: false,
         }
     }
 }
 
+impl GitHubConfig {
+    /// Token from signing in with GitHub: the device-flow token, or the decrypted
+    /// access token from the web flow
+    pub fn login_token(&self) -> Option<String> {
+        self.token.clone().or_else(|| {
+            self.access_token
+                .as_deref()
+                .and_then(
+                    |encrypted| match crate::utils::secrets::decrypt(encrypted) {
+                        Ok(token) => Some(token),
+                        Err(e) => {
+                            tracing::warn!("Failed to decrypt GitHub access token: {}", e);
+                            None
+                        }
+                    },
+                )
+        })
+    }
+
+    /// Token to use for GitHub API calls, preferring a personal access token
+    pub fn resolved_token(&self) -> Option<String> {
+        self.pat.clone().or_else(|| self.login_token())
+    }
+
+    /// Store a freshly issued OAuth access token (encrypted)
+    pub fn set_access_token(&mut self, access_token: &str) -> anyhow::Result<()> {
+        self.access_token = Some(crate::utils::secrets::encrypt(access_token)?);
+        self.has_access_token = true;
+        Ok(())
+    }
+
+    pub fn clear_access_token(&mut self) {
+        self.access_token = None;
+        self.has_access_token = false;
+    }
+}
+
 impl EditorConfig {
     pub fn get_command(&self) -> Vec<String> {
         match &self.editor_type {
@@ -262,7 +542,266 @@ impl SoundFile {
     }
 }
 
+/// Light validation of a BCP-47-ish language tag such as `ja`, `pt-BR` or `zh-Hant-TW`.
+/// Only the shape is checked: a 2-8 letter primary subtag followed by 1-8 character
+/// alphanumeric subtags separated by `-`.
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    if tag.is_empty() || tag.len() > 35 {
+        return false;
+    }
+
+    let mut subtags = tag.split('-');
+    let primary_ok = subtags
+        .next()
+        .map(|s| (2..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphabetic()))
+        .unwrap_or(false);
+
+    primary_ok
+        && subtags
+            .all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Check a response language setting, for the global config and for projects
+pub fn validate_response_language(language: Option<&str>) -> Result<(), String> {
+    match language {
+        Some(language) if !is_valid_language_tag(language) => Err(format!(
+            "Invalid response language '{}'. Use a language tag such as 'en', 'ja' or 'pt-BR'",
+            language
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub const DEFAULT_WORKTREE_NAME_PATTERN: &str = "vk-{short_attempt_id}-{task_title}-{attempt_id}";
+
+const WORKTREE_NAME_PLACEHOLDERS: &[&str] =
+    &["attempt_id", "short_attempt_id", "task_id", "task_title"];
+
+/// Check a worktree name pattern: only known placeholders, `{attempt_id}` among them, and
+/// nothing that would leave the worktree base directory
+pub fn validate_worktree_name_pattern(pattern: &str) -> Result<(), String> {
+    if pattern.contains(['/', '\\']) || pattern.starts_with('.') {
+        return Err("Worktree name pattern must be a plain directory name".to_string());
+    }
+
+    let placeholder = regex::Regex::new(r"\{([^{}]*)\}").unwrap();
+    for captures in placeholder.captures_iter(pattern) {
+        let name = &captures[1];
+        if !WORKTREE_NAME_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder '{{{}}}' in worktree name pattern. Available: {}",
+                name,
+                WORKTREE_NAME_PLACEHOLDERS
+                    .iter()
+                    .map(|name| format!("{{{}}}", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+    if !pattern.contains("{attempt_id}") {
+        return Err(
+            "Worktree name pattern must include {attempt_id} so attempts get distinct directories"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Check an executor allow-list: known agent executor names, at least one of them, including
+/// the default executor
+pub fn validate_enabled_executors(
+    enabled_executors: Option<&[String]>,
+    default_executor: &ExecutorConfig,
+) -> Result<(), String> {
+    let Some(enabled_executors) = enabled_executors else {
+        return Ok(());
+    };
+    if enabled_executors.is_empty() {
+        return Err("At least one executor must be enabled".to_string());
+    }
+    for name in enabled_executors {
+        if !ExecutorConfig::agents()
+            .iter()
+            .any(|executor| executor.to_string() == *name)
+        {
+            return Err(format!("Unknown executor '{}' in enabled executors", name));
+        }
+    }
+    if !enabled_executors.contains(&default_executor.to_string()) {
+        return Err(format!(
+            "The default executor {} must be one of the enabled executors",
+            default_executor.display_name()
+        ));
+    }
+    Ok(())
+}
+
+pub const DEFAULT_GRACEFUL_SHUTDOWN_SECS: u32 = 2;
+
+pub const DEFAULT_MAX_ENTRY_METADATA_BYTES: u32 = 16 * 1024;
+
+/// Longest a stopped process may take per signal, so stopping can't hang indefinitely
+pub const MAX_GRACEFUL_SHUTDOWN_SECS: u32 = 300;
+
+/// Smallest prompt budget that still leaves room for a useful follow-up
+pub const MIN_PROMPT_TOKEN_BUDGET: u32 = 1_000;
+
+/// Check per-executor prompt budgets: known agent executor names and workable sizes
+pub fn validate_prompt_token_budgets(budgets: &HashMap<String, u32>) -> Result<(), String> {
+    for (name, budget) in budgets {
+        if !ExecutorConfig::agents()
+            .iter()
+            .any(|executor| executor.to_string() == *name)
+        {
+            return Err(format!(
+                "Unknown executor '{}' in prompt token budgets",
+                name
+            ));
+        }
+        if *budget < MIN_PROMPT_TOKEN_BUDGET {
+            return Err(format!(
+                "The prompt token budget of {} must be at least {}",
+                name, MIN_PROMPT_TOKEN_BUDGET
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check per-executor output formats: only executors with a text mode can be switched to it
+pub fn validate_executor_output_formats(
+    formats: &HashMap<String, OutputFormat>,
+) -> Result<(), String> {
+    for (name, format) in formats {
+        let Some(executor) = ExecutorConfig::agents()
+            .into_iter()
+            .find(|executor| executor.to_string() == *name)
+        else {
+            return Err(format!("Unknown executor '{}' in output formats", name));
+        };
+        if *format == OutputFormat::Text && !executor.supports_text_output() {
+            return Err(format!(
+                "{} doesn't support text output",
+                executor.display_name()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check per-executor CLI paths: only executors with an installable CLI run one
+pub fn validate_executable_paths(paths: &HashMap<String, String>) -> Result<(), String> {
+    for name in paths.keys() {
+        if !ExecutorConfig::agents()
+            .iter()
+            .any(|executor| executor.to_string() == *name && executor.cli_program().is_some())
+        {
+            return Err(format!(
+                "'{}' doesn't run a CLI that can be installed; set paths for claude, gemini or opencode",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
 impl Config {
+    /// Path of `executor`'s CLI set in `executable_paths`, if any
+    pub fn executable_path(&self, executor: &ExecutorConfig) -> Option<&str> {
+        self.executable_paths
+            .get(&executor.to_string())
+            .map(|path| path.trim())
+            .filter(|path| !path.is_empty())
+    }
+
+    /// Estimated tokens an auto-composed prompt for `executor` may use
+    pub fn prompt_token_budget(&self, executor: &ExecutorConfig) -> u32 {
+        self.prompt_token_budgets
+            .get(&executor.to_string())
+            .copied()
+            .unwrap_or_else(|| executor.default_prompt_token_budget())
+    }
+
+    /// Output format `executor` is run with. The custom executor's is set by
+    /// `custom_executor_jsonl`.
+    pub fn output_format(&self, executor: &ExecutorConfig) -> OutputFormat {
+        if matches!(executor, ExecutorConfig::Custom) {
+            return match self.custom_executor_jsonl {
+                true => OutputFormat::Json,
+                false => OutputFormat::Text,
+            };
+        }
+        self.executor_output_formats
+            .get(&executor.to_string())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Template framing prompts of `kind`, if one is set
+    pub fn prompt_template(&self, kind: PromptKind) -> Option<&str> {
+        match kind {
+            PromptKind::Initial => self.initial_prompt_template.as_deref(),
+            PromptKind::FollowUp => self.followup_prompt_template.as_deref(),
+        }
+    }
+
+    /// Whether a newly created pull request should be opened in the browser, which never
+    /// happens on a `headless` machine
+    pub fn opens_prs_in_browser(&self, headless: bool) -> bool {
+        self.open_pr_in_browser && !headless
+    }
+
+    /// Matcher for `ignored_paths`
+    pub fn path_ignore(&self) -> PathIgnore {
+        PathIgnore::new(&self.ignored_paths)
+    }
+
+    /// Whether attempts may run with `executor`. Setup scripts are part of every attempt, so
+    /// they're never disabled.
+    pub fn executor_enabled(&self, executor: &ExecutorConfig) -> bool {
+        match (&self.enabled_executors, executor) {
+            (_, ExecutorConfig::SetupScript { .. }) | (None, _) => true,
+            (Some(enabled), executor) => enabled.contains(&executor.to_string()),
+        }
+    }
+
+    pub fn check_executor_enabled(&self, executor: &ExecutorConfig) -> Result<(), String> {
+        if self.executor_enabled(executor) {
+            Ok(())
+        } else {
+            Err(format!(
+                "The {} executor is disabled on this server",
+                executor.display_name()
+            ))
+        }
+    }
+
+    /// Agent executors users can pick from
+    pub fn available_executors(&self) -> Vec<ExecutorConfig> {
+        ExecutorConfig::agents()
+            .into_iter()
+            .filter(|executor| self.executor_enabled(executor))
+            .collect()
+    }
+
+    /// Copy of the config that is safe to send to clients
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        config.github.has_access_token = config.github.access_token.is_some();
+        config.github.access_token = None;
+        config
+    }
+
+    /// Carry the stored access token over into a config received from a client, which never
+    /// includes it. The token is dropped if the client cleared `has_access_token`.
+    pub fn restore_secrets(&mut self, current: &Config) {
+        if self.github.has_access_token {
+            self.github.access_token = current.github.access_token.clone();
+        }
+        self.github.has_access_token = self.github.access_token.is_some();
+    }
+
     pub fn load(config_path: &PathBuf) -> anyhow::Result<Self> {
         if config_path.exists() {
             let content = std::fs::read_to_string(config_path)?;
@@ -291,7 +830,27 @@ impl Config {
         }
     }
 
+    /// Read the config without writing it back, for processes other than the server, like
+    /// the MCP server, that must not race it for the file
+    #[allow(dead_code)]
+    pub fn read(config_path: &PathBuf) -> anyhow::Result<Self> {
+        if config_path.exists() {
+            Self::parse_with_defaults(&std::fs::read_to_string(config_path)?)
+        } else {
+            Ok(Config::default())
+        }
+    }
+
     fn load_with_defaults(content: &str, config_path: &PathBuf) -> anyhow::Result<Self> {
+        let config = Self::parse_with_defaults(content)?;
+
+        // Save the updated config with any missing defaults
+        config.save(config_path)?;
+
+        Ok(config)
+    }
+
+    fn parse_with_defaults(content: &str) -> anyhow::Result<Self> {
         // Parse as generic JSON value
         let existing_value: serde_json::Value = serde_json::from_str(content)?;
 
@@ -303,12 +862,7 @@ impl Config {
         let merged_value = Self::merge_json_values(default_value, existing_value);
 
         // Deserialize merged value back to Config
-        let config: Config = serde_json::from_value(merged_value)?;
-
-        // Save the updated config with any missing defaults
-        config.save(config_path)?;
-
-        Ok(config)
+        Ok(serde_json::from_value(merged_value)?)
     }
 
     fn merge_json_values(
@@ -338,3 +892,117 @@ impl Config {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_tags_are_checked_by_shape() {
+        for tag in ["en", "ja", "pt-BR", "zh-Hant-TW", "es-419"] {
+            assert!(is_valid_language_tag(tag), "{}", tag);
+        }
+        for tag in [
+            "",
+            "e",
+            "english!",
+            "pt_BR",
+            "en-",
+            "-en",
+            "12",
+            "en-toolongsubtag",
+        ] {
+            assert!(!is_valid_language_tag(tag), "{}", tag);
+        }
+
+        assert!(validate_response_language(None).is_ok());
+        assert!(validate_response_language(Some("pt-BR")).is_ok());
+        assert!(validate_response_language(Some("Brazilian"))
+            .unwrap_err()
+            .contains("'Brazilian'"));
+    }
+
+    #[test]
+    fn test_prs_open_in_the_browser_only_when_enabled_and_not_headless() {
+        let mut config = Config::default();
+        assert!(!config.opens_prs_in_browser(false));
+        assert!(!config.opens_prs_in_browser(true));
+
+        config.open_pr_in_browser = true;
+        assert!(config.opens_prs_in_browser(false));
+        assert!(!config.opens_prs_in_browser(true));
+    }
+
+    #[test]
+    fn test_disabled_executors_are_rejected_and_not_listed() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.available_executors().len(),
+            ExecutorConfig::agents().len()
+        );
+
+        config.enabled_executors = Some(vec!["claude".to_string()]);
+        let available: Vec<_> = config
+            .available_executors()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(available, vec!["claude"]);
+        assert!(config
+            .check_executor_enabled(&ExecutorConfig::Claude)
+            .is_ok());
+        assert_eq!(
+            config.check_executor_enabled(&ExecutorConfig::Amp),
+            Err("The Amp executor is disabled on this server".to_string())
+        );
+        assert!(config.executor_enabled(&ExecutorConfig::SetupScript {
+            script: "npm install".to_string()
+        }));
+
+        let enabled = Some(vec!["claude".to_string()]);
+        assert!(validate_enabled_executors(enabled.as_deref(), &ExecutorConfig::Claude).is_ok());
+        assert!(validate_enabled_executors(enabled.as_deref(), &ExecutorConfig::Amp).is_err());
+        assert!(validate_enabled_executors(Some(&[]), &ExecutorConfig::Claude).is_err());
+        assert!(validate_enabled_executors(
+            Some(&["claude".to_string(), "setup_script".to_string()]),
+            &ExecutorConfig::Claude
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_access_token_is_redacted_for_clients() {
+        let mut current = Config::default();
+        current.github.access_token = Some("enc:v1:secret".to_string());
+        current.github.has_access_token = true;
+
+        assert!(!format!("{:?}", current).contains("enc:v1:secret"));
+
+        let redacted = current.redacted();
+        let json = serde_json::to_value(&redacted).unwrap();
+        assert!(json["github"].get("access_token").is_none());
+        assert_eq!(json["github"]["has_access_token"], true);
+
+        // Saving the redacted copy back keeps the token; clearing the flag signs out
+        let mut incoming = redacted.clone();
+        incoming.restore_secrets(&current);
+        assert_eq!(
+            incoming.github.access_token.as_deref(),
+            Some("enc:v1:secret")
+        );
+
+        let mut signed_out = redacted;
+        signed_out.github.has_access_token = false;
+        signed_out.restore_secrets(&current);
+        assert!(signed_out.github.access_token.is_none());
+    }
+
+    #[test]
+    fn test_worktree_name_pattern_requires_attempt_id() {
+        assert!(validate_worktree_name_pattern(DEFAULT_WORKTREE_NAME_PATTERN).is_ok());
+        assert!(validate_worktree_name_pattern("{task_title}-{attempt_id}").is_ok());
+        assert!(validate_worktree_name_pattern("vk-{task_id}").is_err());
+        assert!(validate_worktree_name_pattern("{attempt_id}-{branch}").is_err());
+        assert!(validate_worktree_name_pattern("../{attempt_id}").is_err());
+    }
+}
@@ -4,6 +4,7 @@ use sqlx::{FromRow, SqlitePool, Type};
 use ts_rs::TS;
 use uuid::Uuid;
 
+use super::executor_session::ExecutorSession;
 use crate::app_state::ExecutionType;
 
 /// Filter out stderr boundary markers from output
@@ -41,6 +42,22 @@ pub enum ExecutionProcessType {
     SetupScript,
     CodingAgent,
     DevServer,
+    /// `git bisect` over the attempt's commits, in a temporary worktree
+    Bisect,
+    /// The project's formatters, over the files the coding agent changed
+    FormatScript,
+}
+
+impl ExecutionProcessType {
+    /// Whether the process is a stage of the attempt itself, recorded as activities. Dev
+    /// servers and bisect runs only run alongside it, and formatting is recorded with the
+    /// coding agent it follows.
+    pub fn is_attempt_stage(&self) -> bool {
+        matches!(
+            self,
+            ExecutionProcessType::SetupScript | ExecutionProcessType::CodingAgent
+        )
+    }
 }
 
 impl From<ExecutionType> for ExecutionProcessType {
@@ -49,6 +66,8 @@ impl From<ExecutionType> for ExecutionProcessType {
             ExecutionType::SetupScript => ExecutionProcessType::SetupScript,
             ExecutionType::CodingAgent => ExecutionProcessType::CodingAgent,
             ExecutionType::DevServer => ExecutionProcessType::DevServer,
+            ExecutionType::Bisect => ExecutionProcessType::Bisect,
+            ExecutionType::FormatScript => ExecutionProcessType::FormatScript,
         }
     }
 }
@@ -59,6 +78,8 @@ impl From<ExecutionProcessType> for ExecutionType {
             ExecutionProcessType::SetupScript => ExecutionType::SetupScript,
             ExecutionProcessType::CodingAgent => ExecutionType::CodingAgent,
             ExecutionProcessType::DevServer => ExecutionType::DevServer,
+            ExecutionProcessType::Bisect => ExecutionType::Bisect,
+            ExecutionProcessType::FormatScript => ExecutionType::FormatScript,
         }
     }
 }
@@ -78,9 +99,16 @@ pub struct ExecutionProcess {
     #[serde(serialize_with = "serialize_filtered_stderr")]
     pub stderr: Option<String>,
     pub exit_code: Option<i64>,
+    pub stop_reason: Option<String>,
+    /// Output bytes replaced as invalid UTF-8 or dropped as control characters when captured
+    pub replaced_output_bytes: i64,
+    #[serde(with = "crate::utils::rfc3339")]
     pub started_at: DateTime<Utc>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
     pub completed_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -101,6 +129,7 @@ pub struct CreateExecutionProcess {
 pub struct UpdateExecutionProcess {
     pub status: Option<ExecutionProcessStatus>,
     pub exit_code: Option<i64>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
     pub completed_at: Option<DateTime<Utc>>,
 }
 
@@ -116,10 +145,22 @@ pub struct ExecutionProcessSummary {
     pub args: Option<String>, // JSON array of arguments
     pub working_directory: String,
     pub exit_code: Option<i64>,
+    pub stop_reason: Option<String>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub started_at: DateTime<Utc>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
     pub completed_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
+    /// Whether the worktree was snapshotted when the process ended, so the changes it made
+    /// on its own can be diffed
+    pub has_snapshot: bool,
+    /// When focus mode resumes the process, while it's paused. Not stored; the route fills
+    /// it in from the running process.
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub paused_until: Option<DateTime<Utc>>,
 }
 
 impl ExecutionProcess {
@@ -139,6 +180,8 @@ impl ExecutionProcess {
                 stdout, 
                 stderr, 
                 exit_code,
+                stop_reason,
+                replaced_output_bytes,
                 started_at as "started_at!: DateTime<Utc>",
                 completed_at as "completed_at?: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>", 
@@ -170,6 +213,8 @@ impl ExecutionProcess {
                 stdout, 
                 stderr, 
                 exit_code,
+                stop_reason,
+                replaced_output_bytes,
                 started_at as "started_at!: DateTime<Utc>",
                 completed_at as "completed_at?: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>", 
@@ -200,10 +245,16 @@ impl ExecutionProcess {
                 args, 
                 working_directory, 
                 exit_code,
+                stop_reason,
                 started_at as "started_at!: DateTime<Utc>",
                 completed_at as "completed_at?: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>", 
-                updated_at as "updated_at!: DateTime<Utc>"
+                updated_at as "updated_at!: DateTime<Utc>",
+                EXISTS (
+                    SELECT 1 FROM execution_snapshots
+                    WHERE execution_process_id = execution_processes.id
+                ) as "has_snapshot!: bool",
+                NULL as "paused_until?: DateTime<Utc>"
                FROM execution_processes 
                WHERE task_attempt_id = $1 
                ORDER BY created_at ASC"#,
@@ -229,6 +280,8 @@ impl ExecutionProcess {
                 stdout, 
                 stderr, 
                 exit_code,
+                stop_reason,
+                replaced_output_bytes,
                 started_at as "started_at!: DateTime<Utc>",
                 completed_at as "completed_at?: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>", 
@@ -241,6 +294,25 @@ impl ExecutionProcess {
         .await
     }
 
+    /// IDs of finished agent and setup-script processes, optionally for one executor type,
+    /// oldest first
+    pub async fn find_finished_ids(
+        pool: &SqlitePool,
+        executor_type: Option<&str>,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT id as "id!: Uuid"
+               FROM execution_processes
+               WHERE status != 'running'
+                 AND process_type != 'devserver'
+                 AND ($1 IS NULL OR executor_type = $1)
+               ORDER BY created_at ASC"#,
+            executor_type
+        )
+        .fetch_all(pool)
+        .await
+    }
+
     /// Find running dev servers for a specific project
     pub async fn find_running_dev_servers_by_project(
         pool: &SqlitePool,
@@ -260,6 +332,8 @@ impl ExecutionProcess {
                 ep.stdout, 
                 ep.stderr, 
                 ep.exit_code,
+                ep.stop_reason,
+                ep.replaced_output_bytes,
                 ep.started_at as "started_at!: DateTime<Utc>",
                 ep.completed_at as "completed_at?: DateTime<Utc>",
                 ep.created_at as "created_at!: DateTime<Utc>", 
@@ -305,6 +379,8 @@ impl ExecutionProcess {
                 stdout, 
                 stderr, 
                 exit_code,
+                stop_reason,
+                replaced_output_bytes,
                 started_at as "started_at!: DateTime<Utc>",
                 completed_at as "completed_at?: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>", 
@@ -354,9 +430,76 @@ impl ExecutionProcess {
         .execute(pool)
         .await?;
 
+        if let Some(completed_at) = completed_at {
+            ExecutorSession::mark_terminated(pool, id, completed_at).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark an execution process as killed, recording why it was stopped
+    pub async fn mark_stopped(
+        pool: &SqlitePool,
+        id: Uuid,
+        reason: &str,
+    ) -> Result<(), sqlx::Error> {
+        let completed_at = Utc::now();
+
+        sqlx::query!(
+            r#"UPDATE execution_processes 
+               SET status = $1, stop_reason = $2, completed_at = $3, updated_at = datetime('now') 
+               WHERE id = $4"#,
+            ExecutionProcessStatus::Killed,
+            reason,
+            completed_at,
+            id
+        )
+        .execute(pool)
+        .await?;
+
+        ExecutorSession::mark_terminated(pool, id, completed_at).await?;
+
         Ok(())
     }
 
+    /// Status and exit code of a process, without loading its output
+    pub async fn find_status(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<Option<(ExecutionProcessStatus, Option<i64>)>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"SELECT status as "status!: ExecutionProcessStatus", exit_code
+               FROM execution_processes
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(|row| (row.status, row.exit_code)))
+    }
+
+    /// Stdout from byte `offset` on, read together with the process status so a process that
+    /// isn't running anymore has nothing after it. `None` if the process doesn't exist.
+    pub async fn read_stdout_from(
+        pool: &SqlitePool,
+        id: Uuid,
+        offset: i64,
+    ) -> Result<Option<(Vec<u8>, ExecutionProcessStatus)>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"SELECT substr(CAST(COALESCE(stdout, '') AS BLOB), $2 + 1) as "chunk!: Vec<u8>",
+                      status as "status!: ExecutionProcessStatus"
+               FROM execution_processes
+               WHERE id = $1"#,
+            id,
+            offset
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(|row| (row.chunk, row.status)))
+    }
+
     /// Append to stdout for this execution process (for streaming updates)
     pub async fn append_stdout(
         pool: &SqlitePool,
@@ -391,6 +534,24 @@ impl ExecutionProcess {
         Ok(())
     }
 
+    /// Count output bytes that were replaced or dropped while capturing the process's output
+    pub async fn add_replaced_output_bytes(
+        pool: &SqlitePool,
+        id: Uuid,
+        count: usize,
+    ) -> Result<(), sqlx::Error> {
+        let count = count as i64;
+        sqlx::query!(
+            "UPDATE execution_processes SET replaced_output_bytes = replaced_output_bytes + $1 WHERE id = $2",
+            count,
+            id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Append to both stdout and stderr for this execution process
     pub async fn append_output(
         pool: &SqlitePool,
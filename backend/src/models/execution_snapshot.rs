@@ -0,0 +1,84 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+/// The worktree of an attempt as one of its executions left it
+#[derive(Debug, Clone)]
+pub struct ExecutionSnapshot {
+    pub execution_process_id: Uuid,
+    /// Commit holding the worktree's files, uncommitted changes included
+    pub snapshot_commit: String,
+    /// The worktree's HEAD when the snapshot was taken
+    pub head_commit: String,
+}
+
+impl ExecutionSnapshot {
+    /// Ref keeping the snapshot commit of an execution from being garbage collected
+    pub fn ref_name(execution_process_id: Uuid) -> String {
+        format!("refs/codecommand/snapshots/{}", execution_process_id)
+    }
+
+    pub async fn save(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+        task_attempt_id: Uuid,
+        snapshot_commit: &str,
+        head_commit: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"INSERT INTO execution_snapshots
+                   (execution_process_id, task_attempt_id, snapshot_commit, head_commit)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (execution_process_id) DO UPDATE
+               SET snapshot_commit = excluded.snapshot_commit,
+                   head_commit = excluded.head_commit,
+                   created_at = datetime('now', 'subsec')"#,
+            execution_process_id,
+            task_attempt_id,
+            snapshot_commit,
+            head_commit
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_execution_process_id(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            ExecutionSnapshot,
+            r#"SELECT execution_process_id AS "execution_process_id!: Uuid",
+                      snapshot_commit,
+                      head_commit
+               FROM execution_snapshots
+               WHERE execution_process_id = $1"#,
+            execution_process_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Snapshot of the latest execution of the same attempt that started before this one
+    pub async fn find_previous(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            ExecutionSnapshot,
+            r#"SELECT s.execution_process_id AS "execution_process_id!: Uuid",
+                      s.snapshot_commit,
+                      s.head_commit
+               FROM execution_snapshots s
+               JOIN execution_processes ep ON ep.id = s.execution_process_id
+               JOIN execution_processes current ON current.id = $1
+               WHERE s.task_attempt_id = current.task_attempt_id
+                 AND ep.created_at < current.created_at
+               ORDER BY ep.created_at DESC
+               LIMIT 1"#,
+            execution_process_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+}
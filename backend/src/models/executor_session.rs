@@ -1,9 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool};
+use sqlx::{types::Json, FromRow, SqlitePool};
 use ts_rs::TS;
 use uuid::Uuid;
 
+use crate::services::prompt_budget::PromptBudgetReport;
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct ExecutorSession {
@@ -13,16 +15,44 @@ pub struct ExecutorSession {
     pub session_id: Option<String>, // External session ID from Claude/Amp
     pub prompt: Option<String>,     // The prompt sent to the executor
     pub summary: Option<String>,    // Final assistant message/summary
+    pub response_language: Option<String>, // Language the agent was asked to respond in
+    #[ts(type = "PromptBudgetReport | null")]
+    pub prompt_budget: Option<Json<PromptBudgetReport>>, // How the prompt was fitted into the token budget
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub terminated_at: Option<DateTime<Utc>>, // When the execution process finished
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
 }
 
+/// Executor session together with its computed duration
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ExecutorSessionWithDuration {
+    #[serde(flatten)]
+    pub session: ExecutorSession,
+    pub duration_secs: Option<f64>,
+}
+
+impl From<ExecutorSession> for ExecutorSessionWithDuration {
+    fn from(session: ExecutorSession) -> Self {
+        let duration_secs = session.duration_secs();
+        Self {
+            session,
+            duration_secs,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, TS)]
 #[ts(export)]
 pub struct CreateExecutorSession {
     pub task_attempt_id: Uuid,
     pub execution_process_id: Uuid,
     pub prompt: Option<String>,
+    pub response_language: Option<String>,
+    pub prompt_budget: Option<PromptBudgetReport>,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -35,6 +65,13 @@ pub struct UpdateExecutorSession {
 }
 
 impl ExecutorSession {
+    /// How long the session ran, in seconds; `None` while it is still running
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.terminated_at.map(|terminated_at| {
+            (terminated_at - self.created_at).num_milliseconds() as f64 / 1000.0
+        })
+    }
+
     /// Find executor session by ID
     #[allow(dead_code)]
     pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
@@ -47,6 +84,9 @@ impl ExecutorSession {
                 session_id, 
                 prompt,
                 summary,
+                response_language,
+                prompt_budget as "prompt_budget: Json<PromptBudgetReport>",
+                terminated_at as "terminated_at: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>", 
                 updated_at as "updated_at!: DateTime<Utc>"
                FROM executor_sessions 
@@ -71,6 +111,9 @@ impl ExecutorSession {
                 session_id,
                 prompt,
                 summary,
+                response_language,
+                prompt_budget as "prompt_budget: Json<PromptBudgetReport>",
+                terminated_at as "terminated_at: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>",
                 updated_at as "updated_at!: DateTime<Utc>"
                FROM executor_sessions
@@ -82,7 +125,6 @@ impl ExecutorSession {
     }
 
     /// Find all executor sessions for a task attempt
-    #[allow(dead_code)]
     pub async fn find_by_task_attempt_id(
         pool: &SqlitePool,
         task_attempt_id: Uuid,
@@ -96,6 +138,9 @@ impl ExecutorSession {
                 session_id, 
                 prompt,
                 summary,
+                response_language,
+                prompt_budget as "prompt_budget: Json<PromptBudgetReport>",
+                terminated_at as "terminated_at: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>", 
                 updated_at as "updated_at!: DateTime<Utc>"
                FROM executor_sessions 
@@ -107,6 +152,48 @@ impl ExecutorSession {
         .await
     }
 
+    /// The session to resume when following up on a task without naming an attempt: that of
+    /// the most recent completed coding agent run with a session id, among runs that are the
+    /// last of their attempt, so the follow-up continues exactly that session
+    pub async fn find_latest_resumable_for_task(
+        pool: &SqlitePool,
+        task_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            ExecutorSession,
+            r#"SELECT
+                es.id as "id!: Uuid",
+                es.task_attempt_id as "task_attempt_id!: Uuid",
+                es.execution_process_id as "execution_process_id!: Uuid",
+                es.session_id,
+                es.prompt,
+                es.summary,
+                es.response_language,
+                es.prompt_budget as "prompt_budget: Json<PromptBudgetReport>",
+                es.terminated_at as "terminated_at: DateTime<Utc>",
+                es.created_at as "created_at!: DateTime<Utc>",
+                es.updated_at as "updated_at!: DateTime<Utc>"
+               FROM executor_sessions es
+               JOIN execution_processes ep ON ep.id = es.execution_process_id
+               JOIN task_attempts ta ON ta.id = es.task_attempt_id
+               WHERE ta.task_id = $1
+                 AND ep.process_type = 'codingagent'
+                 AND ep.status = 'completed'
+                 AND es.session_id IS NOT NULL
+                 AND NOT EXISTS (
+                     SELECT 1 FROM execution_processes later
+                     WHERE later.task_attempt_id = ep.task_attempt_id
+                       AND later.process_type = 'codingagent'
+                       AND later.created_at > ep.created_at
+                 )
+               ORDER BY ep.created_at DESC
+               LIMIT 1"#,
+            task_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
     /// Create a new executor session
     pub async fn create(
         pool: &SqlitePool,
@@ -124,9 +211,9 @@ impl ExecutorSession {
             ExecutorSession,
             r#"INSERT INTO executor_sessions (
                 id, task_attempt_id, execution_process_id, session_id, prompt, summary,
-                created_at, updated_at
+                response_language, prompt_budget, created_at, updated_at
                )
-               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                RETURNING
                 id as "id!: Uuid",
                 task_attempt_id as "task_attempt_id!: Uuid",
@@ -134,6 +221,9 @@ impl ExecutorSession {
                 session_id,
                 prompt,
                 summary,
+                response_language,
+                prompt_budget as "prompt_budget: Json<PromptBudgetReport>",
+                terminated_at as "terminated_at: DateTime<Utc>",
                 created_at as "created_at!: DateTime<Utc>",
                 updated_at as "updated_at!: DateTime<Utc>""#,
             session_id,
@@ -142,8 +232,10 @@ impl ExecutorSession {
             None::<String>, // session_id initially None until parsed from output
             data.prompt,
             None::<String>, // summary initially None
-            now,            // created_at
-            now             // updated_at
+            data.response_language,
+            data.prompt_budget.clone().map(Json),
+            now, // created_at
+            now  // updated_at
         )
         .fetch_one(pool)
         .await
@@ -207,6 +299,68 @@ impl ExecutorSession {
         Ok(())
     }
 
+    /// Record when the execution process behind the session finished
+    pub async fn mark_terminated(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+        terminated_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE executor_sessions
+               SET terminated_at = $1, updated_at = datetime('now')
+               WHERE execution_process_id = $2"#,
+            terminated_at,
+            execution_process_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Average duration of finished sessions across a project's attempts, in seconds
+    pub async fn average_duration_secs_for_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Option<f64>, sqlx::Error> {
+        let record = sqlx::query!(
+            r#"SELECT AVG((julianday(es.terminated_at) - julianday(es.created_at)) * 86400.0) AS "avg_secs: f64"
+               FROM executor_sessions es
+               JOIN task_attempts ta ON ta.id = es.task_attempt_id
+               JOIN tasks t ON t.id = ta.task_id
+               WHERE t.project_id = $1
+                 AND es.terminated_at IS NOT NULL
+                 AND ta.benchmark_run_id IS NULL"#,
+            project_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(record.avg_secs)
+    }
+
+    /// Session ids recorded by executions of `executor_type`, with their execution process
+    pub async fn find_session_ids_by_executor_type(
+        pool: &SqlitePool,
+        executor_type: &str,
+    ) -> Result<Vec<(Uuid, String)>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT es.execution_process_id AS "execution_process_id!: Uuid",
+                      es.session_id           AS "session_id!"
+               FROM executor_sessions es
+               JOIN execution_processes ep ON ep.id = es.execution_process_id
+               WHERE ep.executor_type = $1 AND es.session_id IS NOT NULL
+               ORDER BY es.created_at"#,
+            executor_type
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(records
+            .into_iter()
+            .map(|record| (record.execution_process_id, record.session_id))
+            .collect())
+    }
+
     /// Delete executor sessions for a task attempt (cleanup)
     #[allow(dead_code)]
     pub async fn delete_by_task_attempt_id(
@@ -223,3 +377,167 @@ impl ExecutorSession {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::test_utils::{insert_attempt, insert_project, insert_task, memory_pool};
+
+    /// Add a coding agent run to `attempt_id`, started `minutes_ago`, with its session
+    async fn coding_agent_run(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        minutes_ago: i64,
+        status: &str,
+        session_id: Option<&str>,
+    ) -> Uuid {
+        let process_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO execution_processes (id, task_attempt_id, process_type, status, command, working_directory, created_at)
+             VALUES ($1, $2, 'codingagent', $3, 'executor', '/tmp/w', datetime('now', $4))",
+        )
+        .bind(process_id)
+        .bind(attempt_id)
+        .bind(status)
+        .bind(format!("-{} minutes", minutes_ago))
+        .execute(pool)
+        .await
+        .unwrap();
+        ExecutorSession::create(
+            pool,
+            &CreateExecutorSession {
+                task_attempt_id: attempt_id,
+                execution_process_id: process_id,
+                prompt: None,
+                response_language: None,
+                prompt_budget: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        if let Some(session_id) = session_id {
+            ExecutorSession::update_session_id(pool, process_id, session_id)
+                .await
+                .unwrap();
+        }
+        process_id
+    }
+
+    #[tokio::test]
+    async fn test_resumes_the_latest_completed_session_of_a_task() {
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, "/tmp/p").await;
+        let task_id = insert_task(&pool, project_id, "t").await;
+
+        // No session to resume yet
+        let first = insert_attempt(&pool, task_id, "/tmp/w", "first").await;
+        assert!(
+            ExecutorSession::find_latest_resumable_for_task(&pool, task_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        coding_agent_run(&pool, first, 30, "completed", None).await;
+        assert!(
+            ExecutorSession::find_latest_resumable_for_task(&pool, task_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let resumable = coding_agent_run(&pool, first, 20, "completed", Some("older")).await;
+        let second = insert_attempt(&pool, task_id, "/tmp/w", "second").await;
+        let latest = coding_agent_run(&pool, second, 10, "completed", Some("latest")).await;
+        let session = ExecutorSession::find_latest_resumable_for_task(&pool, task_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.execution_process_id, latest);
+        assert_eq!(session.session_id.as_deref(), Some("latest"));
+
+        // A run still going, or one that failed, isn't resumed, and hides its attempt's
+        // earlier sessions
+        coding_agent_run(&pool, second, 1, "running", Some("busy")).await;
+        let session = ExecutorSession::find_latest_resumable_for_task(&pool, task_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.execution_process_id, resumable);
+        assert_eq!(session.task_attempt_id, first);
+    }
+
+    #[test]
+    fn test_duration_runs_from_creation_to_termination() {
+        let created_at = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let session = |terminated_at| ExecutorSession {
+            id: Uuid::new_v4(),
+            task_attempt_id: Uuid::new_v4(),
+            execution_process_id: Uuid::new_v4(),
+            session_id: None,
+            prompt: None,
+            summary: None,
+            response_language: None,
+            prompt_budget: None,
+            terminated_at,
+            created_at,
+            updated_at: created_at,
+        };
+
+        let finished = session(Some(created_at + chrono::Duration::milliseconds(90_500)));
+        assert_eq!(finished.duration_secs(), Some(90.5));
+        // Still running, or never marked as terminated
+        let running = ExecutorSessionWithDuration::from(session(None));
+        assert_eq!(running.duration_secs, None);
+        let json = serde_json::to_value(&running).unwrap();
+        assert!(json["duration_secs"].is_null());
+        assert!(json["terminated_at"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_average_duration_covers_the_finished_sessions_of_one_project() {
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, "/tmp/p").await;
+        let other_project_id = insert_project(&pool, "/tmp/q").await;
+        let task_id = insert_task(&pool, project_id, "t").await;
+        let other_task_id = insert_task(&pool, other_project_id, "t").await;
+        let attempt_id = insert_attempt(&pool, task_id, "/tmp/w", "b").await;
+        let other_attempt_id = insert_attempt(&pool, other_task_id, "/tmp/w", "b").await;
+        assert_eq!(
+            ExecutorSession::average_duration_secs_for_project(&pool, project_id)
+                .await
+                .unwrap(),
+            None
+        );
+
+        let terminate_after = |process_id, secs| {
+            let pool = pool.clone();
+            async move {
+                let session = ExecutorSession::find_by_execution_process_id(&pool, process_id)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let terminated_at = session.created_at + chrono::Duration::seconds(secs);
+                ExecutorSession::mark_terminated(&pool, process_id, terminated_at)
+                    .await
+                    .unwrap();
+            }
+        };
+        let short = coding_agent_run(&pool, attempt_id, 30, "completed", None).await;
+        terminate_after(short, 60).await;
+        let long = coding_agent_run(&pool, attempt_id, 20, "killed", None).await;
+        terminate_after(long, 120).await;
+        // Neither a session that's still running nor one in another project counts
+        coding_agent_run(&pool, attempt_id, 1, "running", None).await;
+        let elsewhere = coding_agent_run(&pool, other_attempt_id, 10, "completed", None).await;
+        terminate_after(elsewhere, 1_000).await;
+
+        let average = ExecutorSession::average_duration_secs_for_project(&pool, project_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((average - 90.0).abs() < 0.01, "average was {}", average);
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::project::FormatCommitMode;
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "format_run_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum FormatRunStatus {
+    Running,
+    /// The formatters changed files, and the changes were committed
+    Formatted,
+    /// The files were already formatted
+    Unchanged,
+    /// A formatter failed; whatever the others changed was still committed
+    Failed,
+    Cancelled,
+}
+
+/// Which part of an attempt's diff to show, around the commits made by its format runs
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum FormattingDiff {
+    /// Everything, formatting included
+    #[default]
+    All,
+    /// The agent's changes, with the formatting commits left out
+    Exclude,
+    /// Only what the formatting commits changed
+    Only,
+}
+
+/// The project's format script run over the files an attempt changed, after its coding agent
+/// finished and before it's ready for review. Its id is that of the execution process running
+/// it.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct FormatRun {
+    pub id: Uuid,
+    pub task_attempt_id: Uuid,
+    /// The coding agent run whose changes are formatted
+    pub coding_agent_process_id: Uuid,
+    pub agent_succeeded: bool,
+    #[ts(type = "number | null")]
+    pub agent_exit_code: Option<i64>,
+    pub command: String,
+    pub files: Vec<String>,
+    pub commit_mode: FormatCommitMode,
+    pub status: FormatRunStatus,
+    /// The separate style commit, when the formatters changed something
+    pub commit_sha: Option<String>,
+    pub error: Option<String>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+struct FormatRunRow {
+    id: Uuid,
+    task_attempt_id: Uuid,
+    coding_agent_process_id: Uuid,
+    agent_succeeded: bool,
+    agent_exit_code: Option<i64>,
+    command: String,
+    files: String,
+    commit_mode: FormatCommitMode,
+    status: FormatRunStatus,
+    commit_sha: Option<String>,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<FormatRunRow> for FormatRun {
+    type Error = sqlx::Error;
+
+    fn try_from(row: FormatRunRow) -> Result<Self, Self::Error> {
+        let files =
+            serde_json::from_str(&row.files).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        Ok(Self {
+            id: row.id,
+            task_attempt_id: row.task_attempt_id,
+            coding_agent_process_id: row.coding_agent_process_id,
+            agent_succeeded: row.agent_succeeded,
+            agent_exit_code: row.agent_exit_code,
+            command: row.command,
+            files,
+            commit_mode: row.commit_mode,
+            status: row.status,
+            commit_sha: row.commit_sha,
+            error: row.error,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        })
+    }
+}
+
+/// What a format run is created from
+pub struct CreateFormatRun<'a> {
+    pub task_attempt_id: Uuid,
+    pub coding_agent_process_id: Uuid,
+    pub agent_succeeded: bool,
+    pub agent_exit_code: Option<i64>,
+    pub command: &'a str,
+    pub files: &'a [String],
+    pub commit_mode: FormatCommitMode,
+}
+
+impl FormatRun {
+    pub async fn create(
+        pool: &SqlitePool,
+        id: Uuid,
+        data: &CreateFormatRun<'_>,
+    ) -> Result<Self, sqlx::Error> {
+        let files =
+            serde_json::to_string(data.files).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        sqlx::query_as!(
+            FormatRunRow,
+            r#"INSERT INTO format_runs
+                   (id, task_attempt_id, coding_agent_process_id, agent_succeeded,
+                    agent_exit_code, command, files, commit_mode)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+               RETURNING id                      AS "id!: Uuid",
+                         task_attempt_id         AS "task_attempt_id!: Uuid",
+                         coding_agent_process_id AS "coding_agent_process_id!: Uuid",
+                         agent_succeeded         AS "agent_succeeded!: bool",
+                         agent_exit_code,
+                         command,
+                         files,
+                         commit_mode             AS "commit_mode!: FormatCommitMode",
+                         status                  AS "status!: FormatRunStatus",
+                         commit_sha,
+                         error,
+                         created_at              AS "created_at!: DateTime<Utc>",
+                         completed_at            AS "completed_at: DateTime<Utc>""#,
+            id,
+            data.task_attempt_id,
+            data.coding_agent_process_id,
+            data.agent_succeeded,
+            data.agent_exit_code,
+            data.command,
+            files,
+            data.commit_mode
+        )
+        .fetch_one(pool)
+        .await
+        .and_then(Self::try_from)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            FormatRunRow,
+            r#"SELECT id                      AS "id!: Uuid",
+                      task_attempt_id         AS "task_attempt_id!: Uuid",
+                      coding_agent_process_id AS "coding_agent_process_id!: Uuid",
+                      agent_succeeded         AS "agent_succeeded!: bool",
+                      agent_exit_code,
+                      command,
+                      files,
+                      commit_mode             AS "commit_mode!: FormatCommitMode",
+                      status                  AS "status!: FormatRunStatus",
+                      commit_sha,
+                      error,
+                      created_at              AS "created_at!: DateTime<Utc>",
+                      completed_at            AS "completed_at: DateTime<Utc>"
+               FROM format_runs
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(Self::try_from)
+        .transpose()
+    }
+
+    /// Runs of an attempt, newest first
+    pub async fn find_by_task_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            FormatRunRow,
+            r#"SELECT id                      AS "id!: Uuid",
+                      task_attempt_id         AS "task_attempt_id!: Uuid",
+                      coding_agent_process_id AS "coding_agent_process_id!: Uuid",
+                      agent_succeeded         AS "agent_succeeded!: bool",
+                      agent_exit_code,
+                      command,
+                      files,
+                      commit_mode             AS "commit_mode!: FormatCommitMode",
+                      status                  AS "status!: FormatRunStatus",
+                      commit_sha,
+                      error,
+                      created_at              AS "created_at!: DateTime<Utc>",
+                      completed_at            AS "completed_at: DateTime<Utc>"
+               FROM format_runs
+               WHERE task_attempt_id = $1
+               ORDER BY created_at DESC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+
+    /// The attempt's separate style commits, newest first
+    pub async fn find_format_commits(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT commit_sha AS "commit_sha!"
+               FROM format_runs
+               WHERE task_attempt_id = $1 AND commit_sha IS NOT NULL
+               ORDER BY created_at DESC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Running runs whose process is no longer running: stopped, or lost to a restart
+    pub async fn find_stale(pool: &SqlitePool) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT f.id AS "id!: Uuid"
+               FROM format_runs f
+               LEFT JOIN execution_processes ep ON ep.id = f.id
+               WHERE f.status = 'running' AND (ep.id IS NULL OR ep.status != 'running')"#
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Record how a running run ended. Returns false when it had already ended, so only one
+    /// caller finishes the attempt's coding agent stage.
+    pub async fn complete(
+        pool: &SqlitePool,
+        id: Uuid,
+        status: FormatRunStatus,
+        commit_sha: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"UPDATE format_runs
+               SET status = $2, commit_sha = $3, error = $4,
+                   completed_at = datetime('now', 'subsec'), updated_at = datetime('now', 'subsec')
+               WHERE id = $1 AND status = 'running'"#,
+            id,
+            status,
+            commit_sha,
+            error
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
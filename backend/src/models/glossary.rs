@@ -0,0 +1,301 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "glossary_entry_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum GlossaryEntryStatus {
+    /// Part of the glossary given to agents
+    Approved,
+    /// Mined from merged diffs, waiting for someone to define and approve it
+    Suggested,
+    /// A rejected suggestion, kept so the term isn't suggested again
+    Dismissed,
+}
+
+/// A project-specific term and what it means in this codebase
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct GlossaryEntry {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub term: String,
+    pub definition: String,
+    /// Repository paths where the term is implemented or used
+    pub file_refs: Vec<String>,
+    pub status: GlossaryEntryStatus,
+    /// Times the term appeared in merged changes; the most referenced terms are kept when
+    /// the glossary has to be shortened
+    pub occurrences: u32,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateGlossaryEntry {
+    pub term: String,
+    pub definition: String,
+    #[serde(default)]
+    pub file_refs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateGlossaryEntry {
+    pub term: Option<String>,
+    pub definition: Option<String>,
+    pub file_refs: Option<Vec<String>>,
+    /// Set to `approved` to accept a suggestion or `dismissed` to reject it
+    pub status: Option<GlossaryEntryStatus>,
+}
+
+struct GlossaryEntryRow {
+    id: Uuid,
+    project_id: Uuid,
+    term: String,
+    definition: String,
+    file_refs: String,
+    status: GlossaryEntryStatus,
+    occurrences: i64,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<GlossaryEntryRow> for GlossaryEntry {
+    type Error = sqlx::Error;
+
+    fn try_from(row: GlossaryEntryRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            project_id: row.project_id,
+            term: row.term,
+            definition: row.definition,
+            file_refs: serde_json::from_str(&row.file_refs)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            status: row.status,
+            occurrences: row.occurrences.clamp(0, u32::MAX as i64) as u32,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+fn encode_file_refs(file_refs: &[String]) -> Result<String, sqlx::Error> {
+    serde_json::to_string(file_refs).map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+impl GlossaryEntry {
+    /// Entries of a project, most referenced first
+    pub async fn find_by_project_id(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        status: Option<GlossaryEntryStatus>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            GlossaryEntryRow,
+            r#"SELECT id          AS "id!: Uuid",
+                      project_id  AS "project_id!: Uuid",
+                      term,
+                      definition,
+                      file_refs,
+                      status      AS "status!: GlossaryEntryStatus",
+                      occurrences AS "occurrences!: i64",
+                      created_at  AS "created_at!: DateTime<Utc>",
+                      updated_at  AS "updated_at!: DateTime<Utc>"
+               FROM glossary_entries
+               WHERE project_id = $1 AND ($2 IS NULL OR status = $2)
+               ORDER BY occurrences DESC, term COLLATE NOCASE ASC"#,
+            project_id,
+            status
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+
+    pub async fn find_by_id(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            GlossaryEntryRow,
+            r#"SELECT id          AS "id!: Uuid",
+                      project_id  AS "project_id!: Uuid",
+                      term,
+                      definition,
+                      file_refs,
+                      status      AS "status!: GlossaryEntryStatus",
+                      occurrences AS "occurrences!: i64",
+                      created_at  AS "created_at!: DateTime<Utc>",
+                      updated_at  AS "updated_at!: DateTime<Utc>"
+               FROM glossary_entries
+               WHERE project_id = $1 AND id = $2"#,
+            project_id,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(Self::try_from)
+        .transpose()
+    }
+
+    /// The entry for `term` in any status, ignoring case
+    pub async fn find_by_term(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        term: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            GlossaryEntryRow,
+            r#"SELECT id          AS "id!: Uuid",
+                      project_id  AS "project_id!: Uuid",
+                      term,
+                      definition,
+                      file_refs,
+                      status      AS "status!: GlossaryEntryStatus",
+                      occurrences AS "occurrences!: i64",
+                      created_at  AS "created_at!: DateTime<Utc>",
+                      updated_at  AS "updated_at!: DateTime<Utc>"
+               FROM glossary_entries
+               WHERE project_id = $1 AND term = $2 COLLATE NOCASE"#,
+            project_id,
+            term
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(Self::try_from)
+        .transpose()
+    }
+
+    pub async fn create(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        data: &CreateGlossaryEntry,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let file_refs = encode_file_refs(&data.file_refs)?;
+        sqlx::query_as!(
+            GlossaryEntryRow,
+            r#"INSERT INTO glossary_entries (id, project_id, term, definition, file_refs)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING id          AS "id!: Uuid",
+                         project_id  AS "project_id!: Uuid",
+                         term,
+                         definition,
+                         file_refs,
+                         status      AS "status!: GlossaryEntryStatus",
+                         occurrences AS "occurrences!: i64",
+                         created_at  AS "created_at!: DateTime<Utc>",
+                         updated_at  AS "updated_at!: DateTime<Utc>""#,
+            id,
+            project_id,
+            data.term,
+            data.definition,
+            file_refs
+        )
+        .fetch_one(pool)
+        .await
+        .and_then(Self::try_from)
+    }
+
+    /// Record a term mined from merged changes. Does nothing when the project already has an
+    /// entry for it, whatever its status.
+    pub async fn suggest(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        term: &str,
+        file_refs: &[String],
+        occurrences: u32,
+    ) -> Result<(), sqlx::Error> {
+        let id = Uuid::new_v4();
+        let file_refs = encode_file_refs(file_refs)?;
+        let status = GlossaryEntryStatus::Suggested;
+        sqlx::query!(
+            r#"INSERT INTO glossary_entries (id, project_id, term, file_refs, status, occurrences)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               ON CONFLICT DO NOTHING"#,
+            id,
+            project_id,
+            term,
+            file_refs,
+            status,
+            occurrences
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        id: Uuid,
+        term: &str,
+        definition: &str,
+        file_refs: &[String],
+        status: GlossaryEntryStatus,
+    ) -> Result<Self, sqlx::Error> {
+        let file_refs = encode_file_refs(file_refs)?;
+        sqlx::query_as!(
+            GlossaryEntryRow,
+            r#"UPDATE glossary_entries
+               SET term = $3, definition = $4, file_refs = $5, status = $6,
+                   updated_at = datetime('now', 'subsec')
+               WHERE project_id = $1 AND id = $2
+               RETURNING id          AS "id!: Uuid",
+                         project_id  AS "project_id!: Uuid",
+                         term,
+                         definition,
+                         file_refs,
+                         status      AS "status!: GlossaryEntryStatus",
+                         occurrences AS "occurrences!: i64",
+                         created_at  AS "created_at!: DateTime<Utc>",
+                         updated_at  AS "updated_at!: DateTime<Utc>""#,
+            project_id,
+            id,
+            term,
+            definition,
+            file_refs,
+            status
+        )
+        .fetch_one(pool)
+        .await
+        .and_then(Self::try_from)
+    }
+
+    pub async fn add_occurrences(
+        pool: &SqlitePool,
+        id: Uuid,
+        occurrences: u32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE glossary_entries SET occurrences = occurrences + $2 WHERE id = $1",
+            id,
+            occurrences
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, project_id: Uuid, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM glossary_entries WHERE project_id = $1 AND id = $2",
+            project_id,
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::SqlitePool;
+
+/// A mutating request made with an `Idempotency-Key` header and, once it finished, its
+/// response. Keys are scoped to the route (method and path) they were used on.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    /// Hash of the request body, to tell a retry from a different request reusing the key
+    pub body_hash: String,
+    /// `None` while the first request is still being handled
+    pub status_code: Option<u16>,
+    pub content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+struct IdempotencyKeyRow {
+    body_hash: String,
+    status_code: Option<i64>,
+    content_type: Option<String>,
+    response_body: Option<Vec<u8>>,
+}
+
+impl From<IdempotencyKeyRow> for IdempotencyKey {
+    fn from(row: IdempotencyKeyRow) -> Self {
+        Self {
+            body_hash: row.body_hash,
+            status_code: row.status_code.and_then(|code| u16::try_from(code).ok()),
+            content_type: row.content_type,
+            response_body: row.response_body,
+        }
+    }
+}
+
+impl IdempotencyKey {
+    /// Record the start of a request under `key`. Returns `false` when an unexpired record
+    /// already exists, in which case the caller must not run the request again.
+    pub async fn claim(
+        pool: &SqlitePool,
+        key: &str,
+        route: &str,
+        body_hash: &str,
+        ttl: Duration,
+    ) -> Result<bool, sqlx::Error> {
+        let now = Utc::now();
+        sqlx::query!("DELETE FROM idempotency_keys WHERE expires_at <= $1", now)
+            .execute(pool)
+            .await?;
+
+        let expires_at = now + ttl;
+        let result = sqlx::query!(
+            r#"INSERT INTO idempotency_keys (idempotency_key, route, body_hash, expires_at)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT DO NOTHING"#,
+            key,
+            route,
+            body_hash,
+            expires_at
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn find(
+        pool: &SqlitePool,
+        key: &str,
+        route: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let now = Utc::now();
+        let row = sqlx::query_as!(
+            IdempotencyKeyRow,
+            r#"SELECT body_hash,
+                      status_code AS "status_code: i64",
+                      content_type,
+                      response_body
+               FROM idempotency_keys
+               WHERE idempotency_key = $1 AND route = $2 AND expires_at > $3"#,
+            key,
+            route,
+            now
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Self::from))
+    }
+
+    /// Store the response of the request that claimed `key`, to be replayed to retries
+    pub async fn complete(
+        pool: &SqlitePool,
+        key: &str,
+        route: &str,
+        status_code: u16,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE idempotency_keys
+               SET status_code = $3, content_type = $4, response_body = $5
+               WHERE idempotency_key = $1 AND route = $2"#,
+            key,
+            route,
+            status_code,
+            content_type,
+            response_body
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Forget `key` so a retry runs the request again
+    pub async fn release(pool: &SqlitePool, key: &str, route: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE idempotency_key = $1 AND route = $2",
+            key,
+            route
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Forget requests that never finished, e.g. because the server stopped while handling them
+    pub async fn release_pending(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM idempotency_keys WHERE status_code IS NULL")
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+/// An attempt branch with no attempt in this install that the user chose to leave alone
+pub struct IgnoredAttemptBranch;
+
+impl IgnoredAttemptBranch {
+    pub async fn branches_for_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<HashSet<String>, sqlx::Error> {
+        let records = sqlx::query!(
+            "SELECT branch FROM ignored_attempt_branches WHERE project_id = $1",
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(records.into_iter().map(|r| r.branch).collect())
+    }
+
+    pub async fn ignore(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        branch: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO ignored_attempt_branches (project_id, branch) VALUES ($1, $2)
+             ON CONFLICT (project_id, branch) DO NOTHING",
+            project_id,
+            branch
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Forget a branch once it was adopted or deleted
+    pub async fn forget(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        branch: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM ignored_attempt_branches WHERE project_id = $1 AND branch = $2",
+            project_id,
+            branch
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -1,11 +1,27 @@
+pub mod activity_heatmap;
+pub mod annotation;
 pub mod api_response;
+pub mod benchmark;
+pub mod bisect_run;
 pub mod config;
 pub mod execution_process;
+pub mod execution_snapshot;
 pub mod executor_session;
+pub mod format_run;
+pub mod glossary;
+pub mod idempotency_key;
+pub mod ignored_attempt_branch;
+pub mod network_policy;
+pub mod normalized_log_snapshot;
 pub mod project;
+pub mod scheduled_job;
 pub mod task;
 pub mod task_attempt;
 pub mod task_attempt_activity;
+pub mod task_dependency;
+pub mod untracked_change_journal;
+pub mod user_preferences;
+pub mod webhook;
 
 pub use api_response::ApiResponse;
 pub use config::Config;
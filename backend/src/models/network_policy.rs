@@ -0,0 +1,324 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// Whether a project's agents run behind a logging proxy, and which hosts they're expected to
+/// talk to. Domains in either list also cover their subdomains.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct NetworkPolicy {
+    pub project_id: Uuid,
+    pub enabled: bool,
+    pub allowlist: Vec<String>,
+    /// Contacting one of these marks the attempt as a policy violation
+    pub denylist: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateNetworkPolicy {
+    pub enabled: bool,
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    #[serde(default)]
+    pub denylist: Vec<String>,
+}
+
+/// How a contacted host compares to the project's lists
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[sqlx(type_name = "network_verdict", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum NetworkVerdict {
+    Allowed,
+    /// On neither list
+    Unlisted,
+    Denied,
+}
+
+struct NetworkPolicyRow {
+    project_id: Uuid,
+    enabled: bool,
+    allowlist: String,
+    denylist: String,
+}
+
+impl TryFrom<NetworkPolicyRow> for NetworkPolicy {
+    type Error = sqlx::Error;
+
+    fn try_from(row: NetworkPolicyRow) -> Result<Self, Self::Error> {
+        let decode = |list: &str| -> Result<Vec<String>, sqlx::Error> {
+            serde_json::from_str(list).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        };
+        Ok(Self {
+            project_id: row.project_id,
+            enabled: row.enabled,
+            allowlist: decode(&row.allowlist)?,
+            denylist: decode(&row.denylist)?,
+        })
+    }
+}
+
+impl NetworkPolicy {
+    /// Policy of a project that never opted in
+    pub fn disabled(project_id: Uuid) -> Self {
+        Self {
+            project_id,
+            enabled: false,
+            allowlist: Vec::new(),
+            denylist: Vec::new(),
+        }
+    }
+
+    pub async fn find_for_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        let row = sqlx::query_as!(
+            NetworkPolicyRow,
+            r#"SELECT project_id AS "project_id!: Uuid",
+                      enabled    AS "enabled!: bool",
+                      allowlist,
+                      denylist
+               FROM project_network_policies
+               WHERE project_id = $1"#,
+            project_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        match row {
+            Some(row) => Self::try_from(row),
+            None => Ok(Self::disabled(project_id)),
+        }
+    }
+
+    /// Policy of the project an attempt belongs to, if the attempt exists
+    pub async fn find_for_attempt(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let project_id = sqlx::query_scalar!(
+            r#"SELECT t.project_id AS "project_id!: Uuid"
+               FROM task_attempts ta
+               JOIN tasks t ON t.id = ta.task_id
+               WHERE ta.id = $1"#,
+            attempt_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        match project_id {
+            Some(project_id) => Self::find_for_project(pool, project_id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a project's policy, with its lists already checked by `normalize_domains`
+    pub async fn upsert(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        data: &UpdateNetworkPolicy,
+    ) -> Result<Self, sqlx::Error> {
+        let encode = |list: &[String]| -> Result<String, sqlx::Error> {
+            serde_json::to_string(list).map_err(|e| sqlx::Error::Encode(Box::new(e)))
+        };
+        let allowlist = encode(&data.allowlist)?;
+        let denylist = encode(&data.denylist)?;
+        sqlx::query_as!(
+            NetworkPolicyRow,
+            r#"INSERT INTO project_network_policies (project_id, enabled, allowlist, denylist)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (project_id) DO UPDATE
+               SET enabled = excluded.enabled,
+                   allowlist = excluded.allowlist,
+                   denylist = excluded.denylist,
+                   updated_at = datetime('now', 'subsec')
+               RETURNING project_id AS "project_id!: Uuid",
+                         enabled    AS "enabled!: bool",
+                         allowlist,
+                         denylist"#,
+            project_id,
+            data.enabled,
+            allowlist,
+            denylist
+        )
+        .fetch_one(pool)
+        .await
+        .and_then(Self::try_from)
+    }
+
+    /// The denylist wins over the allowlist
+    pub fn verdict(&self, host: &str) -> NetworkVerdict {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let listed = |list: &[String]| list.iter().any(|domain| covers(domain, &host));
+        if listed(&self.denylist) {
+            NetworkVerdict::Denied
+        } else if listed(&self.allowlist) {
+            NetworkVerdict::Allowed
+        } else {
+            NetworkVerdict::Unlisted
+        }
+    }
+}
+
+fn covers(domain: &str, host: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+/// Lower-case the domains of a list, dropping `*.` prefixes and blank entries. Fails on entries
+/// that aren't domains, like URLs or host:port pairs.
+pub fn normalize_domains(domains: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized = Vec::new();
+    for domain in domains {
+        let trimmed = domain.trim();
+        let domain = trimmed
+            .trim_start_matches("*.")
+            .trim_start_matches('.')
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        if domain.is_empty() {
+            continue;
+        }
+        if domain
+            .chars()
+            .any(|c| !(c.is_ascii_alphanumeric() || c == '-' || c == '.'))
+        {
+            return Err(format!("'{}' is not a domain", trimmed));
+        }
+        if !normalized.contains(&domain) {
+            normalized.push(domain);
+        }
+    }
+    Ok(normalized)
+}
+
+/// A host and port an execution connected to through the logging proxy
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct NetworkContact {
+    pub execution_process_id: Uuid,
+    pub host: String,
+    #[ts(type = "number")]
+    pub port: i64,
+    pub verdict: NetworkVerdict,
+    #[ts(type = "number")]
+    pub connections: i64,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub first_seen_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub last_seen_at: DateTime<Utc>,
+}
+
+/// What an attempt's agents talked to, for the attempt's detail
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct AttemptNetworkReport {
+    /// Whether the project observes its agents' connections
+    pub enabled: bool,
+    /// An agent contacted a denylisted host
+    pub policy_violation: bool,
+    /// First contact first
+    pub contacts: Vec<NetworkContact>,
+}
+
+impl NetworkContact {
+    /// Count a connection, keeping the verdict of the first one
+    pub async fn record(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+        host: &str,
+        port: u16,
+        verdict: NetworkVerdict,
+    ) -> Result<(), sqlx::Error> {
+        let port = i64::from(port);
+        sqlx::query!(
+            r#"INSERT INTO network_contacts (execution_process_id, host, port, verdict)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (execution_process_id, host, port) DO UPDATE
+               SET connections = connections + 1,
+                   last_seen_at = datetime('now', 'subsec')"#,
+            execution_process_id,
+            host,
+            port,
+            verdict
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_task_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            NetworkContact,
+            r#"SELECT nc.execution_process_id AS "execution_process_id!: Uuid",
+                      nc.host,
+                      nc.port,
+                      nc.verdict               AS "verdict!: NetworkVerdict",
+                      nc.connections,
+                      nc.first_seen_at         AS "first_seen_at!: DateTime<Utc>",
+                      nc.last_seen_at          AS "last_seen_at!: DateTime<Utc>"
+               FROM network_contacts nc
+               JOIN execution_processes ep ON ep.id = nc.execution_process_id
+               WHERE ep.task_attempt_id = $1
+               ORDER BY nc.first_seen_at ASC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Whether any of the attempt's executions contacted a denylisted host
+    pub async fn attempt_has_violation(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS (
+                   SELECT 1
+                   FROM network_contacts nc
+                   JOIN execution_processes ep ON ep.id = nc.execution_process_id
+                   WHERE ep.task_attempt_id = $1 AND nc.verdict = 'denied'
+               ) AS "violation!: bool""#,
+            task_attempt_id
+        )
+        .fetch_one(pool)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domains_cover_their_subdomains_and_the_denylist_wins() {
+        let domains = |list: &[&str]| {
+            normalize_domains(&list.iter().map(|d| d.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            domains(&["*.Example.org", " github.com. ", "", "github.com"]).unwrap(),
+            ["example.org", "github.com"]
+        );
+        assert!(domains(&["https://example.com"]).is_err());
+        assert!(domains(&["example.com:443"]).is_err());
+
+        let policy = NetworkPolicy {
+            allowlist: domains(&["github.com"]).unwrap(),
+            denylist: domains(&["gist.github.com", "pastebin.com"]).unwrap(),
+            enabled: true,
+            ..NetworkPolicy::disabled(Uuid::new_v4())
+        };
+        assert_eq!(policy.verdict("api.github.com"), NetworkVerdict::Allowed);
+        assert_eq!(policy.verdict("GitHub.com."), NetworkVerdict::Allowed);
+        assert_eq!(policy.verdict("gist.github.com"), NetworkVerdict::Denied);
+        assert_eq!(policy.verdict("pastebin.com"), NetworkVerdict::Denied);
+        assert_eq!(policy.verdict("notgithub.com"), NetworkVerdict::Unlisted);
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::executor::NormalizedConversation;
+
+/// Which stored normalization of an execution's output to use
+#[derive(Debug, Clone, Copy, PartialEq, Type, Serialize, Deserialize, TS)]
+#[sqlx(type_name = "normalized_log_version", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum SnapshotVersion {
+    /// Pinned normalization shown for the execution
+    Primary,
+    /// Re-normalization with the current parsers, awaiting promotion
+    Shadow,
+}
+
+pub struct NormalizedLogSnapshot;
+
+impl NormalizedLogSnapshot {
+    pub async fn find(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+        version: SnapshotVersion,
+    ) -> Result<Option<NormalizedConversation>, sqlx::Error> {
+        let record = sqlx::query!(
+            r#"SELECT conversation as "conversation!"
+               FROM normalized_log_snapshots
+               WHERE execution_process_id = $1 AND version = $2"#,
+            execution_process_id,
+            version
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        record
+            .map(|r| serde_json::from_str(&r.conversation))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    }
+
+    pub async fn save(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+        version: SnapshotVersion,
+        conversation: &NormalizedConversation,
+    ) -> Result<(), sqlx::Error> {
+        let conversation =
+            serde_json::to_string(conversation).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        sqlx::query!(
+            r#"INSERT INTO normalized_log_snapshots (execution_process_id, version, conversation)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (execution_process_id, version) DO UPDATE
+               SET conversation = excluded.conversation, created_at = datetime('now', 'subsec')"#,
+            execution_process_id,
+            version,
+            conversation
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Replace the primary snapshot with the shadow one. Returns false when there is no
+    /// shadow snapshot to promote.
+    pub async fn promote_shadow(
+        pool: &SqlitePool,
+        execution_process_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let primary = SnapshotVersion::Primary;
+        let shadow = SnapshotVersion::Shadow;
+
+        let mut tx = pool.begin().await?;
+        let has_shadow = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM normalized_log_snapshots
+               WHERE execution_process_id = $1 AND version = $2"#,
+            execution_process_id,
+            shadow
+        )
+        .fetch_one(&mut *tx)
+        .await?
+            > 0;
+        if !has_shadow {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "DELETE FROM normalized_log_snapshots WHERE execution_process_id = $1 AND version = $2",
+            execution_process_id,
+            primary
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"UPDATE normalized_log_snapshots
+               SET version = $3, created_at = datetime('now', 'subsec')
+               WHERE execution_process_id = $1 AND version = $2"#,
+            execution_process_id,
+            shadow,
+            primary
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
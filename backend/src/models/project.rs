@@ -1,22 +1,88 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use git2::{BranchType, Repository};
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool};
+use sqlx::{FromRow, SqlitePool, Type};
 use ts_rs::TS;
 use uuid::Uuid;
 
+use super::executor_session::ExecutorSession;
+use crate::services::repo_config::{self, RepoConfigStatus};
+
+/// Whether a project is a git repository or a plain directory for quick experiments
+#[derive(Debug, Clone, Copy, Default, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "project_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum ProjectType {
+    #[default]
+    Git,
+    /// Attempts run in a copy of the directory, without worktrees, diffs or merges
+    Scratch,
+}
+
+impl ProjectType {
+    pub fn capabilities(self) -> ProjectCapabilities {
+        let git = self == ProjectType::Git;
+        ProjectCapabilities {
+            worktrees: git,
+            branches: git,
+            diff: git,
+            merge: git,
+            pull_requests: git,
+        }
+    }
+}
+
+/// How the changes of a project's format script are committed
+#[derive(Debug, Clone, Copy, Default, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "format_commit_mode", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum FormatCommitMode {
+    /// In a style commit of their own, so they can be reviewed apart from the agent's
+    #[default]
+    Separate,
+    /// Amended into the commit of the agent's changes
+    Fold,
+}
+
+/// What a project supports, so clients can hide what a scratch project can't do
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export)]
+pub struct ProjectCapabilities {
+    pub worktrees: bool,
+    pub branches: bool,
+    pub diff: bool,
+    /// Merging and rebasing attempts
+    pub merge: bool,
+    pub pull_requests: bool,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct Project {
     pub id: Uuid,
     pub name: String,
+    /// The repository, or the plain directory of a scratch project
     pub git_repo_path: String,
     pub setup_script: Option<String>,
     pub dev_script: Option<String>,
+    /// Command that exits non-zero when the project is broken, used to bisect attempts
+    pub test_script: Option<String>,
+    /// Formatters run over the files an attempt changed once its agent is done, one command
+    /// per line
+    pub format_script: Option<String>,
+    pub format_commit_mode: FormatCommitMode,
+    pub response_language: Option<String>,
+    pub project_type: ProjectType,
 
     #[ts(type = "Date")]
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
     #[ts(type = "Date")]
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -28,6 +94,13 @@ pub struct CreateProject {
     pub use_existing_repo: bool,
     pub setup_script: Option<String>,
     pub dev_script: Option<String>,
+    pub test_script: Option<String>,
+    pub format_script: Option<String>,
+    /// Defaults to a separate style commit
+    pub format_commit_mode: Option<FormatCommitMode>,
+    pub response_language: Option<String>,
+    /// Defaults to a git project
+    pub project_type: Option<ProjectType>,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -37,6 +110,46 @@ pub struct UpdateProject {
     pub git_repo_path: Option<String>,
     pub setup_script: Option<String>,
     pub dev_script: Option<String>,
+    pub test_script: Option<String>,
+    pub format_script: Option<String>,
+    /// Left unchanged when not given
+    pub format_commit_mode: Option<FormatCommitMode>,
+    pub response_language: Option<String>,
+}
+
+/// Where the project repository's HEAD points
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HeadState {
+    /// Checked out on a local branch
+    Branch { name: String },
+    /// HEAD points directly at a commit (short id)
+    Detached { commit: String },
+    /// Freshly initialised repository without any commits
+    Unborn,
+}
+
+impl HeadState {
+    pub fn of(repo: &Repository) -> Result<Self, git2::Error> {
+        let head = match repo.head() {
+            Ok(head) => head,
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Ok(HeadState::Unborn),
+            Err(e) => return Err(e),
+        };
+
+        if repo.head_detached()? {
+            let commit = head.peel_to_commit()?;
+            let short_id = commit.as_object().short_id()?;
+            return Ok(HeadState::Detached {
+                commit: short_id.as_str().unwrap_or_default().to_string(),
+            });
+        }
+
+        Ok(HeadState::Branch {
+            name: head.shorthand().unwrap_or("HEAD").to_string(),
+        })
+    }
 }
 
 #[derive(Debug, Serialize, TS)]
@@ -47,20 +160,57 @@ pub struct ProjectWithBranch {
     pub git_repo_path: String,
     pub setup_script: Option<String>,
     pub dev_script: Option<String>,
+    pub test_script: Option<String>,
+    pub format_script: Option<String>,
+    pub format_commit_mode: FormatCommitMode,
+    pub response_language: Option<String>,
+    /// Checked-out branch; `None` when HEAD is detached or the repository has no commits
     pub current_branch: Option<String>,
+    /// `None` when the repository can't be opened
+    pub head_state: Option<HeadState>,
+    pub is_bare: bool,
+    pub project_type: ProjectType,
+    pub capabilities: ProjectCapabilities,
+    /// Settings read from the repository's `.codecommand.toml`, already merged into the scripts
+    pub repo_config: RepoConfigStatus,
 
     #[ts(type = "Date")]
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
     #[ts(type = "Date")]
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct ProjectStats {
+    #[ts(type = "number")]
+    pub task_count: i64,
+    #[ts(type = "number")]
+    pub attempt_count: i64,
+    /// Average duration of finished executor sessions, in seconds
+    pub avg_session_duration_secs: Option<f64>,
+    /// Task updates and deletions based on a stale version, merged or rejected
+    #[ts(type = "number")]
+    pub update_conflicts: i64,
+    /// Of those, the updates merged because they touched other fields
+    #[ts(type = "number")]
+    pub merged_updates: i64,
+}
+
 #[derive(Debug, Serialize, TS)]
 #[ts(export)]
 pub struct SearchResult {
+    /// Repository-relative path for files, title for tasks, name for projects
     pub path: String,
     pub is_file: bool,
     pub match_type: SearchMatchType,
+    pub result_type: SearchResultType,
+    /// Task or project id; `None` for files
+    pub id: Option<Uuid>,
+    /// Relevance between 0 and 1, used to rank blended results
+    pub score: f64,
 }
 
 #[derive(Debug, Serialize, TS)]
@@ -69,6 +219,37 @@ pub enum SearchMatchType {
     FileName,
     DirectoryName,
     FullPath,
+    Title,
+    Description,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub enum SearchResultType {
+    File,
+    Task,
+    Project,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchType {
+    TasksOnly,
+    ProjectsOnly,
+    FilesOnly,
+    #[default]
+    All,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct SearchResultPage {
+    pub results: Vec<SearchResult>,
+    /// Number of matches before the page was truncated
+    #[ts(type = "number")]
+    pub total: u64,
+    pub search_type_applied: SearchType,
 }
 
 #[derive(Debug, Serialize, TS)]
@@ -78,6 +259,7 @@ pub struct GitBranch {
     pub is_current: bool,
     pub is_remote: bool,
     #[ts(type = "Date")]
+    #[serde(with = "crate::utils::rfc3339")]
     pub last_commit_date: DateTime<Utc>,
 }
 
@@ -92,7 +274,7 @@ impl Project {
     pub async fn find_all(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
         sqlx::query_as!(
             Project,
-            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects ORDER BY created_at DESC"#
+            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, test_script, format_script, format_commit_mode as "format_commit_mode!: FormatCommitMode", response_language, project_type as "project_type!: ProjectType", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects ORDER BY created_at DESC"#
         )
         .fetch_all(pool)
         .await
@@ -101,7 +283,7 @@ impl Project {
     pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
         sqlx::query_as!(
             Project,
-            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects WHERE id = $1"#,
+            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, test_script, format_script, format_commit_mode as "format_commit_mode!: FormatCommitMode", response_language, project_type as "project_type!: ProjectType", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects WHERE id = $1"#,
             id
         )
         .fetch_optional(pool)
@@ -114,7 +296,7 @@ impl Project {
     ) -> Result<Option<Self>, sqlx::Error> {
         sqlx::query_as!(
             Project,
-            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects WHERE git_repo_path = $1"#,
+            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, test_script, format_script, format_commit_mode as "format_commit_mode!: FormatCommitMode", response_language, project_type as "project_type!: ProjectType", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects WHERE git_repo_path = $1"#,
             git_repo_path
         )
         .fetch_optional(pool)
@@ -128,7 +310,7 @@ impl Project {
     ) -> Result<Option<Self>, sqlx::Error> {
         sqlx::query_as!(
             Project,
-            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects WHERE git_repo_path = $1 AND id != $2"#,
+            r#"SELECT id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, test_script, format_script, format_commit_mode as "format_commit_mode!: FormatCommitMode", response_language, project_type as "project_type!: ProjectType", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>" FROM projects WHERE git_repo_path = $1 AND id != $2"#,
             git_repo_path,
             exclude_id
         )
@@ -141,19 +323,27 @@ impl Project {
         data: &CreateProject,
         project_id: Uuid,
     ) -> Result<Self, sqlx::Error> {
+        let project_type = data.project_type.unwrap_or_default();
+        let format_commit_mode = data.format_commit_mode.unwrap_or_default();
         sqlx::query_as!(
             Project,
-            r#"INSERT INTO projects (id, name, git_repo_path, setup_script, dev_script) VALUES ($1, $2, $3, $4, $5) RETURNING id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            r#"INSERT INTO projects (id, name, git_repo_path, setup_script, dev_script, test_script, format_script, format_commit_mode, response_language, project_type) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, test_script, format_script, format_commit_mode as "format_commit_mode!: FormatCommitMode", response_language, project_type as "project_type!: ProjectType", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
             project_id,
             data.name,
             data.git_repo_path,
             data.setup_script,
-            data.dev_script
+            data.dev_script,
+            data.test_script,
+            data.format_script,
+            format_commit_mode,
+            data.response_language,
+            project_type
         )
         .fetch_one(pool)
         .await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update(
         pool: &SqlitePool,
         id: Uuid,
@@ -161,15 +351,23 @@ impl Project {
         git_repo_path: String,
         setup_script: Option<String>,
         dev_script: Option<String>,
+        test_script: Option<String>,
+        format_script: Option<String>,
+        format_commit_mode: FormatCommitMode,
+        response_language: Option<String>,
     ) -> Result<Self, sqlx::Error> {
         sqlx::query_as!(
             Project,
-            r#"UPDATE projects SET name = $2, git_repo_path = $3, setup_script = $4, dev_script = $5 WHERE id = $1 RETURNING id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            r#"UPDATE projects SET name = $2, git_repo_path = $3, setup_script = $4, dev_script = $5, test_script = $6, format_script = $7, format_commit_mode = $8, response_language = $9 WHERE id = $1 RETURNING id as "id!: Uuid", name, git_repo_path, setup_script, dev_script, test_script, format_script, format_commit_mode as "format_commit_mode!: FormatCommitMode", response_language, project_type as "project_type!: ProjectType", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
             id,
             name,
             git_repo_path,
             setup_script,
-            dev_script
+            dev_script,
+            test_script,
+            format_script,
+            format_commit_mode,
+            response_language
         )
         .fetch_one(pool)
         .await
@@ -197,19 +395,114 @@ impl Project {
         Ok(result.count > 0)
     }
 
+    pub async fn stats(pool: &SqlitePool, id: Uuid) -> Result<ProjectStats, sqlx::Error> {
+        let counts = sqlx::query!(
+            r#"
+                SELECT
+                    (SELECT COUNT(*) FROM tasks WHERE project_id = $1) as "task_count!: i64",
+                    (SELECT COUNT(*)
+                     FROM task_attempts ta
+                     JOIN tasks t ON t.id = ta.task_id
+                     WHERE t.project_id = $1 AND ta.benchmark_run_id IS NULL) as "attempt_count!: i64",
+                    (SELECT COUNT(*) FROM task_update_conflicts WHERE project_id = $1) as "update_conflicts!: i64",
+                    (SELECT COUNT(*) FROM task_update_conflicts
+                     WHERE project_id = $1 AND merged = TRUE) as "merged_updates!: i64"
+            "#,
+            id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        let avg_session_duration_secs =
+            ExecutorSession::average_duration_secs_for_project(pool, id).await?;
+
+        Ok(ProjectStats {
+            task_count: counts.task_count,
+            attempt_count: counts.attempt_count,
+            avg_session_duration_secs,
+            update_conflicts: counts.update_conflicts,
+            merged_updates: counts.merged_updates,
+        })
+    }
+
+    /// Per project, the number of `todo` tasks without an attempt that is currently running.
+    /// Projects with no such tasks are absent.
+    pub async fn pending_task_counts(pool: &SqlitePool) -> Result<HashMap<Uuid, u32>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"
+                SELECT t.project_id AS "project_id!: Uuid",
+                       COUNT(*)     AS "count!: i64"
+                FROM tasks t
+                LEFT JOIN (
+                    SELECT DISTINCT ta.task_id
+                    FROM task_attempts ta
+                    JOIN execution_processes ep ON ep.task_attempt_id = ta.id
+                    WHERE ep.status = 'running'
+                ) running ON running.task_id = t.id
+                WHERE t.status = 'todo' AND running.task_id IS NULL
+                GROUP BY t.project_id
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| (record.project_id, record.count as u32))
+            .collect())
+    }
+
+    /// Type of the project an attempt belongs to
+    pub async fn find_type_by_attempt_id(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<Option<ProjectType>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT p.project_type AS "project_type!: ProjectType"
+               FROM task_attempts ta
+               JOIN tasks t ON t.id = ta.task_id
+               JOIN projects p ON p.id = t.project_id
+               WHERE ta.id = $1"#,
+            attempt_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub fn is_scratch(&self) -> bool {
+        self.project_type == ProjectType::Scratch
+    }
+
+    /// Message for a git feature asked of a scratch project, e.g. "Merging"
+    pub fn git_unavailable_message(feature: &str) -> String {
+        format!(
+            "{} isn't available for scratch projects, which have no git repository",
+            feature
+        )
+    }
+
     pub fn get_current_branch(&self) -> Result<String, git2::Error> {
         let repo = Repository::open(&self.git_repo_path)?;
-        let head = repo.head()?;
 
-        if let Some(branch_name) = head.shorthand() {
-            Ok(branch_name.to_string())
-        } else {
-            Ok("HEAD".to_string())
+        match HeadState::of(&repo)? {
+            HeadState::Branch { name } => Ok(name),
+            HeadState::Detached { commit } => Err(git2::Error::from_str(&format!(
+                "HEAD is detached at {}",
+                commit
+            ))),
+            HeadState::Unborn => Err(git2::Error::from_str("Repository has no commits yet")),
         }
     }
 
-    pub fn with_branch_info(self) -> ProjectWithBranch {
-        let current_branch = self.get_current_branch().ok();
+    pub fn with_branch_info(mut self) -> ProjectWithBranch {
+        let repo_config = repo_config::merge(&mut self);
+        let repo = Repository::open(&self.git_repo_path).ok();
+        let head_state = repo.as_ref().and_then(|repo| HeadState::of(repo).ok());
+        let is_bare = repo.as_ref().is_some_and(Repository::is_bare);
+        let current_branch = match &head_state {
+            Some(HeadState::Branch { name }) => Some(name.clone()),
+            _ => None,
+        };
 
         ProjectWithBranch {
             id: self.id,
@@ -217,7 +510,16 @@ impl Project {
             git_repo_path: self.git_repo_path,
             setup_script: self.setup_script,
             dev_script: self.dev_script,
+            test_script: self.test_script,
+            format_script: self.format_script,
+            format_commit_mode: self.format_commit_mode,
+            response_language: self.response_language,
             current_branch,
+            head_state,
+            is_bare,
+            project_type: self.project_type,
+            capabilities: self.project_type.capabilities(),
+            repo_config,
             created_at: self.created_at,
             updated_at: self.updated_at,
         }
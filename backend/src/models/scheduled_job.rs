@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+/// Persisted state of a background job
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub enabled: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<i64>,
+    pub last_error: Option<String>,
+    pub last_succeeded: Option<bool>,
+    pub run_count: i64,
+}
+
+impl ScheduledJob {
+    pub async fn find(pool: &SqlitePool, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            ScheduledJob,
+            r#"SELECT name              AS "name!",
+                      enabled           AS "enabled!: bool",
+                      next_run_at       AS "next_run_at: DateTime<Utc>",
+                      last_started_at   AS "last_started_at: DateTime<Utc>",
+                      last_finished_at  AS "last_finished_at: DateTime<Utc>",
+                      last_duration_ms,
+                      last_error,
+                      last_succeeded    AS "last_succeeded: bool",
+                      run_count         AS "run_count!: i64"
+               FROM scheduled_jobs
+               WHERE name = $1"#,
+            name
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Create the job's row on first registration; existing state is kept
+    pub async fn ensure(
+        pool: &SqlitePool,
+        name: &str,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO scheduled_jobs (name, next_run_at) VALUES ($1, $2) ON CONFLICT (name) DO NOTHING",
+            name,
+            next_run_at
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_next_run(
+        pool: &SqlitePool,
+        name: &str,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE scheduled_jobs SET next_run_at = $2, updated_at = datetime('now', 'subsec') WHERE name = $1",
+            name,
+            next_run_at
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_enabled(
+        pool: &SqlitePool,
+        name: &str,
+        enabled: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE scheduled_jobs SET enabled = $2, updated_at = datetime('now', 'subsec') WHERE name = $1",
+            name,
+            enabled
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record the outcome of a run; `error` is `None` when it succeeded
+    pub async fn record_run(
+        pool: &SqlitePool,
+        name: &str,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let duration_ms = (finished_at - started_at).num_milliseconds();
+        let succeeded = error.is_none();
+        sqlx::query!(
+            r#"UPDATE scheduled_jobs
+               SET last_started_at = $2,
+                   last_finished_at = $3,
+                   last_duration_ms = $4,
+                   last_error = $5,
+                   last_succeeded = $6,
+                   run_count = run_count + 1,
+                   updated_at = datetime('now', 'subsec')
+               WHERE name = $1"#,
+            name,
+            started_at,
+            finished_at,
+            duration_ms,
+            error,
+            succeeded
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
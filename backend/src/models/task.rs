@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool, Type};
 use ts_rs::TS;
 use uuid::Uuid;
 
+use super::task_dependency::TaskDependency;
+
 #[derive(Debug, Clone, Type, Serialize, Deserialize, PartialEq, TS)]
 #[sqlx(type_name = "task_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
@@ -24,8 +28,15 @@ pub struct Task {
     pub title: String,
     pub description: Option<String>,
     pub status: TaskStatus,
+    pub estimate: Option<f64>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
+    /// Bumped by every change. Updates pass the version they were based on to detect
+    /// concurrent changes.
+    #[ts(type = "number")]
+    pub version: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -36,11 +47,18 @@ pub struct TaskWithAttemptStatus {
     pub title: String,
     pub description: Option<String>,
     pub status: TaskStatus,
+    pub estimate: Option<f64>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
+    #[ts(type = "number")]
+    pub version: i64,
     pub has_in_progress_attempt: bool,
     pub has_merged_attempt: bool,
     pub has_failed_attempt: bool,
+    /// Tasks this one depends on that aren't done yet; attempts can't start until they are
+    pub blocked_by: Vec<Uuid>,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -49,6 +67,7 @@ pub struct CreateTask {
     pub project_id: Uuid,
     pub title: String,
     pub description: Option<String>,
+    pub estimate: Option<f64>,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -57,6 +76,7 @@ pub struct CreateTaskAndStart {
     pub project_id: Uuid,
     pub title: String,
     pub description: Option<String>,
+    pub estimate: Option<f64>,
     pub executor: Option<crate::executor::ExecutorConfig>,
 }
 
@@ -66,6 +86,151 @@ pub struct UpdateTask {
     pub title: Option<String>,
     pub description: Option<String>,
     pub status: Option<TaskStatus>,
+    pub estimate: Option<f64>,
+    /// Version of the task the update is based on. When the task changed since, the update
+    /// is merged if it only touches fields that didn't change, and rejected otherwise.
+    /// Updates without one overwrite whatever changed.
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub expected_version: Option<i64>,
+}
+
+impl UpdateTask {
+    /// Fields the update sets to something other than their value in `task`
+    pub fn changed_fields(&self, task: &Task) -> Vec<TaskField> {
+        let mut fields = Vec::new();
+        if self
+            .title
+            .as_ref()
+            .is_some_and(|title| *title != task.title)
+        {
+            fields.push(TaskField::Title);
+        }
+        if self.description.is_some() && self.description != task.description {
+            fields.push(TaskField::Description);
+        }
+        if self
+            .status
+            .as_ref()
+            .is_some_and(|status| *status != task.status)
+        {
+            fields.push(TaskField::Status);
+        }
+        if self.estimate.is_some() && self.estimate != task.estimate {
+            fields.push(TaskField::Estimate);
+        }
+        fields
+    }
+}
+
+/// Fields of a task that updates can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskField {
+    Title,
+    Description,
+    Status,
+    Estimate,
+}
+
+/// An update based on an outdated version of a task that changes fields which changed since.
+/// Nothing was written; merge into `current` and retry with its version.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct TaskConflict {
+    pub current: Task,
+    /// Fields both the update and someone else changed
+    pub conflicting_fields: Vec<TaskField>,
+}
+
+#[derive(Debug)]
+pub enum TaskUpdateOutcome {
+    /// `merged` when the update was based on an outdated version and merged with the changes
+    /// made since
+    Updated {
+        task: Task,
+        merged: bool,
+    },
+    Conflict(TaskConflict),
+}
+
+/// Where a task update came from, for counting conflicts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskUpdateSource {
+    Api,
+    Mcp,
+}
+
+impl TaskUpdateSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskUpdateSource::Api => "api",
+            TaskUpdateSource::Mcp => "mcp",
+        }
+    }
+}
+
+/// Tries to write an update before giving up on a task that keeps changing under it
+const MAX_UPDATE_TRIES: usize = 5;
+
+/// Where a task stands, without its contents
+#[derive(Debug, Clone)]
+pub struct TaskVersion {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub status: TaskStatus,
+    pub version: i64,
+}
+
+/// Estimated versus actual effort for a completed task
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TaskEstimateReport {
+    pub task_id: Uuid,
+    pub title: String,
+    pub estimate: Option<f64>,
+    /// Time the attempts spent running processes, in minutes
+    pub actual_minutes: f64,
+    pub attempt_count: usize,
+}
+
+/// Estimates must be finite and non-negative
+pub fn is_valid_estimate(estimate: Option<f64>) -> bool {
+    match estimate {
+        Some(e) => e.is_finite() && e >= 0.0,
+        None => true,
+    }
+}
+
+/// Sum the time each attempt spent running processes. Overlapping processes of an attempt count
+/// once and the idle time between them not at all; processes still running contribute nothing.
+pub fn actual_minutes_from_processes(
+    processes: &[(Uuid, DateTime<Utc>, Option<DateTime<Utc>>)],
+) -> (f64, usize) {
+    let mut intervals: HashMap<Uuid, Vec<(DateTime<Utc>, DateTime<Utc>)>> = HashMap::new();
+    for (attempt_id, started_at, completed_at) in processes {
+        let attempt = intervals.entry(*attempt_id).or_default();
+        if let Some(completed_at) = completed_at {
+            attempt.push((*started_at, *completed_at));
+        }
+    }
+
+    let mut total_seconds = 0;
+    for attempt in intervals.values_mut() {
+        attempt.sort();
+        // Only the part of each process after what earlier ones already covered is added
+        let mut covered_until: Option<DateTime<Utc>> = None;
+        for &(start, end) in attempt.iter() {
+            let start = covered_until.map_or(start, |until| start.max(until));
+            if end > start {
+                total_seconds += (end - start).num_seconds();
+                covered_until = Some(end);
+            }
+        }
+    }
+
+    (total_seconds as f64 / 60.0, intervals.len())
 }
 
 impl Task {
@@ -80,8 +245,10 @@ impl Task {
                 t.title, 
                 t.description, 
                 t.status              AS "status!: TaskStatus", 
+                t.estimate,
                 t.created_at          AS "created_at!: DateTime<Utc>", 
                 t.updated_at          AS "updated_at!: DateTime<Utc>",
+                t.version             AS "version!: i64",
                 CASE 
                 WHEN in_progress_attempts.task_id IS NOT NULL THEN true 
                 ELSE false 
@@ -99,7 +266,7 @@ impl Task {
                 SELECT DISTINCT ta.task_id
                 FROM task_attempts ta
                 JOIN execution_processes ep 
-                ON ta.id = ep.task_attempt_id
+                ON ta.id = ep.task_attempt_id AND ta.benchmark_run_id IS NULL
                 JOIN (
                     -- pick exactly one “latest” activity per process,
                     -- tiebreaking so that running‐states are lower priority
@@ -128,7 +295,7 @@ impl Task {
             LEFT JOIN (
                 SELECT DISTINCT ta.task_id
                 FROM task_attempts ta
-                WHERE ta.merge_commit IS NOT NULL
+                WHERE ta.merge_commit IS NOT NULL AND ta.benchmark_run_id IS NULL
             ) merged_attempts 
             ON t.id = merged_attempts.task_id
             LEFT JOIN (
@@ -139,6 +306,7 @@ impl Task {
                            ROW_NUMBER() OVER (PARTITION BY task_id ORDER BY created_at DESC) AS rn
                     FROM task_attempts
                     WHERE merge_commit IS NULL  -- Don't show as failed if already merged
+                      AND benchmark_run_id IS NULL
                 ) latest_attempts
                 JOIN execution_processes ep 
                 ON latest_attempts.attempt_id = ep.task_attempt_id
@@ -175,6 +343,7 @@ impl Task {
         )
         .fetch_all(pool)
         .await?;
+        let mut blocked_by = TaskDependency::find_blocked_by_for_project(pool, project_id).await?;
 
         let tasks = records
             .into_iter()
@@ -184,11 +353,14 @@ impl Task {
                 title: record.title,
                 description: record.description,
                 status: record.status,
+                estimate: record.estimate,
                 created_at: record.created_at,
                 updated_at: record.updated_at,
+                version: record.version,
                 has_in_progress_attempt: record.has_in_progress_attempt != 0,
                 has_merged_attempt: record.has_merged_attempt != 0,
                 has_failed_attempt: record.has_failed_attempt != 0,
+                blocked_by: blocked_by.remove(&record.id).unwrap_or_default(),
             })
             .collect();
 
@@ -198,7 +370,7 @@ impl Task {
     pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
         sqlx::query_as!(
             Task,
-            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", estimate, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>", version as "version!: i64"
                FROM tasks 
                WHERE id = $1"#,
             id
@@ -207,6 +379,33 @@ impl Task {
         .await
     }
 
+    pub async fn find_by_project_id(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Task,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", estimate, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>", version as "version!: i64"
+               FROM tasks 
+               WHERE project_id = $1
+               ORDER BY created_at DESC"#,
+            project_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Status and version of every task, in every project
+    pub async fn find_all_versions(pool: &SqlitePool) -> Result<Vec<TaskVersion>, sqlx::Error> {
+        sqlx::query_as!(
+            TaskVersion,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", status as "status!: TaskStatus", version as "version!: i64"
+               FROM tasks"#
+        )
+        .fetch_all(pool)
+        .await
+    }
+
     pub async fn find_by_id_and_project_id(
         pool: &SqlitePool,
         id: Uuid,
@@ -214,7 +413,7 @@ impl Task {
     ) -> Result<Option<Self>, sqlx::Error> {
         sqlx::query_as!(
             Task,
-            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", estimate, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>", version as "version!: i64"
                FROM tasks 
                WHERE id = $1 AND project_id = $2"#,
             id,
@@ -231,14 +430,15 @@ impl Task {
     ) -> Result<Self, sqlx::Error> {
         sqlx::query_as!(
             Task,
-            r#"INSERT INTO tasks (id, project_id, title, description, status) 
-               VALUES ($1, $2, $3, $4, $5) 
-               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            r#"INSERT INTO tasks (id, project_id, title, description, status, estimate) 
+               VALUES ($1, $2, $3, $4, $5, $6) 
+               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", estimate, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>", version as "version!: i64""#,
             task_id,
             data.project_id,
             data.title,
             data.description,
-            TaskStatus::Todo as TaskStatus
+            TaskStatus::Todo as TaskStatus,
+            data.estimate
         )
         .fetch_one(pool)
         .await
@@ -251,24 +451,217 @@ impl Task {
         title: String,
         description: Option<String>,
         status: TaskStatus,
+        estimate: Option<f64>,
     ) -> Result<Self, sqlx::Error> {
         let status_value = status as TaskStatus;
         sqlx::query_as!(
             Task,
             r#"UPDATE tasks 
-               SET title = $3, description = $4, status = $5 
+               SET title = $3, description = $4, status = $5, estimate = $6,
+                   title_version = CASE WHEN title IS NOT $3 THEN version + 1 ELSE title_version END,
+                   description_version = CASE WHEN description IS NOT $4 THEN version + 1 ELSE description_version END,
+                   status_version = CASE WHEN status IS NOT $5 THEN version + 1 ELSE status_version END,
+                   estimate_version = CASE WHEN estimate IS NOT $6 THEN version + 1 ELSE estimate_version END,
+                   version = version + 1
                WHERE id = $1 AND project_id = $2 
-               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", estimate, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>", version as "version!: i64""#,
             id,
             project_id,
             title,
             description,
-            status_value
+            status_value,
+            estimate
         )
         .fetch_one(pool)
         .await
     }
 
+    /// Apply `update` with optimistic concurrency, see `UpdateTask::expected_version`. Merged
+    /// and rejected updates are recorded as conflicts. `None` when the task doesn't exist.
+    pub async fn update_checked(
+        pool: &SqlitePool,
+        id: Uuid,
+        project_id: Uuid,
+        update: &UpdateTask,
+        source: TaskUpdateSource,
+    ) -> Result<Option<TaskUpdateOutcome>, sqlx::Error> {
+        let mut current = None;
+        for _ in 0..MAX_UPDATE_TRIES {
+            let Some(task) = Self::find_by_id_and_project_id(pool, id, project_id).await? else {
+                return Ok(None);
+            };
+            let changed = update.changed_fields(&task);
+
+            let outdated = update
+                .expected_version
+                .is_some_and(|expected| expected != task.version);
+            if outdated {
+                let expected = update.expected_version.unwrap_or_default();
+                let changed_since = Self::fields_changed_since(pool, id, expected).await?;
+                let conflicting_fields: Vec<_> = changed
+                    .iter()
+                    .copied()
+                    .filter(|field| changed_since.contains(field))
+                    .collect();
+                if !conflicting_fields.is_empty() {
+                    Self::record_update_conflict(pool, &task, source, false).await?;
+                    return Ok(Some(TaskUpdateOutcome::Conflict(TaskConflict {
+                        current: task,
+                        conflicting_fields,
+                    })));
+                }
+            }
+            if changed.is_empty() {
+                return Ok(Some(TaskUpdateOutcome::Updated {
+                    task,
+                    merged: false,
+                }));
+            }
+
+            // Misses when the task changed since it was read, then the update is checked again
+            if let Some(updated) = Self::update_at_version(
+                pool,
+                &task,
+                update.title.clone().unwrap_or_else(|| task.title.clone()),
+                update
+                    .description
+                    .clone()
+                    .or_else(|| task.description.clone()),
+                update.status.clone().unwrap_or_else(|| task.status.clone()),
+                update.estimate.or(task.estimate),
+            )
+            .await?
+            {
+                if outdated {
+                    Self::record_update_conflict(pool, &task, source, true).await?;
+                }
+                return Ok(Some(TaskUpdateOutcome::Updated {
+                    task: updated,
+                    merged: outdated,
+                }));
+            }
+            current = Some(task);
+        }
+
+        // Changed under every try
+        let Some(task) = current else {
+            return Ok(None);
+        };
+        Self::record_update_conflict(pool, &task, source, false).await?;
+        Ok(Some(TaskUpdateOutcome::Conflict(TaskConflict {
+            conflicting_fields: update.changed_fields(&task),
+            current: task,
+        })))
+    }
+
+    /// Write the fields if `task` is still at its version, bumping the version of each field
+    /// that changes
+    async fn update_at_version(
+        pool: &SqlitePool,
+        task: &Task,
+        title: String,
+        description: Option<String>,
+        status: TaskStatus,
+        estimate: Option<f64>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let status_value = status as TaskStatus;
+        sqlx::query_as!(
+            Task,
+            r#"UPDATE tasks
+               SET title = $4, description = $5, status = $6, estimate = $7,
+                   title_version = CASE WHEN title IS NOT $4 THEN version + 1 ELSE title_version END,
+                   description_version = CASE WHEN description IS NOT $5 THEN version + 1 ELSE description_version END,
+                   status_version = CASE WHEN status IS NOT $6 THEN version + 1 ELSE status_version END,
+                   estimate_version = CASE WHEN estimate IS NOT $7 THEN version + 1 ELSE estimate_version END,
+                   version = version + 1,
+                   updated_at = CURRENT_TIMESTAMP
+               WHERE id = $1 AND project_id = $2 AND version = $3
+               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", estimate, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>", version as "version!: i64""#,
+            task.id,
+            task.project_id,
+            task.version,
+            title,
+            description,
+            status_value,
+            estimate
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// The conflict of a change to `task`, like deleting it, based on `expected_version` when
+    /// the task changed since. Conflicts are recorded.
+    pub async fn version_conflict(
+        pool: &SqlitePool,
+        task: &Task,
+        expected_version: Option<i64>,
+        source: TaskUpdateSource,
+    ) -> Result<Option<TaskConflict>, sqlx::Error> {
+        let Some(expected) = expected_version.filter(|expected| *expected != task.version) else {
+            return Ok(None);
+        };
+        Self::record_update_conflict(pool, task, source, false).await?;
+        Ok(Some(TaskConflict {
+            current: task.clone(),
+            conflicting_fields: Self::fields_changed_since(pool, task.id, expected).await?,
+        }))
+    }
+
+    /// Fields of the task that changed after `version`
+    pub async fn fields_changed_since(
+        pool: &SqlitePool,
+        id: Uuid,
+        version: i64,
+    ) -> Result<Vec<TaskField>, sqlx::Error> {
+        let record = sqlx::query!(
+            r#"SELECT title_version as "title_version!: i64",
+                      description_version as "description_version!: i64",
+                      status_version as "status_version!: i64",
+                      estimate_version as "estimate_version!: i64"
+               FROM tasks
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(record
+            .map(|r| {
+                [
+                    (TaskField::Title, r.title_version),
+                    (TaskField::Description, r.description_version),
+                    (TaskField::Status, r.status_version),
+                    (TaskField::Estimate, r.estimate_version),
+                ]
+                .into_iter()
+                .filter(|(_, changed_at)| *changed_at > version)
+                .map(|(field, _)| field)
+                .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn record_update_conflict(
+        pool: &SqlitePool,
+        task: &Task,
+        source: TaskUpdateSource,
+        merged: bool,
+    ) -> Result<(), sqlx::Error> {
+        let id = Uuid::new_v4();
+        let source = source.as_str();
+        sqlx::query!(
+            "INSERT INTO task_update_conflicts (id, project_id, task_id, source, merged) VALUES ($1, $2, $3, $4, $5)",
+            id,
+            task.project_id,
+            task.id,
+            source,
+            merged
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn update_status(
         pool: &SqlitePool,
         id: Uuid,
@@ -277,7 +670,12 @@ impl Task {
     ) -> Result<(), sqlx::Error> {
         let status_value = status as TaskStatus;
         sqlx::query!(
-            "UPDATE tasks SET status = $3, updated_at = CURRENT_TIMESTAMP WHERE id = $1 AND project_id = $2",
+            r#"UPDATE tasks
+               SET status = $3,
+                   status_version = CASE WHEN status IS NOT $3 THEN version + 1 ELSE status_version END,
+                   version = CASE WHEN status IS NOT $3 THEN version + 1 ELSE version END,
+                   updated_at = CURRENT_TIMESTAMP
+               WHERE id = $1 AND project_id = $2"#,
             id,
             project_id,
             status_value
@@ -287,6 +685,85 @@ impl Task {
         Ok(())
     }
 
+    /// Estimate versus actual time for every completed task in a project
+    pub async fn find_estimate_reports(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<TaskEstimateReport>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT t.id              AS "id!: Uuid",
+                      t.title,
+                      t.estimate,
+                      ep.task_attempt_id AS "task_attempt_id?: Uuid",
+                      ep.started_at      AS "started_at?: DateTime<Utc>",
+                      ep.completed_at    AS "completed_at?: DateTime<Utc>"
+               FROM tasks t
+               LEFT JOIN task_attempts ta ON ta.task_id = t.id AND ta.benchmark_run_id IS NULL
+               LEFT JOIN execution_processes ep
+                      ON ep.task_attempt_id = ta.id AND ep.process_type != 'devserver'
+               WHERE t.project_id = $1 AND t.status = 'done'
+               ORDER BY t.created_at DESC, t.id"#,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut reports: Vec<(TaskEstimateReport, Vec<_>)> = Vec::new();
+        for record in records {
+            if reports.last().map(|(r, _)| r.task_id) != Some(record.id) {
+                reports.push((
+                    TaskEstimateReport {
+                        task_id: record.id,
+                        title: record.title,
+                        estimate: record.estimate,
+                        actual_minutes: 0.0,
+                        attempt_count: 0,
+                    },
+                    Vec::new(),
+                ));
+            }
+            if let (Some(attempt_id), Some(started_at)) =
+                (record.task_attempt_id, record.started_at)
+            {
+                if let Some((_, processes)) = reports.last_mut() {
+                    processes.push((attempt_id, started_at, record.completed_at));
+                }
+            }
+        }
+
+        Ok(reports
+            .into_iter()
+            .map(|(mut report, processes)| {
+                let (actual_minutes, attempt_count) = actual_minutes_from_processes(&processes);
+                report.actual_minutes = actual_minutes;
+                report.attempt_count = attempt_count;
+                report
+            })
+            .collect())
+    }
+
+    /// Executor the task's attempts start with when they don't name one
+    pub async fn preferred_executor(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let executor = sqlx::query_scalar!("SELECT executor FROM tasks WHERE id = $1", id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(executor.flatten())
+    }
+
+    pub async fn set_preferred_executor(
+        pool: &SqlitePool,
+        id: Uuid,
+        executor: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!("UPDATE tasks SET executor = $2 WHERE id = $1", id, executor)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn delete(pool: &SqlitePool, id: Uuid, project_id: Uuid) -> Result<u64, sqlx::Error> {
         let result = sqlx::query!(
             "DELETE FROM tasks WHERE id = $1 AND project_id = $2",
@@ -313,3 +790,209 @@ impl Task {
         Ok(result.is_some())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_project, memory_pool};
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 7, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_actual_minutes_from_attempt_durations() {
+        let first_attempt = Uuid::new_v4();
+        let second_attempt = Uuid::new_v4();
+        let running_attempt = Uuid::new_v4();
+
+        let processes = vec![
+            // Setup script followed by the coding agent: 10:00 - 10:35
+            (first_attempt, at(10, 5), Some(at(10, 35))),
+            (first_attempt, at(10, 0), Some(at(10, 5))),
+            (second_attempt, at(11, 0), Some(at(11, 10))),
+            (running_attempt, at(12, 0), None),
+        ];
+
+        let (actual_minutes, attempt_count) = actual_minutes_from_processes(&processes);
+        assert_eq!(actual_minutes, 45.0);
+        assert_eq!(attempt_count, 3);
+    }
+
+    #[test]
+    fn test_actual_minutes_leave_out_gaps_between_processes() {
+        let attempt_id = Uuid::new_v4();
+
+        let processes = vec![
+            (attempt_id, at(10, 0), Some(at(10, 10))),
+            // Overlaps the first process, so only 10:10 - 10:15 is added
+            (attempt_id, at(10, 5), Some(at(10, 15))),
+            // A follow-up after the attempt sat idle for 25 minutes
+            (attempt_id, at(10, 40), Some(at(10, 50))),
+            // Runs entirely within the follow-up
+            (attempt_id, at(10, 45), Some(at(10, 48))),
+        ];
+
+        let (actual_minutes, attempt_count) = actual_minutes_from_processes(&processes);
+        assert_eq!(actual_minutes, 25.0);
+        assert_eq!(attempt_count, 1);
+    }
+
+    #[test]
+    fn test_is_valid_estimate() {
+        assert!(is_valid_estimate(None));
+        assert!(is_valid_estimate(Some(0.0)));
+        assert!(is_valid_estimate(Some(2.5)));
+        assert!(!is_valid_estimate(Some(-1.0)));
+        assert!(!is_valid_estimate(Some(f64::NAN)));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_is_stored_and_updated() {
+        let pool = memory_pool().await;
+
+        let project_id = insert_project(&pool, "/tmp/p").await;
+
+        let task_id = Uuid::new_v4();
+        let task = Task::create(
+            &pool,
+            &CreateTask {
+                project_id,
+                title: "Estimated task".to_string(),
+                description: None,
+                estimate: Some(3.5),
+            },
+            task_id,
+        )
+        .await
+        .unwrap();
+        assert_eq!(task.estimate, Some(3.5));
+
+        let task = Task::update(
+            &pool,
+            task_id,
+            project_id,
+            task.title,
+            task.description,
+            TaskStatus::Done,
+            Some(5.0),
+        )
+        .await
+        .unwrap();
+        assert_eq!(task.estimate, Some(5.0));
+
+        let reports = Task::find_estimate_reports(&pool, project_id)
+            .await
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].task_id, task_id);
+        assert_eq!(reports[0].estimate, Some(5.0));
+        assert_eq!(reports[0].actual_minutes, 0.0);
+        assert_eq!(reports[0].attempt_count, 0);
+
+        assert_eq!(
+            Task::preferred_executor(&pool, task_id).await.unwrap(),
+            None
+        );
+        Task::set_preferred_executor(&pool, task_id, Some("gemini"))
+            .await
+            .unwrap();
+        assert_eq!(
+            Task::preferred_executor(&pool, task_id)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("gemini")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_updates_merge_disjoint_fields_and_reject_overlaps() {
+        let pool = memory_pool().await;
+
+        let project_id = insert_project(&pool, "/tmp/p").await;
+        let task_id = Uuid::new_v4();
+        let task = Task::create(
+            &pool,
+            &CreateTask {
+                project_id,
+                title: "Shared ticket".to_string(),
+                description: Some("v1".to_string()),
+                estimate: None,
+            },
+            task_id,
+        )
+        .await
+        .unwrap();
+        let update = |description: Option<&str>, status: Option<TaskStatus>| UpdateTask {
+            title: None,
+            description: description.map(str::to_string),
+            status,
+            estimate: None,
+            expected_version: Some(task.version),
+        };
+
+        // Both agents read the same version; one moves the status, the other edits the text
+        let first = Task::update_checked(
+            &pool,
+            task_id,
+            project_id,
+            &update(None, Some(TaskStatus::InReview)),
+            TaskUpdateSource::Mcp,
+        )
+        .await
+        .unwrap();
+        let Some(TaskUpdateOutcome::Updated { merged: false, .. }) = first else {
+            panic!("expected a plain update, got {:?}", first);
+        };
+        let second = Task::update_checked(
+            &pool,
+            task_id,
+            project_id,
+            &update(Some("v2 from agent B"), None),
+            TaskUpdateSource::Mcp,
+        )
+        .await
+        .unwrap();
+        let Some(TaskUpdateOutcome::Updated {
+            task: merged,
+            merged: true,
+        }) = second
+        else {
+            panic!("expected a merged update, got {:?}", second);
+        };
+        assert_eq!(merged.status, TaskStatus::InReview);
+        assert_eq!(merged.description.as_deref(), Some("v2 from agent B"));
+        assert_eq!(merged.version, task.version + 2);
+
+        // A third agent still on the first version edits the description too
+        let third = Task::update_checked(
+            &pool,
+            task_id,
+            project_id,
+            &update(Some("v2 from agent C"), None),
+            TaskUpdateSource::Api,
+        )
+        .await
+        .unwrap();
+        let Some(TaskUpdateOutcome::Conflict(conflict)) = third else {
+            panic!("expected a conflict, got {:?}", third);
+        };
+        assert_eq!(conflict.conflicting_fields, vec![TaskField::Description]);
+        assert_eq!(
+            conflict.current.description.as_deref(),
+            Some("v2 from agent B")
+        );
+        assert_eq!(conflict.current.version, merged.version);
+
+        let counts: (i64, i64) = sqlx::query_as(
+            "SELECT SUM(merged), SUM(NOT merged) FROM task_update_conflicts WHERE task_id = $1",
+        )
+        .bind(task_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(counts, (1, 1));
+    }
+}
@@ -1,22 +1,45 @@
-use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{DateTime, Utc};
 use git2::{BranchType, Error as GitError, Repository};
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool, Type};
+use sqlx::{types::Json, FromRow, SqlitePool, Type};
 use tracing::info;
 use ts_rs::TS;
 use uuid::Uuid;
 
-use super::{project::Project, task::Task};
-use crate::services::{
-    CreatePrRequest, GitHubRepoInfo, GitHubService, GitHubServiceError, GitService,
-    GitServiceError, ProcessService,
+use super::{
+    annotation::Annotation,
+    benchmark::BenchmarkBase,
+    config::ProvenanceMode,
+    execution_process::ExecutionProcess,
+    format_run::{FormatRun, FormattingDiff},
+    network_policy::NetworkContact,
+    project::Project,
+    task::Task,
+    untracked_change_journal::UntrackedChangeJournal,
+    webhook::WebhookEventType,
+};
+use crate::{
+    services::{
+        glossary, provenance::Provenance, webhooks, CreatePrRequest, GitHubRepoInfo, GitHubService,
+        GitHubServiceError, GitService, GitServiceError, ProcessService, DEFAULT_RENAME_THRESHOLD,
+    },
+    utils::{
+        executable::ExecutableLookup, path_ignore::PathIgnore, secrets,
+        worktree_manager::WorktreeManager,
+    },
 };
 
 // Constants for git diff operations
 const GIT_DIFF_CONTEXT_LINES: u32 = 3;
 const GIT_DIFF_INTERHUNK_LINES: u32 = 0;
+/// Cap on the diff summary prepended to follow-up prompts
+pub const FOLLOWUP_DIFF_SUMMARY_MAX_CHARS: usize = 8_000;
 
 #[derive(Debug)]
 pub enum TaskAttemptError {
@@ -28,6 +51,12 @@ pub enum TaskAttemptError {
     ProjectNotFound,
     ValidationError(String),
     BranchNotFound(String),
+    /// The attempt's worktree directory was removed outside of codecommand
+    WorktreeMissing(String),
+    /// The executor's CLI can't be launched on this machine
+    ExecutorUnavailable(String),
+    /// A git feature was used on a scratch project, which has no repository
+    GitUnavailable(&'static str),
 }
 
 impl std::fmt::Display for TaskAttemptError {
@@ -41,6 +70,13 @@ impl std::fmt::Display for TaskAttemptError {
             TaskAttemptError::ProjectNotFound => write!(f, "Project not found"),
             TaskAttemptError::ValidationError(e) => write!(f, "Validation error: {}", e),
             TaskAttemptError::BranchNotFound(branch) => write!(f, "Branch '{}' not found", branch),
+            TaskAttemptError::WorktreeMissing(path) => {
+                write!(f, "Worktree directory '{}' is missing", path)
+            }
+            TaskAttemptError::ExecutorUnavailable(reason) => write!(f, "{}", reason),
+            TaskAttemptError::GitUnavailable(feature) => {
+                write!(f, "{}", Project::git_unavailable_message(feature))
+            }
         }
     }
 }
@@ -97,18 +133,37 @@ pub struct TaskAttempt {
     pub pr_url: Option<String>,    // GitHub PR URL
     pub pr_number: Option<i64>,    // GitHub PR number
     pub pr_status: Option<String>, // open, closed, merged
+    #[serde(default, with = "crate::utils::rfc3339::option")]
     pub pr_merged_at: Option<DateTime<Utc>>, // When PR was merged
     pub worktree_deleted: bool,    // Flag indicating if worktree has been cleaned up
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub worktree_deleted_at: Option<DateTime<Utc>>, // When the worktree was cleaned up
+    #[serde(default, with = "crate::utils::rfc3339::option")]
     pub setup_completed_at: Option<DateTime<Utc>>, // When setup script was last completed
+    pub stop_note: Option<String>, // Note left by the user when last stopping the attempt
+    #[serde(default, serialize_with = "serialize_redacted_env")]
+    #[ts(type = "Record<string, string> | null")]
+    pub env: Option<Json<HashMap<String, String>>>, // Extra environment for the attempt's processes
+    pub benchmark_run_id: Option<Uuid>, // Set when the attempt was started by a benchmark run
+    pub retry_of: Option<Uuid>,    // Attempt this one retries
+    pub retry_count: i64, // Retries before this attempt in its chain; 0 for a first attempt
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize, TS)]
 #[ts(export)]
 pub struct CreateTaskAttempt {
-    pub executor: Option<String>, // Optional executor name (defaults to "echo")
+    pub executor: Option<String>, // Optional executor name (else the task's, repo's or server's default)
     pub base_branch: Option<String>, // Optional base branch to checkout (defaults to current HEAD)
+    /// Variables for the attempt's processes, set over the environment codecommand runs in
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Attempt of the same task that this one retries
+    #[serde(default)]
+    pub retry_of: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -132,6 +187,12 @@ pub struct CreatePrParams<'a> {
 #[ts(export)]
 pub struct CreateFollowUpAttempt {
     pub prompt: String,
+    /// Prepend a summary of the attempt's current diff so the agent knows what it already changed
+    #[serde(default)]
+    pub include_previous_diff: bool,
+    /// Open the prompt with the commit this bisect run of the attempt found
+    #[serde(default)]
+    pub bisect_run_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -140,6 +201,8 @@ pub enum DiffChunkType {
     Equal,
     Insert,
     Delete,
+    Renamed,
+    Copied,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -153,6 +216,10 @@ pub struct DiffChunk {
 #[ts(export)]
 pub struct FileDiff {
     pub path: String,
+    /// Previous path when git detected the file as renamed or copied
+    pub old_path: Option<String>,
+    /// New path when git detected the file as renamed or copied (same as `path`)
+    pub new_path: Option<String>,
     pub chunks: Vec<DiffChunk>,
 }
 
@@ -162,6 +229,94 @@ pub struct WorktreeDiff {
     pub files: Vec<FileDiff>,
 }
 
+impl WorktreeDiff {
+    /// Compact unified-diff-like summary of the changed files and hunks, without unchanged
+    /// context, cut off at `max_chars`
+    pub fn summary(&self, max_chars: usize) -> String {
+        let mut summary = String::new();
+        for file in &self.files {
+            match &file.old_path {
+                Some(old_path) => summary.push_str(&format!("--- {} -> {}\n", old_path, file.path)),
+                None => summary.push_str(&format!("--- {}\n", file.path)),
+            }
+            for chunk in &file.chunks {
+                let prefix = match chunk.chunk_type {
+                    DiffChunkType::Equal => {
+                        summary.push_str("@@\n");
+                        continue;
+                    }
+                    DiffChunkType::Insert => "+",
+                    DiffChunkType::Delete => "-",
+                    DiffChunkType::Renamed | DiffChunkType::Copied => "# ",
+                };
+                for line in chunk.content.lines() {
+                    summary.push_str(prefix);
+                    summary.push_str(line);
+                    summary.push('\n');
+                }
+            }
+        }
+
+        if summary.len() > max_chars {
+            let mut cut = max_chars;
+            while !summary.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            summary.truncate(cut);
+            summary.push_str("\n... (diff truncated)\n");
+        }
+        summary
+    }
+}
+
+/// Summary of the changes made so far, sent ahead of a follow-up prompt; `None` when the diff
+/// is empty
+pub fn followup_diff_context(diff: &WorktreeDiff, max_chars: usize) -> Option<String> {
+    if diff.files.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "For context, these are the changes made so far in this task:\n```diff\n{}```",
+        diff.summary(max_chars)
+    ))
+}
+
+/// Check the names of an attempt's environment variables, which must be settable on a process
+pub fn validate_attempt_env(env: &HashMap<String, String>) -> Result<(), String> {
+    for (name, value) in env {
+        if name.is_empty() || name.contains('=') || name.contains('\0') {
+            return Err(format!(
+                "'{}' is not a valid environment variable name",
+                name
+            ));
+        }
+        if value.contains('\0') {
+            return Err(format!("The value of {} contains a NUL byte", name));
+        }
+    }
+    Ok(())
+}
+
+/// Serialize an attempt's environment with the values of secret-looking variables hidden
+fn serialize_redacted_env<S: serde::Serializer>(
+    env: &Option<Json<HashMap<String, String>>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let redacted = env.as_ref().map(|env| {
+        env.iter()
+            .map(|(name, value)| {
+                let value = if secrets::is_secret_name(name) {
+                    "[REDACTED]"
+                } else {
+                    value.as_str()
+                };
+                (name.as_str(), value)
+            })
+            .collect::<std::collections::BTreeMap<_, _>>()
+    });
+    redacted.serialize(serializer)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct BranchStatus {
@@ -172,6 +327,8 @@ pub struct BranchStatus {
     pub merged: bool,
     pub has_uncommitted_changes: bool,
     pub base_branch_name: String,
+    /// Files git doesn't track that changed during the attempt; they won't be merged
+    pub untracked_changes: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -187,14 +344,191 @@ pub enum ExecutionState {
     Complete,
 }
 
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct WorktreeStatus {
+    pub attempt_id: Uuid,
+    pub task_id: Uuid,
+    pub project_id: Uuid,
+    pub branch: String,
+    pub worktree_path: String,
+    pub worktree_deleted: bool,
+    /// Directory is gone but codecommand did not clean it up; recover with recreate-worktree
+    pub missing: bool,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct WorktreeRelocation {
+    pub attempt_id: Uuid,
+    pub old_path: String,
+    pub new_path: String,
+    /// Whether `git worktree repair` succeeded
+    pub git_repaired: bool,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct WorktreeRepairReport {
+    /// Worktrees that were found, where they were or where they were moved to
+    pub repaired: Vec<WorktreeRelocation>,
+    /// Attempts whose worktree is missing and wasn't found in any of the searched directories
+    pub not_found: Vec<Uuid>,
+}
+
+/// Where an attempt's setup and agent runs stand, for listing attempts across projects
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[sqlx(type_name = "attempt_run_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum AttemptRunStatus {
+    NotStarted,
+    Running,
+    Completed,
+    Failed,
+    Killed,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct TaskAttemptListItem {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub branch: String,
+    /// Executor of the latest agent run, or the one the attempt was created with
+    pub executor_type: Option<String>,
+    pub status: AttemptRunStatus,
+    /// Time spent in setup scripts and agent runs, counting running ones up to now
+    pub duration_secs: u32,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct TaskAttemptListPage {
+    pub items: Vec<TaskAttemptListItem>,
+    /// Pass as `before` to fetch the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl AttemptRunStatus {
+    pub const ALL: [AttemptRunStatus; 5] = [
+        AttemptRunStatus::NotStarted,
+        AttemptRunStatus::Running,
+        AttemptRunStatus::Completed,
+        AttemptRunStatus::Failed,
+        AttemptRunStatus::Killed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttemptRunStatus::NotStarted => "not_started",
+            AttemptRunStatus::Running => "running",
+            AttemptRunStatus::Completed => "completed",
+            AttemptRunStatus::Failed => "failed",
+            AttemptRunStatus::Killed => "killed",
+        }
+    }
+}
+
+impl std::str::FromStr for AttemptRunStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| {
+                let valid: Vec<_> = Self::ALL.iter().map(AttemptRunStatus::as_str).collect();
+                format!(
+                    "Unknown attempt status '{}'. Valid statuses: {}",
+                    s,
+                    valid.join(", ")
+                )
+            })
+    }
+}
+
+/// Filters for listing attempts across projects
+#[derive(Debug, Clone, Default)]
+pub struct TaskAttemptListFilter {
+    pub project_id: Option<Uuid>,
+    pub status: Option<AttemptRunStatus>,
+    pub executor_type: Option<String>,
+    /// Only attempts created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only attempts created before this time
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+/// A time in the format `created_at` columns are stored in, so it compares with them as text
+fn stored_timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+/// Keyset position in the attempt list, newest first. `created_at` is kept as stored so it
+/// compares exactly with the column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskAttemptListCursor {
+    created_at: String,
+    id: Uuid,
+}
+
+impl TaskAttemptListCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.created_at, self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+        let (created_at, id) = decoded.rsplit_once('|')?;
+        Some(Self {
+            created_at: created_at.to_string(),
+            id: Uuid::parse_str(id).ok()?,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct TaskAttemptState {
     pub execution_state: ExecutionState,
+    /// Absolute path of the attempt's worktree on disk
+    pub worktree_path: String,
     pub has_changes: bool,
     pub has_setup_script: bool,
     pub setup_process_id: Option<String>,
     pub coding_agent_process_id: Option<String>,
+    /// Number of reviewer annotations on the attempt's log
+    pub annotation_count: u32,
+    /// An agent of the attempt contacted a host on its project's network denylist
+    pub policy_violation: bool,
+    /// When focus mode resumes the attempt, while it has paused processes
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub paused_until: Option<DateTime<Utc>>,
+}
+
+/// Worktree of an attempt with nothing running, as considered for cleanup
+#[derive(Debug, Clone)]
+pub struct IdleWorktree {
+    pub attempt_id: Uuid,
+    pub worktree_path: String,
+    pub git_repo_path: String,
+    pub last_activity_at: DateTime<Utc>,
+}
+
+/// Unmerged attempt of a task that is still open, with its worktree on disk
+#[derive(Debug, Clone)]
+pub struct OpenAttempt {
+    pub attempt_id: Uuid,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub worktree_path: String,
+    pub base_branch: String,
 }
 
 /// Context data for resume operations (simplified)
@@ -234,7 +568,13 @@ impl TaskAttempt {
                        ta.pr_status,
                        ta.pr_merged_at      AS "pr_merged_at: DateTime<Utc>",
                        ta.worktree_deleted  AS "worktree_deleted!: bool",
+                       ta.worktree_deleted_at AS "worktree_deleted_at: DateTime<Utc>",
                        ta.setup_completed_at AS "setup_completed_at: DateTime<Utc>",
+                       ta.stop_note,
+                       ta.env AS "env: Json<HashMap<String, String>>",
+                       ta.benchmark_run_id AS "benchmark_run_id: Uuid",
+                       ta.retry_of AS "retry_of: Uuid",
+                       ta.retry_count AS "retry_count!: i64",
                        ta.created_at        AS "created_at!: DateTime<Utc>",
                        ta.updated_at        AS "updated_at!: DateTime<Utc>"
                FROM    task_attempts ta
@@ -271,7 +611,7 @@ impl TaskAttempt {
         attempt_id: Uuid,
     ) -> Result<(), sqlx::Error> {
         sqlx::query!(
-            "UPDATE task_attempts SET worktree_deleted = TRUE, updated_at = datetime('now') WHERE id = ?",
+            "UPDATE task_attempts SET worktree_deleted = TRUE, worktree_deleted_at = datetime('now'), updated_at = datetime('now') WHERE id = ?",
             attempt_id
         )
         .execute(pool)
@@ -314,7 +654,13 @@ impl TaskAttempt {
                        pr_status,
                        pr_merged_at      AS "pr_merged_at: DateTime<Utc>",
                        worktree_deleted  AS "worktree_deleted!: bool",
+                       worktree_deleted_at AS "worktree_deleted_at: DateTime<Utc>",
                        setup_completed_at AS "setup_completed_at: DateTime<Utc>",
+                       stop_note,
+                       env AS "env: Json<HashMap<String, String>>",
+                       benchmark_run_id AS "benchmark_run_id: Uuid",
+                       retry_of AS "retry_of: Uuid",
+                       retry_count AS "retry_count!: i64",
                        created_at        AS "created_at!: DateTime<Utc>",
                        updated_at        AS "updated_at!: DateTime<Utc>"
                FROM    task_attempts
@@ -343,11 +689,17 @@ impl TaskAttempt {
                        pr_status,
                        pr_merged_at      AS "pr_merged_at: DateTime<Utc>",
                        worktree_deleted  AS "worktree_deleted!: bool",
+                       worktree_deleted_at AS "worktree_deleted_at: DateTime<Utc>",
                        setup_completed_at AS "setup_completed_at: DateTime<Utc>",
+                       stop_note,
+                       env AS "env: Json<HashMap<String, String>>",
+                       benchmark_run_id AS "benchmark_run_id: Uuid",
+                       retry_of AS "retry_of: Uuid",
+                       retry_count AS "retry_count!: i64",
                        created_at        AS "created_at!: DateTime<Utc>",
                        updated_at        AS "updated_at!: DateTime<Utc>"
                FROM    task_attempts
-               WHERE   task_id = $1
+               WHERE   task_id = $1 AND benchmark_run_id IS NULL
                ORDER BY created_at DESC"#,
             task_id
         )
@@ -355,6 +707,58 @@ impl TaskAttempt {
         .await
     }
 
+    /// The attempts an attempt was retried from and the retries made of it, oldest first.
+    /// Includes the attempt itself; empty when it doesn't exist.
+    pub async fn find_retry_chain(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            TaskAttempt,
+            r#"WITH RECURSIVE
+                   ancestors(id) AS (
+                       SELECT $1
+                       UNION
+                       SELECT ta.retry_of FROM task_attempts ta
+                       JOIN ancestors a ON ta.id = a.id
+                       WHERE ta.retry_of IS NOT NULL
+                   ),
+                   descendants(id) AS (
+                       SELECT $1
+                       UNION
+                       SELECT ta.id FROM task_attempts ta
+                       JOIN descendants d ON ta.retry_of = d.id
+                   )
+               SELECT  id                AS "id!: Uuid",
+                       task_id           AS "task_id!: Uuid",
+                       worktree_path,
+                       branch,
+                       base_branch,
+                       merge_commit,
+                       executor,
+                       pr_url,
+                       pr_number,
+                       pr_status,
+                       pr_merged_at      AS "pr_merged_at: DateTime<Utc>",
+                       worktree_deleted  AS "worktree_deleted!: bool",
+                       worktree_deleted_at AS "worktree_deleted_at: DateTime<Utc>",
+                       setup_completed_at AS "setup_completed_at: DateTime<Utc>",
+                       stop_note,
+                       env AS "env: Json<HashMap<String, String>>",
+                       benchmark_run_id AS "benchmark_run_id: Uuid",
+                       retry_of AS "retry_of: Uuid",
+                       retry_count AS "retry_count!: i64",
+                       created_at        AS "created_at!: DateTime<Utc>",
+                       updated_at        AS "updated_at!: DateTime<Utc>"
+               FROM    task_attempts
+               WHERE   id IN (SELECT id FROM ancestors UNION SELECT id FROM descendants)
+               ORDER BY retry_count ASC, created_at ASC"#,
+            attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
     /// Find task attempts by task_id with project git repo path for cleanup operations
     pub async fn find_by_task_id_with_project(
         pool: &SqlitePool,
@@ -379,60 +783,552 @@ impl TaskAttempt {
             .collect())
     }
 
-    /// Find task attempts that are expired (24+ hours since last activity) and eligible for worktree cleanup
-    /// Activity includes: execution completion, task attempt updates (including worktree recreation),
-    /// and any attempts that are currently in progress
-    pub async fn find_expired_for_cleanup(
+    /// Worktrees of attempts with nothing running, least recently active first. Activity is
+    /// the later of the attempt's last update and its last finished execution.
+    pub async fn find_idle_worktrees(pool: &SqlitePool) -> Result<Vec<IdleWorktree>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT ta.id            AS "attempt_id!: Uuid",
+                      ta.worktree_path AS "worktree_path!",
+                      p.git_repo_path  AS "git_repo_path!",
+                      datetime(MAX(ta.updated_at, COALESCE(MAX(ep.completed_at), ta.updated_at)))
+                                       AS "last_activity_at!: DateTime<Utc>"
+               FROM task_attempts ta
+               JOIN tasks t ON ta.task_id = t.id
+               JOIN projects p ON t.project_id = p.id
+               LEFT JOIN execution_processes ep ON ep.task_attempt_id = ta.id
+               WHERE ta.worktree_deleted = FALSE
+               GROUP BY ta.id
+               -- every process has finished
+               HAVING COUNT(ep.id) = COUNT(ep.completed_at)
+               ORDER BY last_activity_at ASC"#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|r| IdleWorktree {
+                attempt_id: r.attempt_id,
+                worktree_path: r.worktree_path,
+                git_repo_path: r.git_repo_path,
+                last_activity_at: r.last_activity_at,
+            })
+            .collect())
+    }
+
+    /// Unmerged attempts with a worktree, of tasks in the project that aren't done or cancelled
+    pub async fn find_open_by_project_id(
         pool: &SqlitePool,
-    ) -> Result<Vec<(Uuid, String, String)>, sqlx::Error> {
+        project_id: Uuid,
+    ) -> Result<Vec<OpenAttempt>, sqlx::Error> {
         let records = sqlx::query!(
-            r#"
-            SELECT ta.id as "attempt_id!: Uuid", ta.worktree_path, p.git_repo_path as "git_repo_path!"
-            FROM task_attempts ta
-            LEFT JOIN execution_processes ep ON ta.id = ep.task_attempt_id AND ep.completed_at IS NOT NULL
-            JOIN tasks t ON ta.task_id = t.id
-            JOIN projects p ON t.project_id = p.id
-            WHERE ta.worktree_deleted = FALSE
-                -- Exclude attempts with any running processes (in progress)
-                AND ta.id NOT IN (
-                    SELECT DISTINCT ep2.task_attempt_id
-                    FROM execution_processes ep2
-                    WHERE ep2.completed_at IS NULL
-                )
-            GROUP BY ta.id, ta.worktree_path, p.git_repo_path, ta.updated_at
-            HAVING datetime('now', '-24 hours') > datetime(
-                MAX(
-                    CASE
-                        WHEN ep.completed_at IS NOT NULL THEN ep.completed_at
-                        ELSE ta.updated_at
-                    END
-                )
-            )
-            ORDER BY MAX(
-                CASE
-                    WHEN ep.completed_at IS NOT NULL THEN ep.completed_at
-                    ELSE ta.updated_at
-                END
-            ) ASC
-            "#
+            r#"SELECT ta.id            AS "attempt_id!: Uuid",
+                      ta.task_id       AS "task_id!: Uuid",
+                      t.title          AS "task_title!",
+                      ta.worktree_path AS "worktree_path!",
+                      ta.base_branch   AS "base_branch!"
+               FROM task_attempts ta
+               JOIN tasks t ON ta.task_id = t.id
+               WHERE t.project_id = $1
+                 AND t.status NOT IN ('done', 'cancelled')
+                 AND ta.merge_commit IS NULL
+                 AND ta.worktree_deleted = FALSE
+               ORDER BY ta.created_at ASC"#,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|r| OpenAttempt {
+                attempt_id: r.attempt_id,
+                task_id: r.task_id,
+                task_title: r.task_title,
+                worktree_path: r.worktree_path,
+                base_branch: r.base_branch,
+            })
+            .collect())
+    }
+
+    /// List the worktree of every attempt, flagging directories that were deleted by hand
+    pub async fn find_worktree_statuses(
+        pool: &SqlitePool,
+    ) -> Result<Vec<WorktreeStatus>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT ta.id               AS "attempt_id!: Uuid",
+                      ta.task_id          AS "task_id!: Uuid",
+                      t.project_id        AS "project_id!: Uuid",
+                      ta.branch,
+                      ta.worktree_path,
+                      ta.worktree_deleted AS "worktree_deleted!: bool"
+               FROM task_attempts ta
+               JOIN tasks t ON ta.task_id = t.id
+               ORDER BY ta.created_at DESC"#
         )
         .fetch_all(pool)
         .await?;
 
         Ok(records
             .into_iter()
-            .filter_map(|r| {
-                r.worktree_path
-                    .map(|path| (r.attempt_id, path, r.git_repo_path))
+            .map(|r| WorktreeStatus {
+                missing: !r.worktree_deleted && !Path::new(&r.worktree_path).exists(),
+                attempt_id: r.attempt_id,
+                task_id: r.task_id,
+                project_id: r.project_id,
+                branch: r.branch,
+                worktree_path: r.worktree_path,
+                worktree_deleted: r.worktree_deleted,
             })
             .collect())
     }
 
+    /// Point an attempt at its worktree after it was moved to `new_path`, repairing git's
+    /// references to it. The directory has to be a worktree of the project's repository with
+    /// the attempt's branch checked out.
+    pub async fn relocate_worktree(
+        pool: &SqlitePool,
+        task_attempt: &TaskAttempt,
+        git_repo_path: &str,
+        new_path: &Path,
+    ) -> Result<WorktreeRelocation, TaskAttemptError> {
+        if !new_path.is_dir() {
+            return Err(TaskAttemptError::ValidationError(format!(
+                "{} does not exist or is not a directory",
+                new_path.display()
+            )));
+        }
+        // Linked worktrees have a `.git` file pointing into the repository, not a directory
+        if !new_path.join(".git").is_file() {
+            return Err(TaskAttemptError::ValidationError(format!(
+                "{} is not a git worktree",
+                new_path.display()
+            )));
+        }
+
+        let git_repaired = WorktreeManager::repair_worktree(git_repo_path, new_path).await;
+        if !WorktreeManager::is_listed_worktree(git_repo_path, new_path)
+            .await
+            .map_err(GitServiceError::from)?
+        {
+            return Err(TaskAttemptError::ValidationError(format!(
+                "{} is not a worktree of {}",
+                new_path.display(),
+                git_repo_path
+            )));
+        }
+
+        let worktree_repo = Repository::open(new_path)?;
+        let head = worktree_repo.head()?;
+        let checked_out = head.shorthand().unwrap_or_default();
+        if checked_out != task_attempt.branch {
+            return Err(TaskAttemptError::ValidationError(format!(
+                "{} has {} checked out, not the attempt's branch {}",
+                new_path.display(),
+                checked_out,
+                task_attempt.branch
+            )));
+        }
+
+        let new_path = new_path.to_string_lossy().to_string();
+        sqlx::query!(
+            "UPDATE task_attempts SET worktree_path = $1, worktree_deleted = FALSE, worktree_deleted_at = NULL, updated_at = datetime('now') WHERE id = $2",
+            new_path,
+            task_attempt.id
+        )
+        .execute(pool)
+        .await?;
+
+        info!(
+            "Relocated worktree of attempt {} from {} to {}",
+            task_attempt.id, task_attempt.worktree_path, new_path
+        );
+        Ok(WorktreeRelocation {
+            attempt_id: task_attempt.id,
+            old_path: task_attempt.worktree_path.clone(),
+            new_path,
+            git_repaired,
+        })
+    }
+
+    /// Repair the worktrees of a project's attempts, e.g. after the project directory moved.
+    /// Worktrees still at their recorded path get git's references repaired; missing ones are
+    /// looked for by directory name in the worktree base directory and next to the repository.
+    pub async fn repair_project_worktrees(
+        pool: &SqlitePool,
+        project: &Project,
+    ) -> Result<WorktreeRepairReport, TaskAttemptError> {
+        let attempts = sqlx::query_as!(
+            TaskAttempt,
+            r#"SELECT ta.id                AS "id!: Uuid",
+                      ta.task_id           AS "task_id!: Uuid",
+                      ta.worktree_path,
+                      ta.branch,
+                      ta.base_branch,
+                      ta.merge_commit,
+                      ta.executor,
+                      ta.pr_url,
+                      ta.pr_number,
+                      ta.pr_status,
+                      ta.pr_merged_at      AS "pr_merged_at: DateTime<Utc>",
+                      ta.worktree_deleted  AS "worktree_deleted!: bool",
+                      ta.worktree_deleted_at AS "worktree_deleted_at: DateTime<Utc>",
+                      ta.setup_completed_at AS "setup_completed_at: DateTime<Utc>",
+                      ta.stop_note,
+                      ta.env AS "env: Json<HashMap<String, String>>",
+                      ta.benchmark_run_id AS "benchmark_run_id: Uuid",
+                      ta.retry_of AS "retry_of: Uuid",
+                      ta.retry_count AS "retry_count!: i64",
+                      ta.created_at        AS "created_at!: DateTime<Utc>",
+                      ta.updated_at        AS "updated_at!: DateTime<Utc>"
+               FROM task_attempts ta
+               JOIN tasks t ON ta.task_id = t.id
+               WHERE t.project_id = $1 AND ta.worktree_deleted = FALSE
+               ORDER BY ta.created_at DESC"#,
+            project.id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let repo_path = Path::new(&project.git_repo_path);
+        let mut search_dirs = vec![Self::get_worktree_base_dir(), repo_path.to_path_buf()];
+        search_dirs.extend(repo_path.parent().map(Path::to_path_buf));
+
+        let mut report = WorktreeRepairReport {
+            repaired: Vec::new(),
+            not_found: Vec::new(),
+        };
+        for attempt in attempts {
+            let old_path = Path::new(&attempt.worktree_path);
+            let candidates: Vec<_> = if old_path.exists() {
+                vec![old_path.to_path_buf()]
+            } else {
+                old_path
+                    .file_name()
+                    .map(|name| search_dirs.iter().map(|dir| dir.join(name)).collect())
+                    .unwrap_or_default()
+            };
+
+            let mut relocated = None;
+            for candidate in candidates.iter().filter(|candidate| candidate.is_dir()) {
+                match Self::relocate_worktree(pool, &attempt, &project.git_repo_path, candidate)
+                    .await
+                {
+                    Ok(relocation) => {
+                        relocated = Some(relocation);
+                        break;
+                    }
+                    Err(TaskAttemptError::ValidationError(reason)) => {
+                        tracing::debug!(
+                            "{} is not the worktree of attempt {}: {}",
+                            candidate.display(),
+                            attempt.id,
+                            reason
+                        );
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            match relocated {
+                Some(relocation) => report.repaired.push(relocation),
+                None => report.not_found.push(attempt.id),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Attempts across all projects, newest first, one page of at most `limit` after `before`
+    pub async fn list_across_projects(
+        pool: &SqlitePool,
+        filter: &TaskAttemptListFilter,
+        before: Option<&TaskAttemptListCursor>,
+        limit: u32,
+    ) -> Result<TaskAttemptListPage, sqlx::Error> {
+        let before_created_at = before.map(|cursor| cursor.created_at.clone());
+        let before_id = before.map(|cursor| cursor.id);
+        let created_after = filter.created_after.map(stored_timestamp);
+        let created_before = filter.created_before.map(stored_timestamp);
+        // One extra row tells whether there is a next page
+        let fetch = limit as i64 + 1;
+
+        let mut records = sqlx::query!(
+            r#"WITH runs AS (
+                   SELECT task_attempt_id,
+                          MAX(status = 'running') AS any_running,
+                          SUM((julianday(COALESCE(completed_at, datetime('now', 'subsec'))) - julianday(started_at)) * 86400.0) AS duration_secs
+                   FROM execution_processes
+                   WHERE process_type != 'devserver'
+                   GROUP BY task_attempt_id
+               ),
+               latest AS (
+                   SELECT task_attempt_id, status
+                   FROM (
+                       SELECT task_attempt_id, status,
+                              ROW_NUMBER() OVER (PARTITION BY task_attempt_id ORDER BY created_at DESC) AS rn
+                       FROM execution_processes
+                       WHERE process_type != 'devserver'
+                   )
+                   WHERE rn = 1
+               ),
+               latest_agent AS (
+                   SELECT task_attempt_id, executor_type
+                   FROM (
+                       SELECT task_attempt_id, executor_type,
+                              ROW_NUMBER() OVER (PARTITION BY task_attempt_id ORDER BY created_at DESC) AS rn
+                       FROM execution_processes
+                       WHERE process_type = 'codingagent' AND executor_type IS NOT NULL
+                   )
+                   WHERE rn = 1
+               ),
+               items AS (
+                   SELECT ta.id,
+                          ta.task_id,
+                          t.title AS task_title,
+                          t.project_id,
+                          p.name AS project_name,
+                          ta.branch,
+                          COALESCE(latest_agent.executor_type, ta.executor) AS executor_type,
+                          CASE
+                              WHEN runs.any_running THEN 'running'
+                              WHEN latest.status IS NULL THEN 'not_started'
+                              ELSE latest.status
+                          END AS status,
+                          COALESCE(runs.duration_secs, 0.0) AS duration_secs,
+                          ta.created_at
+                   FROM task_attempts ta
+                   JOIN tasks t ON ta.task_id = t.id
+                   JOIN projects p ON t.project_id = p.id
+                   LEFT JOIN runs ON runs.task_attempt_id = ta.id
+                   LEFT JOIN latest ON latest.task_attempt_id = ta.id
+                   LEFT JOIN latest_agent ON latest_agent.task_attempt_id = ta.id
+                   WHERE ta.benchmark_run_id IS NULL
+               )
+               SELECT id AS "id!: Uuid",
+                      task_id AS "task_id!: Uuid",
+                      task_title AS "task_title!: String",
+                      project_id AS "project_id!: Uuid",
+                      project_name AS "project_name!: String",
+                      branch AS "branch!: String",
+                      executor_type AS "executor_type: String",
+                      status AS "status!: AttemptRunStatus",
+                      duration_secs AS "duration_secs!: f64",
+                      created_at AS "created_at_key!: String",
+                      created_at AS "created_at!: DateTime<Utc>"
+               FROM items
+               WHERE ($1 IS NULL OR project_id = $1)
+                 AND ($2 IS NULL OR status = $2)
+                 AND ($3 IS NULL OR executor_type = $3)
+                 AND ($4 IS NULL OR created_at < $4 OR (created_at = $4 AND id < $5))
+                 AND ($7 IS NULL OR created_at >= $7)
+                 AND ($8 IS NULL OR created_at < $8)
+               ORDER BY created_at DESC, id DESC
+               LIMIT $6"#,
+            filter.project_id,
+            filter.status,
+            filter.executor_type,
+            before_created_at,
+            before_id,
+            fetch,
+            created_after,
+            created_before
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let next_cursor = if records.len() > limit as usize {
+            records.truncate(limit as usize);
+            records.last().map(|last| {
+                TaskAttemptListCursor {
+                    created_at: last.created_at_key.clone(),
+                    id: last.id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Ok(TaskAttemptListPage {
+            items: records
+                .into_iter()
+                .map(|r| TaskAttemptListItem {
+                    id: r.id,
+                    task_id: r.task_id,
+                    task_title: r.task_title,
+                    project_id: r.project_id,
+                    project_name: r.project_name,
+                    branch: r.branch,
+                    executor_type: r.executor_type,
+                    status: r.status,
+                    duration_secs: r.duration_secs.max(0.0).round() as u32,
+                    created_at: r.created_at,
+                })
+                .collect(),
+            next_cursor,
+        })
+    }
+
+    /// Branches of all attempts on a repository, across the projects that use it
+    pub async fn branches_in_repo(
+        pool: &SqlitePool,
+        git_repo_path: &str,
+    ) -> Result<HashSet<String>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT ta.branch
+               FROM task_attempts ta
+               JOIN tasks t ON ta.task_id = t.id
+               JOIN projects p ON t.project_id = p.id
+               WHERE p.git_repo_path = $1"#,
+            git_repo_path
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(records.into_iter().map(|r| r.branch).collect())
+    }
+
+    /// Record an attempt for an existing local branch, e.g. one left by another install. No
+    /// worktree is created; it is marked deleted so it gets checked out when first needed.
+    pub async fn adopt_branch(
+        pool: &SqlitePool,
+        task: &Task,
+        branch: &str,
+        base_branch: &str,
+        worktree_name_pattern: &str,
+    ) -> Result<Self, TaskAttemptError> {
+        let attempt_id = Uuid::new_v4();
+        let worktree_path = Self::get_worktree_base_dir()
+            .join(Self::worktree_dir_name(
+                worktree_name_pattern,
+                attempt_id,
+                task,
+            ))
+            .to_string_lossy()
+            .to_string();
+
+        let attempt = sqlx::query_as!(
+            TaskAttempt,
+            r#"INSERT INTO task_attempts (id, task_id, worktree_path, branch, base_branch, worktree_deleted)
+               VALUES ($1, $2, $3, $4, $5, TRUE)
+               RETURNING id as "id!: Uuid", task_id as "task_id!: Uuid", worktree_path, branch, base_branch, merge_commit, executor, pr_url, pr_number, pr_status, pr_merged_at as "pr_merged_at: DateTime<Utc>", worktree_deleted as "worktree_deleted!: bool", worktree_deleted_at as "worktree_deleted_at: DateTime<Utc>", setup_completed_at as "setup_completed_at: DateTime<Utc>", stop_note, env as "env: Json<HashMap<String, String>>", benchmark_run_id as "benchmark_run_id: Uuid", retry_of as "retry_of: Uuid", retry_count as "retry_count!: i64", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            attempt_id,
+            task.id,
+            worktree_path,
+            branch,
+            base_branch
+        )
+        .fetch_one(pool)
+        .await?;
+
+        info!("Adopted branch {} as attempt {}", branch, attempt.id);
+        Ok(attempt)
+    }
+
+    /// Directory name of an attempt's worktree from the configured pattern, with anything
+    /// that isn't safe in a file name replaced by '-'
+    fn worktree_dir_name(pattern: &str, attempt_id: Uuid, task: &Task) -> String {
+        // The config file may have been edited by hand; without the attempt id two attempts
+        // of a task could share a directory
+        let pattern = if pattern.contains("{attempt_id}") {
+            pattern.to_string()
+        } else {
+            format!("{}-{{attempt_id}}", pattern)
+        };
+        let name = pattern
+            .replace("{attempt_id}", &attempt_id.simple().to_string())
+            .replace(
+                "{short_attempt_id}",
+                &crate::utils::text::short_uuid(&attempt_id),
+            )
+            .replace("{task_id}", &task.id.simple().to_string())
+            .replace(
+                "{task_title}",
+                &crate::utils::text::git_branch_id(&task.title),
+            );
+
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+
     pub async fn create(
         pool: &SqlitePool,
         data: &CreateTaskAttempt,
         task_id: Uuid,
+        worktree_name_pattern: &str,
     ) -> Result<Self, TaskAttemptError> {
+        Self::create_with_lookup(
+            pool,
+            data,
+            task_id,
+            worktree_name_pattern,
+            &ExecutableLookup::from_env(),
+            None,
+        )
+        .await
+    }
+
+    /// Create an attempt for a benchmark run, with its worktree checked out at the commit the
+    /// benchmarked task originally started from
+    pub async fn create_for_benchmark(
+        pool: &SqlitePool,
+        data: &CreateTaskAttempt,
+        task_id: Uuid,
+        worktree_name_pattern: &str,
+        benchmark: &BenchmarkBase,
+    ) -> Result<Self, TaskAttemptError> {
+        Self::create_with_lookup(
+            pool,
+            data,
+            task_id,
+            worktree_name_pattern,
+            &ExecutableLookup::from_env(),
+            Some(benchmark),
+        )
+        .await
+    }
+
+    /// Pre-flight for starting an attempt: a warning when the project's repository has
+    /// uncommitted changes that the new worktree won't include
+    pub async fn source_repo_warning(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Option<String>, TaskAttemptError> {
+        let project = Project::find_by_id(pool, project_id)
+            .await?
+            .ok_or(TaskAttemptError::ProjectNotFound)?;
+        // A scratch project is copied as it is, uncommitted changes and all
+        if project.is_scratch() {
+            return Ok(None);
+        }
+        let changes = GitService::new(&project.git_repo_path)?.source_repo_changes()?;
+        Ok(crate::services::git_service::dirty_source_repo_warning(
+            &changes,
+        ))
+    }
+
+    async fn create_with_lookup(
+        pool: &SqlitePool,
+        data: &CreateTaskAttempt,
+        task_id: Uuid,
+        worktree_name_pattern: &str,
+        lookup: &ExecutableLookup,
+        benchmark: Option<&BenchmarkBase>,
+    ) -> Result<Self, TaskAttemptError> {
+        // Fail here rather than deep in the spawn, leaving an orphaned worktree behind
+        ProcessService::resolve_executor_config(&data.executor)
+            .check_available(lookup)
+            .map_err(TaskAttemptError::ExecutorUnavailable)?;
+
+        validate_attempt_env(&data.env).map_err(TaskAttemptError::ValidationError)?;
+        let env = (!data.env.is_empty()).then(|| Json(data.env.clone()));
+
         let attempt_id = Uuid::new_v4();
         // let prefixed_id = format!("codecommand-{}", attempt_id);
 
@@ -441,6 +1337,23 @@ impl TaskAttempt {
             .await?
             .ok_or(TaskAttemptError::TaskNotFound)?;
 
+        // A retry continues its parent's chain
+        let retry_count = match data.retry_of {
+            Some(parent_id) => {
+                let parent = Self::find_by_id(pool, parent_id)
+                    .await?
+                    .filter(|parent| parent.task_id == task_id)
+                    .ok_or_else(|| {
+                        TaskAttemptError::ValidationError(format!(
+                            "Attempt {} to retry is not an attempt of this task",
+                            parent_id
+                        ))
+                    })?;
+                parent.retry_count + 1
+            }
+            None => 0,
+        };
+
         // Create a unique and helpful branch name
         let task_title_id = crate::utils::text::git_branch_id(&task.title);
         let task_attempt_branch = format!(
@@ -450,7 +1363,11 @@ impl TaskAttempt {
         );
 
         // Generate worktree path using codecommand specific directory
-        let worktree_path = Self::get_worktree_base_dir().join(&task_attempt_branch);
+        let worktree_path = Self::get_worktree_base_dir().join(Self::worktree_dir_name(
+            worktree_name_pattern,
+            attempt_id,
+            &task,
+        ));
         let worktree_path_str = worktree_path.to_string_lossy().to_string();
 
         // Then get the project using the project_id
@@ -458,30 +1375,57 @@ impl TaskAttempt {
             .await?
             .ok_or(TaskAttemptError::ProjectNotFound)?;
 
-        // Create GitService instance
-        let git_service = GitService::new(&project.git_repo_path)?;
-
-        // Determine the resolved base branch name first
-        let resolved_base_branch = if let Some(ref base_branch) = data.base_branch {
-            base_branch.clone()
+        // Scratch projects have no repository: the attempt works in a plain copy of the
+        // project directory and has no branch
+        let (task_attempt_branch, resolved_base_branch, git_service) = if project.is_scratch() {
+            if benchmark.is_some() {
+                return Err(TaskAttemptError::GitUnavailable("Benchmarks"));
+            }
+            Self::copy_scratch_project(&project, &worktree_path).await?;
+            (String::new(), String::new(), None)
         } else {
-            // Default to current HEAD branch name or "main"
-            git_service.get_default_branch_name()?
-        };
+            // Create GitService instance
+            let git_service = GitService::new(&project.git_repo_path)?;
 
-        // Create the worktree using GitService
-        git_service.create_worktree(
-            &task_attempt_branch,
-            &worktree_path,
-            data.base_branch.as_deref(),
-        )?;
+            // Determine the resolved base branch name first
+            let resolved_base_branch = if let Some(ref base_branch) = data.base_branch {
+                base_branch.clone()
+            } else {
+                // Default to current HEAD branch name or "main"
+                git_service.get_default_branch_name()?
+            };
+
+            // Create the worktree using GitService
+            let created = match benchmark {
+                Some(benchmark) => git_service.create_worktree_at_commit(
+                    &task_attempt_branch,
+                    &worktree_path,
+                    &benchmark.base_commit,
+                ),
+                None => git_service.create_worktree(
+                    &task_attempt_branch,
+                    &worktree_path,
+                    data.base_branch.as_deref(),
+                ),
+            };
+            if let Err(e) = created {
+                // The directory is unique to this attempt, so whatever is there was left by the
+                // failed creation
+                if worktree_path.exists() {
+                    Self::remove_failed_worktree(&worktree_path, &project.git_repo_path).await;
+                }
+                return Err(e.into());
+            }
+
+            (task_attempt_branch, resolved_base_branch, Some(git_service))
+        };
 
         // Insert the record into the database
-        Ok(sqlx::query_as!(
+        let inserted = sqlx::query_as!(
             TaskAttempt,
-            r#"INSERT INTO task_attempts (id, task_id, worktree_path, branch, base_branch, merge_commit, executor, pr_url, pr_number, pr_status, pr_merged_at, worktree_deleted, setup_completed_at)
-               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-               RETURNING id as "id!: Uuid", task_id as "task_id!: Uuid", worktree_path, branch, base_branch, merge_commit, executor, pr_url, pr_number, pr_status, pr_merged_at as "pr_merged_at: DateTime<Utc>", worktree_deleted as "worktree_deleted!: bool", setup_completed_at as "setup_completed_at: DateTime<Utc>", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            r#"INSERT INTO task_attempts (id, task_id, worktree_path, branch, base_branch, merge_commit, executor, pr_url, pr_number, pr_status, pr_merged_at, worktree_deleted, setup_completed_at, env, benchmark_run_id, retry_of, retry_count)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+               RETURNING id as "id!: Uuid", task_id as "task_id!: Uuid", worktree_path, branch, base_branch, merge_commit, executor, pr_url, pr_number, pr_status, pr_merged_at as "pr_merged_at: DateTime<Utc>", worktree_deleted as "worktree_deleted!: bool", worktree_deleted_at as "worktree_deleted_at: DateTime<Utc>", setup_completed_at as "setup_completed_at: DateTime<Utc>", stop_note, env as "env: Json<HashMap<String, String>>", benchmark_run_id as "benchmark_run_id: Uuid", retry_of as "retry_of: Uuid", retry_count as "retry_count!: i64", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
             attempt_id,
             task_id,
             worktree_path_str,
@@ -494,10 +1438,97 @@ impl TaskAttempt {
             Option::<String>::None, // pr_status is None during creation
             Option::<DateTime<Utc>>::None, // pr_merged_at is None during creation
             false, // worktree_deleted is false during creation
-            Option::<DateTime<Utc>>::None // setup_completed_at is None during creation
+            Option::<DateTime<Utc>>::None, // setup_completed_at is None during creation
+            env,
+            benchmark.map(|benchmark| benchmark.run_id),
+            data.retry_of,
+            retry_count
         )
         .fetch_one(pool)
-        .await?)
+        .await;
+
+        match inserted {
+            Ok(attempt) => {
+                webhooks::emit(pool, WebhookEventType::AttemptCreated, attempt.id, None).await;
+                Ok(attempt)
+            }
+            Err(e) => {
+                Self::remove_failed_worktree(&worktree_path, &project.git_repo_path).await;
+                if let Some(git_service) = git_service {
+                    if let Err(e) = git_service.delete_branch(&task_attempt_branch) {
+                        tracing::warn!(
+                            "Failed to delete branch {} of failed attempt: {}",
+                            task_attempt_branch,
+                            e
+                        );
+                    }
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Fails with `GitUnavailable` when `project` is a scratch project
+    fn require_git(project: &Project, feature: &'static str) -> Result<(), TaskAttemptError> {
+        if project.is_scratch() {
+            return Err(TaskAttemptError::GitUnavailable(feature));
+        }
+        Ok(())
+    }
+
+    /// Copy a scratch project's directory to the attempt's working directory, so the executor
+    /// can't touch the original and cleaning up the attempt can't delete it
+    async fn copy_scratch_project(
+        project: &Project,
+        worktree_path: &Path,
+    ) -> Result<(), TaskAttemptError> {
+        let source = PathBuf::from(&project.git_repo_path);
+        let destination = worktree_path.to_path_buf();
+        // The worktree directory may live inside the project; don't copy it into itself
+        let skip = vec![destination.clone(), Self::get_worktree_base_dir()];
+        let copied = tokio::task::spawn_blocking(move || {
+            WorktreeManager::copy_directory(&source, &destination, &skip)
+        })
+        .await
+        .map_err(|e| GitServiceError::IoError(std::io::Error::other(e.to_string())))?;
+
+        if let Err(e) = copied {
+            if worktree_path.exists() {
+                Self::remove_failed_worktree(worktree_path, &project.git_repo_path).await;
+            }
+            return Err(GitServiceError::from(e).into());
+        }
+        Ok(())
+    }
+
+    async fn remove_failed_worktree(worktree_path: &Path, git_repo_path: &str) {
+        if let Err(e) = WorktreeManager::cleanup_worktree(worktree_path, Some(git_repo_path)).await
+        {
+            tracing::warn!(
+                "Failed to clean up worktree {} of failed attempt: {}",
+                worktree_path.display(),
+                e
+            );
+        }
+    }
+
+    /// The attempt of the project whose recorded merge commit is `sha`
+    pub async fn find_id_by_merge_commit(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        sha: &str,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT ta.id as "id!: Uuid" FROM task_attempts ta
+               JOIN tasks t ON ta.task_id = t.id
+               WHERE t.project_id = $1 AND ta.merge_commit = $2
+               ORDER BY ta.updated_at DESC
+               LIMIT 1"#,
+            project_id,
+            sha
+        )
+        .fetch_optional(pool)
+        .await
     }
 
     pub async fn exists_for_task(
@@ -524,13 +1555,14 @@ impl TaskAttempt {
         worktree_path: &str,
         main_repo_path: &str,
         branch_name: &str,
-        task_title: &str,
+        base_branch: &str,
+        message: &str,
     ) -> Result<String, TaskAttemptError> {
         let git_service = GitService::new(main_repo_path)?;
         let worktree_path = Path::new(worktree_path);
 
         git_service
-            .merge_changes(worktree_path, branch_name, task_title)
+            .merge_changes(worktree_path, branch_name, base_branch, message)
             .map_err(TaskAttemptError::from)
     }
 
@@ -548,26 +1580,44 @@ impl TaskAttempt {
             .map_err(TaskAttemptError::from)
     }
 
-    /// Merge the worktree changes back to the main repository
+    /// Merge the worktree changes back to the main repository. A merge commit is stamped with
+    /// the attempt's provenance according to `provenance`.
     pub async fn merge_changes(
         pool: &SqlitePool,
         attempt_id: Uuid,
         task_id: Uuid,
         project_id: Uuid,
+        provenance: ProvenanceMode,
     ) -> Result<String, TaskAttemptError> {
         // Load context with full validation
         let ctx = TaskAttempt::load_context(pool, attempt_id, task_id, project_id).await?;
+        Self::require_git(&ctx.project, "Merging")?;
 
         // Ensure worktree exists (recreate if needed for cold task support)
         let worktree_path =
             Self::ensure_worktree_exists(pool, attempt_id, project_id, "merge").await?;
 
+        // Taken before merging, while the merge base still separates the attempt's changes
+        let merged_revision = GitService::new(&ctx.project.git_repo_path)
+            .and_then(|git| {
+                git.diff_revision(Path::new(&worktree_path), &ctx.task_attempt.base_branch)
+            })
+            .ok();
+
+        let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt_id).await?;
+        let message = Provenance::new(&ctx.task, &ctx.task_attempt, &processes)
+            .stamp_commit_message(
+                &format!("Merge: {} (codecommand)", ctx.task.title),
+                provenance,
+            );
+
         // Perform the actual merge operation
         let merge_commit_id = Self::perform_merge_operation(
             &worktree_path,
             &ctx.project.git_repo_path,
             &ctx.task_attempt.branch,
-            &ctx.task.title,
+            &ctx.task_attempt.base_branch,
+            &message,
         )?;
 
         // Update the task attempt with the merge commit
@@ -578,6 +1628,16 @@ impl TaskAttempt {
         )
         .execute(pool)
         .await?;
+        webhooks::emit(pool, WebhookEventType::AttemptMerged, attempt_id, None).await;
+
+        if let Some(revision) = merged_revision {
+            glossary::learn_from_merge(
+                pool.clone(),
+                project_id,
+                ctx.project.git_repo_path.clone(),
+                revision,
+            );
+        }
 
         Ok(merge_commit_id)
     }
@@ -613,14 +1673,23 @@ impl TaskAttempt {
         task_id: Uuid,
         project_id: Uuid,
         prompt: &str,
+        include_previous_diff: bool,
     ) -> Result<Uuid, TaskAttemptError> {
         ProcessService::start_followup_execution(
-            pool, app_state, attempt_id, task_id, project_id, prompt,
+            pool,
+            app_state,
+            attempt_id,
+            task_id,
+            project_id,
+            prompt,
+            include_previous_diff,
         )
         .await
     }
 
-    /// Ensure worktree exists, recreating from branch if needed (cold task support)
+    /// Ensure worktree exists, recreating from branch if needed (cold task support).
+    /// Worktrees deleted by hand are not recreated implicitly and yield
+    /// `TaskAttemptError::WorktreeMissing`; see `recreate_worktree`.
     pub async fn ensure_worktree_exists(
         pool: &SqlitePool,
         attempt_id: Uuid,
@@ -632,24 +1701,77 @@ impl TaskAttempt {
             .ok_or(TaskAttemptError::TaskNotFound)?;
 
         // Return existing path if worktree still exists
-        if std::path::Path::new(&task_attempt.worktree_path).exists() {
+        if Path::new(&task_attempt.worktree_path).exists() {
             return Ok(task_attempt.worktree_path);
         }
 
+        if !task_attempt.worktree_deleted {
+            return Err(TaskAttemptError::WorktreeMissing(
+                task_attempt.worktree_path,
+            ));
+        }
+
         // Recreate worktree from branch
         info!(
             "Worktree {} no longer exists, recreating from branch {} for {}",
             task_attempt.worktree_path, task_attempt.branch, context
         );
 
+        Self::restore_worktree(pool, &task_attempt, project_id).await
+    }
+
+    /// `ensure_worktree_exists` for a follow-up, which also recreates a worktree deleted by
+    /// hand when `auto_recreate` (the `auto_recreate_missing_worktrees` setting) is on
+    pub async fn ensure_worktree_for_followup(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        project_id: Uuid,
+        auto_recreate: bool,
+    ) -> Result<String, TaskAttemptError> {
+        match Self::ensure_worktree_exists(pool, attempt_id, project_id, "followup").await {
+            Err(TaskAttemptError::WorktreeMissing(path)) if auto_recreate => {
+                info!(
+                    "Worktree {} for attempt {} was deleted manually, recreating for followup",
+                    path, attempt_id
+                );
+                Self::recreate_worktree(pool, attempt_id, project_id).await
+            }
+            result => result,
+        }
+    }
+
+    /// Recover an attempt whose worktree was deleted by hand: prune the stale git
+    /// registration and check the attempt branch out again at the stored path
+    pub async fn recreate_worktree(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        project_id: Uuid,
+    ) -> Result<String, TaskAttemptError> {
+        let task_attempt = TaskAttempt::find_by_id(pool, attempt_id)
+            .await?
+            .ok_or(TaskAttemptError::TaskNotFound)?;
+
+        info!(
+            "Recreating worktree {} from branch {} for attempt {}",
+            task_attempt.worktree_path, task_attempt.branch, attempt_id
+        );
+
+        Self::restore_worktree(pool, &task_attempt, project_id).await
+    }
+
+    async fn restore_worktree(
+        pool: &SqlitePool,
+        task_attempt: &TaskAttempt,
+        project_id: Uuid,
+    ) -> Result<String, TaskAttemptError> {
         let new_worktree_path =
-            Self::recreate_worktree_from_branch(pool, &task_attempt, project_id).await?;
+            Self::recreate_worktree_from_branch(pool, task_attempt, project_id).await?;
 
         // Update database with new path, reset worktree_deleted flag, and clear setup completion
         sqlx::query!(
-            "UPDATE task_attempts SET worktree_path = $1, worktree_deleted = FALSE, setup_completed_at = NULL, updated_at = datetime('now') WHERE id = $2",
+            "UPDATE task_attempts SET worktree_path = $1, worktree_deleted = FALSE, worktree_deleted_at = NULL, setup_completed_at = NULL, updated_at = datetime('now') WHERE id = $2",
             new_worktree_path,
-            attempt_id
+            task_attempt.id
         )
         .execute(pool)
         .await?;
@@ -666,6 +1788,7 @@ impl TaskAttempt {
         let project = Project::find_by_id(pool, project_id)
             .await?
             .ok_or(TaskAttemptError::ProjectNotFound)?;
+        Self::require_git(&project, "Recreating worktrees")?;
 
         // Create GitService instance
         let git_service = GitService::new(&project.git_repo_path)?;
@@ -681,18 +1804,25 @@ impl TaskAttempt {
         Ok(result_path.to_string_lossy().to_string())
     }
 
-    /// Get the git diff between the base commit and the current committed worktree state
+    /// Get the git diff between the base commit and the current committed worktree state.
+    /// `rename_threshold` is the similarity percentage used to detect renamed and copied files;
+    /// files matching `ignored_paths` are left out.
     pub async fn get_diff(
         pool: &SqlitePool,
         attempt_id: Uuid,
         task_id: Uuid,
         project_id: Uuid,
+        rename_threshold: u16,
+        ignored_paths: PathIgnore,
     ) -> Result<WorktreeDiff, TaskAttemptError> {
         // Load context with full validation
         let ctx = TaskAttempt::load_context(pool, attempt_id, task_id, project_id).await?;
+        Self::require_git(&ctx.project, "Diffs")?;
 
         // Create GitService instance
-        let git_service = GitService::new(&ctx.project.git_repo_path)?;
+        let git_service = GitService::new(&ctx.project.git_repo_path)?
+            .with_rename_threshold(rename_threshold)
+            .with_ignored_paths(ignored_paths);
 
         if let Some(merge_commit_id) = &ctx.task_attempt.merge_commit {
             // Task attempt has been merged - show the diff from the merge commit
@@ -719,6 +1849,42 @@ impl TaskAttempt {
         }
     }
 
+    /// The attempt's committed diff with the commits of its format runs left out, or only
+    /// what they changed. Uncommitted changes aren't part of either.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_formatting_diff(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        task_id: Uuid,
+        project_id: Uuid,
+        rename_threshold: u16,
+        ignored_paths: PathIgnore,
+        part: FormattingDiff,
+    ) -> Result<WorktreeDiff, TaskAttemptError> {
+        let ctx = TaskAttempt::load_context(pool, attempt_id, task_id, project_id).await?;
+        Self::require_git(&ctx.project, "Diffs")?;
+
+        let git_service = GitService::new(&ctx.project.git_repo_path)?
+            .with_rename_threshold(rename_threshold)
+            .with_ignored_paths(ignored_paths);
+
+        let revision = match &ctx.task_attempt.merge_commit {
+            Some(merge_commit_id) => git_service
+                .merged_revision(merge_commit_id, ctx.task_attempt.created_at.timestamp())?,
+            None => {
+                let worktree_path =
+                    Self::ensure_worktree_exists(pool, attempt_id, project_id, "diff").await?;
+                git_service
+                    .diff_revision(Path::new(&worktree_path), &ctx.task_attempt.base_branch)?
+            }
+        };
+        let format_commits = FormatRun::find_format_commits(pool, attempt_id).await?;
+
+        git_service
+            .get_formatting_diff(revision, &format_commits, part)
+            .map_err(TaskAttemptError::from)
+    }
+
     /// Get the branch status for this task attempt
     pub async fn get_branch_status(
         pool: &SqlitePool,
@@ -728,6 +1894,7 @@ impl TaskAttempt {
     ) -> Result<BranchStatus, TaskAttemptError> {
         // Load context with full validation
         let ctx = TaskAttempt::load_context(pool, attempt_id, task_id, project_id).await?;
+        Self::require_git(&ctx.project, "Branch status")?;
 
         use git2::{Status, StatusOptions};
 
@@ -785,6 +1952,8 @@ impl TaskAttempt {
             .iter()
             .any(|e| e.status() != Status::CURRENT);
 
+        let untracked_changes = UntrackedChangeJournal::change_count(pool, attempt_id).await?;
+
         // ── assemble & return ────────────────────────────────────────────────────────
         Ok(BranchStatus {
             is_behind: commits_behind > 0,
@@ -794,6 +1963,7 @@ impl TaskAttempt {
             merged: ctx.task_attempt.merge_commit.is_some(),
             has_uncommitted_changes,
             base_branch_name,
+            untracked_changes,
         })
     }
 
@@ -807,6 +1977,7 @@ impl TaskAttempt {
     ) -> Result<String, TaskAttemptError> {
         // Load context with full validation
         let ctx = TaskAttempt::load_context(pool, attempt_id, task_id, project_id).await?;
+        Self::require_git(&ctx.project, "Rebasing")?;
 
         // Use the stored base branch if no new base branch is provided
         let effective_base_branch =
@@ -837,6 +2008,7 @@ impl TaskAttempt {
     ) -> Result<String, TaskAttemptError> {
         // Load context with full validation
         let ctx = TaskAttempt::load_context(pool, attempt_id, task_id, project_id).await?;
+        Self::require_git(&ctx.project, "Deleting files")?;
 
         // Ensure worktree exists (recreate if needed for cold task support)
         let worktree_path_str =
@@ -861,6 +2033,7 @@ impl TaskAttempt {
         let ctx =
             TaskAttempt::load_context(pool, params.attempt_id, params.task_id, params.project_id)
                 .await?;
+        Self::require_git(&ctx.project, "Pull requests")?;
 
         // Ensure worktree exists (recreate if needed for cold task support)
         let worktree_path =
@@ -905,6 +2078,7 @@ impl TaskAttempt {
         )
         .execute(pool)
         .await?;
+        webhooks::emit(pool, WebhookEventType::PrOpened, params.attempt_id, None).await;
 
         Ok(pr_info.url)
     }
@@ -940,6 +2114,9 @@ impl TaskAttempt {
         )
         .execute(pool)
         .await?;
+        if status == "merged" {
+            webhooks::emit(pool, WebhookEventType::AttemptMerged, attempt_id, None).await;
+        }
 
         Ok(())
     }
@@ -956,6 +2133,7 @@ impl TaskAttempt {
 
         let has_setup_script = ctx
             .project
+            .with_repo_config()
             .setup_script
             .as_ref()
             .map(|script| !script.trim().is_empty())
@@ -1038,17 +2216,34 @@ impl TaskAttempt {
         };
 
         // Check if there are any changes (quick diff check)
-        let has_changes = match Self::get_diff(pool, attempt_id, task_id, project_id).await {
+        let has_changes = match Self::get_diff(
+            pool,
+            attempt_id,
+            task_id,
+            project_id,
+            DEFAULT_RENAME_THRESHOLD,
+            PathIgnore::default(),
+        )
+        .await
+        {
             Ok(diff) => !diff.files.is_empty(),
             Err(_) => false, // If diff fails, assume no changes
         };
 
+        let annotation_count = Annotation::count_by_attempt_id(pool, attempt_id).await?;
+        let policy_violation = NetworkContact::attempt_has_violation(pool, attempt_id).await?;
+
         Ok(TaskAttemptState {
             execution_state,
+            worktree_path: ctx.task_attempt.worktree_path,
             has_changes,
             has_setup_script,
             setup_process_id: setup_process.map(|p| p.id.to_string()),
             coding_agent_process_id: coding_agent_process.map(|p| p.id.to_string()),
+            annotation_count,
+            policy_violation,
+            // Runtime state, filled in by the route
+            paused_until: None,
         })
     }
 
@@ -1079,6 +2274,71 @@ impl TaskAttempt {
         Ok(())
     }
 
+    /// Record whether the attempt is waiting for a slot in its project
+    pub async fn set_queued(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        queued: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE task_attempts SET queued_at = CASE WHEN $1 THEN datetime('now', 'subsec') END WHERE id = $2",
+            queued,
+            attempt_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Attempts waiting for a slot in their project, oldest first, as
+    /// `(attempt_id, task_id, project_id)`
+    pub async fn find_queued(pool: &SqlitePool) -> Result<Vec<(Uuid, Uuid, Uuid)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"SELECT ta.id as "attempt_id!: Uuid", ta.task_id as "task_id!: Uuid", t.project_id as "project_id!: Uuid"
+               FROM task_attempts ta
+               JOIN tasks t ON ta.task_id = t.id
+               WHERE ta.queued_at IS NOT NULL
+               ORDER BY ta.queued_at ASC"#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| (r.attempt_id, r.task_id, r.project_id))
+            .collect())
+    }
+
+    /// Environment variables set for the attempt's processes
+    pub async fn find_env(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<HashMap<String, String>, sqlx::Error> {
+        let env = sqlx::query_scalar!(
+            r#"SELECT env as "env: Json<HashMap<String, String>>" FROM task_attempts WHERE id = $1"#,
+            attempt_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+        Ok(env.map(|Json(env)| env).unwrap_or_default())
+    }
+
+    /// Record the note the user gave when stopping the attempt, replacing any earlier one
+    pub async fn set_stop_note(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        note: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE task_attempts SET stop_note = $1, updated_at = datetime('now') WHERE id = $2",
+            note,
+            attempt_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
     /// Get execution history from current attempt only (simplified)
     pub async fn get_attempt_execution_history(
         pool: &SqlitePool,
@@ -1130,6 +2390,10 @@ impl TaskAttempt {
         let project = Project::find_by_id(pool, project_id)
             .await?
             .ok_or(TaskAttemptError::ProjectNotFound)?;
+        // Scratch projects have nothing to diff against
+        if project.is_scratch() {
+            return Ok(String::new());
+        }
 
         // Open the main repository
         let repo = Repository::open(&project.git_repo_path)?;
@@ -1187,3 +2451,582 @@ impl TaskAttempt {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        models::config::DEFAULT_WORKTREE_NAME_PATTERN,
+        test_utils::{insert_project, insert_task, memory_pool},
+    };
+
+    fn sample_diff() -> WorktreeDiff {
+        WorktreeDiff {
+            files: vec![FileDiff {
+                path: "src/lib.rs".to_string(),
+                old_path: None,
+                new_path: None,
+                chunks: vec![
+                    DiffChunk {
+                        chunk_type: DiffChunkType::Equal,
+                        content: "fn unchanged() {}\n".to_string(),
+                    },
+                    DiffChunk {
+                        chunk_type: DiffChunkType::Delete,
+                        content: "fn old() {}\n".to_string(),
+                    },
+                    DiffChunk {
+                        chunk_type: DiffChunkType::Insert,
+                        content: "fn new() {}\n".to_string(),
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_followup_diff_context_includes_diff_summary() {
+        let context =
+            followup_diff_context(&sample_diff(), FOLLOWUP_DIFF_SUMMARY_MAX_CHARS).unwrap();
+
+        assert!(context.contains("--- src/lib.rs"));
+        assert!(context.contains("-fn old() {}"));
+        assert!(context.contains("+fn new() {}"));
+        assert!(!context.contains("fn unchanged"));
+        assert!(context.ends_with("```"));
+
+        let empty = WorktreeDiff { files: vec![] };
+        assert_eq!(
+            followup_diff_context(&empty, FOLLOWUP_DIFF_SUMMARY_MAX_CHARS),
+            None
+        );
+    }
+
+    #[test]
+    fn test_followup_diff_summary_is_capped() {
+        let mut diff = sample_diff();
+        diff.files[0].chunks.push(DiffChunk {
+            chunk_type: DiffChunkType::Insert,
+            content: "é".repeat(1_000),
+        });
+
+        let summary = diff.summary(100);
+        assert!(summary.len() <= 100 + "\n... (diff truncated)\n".len());
+        assert!(summary.ends_with("(diff truncated)\n"));
+    }
+
+    /// A task in a project backed by a fresh repository with one commit
+    async fn setup_task() -> (SqlitePool, TempDir, Uuid, Uuid) {
+        let pool = memory_pool().await;
+
+        let repo_dir = TempDir::new().unwrap();
+        let repo = Repository::init(repo_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+
+        let project_id = insert_project(&pool, &repo_dir.path().to_string_lossy()).await;
+        let task_id = insert_task(&pool, project_id, "Worktree task").await;
+
+        (pool, repo_dir, project_id, task_id)
+    }
+
+    #[tokio::test]
+    async fn test_execution_state_includes_existing_worktree_path() {
+        let (pool, _repo_dir, project_id, task_id) = setup_task().await;
+
+        let attempt = TaskAttempt::create(
+            &pool,
+            &CreateTaskAttempt {
+                executor: None,
+                base_branch: None,
+                env: HashMap::new(),
+                retry_of: None,
+            },
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await
+        .unwrap();
+
+        let state = TaskAttempt::get_execution_state(&pool, attempt.id, task_id, project_id)
+            .await
+            .unwrap();
+        let worktree_exists = Path::new(&state.worktree_path).is_dir();
+        let _ = std::fs::remove_dir_all(&state.worktree_path);
+
+        assert_eq!(state.worktree_path, attempt.worktree_path);
+        assert!(Path::new(&state.worktree_path).is_absolute());
+        assert!(worktree_exists);
+    }
+
+    /// An attempt with a commit of its own on its branch, whose worktree is then deleted by hand
+    async fn attempt_with_deleted_worktree(pool: &SqlitePool, task_id: Uuid) -> TaskAttempt {
+        let attempt = TaskAttempt::create(
+            pool,
+            &CreateTaskAttempt {
+                executor: None,
+                base_branch: None,
+                env: HashMap::new(),
+                retry_of: None,
+            },
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await
+        .unwrap();
+
+        let worktree = Repository::open(&attempt.worktree_path).unwrap();
+        std::fs::write(Path::new(&attempt.worktree_path).join("notes.txt"), "kept").unwrap();
+        let mut index = worktree.index().unwrap();
+        index.add_path(Path::new("notes.txt")).unwrap();
+        index.write().unwrap();
+        let tree = worktree.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = worktree.head().unwrap().peel_to_commit().unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        worktree
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Add notes",
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+
+        std::fs::remove_dir_all(&attempt.worktree_path).unwrap();
+        attempt
+    }
+
+    #[tokio::test]
+    async fn test_deleted_worktree_is_reported_and_recreated_on_its_branch() {
+        let (pool, _repo_dir, project_id, task_id) = setup_task().await;
+        let attempt = attempt_with_deleted_worktree(&pool, task_id).await;
+
+        let missing =
+            TaskAttempt::ensure_worktree_exists(&pool, attempt.id, project_id, "test").await;
+        let recreated = TaskAttempt::recreate_worktree(&pool, attempt.id, project_id).await;
+        let notes = std::fs::read_to_string(Path::new(&attempt.worktree_path).join("notes.txt"));
+        let head = Repository::open(&attempt.worktree_path)
+            .ok()
+            .and_then(|repo| repo.head().ok()?.shorthand().map(str::to_string));
+        let stored = TaskAttempt::find_by_id(&pool, attempt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&attempt.worktree_path);
+
+        assert!(
+            matches!(missing, Err(TaskAttemptError::WorktreeMissing(path)) if path == attempt.worktree_path)
+        );
+        assert_eq!(recreated.unwrap(), attempt.worktree_path);
+        assert_eq!(notes.unwrap(), "kept");
+        assert_eq!(head.as_deref(), Some(attempt.branch.as_str()));
+        assert_eq!(stored.worktree_path, attempt.worktree_path);
+        assert!(!stored.worktree_deleted);
+    }
+
+    #[tokio::test]
+    async fn test_followups_recreate_missing_worktrees_only_when_enabled() {
+        let (pool, _repo_dir, project_id, task_id) = setup_task().await;
+        let attempt = attempt_with_deleted_worktree(&pool, task_id).await;
+
+        let refused =
+            TaskAttempt::ensure_worktree_for_followup(&pool, attempt.id, project_id, false).await;
+        let exists_after_refusal = Path::new(&attempt.worktree_path).exists();
+        let recreated =
+            TaskAttempt::ensure_worktree_for_followup(&pool, attempt.id, project_id, true).await;
+        let notes = std::fs::read_to_string(Path::new(&attempt.worktree_path).join("notes.txt"));
+        let _ = std::fs::remove_dir_all(&attempt.worktree_path);
+
+        assert!(matches!(refused, Err(TaskAttemptError::WorktreeMissing(_))));
+        assert!(!exists_after_refusal);
+        assert_eq!(recreated.unwrap(), attempt.worktree_path);
+        assert_eq!(notes.unwrap(), "kept");
+    }
+
+    #[tokio::test]
+    async fn test_retried_attempts_link_back_to_their_parent() {
+        let (pool, _repo_dir, _project_id, task_id) = setup_task().await;
+        let create = |retry_of| CreateTaskAttempt {
+            executor: None,
+            base_branch: None,
+            env: HashMap::new(),
+            retry_of,
+        };
+
+        let first =
+            TaskAttempt::create(&pool, &create(None), task_id, DEFAULT_WORKTREE_NAME_PATTERN)
+                .await
+                .unwrap();
+        let second = TaskAttempt::create(
+            &pool,
+            &create(Some(first.id)),
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await
+        .unwrap();
+        let third = TaskAttempt::create(
+            &pool,
+            &create(Some(second.id)),
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await
+        .unwrap();
+        let chain = TaskAttempt::find_retry_chain(&pool, second.id)
+            .await
+            .unwrap();
+        let unrelated = TaskAttempt::create(
+            &pool,
+            &create(Some(Uuid::new_v4())),
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await;
+        for attempt in [&first, &second, &third] {
+            let _ = std::fs::remove_dir_all(&attempt.worktree_path);
+        }
+
+        assert_eq!((first.retry_of, first.retry_count), (None, 0));
+        assert_eq!((second.retry_of, second.retry_count), (Some(first.id), 1));
+        assert_eq!((third.retry_of, third.retry_count), (Some(second.id), 2));
+        assert_eq!(
+            chain.iter().map(|attempt| attempt.id).collect::<Vec<_>>(),
+            vec![first.id, second.id, third.id]
+        );
+        assert!(matches!(
+            unrelated,
+            Err(TaskAttemptError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scratch_project_attempt_runs_in_a_copy_without_git() {
+        let (pool, _repo_dir, _project_id, _task_id) = setup_task().await;
+        let scratch_dir = TempDir::new().unwrap();
+        std::fs::create_dir(scratch_dir.path().join("notes")).unwrap();
+        std::fs::write(scratch_dir.path().join("notes/idea.txt"), "try this").unwrap();
+
+        let project_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO projects (id, name, git_repo_path, project_type) VALUES ($1, 's', $2, 'scratch')",
+        )
+        .bind(project_id)
+        .bind(scratch_dir.path().to_string_lossy().to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+        let task_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO tasks (id, project_id, title) VALUES ($1, $2, 'Scratch task')")
+            .bind(task_id)
+            .bind(project_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let attempt = TaskAttempt::create(
+            &pool,
+            &CreateTaskAttempt {
+                executor: None,
+                base_branch: None,
+                env: HashMap::new(),
+                retry_of: None,
+            },
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await
+        .unwrap();
+        let copy = PathBuf::from(&attempt.worktree_path);
+        let copied = std::fs::read_to_string(copy.join("notes/idea.txt"));
+        let copy_has_git = copy.join(".git").exists();
+        let merge = TaskAttempt::merge_changes(
+            &pool,
+            attempt.id,
+            task_id,
+            project_id,
+            ProvenanceMode::Trailers,
+        )
+        .await;
+        let _ = std::fs::remove_dir_all(&copy);
+
+        assert_eq!(copied.unwrap(), "try this");
+        assert_ne!(copy, scratch_dir.path());
+        // No repository or worktree was created on either side
+        assert!(!copy_has_git);
+        assert!(!scratch_dir.path().join(".git").exists());
+        assert_eq!(attempt.branch, "");
+        assert_eq!(attempt.base_branch, "");
+        assert!(matches!(
+            merge,
+            Err(TaskAttemptError::GitUnavailable("Merging"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_executor_is_rejected_before_worktree_is_created() {
+        let (pool, repo_dir, _project_id, task_id) = setup_task().await;
+        let empty_path = TempDir::new().unwrap();
+        let lookup = ExecutableLookup::new(
+            Some(empty_path.path().as_os_str().to_os_string()),
+            false,
+            None,
+        );
+
+        let result = TaskAttempt::create_with_lookup(
+            &pool,
+            &CreateTaskAttempt {
+                executor: Some("claude".to_string()),
+                base_branch: None,
+                env: HashMap::new(),
+                retry_of: None,
+            },
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+            &lookup,
+            None,
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(TaskAttemptError::ExecutorUnavailable(ref reason)) if reason.contains("npx"))
+        );
+        let repo = Repository::open(repo_dir.path()).unwrap();
+        assert!(repo.worktrees().unwrap().is_empty());
+        assert_eq!(repo.branches(Some(BranchType::Local)).unwrap().count(), 1);
+        let attempts = TaskAttempt::find_by_task_id(&pool, task_id).await.unwrap();
+        assert!(attempts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_moved_worktree_is_relocated_and_found_again() {
+        let (pool, repo_dir, project_id, task_id) = setup_task().await;
+        let attempt = TaskAttempt::create(
+            &pool,
+            &CreateTaskAttempt {
+                executor: None,
+                base_branch: None,
+                env: HashMap::new(),
+                retry_of: None,
+            },
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await
+        .unwrap();
+        let original = PathBuf::from(&attempt.worktree_path);
+        let moved_dir = TaskAttempt::get_worktree_base_dir().join(format!("moved-{}", attempt.id));
+        let moved = moved_dir.join(original.file_name().unwrap());
+        std::fs::create_dir_all(&moved_dir).unwrap();
+        std::fs::rename(&original, &moved).unwrap();
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+
+        let relocation = TaskAttempt::relocate_worktree(&pool, &attempt, &repo_path, &moved)
+            .await
+            .unwrap();
+        let not_a_worktree =
+            TaskAttempt::relocate_worktree(&pool, &attempt, &repo_path, repo_dir.path()).await;
+
+        assert!(relocation.git_repaired);
+        assert_eq!(relocation.old_path, attempt.worktree_path);
+        let relocated = TaskAttempt::find_by_id(&pool, attempt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Path::new(&relocated.worktree_path), moved);
+        assert!(matches!(
+            not_a_worktree,
+            Err(TaskAttemptError::ValidationError(_))
+        ));
+
+        // Moved back by hand, so the recorded path is stale and the worktree has to be found
+        std::fs::rename(&moved, &original).unwrap();
+        let project = Project::find_by_id(&pool, project_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let report = TaskAttempt::repair_project_worktrees(&pool, &project)
+            .await
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&moved_dir);
+        let _ = std::fs::remove_dir_all(&original);
+
+        assert!(report.not_found.is_empty());
+        assert_eq!(report.repaired.len(), 1);
+        assert_eq!(Path::new(&report.repaired[0].old_path), moved);
+        assert_eq!(Path::new(&report.repaired[0].new_path), original);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_attempts_for_one_task_get_distinct_worktrees() {
+        let (pool, repo_dir, _project_id, task_id) = setup_task().await;
+        let create = |pool: SqlitePool| async move {
+            TaskAttempt::create(
+                &pool,
+                &CreateTaskAttempt {
+                    executor: None,
+                    base_branch: None,
+                    env: HashMap::new(),
+                    retry_of: None,
+                },
+                task_id,
+                "{task_title}-{attempt_id}",
+            )
+            .await
+        };
+
+        let (first, second) = tokio::join!(
+            tokio::spawn(create(pool.clone())),
+            tokio::spawn(create(pool.clone()))
+        );
+        let first = first.unwrap().unwrap();
+        let second = second.unwrap().unwrap();
+        let both_exist =
+            Path::new(&first.worktree_path).is_dir() && Path::new(&second.worktree_path).is_dir();
+        let _ = std::fs::remove_dir_all(&first.worktree_path);
+        let _ = std::fs::remove_dir_all(&second.worktree_path);
+
+        assert_ne!(first.worktree_path, second.worktree_path);
+        assert!(first
+            .worktree_path
+            .ends_with(&format!("worktree-t-{}", first.id.simple())));
+        assert!(both_exist);
+        let repo = Repository::open(repo_dir.path()).unwrap();
+        assert_eq!(repo.worktrees().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_across_projects_filters_and_pages() {
+        let (pool, _repo_dir, project_id, task_id) = setup_task().await;
+
+        let mut ids = Vec::new();
+        for (i, executor) in ["claude", "amp", "claude"].iter().enumerate() {
+            let id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO task_attempts (id, task_id, worktree_path, branch, executor, created_at)
+                 VALUES ($1, $2, '/tmp/wt', $3, $4, $5)",
+            )
+            .bind(id)
+            .bind(task_id)
+            .bind(format!("vk-000{}-task", i))
+            .bind(*executor)
+            .bind(format!("2025-07-0{} 12:00:00.000", i + 1))
+            .execute(&pool)
+            .await
+            .unwrap();
+            ids.push(id);
+        }
+        sqlx::query(
+            "INSERT INTO execution_processes (id, task_attempt_id, process_type, executor_type, status, command, working_directory)
+             VALUES ($1, $2, 'codingagent', 'claude', 'running', 'claude', '/tmp/wt')",
+        )
+        .bind(Uuid::new_v4())
+        .bind(ids[2])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let all = TaskAttemptListFilter::default();
+        let first = TaskAttempt::list_across_projects(&pool, &all, None, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            first.items.iter().map(|item| item.id).collect::<Vec<_>>(),
+            vec![ids[2], ids[1]]
+        );
+        assert_eq!(first.items[0].status, AttemptRunStatus::Running);
+        assert_eq!(first.items[0].project_id, project_id);
+        assert_eq!(first.items[0].task_title, "Worktree task");
+        assert_eq!(first.items[1].status, AttemptRunStatus::NotStarted);
+
+        let cursor = TaskAttemptListCursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second = TaskAttempt::list_across_projects(&pool, &all, Some(&cursor), 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            second.items.iter().map(|item| item.id).collect::<Vec<_>>(),
+            vec![ids[0]]
+        );
+        assert_eq!(second.next_cursor, None);
+
+        let running_claude = TaskAttemptListFilter {
+            project_id: Some(project_id),
+            status: Some(AttemptRunStatus::Running),
+            executor_type: Some("claude".to_string()),
+            ..Default::default()
+        };
+        let running = TaskAttempt::list_across_projects(&pool, &running_claude, None, 50)
+            .await
+            .unwrap();
+        assert_eq!(running.items.len(), 1);
+        assert_eq!(running.items[0].id, ids[2]);
+        assert!(TaskAttemptListCursor::decode("not a cursor").is_none());
+
+        // Attempts of July 2nd and later, before the 3rd
+        let july_2nd = TaskAttemptListFilter {
+            project_id: Some(project_id),
+            created_after: Some("2025-07-02T00:00:00Z".parse().unwrap()),
+            created_before: Some("2025-07-03T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let page = TaskAttempt::list_across_projects(&pool, &july_2nd, None, 50)
+            .await
+            .unwrap();
+        assert_eq!(
+            page.items.iter().map(|item| item.id).collect::<Vec<_>>(),
+            vec![ids[1]]
+        );
+        let since_july_2nd_noon = TaskAttemptListFilter {
+            created_after: Some("2025-07-02T12:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let first = TaskAttempt::list_across_projects(&pool, &since_july_2nd_noon, None, 1)
+            .await
+            .unwrap();
+        assert_eq!(first.items[0].id, ids[2]);
+        let cursor = TaskAttemptListCursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second =
+            TaskAttempt::list_across_projects(&pool, &since_july_2nd_noon, Some(&cursor), 1)
+                .await
+                .unwrap();
+        assert_eq!(second.items[0].id, ids[1]);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[test]
+    fn test_attempt_env_is_validated_and_secrets_are_redacted() {
+        let env = HashMap::from([
+            ("FEATURE_FLAG".to_string(), "on".to_string()),
+            ("STRIPE_API_KEY".to_string(), "sk_live_123".to_string()),
+        ]);
+        assert!(validate_attempt_env(&env).is_ok());
+        assert!(
+            validate_attempt_env(&HashMap::from([("A=B".to_string(), String::new())])).is_err()
+        );
+
+        let mut serializer = serde_json::Serializer::new(Vec::new());
+        serialize_redacted_env(&Some(Json(env)), &mut serializer).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&serializer.into_inner()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"FEATURE_FLAG": "on", "STRIPE_API_KEY": "[REDACTED]"})
+        );
+    }
+}
@@ -5,6 +5,7 @@ use ts_rs::TS;
 use uuid::Uuid;
 
 use super::task_attempt::TaskAttemptStatus;
+use crate::services::webhooks;
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
 #[ts(export)]
@@ -13,6 +14,7 @@ pub struct TaskAttemptActivity {
     pub execution_process_id: Uuid, // Foreign key to ExecutionProcess
     pub status: TaskAttemptStatus,
     pub note: Option<String>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -31,6 +33,7 @@ pub struct TaskAttemptActivityWithPrompt {
     pub execution_process_id: Uuid,
     pub status: TaskAttemptStatus,
     pub note: Option<String>,
+    #[serde(with = "crate::utils::rfc3339")]
     pub created_at: DateTime<Utc>,
     pub prompt: Option<String>, // From executor_session
 }
@@ -53,6 +56,7 @@ impl TaskAttemptActivity {
         .await
     }
 
+    /// Also queues the matching lifecycle webhook
     pub async fn create(
         pool: &SqlitePool,
         data: &CreateTaskAttemptActivity,
@@ -60,7 +64,7 @@ impl TaskAttemptActivity {
         status: TaskAttemptStatus,
     ) -> Result<Self, sqlx::Error> {
         let status_value = status as TaskAttemptStatus;
-        sqlx::query_as!(
+        let activity = sqlx::query_as!(
             TaskAttemptActivity,
             r#"INSERT INTO task_attempt_activities (id, execution_process_id, status, note) 
                VALUES ($1, $2, $3, $4) 
@@ -71,7 +75,10 @@ impl TaskAttemptActivity {
             data.note
         )
         .fetch_one(pool)
-        .await
+        .await?;
+
+        webhooks::emit_for_process(pool, activity.execution_process_id, &activity.status).await;
+        Ok(activity)
     }
 
     #[allow(dead_code)]
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::task::{Task, TaskStatus};
+
+/// `task_id` can't be started until `depends_on_task_id` is done
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct TaskDependency {
+    pub task_id: Uuid,
+    pub depends_on_task_id: Uuid,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateTaskDependency {
+    pub depends_on_task_id: Uuid,
+}
+
+#[derive(Debug)]
+pub enum TaskDependencyError {
+    /// The task the dependency would be on already depends on the task, directly or through
+    /// other tasks, or they're the same task
+    Cycle,
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for TaskDependencyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskDependencyError::Cycle => write!(
+                f,
+                "The task can't depend on a task that depends on it, directly or through other tasks"
+            ),
+            TaskDependencyError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TaskDependencyError {}
+
+impl From<sqlx::Error> for TaskDependencyError {
+    fn from(err: sqlx::Error) -> Self {
+        TaskDependencyError::Database(err)
+    }
+}
+
+impl TaskDependency {
+    pub async fn find_by_task_id(
+        pool: &SqlitePool,
+        task_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            TaskDependency,
+            r#"SELECT task_id            AS "task_id!: Uuid",
+                      depends_on_task_id AS "depends_on_task_id!: Uuid",
+                      created_at         AS "created_at!: DateTime<Utc>"
+               FROM task_dependencies
+               WHERE task_id = $1
+               ORDER BY created_at ASC"#,
+            task_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Make `task_id` wait for `depends_on_task_id`, unless that would close a cycle. Adding a
+    /// dependency that exists already returns it unchanged.
+    pub async fn create(
+        pool: &SqlitePool,
+        task_id: Uuid,
+        depends_on_task_id: Uuid,
+    ) -> Result<Self, TaskDependencyError> {
+        if task_id == depends_on_task_id {
+            return Err(TaskDependencyError::Cycle);
+        }
+
+        let mut tx = pool.begin().await?;
+        // A cycle closes when the new dependency already reaches back to the task
+        let closes_cycle = sqlx::query_scalar!(
+            r#"WITH RECURSIVE reachable(id) AS (
+                   SELECT depends_on_task_id FROM task_dependencies WHERE task_id = $1
+                   UNION
+                   SELECT d.depends_on_task_id
+                   FROM task_dependencies d
+                   JOIN reachable r ON d.task_id = r.id
+               )
+               SELECT EXISTS(SELECT 1 FROM reachable WHERE id = $2) AS "closes_cycle!: bool""#,
+            depends_on_task_id,
+            task_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if closes_cycle {
+            return Err(TaskDependencyError::Cycle);
+        }
+
+        sqlx::query!(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_task_id) VALUES ($1, $2)",
+            task_id,
+            depends_on_task_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        let dependency = sqlx::query_as!(
+            TaskDependency,
+            r#"SELECT task_id            AS "task_id!: Uuid",
+                      depends_on_task_id AS "depends_on_task_id!: Uuid",
+                      created_at         AS "created_at!: DateTime<Utc>"
+               FROM task_dependencies
+               WHERE task_id = $1 AND depends_on_task_id = $2"#,
+            task_id,
+            depends_on_task_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(dependency)
+    }
+
+    pub async fn delete(
+        pool: &SqlitePool,
+        task_id: Uuid,
+        depends_on_task_id: Uuid,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM task_dependencies WHERE task_id = $1 AND depends_on_task_id = $2",
+            task_id,
+            depends_on_task_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Tasks `task_id` depends on that aren't done yet, which keep it from being started
+    pub async fn find_blocking_tasks(
+        pool: &SqlitePool,
+        task_id: Uuid,
+    ) -> Result<Vec<Task>, sqlx::Error> {
+        sqlx::query_as!(
+            Task,
+            r#"SELECT t.id         AS "id!: Uuid",
+                      t.project_id AS "project_id!: Uuid",
+                      t.title,
+                      t.description,
+                      t.status     AS "status!: TaskStatus",
+                      t.estimate,
+                      t.created_at AS "created_at!: DateTime<Utc>",
+                      t.updated_at AS "updated_at!: DateTime<Utc>",
+                      t.version    AS "version!: i64"
+               FROM task_dependencies d
+               JOIN tasks t ON t.id = d.depends_on_task_id
+               WHERE d.task_id = $1 AND t.status != $2
+               ORDER BY t.created_at ASC"#,
+            task_id,
+            TaskStatus::Done as TaskStatus
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The unfinished dependencies of every blocked task in a project, by task
+    pub async fn find_blocked_by_for_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<HashMap<Uuid, Vec<Uuid>>, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"SELECT d.task_id            AS "task_id!: Uuid",
+                      d.depends_on_task_id AS "depends_on_task_id!: Uuid"
+               FROM task_dependencies d
+               JOIN tasks t ON t.id = d.task_id
+               JOIN tasks dependency ON dependency.id = d.depends_on_task_id
+               WHERE t.project_id = $1 AND dependency.status != $2
+               ORDER BY dependency.created_at ASC"#,
+            project_id,
+            TaskStatus::Done as TaskStatus
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut blocked_by: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for record in records {
+            blocked_by
+                .entry(record.task_id)
+                .or_default()
+                .push(record.depends_on_task_id);
+        }
+        Ok(blocked_by)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_project, insert_task, memory_pool};
+
+    #[tokio::test]
+    async fn test_dependencies_reject_cycles_block_until_done_and_go_with_their_tasks() {
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, "/tmp/p").await;
+        let a = insert_task(&pool, project_id, "a").await;
+        let b = insert_task(&pool, project_id, "b").await;
+        let c = insert_task(&pool, project_id, "c").await;
+
+        // c -> b -> a
+        TaskDependency::create(&pool, b, a).await.unwrap();
+        TaskDependency::create(&pool, c, b).await.unwrap();
+        TaskDependency::create(&pool, c, b).await.unwrap();
+        for (task_id, depends_on) in [(a, c), (a, b), (a, a)] {
+            assert!(matches!(
+                TaskDependency::create(&pool, task_id, depends_on).await,
+                Err(TaskDependencyError::Cycle)
+            ));
+        }
+
+        let blocking = TaskDependency::find_blocking_tasks(&pool, b).await.unwrap();
+        assert_eq!(blocking.iter().map(|t| t.id).collect::<Vec<_>>(), vec![a]);
+        sqlx::query("UPDATE tasks SET status = 'done' WHERE id = $1")
+            .bind(a)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(TaskDependency::find_blocking_tasks(&pool, b)
+            .await
+            .unwrap()
+            .is_empty());
+        let blocked_by = TaskDependency::find_blocked_by_for_project(&pool, project_id)
+            .await
+            .unwrap();
+        assert_eq!(blocked_by, HashMap::from([(c, vec![b])]));
+
+        // Deleting the task in the middle removes the dependencies on it and its own
+        Task::delete(&pool, b, project_id).await.unwrap();
+        assert!(TaskDependency::find_by_task_id(&pool, c)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(TaskDependency::find_by_task_id(&pool, b)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// What's known about a file without reading all of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    pub size: u64,
+    /// Modification time in milliseconds since the Unix epoch
+    pub modified_ms: i64,
+    /// Git blob hash, only for files small enough to hash cheaply
+    pub hash: Option<String>,
+}
+
+impl FileFingerprint {
+    /// Hashes decide when both files have one; otherwise a changed mtime counts
+    pub fn differs_from(&self, other: &FileFingerprint) -> bool {
+        if self.size != other.size {
+            return true;
+        }
+        match (&self.hash, &other.hash) {
+            (Some(before), Some(after)) => before != after,
+            _ => self.modified_ms != other.modified_ms,
+        }
+    }
+}
+
+/// The files git doesn't track in a worktree, by path relative to it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub files: BTreeMap<String, FileFingerprint>,
+    /// The walk stopped at the file limit and left the rest out
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum UntrackedChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// A file git doesn't track that changed since setup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct UntrackedChange {
+    pub path: String,
+    pub kind: UntrackedChangeKind,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct UntrackedChangeReport {
+    /// False when the journal was off when the attempt's agent first ran
+    pub recorded: bool,
+    #[serde(with = "crate::utils::rfc3339::option")]
+    pub baseline_taken_at: Option<DateTime<Utc>>,
+    /// When the latest execution was compared against the baseline
+    #[serde(with = "crate::utils::rfc3339::option")]
+    pub compared_at: Option<DateTime<Utc>>,
+    pub changes: Vec<UntrackedChange>,
+    /// Part of the worktree wasn't snapshotted, so changes there may be missing
+    pub truncated: bool,
+}
+
+/// Per-attempt snapshot of the files git doesn't track, and the changes found after the
+/// latest execution
+pub struct UntrackedChangeJournal;
+
+impl UntrackedChangeJournal {
+    pub async fn has_baseline(pool: &SqlitePool, attempt_id: Uuid) -> Result<bool, sqlx::Error> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM untracked_change_journals
+               WHERE task_attempt_id = $1"#,
+            attempt_id
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Keeps an existing baseline, so later executions are still compared to the state
+    /// after setup
+    pub async fn save_baseline(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        snapshot: &FileSnapshot,
+    ) -> Result<(), sqlx::Error> {
+        let baseline =
+            serde_json::to_string(snapshot).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        sqlx::query!(
+            r#"INSERT INTO untracked_change_journals (task_attempt_id, baseline, truncated)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (task_attempt_id) DO NOTHING"#,
+            attempt_id,
+            baseline,
+            snapshot.truncated
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_baseline(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<Option<FileSnapshot>, sqlx::Error> {
+        let baseline = sqlx::query_scalar!(
+            r#"SELECT baseline as "baseline!" FROM untracked_change_journals
+               WHERE task_attempt_id = $1"#,
+            attempt_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        baseline
+            .map(|baseline| serde_json::from_str(&baseline))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    }
+
+    pub async fn save_changes(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+        changes: &[UntrackedChange],
+        truncated: bool,
+    ) -> Result<(), sqlx::Error> {
+        let changes =
+            serde_json::to_string(changes).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        sqlx::query!(
+            r#"UPDATE untracked_change_journals
+               SET changes = $2, truncated = truncated OR $3, compared_at = datetime('now', 'subsec')
+               WHERE task_attempt_id = $1"#,
+            attempt_id,
+            changes,
+            truncated
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn report(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<UntrackedChangeReport, sqlx::Error> {
+        let record = sqlx::query!(
+            r#"SELECT changes,
+                      truncated as "truncated!: bool",
+                      baseline_taken_at as "baseline_taken_at!: DateTime<Utc>",
+                      compared_at as "compared_at: DateTime<Utc>"
+               FROM untracked_change_journals
+               WHERE task_attempt_id = $1"#,
+            attempt_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        let Some(record) = record else {
+            return Ok(UntrackedChangeReport {
+                recorded: false,
+                baseline_taken_at: None,
+                compared_at: None,
+                changes: Vec::new(),
+                truncated: false,
+            });
+        };
+        let changes = record
+            .changes
+            .map(|changes| serde_json::from_str(&changes))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+            .unwrap_or_default();
+        Ok(UntrackedChangeReport {
+            recorded: true,
+            baseline_taken_at: Some(record.baseline_taken_at),
+            compared_at: record.compared_at,
+            changes,
+            truncated: record.truncated,
+        })
+    }
+
+    /// Number of changes found after the latest execution; 0 without a journal
+    pub async fn change_count(pool: &SqlitePool, attempt_id: Uuid) -> Result<usize, sqlx::Error> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COALESCE(json_array_length(changes), 0) as "count!: i64"
+               FROM untracked_change_journals
+               WHERE task_attempt_id = $1"#,
+            attempt_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(count.unwrap_or(0) as usize)
+    }
+}
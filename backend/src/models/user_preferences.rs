@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+use super::{config::ThemeMode, task::TaskStatus};
+
+/// Preferences owner when nobody is signed in with GitHub
+pub const LOCAL_IDENTITY: &str = "local";
+
+const THEME_KEY: &str = "theme";
+const COLLAPSED_COLUMNS_KEY: &str = "collapsed_columns";
+const DEFAULT_TASK_SORT_KEY: &str = "default_task_sort";
+const DIFF_GRANULARITY_KEY: &str = "diff_granularity";
+const KNOWN_KEYS: [&str; 4] = [
+    THEME_KEY,
+    COLLAPSED_COLUMNS_KEY,
+    DEFAULT_TASK_SORT_KEY,
+    DIFF_GRANULARITY_KEY,
+];
+const MAX_CUSTOM_KEY_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSortOrder {
+    Newest,
+    Oldest,
+    Title,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffGranularity {
+    Line,
+    Word,
+}
+
+/// Personal UI settings. Unset fields fall back to the client's defaults.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct UserPreferences {
+    pub theme: Option<ThemeMode>,
+    /// Kanban columns the user has collapsed
+    pub collapsed_columns: Vec<TaskStatus>,
+    pub default_task_sort: Option<TaskSortOrder>,
+    pub diff_granularity: Option<DiffGranularity>,
+    /// Free-form preferences for UI state that has no typed key yet
+    #[ts(type = "Record<string, unknown>")]
+    pub custom: HashMap<String, Value>,
+}
+
+/// Partial update: omitted fields are left unchanged, and a `null` custom value removes it
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateUserPreferences {
+    pub theme: Option<ThemeMode>,
+    pub collapsed_columns: Option<Vec<TaskStatus>>,
+    pub default_task_sort: Option<TaskSortOrder>,
+    pub diff_granularity: Option<DiffGranularity>,
+    #[serde(default)]
+    #[ts(type = "Record<string, unknown>")]
+    pub custom: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub enum UserPreferencesError {
+    Database(sqlx::Error),
+    Serialization(serde_json::Error),
+    InvalidKey(String),
+}
+
+impl std::fmt::Display for UserPreferencesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserPreferencesError::Database(e) => write!(f, "Database error: {}", e),
+            UserPreferencesError::Serialization(e) => {
+                write!(f, "Failed to encode preference: {}", e)
+            }
+            UserPreferencesError::InvalidKey(key) => write!(
+                f,
+                "Invalid preference key '{}': custom keys must be 1-{} characters and not a built-in preference",
+                key, MAX_CUSTOM_KEY_LEN
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UserPreferencesError {}
+
+impl From<sqlx::Error> for UserPreferencesError {
+    fn from(err: sqlx::Error) -> Self {
+        UserPreferencesError::Database(err)
+    }
+}
+
+impl From<serde_json::Error> for UserPreferencesError {
+    fn from(err: serde_json::Error) -> Self {
+        UserPreferencesError::Serialization(err)
+    }
+}
+
+/// Decode a stored value, dropping it if it no longer matches the expected type
+fn decode<T: DeserializeOwned>(key: &str, value: Value) -> Option<T> {
+    match serde_json::from_value(value) {
+        Ok(decoded) => Some(decoded),
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable preference '{}': {}", key, e);
+            None
+        }
+    }
+}
+
+impl UserPreferences {
+    /// Identity preferences are stored under: the GitHub username, or `local`
+    pub fn identity_for(github_username: Option<&str>) -> String {
+        github_username
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(LOCAL_IDENTITY)
+            .to_string()
+    }
+
+    pub async fn load(pool: &SqlitePool, identity: &str) -> Result<Self, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"SELECT key as "key!", value as "value!" FROM user_preferences WHERE identity = $1"#,
+            identity
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut preferences = Self::default();
+        for row in rows {
+            let value: Value = match serde_json::from_str(&row.value) {
+                Ok(value) => value,
+                Err(e) => {
+                    tracing::warn!("Ignoring unreadable preference '{}': {}", row.key, e);
+                    continue;
+                }
+            };
+            match row.key.as_str() {
+                THEME_KEY => preferences.theme = decode(&row.key, value),
+                COLLAPSED_COLUMNS_KEY => {
+                    preferences.collapsed_columns = decode(&row.key, value).unwrap_or_default()
+                }
+                DEFAULT_TASK_SORT_KEY => preferences.default_task_sort = decode(&row.key, value),
+                DIFF_GRANULARITY_KEY => preferences.diff_granularity = decode(&row.key, value),
+                _ => {
+                    preferences.custom.insert(row.key, value);
+                }
+            }
+        }
+
+        Ok(preferences)
+    }
+
+    /// Compatibility shim for the theme that used to live in `Config`: used until the user
+    /// picks a theme through preferences
+    pub fn with_legacy_theme(mut self, config_theme: &ThemeMode) -> Self {
+        if self.theme.is_none() {
+            self.theme = Some(config_theme.clone());
+        }
+        self
+    }
+
+    pub async fn update(
+        pool: &SqlitePool,
+        identity: &str,
+        data: UpdateUserPreferences,
+    ) -> Result<Self, UserPreferencesError> {
+        if let Some(key) = data.custom.keys().find(|key| {
+            key.is_empty() || key.len() > MAX_CUSTOM_KEY_LEN || KNOWN_KEYS.contains(&key.as_str())
+        }) {
+            return Err(UserPreferencesError::InvalidKey(key.clone()));
+        }
+
+        let mut changes: Vec<(String, Option<String>)> = Vec::new();
+        if let Some(theme) = &data.theme {
+            changes.push((THEME_KEY.to_string(), Some(serde_json::to_string(theme)?)));
+        }
+        if let Some(columns) = &data.collapsed_columns {
+            changes.push((
+                COLLAPSED_COLUMNS_KEY.to_string(),
+                Some(serde_json::to_string(columns)?),
+            ));
+        }
+        if let Some(sort) = &data.default_task_sort {
+            changes.push((
+                DEFAULT_TASK_SORT_KEY.to_string(),
+                Some(serde_json::to_string(sort)?),
+            ));
+        }
+        if let Some(granularity) = &data.diff_granularity {
+            changes.push((
+                DIFF_GRANULARITY_KEY.to_string(),
+                Some(serde_json::to_string(granularity)?),
+            ));
+        }
+        for (key, value) in data.custom {
+            let value = match value {
+                Value::Null => None,
+                value => Some(serde_json::to_string(&value)?),
+            };
+            changes.push((key, value));
+        }
+
+        let mut tx = pool.begin().await?;
+        for (key, value) in changes {
+            match value {
+                Some(value) => {
+                    sqlx::query!(
+                        r#"INSERT INTO user_preferences (identity, key, value)
+                           VALUES ($1, $2, $3)
+                           ON CONFLICT (identity, key) DO UPDATE
+                           SET value = excluded.value, updated_at = datetime('now', 'subsec')"#,
+                        identity,
+                        key,
+                        value
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None => {
+                    sqlx::query!(
+                        "DELETE FROM user_preferences WHERE identity = $1 AND key = $2",
+                        identity,
+                        key
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+
+        Ok(Self::load(pool, identity).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::memory_pool;
+
+    #[tokio::test]
+    async fn test_update_merges_and_scopes_by_identity() {
+        let pool = memory_pool().await;
+
+        let mut custom = HashMap::new();
+        custom.insert("sidebar_width".to_string(), serde_json::json!(320));
+        UserPreferences::update(
+            &pool,
+            "alice",
+            UpdateUserPreferences {
+                theme: Some(ThemeMode::Dark),
+                collapsed_columns: Some(vec![TaskStatus::Done]),
+                custom,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        // A later patch only touches what it names; null removes custom keys
+        let mut custom = HashMap::new();
+        custom.insert("sidebar_width".to_string(), Value::Null);
+        let prefs = UserPreferences::update(
+            &pool,
+            "alice",
+            UpdateUserPreferences {
+                diff_granularity: Some(DiffGranularity::Word),
+                custom,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(prefs.theme, Some(ThemeMode::Dark)));
+        assert_eq!(prefs.collapsed_columns, vec![TaskStatus::Done]);
+        assert_eq!(prefs.diff_granularity, Some(DiffGranularity::Word));
+        assert!(prefs.custom.is_empty());
+
+        let local = UserPreferences::load(&pool, LOCAL_IDENTITY).await.unwrap();
+        assert!(local.theme.is_none());
+        assert!(matches!(
+            local.with_legacy_theme(&ThemeMode::Purple).theme,
+            Some(ThemeMode::Purple)
+        ));
+
+        let mut custom = HashMap::new();
+        custom.insert(THEME_KEY.to_string(), serde_json::json!("dark"));
+        let result = UserPreferences::update(
+            &pool,
+            "alice",
+            UpdateUserPreferences {
+                custom,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(UserPreferencesError::InvalidKey(_))));
+    }
+}
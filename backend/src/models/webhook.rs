@@ -0,0 +1,483 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::task_attempt::TaskAttemptStatus;
+
+/// Attempt lifecycle events webhooks can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub enum WebhookEventType {
+    #[serde(rename = "attempt.created")]
+    AttemptCreated,
+    #[serde(rename = "attempt.setup_started")]
+    SetupStarted,
+    #[serde(rename = "attempt.setup_completed")]
+    SetupCompleted,
+    #[serde(rename = "attempt.setup_failed")]
+    SetupFailed,
+    #[serde(rename = "attempt.executor_started")]
+    ExecutorStarted,
+    #[serde(rename = "attempt.executor_completed")]
+    ExecutorCompleted,
+    #[serde(rename = "attempt.executor_failed")]
+    ExecutorFailed,
+    /// A user stopped the attempt's running processes
+    #[serde(rename = "attempt.stopped")]
+    AttemptStopped,
+    #[serde(rename = "attempt.pr_opened")]
+    PrOpened,
+    /// Merged locally, or its pull request was merged
+    #[serde(rename = "attempt.merged")]
+    AttemptMerged,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::AttemptCreated => "attempt.created",
+            WebhookEventType::SetupStarted => "attempt.setup_started",
+            WebhookEventType::SetupCompleted => "attempt.setup_completed",
+            WebhookEventType::SetupFailed => "attempt.setup_failed",
+            WebhookEventType::ExecutorStarted => "attempt.executor_started",
+            WebhookEventType::ExecutorCompleted => "attempt.executor_completed",
+            WebhookEventType::ExecutorFailed => "attempt.executor_failed",
+            WebhookEventType::AttemptStopped => "attempt.stopped",
+            WebhookEventType::PrOpened => "attempt.pr_opened",
+            WebhookEventType::AttemptMerged => "attempt.merged",
+        }
+    }
+
+    /// The event an attempt activity with `status` stands for
+    pub fn for_status(status: &TaskAttemptStatus) -> Self {
+        match status {
+            TaskAttemptStatus::SetupRunning => WebhookEventType::SetupStarted,
+            TaskAttemptStatus::SetupComplete => WebhookEventType::SetupCompleted,
+            TaskAttemptStatus::SetupFailed => WebhookEventType::SetupFailed,
+            TaskAttemptStatus::ExecutorRunning => WebhookEventType::ExecutorStarted,
+            TaskAttemptStatus::ExecutorComplete => WebhookEventType::ExecutorCompleted,
+            TaskAttemptStatus::ExecutorFailed => WebhookEventType::ExecutorFailed,
+        }
+    }
+}
+
+/// An outgoing webhook. Its secret is never sent back.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub url: String,
+    /// Events delivered to the endpoint; empty means all of them
+    pub events: Vec<WebhookEventType>,
+    pub enabled: bool,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookEndpoint {
+    pub fn subscribes_to(&self, event: WebhookEventType) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&event))
+    }
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateWebhookEndpoint {
+    pub url: String,
+    /// Key for the HMAC-SHA256 signature of each delivery
+    pub secret: String,
+    #[serde(default)]
+    pub events: Vec<WebhookEventType>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateWebhookEndpoint {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<WebhookEventType>>,
+    pub enabled: Option<bool>,
+}
+
+/// Where to deliver to an endpoint, with the secret to sign with
+#[derive(Debug, Clone)]
+pub struct WebhookTarget {
+    pub id: Uuid,
+    pub url: String,
+    pub encrypted_secret: String,
+}
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, TS)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum WebhookDeliveryStatus {
+    /// Waiting for its first try or a retry
+    Pending,
+    Delivered,
+    /// Ran out of attempts; only sent again when redelivered
+    Dead,
+}
+
+/// One event queued for one endpoint, and how delivering it went
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    /// Shared by the deliveries of one event to different endpoints, and kept on retries
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub task_attempt_id: Option<Uuid>,
+    /// The JSON body, exactly as signed
+    pub payload: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: u32,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_response_status: Option<u16>,
+    pub last_error: Option<String>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub delivered_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub updated_at: DateTime<Utc>,
+}
+
+struct WebhookEndpointRow {
+    id: Uuid,
+    url: String,
+    events: String,
+    enabled: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<WebhookEndpointRow> for WebhookEndpoint {
+    type Error = sqlx::Error;
+
+    fn try_from(row: WebhookEndpointRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            url: row.url,
+            events: serde_json::from_str(&row.events)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            enabled: row.enabled,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+fn encode_events(events: &[WebhookEventType]) -> Result<String, sqlx::Error> {
+    serde_json::to_string(events).map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+impl WebhookEndpoint {
+    pub async fn find_all(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookEndpointRow,
+            r#"SELECT id         AS "id!: Uuid",
+                      url,
+                      events,
+                      enabled    AS "enabled!: bool",
+                      created_at AS "created_at!: DateTime<Utc>",
+                      updated_at AS "updated_at!: DateTime<Utc>"
+               FROM webhook_endpoints
+               ORDER BY created_at ASC"#
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookEndpointRow,
+            r#"SELECT id         AS "id!: Uuid",
+                      url,
+                      events,
+                      enabled    AS "enabled!: bool",
+                      created_at AS "created_at!: DateTime<Utc>",
+                      updated_at AS "updated_at!: DateTime<Utc>"
+               FROM webhook_endpoints
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(Self::try_from)
+        .transpose()
+    }
+
+    pub async fn create(
+        pool: &SqlitePool,
+        url: &str,
+        encrypted_secret: &str,
+        events: &[WebhookEventType],
+        enabled: bool,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let events = encode_events(events)?;
+        sqlx::query_as!(
+            WebhookEndpointRow,
+            r#"INSERT INTO webhook_endpoints (id, url, secret, events, enabled)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING id         AS "id!: Uuid",
+                         url,
+                         events,
+                         enabled    AS "enabled!: bool",
+                         created_at AS "created_at!: DateTime<Utc>",
+                         updated_at AS "updated_at!: DateTime<Utc>""#,
+            id,
+            url,
+            encrypted_secret,
+            events,
+            enabled
+        )
+        .fetch_one(pool)
+        .await
+        .and_then(Self::try_from)
+    }
+
+    /// `encrypted_secret` replaces the secret when given
+    pub async fn update(
+        pool: &SqlitePool,
+        id: Uuid,
+        url: &str,
+        encrypted_secret: Option<&str>,
+        events: &[WebhookEventType],
+        enabled: bool,
+    ) -> Result<Self, sqlx::Error> {
+        let events = encode_events(events)?;
+        sqlx::query_as!(
+            WebhookEndpointRow,
+            r#"UPDATE webhook_endpoints
+               SET url = $2, secret = COALESCE($3, secret), events = $4, enabled = $5,
+                   updated_at = datetime('now', 'subsec')
+               WHERE id = $1
+               RETURNING id         AS "id!: Uuid",
+                         url,
+                         events,
+                         enabled    AS "enabled!: bool",
+                         created_at AS "created_at!: DateTime<Utc>",
+                         updated_at AS "updated_at!: DateTime<Utc>""#,
+            id,
+            url,
+            encrypted_secret,
+            events,
+            enabled
+        )
+        .fetch_one(pool)
+        .await
+        .and_then(Self::try_from)
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM webhook_endpoints WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_target(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<Option<WebhookTarget>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookTarget,
+            r#"SELECT id     AS "id!: Uuid",
+                      url,
+                      secret AS encrypted_secret
+               FROM webhook_endpoints
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+impl WebhookDelivery {
+    pub async fn enqueue(
+        pool: &SqlitePool,
+        endpoint_id: Uuid,
+        event_id: Uuid,
+        event_type: WebhookEventType,
+        task_attempt_id: Option<Uuid>,
+        payload: &str,
+    ) -> Result<(), sqlx::Error> {
+        let id = Uuid::new_v4();
+        let event_type = event_type.as_str();
+        sqlx::query!(
+            r#"INSERT INTO webhook_deliveries
+                   (id, endpoint_id, event_id, event_type, task_attempt_id, payload)
+               VALUES ($1, $2, $3, $4, $5, $6)"#,
+            id,
+            endpoint_id,
+            event_id,
+            event_type,
+            task_attempt_id,
+            payload
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The endpoint's most recent deliveries, newest first
+    pub async fn find_by_endpoint_id(
+        pool: &SqlitePool,
+        endpoint_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookDelivery,
+            r#"SELECT id                   AS "id!: Uuid",
+                      endpoint_id          AS "endpoint_id!: Uuid",
+                      event_id             AS "event_id!: Uuid",
+                      event_type,
+                      task_attempt_id      AS "task_attempt_id: Uuid",
+                      payload,
+                      status               AS "status!: WebhookDeliveryStatus",
+                      attempts             AS "attempts!: u32",
+                      next_attempt_at      AS "next_attempt_at!: DateTime<Utc>",
+                      last_response_status AS "last_response_status: u16",
+                      last_error,
+                      delivered_at         AS "delivered_at: DateTime<Utc>",
+                      created_at           AS "created_at!: DateTime<Utc>",
+                      updated_at           AS "updated_at!: DateTime<Utc>"
+               FROM webhook_deliveries
+               WHERE endpoint_id = $1
+               ORDER BY rowid DESC
+               LIMIT $2"#,
+            endpoint_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Pending deliveries whose time has come, oldest first. A delivery waits while an
+    /// earlier one for the same endpoint and attempt is still pending, so each endpoint sees
+    /// an attempt's events in order.
+    pub async fn find_due(pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookDelivery,
+            r#"SELECT d.id                   AS "id!: Uuid",
+                      d.endpoint_id          AS "endpoint_id!: Uuid",
+                      d.event_id             AS "event_id!: Uuid",
+                      d.event_type,
+                      d.task_attempt_id      AS "task_attempt_id: Uuid",
+                      d.payload,
+                      d.status               AS "status!: WebhookDeliveryStatus",
+                      d.attempts             AS "attempts!: u32",
+                      d.next_attempt_at      AS "next_attempt_at!: DateTime<Utc>",
+                      d.last_response_status AS "last_response_status: u16",
+                      d.last_error,
+                      d.delivered_at         AS "delivered_at: DateTime<Utc>",
+                      d.created_at           AS "created_at!: DateTime<Utc>",
+                      d.updated_at           AS "updated_at!: DateTime<Utc>"
+               FROM webhook_deliveries d
+               JOIN webhook_endpoints e ON e.id = d.endpoint_id
+               WHERE d.status = 'pending'
+                 AND e.enabled
+                 AND d.next_attempt_at <= datetime('now', 'subsec')
+                 AND NOT EXISTS (
+                     SELECT 1 FROM webhook_deliveries earlier
+                     WHERE earlier.endpoint_id = d.endpoint_id
+                       AND earlier.task_attempt_id IS d.task_attempt_id
+                       AND earlier.status = 'pending'
+                       AND earlier.rowid < d.rowid
+                 )
+               ORDER BY d.rowid ASC
+               LIMIT $1"#,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn mark_delivered(
+        pool: &SqlitePool,
+        id: Uuid,
+        response_status: u16,
+    ) -> Result<(), sqlx::Error> {
+        let delivered = WebhookDeliveryStatus::Delivered;
+        sqlx::query!(
+            r#"UPDATE webhook_deliveries
+               SET status = $2, attempts = attempts + 1, last_response_status = $3,
+                   last_error = NULL, delivered_at = datetime('now', 'subsec'),
+                   updated_at = datetime('now', 'subsec')
+               WHERE id = $1"#,
+            id,
+            delivered,
+            response_status
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a failed try. The delivery is retried after `retry_in`, or becomes a dead
+    /// letter without one.
+    pub async fn mark_failed(
+        pool: &SqlitePool,
+        id: Uuid,
+        response_status: Option<u16>,
+        error: &str,
+        retry_in: Option<std::time::Duration>,
+    ) -> Result<(), sqlx::Error> {
+        let status = if retry_in.is_some() {
+            WebhookDeliveryStatus::Pending
+        } else {
+            WebhookDeliveryStatus::Dead
+        };
+        let retry_modifier = format!("+{} seconds", retry_in.unwrap_or_default().as_secs());
+        sqlx::query!(
+            r#"UPDATE webhook_deliveries
+               SET status = $2, attempts = attempts + 1, last_response_status = $3,
+                   last_error = $4, next_attempt_at = datetime('now', 'subsec', $5),
+                   updated_at = datetime('now', 'subsec')
+               WHERE id = $1"#,
+            id,
+            status,
+            response_status,
+            error,
+            retry_modifier
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Queue a dead or delivered delivery to be sent again right away, with a fresh set of
+    /// attempts. Returns false when it's unknown or already pending.
+    pub async fn redeliver(
+        pool: &SqlitePool,
+        endpoint_id: Uuid,
+        id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let pending = WebhookDeliveryStatus::Pending;
+        let result = sqlx::query!(
+            r#"UPDATE webhook_deliveries
+               SET status = $3, attempts = 0, next_attempt_at = datetime('now', 'subsec'),
+                   updated_at = datetime('now', 'subsec')
+               WHERE endpoint_id = $1 AND id = $2 AND status != 'pending'"#,
+            endpoint_id,
+            id,
+            pending
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,15 +1,30 @@
 use axum::{
-    extract::{Request, State},
+    extract::{Query, Request, State},
+    http::{
+        header::{COOKIE, HOST, SET_COOKIE},
+        HeaderMap,
+    },
     middleware::Next,
-    response::{Json as ResponseJson, Response},
+    response::{IntoResponse, Json as ResponseJson, Redirect, Response},
     routing::{get, post},
     Json, Router,
 };
+use uuid::Uuid;
 
-use crate::{app_state::AppState, models::ApiResponse};
+use crate::{app_state::AppState, models::ApiResponse, utils::asset_dir};
+
+const DEFAULT_GITHUB_CLIENT_ID: &str = "Ov23liOdn9Ajg3Q5J5Xy";
+const OAUTH_STATE_COOKIE: &str = "codecommand_oauth_state";
+/// Where the browser lands after the web flow finishes
+const OAUTH_DONE_REDIRECT: &str = "/settings";
+/// File in the asset directory holding the OAuth app's client secret, when it isn't given in
+/// the `GITHUB_APP_CLIENT_SECRET` environment variable
+const GITHUB_CLIENT_SECRET_FILE: &str = "github_client_secret";
 
 pub fn auth_router() -> Router<AppState> {
     Router::new()
+        .route("/auth/github", get(github_authorize))
+        .route("/auth/github/callback", get(github_callback))
         .route("/auth/github/device/start", post(device_start))
         .route("/auth/github/device/poll", post(device_poll))
         .route("/auth/github/check", get(github_check_token))
@@ -32,12 +47,149 @@ struct DevicePollRequest {
     device_code: String,
 }
 
+#[derive(serde::Deserialize)]
+struct OAuthCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+fn github_client_id() -> &'static str {
+    option_env!("GITHUB_APP_CLIENT_ID").unwrap_or(DEFAULT_GITHUB_CLIENT_ID)
+}
+
+/// The OAuth app's client secret, read at runtime so it never ends up in the binary. Only the
+/// web flow needs it; the device flow works without one.
+fn github_client_secret() -> Option<String> {
+    std::env::var("GITHUB_APP_CLIENT_SECRET")
+        .ok()
+        .or_else(|| std::fs::read_to_string(asset_dir().join(GITHUB_CLIENT_SECRET_FILE)).ok())
+        .map(|secret| secret.trim().to_string())
+        .filter(|secret| !secret.is_empty())
+}
+
+/// The callback URL GitHub sends the browser back to, on whichever host served the request
+fn oauth_redirect_uri(headers: &HeaderMap) -> String {
+    let host = headers
+        .get(HOST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("localhost");
+    format!("http://{}/api/auth/github/callback", host)
+}
+
+fn oauth_state_cookie(value: &str, max_age_secs: u32) -> String {
+    format!(
+        "{}={}; HttpOnly; SameSite=Lax; Path=/api/auth/github; Max-Age={}",
+        OAUTH_STATE_COOKIE, value, max_age_secs
+    )
+}
+
+fn oauth_state_from_cookies(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == OAUTH_STATE_COOKIE)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Redirect back to the settings page, clearing the state cookie either way
+fn finish_oauth(error: Option<&str>) -> Response {
+    let target = match error {
+        Some(error) => format!(
+            "{}?github_error={}",
+            OAUTH_DONE_REDIRECT,
+            urlencoding::encode(error)
+        ),
+        None => OAUTH_DONE_REDIRECT.to_string(),
+    };
+    (
+        [(SET_COOKIE, oauth_state_cookie("", 0))],
+        Redirect::to(&target),
+    )
+        .into_response()
+}
+
+/// Fetch the login and primary email for a GitHub access token
+async fn fetch_github_identity(
+    client: &reqwest::Client,
+    access_token: &str,
+) -> Result<(Option<String>, Option<String>), String> {
+    let user_res = client
+        .get("https://api.github.com/user")
+        .bearer_auth(access_token)
+        .header("User-Agent", "codecommand-app")
+        .send()
+        .await;
+    let user_json: serde_json::Value = match user_res {
+        Ok(res) => res
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse GitHub user response: {e}"))?,
+        Err(e) => return Err(format!("Failed to fetch user info: {e}")),
+    };
+    let username = user_json
+        .get("login")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let emails_res = client
+        .get("https://api.github.com/user/emails")
+        .bearer_auth(access_token)
+        .header("User-Agent", "codecommand-app")
+        .send()
+        .await;
+    let emails_json: serde_json::Value = match emails_res {
+        Ok(res) => res
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse GitHub emails response: {e}"))?,
+        Err(e) => return Err(format!("Failed to fetch user emails: {e}")),
+    };
+    let primary_email = emails_json
+        .as_array()
+        .and_then(|arr| {
+            arr.iter()
+                .find(|email| {
+                    email
+                        .get("primary")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false)
+                })
+                .and_then(|email| email.get("email").and_then(|v| v.as_str()))
+        })
+        .map(|s| s.to_string());
+
+    Ok((username, primary_email))
+}
+
+/// Refresh Sentry and identify the user in PostHog after a successful login
+async fn identify_github_user(
+    app_state: &AppState,
+    username: Option<String>,
+    primary_email: Option<String>,
+) {
+    app_state.update_sentry_scope().await;
+    let mut props = serde_json::Map::new();
+    if let Some(username) = username {
+        props.insert("username".to_string(), serde_json::Value::String(username));
+    }
+    if let Some(email) = primary_email {
+        props.insert("email".to_string(), serde_json::Value::String(email));
+    }
+    app_state
+        .track_analytics_event("$identify", Some(serde_json::Value::Object(props)))
+        .await;
+}
+
 /// POST /auth/github/device/start
 async fn device_start() -> ResponseJson<ApiResponse<DeviceStartResponse>> {
-    // TODO: Replace with your own GitHub OAuth App Client ID
+    // Set GITHUB_APP_CLIENT_ID at build time to use your own GitHub OAuth App
     // Instructions: https://github.com/settings/developers -> OAuth Apps -> New OAuth App
     let params = [
-        ("client_id", "Ov23liOdn9Ajg3Q5J5Xy"), // Replace with your Client ID
+        ("client_id", github_client_id()),
         ("scope", "user:email,repo"),
     ];
     let client = reqwest::Client::new();
@@ -105,9 +257,8 @@ async fn device_poll(
     State(app_state): State<AppState>,
     Json(payload): Json<DevicePollRequest>,
 ) -> ResponseJson<ApiResponse<String>> {
-    // TODO: Replace with your own GitHub OAuth App Client ID (same as above)
     let params = [
-        ("client_id", "Ov23liOdn9Ajg3Q5J5Xy"), // Replace with your Client ID
+        ("client_id", github_client_id()),
         ("device_code", payload.device_code.as_str()),
         ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
     ];
@@ -148,81 +299,24 @@ async fn device_poll(
     }
     let access_token = json.get("access_token").and_then(|v| v.as_str());
     if let Some(access_token) = access_token {
-        // Fetch user info
-        let user_res = client
-            .get("https://api.github.com/user")
-            .bearer_auth(access_token)
-            .header("User-Agent", "codecommand-app")
-            .send()
-            .await;
-        let user_json: serde_json::Value = match user_res {
-            Ok(res) => match res.json().await {
-                Ok(json) => json,
-                Err(e) => {
-                    return ResponseJson(ApiResponse {
-                        success: false,
-                        data: None,
-                        message: Some(format!("Failed to parse GitHub user response: {e}")),
-                    });
-                }
-            },
-            Err(e) => {
-                return ResponseJson(ApiResponse {
-                    success: false,
-                    data: None,
-                    message: Some(format!("Failed to fetch user info: {e}")),
-                });
-            }
-        };
-        let username = user_json
-            .get("login")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        // Fetch user emails
-        let emails_res = client
-            .get("https://api.github.com/user/emails")
-            .bearer_auth(access_token)
-            .header("User-Agent", "codecommand-app")
-            .send()
-            .await;
-        let emails_json: serde_json::Value = match emails_res {
-            Ok(res) => match res.json().await {
-                Ok(json) => json,
-                Err(e) => {
-                    return ResponseJson(ApiResponse {
-                        success: false,
-                        data: None,
-                        message: Some(format!("Failed to parse GitHub emails response: {e}")),
-                    });
-                }
-            },
-            Err(e) => {
+        let (username, primary_email) = match fetch_github_identity(&client, access_token).await {
+            Ok(identity) => identity,
+            Err(message) => {
                 return ResponseJson(ApiResponse {
                     success: false,
                     data: None,
-                    message: Some(format!("Failed to fetch user emails: {e}")),
+                    message: Some(message),
                 });
             }
         };
-        let primary_email = emails_json
-            .as_array()
-            .and_then(|arr| {
-                arr.iter()
-                    .find(|email| {
-                        email
-                            .get("primary")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false)
-                    })
-                    .and_then(|email| email.get("email").and_then(|v| v.as_str()))
-            })
-            .map(|s| s.to_string());
         // Save to config
-        {
+        let (old_config, new_config) = {
             let mut config = app_state.get_config().write().await;
+            let old_config = config.clone();
             config.github.username = username.clone();
             config.github.primary_email = primary_email.clone();
             config.github.token = Some(access_token.to_string());
+            config.github.clear_access_token();
             let config_path = crate::utils::config_path();
             if config.save(&config_path).is_err() {
                 return ResponseJson(ApiResponse {
@@ -231,28 +325,12 @@ async fn device_poll(
                     message: Some("Failed to save config".to_string()),
                 });
             }
-        }
-        app_state.update_sentry_scope().await;
-        // Identify user in PostHog
-        let mut props = serde_json::Map::new();
-        if let Some(ref username) = username {
-            props.insert(
-                "username".to_string(),
-                serde_json::Value::String(username.clone()),
-            );
-        }
-        if let Some(ref email) = primary_email {
-            props.insert(
-                "email".to_string(),
-                serde_json::Value::String(email.clone()),
-            );
-        }
-        {
-            let props = serde_json::Value::Object(props);
-            app_state
-                .track_analytics_event("$identify", Some(props))
-                .await;
-        }
+            (old_config, config.clone())
+        };
+        app_state
+            .notify_config_changed(&old_config, &new_config)
+            .await;
+        identify_github_user(&app_state, username, primary_email).await;
 
         ResponseJson(ApiResponse {
             success: true,
@@ -268,10 +346,119 @@ async fn device_poll(
     }
 }
 
+/// GET /auth/github
+async fn github_authorize(headers: HeaderMap) -> Response {
+    let state = Uuid::new_v4().simple().to_string();
+    let url = format!(
+        "https://github.com/login/oauth/authorize?client_id={}&redirect_uri={}&scope={}&state={}",
+        urlencoding::encode(github_client_id()),
+        urlencoding::encode(&oauth_redirect_uri(&headers)),
+        urlencoding::encode("user:email repo"),
+        state
+    );
+    (
+        [(SET_COOKIE, oauth_state_cookie(&state, 600))],
+        Redirect::to(&url),
+    )
+        .into_response()
+}
+
+/// GET /auth/github/callback
+async fn github_callback(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<OAuthCallbackQuery>,
+) -> Response {
+    if let Some(error) = query.error.as_deref() {
+        return finish_oauth(Some(error));
+    }
+    let expected_state = oauth_state_from_cookies(&headers);
+    if expected_state.is_none() || query.state != expected_state {
+        tracing::warn!("GitHub OAuth callback state did not match the session cookie");
+        return finish_oauth(Some("state_mismatch"));
+    }
+    let Some(code) = query.code else {
+        return finish_oauth(Some("missing_code"));
+    };
+    let Some(client_secret) = github_client_secret() else {
+        tracing::error!(
+            "No GitHub client secret: set GITHUB_APP_CLIENT_SECRET or write it to {}",
+            asset_dir().join(GITHUB_CLIENT_SECRET_FILE).display()
+        );
+        return finish_oauth(Some("not_configured"));
+    };
+
+    let redirect_uri = oauth_redirect_uri(&headers);
+    let params = [
+        ("client_id", github_client_id()),
+        ("client_secret", client_secret.as_str()),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+    ];
+    let client = reqwest::Client::new();
+    let json: serde_json::Value = match client
+        .post("https://github.com/login/oauth/access_token")
+        .header("Accept", "application/json")
+        .form(&params)
+        .send()
+        .await
+    {
+        Ok(res) => match res.json().await {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::error!("Failed to parse GitHub token response: {}", e);
+                return finish_oauth(Some("token_exchange_failed"));
+            }
+        },
+        Err(e) => {
+            tracing::error!("Failed to contact GitHub: {}", e);
+            return finish_oauth(Some("token_exchange_failed"));
+        }
+    };
+    if let Some(error) = json.get("error").and_then(|v| v.as_str()) {
+        return finish_oauth(Some(error));
+    }
+    let Some(access_token) = json.get("access_token").and_then(|v| v.as_str()) else {
+        return finish_oauth(Some("token_exchange_failed"));
+    };
+
+    let (username, primary_email) = match fetch_github_identity(&client, access_token).await {
+        Ok(identity) => identity,
+        Err(message) => {
+            tracing::error!("{}", message);
+            return finish_oauth(Some("user_fetch_failed"));
+        }
+    };
+
+    let (old_config, new_config) = {
+        let mut config = app_state.get_config().write().await;
+        let old_config = config.clone();
+        if let Err(e) = config.github.set_access_token(access_token) {
+            tracing::error!("Failed to encrypt GitHub access token: {}", e);
+            return finish_oauth(Some("save_failed"));
+        }
+        config.github.username = username.clone();
+        config.github.primary_email = primary_email.clone();
+        // The web flow token replaces any device-flow token
+        config.github.token = None;
+        if let Err(e) = config.save(&crate::utils::config_path()) {
+            tracing::error!("Failed to save config: {}", e);
+            return finish_oauth(Some("save_failed"));
+        }
+        (old_config, config.clone())
+    };
+    app_state
+        .notify_config_changed(&old_config, &new_config)
+        .await;
+    identify_github_user(&app_state, username, primary_email).await;
+
+    finish_oauth(None)
+}
+
 /// GET /auth/github/check
 async fn github_check_token(State(app_state): State<AppState>) -> ResponseJson<ApiResponse<()>> {
     let config = app_state.get_config().read().await;
-    let token = config.github.token.clone();
+    let token = config.github.login_token();
     drop(config);
     if let Some(token) = token {
         let client = reqwest::Client::new();
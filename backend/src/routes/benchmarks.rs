@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        benchmark::{
+            BenchmarkAttempt, BenchmarkReport, BenchmarkRun, BenchmarkRunStatus,
+            BenchmarkTaskResult, CreateBenchmarkRun,
+        },
+        project::Project,
+        ApiResponse,
+    },
+    services::benchmark::{self, BenchmarkError},
+};
+
+pub fn benchmarks_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/projects/:id/benchmarks",
+            get(get_benchmark_runs).post(create_benchmark_run),
+        )
+        .route(
+            "/projects/:id/benchmarks/:run_id",
+            get(get_benchmark_report),
+        )
+        .route(
+            "/projects/:id/benchmarks/:run_id/cancel",
+            post(cancel_benchmark_run),
+        )
+        .route(
+            "/projects/:id/benchmarks/:run_id/tasks/:task_id",
+            get(get_benchmark_task_result),
+        )
+}
+
+async fn find_run(
+    app_state: &AppState,
+    project_id: Uuid,
+    run_id: Uuid,
+) -> Result<BenchmarkRun, StatusCode> {
+    match BenchmarkRun::find_by_id(&app_state.db_pool, project_id, run_id).await {
+        Ok(Some(run)) => Ok(run),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch benchmark run {}: {}", run_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn build_report(
+    app_state: &AppState,
+    run: BenchmarkRun,
+) -> Result<BenchmarkReport, StatusCode> {
+    let pool = &app_state.db_pool;
+    let attempts = BenchmarkAttempt::find_by_run_id(pool, run.id)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to fetch attempts of benchmark run {}: {}",
+                run.id,
+                e
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let task_titles = benchmark::task_titles(pool, &attempts).await.map_err(|e| {
+        tracing::error!("Failed to fetch tasks of benchmark run {}: {}", run.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(BenchmarkReport::new(run, attempts, &task_titles))
+}
+
+/// Benchmark runs of a project, newest first
+pub async fn get_benchmark_runs(
+    Path(project_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<BenchmarkRun>>>, StatusCode> {
+    match BenchmarkRun::find_by_project_id(&app_state.db_pool, project_id).await {
+        Ok(runs) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(runs),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch benchmark runs of project {}: {}",
+                project_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Replay merged tasks with each of the requested executors. The run continues in the
+/// background; its report fills in as attempts finish.
+pub async fn create_benchmark_run(
+    Path(project_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateBenchmarkRun>,
+) -> Result<ResponseJson<ApiResponse<BenchmarkRun>>, StatusCode> {
+    let project = match Project::find_by_id(&app_state.db_pool, project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    match benchmark::start_run(&app_state, &project, &payload).await {
+        Ok(run) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(run),
+            message: Some("Benchmark run started".to_string()),
+        })),
+        Err(BenchmarkError::Invalid(message)) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to start benchmark run: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Per-executor scores of a run and the results of every task
+pub async fn get_benchmark_report(
+    Path((project_id, run_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<BenchmarkReport>>, StatusCode> {
+    let run = find_run(&app_state, project_id, run_id).await?;
+    let report = build_report(&app_state, run).await?;
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: None,
+    }))
+}
+
+/// Results of every executor on one task of a run. Each result links the benchmark attempt,
+/// whose logs and diff are available like those of any other attempt.
+pub async fn get_benchmark_task_result(
+    Path((project_id, run_id, task_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<BenchmarkTaskResult>>, StatusCode> {
+    let run = find_run(&app_state, project_id, run_id).await?;
+    let report = build_report(&app_state, run).await?;
+    match report
+        .tasks
+        .into_iter()
+        .find(|task| task.task_id == task_id)
+    {
+        Some(task) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(task),
+            message: None,
+        })),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Stop a run: the attempt in progress is stopped and the remaining ones are skipped
+pub async fn cancel_benchmark_run(
+    Path((project_id, run_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    find_run(&app_state, project_id, run_id).await?;
+    match BenchmarkRun::finish(&app_state.db_pool, run_id, BenchmarkRunStatus::Cancelled).await {
+        Ok(true) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: None,
+            message: Some("Benchmark run cancelled".to_string()),
+        })),
+        Ok(false) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("The benchmark run has already finished".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to cancel benchmark run {}: {}", run_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
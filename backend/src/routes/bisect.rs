@@ -0,0 +1,182 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        bisect_run::{BisectRun, BisectRunStatus},
+        execution_process::{ExecutionProcess, ExecutionProcessStatus},
+        task_attempt::{TaskAttempt, TaskAttemptError},
+        ApiResponse,
+    },
+    services::{bisect, GitServiceError},
+};
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct StartBisect {
+    /// Command that fails on bad commits; the project's test script when not given
+    pub command: Option<String>,
+}
+
+pub fn bisect_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/task-attempts/:attempt_id/bisect",
+            get(get_bisect_runs).post(start_bisect),
+        )
+        .route(
+            "/task-attempts/:attempt_id/bisect/:run_id",
+            get(get_bisect_run),
+        )
+        .route(
+            "/task-attempts/:attempt_id/bisect/:run_id/cancel",
+            post(cancel_bisect_run),
+        )
+}
+
+async fn ensure_attempt_exists(app_state: &AppState, attempt_id: Uuid) -> Result<(), StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn find_run(
+    app_state: &AppState,
+    attempt_id: Uuid,
+    run_id: Uuid,
+) -> Result<BisectRun, StatusCode> {
+    match BisectRun::find_by_id(&app_state.db_pool, run_id).await {
+        Ok(Some(run)) if run.task_attempt_id == attempt_id => Ok(run),
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch bisect run {}: {}", run_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Bisect the attempt's commits, from its merge base to its head. Progress streams as the
+/// execution process sharing the run's id.
+pub async fn start_bisect(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<StartBisect>,
+) -> Result<ResponseJson<ApiResponse<BisectRun>>, StatusCode> {
+    ensure_attempt_exists(&app_state, attempt_id).await?;
+
+    match bisect::start(&app_state, attempt_id, payload.command.as_deref()).await {
+        Ok(run) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(run),
+            message: Some("Bisect started".to_string()),
+        })),
+        Err(
+            e @ (TaskAttemptError::ValidationError(_)
+            | TaskAttemptError::GitUnavailable(_)
+            | TaskAttemptError::BranchNotFound(_)
+            | TaskAttemptError::GitService(GitServiceError::BranchNotFound(_))),
+        ) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to start bisecting attempt {}: {}", attempt_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// The attempt's bisect runs, newest first
+pub async fn get_bisect_runs(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<BisectRun>>>, StatusCode> {
+    ensure_attempt_exists(&app_state, attempt_id).await?;
+
+    match BisectRun::find_by_task_attempt_id(&app_state.db_pool, attempt_id).await {
+        Ok(runs) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(runs),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch bisect runs of attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_bisect_run(
+    Path((attempt_id, run_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<BisectRun>>, StatusCode> {
+    let run = find_run(&app_state, attempt_id, run_id).await?;
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(run),
+        message: None,
+    }))
+}
+
+/// Stop a running bisect and remove its worktree
+pub async fn cancel_bisect_run(
+    Path((attempt_id, run_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<BisectRun>>, StatusCode> {
+    let run = find_run(&app_state, attempt_id, run_id).await?;
+    if run.status != BisectRunStatus::Running {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: Some(run),
+            message: Some("This bisect run has already ended".to_string()),
+        }));
+    }
+
+    match app_state.stop_running_execution_by_id(run.id).await {
+        Ok(true) => {
+            if let Err(e) = ExecutionProcess::update_completion(
+                &app_state.db_pool,
+                run.id,
+                ExecutionProcessStatus::Killed,
+                None,
+            )
+            .await
+            {
+                tracing::error!("Failed to mark bisect process {} as killed: {}", run.id, e);
+            }
+        }
+        Ok(false) => {}
+        Err(e) => {
+            tracing::error!("Failed to stop bisect run {}: {}", run.id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    bisect::finish_run(&app_state.db_pool, run.id).await;
+
+    let run = find_run(&app_state, attempt_id, run_id).await?;
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(run),
+        message: Some("Bisect cancelled".to_string()),
+    }))
+}
@@ -13,9 +13,15 @@ use ts_rs::TS;
 
 use crate::{
     app_state::AppState,
-    executor::ExecutorConfig,
+    executor::{ExecutorConfig, ExecutorConstants},
     models::{
-        config::{Config, EditorConstants, SoundConstants},
+        activity_heatmap::parse_timezone,
+        config::{
+            validate_enabled_executors, validate_executable_paths,
+            validate_executor_output_formats, validate_prompt_token_budgets,
+            validate_response_language, validate_worktree_name_pattern, AttemptStartHook, Config,
+            EditorConstants, SoundConstants, MAX_GRACEFUL_SHUTDOWN_SECS,
+        },
         ApiResponse,
     },
     utils,
@@ -34,30 +40,188 @@ async fn get_config(State(app_state): State<AppState>) -> ResponseJson<ApiRespon
     let config = app_state.get_config().read().await;
     ResponseJson(ApiResponse {
         success: true,
-        data: Some(config.clone()),
+        data: Some(config.redacted()),
         message: Some("Config retrieved successfully".to_string()),
     })
 }
 
 async fn update_config(
     State(app_state): State<AppState>,
-    Json(new_config): Json<Config>,
+    Json(mut new_config): Json<Config>,
 ) -> ResponseJson<ApiResponse<Config>> {
+    if let Err(message) = validate_response_language(new_config.response_language.as_deref()) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        });
+    }
+
+    if !(1..=100).contains(&new_config.diff_rename_threshold) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("Diff rename threshold must be between 1 and 100".to_string()),
+        });
+    }
+
+    if new_config.max_concurrent_dev_servers == Some(0) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("Maximum concurrent dev servers must be at least 1".to_string()),
+        });
+    }
+
+    if new_config.max_concurrent_per_project == Some(0) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("Maximum concurrent attempts per project must be at least 1".to_string()),
+        });
+    }
+
+    if new_config.graceful_shutdown_secs > MAX_GRACEFUL_SHUTDOWN_SECS {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(format!(
+                "Graceful shutdown can't take longer than {} seconds",
+                MAX_GRACEFUL_SHUTDOWN_SECS
+            )),
+        });
+    }
+
+    if new_config.focus_mode_minutes == 0 {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("Focus mode must last at least a minute".to_string()),
+        });
+    }
+
+    if let Some(url) = &new_config.public_url {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some("The public URL must start with http:// or https://".to_string()),
+            });
+        }
+    }
+
+    match &new_config.attempt_start_hook {
+        Some(AttemptStartHook::Webhook { url })
+            if !url.starts_with("http://") && !url.starts_with("https://") =>
+        {
+            return ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some("The start hook URL must start with http:// or https://".to_string()),
+            });
+        }
+        Some(AttemptStartHook::Script { command }) if command.trim().is_empty() => {
+            return ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some("The start hook script can't be empty".to_string()),
+            });
+        }
+        _ => {}
+    }
+
+    match new_config.custom_executor_command.as_deref() {
+        Some(command) if command.trim().is_empty() => {
+            return ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some("The custom executor command can't be empty".to_string()),
+            });
+        }
+        None if matches!(new_config.executor, ExecutorConfig::Custom) => {
+            return ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(
+                    "Set a custom executor command before making it the default executor"
+                        .to_string(),
+                ),
+            });
+        }
+        _ => {}
+    }
+
+    if parse_timezone(new_config.timezone.as_deref()).is_none() {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(format!(
+                "Unknown timezone '{}'. Use an IANA name such as 'Europe/Berlin'",
+                new_config.timezone.as_deref().unwrap_or_default()
+            )),
+        });
+    }
+
+    if let Err(message) = validate_worktree_name_pattern(&new_config.worktree_name_pattern) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        });
+    }
+
+    if let Err(message) = validate_enabled_executors(
+        new_config.enabled_executors.as_deref(),
+        &new_config.executor,
+    ) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        });
+    }
+
+    if let Err(message) = validate_prompt_token_budgets(&new_config.prompt_token_budgets) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        });
+    }
+
+    if let Err(message) = validate_executor_output_formats(&new_config.executor_output_formats) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        });
+    }
+
+    if let Err(message) = validate_executable_paths(&new_config.executable_paths) {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        });
+    }
+
+    new_config.restore_secrets(&*app_state.get_config().read().await);
+
     let config_path = utils::config_path();
 
     match new_config.save(&config_path) {
         Ok(_) => {
-            let mut config = app_state.get_config().write().await;
-            *config = new_config.clone();
-            drop(config);
-
+            let old_config = {
+                let mut config = app_state.get_config().write().await;
+                std::mem::replace(&mut *config, new_config.clone())
+            };
             app_state
-                .update_analytics_config(new_config.analytics_enabled.unwrap_or(true))
+                .notify_config_changed(&old_config, &new_config)
                 .await;
 
             ResponseJson(ApiResponse {
                 success: true,
-                data: Some(new_config),
+                data: Some(new_config.redacted()),
                 message: Some("Config updated successfully".to_string()),
             })
         }
@@ -74,12 +238,18 @@ async fn update_config(
 pub struct ConfigConstants {
     pub editor: EditorConstants,
     pub sound: SoundConstants,
+    /// Only the executors enabled on this server
+    pub executor: ExecutorConstants,
 }
 
-async fn get_config_constants() -> ResponseJson<ApiResponse<ConfigConstants>> {
+async fn get_config_constants(
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<ConfigConstants>> {
+    let executors = app_state.get_config().read().await.available_executors();
     let constants = ConfigConstants {
         editor: EditorConstants::new(),
         sound: SoundConstants::new(),
+        executor: ExecutorConstants::new(executors),
     };
 
     ResponseJson(ApiResponse {
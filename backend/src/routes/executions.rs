@@ -0,0 +1,486 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    executor::NormalizedConversation,
+    models::{
+        execution_process::ExecutionProcess,
+        normalized_log_snapshot::{NormalizedLogSnapshot, SnapshotVersion},
+        task_attempt::TaskAttemptStatus,
+        task_attempt_activity::{CreateTaskAttemptActivity, TaskAttemptActivity},
+        ApiResponse,
+    },
+    services::{
+        focus_mode::{self, FocusModeStatus},
+        log_filter::{FilteredConversation, LogFilter, LogFilterQuery},
+        log_normalization::{
+            current_normalization, diff_normalized_entries, renormalize, NormalizationError,
+            NormalizedLogDiff, RenormalizeResult,
+        },
+    },
+};
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct StopAllExecutionsRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct StopAllExecutionsResponse {
+    pub stopped_count: usize,
+}
+
+pub async fn stop_all_executions(
+    State(app_state): State<AppState>,
+    Json(payload): Json<StopAllExecutionsRequest>,
+) -> Result<ResponseJson<ApiResponse<StopAllExecutionsResponse>>, StatusCode> {
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("A reason is required to stop all executions".to_string()),
+        }));
+    }
+
+    let (stopped, errors) = app_state.stop_all_running_executions(reason).await;
+
+    // Create activity records for stopped processes (skip dev servers)
+    for process_id in &stopped {
+        let process = match ExecutionProcess::find_by_id(&app_state.db_pool, *process_id).await {
+            Ok(Some(process)) => process,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
+                continue;
+            }
+        };
+
+        if !process.process_type.is_attempt_stage() {
+            continue;
+        }
+
+        let create_activity = CreateTaskAttemptActivity {
+            execution_process_id: process.id,
+            status: Some(TaskAttemptStatus::ExecutorFailed),
+            note: Some(format!(
+                "Execution process {:?} ({}) stopped: {}",
+                process.process_type, process.id, reason
+            )),
+        };
+
+        if let Err(e) = TaskAttemptActivity::create(
+            &app_state.db_pool,
+            &create_activity,
+            Uuid::new_v4(),
+            TaskAttemptStatus::ExecutorFailed,
+        )
+        .await
+        {
+            tracing::error!("Failed to create stopped activity: {}", e);
+        }
+    }
+
+    let data = Some(StopAllExecutionsResponse {
+        stopped_count: stopped.len(),
+    });
+    if !errors.is_empty() {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data,
+            message: Some(format!(
+                "Stopped {} execution processes, but encountered errors: {}",
+                stopped.len(),
+                errors.join(", ")
+            )),
+        }));
+    }
+
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data,
+        message: Some(format!("Stopped {} execution processes", stopped.len())),
+    }))
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct StartFocusMode {
+    /// How long to keep agents paused; the configured `focus_mode_minutes` when not given
+    pub minutes: Option<u32>,
+}
+
+pub async fn get_focus_mode(
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<FocusModeStatus>> {
+    ResponseJson(ApiResponse {
+        success: true,
+        data: Some(app_state.focus_mode_status().await),
+        message: None,
+    })
+}
+
+/// Pause running agents, setup scripts and bisects without killing them. Calling it again
+/// while focus mode is on extends it.
+pub async fn start_focus_mode(
+    State(app_state): State<AppState>,
+    Json(payload): Json<StartFocusMode>,
+) -> ResponseJson<ApiResponse<FocusModeStatus>> {
+    let minutes = match payload.minutes {
+        Some(minutes) => minutes,
+        None => app_state.get_config().read().await.focus_mode_minutes,
+    };
+    if minutes == 0 {
+        return ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("Focus mode must last at least a minute".to_string()),
+        });
+    }
+
+    let status = focus_mode::start(&app_state, minutes).await;
+    let message = format!(
+        "Paused {} execution processes for {} minutes",
+        status.paused_process_ids.len(),
+        minutes
+    );
+    ResponseJson(ApiResponse {
+        success: true,
+        data: Some(status),
+        message: Some(message),
+    })
+}
+
+pub async fn end_focus_mode(
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<FocusModeStatus>> {
+    ResponseJson(ApiResponse {
+        success: true,
+        data: Some(focus_mode::end(&app_state).await),
+        message: Some("Focus mode ended".to_string()),
+    })
+}
+
+/// Re-run log normalization over stored output, either for one execution or for all
+/// finished executions (optionally of one executor type)
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct RenormalizeRequest {
+    pub execution_id: Option<Uuid>,
+    #[serde(default)]
+    pub all: bool,
+    pub executor: Option<String>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct RenormalizeResponse {
+    pub results: Vec<RenormalizeResult>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum NormalizedLogView {
+    #[default]
+    Current,
+    Shadow,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NormalizedLogQuery {
+    #[serde(default)]
+    version: NormalizedLogView,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct PromoteNormalizationRequest {
+    /// Without this the promotion is only previewed
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct PromotionReport {
+    pub promoted: bool,
+    pub diff: NormalizedLogDiff,
+    /// Sequence numbers of current entries with no counterpart in the shadow normalization.
+    /// Anything attached to these entries can't be carried over by sequence number.
+    pub unmapped_entries: Vec<usize>,
+}
+
+fn normalization_error_response<T>(
+    process_id: Uuid,
+    error: NormalizationError,
+) -> Result<ResponseJson<ApiResponse<T>>, StatusCode> {
+    match error {
+        NormalizationError::Database(e) => {
+            tracing::error!("Failed to normalize logs for {}: {}", process_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        e => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+    }
+}
+
+async fn find_process(
+    app_state: &AppState,
+    process_id: Uuid,
+) -> Result<ExecutionProcess, StatusCode> {
+    match ExecutionProcess::find_by_id(&app_state.db_pool, process_id).await {
+        Ok(Some(process)) => Ok(process),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Normalize one process into its shadow slot and diff it against what is shown today
+async fn renormalize_process(
+    app_state: &AppState,
+    process_id: Uuid,
+) -> Result<NormalizedLogDiff, NormalizationError> {
+    let process = ExecutionProcess::find_by_id(&app_state.db_pool, process_id)
+        .await?
+        .ok_or(NormalizationError::NoLogs)?;
+
+    renormalize(&app_state.db_pool, &process, SnapshotVersion::Shadow).await
+}
+
+pub async fn renormalize_executions(
+    State(app_state): State<AppState>,
+    Json(payload): Json<RenormalizeRequest>,
+) -> Result<ResponseJson<ApiResponse<RenormalizeResponse>>, StatusCode> {
+    let process_ids = match (payload.execution_id, payload.all) {
+        (Some(id), false) => vec![id],
+        (None, true) => {
+            match ExecutionProcess::find_finished_ids(
+                &app_state.db_pool,
+                payload.executor.as_deref(),
+            )
+            .await
+            {
+                Ok(ids) => ids,
+                Err(e) => {
+                    tracing::error!("Failed to list execution processes: {}", e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+        _ => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some("Specify either execution_id or all".to_string()),
+            }));
+        }
+    };
+
+    let mut results = Vec::with_capacity(process_ids.len());
+    for process_id in process_ids {
+        let outcome = renormalize_process(&app_state, process_id).await;
+        results.push(RenormalizeResult::new(process_id, outcome));
+    }
+
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        message: Some(format!(
+            "Re-normalized {} execution(s), {} failed",
+            results.len() - failed,
+            failed
+        )),
+        data: Some(RenormalizeResponse { results }),
+    }))
+}
+
+pub async fn get_normalized_logs(
+    Path(process_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Query(query): Query<NormalizedLogQuery>,
+    Query(filter): Query<LogFilterQuery>,
+) -> Result<ResponseJson<ApiResponse<FilteredConversation>>, StatusCode> {
+    let filter = match LogFilter::try_from(filter) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            }))
+        }
+    };
+    let process = find_process(&app_state, process_id).await?;
+
+    let conversation = match query.version {
+        NormalizedLogView::Current => {
+            match current_normalization(&app_state.db_pool, &process).await {
+                Ok(conversation) => Some(conversation),
+                Err(e) => return normalization_error_response(process_id, e),
+            }
+        }
+        NormalizedLogView::Shadow => {
+            match NormalizedLogSnapshot::find(
+                &app_state.db_pool,
+                process_id,
+                SnapshotVersion::Shadow,
+            )
+            .await
+            {
+                Ok(conversation) => conversation,
+                Err(e) => {
+                    return normalization_error_response(process_id, NormalizationError::from(e))
+                }
+            }
+        }
+    };
+
+    Ok(ResponseJson(match conversation {
+        Some(conversation) => ApiResponse {
+            success: true,
+            data: Some(filter.apply(conversation)),
+            message: None,
+        },
+        None => ApiResponse {
+            success: false,
+            data: None,
+            message: Some("No shadow normalization; re-normalize this execution first".to_string()),
+        },
+    }))
+}
+
+/// Current and shadow normalizations of a process, if a shadow one exists
+async fn load_current_and_shadow(
+    app_state: &AppState,
+    process: &ExecutionProcess,
+) -> Result<Option<(NormalizedConversation, NormalizedConversation)>, NormalizationError> {
+    let Some(shadow) =
+        NormalizedLogSnapshot::find(&app_state.db_pool, process.id, SnapshotVersion::Shadow)
+            .await?
+    else {
+        return Ok(None);
+    };
+    let current = current_normalization(&app_state.db_pool, process).await?;
+    Ok(Some((current, shadow)))
+}
+
+pub async fn get_normalized_logs_diff(
+    Path(process_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<NormalizedLogDiff>>, StatusCode> {
+    let process = find_process(&app_state, process_id).await?;
+
+    match load_current_and_shadow(&app_state, &process).await {
+        Ok(Some((current, shadow))) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(diff_normalized_entries(&current.entries, &shadow.entries)),
+            message: None,
+        })),
+        Ok(None) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("No shadow normalization; re-normalize this execution first".to_string()),
+        })),
+        Err(e) => normalization_error_response(process_id, e),
+    }
+}
+
+pub async fn promote_normalized_logs(
+    Path(process_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<PromoteNormalizationRequest>,
+) -> Result<ResponseJson<ApiResponse<PromotionReport>>, StatusCode> {
+    let process = find_process(&app_state, process_id).await?;
+
+    let (current, shadow) = match load_current_and_shadow(&app_state, &process).await {
+        Ok(Some(pair)) => pair,
+        Ok(None) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(
+                    "No shadow normalization; re-normalize this execution first".to_string(),
+                ),
+            }))
+        }
+        Err(e) => return normalization_error_response(process_id, e),
+    };
+
+    let diff = diff_normalized_entries(&current.entries, &shadow.entries);
+    let unmapped_entries = diff
+        .sequence_map
+        .iter()
+        .enumerate()
+        .filter_map(|(seq, mapped)| mapped.is_none().then_some(seq))
+        .collect();
+
+    let promoted = if payload.confirm {
+        match NormalizedLogSnapshot::promote_shadow(&app_state.db_pool, process_id).await {
+            Ok(promoted) => promoted,
+            Err(e) => {
+                tracing::error!("Failed to promote normalization for {}: {}", process_id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    } else {
+        false
+    };
+
+    let message = if promoted {
+        "Shadow normalization promoted to primary"
+    } else {
+        "Preview only; send confirm: true to promote"
+    };
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(PromotionReport {
+            promoted,
+            diff,
+            unmapped_entries,
+        }),
+        message: Some(message.to_string()),
+    }))
+}
+
+pub fn executions_router() -> Router<AppState> {
+    Router::new()
+        .route("/executions/stop-all", post(stop_all_executions))
+        .route(
+            "/executions/focus-mode",
+            get(get_focus_mode).post(start_focus_mode),
+        )
+        .route("/executions/focus-mode/end", post(end_focus_mode))
+        .route(
+            "/execution-processes/renormalize",
+            post(renormalize_executions),
+        )
+        .route(
+            "/execution-processes/:process_id/normalized",
+            get(get_normalized_logs),
+        )
+        .route(
+            "/execution-processes/:process_id/normalized/diff",
+            get(get_normalized_logs_diff),
+        )
+        .route(
+            "/execution-processes/:process_id/normalized/promote",
+            post(promote_normalized_logs),
+        )
+}
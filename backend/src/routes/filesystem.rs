@@ -4,12 +4,20 @@ use std::{
 };
 
 use axum::{
-    extract::Query, http::StatusCode, response::Json as ResponseJson, routing::get, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
+use uuid::Uuid;
 
-use crate::{app_state::AppState, models::ApiResponse};
+use crate::{
+    app_state::AppState,
+    models::{project::Project, ApiResponse},
+};
 
 #[derive(Debug, Serialize, TS)]
 #[ts(export)]
@@ -199,9 +207,384 @@ pub async fn create_git_repo(
     }
 }
 
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct MovePathRequest {
+    pub project_id: Uuid,
+    pub source: String,
+    pub destination: String,
+    pub overwrite: Option<bool>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct MovePathResponse {
+    pub success: bool,
+    pub git_staged: bool,
+}
+
+/// Resolve a path (relative to the repo root, or absolute) and make sure it stays inside the repo.
+/// The path is normalized lexically so it doesn't need to exist yet.
+fn resolve_repo_path(repo_root: &Path, path: &str) -> Result<PathBuf, String> {
+    let mut resolved = PathBuf::new();
+    for component in repo_root.join(path).components() {
+        match component {
+            std::path::Component::ParentDir => {
+                if !resolved.pop() {
+                    return Err(format!("Path '{}' is outside the project repository", path));
+                }
+            }
+            std::path::Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+
+    check_inside_repo(repo_root, &resolved, path)?;
+    Ok(resolved)
+}
+
+fn check_inside_repo(repo_root: &Path, resolved: &Path, path: &str) -> Result<(), String> {
+    if resolved == repo_root || !resolved.starts_with(repo_root) {
+        return Err(format!("Path '{}' is outside the project repository", path));
+    }
+
+    if resolved
+        .strip_prefix(repo_root)
+        .ok()
+        .and_then(|relative| relative.components().next())
+        .is_some_and(|first| first.as_os_str() == ".git")
+    {
+        return Err(format!("Path '{}' is inside the .git directory", path));
+    }
+
+    Ok(())
+}
+
+/// `resolve_repo_path`, also following symlinks: the closest existing directory above the path,
+/// and the path itself if it exists, must canonicalize to somewhere inside the repo. Otherwise a
+/// symlinked directory in the repo could be used to move or replace files outside it.
+/// `repo_root` must be canonical.
+fn resolve_repo_path_on_disk(repo_root: &Path, path: &str) -> Result<PathBuf, String> {
+    let resolved = resolve_repo_path(repo_root, path)?;
+
+    if let Some(parent) = resolved.ancestors().skip(1).find(|dir| dir.exists()) {
+        let parent =
+            fs::canonicalize(parent).map_err(|e| format!("Failed to resolve '{}': {}", path, e))?;
+        if !parent.starts_with(repo_root) {
+            return Err(format!("Path '{}' is outside the project repository", path));
+        }
+        if parent != repo_root {
+            check_inside_repo(repo_root, &parent, path)?;
+        }
+    }
+
+    // A dangling symlink doesn't canonicalize; moving the link itself is harmless
+    if let Ok(canonical) = fs::canonicalize(&resolved) {
+        check_inside_repo(repo_root, &canonical, path)?;
+    }
+
+    Ok(resolved)
+}
+
+/// Copy a file or directory tree, used when a rename crosses filesystems
+fn copy_recursively(source: &Path, destination: &Path) -> std::io::Result<()> {
+    if source.is_dir() {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(source, destination)?;
+    }
+    Ok(())
+}
+
+async fn move_on_disk(source: &Path, destination: &Path) -> std::io::Result<()> {
+    match tokio::fs::rename(source, destination).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let source = source.to_path_buf();
+            let destination = destination.to_path_buf();
+            tokio::task::spawn_blocking(move || {
+                copy_recursively(&source, &destination)?;
+                if source.is_dir() {
+                    fs::remove_dir_all(&source)
+                } else {
+                    fs::remove_file(&source)
+                }
+            })
+            .await
+            .map_err(std::io::Error::other)?
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether git tracks anything at `path` in the given repository
+async fn is_tracked_by_git(repo_root: &Path, path: &Path) -> bool {
+    tokio::process::Command::new("git")
+        .arg("ls-files")
+        .arg("--error-unmatch")
+        .arg("--")
+        .arg(path)
+        .current_dir(repo_root)
+        .output()
+        .await
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Move `source` to `destination` inside the repository at `repo_root` (canonical), staging the
+/// move when git tracked the source. Returns whether it was staged.
+async fn move_in_repo(
+    repo_root: &Path,
+    source: &str,
+    destination: &str,
+    overwrite: bool,
+) -> Result<bool, String> {
+    let source_path = resolve_repo_path_on_disk(repo_root, source)?;
+    let destination_path = resolve_repo_path_on_disk(repo_root, destination)?;
+
+    if fs::symlink_metadata(&source_path).is_err() {
+        return Err(format!("Source '{}' does not exist", source));
+    }
+
+    if source_path == destination_path || destination_path.starts_with(&source_path) {
+        return Err("Cannot move a path onto itself or into itself".to_string());
+    }
+
+    if let Ok(metadata) = fs::symlink_metadata(&destination_path) {
+        if !overwrite {
+            return Err(format!("Destination '{}' already exists", destination));
+        }
+
+        // A symlink is replaced, not whatever it points to
+        let removed = if metadata.is_dir() {
+            tokio::fs::remove_dir_all(&destination_path).await
+        } else {
+            tokio::fs::remove_file(&destination_path).await
+        };
+        if let Err(e) = removed {
+            tracing::error!("Failed to remove existing destination: {}", e);
+            return Err(format!("Failed to replace destination: {}", e));
+        }
+    }
+
+    if let Some(parent) = destination_path.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
+            tracing::error!("Failed to create destination directory: {}", e);
+            return Err(format!("Failed to create destination directory: {}", e));
+        }
+    }
+
+    // Only stage the move if git knew about the source, so untracked files stay untracked
+    let source_tracked = is_tracked_by_git(repo_root, &source_path).await;
+
+    if let Err(e) = move_on_disk(&source_path, &destination_path).await {
+        tracing::error!(
+            "Failed to move {:?} to {:?}: {}",
+            source_path,
+            destination_path,
+            e
+        );
+        return Err(format!("Failed to move: {}", e));
+    }
+
+    // The files are already moved, so stage both sides - this records the same rename `git mv` would
+    Ok(source_tracked
+        && match tokio::process::Command::new("git")
+            .args(["add", "-A", "--"])
+            .arg(&source_path)
+            .arg(&destination_path)
+            .current_dir(repo_root)
+            .output()
+            .await
+        {
+            Ok(output) if output.status.success() => true,
+            Ok(output) => {
+                tracing::warn!(
+                    "Failed to stage move in git: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Failed to run git add: {}", e);
+                false
+            }
+        })
+}
+
+pub async fn move_path(
+    State(app_state): State<AppState>,
+    Json(payload): Json<MovePathRequest>,
+) -> Result<ResponseJson<ApiResponse<MovePathResponse>>, StatusCode> {
+    let project = match Project::find_by_id(&app_state.db_pool, payload.project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", payload.project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let repo_root = match fs::canonicalize(&project.git_repo_path) {
+        Ok(path) => path,
+        Err(e) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(format!("Project repository is not accessible: {}", e)),
+            }));
+        }
+    };
+
+    match move_in_repo(
+        &repo_root,
+        &payload.source,
+        &payload.destination,
+        payload.overwrite.unwrap_or(false),
+    )
+    .await
+    {
+        Ok(git_staged) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(MovePathResponse {
+                success: true,
+                git_staged,
+            }),
+            message: Some(format!(
+                "Moved '{}' to '{}'",
+                payload.source, payload.destination
+            )),
+        })),
+        Err(message) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        })),
+    }
+}
+
 pub fn filesystem_router() -> Router<AppState> {
     Router::new()
         .route("/filesystem/list", get(list_directory))
         .route("/filesystem/validate-git", get(validate_git_path))
         .route("/filesystem/create-git", get(create_git_repo))
+        .route("/filesystem/move", post(move_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Repository;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// A git repository with `tracked.txt` committed, and its canonical root
+    fn repo_with_tracked_file() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let repo = Repository::init(&root).unwrap();
+        fs::write(root.join("tracked.txt"), "tracked").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("tracked.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+        (dir, root)
+    }
+
+    #[tokio::test]
+    async fn test_move_rejects_parent_dir_traversal() {
+        let (_dir, root) = repo_with_tracked_file();
+
+        for (source, destination) in [
+            ("../tracked.txt", "moved.txt"),
+            ("tracked.txt", "../moved.txt"),
+            ("tracked.txt", "sub/../../moved.txt"),
+            ("tracked.txt", ".git/moved.txt"),
+        ] {
+            assert!(move_in_repo(&root, source, destination, false)
+                .await
+                .is_err());
+        }
+        assert!(root.join("tracked.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_move_rejects_escaping_through_a_symlink() {
+        let (_dir, root) = repo_with_tracked_file();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("victim.txt"), "outside").unwrap();
+        fs::create_dir(outside.path().join("victim")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+
+        // Moving out of, into, and replacing things in the linked directory are all refused
+        let message = move_in_repo(&root, "link/victim.txt", "stolen.txt", false)
+            .await
+            .unwrap_err();
+        assert!(message.contains("outside the project repository"));
+        assert!(
+            move_in_repo(&root, "tracked.txt", "link/tracked.txt", false)
+                .await
+                .is_err()
+        );
+        assert!(move_in_repo(&root, "tracked.txt", "link/victim", true)
+            .await
+            .is_err());
+
+        assert!(outside.path().join("victim.txt").exists());
+        assert!(outside.path().join("victim").is_dir());
+        assert!(root.join("tracked.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_move_onto_an_existing_destination_needs_overwrite() {
+        let (_dir, root) = repo_with_tracked_file();
+        fs::write(root.join("existing.txt"), "existing").unwrap();
+
+        let message = move_in_repo(&root, "tracked.txt", "existing.txt", false)
+            .await
+            .unwrap_err();
+        assert!(message.contains("already exists"));
+        assert_eq!(
+            fs::read_to_string(root.join("existing.txt")).unwrap(),
+            "existing"
+        );
+
+        move_in_repo(&root, "tracked.txt", "existing.txt", true)
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("existing.txt")).unwrap(),
+            "tracked"
+        );
+        assert!(!root.join("tracked.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_move_stages_the_rename_of_tracked_files_only() {
+        let (_dir, root) = repo_with_tracked_file();
+        fs::write(root.join("untracked.txt"), "untracked").unwrap();
+
+        assert!(move_in_repo(&root, "tracked.txt", "sub/renamed.txt", false)
+            .await
+            .unwrap());
+        assert!(
+            !move_in_repo(&root, "untracked.txt", "sub/untracked.txt", false)
+                .await
+                .unwrap()
+        );
+
+        let mut index = Repository::open(&root).unwrap().index().unwrap();
+        index.read(true).unwrap();
+        assert!(index.get_path(Path::new("sub/renamed.txt"), 0).is_some());
+        assert!(index.get_path(Path::new("tracked.txt"), 0).is_none());
+        assert!(index.get_path(Path::new("sub/untracked.txt"), 0).is_none());
+    }
 }
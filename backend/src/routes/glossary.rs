@@ -0,0 +1,222 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        glossary::{CreateGlossaryEntry, GlossaryEntry, GlossaryEntryStatus, UpdateGlossaryEntry},
+        project::Project,
+        ApiResponse,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct GlossaryQuery {
+    /// Only entries with this status; all of them when omitted
+    pub status: Option<GlossaryEntryStatus>,
+}
+
+pub fn glossary_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/projects/:id/glossary",
+            get(get_glossary).post(create_glossary_entry),
+        )
+        .route(
+            "/projects/:id/glossary/:entry_id",
+            put(update_glossary_entry).delete(delete_glossary_entry),
+        )
+}
+
+fn error_response<T>(message: impl Into<String>) -> ResponseJson<ApiResponse<T>> {
+    ResponseJson(ApiResponse {
+        success: false,
+        data: None,
+        message: Some(message.into()),
+    })
+}
+
+async fn ensure_project_exists(app_state: &AppState, project_id: Uuid) -> Result<(), StatusCode> {
+    match Project::exists(&app_state.db_pool, project_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check project existence: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Whether another entry than `entry_id` already uses `term`
+async fn term_is_taken(
+    app_state: &AppState,
+    project_id: Uuid,
+    term: &str,
+    entry_id: Option<Uuid>,
+) -> Result<bool, StatusCode> {
+    match GlossaryEntry::find_by_term(&app_state.db_pool, project_id, term).await {
+        Ok(existing) => Ok(existing.is_some_and(|existing| Some(existing.id) != entry_id)),
+        Err(e) => {
+            tracing::error!("Failed to look up glossary term: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn clean_file_refs(file_refs: Vec<String>) -> Vec<String> {
+    file_refs
+        .into_iter()
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Entries of the project glossary, most referenced first
+pub async fn get_glossary(
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<GlossaryQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<GlossaryEntry>>>, StatusCode> {
+    ensure_project_exists(&app_state, project_id).await?;
+
+    match GlossaryEntry::find_by_project_id(&app_state.db_pool, project_id, query.status).await {
+        Ok(entries) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(entries),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to fetch glossary of project {}: {}", project_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn create_glossary_entry(
+    Path(project_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateGlossaryEntry>,
+) -> Result<ResponseJson<ApiResponse<GlossaryEntry>>, StatusCode> {
+    ensure_project_exists(&app_state, project_id).await?;
+
+    let payload = CreateGlossaryEntry {
+        term: payload.term.trim().to_string(),
+        definition: payload.definition.trim().to_string(),
+        file_refs: clean_file_refs(payload.file_refs),
+    };
+    if payload.term.is_empty() || payload.definition.is_empty() {
+        return Ok(error_response(
+            "A glossary entry needs a term and a definition",
+        ));
+    }
+    if term_is_taken(&app_state, project_id, &payload.term, None).await? {
+        return Ok(error_response(format!(
+            "The glossary already has an entry for '{}'",
+            payload.term
+        )));
+    }
+
+    match GlossaryEntry::create(&app_state.db_pool, project_id, &payload).await {
+        Ok(entry) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(entry),
+            message: Some("Glossary entry created".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to create glossary entry: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Edit an entry. Approving a suggestion is an update to `approved`, which needs a definition.
+pub async fn update_glossary_entry(
+    Path((project_id, entry_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<UpdateGlossaryEntry>,
+) -> Result<ResponseJson<ApiResponse<GlossaryEntry>>, StatusCode> {
+    let existing = match GlossaryEntry::find_by_id(&app_state.db_pool, project_id, entry_id).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch glossary entry {}: {}", entry_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let term = payload
+        .term
+        .map(|term| term.trim().to_string())
+        .unwrap_or(existing.term);
+    let definition = payload
+        .definition
+        .map(|definition| definition.trim().to_string())
+        .unwrap_or(existing.definition);
+    let file_refs = payload
+        .file_refs
+        .map(clean_file_refs)
+        .unwrap_or(existing.file_refs);
+    let status = payload.status.unwrap_or(existing.status);
+
+    if term.is_empty() {
+        return Ok(error_response("A glossary entry needs a term"));
+    }
+    if status == GlossaryEntryStatus::Approved && definition.is_empty() {
+        return Ok(error_response(
+            "Add a definition before approving a glossary entry",
+        ));
+    }
+    if term_is_taken(&app_state, project_id, &term, Some(entry_id)).await? {
+        return Ok(error_response(format!(
+            "The glossary already has an entry for '{}'",
+            term
+        )));
+    }
+
+    match GlossaryEntry::update(
+        &app_state.db_pool,
+        project_id,
+        entry_id,
+        &term,
+        &definition,
+        &file_refs,
+        status,
+    )
+    .await
+    {
+        Ok(entry) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(entry),
+            message: Some("Glossary entry updated".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to update glossary entry {}: {}", entry_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn delete_glossary_entry(
+    Path((project_id, entry_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    match GlossaryEntry::delete(&app_state.db_pool, project_id, entry_id).await {
+        Ok(0) => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: None,
+            message: Some("Glossary entry deleted".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to delete glossary entry {}: {}", entry_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -1,11 +1,39 @@
-use axum::response::Json;
+use axum::{extract::State, response::Json};
+use serde::Serialize;
+use ts_rs::TS;
 
-use crate::models::ApiResponse;
+use crate::{app_state::AppState, models::ApiResponse};
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct HealthStatus {
+    pub status: String,
+    pub version: String,
+    /// Newer release found by the last update check; see `/api/updates` for its notes
+    pub update_available: Option<String>,
+}
+
+pub async fn health_check(State(app_state): State<AppState>) -> Json<ApiResponse<HealthStatus>> {
+    #[cfg(feature = "self-update")]
+    let update_available = app_state
+        .updates
+        .status()
+        .await
+        .update
+        .map(|update| update.version);
+    #[cfg(not(feature = "self-update"))]
+    let update_available = {
+        let _ = app_state;
+        None
+    };
 
-pub async fn health_check() -> Json<ApiResponse<String>> {
     Json(ApiResponse {
         success: true,
-        data: Some("OK".to_string()),
+        data: Some(HealthStatus {
+            status: "OK".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            update_available,
+        }),
         message: Some("Service is healthy".to_string()),
     })
 }
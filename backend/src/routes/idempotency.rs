@@ -0,0 +1,399 @@
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json as ResponseJson, Response},
+};
+use git2::{ObjectType, Oid};
+use sqlx::SqlitePool;
+use tokio::time::Instant;
+
+use crate::{
+    app_state::AppState,
+    models::{idempotency_key::IdempotencyKey, ApiResponse},
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on responses that were replayed from an earlier request with the same key
+pub const IDEMPOTENT_REPLAY_HEADER: &str = "idempotent-replayed";
+
+const KEY_TTL_HOURS: i64 = 24;
+const MAX_KEY_LENGTH: usize = 255;
+const MAX_REQUEST_BODY_BYTES: usize = 16 * 1024 * 1024;
+/// Responses are stored to be replayed, so they're capped like request bodies
+const MAX_RESPONSE_BODY_BYTES: usize = 16 * 1024 * 1024;
+/// How long a retry waits for the first request with its key to finish
+const PENDING_WAIT: Duration = Duration::from_secs(30);
+const PENDING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (
+        status,
+        ResponseJson(ApiResponse::<()> {
+            success: false,
+            data: None,
+            message: Some(message.into()),
+        }),
+    )
+        .into_response()
+}
+
+/// Stable across restarts and builds, unlike `DefaultHasher`, as records outlive the process
+fn body_hash(body: &[u8]) -> Result<String, git2::Error> {
+    Oid::hash_object(ObjectType::Blob, body).map(|oid| oid.to_string())
+}
+
+fn replay(record: IdempotencyKey) -> Option<Response> {
+    let status = StatusCode::from_u16(record.status_code?).ok()?;
+    let mut response = Response::new(Body::from(record.response_body.unwrap_or_default()));
+    *response.status_mut() = status;
+    if let Some(content_type) = record
+        .content_type
+        .and_then(|content_type| HeaderValue::from_str(&content_type).ok())
+    {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, content_type);
+    }
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAY_HEADER, HeaderValue::from_static("true"));
+    Some(response)
+}
+
+/// Releases a claimed key unless the request's response was stored or the key released,
+/// including when the request is dropped midway because the client disconnected. Keys left
+/// pending by a server that stopped are released on the next start.
+struct Claim {
+    pool: SqlitePool,
+    key: String,
+    route: String,
+    settled: bool,
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let pool = self.pool.clone();
+        let key = std::mem::take(&mut self.key);
+        let route = std::mem::take(&mut self.route);
+        runtime.spawn(async move {
+            if let Err(e) = IdempotencyKey::release(&pool, &key, &route).await {
+                tracing::error!("Failed to release idempotency key: {}", e);
+            }
+        });
+    }
+}
+
+/// Makes mutating requests sent with an `Idempotency-Key` header run at most once: the first
+/// response is stored for the key and replayed to retries with the same body. Reusing a key
+/// with a different body is rejected with 422. Retries arriving while the first request is
+/// still running wait for its response.
+pub async fn idempotency_middleware(
+    State(app_state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mutating = matches!(
+        *req.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(key) if mutating => key.to_str().map(|key| key.trim().to_string()),
+        _ => return next.run(req).await,
+    };
+    let key = match key {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+        _ => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "{} must be 1 to {} visible ASCII characters",
+                    IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+                ),
+            )
+        }
+    };
+    let route = format!("{} {}", req.method(), req.uri().path());
+
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, MAX_REQUEST_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+    let body_hash = match body_hash(&body) {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::error!("Failed to hash request body: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let pool = &app_state.db_pool;
+    let deadline = Instant::now() + PENDING_WAIT;
+    loop {
+        match IdempotencyKey::claim(
+            pool,
+            &key,
+            &route,
+            &body_hash,
+            chrono::Duration::hours(KEY_TTL_HOURS),
+        )
+        .await
+        {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => {
+                tracing::error!("Failed to claim idempotency key: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+
+        match IdempotencyKey::find(pool, &key, &route).await {
+            Ok(Some(record)) if record.body_hash != body_hash => {
+                return error_response(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "This idempotency key was already used for a request with a different body",
+                );
+            }
+            Ok(Some(record)) => {
+                if let Some(response) = replay(record) {
+                    return response;
+                }
+                if Instant::now() >= deadline {
+                    return error_response(
+                        StatusCode::CONFLICT,
+                        "A request with this idempotency key is still being processed",
+                    );
+                }
+                tokio::time::sleep(PENDING_POLL_INTERVAL).await;
+            }
+            // Released or expired since the claim; try to claim it again
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("Failed to look up idempotency key: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    let mut claim = Claim {
+        pool: pool.clone(),
+        key: key.clone(),
+        route: route.clone(),
+        settled: false,
+    };
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_RESPONSE_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            // The claim is released on drop
+            tracing::error!("Failed to read response for idempotency key: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // Server errors may be transient, so let retries run the request again
+    let stored = if parts.status.is_server_error() {
+        IdempotencyKey::release(pool, &key, &route).await
+    } else {
+        let content_type = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        IdempotencyKey::complete(
+            pool,
+            &key,
+            &route,
+            parts.status.as_u16(),
+            content_type,
+            &body,
+        )
+        .await
+    };
+    match stored {
+        Ok(()) => claim.settled = true,
+        Err(e) => tracing::error!("Failed to store response for idempotency key: {}", e),
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use git2::Repository;
+    use sqlx::SqlitePool;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        models::{config::Config, execution_process::ExecutionProcess, task_attempt::TaskAttempt},
+        routes::task_attempts::task_attempts_router,
+        test_utils::{insert_project, insert_task, memory_pool},
+    };
+
+    /// A server with the attempt routes behind the middleware, and a task in a fresh repository
+    async fn serve() -> (SqlitePool, TempDir, SocketAddr, Uuid, Uuid) {
+        let pool = memory_pool().await;
+
+        let repo_dir = TempDir::new().unwrap();
+        let repo = Repository::init(repo_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+
+        let project_id = insert_project(&pool, &repo_dir.path().to_string_lossy()).await;
+        let task_id = insert_task(&pool, project_id, "Retry me").await;
+
+        let config = Config {
+            analytics_enabled: Some(false),
+            ..Config::default()
+        };
+        let app_state = AppState::new(pool.clone(), Arc::new(RwLock::new(config))).await;
+        let app = Router::new()
+            .merge(task_attempts_router())
+            .layer(from_fn_with_state(
+                app_state.clone(),
+                idempotency_middleware,
+            ))
+            .with_state(app_state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (pool, repo_dir, addr, project_id, task_id)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_retries_start_one_attempt() {
+        let (pool, _repo_dir, addr, project_id, task_id) = serve().await;
+        let url = format!(
+            "http://{}/projects/{}/tasks/{}/attempts",
+            addr, project_id, task_id
+        );
+        let client = reqwest::Client::new();
+        let send = |body: &'static str| {
+            client
+                .post(&url)
+                .header(IDEMPOTENCY_KEY_HEADER, "attempt-1")
+                .header("content-type", "application/json")
+                .body(body)
+                .send()
+        };
+
+        let body = r#"{"executor":"echo","base_branch":null}"#;
+        let responses = futures_util::future::join_all((0..5).map(|_| send(body))).await;
+        let mut bodies = Vec::new();
+        for response in responses {
+            let response = response.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            bodies.push(response.text().await.unwrap());
+        }
+        assert!(bodies.iter().all(|body| body == &bodies[0]));
+
+        let attempts = TaskAttempt::find_by_task_id(&pool, task_id).await.unwrap();
+        assert_eq!(attempts.len(), 1);
+
+        // Execution starts in the background after the response
+        let mut processes = Vec::new();
+        for _ in 0..50 {
+            processes = ExecutionProcess::find_by_task_attempt_id(&pool, attempts[0].id)
+                .await
+                .unwrap();
+            if !processes.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(processes.len(), 1);
+
+        let other_body = send(r#"{"executor":"echo","base_branch":"main"}"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            other_body.status(),
+            reqwest::StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let replayed = send(body).await.unwrap();
+        assert_eq!(
+            replayed.headers().get(IDEMPOTENT_REPLAY_HEADER).unwrap(),
+            "true"
+        );
+        assert_eq!(replayed.text().await.unwrap(), bodies[0]);
+        for attempt in attempts {
+            let _ = std::fs::remove_dir_all(&attempt.worktree_path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_requests_release_their_key() {
+        let pool = memory_pool().await;
+        let config = Config {
+            analytics_enabled: Some(false),
+            ..Config::default()
+        };
+        let app_state = AppState::new(pool.clone(), Arc::new(RwLock::new(config))).await;
+        let app = Router::new()
+            .route(
+                "/slow",
+                post(|| async { tokio::time::sleep(Duration::from_secs(60)).await }),
+            )
+            .layer(from_fn_with_state(
+                app_state.clone(),
+                idempotency_middleware,
+            ))
+            .with_state(app_state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // The client gives up, which drops the request while the handler runs
+        let sent = reqwest::Client::new()
+            .post(format!("http://{}/slow", addr))
+            .header(IDEMPOTENCY_KEY_HEADER, "slow-1")
+            .timeout(Duration::from_millis(300))
+            .send()
+            .await;
+        assert!(sent.is_err());
+
+        let mut released = false;
+        for _ in 0..50 {
+            if IdempotencyKey::find(&pool, "slow-1", "POST /slow")
+                .await
+                .unwrap()
+                .is_none()
+            {
+                released = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(released);
+    }
+}
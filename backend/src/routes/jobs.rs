@@ -0,0 +1,117 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use ts_rs::TS;
+
+use crate::{
+    app_state::AppState,
+    models::ApiResponse,
+    services::job_scheduler::{JobStatus, SchedulerError},
+};
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateJob {
+    pub enabled: bool,
+}
+
+pub fn jobs_router() -> Router<AppState> {
+    Router::new()
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:name", get(get_job).put(update_job))
+        .route("/jobs/:name/run", post(run_job))
+}
+
+fn scheduler_error_response<T>(
+    error: SchedulerError,
+) -> Result<ResponseJson<ApiResponse<T>>, StatusCode> {
+    match error {
+        SchedulerError::UnknownJob(_) => Err(StatusCode::NOT_FOUND),
+        SchedulerError::AlreadyRunning(_) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(error.to_string()),
+        })),
+        e => {
+            tracing::error!("Job scheduler error: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn list_jobs(
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<JobStatus>>>, StatusCode> {
+    match app_state.jobs.statuses().await {
+        Ok(jobs) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(jobs),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to fetch job statuses: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn get_job(
+    Path(name): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<JobStatus>>, StatusCode> {
+    match app_state.jobs.status(&name).await {
+        Ok(job) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(job),
+            message: None,
+        })),
+        Err(e) => scheduler_error_response(e),
+    }
+}
+
+async fn update_job(
+    Path(name): Path<String>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<UpdateJob>,
+) -> Result<ResponseJson<ApiResponse<JobStatus>>, StatusCode> {
+    if let Err(e) = app_state.jobs.set_enabled(&name, payload.enabled).await {
+        return scheduler_error_response(e);
+    }
+
+    match app_state.jobs.status(&name).await {
+        Ok(job) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(job),
+            message: Some(format!(
+                "Job {} {}",
+                name,
+                if payload.enabled {
+                    "enabled"
+                } else {
+                    "disabled"
+                }
+            )),
+        })),
+        Err(e) => scheduler_error_response(e),
+    }
+}
+
+/// Run a job now, whether or not it is enabled. Returns once the run has started.
+async fn run_job(
+    Path(name): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    match app_state.jobs.trigger(&name).await {
+        Ok(()) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: None,
+            message: Some(format!("Job {} started", name)),
+        })),
+        Err(e) => scheduler_error_response(e),
+    }
+}
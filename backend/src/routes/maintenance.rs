@@ -0,0 +1,91 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::{
+    app_state::AppState,
+    models::ApiResponse,
+    services::asset_usage::{
+        self, AssetCleanupRequest, AssetCleanupResult, AssetScanReport, AssetUsage,
+    },
+    utils::asset_dir,
+};
+
+pub fn maintenance_router() -> Router<AppState> {
+    Router::new()
+        .route("/maintenance/assets", get(get_asset_usage))
+        .route("/maintenance/assets/scan", get(scan_assets))
+        .route("/maintenance/assets/cleanup", post(clean_up_assets))
+}
+
+/// Disk space used by the asset directory as of the last `asset_accounting` run, which can
+/// be rerun through `/api/jobs`. Accounted for on the spot, without evicting anything, when
+/// it hasn't run yet.
+async fn get_asset_usage(
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<AssetUsage>>, StatusCode> {
+    if let Some(usage) = asset_usage::last_usage() {
+        return Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(usage),
+            message: None,
+        }));
+    }
+
+    let quotas = app_state.get_config().read().await.asset_quotas.clone();
+    match asset_usage::account(&asset_dir(), &quotas, false).await {
+        Ok(usage) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(usage),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to account for the asset directory: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Files in the asset directory that no executor session refers to, and sessions whose
+/// files are gone
+async fn scan_assets(
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<AssetScanReport>>, StatusCode> {
+    match asset_usage::scan(&app_state.db_pool, &asset_dir()).await {
+        Ok(report) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(report),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to scan the asset directory: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn clean_up_assets(
+    State(app_state): State<AppState>,
+    Json(payload): Json<AssetCleanupRequest>,
+) -> Result<ResponseJson<ApiResponse<AssetCleanupResult>>, StatusCode> {
+    match asset_usage::delete_orphans(&app_state.db_pool, &asset_dir(), payload.paths.as_deref())
+        .await
+    {
+        Ok(result) => {
+            let message = format!("Deleted {} orphaned files", result.deleted.len());
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: Some(result),
+                message: Some(message),
+            }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to clean up the asset directory: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -1,7 +1,18 @@
 pub mod auth;
+pub mod benchmarks;
+pub mod bisect;
 pub mod config;
+pub mod executions;
 pub mod filesystem;
+pub mod glossary;
 pub mod health;
+pub mod idempotency;
+pub mod jobs;
+pub mod maintenance;
+pub mod preferences;
 pub mod projects;
+pub mod search;
 pub mod task_attempts;
 pub mod tasks;
+pub mod updates;
+pub mod webhooks;
@@ -0,0 +1,70 @@
+use axum::{
+    extract::State, http::StatusCode, response::Json as ResponseJson, routing::get, Json, Router,
+};
+
+use crate::{
+    app_state::AppState,
+    models::{
+        config::ThemeMode,
+        user_preferences::{UpdateUserPreferences, UserPreferences, UserPreferencesError},
+        ApiResponse,
+    },
+};
+
+pub fn preferences_router() -> Router<AppState> {
+    Router::new().route(
+        "/preferences",
+        get(get_preferences).patch(update_preferences),
+    )
+}
+
+/// Identity to scope preferences to, plus the legacy theme from the server config
+async fn current_identity(app_state: &AppState) -> (String, ThemeMode) {
+    let config = app_state.get_config().read().await;
+    (
+        UserPreferences::identity_for(config.github.username.as_deref()),
+        config.theme.clone(),
+    )
+}
+
+pub async fn get_preferences(
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<UserPreferences>>, StatusCode> {
+    let (identity, legacy_theme) = current_identity(&app_state).await;
+
+    match UserPreferences::load(&app_state.db_pool, &identity).await {
+        Ok(preferences) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(preferences.with_legacy_theme(&legacy_theme)),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to load preferences for {}: {}", identity, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn update_preferences(
+    State(app_state): State<AppState>,
+    Json(payload): Json<UpdateUserPreferences>,
+) -> Result<ResponseJson<ApiResponse<UserPreferences>>, StatusCode> {
+    let (identity, legacy_theme) = current_identity(&app_state).await;
+
+    match UserPreferences::update(&app_state.db_pool, &identity, payload).await {
+        Ok(preferences) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(preferences.with_legacy_theme(&legacy_theme)),
+            message: Some("Preferences updated successfully".to_string()),
+        })),
+        Err(e @ UserPreferencesError::InvalidKey(_)) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to update preferences for {}: {}", identity, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
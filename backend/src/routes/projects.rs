@@ -1,25 +1,91 @@
-use std::collections::HashMap;
+use std::{collections::VecDeque, convert::Infallible};
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json as ResponseJson,
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json as ResponseJson,
+    },
     routing::get,
     Json, Router,
 };
+use futures_util::{stream, Stream};
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use ts_rs::TS;
 use uuid::Uuid;
 
 use crate::{
     app_state::AppState,
     models::{
+        activity_heatmap::{parse_range_days, parse_timezone, ActivityHeatmap, HeatmapBucketSize},
+        config::validate_response_language,
+        network_policy::{normalize_domains, NetworkPolicy, UpdateNetworkPolicy},
         project::{
-            CreateBranch, CreateProject, GitBranch, Project, ProjectWithBranch, SearchMatchType,
-            SearchResult, UpdateProject,
+            CreateBranch, CreateProject, GitBranch, HeadState, Project, ProjectStats, ProjectType,
+            ProjectWithBranch, SearchMatchType, SearchResult, SearchResultPage, SearchResultType,
+            SearchType, UpdateProject,
         },
+        task::Task,
+        task_attempt::{TaskAttempt, TaskAttemptError, WorktreeRepairReport},
         ApiResponse,
     },
+    services::{
+        attempt_overlap::{analyze_project, AttemptOverlapReport},
+        orphan_branches::{self, AdoptedBranch, UnknownAttemptBranch},
+        project_events::ProjectEvent,
+        provenance::{self, ProvenanceError, ProvenanceResolution},
+    },
 };
 
+/// Check that `path` is a usable git repository (bare repositories included).
+/// Returns a note describing HEAD when it isn't on a branch, or an error message.
+fn validate_git_repository(path: &std::path::Path) -> Result<Option<String>, String> {
+    if !path.exists() {
+        return Err("The specified path does not exist".to_string());
+    }
+
+    if !path.is_dir() {
+        return Err("The specified path is not a directory".to_string());
+    }
+
+    let repo = git2::Repository::open(path)
+        .map_err(|_| "The specified directory is not a git repository".to_string())?;
+
+    match HeadState::of(&repo) {
+        Ok(HeadState::Branch { .. }) => Ok(None),
+        Ok(HeadState::Detached { commit }) => Ok(Some(format!(
+            "HEAD is detached at {}; choose a base branch when starting task attempts",
+            commit
+        ))),
+        Ok(HeadState::Unborn) => Ok(Some(
+            "The repository has no commits yet; an initial commit will be created on 'main' for the first task attempt"
+                .to_string(),
+        )),
+        Err(e) => Err(format!("Failed to read the repository HEAD: {}", e)),
+    }
+}
+
+fn git_unavailable_response<T>(feature: &str) -> ResponseJson<ApiResponse<T>> {
+    ResponseJson(ApiResponse {
+        success: false,
+        data: None,
+        message: Some(Project::git_unavailable_message(feature)),
+    })
+}
+
+/// Check, or create when it isn't meant to exist yet, the directory of a scratch project
+fn prepare_scratch_directory(path: &std::path::Path, existing: bool) -> Result<(), String> {
+    if existing && !path.exists() {
+        return Err("The specified path does not exist".to_string());
+    }
+    if path.exists() && !path.is_dir() {
+        return Err("The specified path is not a directory".to_string());
+    }
+    std::fs::create_dir_all(path).map_err(|e| format!("Failed to create directory: {}", e))
+}
+
 pub async fn get_projects(
     State(app_state): State<AppState>,
 ) -> Result<ResponseJson<ApiResponse<Vec<Project>>>, StatusCode> {
@@ -77,6 +143,12 @@ pub async fn get_project_branches(
     State(app_state): State<AppState>,
 ) -> Result<ResponseJson<ApiResponse<Vec<GitBranch>>>, StatusCode> {
     match Project::find_by_id(&app_state.db_pool, id).await {
+        // Scratch projects have no branches to choose from
+        Ok(Some(project)) if project.is_scratch() => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(Vec::new()),
+            message: None,
+        })),
         Ok(Some(project)) => match project.get_all_branches() {
             Ok(branches) => Ok(ResponseJson(ApiResponse {
                 success: true,
@@ -120,6 +192,9 @@ pub async fn create_project_branch(
     }
 
     match Project::find_by_id(&app_state.db_pool, id).await {
+        Ok(Some(project)) if project.is_scratch() => {
+            Ok(git_unavailable_response("Creating branches"))
+        }
         Ok(Some(project)) => {
             match project.create_branch(&payload.name, payload.base_branch.as_deref()) {
                 Ok(branch) => Ok(ResponseJson(ApiResponse {
@@ -158,6 +233,14 @@ pub async fn create_project(
 
     tracing::debug!("Creating project '{}'", payload.name);
 
+    if let Err(message) = validate_response_language(payload.response_language.as_deref()) {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        }));
+    }
+
     // Check if git repo path is already used by another project
     match Project::find_by_git_repo_path(&app_state.db_pool, &payload.git_repo_path).await {
         Ok(Some(_)) => {
@@ -179,30 +262,28 @@ pub async fn create_project(
     // Validate and setup git repository
     let path = std::path::Path::new(&payload.git_repo_path);
 
-    if payload.use_existing_repo {
-        // For existing repos, validate that the path exists and is a git repository
-        if !path.exists() {
-            return Ok(ResponseJson(ApiResponse {
-                success: false,
-                data: None,
-                message: Some("The specified path does not exist".to_string()),
-            }));
-        }
+    let mut repository_note = None;
 
-        if !path.is_dir() {
+    if payload.project_type == Some(ProjectType::Scratch) {
+        // A plain directory: attempts run in copies of it, so git is never set up
+        if let Err(message) = prepare_scratch_directory(path, payload.use_existing_repo) {
             return Ok(ResponseJson(ApiResponse {
                 success: false,
                 data: None,
-                message: Some("The specified path is not a directory".to_string()),
+                message: Some(message),
             }));
         }
-
-        if !path.join(".git").exists() {
-            return Ok(ResponseJson(ApiResponse {
-                success: false,
-                data: None,
-                message: Some("The specified directory is not a git repository".to_string()),
-            }));
+    } else if payload.use_existing_repo {
+        // For existing repos, validate that the path exists and is a git repository
+        match validate_git_repository(path) {
+            Ok(note) => repository_note = note,
+            Err(message) => {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(message),
+                }));
+            }
         }
     } else {
         // For new repos, create directory and initialize git
@@ -219,8 +300,8 @@ pub async fn create_project(
             }
         }
 
-        // Check if it's already a git repo, if not initialize it
-        if !path.join(".git").exists() {
+        // Check if it's already a git repo (possibly bare), if not initialize it
+        if git2::Repository::open(path).is_err() {
             match std::process::Command::new("git")
                 .arg("init")
                 .current_dir(path)
@@ -258,16 +339,22 @@ pub async fn create_project(
                     Some(serde_json::json!({
                         "project_id": project.id.to_string(),
                         "use_existing_repo": payload.use_existing_repo,
+                        "project_type": project.project_type,
                         "has_setup_script": payload.setup_script.is_some(),
                         "has_dev_script": payload.dev_script.is_some(),
                     })),
                 )
                 .await;
 
+            let message = match repository_note {
+                Some(note) => format!("Project created successfully. {}", note),
+                None => "Project created successfully".to_string(),
+            };
+
             Ok(ResponseJson(ApiResponse {
                 success: true,
                 data: Some(project),
-                message: Some("Project created successfully".to_string()),
+                message: Some(message),
             }))
         }
         Err(e) => {
@@ -295,6 +382,19 @@ pub async fn update_project(
     // If git_repo_path is being changed, check if the new path is already used by another project
     if let Some(new_git_repo_path) = &payload.git_repo_path {
         if new_git_repo_path != &existing_project.git_repo_path {
+            let validated = if existing_project.is_scratch() {
+                prepare_scratch_directory(std::path::Path::new(new_git_repo_path), true)
+            } else {
+                validate_git_repository(std::path::Path::new(new_git_repo_path)).map(|_| ())
+            };
+            if let Err(message) = validated {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(message),
+                }));
+            }
+
             match Project::find_by_git_repo_path_excluding_id(
                 &app_state.db_pool,
                 new_git_repo_path,
@@ -330,10 +430,23 @@ pub async fn update_project(
         git_repo_path,
         setup_script,
         dev_script,
+        test_script,
+        format_script,
+        format_commit_mode,
+        response_language,
     } = payload;
 
+    if let Err(message) = validate_response_language(response_language.as_deref()) {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        }));
+    }
+
     let name = name.unwrap_or(existing_project.name);
     let git_repo_path = git_repo_path.unwrap_or(existing_project.git_repo_path);
+    let format_commit_mode = format_commit_mode.unwrap_or(existing_project.format_commit_mode);
 
     match Project::update(
         &app_state.db_pool,
@@ -342,6 +455,10 @@ pub async fn update_project(
         git_repo_path,
         setup_script,
         dev_script,
+        test_script,
+        format_script,
+        format_commit_mode,
+        response_language,
     )
     .await
     {
@@ -462,12 +579,21 @@ pub async fn open_project_in_editor(
     }
 }
 
+/// Maximum number of results returned by project search
+const SEARCH_RESULT_LIMIT: usize = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub search_type: Option<SearchType>,
+}
+
 pub async fn search_project_files(
     Path(id): Path<Uuid>,
-    Query(params): Query<HashMap<String, String>>,
+    Query(params): Query<SearchQuery>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<Vec<SearchResult>>>, StatusCode> {
-    let query = match params.get("q") {
+) -> Result<ResponseJson<ApiResponse<SearchResultPage>>, StatusCode> {
+    let query = match params.q.as_deref() {
         Some(q) if !q.trim().is_empty() => q.trim(),
         _ => {
             return Ok(ResponseJson(ApiResponse {
@@ -477,6 +603,7 @@ pub async fn search_project_files(
             }));
         }
     };
+    let search_type = params.search_type.unwrap_or_default();
 
     // Check if project exists
     let project = match Project::find_by_id(&app_state.db_pool, id).await {
@@ -488,37 +615,253 @@ pub async fn search_project_files(
         }
     };
 
-    // Search files in the project repository
-    match search_files_in_repo(&project.git_repo_path, query).await {
-        Ok(results) => Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: Some(results),
-            message: None,
-        })),
-        Err(e) => {
-            tracing::error!("Failed to search files: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    let mut results = Vec::new();
+
+    if matches!(search_type, SearchType::FilesOnly | SearchType::All) {
+        match search_files_in_repo(&project.git_repo_path, query).await {
+            Ok(files) => results.extend(files),
+            Err(e) => {
+                tracing::error!("Failed to search files: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
         }
     }
+
+    if matches!(search_type, SearchType::TasksOnly | SearchType::All) {
+        match Task::find_by_project_id(&app_state.db_pool, project.id).await {
+            Ok(tasks) => results.extend(search_tasks(&tasks, query)),
+            Err(e) => {
+                tracing::error!("Failed to search tasks: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    if matches!(search_type, SearchType::ProjectsOnly | SearchType::All) {
+        match Project::find_all(&app_state.db_pool).await {
+            Ok(projects) => results.extend(search_projects(&projects, query)),
+            Err(e) => {
+                tracing::error!("Failed to search projects: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    // Rank by relevance so blended results interleave sensibly
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let total = results.len() as u64;
+    results.truncate(SEARCH_RESULT_LIMIT);
+
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(SearchResultPage {
+            results,
+            total,
+            search_type_applied: search_type,
+        }),
+        message: None,
+    }))
+}
+
+/// Relevance of a match: the match type sets the base score, and exact or prefix
+/// matches of the matched text rank higher within it
+fn relevance_score(match_type: &SearchMatchType, matched_text: &str, query_lower: &str) -> f64 {
+    let base = match match_type {
+        SearchMatchType::FileName | SearchMatchType::Title => 0.7,
+        SearchMatchType::DirectoryName => 0.5,
+        SearchMatchType::Description => 0.4,
+        SearchMatchType::FullPath => 0.3,
+    };
+
+    let matched_lower = matched_text.to_lowercase();
+    if matched_lower == query_lower {
+        base + 0.3
+    } else if matched_lower.starts_with(query_lower) {
+        base + 0.15
+    } else {
+        base
+    }
+}
+
+fn search_tasks(tasks: &[Task], query: &str) -> Vec<SearchResult> {
+    let query_lower = query.to_lowercase();
+
+    tasks
+        .iter()
+        .filter_map(|task| {
+            let (match_type, matched_text) = if task.title.to_lowercase().contains(&query_lower) {
+                (SearchMatchType::Title, task.title.as_str())
+            } else {
+                let description = task.description.as_deref()?;
+                if !description.to_lowercase().contains(&query_lower) {
+                    return None;
+                }
+                (SearchMatchType::Description, description)
+            };
+
+            Some(SearchResult {
+                path: task.title.clone(),
+                is_file: false,
+                score: relevance_score(&match_type, matched_text, &query_lower),
+                match_type,
+                result_type: SearchResultType::Task,
+                id: Some(task.id),
+            })
+        })
+        .collect()
+}
+
+fn search_projects(projects: &[Project], query: &str) -> Vec<SearchResult> {
+    let query_lower = query.to_lowercase();
+
+    projects
+        .iter()
+        .filter_map(|project| {
+            let (match_type, matched_text) = if project.name.to_lowercase().contains(&query_lower) {
+                (SearchMatchType::Title, project.name.as_str())
+            } else if project.git_repo_path.to_lowercase().contains(&query_lower) {
+                (SearchMatchType::FullPath, project.git_repo_path.as_str())
+            } else {
+                return None;
+            };
+
+            Some(SearchResult {
+                path: project.name.clone(),
+                is_file: false,
+                score: relevance_score(&match_type, matched_text, &query_lower),
+                match_type,
+                result_type: SearchResultType::Project,
+                id: Some(project.id),
+            })
+        })
+        .collect()
+}
+
+/// Classify a repository-relative path against the query, returning `None` if it doesn't match
+fn file_search_result(
+    relative_path: &str,
+    is_file: bool,
+    query_lower: &str,
+) -> Option<SearchResult> {
+    let path = std::path::Path::new(relative_path);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parent_name = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let (match_type, matched_text) = if file_name.to_lowercase().contains(query_lower) {
+        (SearchMatchType::FileName, file_name.as_str())
+    } else if relative_path.to_lowercase().contains(query_lower) {
+        // Check if it's a directory name match or full path match
+        if parent_name.to_lowercase().contains(query_lower) {
+            (SearchMatchType::DirectoryName, parent_name.as_str())
+        } else {
+            (SearchMatchType::FullPath, relative_path)
+        }
+    } else {
+        return None;
+    };
+
+    Some(SearchResult {
+        path: relative_path.to_string(),
+        is_file,
+        score: relevance_score(&match_type, matched_text, query_lower),
+        match_type,
+        result_type: SearchResultType::File,
+        id: None,
+    })
 }
 
 async fn search_files_in_repo(
     repo_path: &str,
     query: &str,
 ) -> Result<Vec<SearchResult>, Box<dyn std::error::Error + Send + Sync>> {
-    use std::path::Path;
-
-    use ignore::WalkBuilder;
-
-    let repo_path = Path::new(repo_path);
+    let repo_path = std::path::Path::new(repo_path);
 
     if !repo_path.exists() {
         return Err("Repository path does not exist".into());
     }
 
-    let mut results = Vec::new();
     let query_lower = query.to_lowercase();
 
+    // List tracked and untracked (but not ignored) files through git
+    let output = tokio::process::Command::new("git")
+        .args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])
+        .current_dir(repo_path)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        tracing::warn!(
+            "git ls-files failed in {}, falling back to a directory walk: {}",
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return walk_files_in_repo(repo_path, &query_lower);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(search_listed_files(stdout.split('\0'), &query_lower))
+}
+
+/// Match the files listed by git, and the directories holding them, which git doesn't list
+fn search_listed_files<'a>(
+    files: impl Iterator<Item = &'a str>,
+    query_lower: &str,
+) -> Vec<SearchResult> {
+    let mut seen = std::collections::HashSet::new();
+    let mut directories = std::collections::BTreeSet::new();
+    let mut results = Vec::new();
+
+    for file in files {
+        // Unmerged paths are listed once per stage, so deduplicate
+        if file.is_empty() || !seen.insert(file) {
+            continue;
+        }
+        // git separates with '/' on every platform; once a directory is known, so are its parents
+        let mut parent = file;
+        while let Some(slash) = parent.rfind('/') {
+            parent = &parent[..slash];
+            if !directories.insert(parent) {
+                break;
+            }
+        }
+        results.extend(file_search_result(file, true, query_lower));
+    }
+
+    results.extend(
+        directories
+            .into_iter()
+            .filter_map(|directory| file_search_result(directory, false, query_lower)),
+    );
+    results
+}
+
+/// Search by walking the directory when the project isn't usable as a git repository
+fn walk_files_in_repo(
+    repo_path: &std::path::Path,
+    query_lower: &str,
+) -> Result<Vec<SearchResult>, Box<dyn std::error::Error + Send + Sync>> {
+    use ignore::WalkBuilder;
+
+    let mut results = Vec::new();
+
     // Use ignore::WalkBuilder to respect gitignore files
     let walker = WalkBuilder::new(repo_path)
         .git_ignore(true)
@@ -545,60 +888,558 @@ async fn search_files_in_repo(
         {
             continue;
         }
-        let relative_path_str = relative_path.to_string_lossy().to_lowercase();
-
-        let file_name = path
-            .file_name()
-            .map(|name| name.to_string_lossy().to_lowercase())
-            .unwrap_or_default();
-
-        // Check for matches
-        if file_name.contains(&query_lower) {
-            results.push(SearchResult {
-                path: relative_path.to_string_lossy().to_string(),
-                is_file: path.is_file(),
-                match_type: SearchMatchType::FileName,
-            });
-        } else if relative_path_str.contains(&query_lower) {
-            // Check if it's a directory name match or full path match
-            let match_type = if path
-                .parent()
-                .and_then(|p| p.file_name())
-                .map(|name| name.to_string_lossy().to_lowercase())
-                .unwrap_or_default()
-                .contains(&query_lower)
-            {
-                SearchMatchType::DirectoryName
-            } else {
-                SearchMatchType::FullPath
-            };
 
-            results.push(SearchResult {
-                path: relative_path.to_string_lossy().to_string(),
-                is_file: path.is_file(),
-                match_type,
-            });
+        if let Some(result) = file_search_result(
+            &relative_path.to_string_lossy(),
+            path.is_file(),
+            query_lower,
+        ) {
+            results.push(result);
         }
     }
 
-    // Sort results by priority: FileName > DirectoryName > FullPath
-    results.sort_by(|a, b| {
-        use SearchMatchType::*;
-        let priority = |match_type: &SearchMatchType| match match_type {
-            FileName => 0,
-            DirectoryName => 1,
-            FullPath => 2,
-        };
+    Ok(results)
+}
 
-        priority(&a.match_type)
-            .cmp(&priority(&b.match_type))
-            .then_with(|| a.path.cmp(&b.path))
-    });
+pub async fn get_project_stats(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<ProjectStats>>, StatusCode> {
+    match Project::exists(&app_state.db_pool, id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check project existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
 
-    // Limit to top 10 results
-    results.truncate(10);
+    match Project::stats(&app_state.db_pool, id).await {
+        Ok(stats) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(stats),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to compute stats for project {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
 
-    Ok(results)
+/// Which open attempts of the project change the same files, to warn before merge conflicts
+pub async fn get_project_attempt_overlaps(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<AttemptOverlapReport>>, StatusCode> {
+    let project = match Project::find_by_id(&app_state.db_pool, id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    if project.is_scratch() {
+        return Ok(git_unavailable_response("Overlap analysis"));
+    }
+
+    match analyze_project(&app_state.db_pool, &app_state.attempt_changes, &project).await {
+        Ok(report) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(report),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to analyze attempt overlaps for project {}: {}",
+                id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Find and repair the worktrees of a project's attempts after directories were moved
+pub async fn repair_project_worktrees(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<WorktreeRepairReport>>, StatusCode> {
+    let project = match Project::find_by_id(&app_state.db_pool, id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    if project.is_scratch() {
+        return Ok(git_unavailable_response("Repairing worktrees"));
+    }
+
+    match TaskAttempt::repair_project_worktrees(&app_state.db_pool, &project).await {
+        Ok(report) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            message: Some(format!(
+                "Repaired {} worktree(s); {} could not be found",
+                report.repaired.len(),
+                report.not_found.len()
+            )),
+            data: Some(report),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to repair worktrees of project {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnknownAttemptBranchesQuery {
+    #[serde(default)]
+    pub include_ignored: bool,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct UnknownAttemptBranchAction {
+    pub branch: String,
+    /// For deletes, also delete the branch from the remotes carrying it
+    #[serde(default)]
+    pub remote: bool,
+}
+
+async fn find_project_or_status(app_state: &AppState, id: Uuid) -> Result<Project, StatusCode> {
+    match Project::find_by_id(&app_state.db_pool, id).await {
+        Ok(Some(project)) => Ok(project),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Respond to an action on an unknown attempt branch. Asking for a branch that isn't unknown,
+/// or a failed remote delete, is reported to the user rather than as a server error.
+fn unknown_branch_response<T>(
+    result: Result<T, TaskAttemptError>,
+    action: &str,
+    message: impl FnOnce(&T) -> String,
+) -> Result<ResponseJson<ApiResponse<T>>, StatusCode> {
+    match result {
+        Ok(data) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            message: Some(message(&data)),
+            data: Some(data),
+        })),
+        Err(TaskAttemptError::ValidationError(reason)) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(reason),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to {} unknown attempt branch: {}", action, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_unknown_attempt_branches(
+    Path(id): Path<Uuid>,
+    Query(params): Query<UnknownAttemptBranchesQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<UnknownAttemptBranch>>>, StatusCode> {
+    let project = find_project_or_status(&app_state, id).await?;
+    // Attempts of scratch projects have no branches to lose track of
+    if project.is_scratch() {
+        return Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(Vec::new()),
+            message: None,
+        }));
+    }
+
+    match orphan_branches::find_unknown_attempt_branches(
+        &app_state.db_pool,
+        &project,
+        params.include_ignored,
+    )
+    .await
+    {
+        Ok(branches) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(branches),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to list unknown attempt branches of project {}: {}",
+                id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn adopt_unknown_attempt_branch(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<UnknownAttemptBranchAction>,
+) -> Result<ResponseJson<ApiResponse<AdoptedBranch>>, StatusCode> {
+    let project = find_project_or_status(&app_state, id).await?;
+    if project.is_scratch() {
+        return Ok(git_unavailable_response("Attempt branches"));
+    }
+    let worktree_name_pattern = app_state
+        .get_config()
+        .read()
+        .await
+        .worktree_name_pattern
+        .clone();
+
+    let result = orphan_branches::adopt_branch(
+        &app_state.db_pool,
+        &project,
+        &payload.branch,
+        &worktree_name_pattern,
+    )
+    .await;
+    unknown_branch_response(result, "adopt", |adopted| {
+        format!(
+            "Adopted {} as task '{}'",
+            payload.branch, adopted.task.title
+        )
+    })
+}
+
+pub async fn delete_unknown_attempt_branch(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<UnknownAttemptBranchAction>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    let project = find_project_or_status(&app_state, id).await?;
+    if project.is_scratch() {
+        return Ok(git_unavailable_response("Attempt branches"));
+    }
+
+    let result = orphan_branches::delete_branch(
+        &app_state.db_pool,
+        &project,
+        &payload.branch,
+        payload.remote,
+    )
+    .await;
+    unknown_branch_response(result, "delete", |_| format!("Deleted {}", payload.branch))
+}
+
+pub async fn ignore_unknown_attempt_branch(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<UnknownAttemptBranchAction>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    let project = find_project_or_status(&app_state, id).await?;
+    if project.is_scratch() {
+        return Ok(git_unavailable_response("Attempt branches"));
+    }
+
+    let result =
+        orphan_branches::ignore_branch(&app_state.db_pool, &project, &payload.branch).await;
+    unknown_branch_response(result, "ignore", |_| {
+        format!("{} will no longer be reported", payload.branch)
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityHeatmapQuery {
+    pub range: Option<String>,
+    pub bucket: Option<HeatmapBucketSize>,
+}
+
+pub async fn get_project_activity_heatmap(
+    Path(id): Path<Uuid>,
+    Query(params): Query<ActivityHeatmapQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<ActivityHeatmap>>, StatusCode> {
+    match Project::exists(&app_state.db_pool, id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check project existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    activity_heatmap_response(&app_state, Some(id), params).await
+}
+
+pub async fn get_activity_heatmap(
+    Query(params): Query<ActivityHeatmapQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<ActivityHeatmap>>, StatusCode> {
+    activity_heatmap_response(&app_state, None, params).await
+}
+
+async fn activity_heatmap_response(
+    app_state: &AppState,
+    project_id: Option<Uuid>,
+    params: ActivityHeatmapQuery,
+) -> Result<ResponseJson<ApiResponse<ActivityHeatmap>>, StatusCode> {
+    let range_days = match params.range.as_deref() {
+        None => 30,
+        Some(range) => match parse_range_days(range) {
+            Some(days) => days,
+            None => {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(format!(
+                        "Invalid range '{}'. Use a value such as '30d', '2w' or '48h' (up to 365 days)",
+                        range
+                    )),
+                }));
+            }
+        },
+    };
+
+    let timezone = {
+        let config = app_state.get_config().read().await;
+        parse_timezone(config.timezone.as_deref()).unwrap_or_else(|| {
+            tracing::warn!(
+                "Configured timezone {:?} is not recognised, using UTC",
+                config.timezone
+            );
+            chrono_tz::Tz::UTC
+        })
+    };
+
+    match ActivityHeatmap::compute(
+        &app_state.db_pool,
+        project_id,
+        range_days,
+        params.bucket.unwrap_or_default(),
+        timezone,
+    )
+    .await
+    {
+        Ok(heatmap) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(heatmap),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to compute activity heatmap: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveProvenanceQuery {
+    pub sha: String,
+}
+
+/// The task and attempt a commit of the project came from
+pub async fn resolve_commit_provenance(
+    Path(id): Path<Uuid>,
+    Query(params): Query<ResolveProvenanceQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<ProvenanceResolution>>, StatusCode> {
+    let project = find_project_or_status(&app_state, id).await?;
+    if project.is_scratch() {
+        return Ok(git_unavailable_response("Commit provenance"));
+    }
+    let sha = params.sha.trim();
+
+    match provenance::resolve(&app_state.db_pool, id, &project.git_repo_path, sha).await {
+        Ok(Some(resolution)) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(resolution),
+            message: None,
+        })),
+        Ok(None) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(format!(
+                "Commit '{}' doesn't belong to an attempt of this project",
+                sha
+            )),
+        })),
+        Err(e @ ProvenanceError::UnknownCommit(_)) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to resolve provenance of commit {} in project {}: {}",
+                sha,
+                id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Whether the project's agents run behind a logging proxy, and the hosts they may contact
+pub async fn get_project_network_policy(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<NetworkPolicy>>, StatusCode> {
+    match Project::exists(&app_state.db_pool, id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check project existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    match NetworkPolicy::find_for_project(&app_state.db_pool, id).await {
+        Ok(policy) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(policy),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to fetch network policy of project {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Opt the project's agents in or out of network observation. Applies to agent runs started
+/// afterwards.
+pub async fn update_project_network_policy(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(mut payload): Json<UpdateNetworkPolicy>,
+) -> Result<ResponseJson<ApiResponse<NetworkPolicy>>, StatusCode> {
+    match Project::exists(&app_state.db_pool, id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check project existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let lists = normalize_domains(&payload.allowlist)
+        .and_then(|allowlist| Ok((allowlist, normalize_domains(&payload.denylist)?)));
+    match lists {
+        Ok((allowlist, denylist)) => {
+            payload.allowlist = allowlist;
+            payload.denylist = denylist;
+        }
+        Err(message) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(message),
+            }));
+        }
+    }
+
+    match NetworkPolicy::upsert(&app_state.db_pool, id, &payload).await {
+        Ok(policy) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(policy),
+            message: Some("Network policy updated".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to update network policy of project {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+struct ProjectEventStream {
+    project_id: Uuid,
+    pending: VecDeque<Event>,
+    events: broadcast::Receiver<ProjectEvent>,
+}
+
+impl ProjectEventStream {
+    async fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            match self.events.recv().await {
+                Ok(event) if event.project_id == self.project_id => {
+                    if let Some(event) = project_event(&event) {
+                        return Some(event);
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => return Some(resync_event()),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+fn project_event(event: &ProjectEvent) -> Option<Event> {
+    match serde_json::to_string(&event.change) {
+        Ok(data) => Some(Event::default().id(event.id.to_string()).data(data)),
+        Err(e) => {
+            tracing::error!("Failed to serialize project event {}: {}", event.id, e);
+            None
+        }
+    }
+}
+
+/// Tells the client it missed events and should reload the project's tasks
+fn resync_event() -> Event {
+    Event::default().event("resync").data("{}")
+}
+
+/// Server-sent events with a `ProjectChange` whenever one of the project's tasks is created,
+/// updated or deleted, or one of its attempts starts or finishes. Reconnecting clients get
+/// the events after their `Last-Event-ID` while they're still kept, or a `resync` event.
+pub async fn stream_project_events(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    match Project::exists(&app_state.db_pool, id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check project existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    let subscription = app_state.project_events.subscribe(id, last_event_id);
+    let mut pending = VecDeque::new();
+    if subscription.gap {
+        pending.push_back(resync_event());
+    }
+    pending.extend(subscription.missed.iter().filter_map(project_event));
+
+    let event_stream = ProjectEventStream {
+        project_id: id,
+        pending,
+        events: subscription.events,
+    };
+    let events = stream::unfold(event_stream, |mut event_stream| async move {
+        event_stream
+            .next_event()
+            .await
+            .map(|event| (Ok(event), event_stream))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
 }
 
 pub fn projects_router() -> Router<AppState> {
@@ -616,5 +1457,174 @@ pub fn projects_router() -> Router<AppState> {
             get(get_project_branches).post(create_project_branch),
         )
         .route("/projects/:id/search", get(search_project_files))
+        .route("/projects/:id/stats", get(get_project_stats))
+        .route("/projects/:id/events", get(stream_project_events))
+        .route(
+            "/projects/:id/attempt-overlaps",
+            get(get_project_attempt_overlaps),
+        )
+        .route(
+            "/projects/:id/activity-heatmap",
+            get(get_project_activity_heatmap),
+        )
+        .route("/activity-heatmap", get(get_activity_heatmap))
         .route("/projects/:id/open-editor", post(open_project_in_editor))
+        .route(
+            "/projects/:id/repair-worktrees",
+            post(repair_project_worktrees),
+        )
+        .route(
+            "/projects/:id/unknown-attempt-branches",
+            get(get_unknown_attempt_branches),
+        )
+        .route(
+            "/projects/:id/unknown-attempt-branches/adopt",
+            post(adopt_unknown_attempt_branch),
+        )
+        .route(
+            "/projects/:id/unknown-attempt-branches/delete",
+            post(delete_unknown_attempt_branch),
+        )
+        .route(
+            "/projects/:id/unknown-attempt-branches/ignore",
+            post(ignore_unknown_attempt_branch),
+        )
+        .route(
+            "/projects/:id/network-policy",
+            get(get_project_network_policy).put(update_project_network_policy),
+        )
+        .route(
+            "/projects/:id/provenance/resolve",
+            get(resolve_commit_provenance),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use crate::{
+        models::config::Config,
+        test_utils::{insert_task, memory_pool},
+    };
+
+    #[test]
+    fn test_listed_files_bring_their_directories_into_the_search() {
+        let files = [
+            "src/routes/projects.rs",
+            "src/routes/tasks.rs",
+            "src/main.rs",
+            "src/main.rs",
+            "README.md",
+            "",
+        ];
+
+        let results = search_listed_files(files.into_iter(), "routes");
+        let directories: Vec<_> = results.iter().filter(|result| !result.is_file).collect();
+        assert_eq!(directories.len(), 1);
+        assert_eq!(directories[0].path, "src/routes");
+        assert!(matches!(
+            directories[0].match_type,
+            SearchMatchType::FileName
+        ));
+        assert_eq!(results.iter().filter(|result| result.is_file).count(), 2);
+
+        let results = search_listed_files(files.into_iter(), "main");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "src/main.rs");
+    }
+
+    #[tokio::test]
+    async fn test_search_type_filters_results_and_all_ranks_them_by_relevance() {
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        for file in ["login/form.ts", "src/login.rs", "docs/notes.md"] {
+            let path = repo_dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let pool = memory_pool().await;
+        let project_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO projects (id, name, git_repo_path) VALUES ($1, 'Login portal', $2)",
+        )
+        .bind(project_id)
+        .bind(repo_dir.path().to_string_lossy().to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+        insert_task(&pool, project_id, "Login").await;
+        insert_task(&pool, project_id, "Fix the login redirect").await;
+        insert_task(&pool, project_id, "Dark mode").await;
+        let config = Config {
+            analytics_enabled: Some(false),
+            ..Config::default()
+        };
+        let app_state = AppState::new(pool, Arc::new(RwLock::new(config))).await;
+        let search = |search_type| {
+            let app_state = app_state.clone();
+            async move {
+                search_project_files(
+                    Path(project_id),
+                    Query(SearchQuery {
+                        q: Some("login".to_string()),
+                        search_type: Some(search_type),
+                    }),
+                    State(app_state),
+                )
+                .await
+                .unwrap()
+                .0
+                .data
+                .unwrap()
+            }
+        };
+
+        let files = search(SearchType::FilesOnly).await;
+        assert_eq!(files.search_type_applied, SearchType::FilesOnly);
+        assert!(files
+            .results
+            .iter()
+            .all(|result| matches!(result.result_type, SearchResultType::File)));
+        let mut paths: Vec<_> = files.results.iter().map(|r| r.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["login", "login/form.ts", "src/login.rs"]);
+
+        let tasks = search(SearchType::TasksOnly).await;
+        assert_eq!(tasks.total, 2);
+        assert!(tasks
+            .results
+            .iter()
+            .all(|result| matches!(result.result_type, SearchResultType::Task)));
+
+        let projects = search(SearchType::ProjectsOnly).await;
+        assert_eq!(projects.total, 1);
+        assert_eq!(projects.results[0].id, Some(project_id));
+
+        // Exact matches first, then prefixes, then a directory holding a file, then the rest
+        let all = search(SearchType::All).await;
+        assert_eq!(all.total, 6);
+        assert_eq!(
+            all.results
+                .iter()
+                .map(|r| r.path.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "Login",
+                "login",
+                "Login portal",
+                "src/login.rs",
+                "login/form.ts",
+                "Fix the login redirect",
+            ]
+        );
+        assert!(all
+            .results
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+    }
 }
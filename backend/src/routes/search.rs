@@ -0,0 +1,71 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    models::ApiResponse,
+    services::search::{
+        self, SearchQuery, SearchResponse, SearchResultType, DEFAULT_LIMIT, MAX_LIMIT,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    #[serde(default)]
+    pub q: String,
+    /// Comma-separated result types to search; all of them when omitted
+    pub types: Option<String>,
+    /// Results of each type at most
+    pub limit: Option<usize>,
+}
+
+pub fn search_router() -> Router<AppState> {
+    Router::new().route("/search", get(quick_search))
+}
+
+/// Search projects, tasks, recent attempts and branches at once, best matches first
+pub async fn quick_search(
+    Query(params): Query<SearchParams>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<SearchResponse>>, StatusCode> {
+    let types = match params.types.as_deref() {
+        None | Some("") => SearchResultType::ALL.to_vec(),
+        Some(types) => match types
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<SearchResultType>, _>>()
+        {
+            Ok(types) => types,
+            Err(message) => {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(message),
+                }))
+            }
+        },
+    };
+    let query = SearchQuery {
+        text: params.q,
+        types,
+        limit: params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+    };
+
+    match search::search(&app_state.db_pool, &query).await {
+        Ok(response) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(response),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to search for {:?}: {}", query.text, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
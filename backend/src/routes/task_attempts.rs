@@ -1,32 +1,86 @@
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    convert::Infallible,
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json as ResponseJson,
+    body::Body,
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json as ResponseJson, Response,
+    },
     routing::get,
     Json, Router,
 };
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use futures_util::{stream, Stream};
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use ts_rs::TS;
 use uuid::Uuid;
 
 use crate::{
-    app_state::AppState,
-    executor::{ExecutorConfig, NormalizedConversation, NormalizedEntry, NormalizedEntryType},
+    app_state::{AppState, AttemptSlot},
+    executor::{ExecutorConfig, ExecutorConstants, IncrementalNormalizer, NormalizedEntry},
     models::{
-        config::Config,
+        annotation::{Annotation, CreateAnnotation},
+        bisect_run::BisectRun,
+        config::{Config, ProvenanceMode},
         execution_process::{
             ExecutionProcess, ExecutionProcessStatus, ExecutionProcessSummary, ExecutionProcessType,
         },
-        executor_session::ExecutorSession,
+        executor_session::{ExecutorSession, ExecutorSessionWithDuration},
+        format_run::{FormatRun, FormattingDiff},
+        network_policy::{AttemptNetworkReport, NetworkContact, NetworkPolicy, NetworkVerdict},
+        project::Project,
         task::Task,
         task_attempt::{
-            BranchStatus, CreateFollowUpAttempt, CreatePrParams, CreateTaskAttempt, TaskAttempt,
-            TaskAttemptState, TaskAttemptStatus, WorktreeDiff,
+            AttemptRunStatus, CreateFollowUpAttempt, CreatePrParams, CreateTaskAttempt,
+            ExecutionState, TaskAttempt, TaskAttemptError, TaskAttemptListCursor,
+            TaskAttemptListFilter, TaskAttemptListPage, TaskAttemptState, TaskAttemptStatus,
+            WorktreeDiff, WorktreeRelocation, WorktreeStatus,
         },
         task_attempt_activity::{
             CreateTaskAttemptActivity, TaskAttemptActivity, TaskAttemptActivityWithPrompt,
         },
+        task_dependency::TaskDependency,
+        untracked_change_journal::{UntrackedChangeJournal, UntrackedChangeReport},
         ApiResponse,
     },
+    services::{
+        attempt_export::{
+            apply_diff_patch, apply_patch_series, attempt_diff_patch, export_attempt,
+            split_patch_series, AttemptExportError, DiffApplyResult, ExportFormat,
+            PatchApplyResult,
+        },
+        attempt_stop::{
+            cancel_attempt, restart_with_note, stop_attempt, CancelAttemptResult, RestartAfterStop,
+            StopAttemptResult, StopLink, StopLinkError, StopTaskAttempt,
+        },
+        attempt_wait::{
+            wait_for_attempt, AttemptWaitError, AttemptWaitResult, WaitCondition,
+            DEFAULT_WAIT_TIMEOUT,
+        },
+        bisect::followup_context,
+        branch_status::CachedBranchStatus,
+        execution_diff::{diff_executions, ExecutionDiffError},
+        live_normalization::{self, LiveLogMessage},
+        log_filter::{FilteredConversation, LogFilter, LogFilterQuery},
+        log_normalization::{
+            current_normalization, parser_working_dir, renormalize_attempt, stdout_parser,
+            NormalizationError, RenormalizeResult,
+        },
+        prompt_budget::PromptBudgetReport,
+        provenance::{self, Provenance},
+        repo_config, GitServiceError, ProcessService,
+    },
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -41,6 +95,68 @@ pub struct CreateGitHubPRRequest {
     pub base_branch: Option<String>,
 }
 
+/// Message returned when an attempt's worktree was deleted by hand; the client
+/// can recover with `POST /api/task-attempts/:attempt_id/recreate-worktree`
+const WORKTREE_MISSING_MESSAGE: &str = "worktree_missing";
+
+fn worktree_missing_response<T>() -> ResponseJson<ApiResponse<T>> {
+    ResponseJson(ApiResponse {
+        success: false,
+        data: None,
+        message: Some(WORKTREE_MISSING_MESSAGE.to_string()),
+    })
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct RelocateWorktree {
+    pub new_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportAttemptQuery {
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WaitAttemptQuery {
+    /// Seconds to wait at most, capped at `MAX_WAIT_TIMEOUT`
+    pub timeout: Option<u64>,
+    #[serde(default)]
+    pub until: WaitCondition,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportPatchSeries {
+    pub task_id: Uuid,
+    /// Branch the new attempt starts from; the repository's current branch when unset
+    pub base_branch: Option<String>,
+    /// Patch files or `git format-patch --stdout` output, applied in order
+    pub patches: Vec<String>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct PatchImportResult {
+    pub attempt: TaskAttempt,
+    pub patches: Vec<PatchApplyResult>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct ApplyAttemptToBranch {
+    /// Branch the attempt's changes are applied onto, in a new attempt of the same task
+    pub base_branch: String,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct BranchApplyResult {
+    pub attempt: TaskAttempt,
+    pub result: DiffApplyResult,
+}
+
 #[derive(Debug, Serialize)]
 pub struct FollowUpResponse {
     pub message: String,
@@ -48,6 +164,15 @@ pub struct FollowUpResponse {
     pub created_new_attempt: bool,
 }
 
+/// The prompt a follow-up would be dispatched with, and how it fits the token budget
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct FollowUpPreview {
+    pub prompt: String,
+    #[serde(flatten)]
+    pub budget: PromptBudgetReport,
+}
+
 pub async fn get_task_attempts(
     Path((project_id, task_id)): Path<(Uuid, Uuid)>,
     State(app_state): State<AppState>,
@@ -109,11 +234,125 @@ pub async fn get_task_attempt_activities(
     }
 }
 
+/// The attempts an attempt was retried from and its retries, oldest first
+pub async fn get_task_attempt_retries(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<TaskAttempt>>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    match TaskAttempt::find_retry_chain(&app_state.db_pool, attempt_id).await {
+        Ok(attempts) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(attempts),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch retry chain for attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Files git doesn't track that the attempt's executions created, modified or deleted
+pub async fn get_task_attempt_untracked_changes(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<UntrackedChangeReport>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    match UntrackedChangeJournal::report(&app_state.db_pool, attempt_id).await {
+        Ok(report) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(report),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch untracked changes for attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Warning about uncommitted changes in the project's repository. A failed check is logged
+/// rather than stopping the attempt.
+pub async fn source_repo_warning(app_state: &AppState, project_id: Uuid) -> Option<String> {
+    match TaskAttempt::source_repo_warning(&app_state.db_pool, project_id).await {
+        Ok(warning) => warning,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to check project {} for uncommitted changes: {}",
+                project_id,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// The executor the project's `.codecommand.toml` picks for attempts started without one
+pub async fn repo_default_executor(app_state: &AppState, project_id: Uuid) -> Option<String> {
+    match Project::find_by_id(&app_state.db_pool, project_id).await {
+        Ok(Some(mut project)) => repo_config::merge(&mut project).default_executor,
+        Ok(None) => None,
+        Err(e) => {
+            tracing::warn!("Failed to fetch project {}: {}", project_id, e);
+            None
+        }
+    }
+}
+
+/// 400 for an executor name that isn't one of the agents, listing the executors the server
+/// offers
+fn unknown_executor_response(executor: &str, config: &Config) -> Response {
+    let valid: Vec<String> = ExecutorConstants::new(config.available_executors())
+        .executor_types
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    (
+        StatusCode::BAD_REQUEST,
+        ResponseJson(ApiResponse::<()> {
+            success: false,
+            data: None,
+            message: Some(format!(
+                "Unknown executor '{}'. Valid executors: {}",
+                executor,
+                valid.join(", ")
+            )),
+        }),
+    )
+        .into_response()
+}
+
 pub async fn create_task_attempt(
     Path((project_id, task_id)): Path<(Uuid, Uuid)>,
     State(app_state): State<AppState>,
-    Json(payload): Json<CreateTaskAttempt>,
-) -> Result<ResponseJson<ApiResponse<TaskAttempt>>, StatusCode> {
+    Json(mut payload): Json<CreateTaskAttempt>,
+) -> Result<Response, StatusCode> {
     // Verify task exists in project first
     match Task::exists(&app_state.db_pool, task_id, project_id).await {
         Ok(false) => return Err(StatusCode::NOT_FOUND),
@@ -124,16 +363,99 @@ pub async fn create_task_attempt(
         Ok(true) => {}
     }
 
-    let executor_string = payload.executor.as_ref().map(|exec| exec.to_string());
+    // Refused with 409 and the tasks in the way while a dependency isn't done
+    match TaskDependency::find_blocking_tasks(&app_state.db_pool, task_id).await {
+        Ok(blocking) if !blocking.is_empty() => {
+            let titles: Vec<_> = blocking
+                .iter()
+                .map(|task| format!("'{}'", task.title))
+                .collect();
+            return Ok((
+                StatusCode::CONFLICT,
+                ResponseJson(ApiResponse {
+                    success: false,
+                    message: Some(format!(
+                        "The task is blocked until these tasks are done: {}",
+                        titles.join(", ")
+                    )),
+                    data: Some(blocking),
+                }),
+            )
+                .into_response());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Failed to check dependencies of task {}: {}", task_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    // Attempts started without an executor use the task's, then the repo's, then the
+    // server's default
+    if payload.executor.is_none() {
+        payload.executor = match Task::preferred_executor(&app_state.db_pool, task_id).await {
+            Ok(executor) => executor,
+            Err(e) => {
+                tracing::warn!("Failed to fetch executor of task {}: {}", task_id, e);
+                None
+            }
+        };
+    }
+    if payload.executor.is_none() {
+        payload.executor = repo_default_executor(&app_state, project_id).await;
+    }
+
+    let (worktree_name_pattern, block_on_dirty_repo) = {
+        let config = app_state.get_config().read().await;
+        let executor = payload
+            .executor
+            .get_or_insert_with(|| config.executor.to_string());
+        if !ExecutorConfig::agents()
+            .iter()
+            .any(|agent| agent.to_string() == *executor)
+        {
+            return Ok(unknown_executor_response(executor, &config));
+        }
+        if let Err(reason) = config
+            .check_executor_enabled(&ProcessService::resolve_executor_config(&payload.executor))
+        {
+            return Ok(ResponseJson(ApiResponse::<()> {
+                success: false,
+                data: None,
+                message: Some(reason),
+            })
+            .into_response());
+        }
+        (
+            config.worktree_name_pattern.clone(),
+            config.block_attempts_on_dirty_repo,
+        )
+    };
 
-    match TaskAttempt::create(&app_state.db_pool, &payload, task_id).await {
+    let dirty_repo_warning = source_repo_warning(&app_state, project_id).await;
+    if let (Some(warning), true) = (&dirty_repo_warning, block_on_dirty_repo) {
+        return Ok(ResponseJson(ApiResponse::<()> {
+            success: false,
+            data: None,
+            message: Some(warning.clone()),
+        })
+        .into_response());
+    }
+    match TaskAttempt::create(
+        &app_state.db_pool,
+        &payload,
+        task_id,
+        &worktree_name_pattern,
+    )
+    .await
+    {
         Ok(attempt) => {
             app_state
                 .track_analytics_event(
                     "task_attempt_started",
                     Some(serde_json::json!({
                         "task_id": task_id.to_string(),
-                        "executor_type": executor_string.as_deref().unwrap_or("default"),
+                        "executor_type": payload.executor.as_deref().unwrap_or("default"),
                         "attempt_id": attempt.id.to_string(),
                     })),
                 )
@@ -163,9 +485,32 @@ pub async fn create_task_attempt(
             Ok(ResponseJson(ApiResponse {
                 success: true,
                 data: Some(attempt),
-                message: Some("Task attempt created successfully".to_string()),
-            }))
+                message: Some(
+                    dirty_repo_warning
+                        .unwrap_or_else(|| "Task attempt created successfully".to_string()),
+                ),
+            })
+            .into_response())
         }
+        Err(TaskAttemptError::GitService(
+            e @ (crate::services::GitServiceError::DetachedHead(_)
+            | crate::services::GitServiceError::Worktree(_)),
+        )) => Ok(ResponseJson(ApiResponse::<()> {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })
+        .into_response()),
+        Err(
+            e @ (TaskAttemptError::ExecutorUnavailable(_)
+            | TaskAttemptError::ValidationError(_)
+            | TaskAttemptError::GitUnavailable(_)),
+        ) => Ok(ResponseJson(ApiResponse::<()> {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })
+        .into_response()),
         Err(e) => {
             tracing::error!("Failed to create task attempt: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -228,8 +573,35 @@ pub async fn create_task_attempt_activity(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    /// Separate the commits of format runs from the agent's changes
+    #[serde(default)]
+    pub formatting: FormattingDiff,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BranchStatusRefresh {
+    /// Recompute the branch status before responding
+    Wait,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BranchStatusQuery {
+    pub refresh: Option<BranchStatusRefresh>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecutionDiffQuery {
+    /// Diff from the end of this execution instead of the one before `to_execution`
+    pub from_execution: Option<Uuid>,
+    pub to_execution: Uuid,
+}
+
 pub async fn get_task_attempt_diff(
     Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    Query(query): Query<DiffQuery>,
     State(app_state): State<AppState>,
 ) -> Result<ResponseJson<ApiResponse<WorktreeDiff>>, StatusCode> {
     // Verify task attempt exists and belongs to the correct task
@@ -242,112 +614,2079 @@ pub async fn get_task_attempt_diff(
         Ok(true) => {}
     }
 
-    match TaskAttempt::get_diff(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(diff) => Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: Some(diff),
-            message: None,
-        })),
-        Err(e) => {
-            tracing::error!("Failed to get diff for task attempt {}: {}", attempt_id, e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
-}
+    let (rename_threshold, ignored_paths) = {
+        let config = app_state.get_config().read().await;
+        (config.diff_rename_threshold, config.path_ignore())
+    };
 
-#[axum::debug_handler]
-pub async fn merge_task_attempt(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
-    State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    let diff = match query.formatting {
+        FormattingDiff::All => {
+            TaskAttempt::get_diff(
+                &app_state.db_pool,
+                attempt_id,
+                task_id,
+                project_id,
+                rename_threshold,
+                ignored_paths,
+            )
+            .await
         }
-        Ok(true) => {}
-    }
-
-    match TaskAttempt::merge_changes(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(_) => {
-            // Update task status to Done
-            if let Err(e) = Task::update_status(
+        part => {
+            TaskAttempt::get_formatting_diff(
                 &app_state.db_pool,
+                attempt_id,
                 task_id,
                 project_id,
-                crate::models::task::TaskStatus::Done,
+                rename_threshold,
+                ignored_paths,
+                part,
             )
             .await
-            {
-                tracing::error!("Failed to update task status to Done after merge: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-
-            // Track task attempt merged event
-            app_state
-                .track_analytics_event(
-                    "task_attempt_merged",
-                    Some(serde_json::json!({
-                        "task_id": task_id.to_string(),
-                        "project_id": project_id.to_string(),
-                        "attempt_id": attempt_id.to_string(),
-                    })),
-                )
-                .await;
+        }
+    };
 
+    match diff {
+        Ok(diff) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(diff),
+            message: None,
+        })),
+        Err(TaskAttemptError::WorktreeMissing(_)) => Ok(worktree_missing_response()),
+        Err(TaskAttemptError::GitService(GitServiceError::MergeConflicts(reason))) => {
             Ok(ResponseJson(ApiResponse {
-                success: true,
+                success: false,
                 data: None,
-                message: Some("Changes merged successfully".to_string()),
+                message: Some(format!("The formatting can't be separated: {}", reason)),
             }))
         }
+        Err(e @ TaskAttemptError::GitUnavailable(_)) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
         Err(e) => {
-            tracing::error!("Failed to merge task attempt {}: {}", attempt_id, e);
+            tracing::error!("Failed to get diff for task attempt {}: {}", attempt_id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-pub async fn create_github_pr(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+/// What changed between the ends of two executions of an attempt; by default what
+/// `to_execution` itself changed
+pub async fn get_task_attempt_execution_diff(
+    Path(attempt_id): Path<Uuid>,
+    Query(query): Query<ExecutionDiffQuery>,
     State(app_state): State<AppState>,
-    Json(request): Json<CreateGitHubPRRequest>,
-) -> Result<ResponseJson<ApiResponse<String>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-        Ok(true) => {}
-    }
-
-    // Load the user's GitHub configuration
-    let config = match Config::load(&crate::utils::config_path()) {
-        Ok(config) => config,
-        Err(e) => {
-            tracing::error!("Failed to load config: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+) -> Result<Response, StatusCode> {
+    let (rename_threshold, ignored_paths) = {
+        let config = app_state.get_config().read().await;
+        (config.diff_rename_threshold, config.path_ignore())
     };
 
-    let github_token = match config.github.token {
-        Some(token) => token,
-        None => {
-            return Ok(ResponseJson(ApiResponse {
+    match diff_executions(
+        &app_state.db_pool,
+        attempt_id,
+        query.from_execution,
+        query.to_execution,
+        rename_threshold,
+        ignored_paths,
+    )
+    .await
+    {
+        Ok(diff) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(diff),
+            message: None,
+        })
+        .into_response()),
+        Err(ExecutionDiffError::AttemptNotFound | ExecutionDiffError::ExecutionNotFound(_)) => {
+            Err(StatusCode::NOT_FOUND)
+        }
+        Err(e @ ExecutionDiffError::SnapshotUnavailable(_)) => Ok((
+            StatusCode::GONE,
+            ResponseJson(ApiResponse::<()> {
                 success: false,
                 data: None,
-                message: Some(
-                    "GitHub authentication not configured. Please sign in with GitHub.".to_string(),
-                ),
-            }));
+                message: Some(e.to_string()),
+            }),
+        )
+            .into_response()),
+        Err(e @ (ExecutionDiffError::NoSnapshot(_) | ExecutionDiffError::GitUnavailable)) => {
+            Ok(ResponseJson(ApiResponse::<()> {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            })
+            .into_response())
         }
-    };
+        Err(e) => {
+            tracing::error!(
+                "Failed to diff executions of task attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// How often the diff stream re-reads the worktree
+const DIFF_STREAM_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the log stream checks for new output
+const LOG_STREAM_INTERVAL: Duration = Duration::from_millis(500);
+/// Empty reads after a process exits before its output counts as complete, since the last
+/// of it can be stored just after the exit is recorded
+const LOG_STREAM_READS_AFTER_EXIT: u8 = 2;
+
+/// Whether anything runs, or is about to run, for the attempt
+async fn attempt_is_running(
+    app_state: &AppState,
+    attempt_id: Uuid,
+    task_id: Uuid,
+    project_id: Uuid,
+) -> bool {
+    if app_state.has_running_execution(attempt_id).await {
+        return true;
+    }
+
+    // Nothing runs between the setup script finishing and the coding agent starting
+    matches!(
+        TaskAttempt::get_execution_state(&app_state.db_pool, attempt_id, task_id, project_id).await,
+        Ok(TaskAttemptState {
+            execution_state: ExecutionState::SetupComplete,
+            ..
+        })
+    )
+}
+
+struct DiffStream {
+    app_state: AppState,
+    attempt_id: Uuid,
+    task_id: Uuid,
+    project_id: Uuid,
+    _slot: AttemptSlot,
+    last_hash: Option<u64>,
+    polled: bool,
+    done_pending: bool,
+    finished: bool,
+}
+
+impl DiffStream {
+    /// Wait for the next event worth sending; `None` after the `done` event
+    async fn next_event(&mut self) -> Option<Event> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            if self.done_pending {
+                self.finished = true;
+                return Some(Event::default().event("done").data("{}"));
+            }
+            if self.polled {
+                tokio::time::sleep(DIFF_STREAM_INTERVAL).await;
+            }
+            self.polled = true;
+
+            // Checked before diffing so the final state of the worktree is still sent
+            self.done_pending = !attempt_is_running(
+                &self.app_state,
+                self.attempt_id,
+                self.task_id,
+                self.project_id,
+            )
+            .await;
+            if let Some(event) = self.diff_event().await {
+                return Some(event);
+            }
+        }
+    }
+
+    /// The diff (or why it can't be read) if it changed since the last event
+    async fn diff_event(&mut self) -> Option<Event> {
+        let (rename_threshold, ignored_paths) = {
+            let config = self.app_state.get_config().read().await;
+            (config.diff_rename_threshold, config.path_ignore())
+        };
+
+        let (event, data) = match TaskAttempt::get_diff(
+            &self.app_state.db_pool,
+            self.attempt_id,
+            self.task_id,
+            self.project_id,
+            rename_threshold,
+            ignored_paths,
+        )
+        .await
+        {
+            Ok(diff) => match serde_json::to_string(&diff) {
+                Ok(data) => ("diff-update", data),
+                Err(e) => {
+                    tracing::error!("Failed to serialize diff for {}: {}", self.attempt_id, e);
+                    return None;
+                }
+            },
+            Err(TaskAttemptError::WorktreeMissing(_)) => (
+                "error",
+                serde_json::json!({ "message": WORKTREE_MISSING_MESSAGE }).to_string(),
+            ),
+            Err(e @ TaskAttemptError::GitUnavailable(_)) => (
+                "error",
+                serde_json::json!({ "message": e.to_string() }).to_string(),
+            ),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to get diff for task attempt {}: {}",
+                    self.attempt_id,
+                    e
+                );
+                (
+                    "error",
+                    serde_json::json!({ "message": "Failed to get diff" }).to_string(),
+                )
+            }
+        };
+
+        let mut hasher = DefaultHasher::new();
+        event.hash(&mut hasher);
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.last_hash == Some(hash) {
+            return None;
+        }
+        self.last_hash = Some(hash);
+
+        Some(Event::default().event(event).data(data))
+    }
+}
+
+/// Server-sent `diff-update` events whenever the attempt's diff changes, then `done`
+/// once nothing is running for the attempt
+pub async fn stream_task_attempt_diff(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let attempt = match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let task = match Task::find_by_id(&app_state.db_pool, attempt.task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task {}: {}", attempt.task_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let Some(slot) = app_state.try_acquire_diff_stream(attempt_id) else {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    };
+
+    let diff_stream = DiffStream {
+        app_state,
+        attempt_id,
+        task_id: task.id,
+        project_id: task.project_id,
+        _slot: slot,
+        last_hash: None,
+        polled: false,
+        done_pending: false,
+        finished: false,
+    };
+    let events = stream::unfold(diff_stream, |mut diff_stream| async move {
+        diff_stream
+            .next_event()
+            .await
+            .map(|event| (Ok(event), diff_stream))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogStreamQuery {
+    /// Follow only this process, rather than each setup and coding agent run in turn
+    pub process_id: Option<Uuid>,
+    /// Follow only the runs of this type, like the setup script or the dev server, so their
+    /// output isn't mixed into the agent's
+    pub process_type: Option<ExecutionProcessType>,
+    /// Byte offset into the stdout of the first process followed to resume from, as sent in
+    /// the ids of `entry` events. Takes precedence over a `Last-Event-ID` header.
+    pub since: Option<usize>,
+}
+
+/// A process whose stdout a log stream is reading
+struct FollowedProcess {
+    id: Uuid,
+    normalizer: IncrementalNormalizer,
+    /// Stdout bytes read so far
+    offset: usize,
+    /// The client already has the entries completed by stdout before this offset
+    resume_from: usize,
+    reads_since_exit: u8,
+}
+
+struct LogStream {
+    app_state: AppState,
+    attempt_id: Uuid,
+    task_id: Uuid,
+    project_id: Uuid,
+    /// The process the client asked for, if any
+    process_id: Option<Uuid>,
+    /// The type of the processes the client asked for, if any
+    process_type: Option<ExecutionProcessType>,
+    _slot: AttemptSlot,
+    followed: Option<FollowedProcess>,
+    last_followed: Option<Uuid>,
+    /// Resume offset for the first process followed
+    since: usize,
+    pending: VecDeque<Event>,
+    polled: bool,
+    finished: bool,
+}
+
+impl LogStream {
+    /// Wait for the next event worth sending; `None` after the `done` event
+    async fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            if self.finished {
+                return None;
+            }
+            if self.polled {
+                tokio::time::sleep(LOG_STREAM_INTERVAL).await;
+            }
+            self.polled = true;
+
+            match self.followed.as_mut() {
+                Some(followed) => {
+                    let ended =
+                        read_followed(&self.app_state.db_pool, followed, &mut self.pending).await;
+                    if ended == Some(true) {
+                        self.followed = None;
+                    }
+                }
+                None => self.follow_next().await,
+            }
+        }
+    }
+
+    /// Whether the stream follows processes of this type
+    fn selects(&self, process_type: &ExecutionProcessType) -> bool {
+        match &self.process_type {
+            Some(selected) => process_type == selected,
+            None => process_type.is_attempt_stage(),
+        }
+    }
+
+    /// Start reading the attempt's latest stage, or latest run of the requested type, or the
+    /// requested process, unless it was read already. Finishes the stream once there's
+    /// nothing left to read.
+    async fn follow_next(&mut self) {
+        let pool = &self.app_state.db_pool;
+        let mut selected_running = false;
+        let next = match self.process_id {
+            Some(process_id) => Some(process_id).filter(|_| self.last_followed.is_none()),
+            None => {
+                match ExecutionProcess::find_summaries_by_task_attempt_id(pool, self.attempt_id)
+                    .await
+                {
+                    Ok(processes) => {
+                        let selected: Vec<_> = processes
+                            .into_iter()
+                            .filter(|process| self.selects(&process.process_type))
+                            .collect();
+                        selected_running = selected
+                            .iter()
+                            .any(|process| process.status == ExecutionProcessStatus::Running);
+                        selected
+                            .last()
+                            .map(|process| process.id)
+                            .filter(|id| Some(*id) != self.last_followed)
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to list processes of task attempt {}: {}",
+                            self.attempt_id,
+                            e
+                        );
+                        return;
+                    }
+                }
+            }
+        };
+
+        let Some(process_id) = next else {
+            let more_to_come = match &self.process_type {
+                // Dev servers and the like don't keep the attempt running, only themselves
+                Some(process_type) if !process_type.is_attempt_stage() => selected_running,
+                _ => {
+                    attempt_is_running(
+                        &self.app_state,
+                        self.attempt_id,
+                        self.task_id,
+                        self.project_id,
+                    )
+                    .await
+                }
+            };
+            if self.process_id.is_some() || !more_to_come {
+                self.pending
+                    .push_back(Event::default().event("done").data("{}"));
+                self.finished = true;
+            }
+            return;
+        };
+
+        let process = match ExecutionProcess::find_by_id(pool, process_id).await {
+            Ok(Some(process)) => process,
+            Ok(None) => {
+                self.last_followed = Some(process_id);
+                return;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch execution process {}: {}", process_id, e);
+                return;
+            }
+        };
+        self.last_followed = Some(process_id);
+
+        let executor_session = ExecutorSession::find_by_execution_process_id(pool, process_id)
+            .await
+            .ok()
+            .flatten();
+        let parser = match stdout_parser(&process, executor_session.as_ref()) {
+            Ok(parser) => parser,
+            Err(e) => {
+                self.pending.push_back(
+                    Event::default()
+                        .event("error")
+                        .data(serde_json::json!({ "message": e.to_string() }).to_string()),
+                );
+                return;
+            }
+        };
+
+        self.pending.push_back(
+            Event::default().event("process").data(
+                serde_json::json!({
+                    "execution_process_id": process.id,
+                    "process_type": &process.process_type,
+                })
+                .to_string(),
+            ),
+        );
+        self.followed = Some(FollowedProcess {
+            id: process.id,
+            normalizer: IncrementalNormalizer::new(parser, parser_working_dir(&process)),
+            offset: 0,
+            resume_from: std::mem::take(&mut self.since),
+            reads_since_exit: 0,
+        });
+    }
+}
+
+/// Read a followed process's new stdout and queue the entries it completes. Returns whether
+/// the process's output has ended, or `None` if it couldn't be read.
+async fn read_followed(
+    pool: &sqlx::SqlitePool,
+    followed: &mut FollowedProcess,
+    pending: &mut VecDeque<Event>,
+) -> Option<bool> {
+    let (chunk, status) =
+        match ExecutionProcess::read_stdout_from(pool, followed.id, followed.offset as i64).await {
+            Ok(Some(read)) => read,
+            // Deleted along with its attempt
+            Ok(None) => return Some(true),
+            Err(e) => {
+                tracing::warn!("Failed to read output of process {}: {}", followed.id, e);
+                return None;
+            }
+        };
+
+    if chunk.is_empty() {
+        if status == ExecutionProcessStatus::Running {
+            return Some(false);
+        }
+        followed.reads_since_exit += 1;
+        if followed.reads_since_exit < LOG_STREAM_READS_AFTER_EXIT {
+            return Some(false);
+        }
+        let entries = followed.normalizer.finish();
+        queue_log_entries(pending, entries, followed.offset);
+        return Some(true);
+    }
+
+    let start = followed.offset;
+    followed.offset += chunk.len();
+    // Output the client has seen is still parsed, since later entries can depend on it
+    let (seen, unseen) =
+        chunk.split_at(followed.resume_from.saturating_sub(start).min(chunk.len()));
+    if !seen.is_empty() {
+        followed.normalizer.push(&String::from_utf8_lossy(seen));
+    }
+    if !unseen.is_empty() {
+        let entries = followed.normalizer.push(&String::from_utf8_lossy(unseen));
+        queue_log_entries(pending, entries, followed.offset);
+    }
+    Some(false)
+}
+
+/// Queue `entry` events, the last one carrying the stdout offset to resume after. A client
+/// cut off within a batch resumes at its start and can drop repeats by `sequence`.
+fn queue_log_entries(pending: &mut VecDeque<Event>, entries: Vec<NormalizedEntry>, offset: usize) {
+    let count = entries.len();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let data = match serde_json::to_string(&entry) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::error!("Failed to serialize log entry: {}", e);
+                continue;
+            }
+        };
+        let mut event = Event::default().event("entry").data(data);
+        if index + 1 == count {
+            event = event.id(offset.to_string());
+        }
+        pending.push_back(event);
+    }
+}
+
+/// Server-sent `entry` events with the attempt's normalized log entries as their processes
+/// write them. Each setup and coding agent run is announced with a `process` event before
+/// its entries; `done` follows once nothing runs for the attempt anymore. `process_type`
+/// follows only one kind of run instead, dev servers included. Resumes after a byte offset
+/// from `since` or `Last-Event-ID`. A client going away just drops the stream.
+pub async fn stream_task_attempt_logs(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Query(query): Query<LogStreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let (attempt, task) = match load_attempt_and_task(&app_state, attempt_id).await {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to load task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    if let Some(process_id) = query.process_id {
+        match ExecutionProcess::find_by_id(&app_state.db_pool, process_id).await {
+            Ok(Some(process)) if process.task_attempt_id == attempt.id => {}
+            Ok(_) => return Err(StatusCode::NOT_FOUND),
+            Err(e) => {
+                tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    let Some(slot) = app_state.try_acquire_log_stream(attempt_id) else {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    };
+
+    let since = query.since.or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    });
+    let log_stream = LogStream {
+        app_state,
+        attempt_id,
+        task_id: task.id,
+        project_id: task.project_id,
+        process_id: query.process_id,
+        process_type: query.process_type,
+        _slot: slot,
+        followed: None,
+        last_followed: None,
+        since: since.unwrap_or(0),
+        pending: VecDeque::new(),
+        polled: false,
+        finished: false,
+    };
+    let events = stream::unfold(log_stream, |mut log_stream| async move {
+        log_stream
+            .next_event()
+            .await
+            .map(|event| (Ok(event), log_stream))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// How often a live log socket checks on a process that has no live output (yet)
+const LIVE_LOG_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Live output of the attempt's running setup script or coding agent over a WebSocket, as
+/// JSON `LiveLogMessage`s. Clients joining mid-run first get the entries so far and recent
+/// stderr. When the process ends the socket sends `exited` and closes. With nothing running
+/// it replays the latest run's stored entries instead.
+pub async fn stream_task_attempt_logs_ws(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    let process_id = match app_state.running_attempt_stage(attempt_id).await {
+        Some(process_id) => process_id,
+        None => {
+            match ExecutionProcess::find_summaries_by_task_attempt_id(
+                &app_state.db_pool,
+                attempt_id,
+            )
+            .await
+            {
+                Ok(processes) => match processes
+                    .into_iter()
+                    .filter(|process| process.process_type.is_attempt_stage())
+                    .last()
+                {
+                    Some(process) => process.id,
+                    None => return Err(StatusCode::NOT_FOUND),
+                },
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to list processes of task attempt {}: {}",
+                        attempt_id,
+                        e
+                    );
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+    };
+
+    let Some(slot) = app_state.try_acquire_log_stream(attempt_id) else {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    };
+
+    Ok(ws.on_upgrade(move |socket| send_live_logs(socket, app_state, process_id, slot)))
+}
+
+/// Whether a message read from a live log socket means the client went away. Clients have
+/// nothing to say otherwise; pings are answered by axum.
+fn client_left(incoming: Option<Result<Message, axum::Error>>) -> bool {
+    matches!(incoming, None | Some(Err(_)) | Some(Ok(Message::Close(_))))
+}
+
+/// Send a live log message; `false` once the client is gone
+async fn send_live_log_message(socket: &mut WebSocket, message: &LiveLogMessage) -> bool {
+    let text = match serde_json::to_string(message) {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::error!("Failed to serialize live log message: {}", e);
+            return true;
+        }
+    };
+    socket.send(Message::Text(text)).await.is_ok()
+}
+
+async fn send_live_logs(
+    mut socket: WebSocket,
+    app_state: AppState,
+    process_id: Uuid,
+    _slot: AttemptSlot,
+) {
+    let pool = &app_state.db_pool;
+
+    // The process may have just started, before its output is being read
+    let subscription = loop {
+        if let Some(subscription) = live_normalization::subscribe(process_id) {
+            break Some(subscription);
+        }
+        match ExecutionProcess::find_status(pool, process_id).await {
+            Ok(Some((ExecutionProcessStatus::Running, _))) => {}
+            _ => break None,
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(LIVE_LOG_POLL_INTERVAL) => {}
+            incoming = socket.recv() => if client_left(incoming) { return },
+        }
+    };
+
+    match subscription {
+        Some(subscription) => {
+            let mut last_sequence = subscription.entries.last().map(|entry| entry.sequence);
+            let backfill = subscription
+                .entries
+                .into_iter()
+                .map(|entry| LiveLogMessage::Entry { entry })
+                .chain(
+                    subscription
+                        .stderr
+                        .into_iter()
+                        .map(|line| LiveLogMessage::Stderr { line }),
+                );
+            for message in backfill {
+                if !send_live_log_message(&mut socket, &message).await {
+                    return;
+                }
+            }
+
+            let mut messages = subscription.messages;
+            loop {
+                let message = tokio::select! {
+                    message = messages.recv() => message,
+                    incoming = socket.recv() => {
+                        if client_left(incoming) {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+                match message {
+                    Ok(message) => {
+                        if let LiveLogMessage::Entry { entry } = &message {
+                            last_sequence = Some(entry.sequence);
+                        }
+                        if !send_live_log_message(&mut socket, &message).await {
+                            return;
+                        }
+                    }
+                    // Catch up on the entries from the normalizer; the stderr lines missed
+                    // meanwhile are only in the stored output
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!(
+                            "Live log subscriber of process {} missed {} messages",
+                            process_id,
+                            missed
+                        );
+                        let entries = live_normalization::entries(process_id).unwrap_or_default();
+                        for entry in entries {
+                            if last_sequence.is_some_and(|sequence| entry.sequence <= sequence) {
+                                continue;
+                            }
+                            last_sequence = Some(entry.sequence);
+                            let message = LiveLogMessage::Entry { entry };
+                            if !send_live_log_message(&mut socket, &message).await {
+                                return;
+                            }
+                        }
+                    }
+                    // All of stdout was read
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+        None => {
+            // Not running anymore: replay what was stored
+            let conversation = match ExecutionProcess::find_by_id(pool, process_id).await {
+                Ok(Some(process)) => current_normalization(pool, &process).await.ok(),
+                _ => None,
+            };
+            for entry in conversation.map(|c| c.entries).unwrap_or_default() {
+                if !send_live_log_message(&mut socket, &LiveLogMessage::Entry { entry }).await {
+                    return;
+                }
+            }
+        }
+    }
+
+    // Stdout ends as the process exits, but the exit is recorded a moment later
+    let (status, exit_code) = loop {
+        match ExecutionProcess::find_status(pool, process_id).await {
+            Ok(Some((ExecutionProcessStatus::Running, _))) => {}
+            Ok(Some(exit)) => break exit,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Failed to check execution process {}: {}", process_id, e);
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(LIVE_LOG_POLL_INTERVAL) => {}
+            incoming = socket.recv() => if client_left(incoming) { return },
+        }
+    };
+    let exited = LiveLogMessage::Exited {
+        execution_process_id: process_id,
+        exit_code,
+        success: status == ExecutionProcessStatus::Completed,
+    };
+    if send_live_log_message(&mut socket, &exited).await {
+        let _ = socket
+            .send(Message::Close(Some(CloseFrame {
+                code: close_code::NORMAL,
+                reason: "process exited".into(),
+            })))
+            .await;
+    }
+}
+
+/// Long-poll until the attempt reaches `until` (finished by default) or `timeout` seconds
+/// pass. Responds with the attempt's state either way; `timed_out` tells them apart.
+pub async fn wait_for_task_attempt(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Query(query): Query<WaitAttemptQuery>,
+) -> Result<Response, StatusCode> {
+    let Some(_slot) = app_state.try_acquire_attempt_waiter(attempt_id) else {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    };
+    let timeout = query
+        .timeout
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_WAIT_TIMEOUT);
+
+    match wait_for_attempt(&app_state, attempt_id, query.until, timeout).await {
+        Ok(result) => Ok(ResponseJson(ApiResponse::<AttemptWaitResult> {
+            success: true,
+            data: Some(result),
+            message: None,
+        })
+        .into_response()),
+        Err(AttemptWaitError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e @ AttemptWaitError::Gone) => Ok((
+            StatusCode::GONE,
+            ResponseJson(ApiResponse::<()> {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            }),
+        )
+            .into_response()),
+        Err(AttemptWaitError::Database(e)) => {
+            tracing::error!("Failed to check task attempt {}: {}", attempt_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[axum::debug_handler]
+pub async fn merge_task_attempt(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    let provenance = app_state.get_config().read().await.provenance;
+    match TaskAttempt::merge_changes(
+        &app_state.db_pool,
+        attempt_id,
+        task_id,
+        project_id,
+        provenance,
+    )
+    .await
+    {
+        Ok(_) => {
+            app_state.branch_statuses.invalidate(attempt_id);
+
+            // Update task status to Done
+            if let Err(e) = app_state
+                .project_events
+                .update_task_status(
+                    &app_state.db_pool,
+                    task_id,
+                    project_id,
+                    crate::models::task::TaskStatus::Done,
+                )
+                .await
+            {
+                tracing::error!("Failed to update task status to Done after merge: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+
+            // Track task attempt merged event
+            app_state
+                .track_analytics_event(
+                    "task_attempt_merged",
+                    Some(serde_json::json!({
+                        "task_id": task_id.to_string(),
+                        "project_id": project_id.to_string(),
+                        "attempt_id": attempt_id.to_string(),
+                    })),
+                )
+                .await;
+
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: None,
+                message: Some("Changes merged successfully".to_string()),
+            }))
+        }
+        Err(e @ TaskAttemptError::GitUnavailable(_)) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to merge task attempt {}: {}", attempt_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn create_github_pr(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Json(request): Json<CreateGitHubPRRequest>,
+) -> Result<ResponseJson<ApiResponse<String>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    // Load the user's GitHub configuration
+    let config = match Config::load(&crate::utils::config_path()) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Failed to load config: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let github_token = match config.github.login_token() {
+        Some(token) => token,
+        None => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(
+                    "GitHub authentication not configured. Please sign in with GitHub.".to_string(),
+                ),
+            }));
+        }
+    };
+
+    // Get the task attempt to access the stored base branch
+    let attempt = match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let base_branch = request.base_branch.unwrap_or_else(|| {
+        // Use the stored base branch from the task attempt as the default
+        // Fall back to config default or "main" only if stored base branch is somehow invalid
+        if !attempt.base_branch.trim().is_empty() {
+            attempt.base_branch.clone()
+        } else {
+            config
+                .github
+                .default_pr_base
+                .unwrap_or_else(|| "main".to_string())
+        }
+    });
+
+    let body = match config.provenance {
+        ProvenanceMode::Full => match Provenance::load(&app_state.db_pool, &attempt).await {
+            Ok(provenance) => {
+                let link = provenance::web_base_url(config.public_url.as_deref())
+                    .map(|base| provenance::attempt_link(&base, &provenance));
+                Some(provenance.stamp_pr_body(
+                    request.body.as_deref(),
+                    config.provenance,
+                    link.as_deref(),
+                ))
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Creating PR of attempt {} without provenance: {}",
+                    attempt_id,
+                    e
+                );
+                request.body.clone()
+            }
+        },
+        _ => request.body.clone(),
+    };
+    let open_in_browser = config.opens_prs_in_browser(crate::utils::is_headless());
+
+    match TaskAttempt::create_github_pr(
+        &app_state.db_pool,
+        CreatePrParams {
+            attempt_id,
+            task_id,
+            project_id,
+            github_token: &config.github.pat.unwrap_or(github_token),
+            title: &request.title,
+            body: body.as_deref(),
+            base_branch: Some(&base_branch),
+        },
+    )
+    .await
+    {
+        Ok(pr_url) => {
+            app_state
+                .track_analytics_event(
+                    "github_pr_created",
+                    Some(serde_json::json!({
+                        "task_id": task_id.to_string(),
+                        "project_id": project_id.to_string(),
+                        "attempt_id": attempt_id.to_string(),
+                    })),
+                )
+                .await;
+
+            if open_in_browser {
+                let url = pr_url.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = crate::utils::open_browser(&url).await {
+                        tracing::warn!("Failed to open PR {} in the browser: {}", url, e);
+                    }
+                });
+            }
+
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: Some(pr_url),
+                message: Some("GitHub PR created successfully".to_string()),
+            }))
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to create GitHub PR for attempt {}: {}",
+                attempt_id,
+                e
+            );
+            let message = match &e {
+                crate::models::task_attempt::TaskAttemptError::GitHubService(
+                    crate::services::GitHubServiceError::TokenInvalid,
+                ) => Some("github_token_invalid".to_string()),
+                crate::models::task_attempt::TaskAttemptError::GitService(
+                    crate::services::git_service::GitServiceError::Git(err),
+                ) if err
+                    .message()
+                    .contains("too many redirects or authentication replays") =>
+                {
+                    Some("insufficient_github_permissions".to_string()) // PAT is invalid
+                }
+                crate::models::task_attempt::TaskAttemptError::GitService(
+                    crate::services::git_service::GitServiceError::Git(err),
+                ) if err.message().contains("status code: 403") => {
+                    Some("insufficient_github_permissions".to_string())
+                }
+                crate::models::task_attempt::TaskAttemptError::GitService(
+                    crate::services::git_service::GitServiceError::Git(err),
+                ) if err.message().contains("status code: 404") => {
+                    Some("github_repo_not_found_or_no_access".to_string())
+                }
+                crate::models::task_attempt::TaskAttemptError::GitUnavailable(_) => {
+                    Some(e.to_string())
+                }
+                _ => Some(format!("Failed to create PR: {}", e)),
+            };
+            Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message,
+            }))
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct OpenEditorRequest {
+    editor_type: Option<String>,
+}
+
+pub async fn open_task_attempt_in_editor(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<Option<OpenEditorRequest>>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    // Resolve the worktree path, recreating cleaned-up worktrees
+    let worktree_path = match TaskAttempt::ensure_worktree_exists(
+        &app_state.db_pool,
+        attempt_id,
+        project_id,
+        "open editor",
+    )
+    .await
+    {
+        Ok(path) => path,
+        Err(TaskAttemptError::WorktreeMissing(_)) => return Ok(worktree_missing_response()),
+        Err(e) => {
+            tracing::error!(
+                "Failed to resolve worktree for task attempt {}: {}",
+                attempt_id,
+                e
+            );
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // Get editor command from config or override
+    let editor_command = {
+        let config_guard = app_state.get_config().read().await;
+        if let Some(ref request) = payload {
+            if let Some(ref editor_type) = request.editor_type {
+                // Create a temporary editor config with the override
+                use crate::models::config::{EditorConfig, EditorType};
+                let override_editor_type = match editor_type.as_str() {
+                    "vscode" => EditorType::VSCode,
+                    "cursor" => EditorType::Cursor,
+                    "windsurf" => EditorType::Windsurf,
+                    "intellij" => EditorType::IntelliJ,
+                    "zed" => EditorType::Zed,
+                    "custom" => EditorType::Custom,
+                    _ => config_guard.editor.editor_type.clone(),
+                };
+                let temp_config = EditorConfig {
+                    editor_type: override_editor_type,
+                    custom_command: config_guard.editor.custom_command.clone(),
+                };
+                temp_config.get_command()
+            } else {
+                config_guard.editor.get_command()
+            }
+        } else {
+            config_guard.editor.get_command()
+        }
+    };
+
+    // Open editor in the worktree directory
+    let mut cmd = std::process::Command::new(&editor_command[0]);
+    for arg in &editor_command[1..] {
+        cmd.arg(arg);
+    }
+    cmd.arg(&worktree_path);
+
+    match cmd.spawn() {
+        Ok(_) => {
+            tracing::info!(
+                "Opened editor ({}) for task attempt {} at path: {}",
+                editor_command.join(" "),
+                attempt_id,
+                worktree_path
+            );
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: None,
+                message: Some("Editor opened successfully".to_string()),
+            }))
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to open editor ({}) for attempt {}: {}",
+                editor_command.join(" "),
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// The branch status as last computed, with how old it is. A stale one is refreshed in the
+/// background, which the project's event stream announces when done; `?refresh=wait` waits
+/// for a fresh one instead.
+pub async fn get_task_attempt_branch_status(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Query(query): Query<BranchStatusQuery>,
+) -> Result<ResponseJson<ApiResponse<CachedBranchStatus>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    let status = app_state
+        .branch_statuses
+        .get(
+            &app_state.db_pool,
+            &app_state.project_events,
+            project_id,
+            task_id,
+            attempt_id,
+            matches!(query.refresh, Some(BranchStatusRefresh::Wait)),
+        )
+        .await;
+    // Failures are logged by the refresh and kept with the cached status
+    match status {
+        Ok(status) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(status),
+            message: None,
+        })),
+        Err(message) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message),
+        })),
+    }
+}
+
+#[axum::debug_handler]
+pub async fn rebase_task_attempt(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    request_body: Option<Json<RebaseTaskAttemptRequest>>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    // Extract new base branch from request body if provided
+    let new_base_branch = request_body.and_then(|body| body.new_base_branch.clone());
+
+    match TaskAttempt::rebase_attempt(
+        &app_state.db_pool,
+        attempt_id,
+        task_id,
+        project_id,
+        new_base_branch,
+    )
+    .await
+    {
+        Ok(_new_base_commit) => {
+            app_state.branch_statuses.invalidate(attempt_id);
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: None,
+                message: Some("Branch rebased successfully".to_string()),
+            }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to rebase task attempt {}: {}", attempt_id, e);
+            Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            }))
+        }
+    }
+}
+
+pub async fn get_task_attempt_session(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<ExecutorSessionWithDuration>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    // Sessions are ordered oldest first; the latest one is the current session
+    match ExecutorSession::find_by_task_attempt_id(&app_state.db_pool, attempt_id).await {
+        Ok(sessions) => match sessions.into_iter().last() {
+            Some(session) => Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: Some(session.into()),
+                message: None,
+            })),
+            None => Err(StatusCode::NOT_FOUND),
+        },
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch executor sessions for attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_task_attempt_execution_processes(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<ExecutionProcessSummary>>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    match ExecutionProcess::find_summaries_by_task_attempt_id(&app_state.db_pool, attempt_id).await
+    {
+        Ok(mut processes) => {
+            let focus_mode = app_state.focus_mode_status().await;
+            for process in &mut processes {
+                if focus_mode.paused_process_ids.contains(&process.id) {
+                    process.paused_until = focus_mode.until;
+                }
+            }
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: Some(processes),
+                message: None,
+            }))
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch execution processes for attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_execution_process(
+    Path((project_id, process_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<ExecutionProcess>>, StatusCode> {
+    match ExecutionProcess::find_by_id(&app_state.db_pool, process_id).await {
+        Ok(Some(process)) => {
+            // Verify the process belongs to a task attempt in the correct project
+            match TaskAttempt::find_by_id(&app_state.db_pool, process.task_attempt_id).await {
+                Ok(Some(attempt)) => {
+                    match Task::find_by_id(&app_state.db_pool, attempt.task_id).await {
+                        Ok(Some(task)) if task.project_id == project_id => {
+                            Ok(ResponseJson(ApiResponse {
+                                success: true,
+                                data: Some(process),
+                                message: None,
+                            }))
+                        }
+                        Ok(Some(_)) => Err(StatusCode::NOT_FOUND), // Wrong project
+                        Ok(None) => Err(StatusCode::NOT_FOUND),
+                        Err(e) => {
+                            tracing::error!("Failed to fetch task: {}", e);
+                            Err(StatusCode::INTERNAL_SERVER_ERROR)
+                        }
+                    }
+                }
+                Ok(None) => Err(StatusCode::NOT_FOUND),
+                Err(e) => {
+                    tracing::error!("Failed to fetch task attempt: {}", e);
+                    Err(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            }
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[axum::debug_handler]
+pub async fn stop_all_execution_processes(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    payload: Option<Json<StopTaskAttempt>>,
+) -> Result<ResponseJson<ApiResponse<StopAttemptResult>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    stop_and_maybe_restart(&app_state, attempt_id, payload).await
+}
+
+/// Stop all processes of an attempt with an optional note, then restart with the note if
+/// asked to. Like cancelling, this takes a queued attempt out of its project's queue and keeps
+/// the attempt's next stages from starting. Shared by the stop endpoint and stop links.
+async fn stop_and_maybe_restart(
+    app_state: &AppState,
+    attempt_id: Uuid,
+    payload: StopTaskAttempt,
+) -> Result<ResponseJson<ApiResponse<StopAttemptResult>>, StatusCode> {
+    let note = payload
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+    if payload.restart_with_note && note.is_none() {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("Add a note to restart the attempt with".to_string()),
+        }));
+    }
+
+    app_state.cancel_attempt(attempt_id).await;
+    let (stopped_count, errors) = match stop_attempt(app_state, attempt_id, note).await {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!("Failed to stop task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    if !errors.is_empty() {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(format!(
+                "Stopped {} processes, but encountered errors: {}",
+                stopped_count,
+                errors.join(", ")
+            )),
+        }));
+    }
+
+    let restart = match note.filter(|_| payload.restart_with_note) {
+        None => None,
+        Some(note) => {
+            let (attempt, task) = match load_attempt_and_task(app_state, attempt_id).await {
+                Ok(Some(loaded)) => loaded,
+                Ok(None) => return Err(StatusCode::NOT_FOUND),
+                Err(e) => {
+                    tracing::error!("Failed to load task attempt {}: {}", attempt_id, e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+            match restart_with_note(app_state, &attempt, &task, note).await {
+                Ok(restart) => Some(restart),
+                Err(
+                    e @ (TaskAttemptError::ExecutorUnavailable(_)
+                    | TaskAttemptError::ValidationError(_)),
+                ) => {
+                    return Ok(ResponseJson(ApiResponse {
+                        success: false,
+                        data: None,
+                        message: Some(format!(
+                            "Stopped {} processes, but could not restart: {}",
+                            stopped_count, e
+                        )),
+                    }));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to restart task attempt {}: {}", attempt_id, e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+    };
+
+    let message = match (&restart, stopped_count) {
+        (Some(RestartAfterStop::FollowUp { .. }), _) => {
+            "Stopped the attempt and sent the note as a follow-up".to_string()
+        }
+        (Some(RestartAfterStop::RetryAttempt { .. }), _) => {
+            "Stopped the attempt and started a new attempt with the note".to_string()
+        }
+        (None, 0) => "No running processes found to stop".to_string(),
+        (None, count) => format!("Successfully stopped {} execution processes", count),
+    };
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(StopAttemptResult {
+            stopped_processes: stopped_count,
+            restart,
+        }),
+        message: Some(message),
+    }))
+}
+
+/// Cancel an attempt by its id alone: stop what it's running and keep its next stages from
+/// starting
+pub async fn cancel_task_attempt(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<CancelAttemptResult>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let (result, errors) = match cancel_attempt(&app_state, attempt_id).await {
+        Ok(cancelled) => cancelled,
+        Err(e) => {
+            tracing::error!("Failed to cancel task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    if !errors.is_empty() {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: Some(result),
+            message: Some(format!(
+                "Cancelled the attempt, but encountered errors: {}",
+                errors.join(", ")
+            )),
+        }));
+    }
+
+    let message = match (result.stopped_processes, result.dequeued) {
+        (0, false) => "Nothing was running; the attempt won't continue".to_string(),
+        (0, true) => "Removed the attempt from its project's queue".to_string(),
+        (count, _) => format!("Cancelled the attempt and stopped {} processes", count),
+    };
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(result),
+        message: Some(message),
+    }))
+}
+
+async fn load_attempt_and_task(
+    app_state: &AppState,
+    attempt_id: Uuid,
+) -> Result<Option<(TaskAttempt, Task)>, sqlx::Error> {
+    let Some(attempt) = TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await? else {
+        return Ok(None);
+    };
+    let task = Task::find_by_id(&app_state.db_pool, attempt.task_id).await?;
+    Ok(task.map(|task| (attempt, task)))
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct StopLinkDetails {
+    pub attempt_id: Uuid,
+    pub task_id: Uuid,
+    pub project_id: Uuid,
+    pub task_title: String,
+    pub branch: String,
+    pub running: bool,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct CreatedStopLink {
+    /// Path of the confirmation page, relative to the server's address
+    pub path: String,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Create a pre-signed link to stop an attempt, for notifications sent outside the app
+pub async fn create_stop_link(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<CreatedStopLink>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let link = StopLink::new(attempt_id);
+    match link.path() {
+        Ok(path) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(CreatedStopLink {
+                path,
+                expires_at: link.expires_at,
+            }),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("{}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Check a stop link, answering with a failure message for invalid or expired links
+fn verify_stop_link<T>(
+    token: &str,
+) -> Result<StopLink, Result<ResponseJson<ApiResponse<T>>, StatusCode>> {
+    StopLink::from_token(token).map_err(|e| match e {
+        StopLinkError::Invalid | StopLinkError::Expired => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        StopLinkError::Signing(_) => {
+            tracing::error!("{}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    })
+}
+
+/// What a stop link is about to stop, shown on the confirmation page
+pub async fn get_stop_link(
+    Path(token): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<StopLinkDetails>>, StatusCode> {
+    let link = match verify_stop_link(&token) {
+        Ok(link) => link,
+        Err(response) => return response,
+    };
+    let (attempt, task) = match load_attempt_and_task(&app_state, link.attempt_id).await {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to load task attempt {}: {}", link.attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(StopLinkDetails {
+            attempt_id: attempt.id,
+            task_id: task.id,
+            project_id: task.project_id,
+            task_title: task.title,
+            branch: attempt.branch,
+            running: app_state.has_running_execution(attempt.id).await,
+            expires_at: link.expires_at,
+        }),
+        message: None,
+    }))
+}
+
+/// Confirm a stop link: stop the attempt, optionally restarting with a note
+pub async fn confirm_stop_link(
+    Path(token): Path<String>,
+    State(app_state): State<AppState>,
+    payload: Option<Json<StopTaskAttempt>>,
+) -> Result<ResponseJson<ApiResponse<StopAttemptResult>>, StatusCode> {
+    let link = match verify_stop_link(&token) {
+        Ok(link) => link,
+        Err(response) => return response,
+    };
+    match TaskAttempt::find_by_id(&app_state.db_pool, link.attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", link.attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    stop_and_maybe_restart(&app_state, link.attempt_id, payload).await
+}
+
+#[axum::debug_handler]
+pub async fn stop_execution_process(
+    Path((project_id, task_id, attempt_id, process_id)): Path<(Uuid, Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    // Verify execution process exists and belongs to the task attempt
+    let process = match ExecutionProcess::find_by_id(&app_state.db_pool, process_id).await {
+        Ok(Some(process)) if process.task_attempt_id == attempt_id => process,
+        Ok(Some(_)) => return Err(StatusCode::NOT_FOUND), // Process exists but wrong attempt
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
 
-    // Get the task attempt to access the stored base branch
+    // Stop the specific execution process
+    let signal = match app_state.stop_running_execution(process_id).await {
+        Ok(Some(signal)) => signal,
+        Ok(None) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: None,
+                message: Some("Execution process was not running".to_string()),
+            }));
+        }
+        Err(e) => {
+            tracing::error!("Failed to stop execution process {}: {}", process_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // Update the execution process status in the database
+    if let Err(e) = ExecutionProcess::update_completion(
+        &app_state.db_pool,
+        process_id,
+        crate::models::execution_process::ExecutionProcessStatus::Killed,
+        None,
+    )
+    .await
+    {
+        tracing::error!("Failed to update execution process status: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    // Create activity record for stopped processes (skip dev servers and bisect runs)
+    if process.process_type.is_attempt_stage() {
+        let activity_id = Uuid::new_v4();
+        let create_activity = CreateTaskAttemptActivity {
+            execution_process_id: process_id,
+            status: Some(TaskAttemptStatus::ExecutorFailed),
+            note: Some(format!(
+                "Execution process {:?} ({}) stopped by user with {}",
+                process.process_type, process_id, signal
+            )),
+        };
+
+        if let Err(e) = TaskAttemptActivity::create(
+            &app_state.db_pool,
+            &create_activity,
+            activity_id,
+            TaskAttemptStatus::ExecutorFailed,
+        )
+        .await
+        {
+            tracing::error!("Failed to create stopped activity: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: None,
+        message: Some(format!(
+            "Execution process {} stopped successfully ({})",
+            process_id, signal
+        )),
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct DeleteFileQuery {
+    file_path: String,
+}
+
+#[axum::debug_handler]
+pub async fn delete_task_attempt_file(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    Query(query): Query<DeleteFileQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    match TaskAttempt::delete_file(
+        &app_state.db_pool,
+        attempt_id,
+        task_id,
+        project_id,
+        &query.file_path,
+    )
+    .await
+    {
+        Ok(_commit_id) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: None,
+            message: Some(format!("File '{}' deleted successfully", query.file_path)),
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to delete file '{}' from task attempt {}: {}",
+                query.file_path,
+                attempt_id,
+                e
+            );
+            Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            }))
+        }
+    }
+}
+
+pub async fn recreate_task_attempt_worktree(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<String>>, StatusCode> {
+    let project_id = match load_attempt_and_task(&app_state, attempt_id).await {
+        Ok(Some((_, task))) => task.project_id,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to load task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    match TaskAttempt::recreate_worktree(&app_state.db_pool, attempt_id, project_id).await {
+        Ok(worktree_path) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(worktree_path),
+            message: Some("Worktree recreated successfully".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to recreate worktree for task attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(format!("Failed to recreate worktree: {}", e)),
+            }))
+        }
+    }
+}
+
+pub async fn get_worktree_statuses(
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<WorktreeStatus>>>, StatusCode> {
+    match TaskAttempt::find_worktree_statuses(&app_state.db_pool).await {
+        Ok(statuses) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(statuses),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to list worktree statuses: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// The follow-up's prompt, opened with what the bisect run it refers to found. The inner
+/// error explains why the run can't be used.
+async fn followup_prompt(
+    app_state: &AppState,
+    attempt_id: Uuid,
+    payload: &CreateFollowUpAttempt,
+) -> Result<Result<String, String>, StatusCode> {
+    let Some(run_id) = payload.bisect_run_id else {
+        return Ok(Ok(payload.prompt.clone()));
+    };
+    let run = match BisectRun::find_by_id(&app_state.db_pool, run_id).await {
+        Ok(Some(run)) if run.task_attempt_id == attempt_id => run,
+        Ok(_) => {
+            return Ok(Err(
+                "The bisect run doesn't belong to this attempt".to_string()
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch bisect run {}: {}", run_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    Ok(match followup_context(&run) {
+        Some(context) => Ok(format!("{}\n\n{}", context, payload.prompt)),
+        None => Err("The bisect run hasn't found a commit to follow up on".to_string()),
+    })
+}
+
+pub async fn create_followup_attempt(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateFollowUpAttempt>,
+) -> Result<ResponseJson<ApiResponse<FollowUpResponse>>, StatusCode> {
+    // Verify task attempt exists
+    if !TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    start_followup(&app_state, project_id, task_id, attempt_id, &payload).await
+}
+
+/// Follow up on the task's most recent completed attempt, resuming the session its agent
+/// recorded, without the caller having to look the attempt up
+pub async fn resume_latest_followup(
+    Path((project_id, task_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateFollowUpAttempt>,
+) -> Result<ResponseJson<ApiResponse<FollowUpResponse>>, StatusCode> {
+    match Task::exists(&app_state.db_pool, task_id, project_id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let session =
+        match ExecutorSession::find_latest_resumable_for_task(&app_state.db_pool, task_id).await {
+            Ok(Some(session)) => session,
+            Ok(None) => {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(
+                        "No completed attempt of this task has a session to resume".to_string(),
+                    ),
+                }))
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to find the latest session of task {}: {}",
+                    task_id,
+                    e
+                );
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    start_followup(
+        &app_state,
+        project_id,
+        task_id,
+        session.task_attempt_id,
+        &payload,
+    )
+    .await
+}
+
+async fn start_followup(
+    app_state: &AppState,
+    project_id: Uuid,
+    task_id: Uuid,
+    attempt_id: Uuid,
+    payload: &CreateFollowUpAttempt,
+) -> Result<ResponseJson<ApiResponse<FollowUpResponse>>, StatusCode> {
+    let prompt = match followup_prompt(app_state, attempt_id, payload).await? {
+        Ok(prompt) => prompt,
+        Err(message) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(message),
+            }))
+        }
+    };
+
+    // Start follow-up execution synchronously to catch errors
+    match TaskAttempt::start_followup_execution(
+        &app_state.db_pool,
+        app_state,
+        attempt_id,
+        task_id,
+        project_id,
+        &prompt,
+        payload.include_previous_diff,
+    )
+    .await
+    {
+        Ok(actual_attempt_id) => {
+            let created_new_attempt = actual_attempt_id != attempt_id;
+            let message = if created_new_attempt {
+                format!(
+                    "Follow-up execution started on new attempt {} (original worktree was deleted)",
+                    actual_attempt_id
+                )
+            } else {
+                "Follow-up execution started successfully".to_string()
+            };
+
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: Some(FollowUpResponse {
+                    message: message.clone(),
+                    actual_attempt_id,
+                    created_new_attempt,
+                }),
+                message: Some(message),
+            }))
+        }
+        Err(TaskAttemptError::WorktreeMissing(_)) => Ok(worktree_missing_response()),
+        Err(
+            e @ (TaskAttemptError::ExecutorUnavailable(_) | TaskAttemptError::ValidationError(_)),
+        ) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to start follow-up execution for task attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Compose a follow-up's prompt without dispatching it, to check its estimated size and what
+/// was trimmed to fit the executor's token budget
+pub async fn preview_followup_attempt(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateFollowUpAttempt>,
+) -> Result<ResponseJson<ApiResponse<FollowUpPreview>>, StatusCode> {
+    if !TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
     let attempt = match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
         Ok(Some(attempt)) => attempt,
         Ok(None) => return Err(StatusCode::NOT_FOUND),
@@ -356,101 +2695,134 @@ pub async fn create_github_pr(
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
+    let prompt = match followup_prompt(&app_state, attempt_id, &payload).await? {
+        Ok(prompt) => prompt,
+        Err(message) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(message),
+            }))
+        }
+    };
 
-    let base_branch = request.base_branch.unwrap_or_else(|| {
-        // Use the stored base branch from the task attempt as the default
-        // Fall back to config default or "main" only if stored base branch is somehow invalid
-        if !attempt.base_branch.trim().is_empty() {
-            attempt.base_branch.clone()
+    let (prompt, budget) = ProcessService::compose_followup_prompt(
+        &app_state.db_pool,
+        &app_state,
+        &attempt,
+        task_id,
+        project_id,
+        &prompt,
+        payload.include_previous_diff,
+    )
+    .await;
+    let message = (!budget.within_budget()).then(|| {
+        format!(
+            "The follow-up is about {} tokens, over the {} token budget even after trimming its context",
+            budget.estimated_tokens, budget.budget
+        )
+    });
+
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(FollowUpPreview { prompt, budget }),
+        message,
+    }))
+}
+
+pub async fn start_dev_server(
+    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    // Verify task attempt exists and belongs to the correct task
+    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task attempt existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    // Stop any existing dev servers for this project
+    let existing_dev_servers =
+        match ExecutionProcess::find_running_dev_servers_by_project(&app_state.db_pool, project_id)
+            .await
+        {
+            Ok(servers) => servers,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to find running dev servers for project {}: {}",
+                    project_id,
+                    e
+                );
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    for dev_server in existing_dev_servers {
+        tracing::info!(
+            "Stopping existing dev server {} for project {}",
+            dev_server.id,
+            project_id
+        );
+
+        // Stop the running process
+        if let Err(e) = app_state.stop_running_execution_by_id(dev_server.id).await {
+            tracing::error!("Failed to stop dev server {}: {}", dev_server.id, e);
         } else {
-            config
-                .github
-                .default_pr_base
-                .unwrap_or_else(|| "main".to_string())
+            // Update the execution process status in the database
+            if let Err(e) = ExecutionProcess::update_completion(
+                &app_state.db_pool,
+                dev_server.id,
+                crate::models::execution_process::ExecutionProcessStatus::Killed,
+                None,
+            )
+            .await
+            {
+                tracing::error!(
+                    "Failed to update dev server {} status: {}",
+                    dev_server.id,
+                    e
+                );
+            }
         }
-    });
+    }
 
-    match TaskAttempt::create_github_pr(
+    // Start dev server execution
+    match TaskAttempt::start_dev_server(
         &app_state.db_pool,
-        CreatePrParams {
-            attempt_id,
-            task_id,
-            project_id,
-            github_token: &config.github.pat.unwrap_or(github_token),
-            title: &request.title,
-            body: request.body.as_deref(),
-            base_branch: Some(&base_branch),
-        },
+        &app_state,
+        attempt_id,
+        task_id,
+        project_id,
     )
     .await
     {
-        Ok(pr_url) => {
-            app_state
-                .track_analytics_event(
-                    "github_pr_created",
-                    Some(serde_json::json!({
-                        "task_id": task_id.to_string(),
-                        "project_id": project_id.to_string(),
-                        "attempt_id": attempt_id.to_string(),
-                    })),
-                )
-                .await;
-
-            Ok(ResponseJson(ApiResponse {
-                success: true,
-                data: Some(pr_url),
-                message: Some("GitHub PR created successfully".to_string()),
-            }))
-        }
+        Ok(_) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: None,
+            message: Some("Dev server started successfully".to_string()),
+        })),
         Err(e) => {
             tracing::error!(
-                "Failed to create GitHub PR for attempt {}: {}",
+                "Failed to start dev server for task attempt {}: {}",
                 attempt_id,
                 e
             );
-            let message = match &e {
-                crate::models::task_attempt::TaskAttemptError::GitHubService(
-                    crate::services::GitHubServiceError::TokenInvalid,
-                ) => Some("github_token_invalid".to_string()),
-                crate::models::task_attempt::TaskAttemptError::GitService(
-                    crate::services::git_service::GitServiceError::Git(err),
-                ) if err
-                    .message()
-                    .contains("too many redirects or authentication replays") =>
-                {
-                    Some("insufficient_github_permissions".to_string()) // PAT is invalid
-                }
-                crate::models::task_attempt::TaskAttemptError::GitService(
-                    crate::services::git_service::GitServiceError::Git(err),
-                ) if err.message().contains("status code: 403") => {
-                    Some("insufficient_github_permissions".to_string())
-                }
-                crate::models::task_attempt::TaskAttemptError::GitService(
-                    crate::services::git_service::GitServiceError::Git(err),
-                ) if err.message().contains("status code: 404") => {
-                    Some("github_repo_not_found_or_no_access".to_string())
-                }
-                _ => Some(format!("Failed to create PR: {}", e)),
-            };
             Ok(ResponseJson(ApiResponse {
                 success: false,
                 data: None,
-                message,
+                message: Some(e.to_string()),
             }))
         }
     }
 }
 
-#[derive(serde::Deserialize)]
-pub struct OpenEditorRequest {
-    editor_type: Option<String>,
-}
-
-pub async fn open_task_attempt_in_editor(
+pub async fn get_task_attempt_execution_state(
     Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
     State(app_state): State<AppState>,
-    Json(payload): Json<Option<OpenEditorRequest>>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+) -> Result<ResponseJson<ApiResponse<TaskAttemptState>>, StatusCode> {
     // Verify task attempt exists and belongs to the correct task
     match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
         Ok(false) => return Err(StatusCode::NOT_FOUND),
@@ -461,70 +2833,21 @@ pub async fn open_task_attempt_in_editor(
         Ok(true) => {}
     }
 
-    // Get the task attempt to access the worktree path
-    let attempt = match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
-        Ok(Some(attempt)) => attempt,
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
-
-    // Get editor command from config or override
-    let editor_command = {
-        let config_guard = app_state.get_config().read().await;
-        if let Some(ref request) = payload {
-            if let Some(ref editor_type) = request.editor_type {
-                // Create a temporary editor config with the override
-                use crate::models::config::{EditorConfig, EditorType};
-                let override_editor_type = match editor_type.as_str() {
-                    "vscode" => EditorType::VSCode,
-                    "cursor" => EditorType::Cursor,
-                    "windsurf" => EditorType::Windsurf,
-                    "intellij" => EditorType::IntelliJ,
-                    "zed" => EditorType::Zed,
-                    "custom" => EditorType::Custom,
-                    _ => config_guard.editor.editor_type.clone(),
-                };
-                let temp_config = EditorConfig {
-                    editor_type: override_editor_type,
-                    custom_command: config_guard.editor.custom_command.clone(),
-                };
-                temp_config.get_command()
-            } else {
-                config_guard.editor.get_command()
-            }
-        } else {
-            config_guard.editor.get_command()
-        }
-    };
-
-    // Open editor in the worktree directory
-    let mut cmd = std::process::Command::new(&editor_command[0]);
-    for arg in &editor_command[1..] {
-        cmd.arg(arg);
-    }
-    cmd.arg(&attempt.worktree_path);
-
-    match cmd.spawn() {
-        Ok(_) => {
-            tracing::info!(
-                "Opened editor ({}) for task attempt {} at path: {}",
-                editor_command.join(" "),
-                attempt_id,
-                attempt.worktree_path
-            );
+    // Get the execution state
+    match TaskAttempt::get_execution_state(&app_state.db_pool, attempt_id, task_id, project_id)
+        .await
+    {
+        Ok(mut state) => {
+            state.paused_until = app_state.attempt_paused_until(attempt_id).await;
             Ok(ResponseJson(ApiResponse {
                 success: true,
-                data: None,
-                message: Some("Editor opened successfully".to_string()),
+                data: Some(state),
+                message: None,
             }))
         }
         Err(e) => {
             tracing::error!(
-                "Failed to open editor ({}) for attempt {}: {}",
-                editor_command.join(" "),
+                "Failed to get execution state for task attempt {}: {}",
                 attempt_id,
                 e
             );
@@ -533,30 +2856,36 @@ pub async fn open_task_attempt_in_editor(
     }
 }
 
-pub async fn get_task_attempt_branch_status(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+/// Re-parse the stored output of an attempt's finished processes with the current parsers
+pub async fn renormalize_task_attempt(
+    Path(attempt_id): Path<Uuid>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<BranchStatus>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
+) -> Result<ResponseJson<ApiResponse<Vec<RenormalizeResult>>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        Ok(true) => {}
     }
 
-    match TaskAttempt::get_branch_status(&app_state.db_pool, attempt_id, task_id, project_id).await
-    {
-        Ok(status) => Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: Some(status),
-            message: None,
-        })),
+    match renormalize_attempt(&app_state.db_pool, attempt_id).await {
+        Ok(results) => {
+            let failed = results.iter().filter(|r| r.error.is_some()).count();
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                message: Some(format!(
+                    "Re-normalized {} execution(s), {} failed",
+                    results.len() - failed,
+                    failed
+                )),
+                data: Some(results),
+            }))
+        }
         Err(e) => {
             tracing::error!(
-                "Failed to get branch status for task attempt {}: {}",
+                "Failed to re-normalize logs for task attempt {}: {}",
                 attempt_id,
                 e
             );
@@ -565,74 +2894,63 @@ pub async fn get_task_attempt_branch_status(
     }
 }
 
-#[axum::debug_handler]
-pub async fn rebase_task_attempt(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+pub async fn annotate_task_attempt(
+    Path(attempt_id): Path<Uuid>,
     State(app_state): State<AppState>,
-    request_body: Option<Json<RebaseTaskAttemptRequest>>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
+    Json(payload): Json<CreateAnnotation>,
+) -> Result<ResponseJson<ApiResponse<Annotation>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        Ok(true) => {}
     }
 
-    // Extract new base branch from request body if provided
-    let new_base_branch = request_body.and_then(|body| body.new_base_branch.clone());
+    if payload.entry_index < 0 {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("entry_index must not be negative".to_string()),
+        }));
+    }
 
-    match TaskAttempt::rebase_attempt(
-        &app_state.db_pool,
-        attempt_id,
-        task_id,
-        project_id,
-        new_base_branch,
-    )
-    .await
-    {
-        Ok(_new_base_commit) => Ok(ResponseJson(ApiResponse {
+    match Annotation::create(&app_state.db_pool, attempt_id, &payload).await {
+        Ok(annotation) => Ok(ResponseJson(ApiResponse {
             success: true,
-            data: None,
-            message: Some("Branch rebased successfully".to_string()),
+            data: Some(annotation),
+            message: Some("Annotation added".to_string()),
         })),
         Err(e) => {
-            tracing::error!("Failed to rebase task attempt {}: {}", attempt_id, e);
-            Ok(ResponseJson(ApiResponse {
-                success: false,
-                data: None,
-                message: Some(e.to_string()),
-            }))
+            tracing::error!("Failed to annotate task attempt {}: {}", attempt_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-pub async fn get_task_attempt_execution_processes(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+pub async fn get_task_attempt_annotations(
+    Path(attempt_id): Path<Uuid>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<Vec<ExecutionProcessSummary>>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
+) -> Result<ResponseJson<ApiResponse<Vec<Annotation>>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        Ok(true) => {}
     }
 
-    match ExecutionProcess::find_summaries_by_task_attempt_id(&app_state.db_pool, attempt_id).await
-    {
-        Ok(processes) => Ok(ResponseJson(ApiResponse {
+    match Annotation::find_by_attempt_id(&app_state.db_pool, attempt_id).await {
+        Ok(annotations) => Ok(ResponseJson(ApiResponse {
             success: true,
-            data: Some(processes),
+            data: Some(annotations),
             message: None,
         })),
         Err(e) => {
             tracing::error!(
-                "Failed to fetch execution processes for attempt {}: {}",
+                "Failed to fetch annotations for task attempt {}: {}",
                 attempt_id,
                 e
             );
@@ -641,187 +2959,98 @@ pub async fn get_task_attempt_execution_processes(
     }
 }
 
-pub async fn get_execution_process(
-    Path((project_id, process_id)): Path<(Uuid, Uuid)>,
+/// The attempt's format runs, newest first, with any formatter failures they flagged
+pub async fn get_task_attempt_format_runs(
+    Path(attempt_id): Path<Uuid>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<ExecutionProcess>>, StatusCode> {
-    match ExecutionProcess::find_by_id(&app_state.db_pool, process_id).await {
-        Ok(Some(process)) => {
-            // Verify the process belongs to a task attempt in the correct project
-            match TaskAttempt::find_by_id(&app_state.db_pool, process.task_attempt_id).await {
-                Ok(Some(attempt)) => {
-                    match Task::find_by_id(&app_state.db_pool, attempt.task_id).await {
-                        Ok(Some(task)) if task.project_id == project_id => {
-                            Ok(ResponseJson(ApiResponse {
-                                success: true,
-                                data: Some(process),
-                                message: None,
-                            }))
-                        }
-                        Ok(Some(_)) => Err(StatusCode::NOT_FOUND), // Wrong project
-                        Ok(None) => Err(StatusCode::NOT_FOUND),
-                        Err(e) => {
-                            tracing::error!("Failed to fetch task: {}", e);
-                            Err(StatusCode::INTERNAL_SERVER_ERROR)
-                        }
-                    }
-                }
-                Ok(None) => Err(StatusCode::NOT_FOUND),
-                Err(e) => {
-                    tracing::error!("Failed to fetch task attempt: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
-            }
+) -> Result<ResponseJson<ApiResponse<Vec<FormatRun>>>, StatusCode> {
+    match TaskAttempt::find_by_id(&app_state.db_pool, attempt_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
+    }
+
+    match FormatRun::find_by_task_attempt_id(&app_state.db_pool, attempt_id).await {
+        Ok(runs) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(runs),
+            message: None,
+        })),
         Err(e) => {
-            tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
+            tracing::error!(
+                "Failed to fetch format runs of task attempt {}: {}",
+                attempt_id,
+                e
+            );
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-#[axum::debug_handler]
-pub async fn stop_all_execution_processes(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+/// Hosts the attempt's agents contacted through its project's logging proxy, and whether one
+/// of them was denylisted
+pub async fn get_task_attempt_network(
+    Path(attempt_id): Path<Uuid>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
+) -> Result<ResponseJson<ApiResponse<AttemptNetworkReport>>, StatusCode> {
+    let pool = &app_state.db_pool;
+    let policy = match NetworkPolicy::find_for_attempt(pool, attempt_id).await {
+        Ok(Some(policy)) => policy,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        Ok(true) => {}
-    }
-
-    // Get all execution processes for the task attempt
-    let processes =
-        match ExecutionProcess::find_by_task_attempt_id(&app_state.db_pool, attempt_id).await {
-            Ok(processes) => processes,
-            Err(e) => {
-                tracing::error!(
-                    "Failed to fetch execution processes for attempt {}: {}",
-                    attempt_id,
-                    e
-                );
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
-
-    let mut stopped_count = 0;
-    let mut errors = Vec::new();
-
-    // Stop all running processes
-    for process in processes {
-        match app_state.stop_running_execution_by_id(process.id).await {
-            Ok(true) => {
-                stopped_count += 1;
+    };
 
-                // Update the execution process status in the database
-                if let Err(e) = ExecutionProcess::update_completion(
-                    &app_state.db_pool,
-                    process.id,
-                    crate::models::execution_process::ExecutionProcessStatus::Killed,
-                    None,
-                )
-                .await
-                {
-                    tracing::error!("Failed to update execution process status: {}", e);
-                    errors.push(format!("Failed to update process {} status", process.id));
-                } else {
-                    // Create activity record for stopped processes (skip dev servers)
-                    if !matches!(
-                        process.process_type,
-                        crate::models::execution_process::ExecutionProcessType::DevServer
-                    ) {
-                        let activity_id = Uuid::new_v4();
-                        let create_activity = CreateTaskAttemptActivity {
-                            execution_process_id: process.id,
-                            status: Some(TaskAttemptStatus::ExecutorFailed),
-                            note: Some(format!(
-                                "Execution process {:?} ({}) stopped by user",
-                                process.process_type, process.id
-                            )),
-                        };
-
-                        if let Err(e) = TaskAttemptActivity::create(
-                            &app_state.db_pool,
-                            &create_activity,
-                            activity_id,
-                            TaskAttemptStatus::ExecutorFailed,
-                        )
-                        .await
-                        {
-                            tracing::error!("Failed to create stopped activity: {}", e);
-                            errors.push(format!(
-                                "Failed to create activity for process {}",
-                                process.id
-                            ));
-                        }
-                    }
-                }
-            }
-            Ok(false) => {
-                // Process was not running, which is fine
-            }
-            Err(e) => {
-                tracing::error!("Failed to stop execution process {}: {}", process.id, e);
-                errors.push(format!("Failed to stop process {}: {}", process.id, e));
-            }
+    match NetworkContact::find_by_task_attempt_id(pool, attempt_id).await {
+        Ok(contacts) => {
+            let policy_violation = contacts
+                .iter()
+                .any(|contact| contact.verdict == NetworkVerdict::Denied);
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: Some(AttemptNetworkReport {
+                    enabled: policy.enabled,
+                    policy_violation,
+                    contacts,
+                }),
+                message: None,
+            }))
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch network contacts of task attempt {}: {}",
+                attempt_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
-
-    if !errors.is_empty() {
-        return Ok(ResponseJson(ApiResponse {
-            success: false,
-            data: None,
-            message: Some(format!(
-                "Stopped {} processes, but encountered errors: {}",
-                stopped_count,
-                errors.join(", ")
-            )),
-        }));
-    }
-
-    if stopped_count == 0 {
-        return Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: None,
-            message: Some("No running processes found to stop".to_string()),
-        }));
-    }
-
-    Ok(ResponseJson(ApiResponse {
-        success: true,
-        data: None,
-        message: Some(format!(
-            "Successfully stopped {} execution processes",
-            stopped_count
-        )),
-    }))
 }
 
-#[axum::debug_handler]
-pub async fn stop_execution_process(
-    Path((project_id, task_id, attempt_id, process_id)): Path<(Uuid, Uuid, Uuid, Uuid)>,
+pub async fn get_execution_process_normalized_logs(
+    Path((project_id, process_id)): Path<(Uuid, Uuid)>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
+    Query(filter): Query<LogFilterQuery>,
+) -> Result<ResponseJson<ApiResponse<FilteredConversation>>, StatusCode> {
+    let filter = match LogFilter::try_from(filter) {
+        Ok(filter) => filter,
         Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            }))
         }
-        Ok(true) => {}
-    }
+    };
 
-    // Verify execution process exists and belongs to the task attempt
+    // Get the execution process and verify it belongs to the correct project
     let process = match ExecutionProcess::find_by_id(&app_state.db_pool, process_id).await {
-        Ok(Some(process)) if process.task_attempt_id == attempt_id => process,
-        Ok(Some(_)) => return Err(StatusCode::NOT_FOUND), // Process exists but wrong attempt
+        Ok(Some(process)) => process,
         Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
             tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
@@ -829,177 +3058,271 @@ pub async fn stop_execution_process(
         }
     };
 
-    // Stop the specific execution process
-    let stopped = match app_state.stop_running_execution_by_id(process_id).await {
-        Ok(stopped) => stopped,
+    // Verify the process belongs to a task attempt in the correct project
+    let attempt = match TaskAttempt::find_by_id(&app_state.db_pool, process.task_attempt_id).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            tracing::error!("Failed to stop execution process {}: {}", process_id, e);
+            tracing::error!("Failed to fetch task attempt: {}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    if !stopped {
-        return Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: None,
-            message: Some("Execution process was not running".to_string()),
-        }));
-    }
-
-    // Update the execution process status in the database
-    if let Err(e) = ExecutionProcess::update_completion(
-        &app_state.db_pool,
-        process_id,
-        crate::models::execution_process::ExecutionProcessStatus::Killed,
-        None,
-    )
-    .await
-    {
-        tracing::error!("Failed to update execution process status: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-
-    // Create activity record for stopped processes (skip dev servers)
-    if !matches!(
-        process.process_type,
-        crate::models::execution_process::ExecutionProcessType::DevServer
-    ) {
-        let activity_id = Uuid::new_v4();
-        let create_activity = CreateTaskAttemptActivity {
-            execution_process_id: process_id,
-            status: Some(TaskAttemptStatus::ExecutorFailed),
-            note: Some(format!(
-                "Execution process {:?} ({}) stopped by user",
-                process.process_type, process_id
-            )),
-        };
-
-        if let Err(e) = TaskAttemptActivity::create(
-            &app_state.db_pool,
-            &create_activity,
-            activity_id,
-            TaskAttemptStatus::ExecutorFailed,
-        )
-        .await
-        {
-            tracing::error!("Failed to create stopped activity: {}", e);
+    let _task = match Task::find_by_id(&app_state.db_pool, attempt.task_id).await {
+        Ok(Some(task)) if task.project_id == project_id => task,
+        Ok(Some(_)) => return Err(StatusCode::NOT_FOUND), // Wrong project
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task: {}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-    }
-
-    Ok(ResponseJson(ApiResponse {
-        success: true,
-        data: None,
-        message: Some(format!(
-            "Execution process {} stopped successfully",
-            process_id
-        )),
-    }))
-}
+    };
 
-#[derive(serde::Deserialize)]
-pub struct DeleteFileQuery {
-    file_path: String,
+    match current_normalization(&app_state.db_pool, &process).await {
+        Ok(conversation) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(filter.apply(conversation)),
+            message: None,
+        })),
+        Err(NormalizationError::Database(e)) => {
+            tracing::error!("Failed to load normalized logs for {}: {}", process_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(e) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+    }
 }
-
-#[axum::debug_handler]
-pub async fn delete_task_attempt_file(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
-    Query(query): Query<DeleteFileQuery>,
+
+/// Record that an attempt's worktree was moved, e.g. along with its project directory
+pub async fn relocate_task_attempt_worktree(
+    Path(attempt_id): Path<Uuid>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
+    Json(payload): Json<RelocateWorktree>,
+) -> Result<ResponseJson<ApiResponse<WorktreeRelocation>>, StatusCode> {
+    let pool = &app_state.db_pool;
+    let attempt = match TaskAttempt::find_by_id(pool, attempt_id).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        Ok(true) => {}
-    }
+    };
+    let task = match Task::find_by_id(pool, attempt.task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task {}: {}", attempt.task_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let project = match Project::find_by_id(pool, task.project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", task.project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
 
-    match TaskAttempt::delete_file(
-        &app_state.db_pool,
-        attempt_id,
-        task_id,
-        project_id,
-        &query.file_path,
+    match TaskAttempt::relocate_worktree(
+        pool,
+        &attempt,
+        &project.git_repo_path,
+        std::path::Path::new(&payload.new_path),
     )
     .await
     {
-        Ok(_commit_id) => Ok(ResponseJson(ApiResponse {
+        Ok(relocation) => Ok(ResponseJson(ApiResponse {
             success: true,
+            data: Some(relocation),
+            message: Some("Worktree path updated".to_string()),
+        })),
+        Err(TaskAttemptError::ValidationError(reason)) => Ok(ResponseJson(ApiResponse {
+            success: false,
             data: None,
-            message: Some(format!("File '{}' deleted successfully", query.file_path)),
+            message: Some(reason),
         })),
         Err(e) => {
             tracing::error!(
-                "Failed to delete file '{}' from task attempt {}: {}",
-                query.file_path,
+                "Failed to relocate worktree of task attempt {}: {}",
                 attempt_id,
                 e
             );
-            Ok(ResponseJson(ApiResponse {
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Download an attempt's commits as a tar of a patch series or a bundle plus a manifest.
+/// Refused with 409 when there's nothing committed to export or the worktree has uncommitted
+/// changes, since those wouldn't be part of the export.
+pub async fn export_task_attempt(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Query(query): Query<ExportAttemptQuery>,
+) -> Result<Response, StatusCode> {
+    let pool = &app_state.db_pool;
+    let attempt = match TaskAttempt::find_by_id(pool, attempt_id).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let task = match Task::find_by_id(pool, attempt.task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task {}: {}", attempt.task_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let project = match Project::find_by_id(pool, task.project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", task.project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    match export_attempt(&project.git_repo_path, &attempt, &task, query.format).await {
+        Ok(export) => {
+            tracing::info!(
+                "Exported {} commits of task attempt {} since {}",
+                export.manifest.commit_count,
+                attempt_id,
+                export.manifest.base_sha
+            );
+            Response::builder()
+                .header(header::CONTENT_TYPE, "application/x-tar")
+                .header(
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", export.file_name),
+                )
+                .body(Body::from(export.archive))
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(
+            e @ (AttemptExportError::UncommittedChanges
+            | AttemptExportError::NoCommits
+            | AttemptExportError::Git(GitServiceError::BranchNotFound(_))),
+        ) => Ok((
+            StatusCode::CONFLICT,
+            ResponseJson(ApiResponse::<()> {
                 success: false,
                 data: None,
                 message: Some(e.to_string()),
-            }))
+            }),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!("Failed to export task attempt {}: {}", attempt_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-pub async fn create_followup_attempt(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+/// Apply a reviewed patch series onto a new attempt of an existing task. Patches that don't
+/// apply are reported in the result rather than failing the import.
+pub async fn import_patch_series(
     State(app_state): State<AppState>,
-    Json(payload): Json<CreateFollowUpAttempt>,
-) -> Result<ResponseJson<ApiResponse<FollowUpResponse>>, StatusCode> {
-    // Verify task attempt exists
-    if !TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to check task attempt existence: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-    {
-        return Err(StatusCode::NOT_FOUND);
+    Json(payload): Json<ImportPatchSeries>,
+) -> Result<ResponseJson<ApiResponse<PatchImportResult>>, StatusCode> {
+    let patches: Vec<String> = payload
+        .patches
+        .iter()
+        .flat_map(|patches| split_patch_series(patches))
+        .collect();
+    if patches.is_empty() {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("No patches to import".to_string()),
+        }));
     }
 
-    // Start follow-up execution synchronously to catch errors
-    match TaskAttempt::start_followup_execution(
-        &app_state.db_pool,
-        &app_state,
-        attempt_id,
-        task_id,
-        project_id,
-        &payload.prompt,
+    let pool = &app_state.db_pool;
+    let task = match Task::find_by_id(pool, payload.task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task {}: {}", payload.task_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let project = match Project::find_by_id(pool, task.project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", task.project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let worktree_name_pattern = app_state
+        .get_config()
+        .read()
+        .await
+        .worktree_name_pattern
+        .clone();
+    let create_attempt = CreateTaskAttempt {
+        executor: None,
+        base_branch: payload.base_branch.clone(),
+        env: Default::default(),
+        retry_of: None,
+    };
+    let attempt =
+        match TaskAttempt::create(pool, &create_attempt, task.id, &worktree_name_pattern).await {
+            Ok(attempt) => attempt,
+            Err(TaskAttemptError::GitService(
+                e @ (GitServiceError::BranchNotFound(_)
+                | GitServiceError::DetachedHead(_)
+                | GitServiceError::Worktree(_)),
+            )) => {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(e.to_string()),
+                }))
+            }
+            Err(e) => {
+                tracing::error!("Failed to create attempt for patch import: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    match apply_patch_series(
+        &project.git_repo_path,
+        std::path::Path::new(&attempt.worktree_path),
+        &patches,
     )
     .await
     {
-        Ok(actual_attempt_id) => {
-            let created_new_attempt = actual_attempt_id != attempt_id;
-            let message = if created_new_attempt {
-                format!(
-                    "Follow-up execution started on new attempt {} (original worktree was deleted)",
-                    actual_attempt_id
-                )
-            } else {
-                "Follow-up execution started successfully".to_string()
-            };
-
+        Ok(results) => {
+            let applied = results.iter().filter(|result| result.applied).count();
             Ok(ResponseJson(ApiResponse {
                 success: true,
-                data: Some(FollowUpResponse {
-                    message: message.clone(),
-                    actual_attempt_id,
-                    created_new_attempt,
+                message: Some(format!(
+                    "Applied {} of {} patches to a new attempt",
+                    applied,
+                    results.len()
+                )),
+                data: Some(PatchImportResult {
+                    attempt,
+                    patches: results,
                 }),
-                message: Some(message),
             }))
         }
         Err(e) => {
             tracing::error!(
-                "Failed to start follow-up execution for task attempt {}: {}",
-                attempt_id,
+                "Failed to import patches into attempt {}: {}",
+                attempt.id,
                 e
             );
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -1007,367 +3330,359 @@ pub async fn create_followup_attempt(
     }
 }
 
-pub async fn start_dev_server(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
-    State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
+async fn find_attempt_with_project(
+    app_state: &AppState,
+    attempt_id: Uuid,
+) -> Result<(TaskAttempt, Task, Project), StatusCode> {
+    let pool = &app_state.db_pool;
+    let attempt = match TaskAttempt::find_by_id(pool, attempt_id).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
+            tracing::error!("Failed to fetch task attempt {}: {}", attempt_id, e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        Ok(true) => {}
-    }
-
-    // Stop any existing dev servers for this project
-    let existing_dev_servers =
-        match ExecutionProcess::find_running_dev_servers_by_project(&app_state.db_pool, project_id)
-            .await
-        {
-            Ok(servers) => servers,
-            Err(e) => {
-                tracing::error!(
-                    "Failed to find running dev servers for project {}: {}",
-                    project_id,
-                    e
-                );
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
+    };
+    let task = match Task::find_by_id(pool, attempt.task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch task {}: {}", attempt.task_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let project = match Project::find_by_id(pool, task.project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", task.project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    Ok((attempt, task, project))
+}
 
-    for dev_server in existing_dev_servers {
-        tracing::info!(
-            "Stopping existing dev server {} for project {}",
-            dev_server.id,
-            project_id
-        );
+/// Download an attempt's changes since its merge base as one patch, without its commits, to
+/// apply onto any branch. Refused with 409 for the same reasons as an export.
+pub async fn get_task_attempt_patch(
+    Path(attempt_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let (attempt, _, project) = find_attempt_with_project(&app_state, attempt_id).await?;
 
-        // Stop the running process
-        if let Err(e) = app_state.stop_running_execution_by_id(dev_server.id).await {
-            tracing::error!("Failed to stop dev server {}: {}", dev_server.id, e);
-        } else {
-            // Update the execution process status in the database
-            if let Err(e) = ExecutionProcess::update_completion(
-                &app_state.db_pool,
-                dev_server.id,
-                crate::models::execution_process::ExecutionProcessStatus::Killed,
-                None,
+    match attempt_diff_patch(&project.git_repo_path, &attempt).await {
+        Ok(patch) => Response::builder()
+            .header(header::CONTENT_TYPE, "text/x-diff; charset=utf-8")
+            .header(
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"attempt-{}.patch\"",
+                    &attempt.id.simple().to_string()[..8]
+                ),
             )
-            .await
-            {
-                tracing::error!(
-                    "Failed to update dev server {} status: {}",
-                    dev_server.id,
-                    e
-                );
-            }
-        }
-    }
-
-    // Start dev server execution
-    match TaskAttempt::start_dev_server(
-        &app_state.db_pool,
-        &app_state,
-        attempt_id,
-        task_id,
-        project_id,
-    )
-    .await
-    {
-        Ok(_) => Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: None,
-            message: Some("Dev server started successfully".to_string()),
-        })),
+            .body(Body::from(patch))
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR),
+        Err(
+            e @ (AttemptExportError::UncommittedChanges
+            | AttemptExportError::NoCommits
+            | AttemptExportError::Git(GitServiceError::BranchNotFound(_))),
+        ) => Ok((
+            StatusCode::CONFLICT,
+            ResponseJson(ApiResponse::<()> {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            }),
+        )
+            .into_response()),
         Err(e) => {
             tracing::error!(
-                "Failed to start dev server for task attempt {}: {}",
+                "Failed to create patch of task attempt {}: {}",
                 attempt_id,
                 e
             );
-            Ok(ResponseJson(ApiResponse {
-                success: false,
-                data: None,
-                message: Some(e.to_string()),
-            }))
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-pub async fn get_task_attempt_execution_state(
-    Path((project_id, task_id, attempt_id)): Path<(Uuid, Uuid, Uuid)>,
+/// Apply an attempt's changes as one commit onto another branch, in a new attempt of the same
+/// task. When they conflict, the new attempt is left at the branch and the conflicting files
+/// are reported.
+pub async fn apply_task_attempt_to_branch(
+    Path(attempt_id): Path<Uuid>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<TaskAttemptState>>, StatusCode> {
-    // Verify task attempt exists and belongs to the correct task
-    match TaskAttempt::exists_for_task(&app_state.db_pool, attempt_id, task_id, project_id).await {
-        Ok(false) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to check task attempt existence: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-        Ok(true) => {}
+    Json(payload): Json<ApplyAttemptToBranch>,
+) -> Result<ResponseJson<ApiResponse<BranchApplyResult>>, StatusCode> {
+    let (source, task, project) = find_attempt_with_project(&app_state, attempt_id).await?;
+    let base_branch = payload.base_branch.trim();
+    if base_branch.is_empty() {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some("Choose a branch to apply the changes onto".to_string()),
+        }));
     }
 
-    // Get the execution state
-    match TaskAttempt::get_execution_state(&app_state.db_pool, attempt_id, task_id, project_id)
-        .await
-    {
-        Ok(state) => Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: Some(state),
-            message: None,
-        })),
+    let patch = match attempt_diff_patch(&project.git_repo_path, &source).await {
+        Ok(patch) => patch,
+        Err(
+            e @ (AttemptExportError::UncommittedChanges
+            | AttemptExportError::NoCommits
+            | AttemptExportError::Git(GitServiceError::BranchNotFound(_))),
+        ) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(e.to_string()),
+            }))
+        }
         Err(e) => {
             tracing::error!(
-                "Failed to get execution state for task attempt {}: {}",
+                "Failed to create patch of task attempt {}: {}",
                 attempt_id,
                 e
             );
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
-}
-
-pub async fn get_execution_process_normalized_logs(
-    Path((project_id, process_id)): Path<(Uuid, Uuid)>,
-    State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<NormalizedConversation>>, StatusCode> {
-    // Get the execution process and verify it belongs to the correct project
-    let process = match ExecutionProcess::find_by_id(&app_state.db_pool, process_id).await {
-        Ok(Some(process)) => process,
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to fetch execution process {}: {}", process_id, e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
-
-    // Verify the process belongs to a task attempt in the correct project
-    let attempt = match TaskAttempt::find_by_id(&app_state.db_pool, process.task_attempt_id).await {
-        Ok(Some(attempt)) => attempt,
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to fetch task attempt: {}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    let _task = match Task::find_by_id(&app_state.db_pool, attempt.task_id).await {
-        Ok(Some(task)) if task.project_id == project_id => task,
-        Ok(Some(_)) => return Err(StatusCode::NOT_FOUND), // Wrong project
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to fetch task: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+    let pool = &app_state.db_pool;
+    let worktree_name_pattern = app_state
+        .get_config()
+        .read()
+        .await
+        .worktree_name_pattern
+        .clone();
+    let create_attempt = CreateTaskAttempt {
+        executor: source.executor.clone(),
+        base_branch: Some(base_branch.to_string()),
+        env: Default::default(),
+        retry_of: None,
     };
-
-    // Get executor session data for this execution process
-    let executor_session =
-        match ExecutorSession::find_by_execution_process_id(&app_state.db_pool, process_id).await {
-            Ok(session) => session,
+    let attempt =
+        match TaskAttempt::create(pool, &create_attempt, task.id, &worktree_name_pattern).await {
+            Ok(attempt) => attempt,
+            Err(TaskAttemptError::GitService(
+                e @ (GitServiceError::BranchNotFound(_)
+                | GitServiceError::DetachedHead(_)
+                | GitServiceError::Worktree(_)),
+            )) => {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(e.to_string()),
+                }))
+            }
             Err(e) => {
-                tracing::error!(
-                    "Failed to fetch executor session for process {}: {}",
-                    process_id,
-                    e
-                );
-                None
+                tracing::error!("Failed to create attempt to apply changes onto: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
             }
         };
 
-    // Handle the case where no logs are available
-    let has_stdout =
-        process.stdout.is_some() && !process.stdout.as_ref().unwrap().trim().is_empty();
-    let has_stderr =
-        process.stderr.is_some() && !process.stderr.as_ref().unwrap().trim().is_empty();
-
-    // If the process is still running and has no stdout/stderr, return empty logs
-    if process.status == ExecutionProcessStatus::Running && !has_stdout && !has_stderr {
-        return Ok(ResponseJson(ApiResponse {
+    let message = format!("{}\n\nApplied from {}", task.title, source.branch);
+    let provenance_mode = app_state.get_config().read().await.provenance;
+    let message = match Provenance::load(pool, &source).await {
+        Ok(provenance) => provenance.stamp_commit_message(&message, provenance_mode),
+        Err(e) => {
+            tracing::warn!("Applying attempt {} without provenance: {}", attempt_id, e);
+            message
+        }
+    };
+    match apply_diff_patch(
+        &project.git_repo_path,
+        std::path::Path::new(&attempt.worktree_path),
+        &patch,
+        &message,
+    )
+    .await
+    {
+        Ok(result) => Ok(ResponseJson(ApiResponse {
             success: true,
-            data: Some(NormalizedConversation {
-                entries: vec![],
-                session_id: None,
-                executor_type: process
-                    .executor_type
-                    .clone()
-                    .unwrap_or("unknown".to_string()),
-                prompt: executor_session.as_ref().and_then(|s| s.prompt.clone()),
-                summary: executor_session.as_ref().and_then(|s| s.summary.clone()),
+            message: Some(if result.applied {
+                format!("Applied the changes onto {} in a new attempt", base_branch)
+            } else if result.conflicts.is_empty() {
+                format!("The changes don't apply onto {}", base_branch)
+            } else {
+                format!(
+                    "The changes conflict with {} in {} files",
+                    base_branch,
+                    result.conflicts.len()
+                )
             }),
-            message: None,
-        }));
-    }
-
-    // If process is completed but has no logs, return appropriate error
-    if process.status != ExecutionProcessStatus::Running && !has_stdout && !has_stderr {
-        return Ok(ResponseJson(ApiResponse {
-            success: false,
-            data: None,
-            message: Some("No logs available for this execution process".to_string()),
-        }));
+            data: Some(BranchApplyResult { attempt, result }),
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to apply changes of attempt {} onto attempt {}: {}",
+                attempt_id,
+                attempt.id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
     }
+}
 
-    // Parse stdout as JSONL using executor normalization
-    let mut stdout_entries = Vec::new();
-    if let Some(stdout) = &process.stdout {
-        if !stdout.trim().is_empty() {
-            // Determine executor type and create appropriate executor for normalization
-            let executor_type = process.executor_type.as_deref().unwrap_or("unknown");
-
-            let executor_config = if process.process_type == ExecutionProcessType::SetupScript {
-                // For setup scripts, use the setup script executor
-                ExecutorConfig::SetupScript {
-                    script: executor_session
-                        .as_ref()
-                        .and_then(|s| s.prompt.clone())
-                        .unwrap_or_else(|| "setup script".to_string()),
-                }
-            } else {
-                match executor_type {
-                    "amp" => ExecutorConfig::Amp,
-                    "claude" => ExecutorConfig::Claude,
-                    "echo" => ExecutorConfig::Echo,
-                    "gemini" => ExecutorConfig::Gemini,
-                    "opencode" => ExecutorConfig::Opencode,
-                    _ => {
-                        tracing::warn!(
-                            "Unsupported executor type: {}, cannot normalize logs properly",
-                            executor_type
-                        );
-                        return Ok(ResponseJson(ApiResponse {
-                            success: false,
-                            data: None,
-                            message: Some(format!("Unsupported executor type: {}", executor_type)),
-                        }));
-                    }
-                }
-            };
+const DEFAULT_ATTEMPT_LIST_LIMIT: u32 = 50;
+const MAX_ATTEMPT_LIST_LIMIT: u32 = 200;
 
-            let executor = executor_config.create_executor();
+#[derive(Debug, Deserialize)]
+pub struct ListAttemptsQuery {
+    pub project_id: Option<Uuid>,
+    /// An `AttemptRunStatus`; parsed by hand to say which ones exist
+    #[serde(alias = "outcome")]
+    pub status: Option<String>,
+    pub executor_type: Option<String>,
+    /// Only attempts created at or after this RFC 3339 time, or date (UTC midnight)
+    pub created_after: Option<String>,
+    /// Only attempts created before this RFC 3339 time, or date (UTC midnight)
+    pub created_before: Option<String>,
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page
+    pub before: Option<String>,
+}
 
-            // Use the working directory path for normalization
-            // Try to canonicalize if the directory exists, otherwise use the stored path as-is
-            let working_dir_path = match std::fs::canonicalize(&process.working_directory) {
-                Ok(canonical_path) => {
-                    tracing::debug!(
-                        "Using canonical path for normalization: {}",
-                        canonical_path.display()
-                    );
-                    canonical_path.to_string_lossy().to_string()
-                }
-                Err(_) => {
-                    tracing::debug!(
-                            "Working directory {} no longer exists, using stored path for normalization",
-                            process.working_directory
-                        );
-                    process.working_directory.clone()
-                }
-            };
+/// A time bound of an attempt list: an RFC 3339 time, or a date for its start in UTC
+fn parse_created_bound(param: &str, value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_| {
+            format!(
+                "Invalid {} '{}': expected a date like 2025-07-01 or an RFC 3339 time",
+                param, value
+            )
+        })
+}
 
-            // Normalize stdout logs with error handling
-            match executor.normalize_logs(stdout, &working_dir_path) {
-                Ok(normalized) => {
-                    stdout_entries = normalized.entries;
-                    tracing::debug!(
-                        "Successfully normalized {} stdout entries for process {}",
-                        stdout_entries.len(),
-                        process_id
-                    );
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to normalize stdout for process {}: {}",
-                        process_id,
-                        e
-                    );
-                    return Ok(ResponseJson(ApiResponse {
-                        success: false,
-                        data: None,
-                        message: Some(format!("Failed to normalize logs: {}", e)),
-                    }));
-                }
-            }
+/// Turn the query into a list filter, or say what's wrong with it
+fn attempt_list_filter(
+    project_id: Option<Uuid>,
+    query: ListAttemptsQuery,
+) -> Result<TaskAttemptListFilter, String> {
+    let status = query
+        .status
+        .as_deref()
+        .map(str::parse::<AttemptRunStatus>)
+        .transpose()?;
+    let created_after = query
+        .created_after
+        .as_deref()
+        .map(|value| parse_created_bound("created_after", value))
+        .transpose()?;
+    let created_before = query
+        .created_before
+        .as_deref()
+        .map(|value| parse_created_bound("created_before", value))
+        .transpose()?;
+    if let (Some(after), Some(before)) = (created_after, created_before) {
+        if after >= before {
+            return Err("created_after must be earlier than created_before".to_string());
         }
     }
 
-    // Parse stderr chunks separated by boundary markers
-    let mut stderr_entries = Vec::new();
-    if let Some(stderr) = &process.stderr {
-        let trimmed = stderr.trim();
-        if !trimmed.is_empty() {
-            // Split stderr by chunk boundaries and create separate error messages
-            let chunks: Vec<&str> = trimmed.split("---STDERR_CHUNK_BOUNDARY---").collect();
-
-            for chunk in chunks {
-                let chunk_trimmed = chunk.trim();
-                if !chunk_trimmed.is_empty() {
-                    // Filter out any remaining boundary markers from the chunk content
-                    let filtered_content = chunk_trimmed.replace("---STDERR_CHUNK_BOUNDARY---", "");
-                    if !filtered_content.trim().is_empty() {
-                        stderr_entries.push(NormalizedEntry {
-                            timestamp: Some(chrono::Utc::now().to_rfc3339()),
-                            entry_type: NormalizedEntryType::ErrorMessage,
-                            content: filtered_content.trim().to_string(),
-                            metadata: None,
-                        });
-                    }
-                }
+    Ok(TaskAttemptListFilter {
+        project_id,
+        status,
+        executor_type: query.executor_type,
+        created_after,
+        created_before,
+    })
+}
+
+async fn list_attempts(
+    app_state: &AppState,
+    project_id: Option<Uuid>,
+    query: ListAttemptsQuery,
+) -> Result<ResponseJson<ApiResponse<TaskAttemptListPage>>, StatusCode> {
+    let before = match query.before.as_deref() {
+        None => None,
+        Some(cursor) => match TaskAttemptListCursor::decode(cursor) {
+            Some(cursor) => Some(cursor),
+            None => {
+                return Ok(ResponseJson(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: Some(format!("Invalid cursor '{}'", cursor)),
+                }));
             }
+        },
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_ATTEMPT_LIST_LIMIT)
+        .clamp(1, MAX_ATTEMPT_LIST_LIMIT);
+    let filter = match attempt_list_filter(project_id, query) {
+        Ok(filter) => filter,
+        Err(message) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some(message),
+            }));
+        }
+    };
 
-            tracing::debug!(
-                "Processed stderr content into {} error messages for process {}",
-                stderr_entries.len(),
-                process_id
-            );
+    match TaskAttempt::list_across_projects(&app_state.db_pool, &filter, before.as_ref(), limit)
+        .await
+    {
+        Ok(page) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(page),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to list task attempts: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
+}
 
-    // Merge stdout and stderr entries chronologically
-    let mut all_entries = Vec::new();
-    all_entries.extend(stdout_entries);
-    all_entries.extend(stderr_entries);
-
-    // Sort by timestamp (entries without timestamps go to the end)
-    all_entries.sort_by(|a, b| match (&a.timestamp, &b.timestamp) {
-        (Some(a_ts), Some(b_ts)) => a_ts.cmp(b_ts),
-        (Some(_), None) => std::cmp::Ordering::Less,
-        (None, Some(_)) => std::cmp::Ordering::Greater,
-        (None, None) => std::cmp::Ordering::Equal,
-    });
-
-    // Create final normalized conversation
-    let executor_type = if process.process_type == ExecutionProcessType::SetupScript {
-        "setup_script".to_string()
-    } else {
-        process
-            .executor_type
-            .clone()
-            .unwrap_or("unknown".to_string())
-    };
-
-    let normalized_conversation = NormalizedConversation {
-        entries: all_entries,
-        session_id: None,
-        executor_type,
-        prompt: executor_session.as_ref().and_then(|s| s.prompt.clone()),
-        summary: executor_session.as_ref().and_then(|s| s.summary.clone()),
-    };
+pub async fn list_all_task_attempts(
+    Query(mut query): Query<ListAttemptsQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<TaskAttemptListPage>>, StatusCode> {
+    let project_id = query.project_id.take();
+    list_attempts(&app_state, project_id, query).await
+}
 
-    Ok(ResponseJson(ApiResponse {
-        success: true,
-        data: Some(normalized_conversation),
-        message: None,
-    }))
+/// A project's attempts across its tasks, newest first, e.g. yesterday's failed ones with
+/// `?status=failed&created_after=2025-07-01&created_before=2025-07-02`
+pub async fn list_project_attempts(
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<ListAttemptsQuery>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<TaskAttemptListPage>>, StatusCode> {
+    match Project::find_by_id(&app_state.db_pool, project_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    list_attempts(&app_state, Some(project_id), query).await
 }
 
 pub fn task_attempts_router() -> Router<AppState> {
-    use axum::routing::post;
+    use axum::routing::{post, put};
 
     Router::new()
+        .route("/task-attempts", get(list_all_task_attempts))
+        .route("/projects/:project_id/attempts", get(list_project_attempts))
+        .route("/task-attempts/:attempt_id/stop", post(cancel_task_attempt))
+        .route("/task-attempts/:attempt_id/stop-link", post(create_stop_link))
+        .route(
+            "/task-attempts/:attempt_id/untracked-changes",
+            get(get_task_attempt_untracked_changes),
+        )
+        .route(
+            "/stop-links/:token",
+            get(get_stop_link).post(confirm_stop_link),
+        )
         .route(
             "/projects/:project_id/tasks/:task_id/attempts",
             get(get_task_attempts).post(create_task_attempt),
@@ -1376,11 +3691,80 @@ pub fn task_attempts_router() -> Router<AppState> {
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/activities",
             get(get_task_attempt_activities).post(create_task_attempt_activity),
         )
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/retries",
+            get(get_task_attempt_retries),
+        )
 
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/logs/ws",
+            get(stream_task_attempt_logs_ws),
+        )
         .route(
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/diff",
             get(get_task_attempt_diff),
         )
+        .route(
+            "/task-attempts/:attempt_id/diff",
+            get(get_task_attempt_execution_diff),
+        )
+        .route(
+            "/task-attempts/:attempt_id/diff-stream",
+            get(stream_task_attempt_diff),
+        )
+        .route(
+            "/task-attempts/:attempt_id/logs/stream",
+            get(stream_task_attempt_logs),
+        )
+        .route(
+            "/task-attempts/:attempt_id/wait",
+            get(wait_for_task_attempt),
+        )
+        .route(
+            "/task-attempts/:attempt_id/renormalize",
+            post(renormalize_task_attempt),
+        )
+        .route(
+            "/task-attempts/:attempt_id/annotate",
+            post(annotate_task_attempt),
+        )
+        .route(
+            "/task-attempts/:attempt_id/annotations",
+            get(get_task_attempt_annotations),
+        )
+        .route(
+            "/task-attempts/:attempt_id/format-runs",
+            get(get_task_attempt_format_runs),
+        )
+        .route(
+            "/task-attempts/:attempt_id/network",
+            get(get_task_attempt_network),
+        )
+        .route(
+            "/task-attempts/:attempt_id/session",
+            get(get_task_attempt_session),
+        )
+        .route(
+            "/task-attempts/:attempt_id/recreate-worktree",
+            post(recreate_task_attempt_worktree),
+        )
+        .route(
+            "/task-attempts/:attempt_id/export",
+            get(export_task_attempt),
+        )
+        .route("/task-attempts/import-patch", post(import_patch_series))
+        .route(
+            "/task-attempts/:attempt_id/patch",
+            get(get_task_attempt_patch),
+        )
+        .route(
+            "/task-attempts/:attempt_id/apply-to-branch",
+            post(apply_task_attempt_to_branch),
+        )
+        .route(
+            "/task-attempts/:attempt_id/worktree-path",
+            put(relocate_task_attempt_worktree),
+        )
         .route(
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/merge",
             post(merge_task_attempt),
@@ -1429,12 +3813,358 @@ pub fn task_attempts_router() -> Router<AppState> {
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/follow-up",
             post(create_followup_attempt),
         )
+        .route(
+            "/projects/:project_id/tasks/:task_id/follow-up",
+            post(resume_latest_followup),
+        )
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/follow-up/preview",
+            post(preview_followup_attempt),
+        )
         .route(
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/start-dev-server",
             post(start_dev_server),
         )
+        .route("/maintenance/worktrees", get(get_worktree_statuses))
         .route(
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id",
             get(get_task_attempt_execution_state),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app_state::QueuedAttempt,
+        executor::OutputFormat,
+        executors::custom::CustomExecutor,
+        models::{
+            config::DEFAULT_WORKTREE_NAME_PATTERN, execution_process::CreateExecutionProcess,
+        },
+        test_utils::{insert_project, insert_task, memory_pool, seeded_pool, Seeded},
+    };
+
+    #[test]
+    fn test_attempt_list_query_is_validated() {
+        let query = |query: &str| {
+            let uri: axum::http::Uri = format!("/attempts?{}", query).parse().unwrap();
+            Query::<ListAttemptsQuery>::try_from_uri(&uri).unwrap().0
+        };
+
+        let filter = attempt_list_filter(
+            None,
+            query("outcome=failed&created_after=2025-07-01&created_before=2025-07-01T18:00:00%2B02:00"),
+        )
+        .unwrap();
+        assert_eq!(filter.status, Some(AttemptRunStatus::Failed));
+        assert_eq!(
+            filter.created_after.unwrap().to_rfc3339(),
+            "2025-07-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            filter.created_before.unwrap().to_rfc3339(),
+            "2025-07-01T16:00:00+00:00"
+        );
+
+        let error = attempt_list_filter(None, query("status=broken")).unwrap_err();
+        assert!(error.contains("not_started, running, completed, failed, killed"));
+        assert!(attempt_list_filter(None, query("created_after=yesterday")).is_err());
+        assert!(attempt_list_filter(
+            None,
+            query("created_after=2025-07-02&created_before=2025-07-01")
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_resumes_after_offset_and_flushes_the_last_line() {
+        let (pool, Seeded { attempt_id, .. }) = seeded_pool().await;
+        let process_id = Uuid::new_v4();
+        ExecutionProcess::create(
+            &pool,
+            &CreateExecutionProcess {
+                task_attempt_id: attempt_id,
+                process_type: ExecutionProcessType::CodingAgent,
+                executor_type: Some("custom".to_string()),
+                command: "agent".to_string(),
+                args: None,
+                working_directory: "/tmp".to_string(),
+            },
+            process_id,
+        )
+        .await
+        .unwrap();
+        ExecutionProcess::append_stdout(&pool, process_id, "first\nsecond\n")
+            .await
+            .unwrap();
+
+        // The client saw the entry of the first line
+        let mut followed = FollowedProcess {
+            id: process_id,
+            normalizer: IncrementalNormalizer::new(
+                Box::new(CustomExecutor {
+                    prompt: None,
+                    output_format: OutputFormat::Text,
+                }),
+                "/tmp",
+            ),
+            offset: 0,
+            resume_from: "first\n".len(),
+            reads_since_exit: 0,
+        };
+        let mut pending = VecDeque::new();
+        assert_eq!(
+            read_followed(&pool, &mut followed, &mut pending).await,
+            Some(false)
+        );
+        assert_eq!(followed.offset, "first\nsecond\n".len());
+        assert_eq!(followed.normalizer.entries().len(), 2);
+        assert_eq!(pending.len(), 1);
+
+        ExecutionProcess::append_stdout(&pool, process_id, "third")
+            .await
+            .unwrap();
+        ExecutionProcess::update_completion(
+            &pool,
+            process_id,
+            ExecutionProcessStatus::Completed,
+            Some(0),
+        )
+        .await
+        .unwrap();
+        let mut ended = None;
+        for _ in 0..=LOG_STREAM_READS_AFTER_EXIT {
+            ended = read_followed(&pool, &mut followed, &mut pending).await;
+        }
+        assert_eq!(ended, Some(true));
+        assert_eq!(pending.len(), 2);
+        assert_eq!(
+            followed.normalizer.entries().last().unwrap().content,
+            "third"
+        );
+    }
+
+    /// Follow the next process the stream selects to the end of its output, returning it and
+    /// the contents of its entries
+    async fn follow_to_end(log_stream: &mut LogStream) -> (Uuid, Vec<String>) {
+        log_stream.follow_next().await;
+        let followed = log_stream.followed.as_mut().expect("a process to follow");
+        let mut pending = VecDeque::new();
+        while read_followed(&log_stream.app_state.db_pool, followed, &mut pending).await
+            == Some(false)
+        {}
+        let contents = followed
+            .normalizer
+            .entries()
+            .iter()
+            .map(|entry| entry.content.clone())
+            .collect();
+        (followed.id, contents)
+    }
+
+    #[tokio::test]
+    async fn test_stopping_a_queued_attempt_takes_it_out_of_the_queue() {
+        let pool = memory_pool().await;
+        let config = Config {
+            analytics_enabled: Some(false),
+            max_concurrent_per_project: Some(1),
+            ..Config::default()
+        };
+        let app_state =
+            AppState::new(pool, std::sync::Arc::new(tokio::sync::RwLock::new(config))).await;
+        let project_id = Uuid::new_v4();
+        let attempt = || QueuedAttempt {
+            attempt_id: Uuid::new_v4(),
+            task_id: Uuid::new_v4(),
+            project_id,
+        };
+        let (running, queued) = (attempt(), attempt());
+        assert!(app_state.try_reserve_project_slot(running).await);
+        app_state
+            .project_slot_started(running.attempt_id, true)
+            .await;
+        assert!(!app_state.try_reserve_project_slot(queued).await);
+
+        let response =
+            stop_and_maybe_restart(&app_state, queued.attempt_id, StopTaskAttempt::default())
+                .await
+                .unwrap();
+        assert!(response.0.success);
+        assert!(!app_state.is_attempt_queued(queued.attempt_id).await);
+        assert!(app_state.is_attempt_cancelled(queued.attempt_id));
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_selects_setup_output_apart_from_the_agent() {
+        let (
+            pool,
+            Seeded {
+                project_id,
+                task_id,
+                attempt_id,
+            },
+        ) = seeded_pool().await;
+        let config = Config {
+            analytics_enabled: Some(false),
+            ..Config::default()
+        };
+        let app_state = AppState::new(
+            pool.clone(),
+            std::sync::Arc::new(tokio::sync::RwLock::new(config)),
+        )
+        .await;
+
+        let mut processes = Vec::new();
+        for (process_type, executor_type, stdout) in [
+            (
+                ExecutionProcessType::SetupScript,
+                None,
+                "installing dependencies\n",
+            ),
+            (
+                ExecutionProcessType::CodingAgent,
+                Some("custom".to_string()),
+                "agent output\n",
+            ),
+            (ExecutionProcessType::DevServer, None, "listening on 3000\n"),
+        ] {
+            let id = Uuid::new_v4();
+            ExecutionProcess::create(
+                &pool,
+                &CreateExecutionProcess {
+                    task_attempt_id: attempt_id,
+                    process_type,
+                    executor_type,
+                    command: "sh".to_string(),
+                    args: OutputFormat::Text.process_args(),
+                    working_directory: "/tmp".to_string(),
+                },
+                id,
+            )
+            .await
+            .unwrap();
+            ExecutionProcess::append_stdout(&pool, id, stdout)
+                .await
+                .unwrap();
+            ExecutionProcess::update_completion(
+                &pool,
+                id,
+                ExecutionProcessStatus::Completed,
+                Some(0),
+            )
+            .await
+            .unwrap();
+            processes.push(id);
+        }
+
+        let log_stream = |process_type| LogStream {
+            app_state: app_state.clone(),
+            attempt_id,
+            task_id,
+            project_id,
+            process_id: None,
+            process_type,
+            _slot: app_state.try_acquire_log_stream(attempt_id).unwrap(),
+            followed: None,
+            last_followed: None,
+            since: 0,
+            pending: VecDeque::new(),
+            polled: false,
+            finished: false,
+        };
+
+        let (id, setup_output) =
+            follow_to_end(&mut log_stream(Some(ExecutionProcessType::SetupScript))).await;
+        assert_eq!(id, processes[0]);
+        assert!(setup_output
+            .iter()
+            .any(|content| content.contains("installing dependencies")));
+        assert!(!setup_output
+            .iter()
+            .any(|content| content.contains("agent output")));
+
+        let (id, agent_output) =
+            follow_to_end(&mut log_stream(Some(ExecutionProcessType::CodingAgent))).await;
+        assert_eq!(id, processes[1]);
+        assert_eq!(agent_output, vec!["agent output".to_string()]);
+
+        let mut dev_server_stream = log_stream(Some(ExecutionProcessType::DevServer));
+        let (id, dev_server_output) = follow_to_end(&mut dev_server_stream).await;
+        assert_eq!(id, processes[2]);
+        assert_eq!(dev_server_output, vec!["listening on 3000".to_string()]);
+        // Nothing else of the type runs, so the stream is done
+        dev_server_stream.followed = None;
+        dev_server_stream.follow_next().await;
+        assert!(dev_server_stream.finished);
+    }
+
+    #[tokio::test]
+    async fn test_diff_of_a_deleted_worktree_asks_for_it_to_be_recreated() {
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init(repo_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, &repo_dir.path().to_string_lossy()).await;
+        let task_id = insert_task(&pool, project_id, "t").await;
+        let attempt = TaskAttempt::create(
+            &pool,
+            &CreateTaskAttempt {
+                executor: None,
+                base_branch: None,
+                env: std::collections::HashMap::new(),
+                retry_of: None,
+            },
+            task_id,
+            DEFAULT_WORKTREE_NAME_PATTERN,
+        )
+        .await
+        .unwrap();
+        std::fs::remove_dir_all(&attempt.worktree_path).unwrap();
+        let config = Config {
+            analytics_enabled: Some(false),
+            ..Config::default()
+        };
+        let app_state = AppState::new(
+            pool.clone(),
+            std::sync::Arc::new(tokio::sync::RwLock::new(config)),
+        )
+        .await;
+        let diff = || {
+            get_task_attempt_diff(
+                Path((project_id, task_id, attempt.id)),
+                Query(DiffQuery {
+                    formatting: FormattingDiff::All,
+                }),
+                State(app_state.clone()),
+            )
+        };
+
+        let missing = diff().await.unwrap().0;
+        let recreated = recreate_task_attempt_worktree(Path(attempt.id), State(app_state.clone()))
+            .await
+            .unwrap()
+            .0;
+        let after = diff().await.unwrap().0;
+        let _ = std::fs::remove_dir_all(&attempt.worktree_path);
+
+        assert!(!missing.success);
+        assert_eq!(missing.message.as_deref(), Some(WORKTREE_MISSING_MESSAGE));
+        assert!(recreated.success);
+        assert_eq!(recreated.data, Some(attempt.worktree_path.clone()));
+        assert!(after.success);
+    }
+}
@@ -1,21 +1,30 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::Json as ResponseJson,
+    response::{IntoResponse, Json as ResponseJson, Response},
     routing::get,
     Json, Router,
 };
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::{
     app_state::AppState,
     execution_monitor,
+    executor::ExecutorConfig,
     models::{
         project::Project,
-        task::{CreateTask, CreateTaskAndStart, Task, TaskWithAttemptStatus, UpdateTask},
-        task_attempt::{CreateTaskAttempt, TaskAttempt},
+        task::{
+            is_valid_estimate, CreateTask, CreateTaskAndStart, Task, TaskConflict,
+            TaskEstimateReport, TaskUpdateOutcome, TaskUpdateSource, TaskWithAttemptStatus,
+            UpdateTask,
+        },
+        task_attempt::{CreateTaskAttempt, TaskAttempt, TaskAttemptError},
+        task_dependency::{CreateTaskDependency, TaskDependency, TaskDependencyError},
         ApiResponse,
     },
+    routes::task_attempts::{repo_default_executor, source_repo_warning},
+    utils::executable::ExecutableLookup,
 };
 
 pub async fn get_project_tasks(
@@ -35,6 +44,35 @@ pub async fn get_project_tasks(
     }
 }
 
+pub async fn get_task_estimates(
+    Path(project_id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<TaskEstimateReport>>>, StatusCode> {
+    match Task::find_estimate_reports(&app_state.db_pool, project_id).await {
+        Ok(reports) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(reports),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch task estimates for project {}: {}",
+                project_id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn invalid_estimate_response<T>() -> ResponseJson<ApiResponse<T>> {
+    ResponseJson(ApiResponse {
+        success: false,
+        data: None,
+        message: Some("Estimate must be a non-negative number".to_string()),
+    })
+}
+
 pub async fn get_task(
     Path((project_id, task_id)): Path<(Uuid, Uuid)>,
     State(app_state): State<AppState>,
@@ -68,6 +106,10 @@ pub async fn create_task(
     // Ensure the project_id in the payload matches the path parameter
     payload.project_id = project_id;
 
+    if !is_valid_estimate(payload.estimate) {
+        return Ok(invalid_estimate_response());
+    }
+
     // Verify project exists first
     match Project::exists(&app_state.db_pool, project_id).await {
         Ok(false) => return Err(StatusCode::NOT_FOUND),
@@ -86,6 +128,8 @@ pub async fn create_task(
 
     match Task::create(&app_state.db_pool, &payload, id).await {
         Ok(task) => {
+            app_state.project_events.task_changed(&task);
+
             // Track task creation event
             app_state
                 .track_analytics_event(
@@ -94,6 +138,7 @@ pub async fn create_task(
                         "task_id": task.id.to_string(),
                         "project_id": project_id.to_string(),
                         "has_description": task.description.is_some(),
+                        "task_title": task.title,
                     })),
                 )
                 .await;
@@ -121,6 +166,10 @@ pub async fn create_task_and_start(
     // Ensure the project_id in the payload matches the path parameter
     payload.project_id = project_id;
 
+    if !is_valid_estimate(payload.estimate) {
+        return Ok(invalid_estimate_response());
+    }
+
     // Verify project exists first
     match Project::exists(&app_state.db_pool, project_id).await {
         Ok(false) => return Err(StatusCode::NOT_FOUND),
@@ -131,6 +180,44 @@ pub async fn create_task_and_start(
         Ok(true) => {}
     }
 
+    // Without an executor, the repo's default is used, then the server's
+    let executor = match &payload.executor {
+        Some(executor) => Some(executor.clone()),
+        None => repo_default_executor(&app_state, project_id)
+            .await
+            .and_then(|name| name.parse::<ExecutorConfig>().ok()),
+    };
+    let executor = match executor {
+        Some(executor) => executor,
+        None => app_state.get_config().read().await.executor.clone(),
+    };
+
+    // Don't leave a task behind when its attempt can't start
+    let (executor_check, block_on_dirty_repo) = {
+        let config = app_state.get_config().read().await;
+        (
+            config
+                .check_executor_enabled(&executor)
+                .and_then(|()| executor.check_available(&ExecutableLookup::from_env())),
+            config.block_attempts_on_dirty_repo,
+        )
+    };
+    if let Err(reason) = executor_check {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(reason),
+        }));
+    }
+    let dirty_repo_warning = source_repo_warning(&app_state, project_id).await;
+    if let (Some(warning), true) = (&dirty_repo_warning, block_on_dirty_repo) {
+        return Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(warning.clone()),
+        }));
+    }
+
     tracing::debug!(
         "Creating and starting task '{}' in project {}",
         payload.title,
@@ -142,6 +229,7 @@ pub async fn create_task_and_start(
         project_id: payload.project_id,
         title: payload.title.clone(),
         description: payload.description.clone(),
+        estimate: payload.estimate,
     };
     let task = match Task::create(&app_state.db_pool, &create_task_payload, task_id).await {
         Ok(task) => task,
@@ -150,15 +238,31 @@ pub async fn create_task_and_start(
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
+    app_state.project_events.task_changed(&task);
 
     // Create task attempt
-    let executor_string = payload.executor.as_ref().map(|exec| exec.to_string());
+    let executor_string = Some(executor.to_string());
     let attempt_payload = CreateTaskAttempt {
         executor: executor_string.clone(),
         base_branch: None, // Not supported in task creation endpoint, only in task attempts
+        env: Default::default(),
+        retry_of: None,
     };
 
-    match TaskAttempt::create(&app_state.db_pool, &attempt_payload, task_id).await {
+    let worktree_name_pattern = app_state
+        .get_config()
+        .read()
+        .await
+        .worktree_name_pattern
+        .clone();
+    match TaskAttempt::create(
+        &app_state.db_pool,
+        &attempt_payload,
+        task_id,
+        &worktree_name_pattern,
+    )
+    .await
+    {
         Ok(attempt) => {
             app_state
                 .track_analytics_event(
@@ -167,6 +271,7 @@ pub async fn create_task_and_start(
                         "task_id": task.id.to_string(),
                         "project_id": project_id.to_string(),
                         "has_description": task.description.is_some(),
+                        "task_title": task.title,
                     })),
                 )
                 .await;
@@ -178,6 +283,8 @@ pub async fn create_task_and_start(
                         "task_id": task.id.to_string(),
                         "executor_type": executor_string.as_deref().unwrap_or("default"),
                         "attempt_id": attempt.id.to_string(),
+                        // Redacted before sending; only a hash goes out when enabled
+                        "task_title": task.title,
                     })),
                 )
                 .await;
@@ -206,9 +313,17 @@ pub async fn create_task_and_start(
             Ok(ResponseJson(ApiResponse {
                 success: true,
                 data: Some(task),
-                message: Some("Task created and started successfully".to_string()),
+                message: Some(
+                    dirty_repo_warning
+                        .unwrap_or_else(|| "Task created and started successfully".to_string()),
+                ),
             }))
         }
+        Err(e @ TaskAttemptError::ExecutorUnavailable(_)) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
         Err(e) => {
             tracing::error!("Failed to create task attempt: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -216,42 +331,86 @@ pub async fn create_task_and_start(
     }
 }
 
+/// 409 with the task as it is now, for the client to merge into and retry
+async fn task_conflict_response(app_state: &AppState, conflict: TaskConflict) -> Response {
+    track_task_conflict(app_state, &conflict.current, false).await;
+    let fields: Vec<_> = conflict
+        .conflicting_fields
+        .iter()
+        .map(|field| format!("{:?}", field).to_lowercase())
+        .collect();
+    let message = if fields.is_empty() {
+        "The task changed since it was loaded".to_string()
+    } else {
+        format!(
+            "The task's {} changed since it was loaded",
+            fields.join(", ")
+        )
+    };
+    (
+        StatusCode::CONFLICT,
+        ResponseJson(ApiResponse {
+            success: false,
+            data: Some(conflict),
+            message: Some(message),
+        }),
+    )
+        .into_response()
+}
+
+async fn track_task_conflict(app_state: &AppState, task: &Task, merged: bool) {
+    app_state
+        .track_analytics_event(
+            "task_update_conflict",
+            Some(serde_json::json!({
+                "task_id": task.id.to_string(),
+                "project_id": task.project_id.to_string(),
+                "merged": merged,
+                "source": TaskUpdateSource::Api.as_str(),
+            })),
+        )
+        .await;
+}
+
 pub async fn update_task(
     Path((project_id, task_id)): Path<(Uuid, Uuid)>,
     State(app_state): State<AppState>,
     Json(payload): Json<UpdateTask>,
-) -> Result<ResponseJson<ApiResponse<Task>>, StatusCode> {
-    // Check if task exists in the specified project
-    let existing_task =
-        match Task::find_by_id_and_project_id(&app_state.db_pool, task_id, project_id).await {
-            Ok(Some(task)) => task,
-            Ok(None) => return Err(StatusCode::NOT_FOUND),
-            Err(e) => {
-                tracing::error!("Failed to check task existence: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
-
-    // Use existing values if not provided in update
-    let title = payload.title.unwrap_or(existing_task.title);
-    let description = payload.description.or(existing_task.description);
-    let status = payload.status.unwrap_or(existing_task.status);
+) -> Result<Response, StatusCode> {
+    if !is_valid_estimate(payload.estimate) {
+        return Ok(invalid_estimate_response::<Task>().into_response());
+    }
 
-    match Task::update(
+    match Task::update_checked(
         &app_state.db_pool,
         task_id,
         project_id,
-        title,
-        description,
-        status,
+        &payload,
+        TaskUpdateSource::Api,
     )
     .await
     {
-        Ok(task) => Ok(ResponseJson(ApiResponse {
-            success: true,
-            data: Some(task),
-            message: Some("Task updated successfully".to_string()),
-        })),
+        Ok(Some(TaskUpdateOutcome::Updated { task, merged })) => {
+            app_state.project_events.task_changed(&task);
+            if merged {
+                track_task_conflict(&app_state, &task, true).await;
+            }
+            let message = if merged {
+                "Task updated, merged with changes made since it was loaded"
+            } else {
+                "Task updated successfully"
+            };
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                data: Some(task),
+                message: Some(message.to_string()),
+            })
+            .into_response())
+        }
+        Ok(Some(TaskUpdateOutcome::Conflict(conflict))) => {
+            Ok(task_conflict_response(&app_state, conflict).await)
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
             tracing::error!("Failed to update task: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -259,18 +418,41 @@ pub async fn update_task(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DeleteTaskQuery {
+    /// Version of the task the deletion is based on; it's refused if the task changed since
+    pub expected_version: Option<i64>,
+}
+
 pub async fn delete_task(
     Path((project_id, task_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<DeleteTaskQuery>,
     State(app_state): State<AppState>,
-) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+) -> Result<Response, StatusCode> {
     // Verify task exists in the specified project
-    match Task::find_by_id_and_project_id(&app_state.db_pool, task_id, project_id).await {
-        Ok(Some(_)) => {} // Task exists, proceed
+    let task = match Task::find_by_id_and_project_id(&app_state.db_pool, task_id, project_id).await
+    {
+        Ok(Some(task)) => task,
         Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
             tracing::error!("Failed to check task existence: {}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
+    };
+    match Task::version_conflict(
+        &app_state.db_pool,
+        &task,
+        query.expected_version,
+        TaskUpdateSource::Api,
+    )
+    .await
+    {
+        Ok(Some(conflict)) => return Ok(task_conflict_response(&app_state, conflict).await),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to check task version: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
     }
 
     // Clean up all worktrees for this task before deletion
@@ -315,11 +497,13 @@ pub async fn delete_task(
             if rows_affected == 0 {
                 Err(StatusCode::NOT_FOUND)
             } else {
-                Ok(ResponseJson(ApiResponse {
+                app_state.project_events.task_deleted(project_id, task_id);
+                Ok(ResponseJson(ApiResponse::<()> {
                     success: true,
                     data: None,
                     message: Some("Task deleted successfully".to_string()),
-                }))
+                })
+                .into_response())
             }
         }
         Err(e) => {
@@ -329,14 +513,119 @@ pub async fn delete_task(
     }
 }
 
+pub async fn get_task_dependencies(
+    Path((project_id, task_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<TaskDependency>>>, StatusCode> {
+    match Task::exists(&app_state.db_pool, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    match TaskDependency::find_by_task_id(&app_state.db_pool, task_id).await {
+        Ok(dependencies) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(dependencies),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to fetch dependencies of task {}: {}", task_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Keep the task from being started until another task of the same project is done
+pub async fn create_task_dependency(
+    Path((project_id, task_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateTaskDependency>,
+) -> Result<ResponseJson<ApiResponse<TaskDependency>>, StatusCode> {
+    let pool = &app_state.db_pool;
+    match Task::exists(pool, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+    match Task::exists(pool, payload.depends_on_task_id, project_id).await {
+        Ok(false) => {
+            return Ok(ResponseJson(ApiResponse {
+                success: false,
+                data: None,
+                message: Some("Tasks can only depend on tasks of the same project".to_string()),
+            }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to check task existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    match TaskDependency::create(pool, task_id, payload.depends_on_task_id).await {
+        Ok(dependency) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(dependency),
+            message: None,
+        })),
+        Err(e @ TaskDependencyError::Cycle) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to add a dependency to task {}: {}", task_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn delete_task_dependency(
+    Path((project_id, task_id, depends_on_task_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    match Task::exists(&app_state.db_pool, task_id, project_id).await {
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to check task existence: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(true) => {}
+    }
+
+    match TaskDependency::delete(&app_state.db_pool, task_id, depends_on_task_id).await {
+        Ok(0) => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: None,
+            message: Some("Dependency removed".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to remove a dependency of task {}: {}", task_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 pub fn tasks_router() -> Router<AppState> {
-    use axum::routing::post;
+    use axum::routing::{delete, post};
 
     Router::new()
         .route(
             "/projects/:project_id/tasks",
             get(get_project_tasks).post(create_task),
         )
+        .route(
+            "/projects/:project_id/tasks/estimates",
+            get(get_task_estimates),
+        )
         .route(
             "/projects/:project_id/tasks/create-and-start",
             post(create_task_and_start),
@@ -345,4 +634,12 @@ pub fn tasks_router() -> Router<AppState> {
             "/projects/:project_id/tasks/:task_id",
             get(get_task).put(update_task).delete(delete_task),
         )
+        .route(
+            "/projects/:project_id/tasks/:task_id/dependencies",
+            get(get_task_dependencies).post(create_task_dependency),
+        )
+        .route(
+            "/projects/:project_id/tasks/:task_id/dependencies/:depends_on_task_id",
+            delete(delete_task_dependency),
+        )
 }
@@ -0,0 +1,106 @@
+#[cfg(feature = "self-update")]
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap},
+};
+use axum::{
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Router,
+};
+
+#[cfg(feature = "self-update")]
+use crate::services::self_update::{self, UpdateError, UpdateStatus};
+use crate::{app_state::AppState, models::ApiResponse};
+
+/// Grace period for the apply response to reach the client before the server shuts down
+#[cfg(feature = "self-update")]
+const RESTART_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+pub fn updates_router() -> Router<AppState> {
+    Router::new()
+        .route("/updates", get(get_updates))
+        .route("/updates/apply", post(apply_update))
+}
+
+/// The result of the last daily update check, with the release notes of a newer release
+#[cfg(feature = "self-update")]
+pub async fn get_updates(
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<UpdateStatus>>, StatusCode> {
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(app_state.updates.status().await),
+        message: None,
+    }))
+}
+
+/// Install the latest release over the running binary and restart into it. Requires
+/// `Authorization: Bearer <token>`, with the token printed by `codecommand self-update --token`.
+#[cfg(feature = "self-update")]
+pub async fn apply_update(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<ApiResponse<String>>, StatusCode> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !token.is_some_and(self_update::verify_apply_token) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    match self_update::install_latest().await {
+        Ok(version) => {
+            tracing::info!("Installed codecommand {}, restarting", version);
+            let updates = app_state.updates.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(RESTART_DELAY).await;
+                updates.request_restart();
+            });
+            Ok(ResponseJson(ApiResponse {
+                success: true,
+                message: Some(format!("Installed codecommand {}; restarting", version)),
+                data: Some(version),
+            }))
+        }
+        Err(
+            e @ (UpdateError::UpToDate(_)
+            | UpdateError::NoAsset(_)
+            | UpdateError::Verification(_)
+            | UpdateError::InvalidRelease(_)
+            | UpdateError::Http(_)),
+        ) => Ok(ResponseJson(ApiResponse {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to install update: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(not(feature = "self-update"))]
+fn updates_unavailable() -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    Ok(ResponseJson(ApiResponse {
+        success: false,
+        data: None,
+        message: Some(
+            "This build of codecommand doesn't update itself; update it through the package manager it came from"
+                .to_string(),
+        ),
+    }))
+}
+
+#[cfg(not(feature = "self-update"))]
+pub async fn get_updates() -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    updates_unavailable()
+}
+
+#[cfg(not(feature = "self-update"))]
+pub async fn apply_update() -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    updates_unavailable()
+}
@@ -0,0 +1,258 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        webhook::{CreateWebhookEndpoint, UpdateWebhookEndpoint, WebhookDelivery, WebhookEndpoint},
+        ApiResponse,
+    },
+    services::webhooks::DELIVERY_JOB,
+    utils::secrets,
+};
+
+/// Deliveries shown in an endpoint's log
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
+pub fn webhooks_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/webhooks",
+            get(get_webhook_endpoints).post(create_webhook_endpoint),
+        )
+        .route(
+            "/webhooks/:id",
+            get(get_webhook_endpoint)
+                .put(update_webhook_endpoint)
+                .delete(delete_webhook_endpoint),
+        )
+        .route("/webhooks/:id/deliveries", get(get_webhook_deliveries))
+        .route(
+            "/webhooks/:id/deliveries/:delivery_id/redeliver",
+            post(redeliver_webhook_delivery),
+        )
+}
+
+fn error_response<T>(message: impl Into<String>) -> ResponseJson<ApiResponse<T>> {
+    ResponseJson(ApiResponse {
+        success: false,
+        data: None,
+        message: Some(message.into()),
+    })
+}
+
+/// Why `url` can't receive webhooks, if it can't
+fn invalid_url_reason(url: &str) -> Option<String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => None,
+        Ok(parsed) => Some(format!(
+            "Webhook URLs must use http or https, not {}",
+            parsed.scheme()
+        )),
+        Err(e) => Some(format!("Invalid webhook URL: {}", e)),
+    }
+}
+
+fn encrypt_secret(secret: &str) -> Result<String, StatusCode> {
+    secrets::encrypt(secret).map_err(|e| {
+        tracing::error!("Failed to encrypt webhook secret: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+async fn find_endpoint(app_state: &AppState, id: Uuid) -> Result<WebhookEndpoint, StatusCode> {
+    match WebhookEndpoint::find_by_id(&app_state.db_pool, id).await {
+        Ok(Some(endpoint)) => Ok(endpoint),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch webhook endpoint {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_webhook_endpoints(
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<WebhookEndpoint>>>, StatusCode> {
+    match WebhookEndpoint::find_all(&app_state.db_pool).await {
+        Ok(endpoints) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(endpoints),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to fetch webhook endpoints: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_webhook_endpoint(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<WebhookEndpoint>>, StatusCode> {
+    let endpoint = find_endpoint(&app_state, id).await?;
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: Some(endpoint),
+        message: None,
+    }))
+}
+
+pub async fn create_webhook_endpoint(
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateWebhookEndpoint>,
+) -> Result<ResponseJson<ApiResponse<WebhookEndpoint>>, StatusCode> {
+    let url = payload.url.trim();
+    if let Some(reason) = invalid_url_reason(url) {
+        return Ok(error_response(reason));
+    }
+    if payload.secret.is_empty() {
+        return Ok(error_response("A webhook needs a signing secret"));
+    }
+
+    let encrypted_secret = encrypt_secret(&payload.secret)?;
+    match WebhookEndpoint::create(
+        &app_state.db_pool,
+        url,
+        &encrypted_secret,
+        &payload.events,
+        payload.enabled.unwrap_or(true),
+    )
+    .await
+    {
+        Ok(endpoint) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(endpoint),
+            message: Some("Webhook created".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to create webhook endpoint: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Edit an endpoint. The secret is only replaced when a new one is given.
+pub async fn update_webhook_endpoint(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<UpdateWebhookEndpoint>,
+) -> Result<ResponseJson<ApiResponse<WebhookEndpoint>>, StatusCode> {
+    let existing = find_endpoint(&app_state, id).await?;
+
+    let url = payload
+        .url
+        .map(|url| url.trim().to_string())
+        .unwrap_or(existing.url);
+    if let Some(reason) = invalid_url_reason(&url) {
+        return Ok(error_response(reason));
+    }
+    let encrypted_secret = match payload.secret.as_deref() {
+        Some("") => return Ok(error_response("A webhook needs a signing secret")),
+        Some(secret) => Some(encrypt_secret(secret)?),
+        None => None,
+    };
+    let events = payload.events.unwrap_or(existing.events);
+    let enabled = payload.enabled.unwrap_or(existing.enabled);
+
+    match WebhookEndpoint::update(
+        &app_state.db_pool,
+        id,
+        &url,
+        encrypted_secret.as_deref(),
+        &events,
+        enabled,
+    )
+    .await
+    {
+        Ok(endpoint) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(endpoint),
+            message: Some("Webhook updated".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to update webhook endpoint {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Remove an endpoint along with its delivery log
+pub async fn delete_webhook_endpoint(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    match WebhookEndpoint::delete(&app_state.db_pool, id).await {
+        Ok(0) => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: None,
+            message: Some("Webhook deleted".to_string()),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to delete webhook endpoint {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// The endpoint's most recent deliveries, newest first
+pub async fn get_webhook_deliveries(
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<WebhookDelivery>>>, StatusCode> {
+    find_endpoint(&app_state, id).await?;
+
+    match WebhookDelivery::find_by_endpoint_id(&app_state.db_pool, id, DELIVERY_LOG_LIMIT).await {
+        Ok(deliveries) => Ok(ResponseJson(ApiResponse {
+            success: true,
+            data: Some(deliveries),
+            message: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to fetch deliveries of webhook {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Send a delivery again, typically one that was dead-lettered after running out of attempts
+pub async fn redeliver_webhook_delivery(
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    match WebhookDelivery::redeliver(&app_state.db_pool, id, delivery_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return Ok(error_response(
+                "This delivery doesn't exist or is already waiting to be sent",
+            ))
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to redeliver webhook delivery {}: {}",
+                delivery_id,
+                e
+            );
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    // Send it now rather than on the next poll, unless a run is already going
+    if let Err(e) = app_state.jobs.trigger(DELIVERY_JOB).await {
+        tracing::debug!("Didn't trigger webhook delivery: {}", e);
+    }
+
+    Ok(ResponseJson(ApiResponse {
+        success: true,
+        data: None,
+        message: Some("Delivery queued".to_string()),
+    }))
+}
@@ -7,6 +7,15 @@ use std::{
 use os_info;
 use serde_json::{json, Value};
 
+/// Event properties that may hold user-written content and must never be sent as-is
+const CONTENT_PROPERTY_KEYS: [&str; 5] = [
+    "title",
+    "description",
+    "prompt",
+    "task_title",
+    "task_description",
+];
+
 #[derive(Debug, Clone)]
 pub struct AnalyticsConfig {
     pub posthog_api_key: String,
@@ -58,7 +67,7 @@ impl AnalyticsService {
         // Force disable analytics - return early and never send data
         tracing::debug!("Analytics disabled - skipping event: {}", event_name);
         return;
-        
+
         /*
         let endpoint = format!(
             "{}/capture/",
@@ -122,6 +131,32 @@ impl AnalyticsService {
     }
 }
 
+/// Remove user-written content from event properties. With `include_hashes`, each removed
+/// string is replaced by `<key>_hash`, a hash salted with the user id so values can be
+/// correlated across events without being recoverable.
+pub fn redact_content_properties(properties: &mut Value, salt: &str, include_hashes: bool) {
+    let Some(props) = properties.as_object_mut() else {
+        return;
+    };
+
+    for key in CONTENT_PROPERTY_KEYS {
+        let Some(value) = props.remove(key) else {
+            continue;
+        };
+        if include_hashes {
+            if let Some(content) = value.as_str() {
+                let mut hasher = DefaultHasher::new();
+                salt.hash(&mut hasher);
+                content.hash(&mut hasher);
+                props.insert(
+                    format!("{}_hash", key),
+                    json!(format!("{:016x}", hasher.finish())),
+                );
+            }
+        }
+    }
+}
+
 /// Generates a consistent, anonymous user ID for npm package telemetry.
 /// Returns a hex string prefixed with "npm_user_"
 pub fn generate_user_id() -> String {
@@ -200,6 +235,36 @@ mod tests {
         assert_eq!(id.len(), 25);
     }
 
+    #[test]
+    fn test_attempt_event_never_contains_raw_title() {
+        let title = "Fix login for ACME Corp customer portal";
+        let event = || {
+            json!({
+                "task_id": "00000000-0000-0000-0000-000000000001",
+                "attempt_id": "00000000-0000-0000-0000-000000000002",
+                "executor_type": "claude",
+                "task_title": title,
+                "prompt": format!("{}\n\nPlease fix it", title),
+            })
+        };
+
+        let mut redacted = event();
+        redact_content_properties(&mut redacted, "npm_user_1", false);
+        assert!(!redacted.to_string().contains(title));
+        assert!(redacted.get("task_title_hash").is_none());
+        assert_eq!(redacted["executor_type"], "claude");
+
+        let mut hashed = event();
+        redact_content_properties(&mut hashed, "npm_user_1", true);
+        assert!(!hashed.to_string().contains(title));
+        let hash = hashed["task_title_hash"].as_str().unwrap();
+        assert!(hashed.get("prompt_hash").is_some());
+
+        let mut other_user = event();
+        redact_content_properties(&mut other_user, "npm_user_2", true);
+        assert_ne!(other_user["task_title_hash"].as_str().unwrap(), hash);
+    }
+
     #[test]
     fn test_consistency() {
         let id1 = generate_user_id();
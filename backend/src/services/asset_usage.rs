@@ -0,0 +1,572 @@
+//! Disk space used by the asset directory, by kind of file, and the files in it that the
+//! database has lost track of.
+//!
+//! Besides the database and a few small files (the config, the secret key, the port and
+//! discovery files), the asset directory only grows with the chat histories Aider sessions
+//! keep in `aider/`. Sounds are built into the binary and worktrees live elsewhere, so
+//! neither is counted.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::models::{config::AssetQuotas, executor_session::ExecutorSession};
+
+/// Name of the background job that accounts for the asset directory and enforces quotas
+pub const ACCOUNTING_JOB: &str = "asset_accounting";
+pub const ACCOUNTING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Aider sessions used this recently are never evicted, as a follow-up may resume them
+const EVICTION_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+const AIDER_SESSIONS_DIR: &str = "aider";
+/// The database and its write-ahead log
+const DATABASE_FILE_PREFIX: &str = "db.sqlite";
+
+lazy_static::lazy_static! {
+    static ref LAST_USAGE: Mutex<Option<AssetUsage>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum AssetCategory {
+    Database,
+    AiderSessions,
+    /// The config, secret key, port and discovery files
+    Other,
+}
+
+impl AssetCategory {
+    const ALL: [AssetCategory; 3] = [
+        AssetCategory::Database,
+        AssetCategory::AiderSessions,
+        AssetCategory::Other,
+    ];
+
+    /// Category of a file, from its path relative to the asset directory
+    fn of(relative_path: &Path) -> Self {
+        let mut components = relative_path.components();
+        match components
+            .next()
+            .and_then(|first| first.as_os_str().to_str())
+        {
+            Some(AIDER_SESSIONS_DIR) if components.next().is_some() => AssetCategory::AiderSessions,
+            Some(name) if name.starts_with(DATABASE_FILE_PREFIX) => AssetCategory::Database,
+            _ => AssetCategory::Other,
+        }
+    }
+
+    /// Whether files are deleted, oldest first, to keep the category under its quota
+    fn evictable(self) -> bool {
+        matches!(self, AssetCategory::AiderSessions)
+    }
+
+    fn quota_bytes(self, quotas: &AssetQuotas) -> Option<u64> {
+        let megabytes = match self {
+            AssetCategory::Database => quotas.database_mb,
+            AssetCategory::AiderSessions => quotas.aider_sessions_mb,
+            AssetCategory::Other => None,
+        };
+        megabytes.map(|megabytes| u64::from(megabytes) * 1024 * 1024)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct CategoryUsage {
+    pub category: AssetCategory,
+    #[ts(type = "number")]
+    pub bytes: u64,
+    #[ts(type = "number")]
+    pub files: u64,
+    #[ts(type = "number | null")]
+    pub quota_bytes: Option<u64>,
+    pub over_quota: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AssetUsage {
+    pub categories: Vec<CategoryUsage>,
+    /// Files deleted to bring categories back under their quota while accounting
+    pub evicted: Vec<String>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub computed_at: DateTime<Utc>,
+}
+
+impl AssetUsage {
+    /// Categories over their quota that can't be evicted from
+    pub fn quota_errors(&self) -> Vec<String> {
+        self.categories
+            .iter()
+            .filter(|usage| usage.over_quota && !usage.category.evictable())
+            .map(|usage| {
+                format!(
+                    "{:?} uses {} MB, over its quota of {} MB",
+                    usage.category,
+                    usage.bytes / (1024 * 1024),
+                    usage.quota_bytes.unwrap_or_default() / (1024 * 1024)
+                )
+            })
+            .collect()
+    }
+}
+
+/// A file no executor session refers to
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct OrphanedAsset {
+    /// Relative to the asset directory
+    pub path: String,
+    #[ts(type = "number")]
+    pub bytes: u64,
+}
+
+/// An Aider session whose chat history is gone, so follow-ups start without it
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct MissingAsset {
+    pub execution_process_id: Uuid,
+    pub session_id: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AssetScanReport {
+    pub orphaned: Vec<OrphanedAsset>,
+    pub missing: Vec<MissingAsset>,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub scanned_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct AssetCleanupRequest {
+    /// Orphaned files to delete, relative to the asset directory; all of them when unset.
+    /// Files that aren't orphaned (anymore) are left alone.
+    pub paths: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AssetCleanupResult {
+    pub deleted: Vec<String>,
+    #[ts(type = "number")]
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug)]
+pub enum AssetUsageError {
+    Io(std::io::Error),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for AssetUsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetUsageError::Io(e) => write!(f, "Failed to read the asset directory: {}", e),
+            AssetUsageError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AssetUsageError {}
+
+impl From<std::io::Error> for AssetUsageError {
+    fn from(e: std::io::Error) -> Self {
+        AssetUsageError::Io(e)
+    }
+}
+
+impl From<sqlx::Error> for AssetUsageError {
+    fn from(e: sqlx::Error) -> Self {
+        AssetUsageError::Database(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AssetFile {
+    relative_path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+impl AssetFile {
+    fn display_path(&self) -> String {
+        self.relative_path.to_string_lossy().replace('\\', "/")
+    }
+
+    /// The Aider session a file in `aider/` belongs to: its name up to the first dot
+    fn aider_session(&self) -> Option<&str> {
+        if AssetCategory::of(&self.relative_path) != AssetCategory::AiderSessions {
+            return None;
+        }
+        let name = self.relative_path.file_name()?.to_str()?;
+        name.split('.').next()
+    }
+}
+
+fn list_files(dir: &Path) -> std::io::Result<Vec<AssetFile>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<AssetFile>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                walk(root, &entry.path(), files)?;
+            } else if file_type.is_file() {
+                let path = entry.path();
+                let metadata = entry.metadata()?;
+                files.push(AssetFile {
+                    relative_path: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+                    bytes: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    if dir.exists() {
+        walk(dir, dir, &mut files)?;
+    }
+    Ok(files)
+}
+
+async fn list_files_in(dir: &Path) -> Result<Vec<AssetFile>, AssetUsageError> {
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || list_files(&dir))
+        .await
+        .map_err(|e| AssetUsageError::Io(std::io::Error::other(e)))?
+        .map_err(AssetUsageError::from)
+}
+
+/// Delete the oldest Aider sessions, all of a session's files at once, until the category
+/// fits `quota` or only recently used sessions are left. Returns the deleted files.
+fn evict_aider_sessions(dir: &Path, files: &mut Vec<AssetFile>, quota: u64) -> Vec<String> {
+    let mut total: u64 = files
+        .iter()
+        .filter(|file| file.aider_session().is_some())
+        .map(|file| file.bytes)
+        .sum();
+    if total <= quota {
+        return Vec::new();
+    }
+
+    let mut sessions: HashMap<String, (SystemTime, u64)> = HashMap::new();
+    for file in files.iter() {
+        if let Some(session) = file.aider_session() {
+            let (last_used, bytes) = sessions
+                .entry(session.to_string())
+                .or_insert((SystemTime::UNIX_EPOCH, 0));
+            *last_used = (*last_used).max(file.modified);
+            *bytes += file.bytes;
+        }
+    }
+    let mut sessions: Vec<_> = sessions.into_iter().collect();
+    sessions.sort_by_key(|(session, (last_used, _))| (*last_used, session.clone()));
+
+    let cutoff = SystemTime::now() - EVICTION_GRACE;
+    let mut evicted_sessions = HashSet::new();
+    for (session, (last_used, bytes)) in sessions {
+        if total <= quota || last_used > cutoff {
+            break;
+        }
+        evicted_sessions.insert(session);
+        total -= bytes;
+    }
+
+    let mut evicted = Vec::new();
+    files.retain(|file| {
+        let evict = file
+            .aider_session()
+            .is_some_and(|session| evicted_sessions.contains(session));
+        if !evict {
+            return true;
+        }
+        match std::fs::remove_file(dir.join(&file.relative_path)) {
+            Ok(()) => {
+                evicted.push(file.display_path());
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Failed to evict {}: {}", file.display_path(), e);
+                true
+            }
+        }
+    });
+    evicted
+}
+
+/// Size up the asset directory `dir` by category, evicting from categories over their quota
+/// when `evict` is set. The result is kept for `last_usage`.
+pub async fn account(
+    dir: &Path,
+    quotas: &AssetQuotas,
+    evict: bool,
+) -> Result<AssetUsage, AssetUsageError> {
+    let mut files = list_files_in(dir).await?;
+
+    let mut evicted = Vec::new();
+    if evict {
+        if let Some(quota) = AssetCategory::AiderSessions.quota_bytes(quotas) {
+            let dir = dir.to_path_buf();
+            let (remaining, deleted) = tokio::task::spawn_blocking(move || {
+                let deleted = evict_aider_sessions(&dir, &mut files, quota);
+                (files, deleted)
+            })
+            .await
+            .map_err(|e| AssetUsageError::Io(std::io::Error::other(e)))?;
+            files = remaining;
+            evicted = deleted;
+        }
+    }
+
+    let categories = AssetCategory::ALL
+        .into_iter()
+        .map(|category| {
+            let (bytes, count) = files
+                .iter()
+                .filter(|file| AssetCategory::of(&file.relative_path) == category)
+                .fold((0, 0), |(bytes, count), file| {
+                    (bytes + file.bytes, count + 1)
+                });
+            let quota_bytes = category.quota_bytes(quotas);
+            CategoryUsage {
+                category,
+                bytes,
+                files: count,
+                quota_bytes,
+                over_quota: quota_bytes.is_some_and(|quota| bytes > quota),
+            }
+        })
+        .collect();
+    let usage = AssetUsage {
+        categories,
+        evicted,
+        computed_at: Utc::now(),
+    };
+    *LAST_USAGE.lock().unwrap() = Some(usage.clone());
+    Ok(usage)
+}
+
+/// What the last accounting found, if any ran since the server started
+pub fn last_usage() -> Option<AssetUsage> {
+    LAST_USAGE.lock().unwrap().clone()
+}
+
+/// Compare the Aider sessions executor sessions refer to with the files in the asset
+/// directory `dir`
+pub async fn scan(pool: &SqlitePool, dir: &Path) -> Result<AssetScanReport, AssetUsageError> {
+    let referenced = ExecutorSession::find_session_ids_by_executor_type(pool, "aider").await?;
+    let files = list_files_in(dir).await?;
+
+    let referenced_ids: HashSet<&str> = referenced
+        .iter()
+        .map(|(_, session_id)| session_id.as_str())
+        .collect();
+    let mut orphaned: Vec<OrphanedAsset> = files
+        .iter()
+        .filter(|file| {
+            file.aider_session()
+                .is_some_and(|session| !referenced_ids.contains(session))
+        })
+        .map(|file| OrphanedAsset {
+            path: file.display_path(),
+            bytes: file.bytes,
+        })
+        .collect();
+    orphaned.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let sessions_on_disk: HashSet<&str> =
+        files.iter().filter_map(AssetFile::aider_session).collect();
+    let missing = referenced
+        .iter()
+        .filter(|(_, session_id)| !sessions_on_disk.contains(session_id.as_str()))
+        .map(|(execution_process_id, session_id)| MissingAsset {
+            execution_process_id: *execution_process_id,
+            session_id: session_id.clone(),
+            path: format!("{}/{}.chat.md", AIDER_SESSIONS_DIR, session_id),
+        })
+        .collect();
+
+    Ok(AssetScanReport {
+        orphaned,
+        missing,
+        scanned_at: Utc::now(),
+    })
+}
+
+/// Delete orphaned files, all of them or those in `paths`, after scanning again so a file a
+/// new session started using meanwhile is kept
+pub async fn delete_orphans(
+    pool: &SqlitePool,
+    dir: &Path,
+    paths: Option<&[String]>,
+) -> Result<AssetCleanupResult, AssetUsageError> {
+    let report = scan(pool, dir).await?;
+    let mut result = AssetCleanupResult {
+        deleted: Vec::new(),
+        freed_bytes: 0,
+    };
+    for orphan in report.orphaned {
+        if paths.is_some_and(|paths| !paths.contains(&orphan.path)) {
+            continue;
+        }
+        match tokio::fs::remove_file(dir.join(&orphan.path)).await {
+            Ok(()) => {
+                result.freed_bytes += orphan.bytes;
+                result.deleted.push(orphan.path);
+            }
+            Err(e) => tracing::warn!("Failed to delete orphaned {}: {}", orphan.path, e),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::{
+        models::execution_process::{
+            CreateExecutionProcess, ExecutionProcess, ExecutionProcessType,
+        },
+        test_utils::{seeded_pool, Seeded},
+    };
+
+    fn write(dir: &Path, path: &str, bytes: usize, age: Duration) {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, vec![b'x'; bytes]).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_accounting_evicts_old_sessions_and_scan_finds_orphans() {
+        let (pool, Seeded { attempt_id, .. }) = seeded_pool().await;
+        let mut processes = Vec::new();
+        for session_id in ["recent", "gone"] {
+            let process_id = Uuid::new_v4();
+            ExecutionProcess::create(
+                &pool,
+                &CreateExecutionProcess {
+                    task_attempt_id: attempt_id,
+                    process_type: ExecutionProcessType::CodingAgent,
+                    executor_type: Some("aider".to_string()),
+                    command: "executor".to_string(),
+                    args: None,
+                    working_directory: "/tmp/w".to_string(),
+                },
+                process_id,
+            )
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO executor_sessions (id, task_attempt_id, execution_process_id, session_id) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(attempt_id)
+            .bind(process_id)
+            .bind(session_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+            processes.push(process_id);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+        write(dir.path(), "db.sqlite", 4096, Duration::ZERO);
+        write(dir.path(), "config.json", 100, Duration::ZERO);
+        write(dir.path(), "aider/oldest.chat.md", 600 * 1024, day * 10);
+        write(dir.path(), "aider/oldest.input", 100 * 1024, day * 10);
+        write(dir.path(), "aider/older.chat.md", 600 * 1024, day * 5);
+        write(
+            dir.path(),
+            "aider/recent.chat.md",
+            600 * 1024,
+            Duration::ZERO,
+        );
+
+        let quotas = AssetQuotas {
+            database_mb: Some(1),
+            aider_sessions_mb: Some(1),
+        };
+        let usage = account(dir.path(), &quotas, false).await.unwrap();
+        let aider = &usage.categories[1];
+        assert_eq!(aider.category, AssetCategory::AiderSessions);
+        assert_eq!((aider.files, aider.over_quota), (4, true));
+        assert!(usage.evicted.is_empty());
+
+        // Oldest first, a session at a time, until the rest fits
+        let usage = account(dir.path(), &quotas, true).await.unwrap();
+        assert_eq!(
+            usage
+                .evicted
+                .iter()
+                .map(String::as_str)
+                .collect::<HashSet<_>>(),
+            HashSet::from([
+                "aider/oldest.chat.md",
+                "aider/oldest.input",
+                "aider/older.chat.md"
+            ])
+        );
+        assert_eq!(usage.categories[1].bytes, 600 * 1024);
+        assert!(!usage.categories[1].over_quota);
+        assert_eq!(last_usage().unwrap().computed_at, usage.computed_at);
+
+        // Recently used sessions are kept even over quota, without failing accounting
+        let tight = AssetQuotas {
+            database_mb: Some(1),
+            aider_sessions_mb: Some(0),
+        };
+        let usage = account(dir.path(), &tight, true).await.unwrap();
+        assert!(usage.evicted.is_empty());
+        assert!(usage.categories[1].over_quota);
+        assert!(usage.quota_errors().is_empty());
+
+        write(dir.path(), "aider/stray.chat.md", 10, day * 2);
+        let report = scan(&pool, dir.path()).await.unwrap();
+        assert_eq!(
+            report
+                .orphaned
+                .iter()
+                .map(|orphan| orphan.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["aider/stray.chat.md"]
+        );
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].execution_process_id, processes[1]);
+
+        let cleanup = delete_orphans(&pool, dir.path(), None).await.unwrap();
+        assert_eq!(cleanup.deleted, vec!["aider/stray.chat.md".to_string()]);
+        assert_eq!(cleanup.freed_bytes, 10);
+        assert!(dir.path().join("aider/recent.chat.md").exists());
+        assert!(dir.path().join("db.sqlite").exists());
+
+        let quotas = AssetQuotas {
+            database_mb: Some(0),
+            aider_sessions_mb: None,
+        };
+        let usage = account(dir.path(), &quotas, true).await.unwrap();
+        assert_eq!(usage.quota_errors().len(), 1);
+    }
+}
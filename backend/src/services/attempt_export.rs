@@ -0,0 +1,655 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    models::{task::Task, task_attempt::TaskAttempt},
+    services::git_service::{DiffRevision, GitService, GitServiceError},
+};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const BUNDLE_FILE: &str = "attempt.bundle";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum ExportFormat {
+    /// `git format-patch` series of the attempt's commits
+    Patch,
+    /// `git bundle` of the attempt branch; the base commit is a prerequisite
+    Bundle,
+}
+
+/// Describes an exported attempt; stored as `manifest.json` next to the patches or bundle
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AttemptExportManifest {
+    pub attempt_id: Uuid,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub branch: String,
+    pub base_branch: String,
+    /// Merge base of the attempt branch and its base branch
+    pub base_sha: String,
+    pub head_sha: String,
+    pub format: ExportFormat,
+    pub commit_count: u32,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Tar archive of an export, holding the manifest and the patches or bundle
+#[derive(Debug)]
+pub struct AttemptExport {
+    pub manifest: AttemptExportManifest,
+    pub file_name: String,
+    pub archive: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct PatchApplyResult {
+    /// 1-based position of the patch in the series
+    pub index: u32,
+    pub subject: Option<String>,
+    pub applied: bool,
+    /// Commit created for the patch when it applied
+    pub commit: Option<String>,
+    /// What `git am` reported when it didn't
+    pub error: Option<String>,
+}
+
+/// Outcome of applying an attempt's diff onto another branch
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct DiffApplyResult {
+    pub applied: bool,
+    /// Commit holding the changes, unless the branch already had them
+    pub commit: Option<String>,
+    /// Files whose changes conflicted with the branch
+    pub conflicts: Vec<String>,
+    /// What `git apply` reported when the changes didn't apply
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum AttemptExportError {
+    Git(GitServiceError),
+    Io(std::io::Error),
+    /// A git command exited unsuccessfully, with its output
+    Command(String),
+    UncommittedChanges,
+    NoCommits,
+}
+
+impl std::fmt::Display for AttemptExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttemptExportError::Git(e) => write!(f, "{}", e),
+            AttemptExportError::Io(e) => write!(f, "IO error: {}", e),
+            AttemptExportError::Command(e) => write!(f, "git failed: {}", e),
+            AttemptExportError::UncommittedChanges => write!(
+                f,
+                "The attempt's worktree has uncommitted changes; commit or discard them first"
+            ),
+            AttemptExportError::NoCommits => {
+                write!(f, "The attempt has no commits on top of its base branch")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttemptExportError {}
+
+impl From<GitServiceError> for AttemptExportError {
+    fn from(err: GitServiceError) -> Self {
+        AttemptExportError::Git(err)
+    }
+}
+
+impl From<std::io::Error> for AttemptExportError {
+    fn from(err: std::io::Error) -> Self {
+        AttemptExportError::Io(err)
+    }
+}
+
+/// Scratch directory removed when dropped
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new() -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("codecommand-export-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+async fn git(cwd: &Path, args: &[&str]) -> Result<Vec<u8>, AttemptExportError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .await?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        // git am explains failures on stdout, everything else on stderr
+        let message = [output.stderr, output.stdout]
+            .iter()
+            .map(|out| String::from_utf8_lossy(out).trim().to_string())
+            .filter(|out| !out.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Err(AttemptExportError::Command(message))
+    }
+}
+
+fn ensure_committed(git_repo_path: &str, worktree_path: &Path) -> Result<(), AttemptExportError> {
+    if worktree_path.exists()
+        && GitService::new(git_repo_path)?.has_uncommitted_changes(worktree_path)?
+    {
+        return Err(AttemptExportError::UncommittedChanges);
+    }
+    Ok(())
+}
+
+/// Export the commits of an attempt since its merge base as a tar archive. The branch is read
+/// from the main repository, so attempts whose worktree was cleaned up can still be exported.
+pub async fn export_attempt(
+    git_repo_path: &str,
+    attempt: &TaskAttempt,
+    task: &Task,
+    format: ExportFormat,
+) -> Result<AttemptExport, AttemptExportError> {
+    ensure_committed(git_repo_path, Path::new(&attempt.worktree_path))?;
+
+    let git_service = GitService::new(git_repo_path)?;
+    let revision = git_service.branch_revision(&attempt.branch, &attempt.base_branch)?;
+    let commit_count = git_service.count_commits(revision)?;
+    if commit_count == 0 {
+        return Err(AttemptExportError::NoCommits);
+    }
+
+    let manifest = AttemptExportManifest {
+        attempt_id: attempt.id,
+        task_id: task.id,
+        task_title: task.title.clone(),
+        branch: attempt.branch.clone(),
+        base_branch: attempt.base_branch.clone(),
+        base_sha: revision.base.to_string(),
+        head_sha: revision.head.to_string(),
+        format,
+        commit_count: commit_count as u32,
+        exported_at: Utc::now(),
+    };
+
+    let repo_path = Path::new(git_repo_path);
+    let scratch = ScratchDir::new()?;
+    let out_dir = scratch.0.to_string_lossy().to_string();
+    let mut files = Vec::new();
+    match format {
+        ExportFormat::Patch => {
+            let range = format!("{}..{}", revision.base, revision.head);
+            git(
+                repo_path,
+                &["format-patch", "--output-directory", &out_dir, &range],
+            )
+            .await?;
+            let mut patches: Vec<_> = std::fs::read_dir(&scratch.0)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect();
+            patches.sort();
+            for patch in patches {
+                let name = patch.file_name().unwrap_or_default().to_string_lossy();
+                files.push((format!("patches/{}", name), std::fs::read(&patch)?));
+            }
+        }
+        ExportFormat::Bundle => {
+            let bundle = scratch.0.join(BUNDLE_FILE);
+            let range = format!("{}..refs/heads/{}", revision.base, attempt.branch);
+            git(
+                repo_path,
+                &["bundle", "create", &bundle.to_string_lossy(), &range],
+            )
+            .await?;
+            files.push((BUNDLE_FILE.to_string(), std::fs::read(&bundle)?));
+        }
+    }
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| AttemptExportError::Io(std::io::Error::other(e)))?;
+    files.insert(0, (MANIFEST_FILE.to_string(), manifest_json));
+    let archive = tar_archive(&files)?;
+
+    let format_name = match format {
+        ExportFormat::Patch => "patch",
+        ExportFormat::Bundle => "bundle",
+    };
+    Ok(AttemptExport {
+        file_name: format!(
+            "attempt-{}-{}.tar",
+            &attempt.id.simple().to_string()[..8],
+            format_name
+        ),
+        manifest,
+        archive,
+    })
+}
+
+fn tar_archive(files: &[(String, Vec<u8>)]) -> std::io::Result<Vec<u8>> {
+    let mtime = Utc::now().timestamp().max(0) as u64;
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        builder.append_data(&mut header, path, data.as_slice())?;
+    }
+    builder.into_inner()
+}
+
+/// The attempt's changes since its merge base as a single patch, without its commits, so they
+/// can be applied onto any branch
+pub async fn attempt_diff_patch(
+    git_repo_path: &str,
+    attempt: &TaskAttempt,
+) -> Result<String, AttemptExportError> {
+    ensure_committed(git_repo_path, Path::new(&attempt.worktree_path))?;
+
+    let git_service = GitService::new(git_repo_path)?;
+    let revision = git_service.branch_revision(&attempt.branch, &attempt.base_branch)?;
+    if git_service.count_commits(revision)? == 0 {
+        return Err(AttemptExportError::NoCommits);
+    }
+    diff_patch(Path::new(git_repo_path), revision).await
+}
+
+async fn diff_patch(
+    repo_path: &Path,
+    revision: DiffRevision,
+) -> Result<String, AttemptExportError> {
+    let base = revision.base.to_string();
+    let head = revision.head.to_string();
+    let patch = git(
+        repo_path,
+        &[
+            "diff",
+            "--binary",
+            "--full-index",
+            "--no-color",
+            &base,
+            &head,
+        ],
+    )
+    .await?;
+    Ok(String::from_utf8_lossy(&patch).into_owned())
+}
+
+/// Apply a patch from `attempt_diff_patch` as one commit on the branch checked out in
+/// `worktree_path`. When it conflicts, the worktree is reset and the conflicting files are
+/// reported instead.
+pub async fn apply_diff_patch(
+    git_repo_path: &str,
+    worktree_path: &Path,
+    patch: &str,
+    message: &str,
+) -> Result<DiffApplyResult, AttemptExportError> {
+    ensure_committed(git_repo_path, worktree_path)?;
+
+    let scratch = ScratchDir::new()?;
+    let patch_file = scratch.0.join("changes.patch");
+    std::fs::write(&patch_file, patch)?;
+
+    match git(
+        worktree_path,
+        &["apply", "--3way", "--index", &patch_file.to_string_lossy()],
+    )
+    .await
+    {
+        Ok(_) => {
+            // Nothing staged means the branch already had the changes
+            if git(worktree_path, &["diff", "--cached", "--quiet"])
+                .await
+                .is_ok()
+            {
+                return Ok(DiffApplyResult {
+                    applied: true,
+                    commit: None,
+                    conflicts: Vec::new(),
+                    error: None,
+                });
+            }
+            git(worktree_path, &["commit", "--quiet", "-m", message]).await?;
+            let head = git(worktree_path, &["rev-parse", "HEAD"]).await?;
+            Ok(DiffApplyResult {
+                applied: true,
+                commit: Some(String::from_utf8_lossy(&head).trim().to_string()),
+                conflicts: Vec::new(),
+                error: None,
+            })
+        }
+        Err(AttemptExportError::Command(error)) => {
+            let unmerged = git(worktree_path, &["diff", "--name-only", "--diff-filter=U"]).await?;
+            let conflicts = String::from_utf8_lossy(&unmerged)
+                .lines()
+                .map(str::to_string)
+                .collect();
+            // The worktree was clean before, so nothing but the failed apply is discarded
+            git(worktree_path, &["reset", "--hard", "--quiet", "HEAD"]).await?;
+            Ok(DiffApplyResult {
+                applied: false,
+                commit: None,
+                conflicts,
+                error: Some(error),
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Split `git format-patch --stdout` output, or any concatenation of patch files, into one
+/// patch per commit
+pub fn split_patch_series(text: &str) -> Vec<String> {
+    let mut patches: Vec<String> = Vec::new();
+    for line in text.split_inclusive('\n') {
+        if is_mbox_separator(line) || patches.is_empty() {
+            patches.push(String::new());
+        }
+        if let Some(patch) = patches.last_mut() {
+            patch.push_str(line);
+        }
+    }
+    patches.retain(|patch| !patch.trim().is_empty());
+    patches
+}
+
+/// The `From <sha> Mon Sep 17 00:00:00 2001` line format-patch starts each patch with
+fn is_mbox_separator(line: &str) -> bool {
+    line.strip_prefix("From ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .is_some_and(|sha| sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Subject of a patch without its `[PATCH n/m]` prefix
+fn patch_subject(patch: &str) -> Option<String> {
+    let subject = patch
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix("Subject:"))?
+        .trim();
+    let subject = match subject.strip_prefix('[') {
+        Some(rest) if rest.starts_with("PATCH") => rest
+            .split_once(']')
+            .map_or(subject, |(_, rest)| rest.trim()),
+        _ => subject,
+    };
+    Some(subject.to_string())
+}
+
+/// Apply patches one by one with `git am` on the branch checked out in `worktree_path`. A patch
+/// that doesn't apply is skipped and reported, and the ones after it are still attempted.
+pub async fn apply_patch_series(
+    git_repo_path: &str,
+    worktree_path: &Path,
+    patches: &[String],
+) -> Result<Vec<PatchApplyResult>, AttemptExportError> {
+    ensure_committed(git_repo_path, worktree_path)?;
+
+    let scratch = ScratchDir::new()?;
+    let mut results = Vec::with_capacity(patches.len());
+    for (index, patch) in patches.iter().enumerate() {
+        let patch_file = scratch.0.join(format!("{:04}.patch", index + 1));
+        std::fs::write(&patch_file, patch)?;
+
+        let applied = git(
+            worktree_path,
+            &["am", "--3way", &patch_file.to_string_lossy()],
+        )
+        .await;
+        let (commit, error) = match applied {
+            Ok(_) => {
+                let head = git(worktree_path, &["rev-parse", "HEAD"]).await?;
+                (
+                    Some(String::from_utf8_lossy(&head).trim().to_string()),
+                    None,
+                )
+            }
+            Err(AttemptExportError::Command(message)) => {
+                // Leave the worktree clean for the next patch
+                git(worktree_path, &["am", "--abort"]).await?;
+                (None, Some(message))
+            }
+            Err(e) => return Err(e),
+        };
+
+        results.push(PatchApplyResult {
+            index: index as u32 + 1,
+            subject: patch_subject(patch),
+            applied: commit.is_some(),
+            commit,
+            error,
+        });
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::{Repository, Signature};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test User", "test@example.com").unwrap();
+        let parents: Vec<_> = repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<_> = parents.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    fn init_repo() -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        commit_file(&repo, "notes.txt", "one\ntwo\nthree\n", "Initial commit");
+        (dir, repo)
+    }
+
+    #[test]
+    fn test_split_patch_series_and_subjects() {
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+        let series = format!(
+            "From {sha} Mon Sep 17 00:00:00 2001\nSubject: [PATCH 1/2] Add a\n\nbody\nFrom here on\n\
+             From {sha} Mon Sep 17 00:00:00 2001\nSubject: Fix b\n\n---\n"
+        );
+
+        let patches = split_patch_series(&series);
+
+        assert_eq!(patches.len(), 2);
+        assert!(patches[0].ends_with("From here on\n"));
+        assert_eq!(patch_subject(&patches[0]).as_deref(), Some("Add a"));
+        assert_eq!(patch_subject(&patches[1]).as_deref(), Some("Fix b"));
+        assert!(split_patch_series("  \n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_attempt_diff_applies_onto_another_branch() {
+        let (dir, repo) = init_repo();
+        let repo_path = dir.path().to_string_lossy().to_string();
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+        let initial = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("release", &initial, false).unwrap();
+        repo.branch("attempt", &initial, false).unwrap();
+        repo.set_head("refs/heads/attempt").unwrap();
+        commit_file(&repo, "notes.txt", "one\n2\nthree\n", "Change two");
+        commit_file(&repo, "added.txt", "new\n", "Add a file");
+        repo.set_head("refs/heads/release").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(&repo, "release.txt", "1.0\n", "Cut a release");
+        repo.set_head(&format!("refs/heads/{}", main)).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let revision = GitService::new(&repo_path)
+            .unwrap()
+            .branch_revision("attempt", &main)
+            .unwrap();
+        let patch = diff_patch(dir.path(), revision).await.unwrap();
+        assert!(patch.contains("+++ b/added.txt"));
+
+        let worktree_dir = TempDir::new().unwrap();
+        let worktree_path = worktree_dir.path().join("onto-release");
+        git(
+            dir.path(),
+            &[
+                "worktree",
+                "add",
+                "-b",
+                "onto-release",
+                &worktree_path.to_string_lossy(),
+                "release",
+            ],
+        )
+        .await
+        .unwrap();
+
+        let result = apply_diff_patch(&repo_path, &worktree_path, &patch, "Apply attempt")
+            .await
+            .unwrap();
+        assert!(result.applied, "{:?}", result.error);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(worktree_path.join("notes.txt")).unwrap(),
+            "one\n2\nthree\n"
+        );
+        assert!(worktree_path.join("added.txt").exists());
+        assert!(worktree_path.join("release.txt").exists());
+
+        // A branch that changed the same line gets a conflict report and a clean worktree
+        let conflicting_path = worktree_dir.path().join("onto-hotfix");
+        git(
+            dir.path(),
+            &[
+                "worktree",
+                "add",
+                "-b",
+                "onto-hotfix",
+                &conflicting_path.to_string_lossy(),
+                &initial.id().to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        std::fs::write(conflicting_path.join("notes.txt"), "one\nTWO\nthree\n").unwrap();
+        git(&conflicting_path, &["commit", "--quiet", "-am", "Hotfix"])
+            .await
+            .unwrap();
+
+        let result = apply_diff_patch(&repo_path, &conflicting_path, &patch, "Apply attempt")
+            .await
+            .unwrap();
+        assert!(!result.applied);
+        assert_eq!(result.conflicts, vec!["notes.txt".to_string()]);
+        assert!(result.error.is_some());
+        assert!(!conflicting_path.join("added.txt").exists());
+        assert!(!GitService::new(&repo_path)
+            .unwrap()
+            .has_uncommitted_changes(&conflicting_path)
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_patch_series_round_trip_reports_failed_patches() {
+        let (dir, repo) = init_repo();
+        let repo_path = dir.path().to_string_lossy().to_string();
+        let base = repo.head().unwrap().peel_to_commit().unwrap().id();
+        commit_file(&repo, "notes.txt", "one\n2\nthree\n", "Change two");
+        commit_file(&repo, "added.txt", "new\n", "Add a file");
+        let range = format!("{}..HEAD", base);
+        let series = git(dir.path(), &["format-patch", "--stdout", &range])
+            .await
+            .unwrap();
+        let mut patches = split_patch_series(&String::from_utf8(series).unwrap());
+        assert_eq!(patches.len(), 2);
+        patches.insert(
+            1,
+            patches[0]
+                .replace("-two\n+2\n", "-missing\n+line\n")
+                .replace("Change two", "Change a line that isn't there"),
+        );
+
+        let worktree_dir = TempDir::new().unwrap();
+        let worktree_path = worktree_dir.path().join("imported");
+        let base = base.to_string();
+        git(
+            dir.path(),
+            &[
+                "worktree",
+                "add",
+                "-b",
+                "imported",
+                &worktree_path.to_string_lossy(),
+                &base,
+            ],
+        )
+        .await
+        .unwrap();
+
+        let results = apply_patch_series(&repo_path, &worktree_path, &patches)
+            .await
+            .unwrap();
+
+        let applied: Vec<_> = results.iter().map(|result| result.applied).collect();
+        assert_eq!(applied, vec![true, false, true]);
+        assert_eq!(
+            results[1].subject.as_deref(),
+            Some("Change a line that isn't there")
+        );
+        assert!(results[1].error.is_some());
+        assert_eq!(
+            std::fs::read_to_string(worktree_path.join("notes.txt")).unwrap(),
+            "one\n2\nthree\n"
+        );
+        assert!(worktree_path.join("added.txt").exists());
+
+        std::fs::write(worktree_path.join("scratch.txt"), "wip").unwrap();
+        assert!(matches!(
+            apply_patch_series(&repo_path, &worktree_path, &patches[..1]).await,
+            Err(AttemptExportError::UncommittedChanges)
+        ));
+    }
+}
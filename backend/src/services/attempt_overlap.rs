@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        project::Project,
+        task_attempt::{OpenAttempt, TaskAttempt},
+    },
+    services::git_service::{ChangedLines, DiffRevision, GitService, GitServiceError},
+};
+
+/// How likely two attempts are to conflict when the second one merges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum OverlapSeverity {
+    /// Both change the file, in different places
+    SameFile,
+    /// Both change the same or adjacent lines
+    OverlappingHunks,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct FileOverlap {
+    pub path: String,
+    pub severity: OverlapSeverity,
+}
+
+/// Files changed by both attempts of a pair
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AttemptOverlap {
+    pub attempt_id: Uuid,
+    pub other_attempt_id: Uuid,
+    /// The most severe of `files`
+    pub severity: OverlapSeverity,
+    pub files: Vec<FileOverlap>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AttemptOverlapSummary {
+    pub attempt_id: Uuid,
+    pub task_id: Uuid,
+    pub task_title: String,
+    /// Open attempts this one shares changed files with
+    pub overlapping_attempts: u32,
+    /// `None` when the attempt overlaps with nothing
+    pub severity: Option<OverlapSeverity>,
+}
+
+/// Overlaps between the open attempts of a project. Only committed changes are compared, as
+/// that is what gets merged.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AttemptOverlapReport {
+    pub attempts: Vec<AttemptOverlapSummary>,
+    pub overlaps: Vec<AttemptOverlap>,
+    /// Advisory: attempts least entangled with the others first, so conflicts land on the
+    /// attempts that would need a rebase anyway
+    pub suggested_merge_order: Vec<Uuid>,
+    /// Open attempts whose changes couldn't be read, e.g. because the worktree is gone
+    pub skipped: Vec<Uuid>,
+}
+
+/// Changed lines per attempt, recomputed only when an attempt's HEAD or merge base moves
+#[derive(Debug, Clone, Default)]
+pub struct AttemptChangeCache {
+    entries: Arc<Mutex<HashMap<Uuid, (DiffRevision, Arc<ChangedLines>)>>>,
+}
+
+impl AttemptChangeCache {
+    pub fn changed_lines(
+        &self,
+        git_service: &GitService,
+        attempt: &OpenAttempt,
+    ) -> Result<Arc<ChangedLines>, GitServiceError> {
+        let worktree_path = Path::new(&attempt.worktree_path);
+        let revision = git_service.diff_revision(worktree_path, &attempt.base_branch)?;
+        if let Some((cached_revision, lines)) =
+            self.entries.lock().unwrap().get(&attempt.attempt_id)
+        {
+            if *cached_revision == revision {
+                return Ok(lines.clone());
+            }
+        }
+
+        let lines = Arc::new(git_service.changed_lines(worktree_path, revision)?);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(attempt.attempt_id, (revision, lines.clone()));
+        Ok(lines)
+    }
+}
+
+fn file_overlaps(changes: &ChangedLines, other: &ChangedLines) -> Vec<FileOverlap> {
+    changes
+        .iter()
+        .filter_map(|(path, ranges)| {
+            let other_ranges = other.get(path)?;
+            let overlapping = ranges.iter().any(|range| {
+                other_ranges
+                    .iter()
+                    .any(|other| range.start < other.end && other.start < range.end)
+            });
+            Some(FileOverlap {
+                path: path.clone(),
+                severity: if overlapping {
+                    OverlapSeverity::OverlappingHunks
+                } else {
+                    OverlapSeverity::SameFile
+                },
+            })
+        })
+        .collect()
+}
+
+/// Pairwise overlaps between attempts, each pair reported once
+fn find_overlaps(changes: &[(Uuid, Arc<ChangedLines>)]) -> Vec<AttemptOverlap> {
+    let mut overlaps = Vec::new();
+    for (index, (attempt_id, lines)) in changes.iter().enumerate() {
+        for (other_attempt_id, other_lines) in &changes[index + 1..] {
+            let files = file_overlaps(lines, other_lines);
+            if let Some(severity) = files.iter().map(|file| file.severity).max() {
+                overlaps.push(AttemptOverlap {
+                    attempt_id: *attempt_id,
+                    other_attempt_id: *other_attempt_id,
+                    severity,
+                    files,
+                });
+            }
+        }
+    }
+    overlaps
+}
+
+/// Fewest overlapping-hunk pairs first, then fewest same-file pairs, then smallest change
+fn suggest_merge_order(
+    changes: &[(Uuid, Arc<ChangedLines>)],
+    overlaps: &[AttemptOverlap],
+) -> Vec<Uuid> {
+    let count = |attempt_id: Uuid, severity: OverlapSeverity| {
+        overlaps
+            .iter()
+            .filter(|o| {
+                o.severity == severity
+                    && (o.attempt_id == attempt_id || o.other_attempt_id == attempt_id)
+            })
+            .count()
+    };
+
+    let mut order: Vec<_> = changes
+        .iter()
+        .map(|(attempt_id, lines)| {
+            (
+                count(*attempt_id, OverlapSeverity::OverlappingHunks),
+                count(*attempt_id, OverlapSeverity::SameFile),
+                lines.len(),
+                *attempt_id,
+            )
+        })
+        .collect();
+    // Stable, so ties keep the attempts' creation order
+    order.sort_by_key(|(hunks, files, size, _)| (*hunks, *files, *size));
+    order
+        .into_iter()
+        .map(|(.., attempt_id)| attempt_id)
+        .collect()
+}
+
+pub async fn analyze_project(
+    pool: &sqlx::SqlitePool,
+    cache: &AttemptChangeCache,
+    project: &Project,
+) -> Result<AttemptOverlapReport, sqlx::Error> {
+    let attempts = TaskAttempt::find_open_by_project_id(pool, project.id).await?;
+
+    let git_repo_path = project.git_repo_path.clone();
+    let cache = cache.clone();
+    let blocking_attempts = attempts.clone();
+    let results = tokio::task::spawn_blocking(move || {
+        let git_service = GitService::new(&git_repo_path);
+        blocking_attempts
+            .iter()
+            .map(|attempt| {
+                let lines = git_service
+                    .as_ref()
+                    .map_err(|e| e.to_string())
+                    .and_then(|git| cache.changed_lines(git, attempt).map_err(|e| e.to_string()));
+                (attempt.attempt_id, lines)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    let mut changes = Vec::new();
+    let mut skipped = Vec::new();
+    for (attempt_id, lines) in results {
+        match lines {
+            Ok(lines) => changes.push((attempt_id, lines)),
+            Err(e) => {
+                tracing::warn!("Skipping attempt {} in overlap analysis: {}", attempt_id, e);
+                skipped.push(attempt_id);
+            }
+        }
+    }
+
+    let overlaps = find_overlaps(&changes);
+    let suggested_merge_order = suggest_merge_order(&changes, &overlaps);
+    let summaries = attempts
+        .into_iter()
+        .filter(|attempt| !skipped.contains(&attempt.attempt_id))
+        .map(|attempt| {
+            let involved: Vec<_> = overlaps
+                .iter()
+                .filter(|o| {
+                    o.attempt_id == attempt.attempt_id || o.other_attempt_id == attempt.attempt_id
+                })
+                .collect();
+            AttemptOverlapSummary {
+                attempt_id: attempt.attempt_id,
+                task_id: attempt.task_id,
+                task_title: attempt.task_title,
+                overlapping_attempts: involved.len() as u32,
+                severity: involved.iter().map(|o| o.severity).max(),
+            }
+        })
+        .collect();
+
+    Ok(AttemptOverlapReport {
+        attempts: summaries,
+        overlaps,
+        suggested_merge_order,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(files: &[(&str, Vec<std::ops::Range<u32>>)]) -> Arc<ChangedLines> {
+        Arc::new(
+            files
+                .iter()
+                .map(|(path, ranges)| (path.to_string(), ranges.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_overlaps_are_graded_and_ordered() {
+        let router = Uuid::new_v4();
+        let router_too = Uuid::new_v4();
+        let styles = Uuid::new_v4();
+        let attempts = vec![
+            (
+                router,
+                changes(&[("src/router.ts", vec![10..20]), ("src/app.ts", vec![1..2])]),
+            ),
+            (
+                router_too,
+                changes(&[
+                    ("src/router.ts", vec![18..25]),
+                    ("src/app.ts", vec![50..51]),
+                ]),
+            ),
+            (styles, changes(&[("src/app.css", vec![0..u32::MAX])])),
+        ];
+
+        let overlaps = find_overlaps(&attempts);
+
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].severity, OverlapSeverity::OverlappingHunks);
+        let severities: Vec<_> = overlaps[0]
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.severity))
+            .collect();
+        assert_eq!(
+            severities,
+            vec![
+                ("src/app.ts", OverlapSeverity::SameFile),
+                ("src/router.ts", OverlapSeverity::OverlappingHunks),
+            ]
+        );
+        assert_eq!(
+            suggest_merge_order(&attempts, &overlaps),
+            vec![styles, router, router_too]
+        );
+    }
+}
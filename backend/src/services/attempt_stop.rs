@@ -0,0 +1,430 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
+        executor_session::ExecutorSession,
+        task::Task,
+        task_attempt::{CreateTaskAttempt, TaskAttempt, TaskAttemptError, TaskAttemptStatus},
+        task_attempt_activity::{CreateTaskAttemptActivity, TaskAttemptActivity},
+        webhook::WebhookEventType,
+    },
+    services::{webhooks, ProcessService},
+    utils::secrets,
+};
+
+const STOP_LINK_PURPOSE: &str = "stop-link:v1";
+/// Signature bytes kept in a stop link; enough against forgery while keeping the link short
+const STOP_LINK_SIGNATURE_LEN: usize = 16;
+/// How long a stop link stays valid after it is created
+pub const STOP_LINK_TTL_HOURS: i64 = 24;
+
+/// Optional body of a stop request
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export)]
+pub struct StopTaskAttempt {
+    /// Why the attempt was stopped, e.g. "wrong approach, use the existing helper instead"
+    pub note: Option<String>,
+    /// Once the processes are gone, continue with the note as the next prompt
+    #[serde(default)]
+    pub restart_with_note: bool,
+}
+
+/// What was started after a stop with `restart_with_note`
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export)]
+pub enum RestartAfterStop {
+    /// The note was sent as a follow-up in the stopped attempt's session
+    FollowUp { attempt_id: Uuid },
+    /// There was no session to resume, so a new attempt was started
+    RetryAttempt { attempt_id: Uuid },
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct StopAttemptResult {
+    pub stopped_processes: u32,
+    pub restart: Option<RestartAfterStop>,
+}
+
+/// Stop every running process of an attempt, waiting for each process group to exit, and
+/// record the note on the attempt. Returns the number of processes stopped and any errors.
+pub async fn stop_attempt(
+    app_state: &AppState,
+    attempt_id: Uuid,
+    note: Option<&str>,
+) -> Result<(u32, Vec<String>), TaskAttemptError> {
+    let pool = &app_state.db_pool;
+    let note = note.map(str::trim).filter(|note| !note.is_empty());
+    if note.is_some() {
+        TaskAttempt::set_stop_note(pool, attempt_id, note).await?;
+    }
+
+    let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt_id).await?;
+    let mut stopped_count = 0;
+    let mut errors = Vec::new();
+
+    for process in processes {
+        match app_state.stop_running_execution_by_id(process.id).await {
+            Ok(true) => {
+                stopped_count += 1;
+
+                // Update the execution process status in the database
+                if let Err(e) = ExecutionProcess::update_completion(
+                    pool,
+                    process.id,
+                    ExecutionProcessStatus::Killed,
+                    None,
+                )
+                .await
+                {
+                    tracing::error!("Failed to update execution process status: {}", e);
+                    errors.push(format!("Failed to update process {} status", process.id));
+                    continue;
+                }
+
+                // Create activity record for stopped processes (skip dev servers and bisect runs)
+                if !process.process_type.is_attempt_stage() {
+                    continue;
+                }
+                let mut activity_note = format!(
+                    "Execution process {:?} ({}) stopped by user",
+                    process.process_type, process.id
+                );
+                if let Some(note) = note {
+                    activity_note.push_str(": ");
+                    activity_note.push_str(note);
+                }
+                let create_activity = CreateTaskAttemptActivity {
+                    execution_process_id: process.id,
+                    status: Some(TaskAttemptStatus::ExecutorFailed),
+                    note: Some(activity_note),
+                };
+                if let Err(e) = TaskAttemptActivity::create(
+                    pool,
+                    &create_activity,
+                    Uuid::new_v4(),
+                    TaskAttemptStatus::ExecutorFailed,
+                )
+                .await
+                {
+                    tracing::error!("Failed to create stopped activity: {}", e);
+                    errors.push(format!(
+                        "Failed to create activity for process {}",
+                        process.id
+                    ));
+                }
+            }
+            Ok(false) => {
+                // Process was not running, which is fine
+            }
+            Err(e) => {
+                tracing::error!("Failed to stop execution process {}: {}", process.id, e);
+                errors.push(format!("Failed to stop process {}: {}", process.id, e));
+            }
+        }
+    }
+
+    if stopped_count > 0 {
+        webhooks::emit(pool, WebhookEventType::AttemptStopped, attempt_id, None).await;
+    }
+
+    Ok((stopped_count, errors))
+}
+
+/// What cancelling an attempt stopped
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct CancelAttemptResult {
+    pub stopped_processes: u32,
+    /// The attempt was waiting for a slot in its project and won't start
+    pub dequeued: bool,
+}
+
+/// Cancel an attempt: stop its running processes and keep the stages that would follow them,
+/// or its start when it's queued behind other attempts in its project, from starting. When
+/// nothing was running, the cancellation is recorded on the attempt's last stage.
+pub async fn cancel_attempt(
+    app_state: &AppState,
+    attempt_id: Uuid,
+) -> Result<(CancelAttemptResult, Vec<String>), TaskAttemptError> {
+    let dequeued = app_state.cancel_attempt(attempt_id).await;
+    let (stopped_processes, mut errors) = stop_attempt(app_state, attempt_id, None).await?;
+
+    if stopped_processes == 0 {
+        let pool = &app_state.db_pool;
+        let last_stage = ExecutionProcess::find_by_task_attempt_id(pool, attempt_id)
+            .await?
+            .into_iter()
+            .rev()
+            .find(|process| process.process_type.is_attempt_stage());
+        if let Some(process) = last_stage {
+            let create_activity = CreateTaskAttemptActivity {
+                execution_process_id: process.id,
+                status: Some(TaskAttemptStatus::ExecutorFailed),
+                note: Some("Attempt cancelled by user".to_string()),
+            };
+            if let Err(e) = TaskAttemptActivity::create(
+                pool,
+                &create_activity,
+                Uuid::new_v4(),
+                TaskAttemptStatus::ExecutorFailed,
+            )
+            .await
+            {
+                tracing::error!("Failed to create cancelled activity: {}", e);
+                errors.push(format!(
+                    "Failed to create activity for process {}",
+                    process.id
+                ));
+            }
+        }
+    }
+
+    Ok((
+        CancelAttemptResult {
+            stopped_processes,
+            dequeued,
+        },
+        errors,
+    ))
+}
+
+/// Prompt that continues after a stop: the user's note, then where the previous run was when
+/// it was stopped
+pub fn restart_prompt(
+    note: &str,
+    stopped_during: Option<&ExecutionProcessType>,
+    last_message: Option<&str>,
+) -> String {
+    let stage = match stopped_during {
+        Some(ExecutionProcessType::SetupScript) => "while the setup script was running",
+        Some(ExecutionProcessType::DevServer | ExecutionProcessType::Bisect) | None => {
+            "before the agent started"
+        }
+        Some(ExecutionProcessType::CodingAgent) => "while the agent was working",
+        Some(ExecutionProcessType::FormatScript) => "while your changes were being formatted",
+    };
+    let mut prompt = format!("{}\n\nI stopped the previous run {}.", note.trim(), stage);
+    match last_message.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(message) => {
+            prompt.push_str(" Your last message before it was stopped was:\n\n");
+            prompt.push_str(message);
+        }
+        None => prompt.push_str(" Check the current state of the worktree before continuing."),
+    }
+    prompt
+}
+
+/// Continue an attempt that was just stopped, with `note` as the next prompt. Sent as a
+/// follow-up when the agent's session can be resumed; otherwise the note is added to the task
+/// description and a new attempt is started from the same base branch.
+pub async fn restart_with_note(
+    app_state: &AppState,
+    attempt: &TaskAttempt,
+    task: &Task,
+    note: &str,
+) -> Result<RestartAfterStop, TaskAttemptError> {
+    let pool = &app_state.db_pool;
+    let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt.id).await?;
+    let stopped_during = processes
+        .iter()
+        .rev()
+        .find(|p| p.process_type.is_attempt_stage())
+        .map(|p| &p.process_type);
+    let last_agent_run = processes
+        .iter()
+        .rev()
+        .find(|p| matches!(p.process_type, ExecutionProcessType::CodingAgent));
+
+    let session = match last_agent_run {
+        Some(process) => ExecutorSession::find_by_execution_process_id(pool, process.id).await?,
+        None => None,
+    };
+    // Gemini follow-ups replay the attempt's context instead of resuming a session
+    let resumable = session.as_ref().is_some_and(|s| s.session_id.is_some())
+        || last_agent_run.is_some_and(|p| p.executor_type.as_deref() == Some("gemini"));
+
+    if resumable {
+        let prompt = restart_prompt(
+            note,
+            stopped_during,
+            session.as_ref().and_then(|s| s.summary.as_deref()),
+        );
+        let attempt_id = ProcessService::start_followup_execution(
+            pool,
+            app_state,
+            attempt.id,
+            task.id,
+            task.project_id,
+            &prompt,
+            true,
+        )
+        .await?;
+        return Ok(RestartAfterStop::FollowUp { attempt_id });
+    }
+
+    let description = match task.description.as_deref().map(str::trim) {
+        Some(description) if !description.is_empty() => format!(
+            "{}\n\nNote from stopping attempt {}: {}",
+            description,
+            attempt.id,
+            note.trim()
+        ),
+        _ => format!("Note from stopping attempt {}: {}", attempt.id, note.trim()),
+    };
+    let updated = Task::update(
+        pool,
+        task.id,
+        task.project_id,
+        task.title.clone(),
+        Some(description),
+        task.status.clone(),
+        task.estimate,
+    )
+    .await?;
+    app_state.project_events.task_changed(&updated);
+
+    let worktree_name_pattern = app_state
+        .get_config()
+        .read()
+        .await
+        .worktree_name_pattern
+        .clone();
+    let retry = TaskAttempt::create(
+        pool,
+        &CreateTaskAttempt {
+            executor: attempt.executor.clone(),
+            base_branch: Some(attempt.base_branch.clone()),
+            env: attempt
+                .env
+                .as_ref()
+                .map(|Json(env)| env.clone())
+                .unwrap_or_default(),
+            retry_of: Some(attempt.id),
+        },
+        task.id,
+        &worktree_name_pattern,
+    )
+    .await?;
+    TaskAttempt::start_execution(pool, app_state, retry.id, task.id, task.project_id).await?;
+    Ok(RestartAfterStop::RetryAttempt {
+        attempt_id: retry.id,
+    })
+}
+
+#[derive(Debug)]
+pub enum StopLinkError {
+    Invalid,
+    Expired,
+    Signing(anyhow::Error),
+}
+
+impl std::fmt::Display for StopLinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopLinkError::Invalid => write!(f, "This stop link is not valid"),
+            StopLinkError::Expired => write!(f, "This stop link has expired"),
+            StopLinkError::Signing(e) => write!(f, "Failed to sign stop link: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StopLinkError {}
+
+/// Pre-signed link that stops an attempt without a session, e.g. from a chat notification.
+/// Opening it shows a confirmation page where a note can be added before anything stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopLink {
+    pub attempt_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl StopLink {
+    pub fn new(attempt_id: Uuid) -> Self {
+        Self {
+            attempt_id,
+            expires_at: Utc::now() + Duration::hours(STOP_LINK_TTL_HOURS),
+        }
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let mut message = self.attempt_id.as_bytes().to_vec();
+        message.extend_from_slice(&self.expires_at.timestamp().to_be_bytes());
+        message
+    }
+
+    /// URL-safe token: attempt id, expiry and a truncated signature over both
+    pub fn token(&self) -> Result<String, StopLinkError> {
+        let mut token = self.message();
+        let signature = secrets::sign(STOP_LINK_PURPOSE, &token).map_err(StopLinkError::Signing)?;
+        token.extend_from_slice(&signature[..STOP_LINK_SIGNATURE_LEN]);
+        Ok(URL_SAFE_NO_PAD.encode(token))
+    }
+
+    /// Path of the confirmation page, to be joined with the server's address
+    pub fn path(&self) -> Result<String, StopLinkError> {
+        Ok(format!("/stop/{}", self.token()?))
+    }
+
+    pub fn from_token(token: &str) -> Result<Self, StopLinkError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| StopLinkError::Invalid)?;
+        if bytes.len() != 16 + 8 + STOP_LINK_SIGNATURE_LEN {
+            return Err(StopLinkError::Invalid);
+        }
+        let (message, signature) = bytes.split_at(16 + 8);
+        if !secrets::verify(STOP_LINK_PURPOSE, message, signature)
+            .map_err(StopLinkError::Signing)?
+        {
+            return Err(StopLinkError::Invalid);
+        }
+
+        let attempt_id = Uuid::from_slice(&message[..16]).map_err(|_| StopLinkError::Invalid)?;
+        let expiry = i64::from_be_bytes(message[16..].try_into().expect("8 bytes"));
+        let expires_at = Utc
+            .timestamp_opt(expiry, 0)
+            .single()
+            .ok_or(StopLinkError::Invalid)?;
+        if expires_at < Utc::now() {
+            return Err(StopLinkError::Expired);
+        }
+        Ok(Self {
+            attempt_id,
+            expires_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_prompt_leads_with_note_and_says_where_the_run_stopped() {
+        let prompt = restart_prompt(
+            "  Wrong approach, try using the existing helper instead\n",
+            Some(&ExecutionProcessType::CodingAgent),
+            Some("I'm now rewriting the parser from scratch."),
+        );
+        assert!(prompt.starts_with("Wrong approach, try using the existing helper instead\n\n"));
+        assert!(prompt.contains("while the agent was working"));
+        assert!(prompt.ends_with("I'm now rewriting the parser from scratch."));
+
+        let prompt = restart_prompt(
+            "Use the helper",
+            Some(&ExecutionProcessType::SetupScript),
+            None,
+        );
+        assert!(prompt.contains("while the setup script was running"));
+        assert!(prompt.contains("Check the current state of the worktree"));
+    }
+}
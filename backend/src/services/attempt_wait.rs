@@ -0,0 +1,317 @@
+//! Waiting for an attempt to reach a state, for scripts that start an attempt and need to
+//! know when it's done. Waiters sleep on the attempt event channel and only query the
+//! database to re-check the attempt when one of its events arrives, or every few seconds in
+//! case a change came without an event (like the task moving to review after its executor
+//! completed, or the attempt being deleted).
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::{sync::broadcast::error::RecvError, time::Instant};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
+        task::{Task, TaskStatus},
+        task_attempt::TaskAttempt,
+    },
+    services::webhooks,
+};
+
+/// How long a wait lasts when the request doesn't say
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(300);
+/// Longest wait a single request can ask for; scripts waiting longer request again
+pub const MAX_WAIT_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+/// How often the attempt is re-checked when no events arrive for it
+const RECHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The state an attempt is waited for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum WaitCondition {
+    /// Setup and the coding agent have run and nothing is running or queued for the attempt
+    #[default]
+    Finished,
+    /// Finished, and its task is in review or done
+    ReviewReady,
+    /// Merged locally, or its pull request was merged
+    Merged,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct AttemptWaitResult {
+    pub until: WaitCondition,
+    /// The wait ended before the attempt reached `until`
+    pub timed_out: bool,
+    pub attempt: TaskAttempt,
+    pub task_status: TaskStatus,
+    /// Whether setup or the coding agent is running or queued
+    pub running: bool,
+}
+
+#[derive(Debug)]
+pub enum AttemptWaitError {
+    NotFound,
+    /// The attempt was deleted while waiting for it
+    Gone,
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for AttemptWaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttemptWaitError::NotFound => write!(f, "Task attempt not found"),
+            AttemptWaitError::Gone => write!(f, "Task attempt was deleted while waiting for it"),
+            AttemptWaitError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AttemptWaitError {}
+
+impl From<sqlx::Error> for AttemptWaitError {
+    fn from(e: sqlx::Error) -> Self {
+        AttemptWaitError::Database(e)
+    }
+}
+
+/// What a check found out about the attempt
+struct AttemptSnapshot {
+    attempt: TaskAttempt,
+    task_status: TaskStatus,
+    /// Setup or the coding agent is running or queued
+    running: bool,
+    /// Setup failed or was stopped, or the coding agent ran
+    ran: bool,
+}
+
+impl AttemptSnapshot {
+    fn reached(&self, until: WaitCondition) -> bool {
+        let finished = self.ran && !self.running;
+        match until {
+            WaitCondition::Finished => finished,
+            WaitCondition::ReviewReady => {
+                finished && matches!(self.task_status, TaskStatus::InReview | TaskStatus::Done)
+            }
+            WaitCondition::Merged => {
+                self.attempt.merge_commit.is_some()
+                    || self.attempt.pr_merged_at.is_some()
+                    || self.attempt.pr_status.as_deref() == Some("merged")
+            }
+        }
+    }
+
+    fn into_result(self, until: WaitCondition, timed_out: bool) -> AttemptWaitResult {
+        AttemptWaitResult {
+            until,
+            timed_out,
+            attempt: self.attempt,
+            task_status: self.task_status,
+            running: self.running,
+        }
+    }
+}
+
+async fn snapshot(
+    app_state: &AppState,
+    attempt_id: Uuid,
+) -> Result<Option<AttemptSnapshot>, sqlx::Error> {
+    let pool = &app_state.db_pool;
+    let Some(attempt) = TaskAttempt::find_by_id(pool, attempt_id).await? else {
+        return Ok(None);
+    };
+    let Some(task) = Task::find_by_id(pool, attempt.task_id).await? else {
+        return Ok(None);
+    };
+    let stages: Vec<_> = ExecutionProcess::find_summaries_by_task_attempt_id(pool, attempt_id)
+        .await?
+        .into_iter()
+        .filter(|process| process.process_type.is_attempt_stage())
+        .collect();
+
+    let running = stages
+        .iter()
+        .any(|process| process.status == ExecutionProcessStatus::Running)
+        || app_state.is_attempt_queued(attempt_id).await;
+    let ran = stages.iter().any(|process| {
+        process.process_type == ExecutionProcessType::CodingAgent
+            || process.status != ExecutionProcessStatus::Completed
+    });
+
+    Ok(Some(AttemptSnapshot {
+        attempt,
+        task_status: task.status,
+        running,
+        ran,
+    }))
+}
+
+/// Wait until the attempt reaches `until` or `timeout` passes, whichever comes first, and
+/// return its state at that point
+pub async fn wait_for_attempt(
+    app_state: &AppState,
+    attempt_id: Uuid,
+    until: WaitCondition,
+    timeout: Duration,
+) -> Result<AttemptWaitResult, AttemptWaitError> {
+    // Subscribed before the first check, so nothing that happens after it is missed
+    let mut events = webhooks::subscribe();
+    let deadline = Instant::now() + timeout.min(MAX_WAIT_TIMEOUT);
+    let mut seen = false;
+
+    loop {
+        let Some(snapshot) = snapshot(app_state, attempt_id).await? else {
+            return Err(if seen {
+                AttemptWaitError::Gone
+            } else {
+                AttemptWaitError::NotFound
+            });
+        };
+        seen = true;
+
+        let reached = snapshot.reached(until);
+        if reached || Instant::now() >= deadline {
+            return Ok(snapshot.into_result(until, !reached));
+        }
+
+        let next_check = deadline.min(Instant::now() + RECHECK_INTERVAL);
+        loop {
+            match tokio::time::timeout_at(next_check, events.recv()).await {
+                Ok(Ok(event)) if event.attempt_id != attempt_id => continue,
+                // An event about the attempt, or events were missed
+                Ok(Ok(_)) | Ok(Err(RecvError::Lagged(_))) => break,
+                Ok(Err(RecvError::Closed)) => {
+                    tokio::time::sleep_until(next_check).await;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use crate::{
+        models::{
+            config::Config, execution_process::CreateExecutionProcess, webhook::WebhookEventType,
+        },
+        test_utils::{seeded_pool, Seeded},
+    };
+
+    #[tokio::test]
+    async fn test_wait_wakes_on_attempt_events_and_reports_timeouts_and_deletion() {
+        let (
+            pool,
+            Seeded {
+                project_id,
+                task_id,
+                attempt_id,
+            },
+        ) = seeded_pool().await;
+        let config = Config {
+            analytics_enabled: Some(false),
+            ..Config::default()
+        };
+        let app_state = AppState::new(pool.clone(), Arc::new(RwLock::new(config))).await;
+
+        let process_id = Uuid::new_v4();
+        ExecutionProcess::create(
+            &pool,
+            &CreateExecutionProcess {
+                task_attempt_id: attempt_id,
+                process_type: ExecutionProcessType::CodingAgent,
+                executor_type: Some("echo".to_string()),
+                command: "echo".to_string(),
+                args: None,
+                working_directory: "/tmp".to_string(),
+            },
+            process_id,
+        )
+        .await
+        .unwrap();
+
+        // Nothing to wait for yet: times out with the current state
+        let result = wait_for_attempt(
+            &app_state,
+            attempt_id,
+            WaitCondition::Finished,
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap();
+        assert!(result.timed_out);
+        assert!(result.running);
+
+        let waiter = {
+            let app_state = app_state.clone();
+            tokio::spawn(async move {
+                wait_for_attempt(
+                    &app_state,
+                    attempt_id,
+                    WaitCondition::Finished,
+                    Duration::from_secs(60),
+                )
+                .await
+            })
+        };
+        ExecutionProcess::update_completion(
+            &pool,
+            process_id,
+            ExecutionProcessStatus::Completed,
+            Some(0),
+        )
+        .await
+        .unwrap();
+        webhooks::emit(&pool, WebhookEventType::ExecutorCompleted, attempt_id, None).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("the event should wake the waiter")
+            .unwrap()
+            .unwrap();
+        assert!(!result.timed_out);
+        assert!(!result.running);
+        assert_eq!(result.attempt.id, attempt_id);
+
+        let waiter = {
+            let app_state = app_state.clone();
+            tokio::spawn(async move {
+                wait_for_attempt(
+                    &app_state,
+                    attempt_id,
+                    WaitCondition::Merged,
+                    Duration::from_secs(60),
+                )
+                .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Task::delete(&pool, task_id, project_id).await.unwrap();
+        let result = tokio::time::timeout(RECHECK_INTERVAL * 3, waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(result, Err(AttemptWaitError::Gone)));
+
+        assert!(matches!(
+            wait_for_attempt(
+                &app_state,
+                attempt_id,
+                WaitCondition::Finished,
+                Duration::ZERO
+            )
+            .await,
+            Err(AttemptWaitError::NotFound)
+        ));
+    }
+}
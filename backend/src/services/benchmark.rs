@@ -0,0 +1,537 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    executor::ExecutorConfig,
+    models::{
+        benchmark::{
+            BenchmarkAttempt, BenchmarkAttemptStatus, BenchmarkBase, BenchmarkRun,
+            BenchmarkRunStatus, BenchmarkScore, CreateBenchmarkRun,
+            DEFAULT_BENCHMARK_MAX_DURATION_SECS, DEFAULT_BENCHMARK_TASK_COUNT,
+            MAX_BENCHMARK_TASK_COUNT,
+        },
+        execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
+        project::Project,
+        task::Task,
+        task_attempt::{CreateTaskAttempt, TaskAttempt, TaskAttemptError},
+    },
+    services::{
+        attempt_stop,
+        git_service::{DiffRevision, GitService, GitServiceError},
+    },
+    utils::{executable::ExecutableLookup, shell::get_shell_command},
+};
+
+/// How often a running benchmark attempt is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Benchmark attempts run one at a time, across runs, so they don't compete for the machine
+/// and their durations stay comparable
+fn queue() -> &'static Mutex<()> {
+    static QUEUE: OnceLock<Mutex<()>> = OnceLock::new();
+    QUEUE.get_or_init(|| Mutex::new(()))
+}
+
+#[derive(Debug)]
+pub enum BenchmarkError {
+    Database(sqlx::Error),
+    GitService(GitServiceError),
+    /// The request can't be run as asked, e.g. an unknown executor
+    Invalid(String),
+}
+
+impl std::fmt::Display for BenchmarkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BenchmarkError::Database(e) => write!(f, "Database error: {}", e),
+            BenchmarkError::GitService(e) => write!(f, "Git service error: {}", e),
+            BenchmarkError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for BenchmarkError {}
+
+impl From<sqlx::Error> for BenchmarkError {
+    fn from(err: sqlx::Error) -> Self {
+        BenchmarkError::Database(err)
+    }
+}
+
+impl From<GitServiceError> for BenchmarkError {
+    fn from(err: GitServiceError) -> Self {
+        BenchmarkError::GitService(err)
+    }
+}
+
+/// Check the requested executors, pick the merged tasks to replay and start the run in the
+/// background
+pub async fn start_run(
+    app_state: &AppState,
+    project: &Project,
+    data: &CreateBenchmarkRun,
+) -> Result<BenchmarkRun, BenchmarkError> {
+    let pool = &app_state.db_pool;
+
+    let mut executors: Vec<String> = Vec::new();
+    for executor in &data.executors {
+        let executor = executor.trim().to_string();
+        if !executors.contains(&executor) {
+            executors.push(executor);
+        }
+    }
+    if executors.is_empty() {
+        return Err(BenchmarkError::Invalid(
+            "Choose at least one executor to benchmark".to_string(),
+        ));
+    }
+    {
+        let config = app_state.get_config().read().await;
+        for name in &executors {
+            let executor = ExecutorConfig::from_str(name)
+                .ok()
+                .filter(|executor| !matches!(executor, ExecutorConfig::SetupScript { .. }))
+                .ok_or_else(|| BenchmarkError::Invalid(format!("Unknown executor: {}", name)))?;
+            config
+                .check_executor_enabled(&executor)
+                .and_then(|()| executor.check_available(&ExecutableLookup::from_env()))
+                .map_err(BenchmarkError::Invalid)?;
+        }
+    }
+
+    let max_duration_secs = data
+        .max_duration_secs
+        .unwrap_or(DEFAULT_BENCHMARK_MAX_DURATION_SECS);
+    if max_duration_secs == 0 {
+        return Err(BenchmarkError::Invalid(
+            "The time budget must be at least a second".to_string(),
+        ));
+    }
+    let test_command = data
+        .test_command
+        .as_deref()
+        .map(str::trim)
+        .filter(|command| !command.is_empty());
+
+    let git_service = GitService::new(&project.git_repo_path)?;
+    let candidates = BenchmarkRun::find_candidates(pool, project.id).await?;
+    let mut selected = Vec::new();
+    match &data.task_ids {
+        Some(task_ids) => {
+            for task_id in task_ids {
+                let candidate = candidates
+                    .iter()
+                    .find(|candidate| candidate.task_id == *task_id)
+                    .ok_or_else(|| {
+                        BenchmarkError::Invalid(format!(
+                            "Task {} has no merged attempt to benchmark against",
+                            task_id
+                        ))
+                    })?;
+                let revision = git_service
+                    .merged_revision(
+                        &candidate.merge_commit,
+                        candidate.attempt_created_at.timestamp(),
+                    )
+                    .map_err(|e| {
+                        BenchmarkError::Invalid(format!(
+                            "Can't tell what task {} merged: {}",
+                            task_id, e
+                        ))
+                    })?;
+                selected.push((candidate, revision));
+            }
+        }
+        None => {
+            let task_count = data
+                .task_count
+                .unwrap_or(DEFAULT_BENCHMARK_TASK_COUNT)
+                .clamp(1, MAX_BENCHMARK_TASK_COUNT) as usize;
+            for candidate in &candidates {
+                if selected.len() == task_count {
+                    break;
+                }
+                // Merges that can't be traced back to where they started are left out
+                match git_service.merged_revision(
+                    &candidate.merge_commit,
+                    candidate.attempt_created_at.timestamp(),
+                ) {
+                    Ok(revision) if revision.base != revision.head => {
+                        selected.push((candidate, revision))
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::debug!(
+                        "Skipping task {} for benchmarking: {}",
+                        candidate.task_id,
+                        e
+                    ),
+                }
+            }
+        }
+    }
+    if selected.is_empty() {
+        return Err(BenchmarkError::Invalid(
+            "This project has no merged tasks to benchmark".to_string(),
+        ));
+    }
+
+    let run = BenchmarkRun::create(
+        pool,
+        project.id,
+        &executors,
+        test_command,
+        max_duration_secs,
+    )
+    .await?;
+    for (candidate, revision) in selected {
+        for executor in &executors {
+            BenchmarkAttempt::create(
+                pool,
+                run.id,
+                candidate.task_id,
+                executor,
+                candidate.source_attempt_id,
+                &revision.base.to_string(),
+                &revision.head.to_string(),
+            )
+            .await?;
+        }
+    }
+
+    spawn_run(app_state, run.id);
+    Ok(run)
+}
+
+/// Continue runs the server stopped in the middle of. Attempts that were running are
+/// recorded as failed since their processes are gone.
+pub async fn resume_runs(app_state: &AppState) -> Result<(), sqlx::Error> {
+    for run_id in BenchmarkRun::find_running_ids(&app_state.db_pool).await? {
+        BenchmarkAttempt::fail_running(
+            &app_state.db_pool,
+            run_id,
+            "Interrupted by a server restart",
+        )
+        .await?;
+        spawn_run(app_state, run_id);
+    }
+    Ok(())
+}
+
+fn spawn_run(app_state: &AppState, run_id: Uuid) {
+    let app_state = app_state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = run_pending_attempts(&app_state, run_id).await {
+            tracing::error!("Benchmark run {} failed: {}", run_id, e);
+        }
+    });
+}
+
+async fn run_pending_attempts(app_state: &AppState, run_id: Uuid) -> Result<(), BenchmarkError> {
+    let pool = &app_state.db_pool;
+    for attempt in BenchmarkAttempt::find_by_run_id(pool, run_id).await? {
+        if attempt.status != BenchmarkAttemptStatus::Pending {
+            continue;
+        }
+        let _turn = queue().lock().await;
+        if BenchmarkRun::status(pool, run_id).await? != BenchmarkRunStatus::Running {
+            return Ok(());
+        }
+        let (status, score) = match run_attempt(app_state, &attempt).await {
+            Ok(outcome) => outcome,
+            Err(e) => (
+                BenchmarkAttemptStatus::Failed,
+                BenchmarkScore {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                },
+            ),
+        };
+        BenchmarkAttempt::finish(pool, attempt.id, status, &score).await?;
+    }
+    BenchmarkRun::finish(pool, run_id, BenchmarkRunStatus::Completed).await?;
+    Ok(())
+}
+
+/// How the agent of an attempt ended, once it has
+fn agent_outcome(processes: &[ExecutionProcess]) -> Option<bool> {
+    let finished = |p: &&ExecutionProcess| p.status != ExecutionProcessStatus::Running;
+    if let Some(agent) = processes
+        .iter()
+        .filter(|p| p.process_type == ExecutionProcessType::CodingAgent)
+        .find(finished)
+    {
+        return Some(agent.status == ExecutionProcessStatus::Completed);
+    }
+    processes
+        .iter()
+        .filter(|p| p.process_type == ExecutionProcessType::SetupScript)
+        .filter(finished)
+        .any(|p| p.status != ExecutionProcessStatus::Completed)
+        .then_some(false)
+}
+
+async fn run_attempt(
+    app_state: &AppState,
+    benchmark: &BenchmarkAttempt,
+) -> Result<(BenchmarkAttemptStatus, BenchmarkScore), TaskAttemptError> {
+    let pool = &app_state.db_pool;
+    let run_id = benchmark.run_id;
+    let task = Task::find_by_id(pool, benchmark.task_id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+    let project = Project::find_by_id(pool, task.project_id)
+        .await?
+        .ok_or(TaskAttemptError::ProjectNotFound)?;
+    let run = BenchmarkRun::find_by_id(pool, project.id, run_id)
+        .await?
+        .ok_or_else(|| TaskAttemptError::ValidationError("Benchmark run not found".to_string()))?;
+
+    let worktree_name_pattern = app_state
+        .get_config()
+        .read()
+        .await
+        .worktree_name_pattern
+        .clone();
+    let attempt = TaskAttempt::create_for_benchmark(
+        pool,
+        &CreateTaskAttempt {
+            executor: Some(benchmark.executor.clone()),
+            base_branch: None,
+            env: Default::default(),
+            retry_of: None,
+        },
+        task.id,
+        &worktree_name_pattern,
+        &BenchmarkBase {
+            run_id,
+            base_commit: benchmark.base_commit.clone(),
+        },
+    )
+    .await?;
+    BenchmarkAttempt::mark_running(pool, benchmark.id, attempt.id).await?;
+    TaskAttempt::start_execution(pool, app_state, attempt.id, task.id, project.id).await?;
+
+    let deadline = Instant::now() + Duration::from_secs(u64::from(run.max_duration_secs));
+    // The reason is recorded on the benchmark attempt, and as the stop note when it had to
+    // be stopped
+    let (status, reason, stop) = loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt.id).await?;
+        match agent_outcome(&processes) {
+            Some(true) => break (BenchmarkAttemptStatus::Completed, None, false),
+            Some(false) => {
+                break (
+                    BenchmarkAttemptStatus::Failed,
+                    Some("The agent or setup script failed"),
+                    false,
+                )
+            }
+            None => {}
+        }
+        if Instant::now() >= deadline {
+            break (
+                BenchmarkAttemptStatus::TimedOut,
+                Some("Benchmark time budget used up"),
+                true,
+            );
+        }
+        if BenchmarkRun::status(pool, run_id).await? == BenchmarkRunStatus::Cancelled {
+            break (
+                BenchmarkAttemptStatus::Failed,
+                Some("Benchmark run cancelled"),
+                true,
+            );
+        }
+    };
+    if stop {
+        attempt_stop::stop_attempt(app_state, attempt.id, reason).await?;
+    }
+
+    let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt.id).await?;
+    let mut score = BenchmarkScore {
+        cost_usd: processes
+            .iter()
+            .filter(|p| p.process_type == ExecutionProcessType::CodingAgent)
+            .filter_map(|p| p.stdout.as_deref().and_then(reported_cost_usd))
+            .reduce(|a, b| a + b),
+        duration_secs: Some(
+            processes
+                .iter()
+                .filter(|p| p.process_type != ExecutionProcessType::DevServer)
+                .filter_map(|p| Some((p.completed_at? - p.started_at).num_milliseconds()))
+                .sum::<i64>() as f64
+                / 1000.0,
+        ),
+        error: reason.map(str::to_string),
+        ..Default::default()
+    };
+
+    match score_diff(&project.git_repo_path, &attempt.worktree_path, benchmark) {
+        Ok(similarity) => score.diff_similarity = Some(similarity),
+        Err(e) => {
+            tracing::warn!("Failed to score benchmark attempt {}: {}", benchmark.id, e);
+            score.error.get_or_insert_with(|| e.to_string());
+        }
+    }
+    if let (Some(test_command), BenchmarkAttemptStatus::Completed) = (&run.test_command, status) {
+        score.tests_passed = Some(
+            run_test_command(
+                test_command,
+                &attempt.worktree_path,
+                Duration::from_secs(u64::from(run.max_duration_secs)),
+            )
+            .await,
+        );
+    }
+
+    Ok((status, score))
+}
+
+/// Similarity of the attempt's worktree, committed or not, to what was merged for the task
+fn score_diff(
+    git_repo_path: &str,
+    worktree_path: &str,
+    benchmark: &BenchmarkAttempt,
+) -> Result<f64, GitServiceError> {
+    let git_service = GitService::new(git_repo_path)?;
+    let base = git2::Oid::from_str(&benchmark.base_commit)?;
+    let reference = git_service.diff_lines(DiffRevision {
+        base,
+        head: git2::Oid::from_str(&benchmark.reference_commit)?,
+    })?;
+    let result = git_service.worktree_diff_lines(std::path::Path::new(worktree_path), base)?;
+    Ok(diff_similarity(&reference, &result))
+}
+
+/// Dice coefficient of the changed lines of two diffs, ignoring blank lines: 1 when both
+/// add and remove the same lines in the same files, 0 when they share none
+pub fn diff_similarity(
+    reference: &[(char, String, String)],
+    result: &[(char, String, String)],
+) -> f64 {
+    let significant = |lines: &[(char, String, String)]| -> Vec<(char, String, String)> {
+        lines
+            .iter()
+            .filter(|(_, _, content)| !content.trim().is_empty())
+            .map(|(origin, path, content)| (*origin, path.clone(), content.trim().to_string()))
+            .collect()
+    };
+    let reference = significant(reference);
+    let result = significant(result);
+    if reference.is_empty() && result.is_empty() {
+        return 1.0;
+    }
+
+    let mut remaining: HashMap<&(char, String, String), usize> = HashMap::new();
+    for line in &reference {
+        *remaining.entry(line).or_default() += 1;
+    }
+    let mut shared = 0;
+    for line in &result {
+        if let Some(count) = remaining.get_mut(line).filter(|count| **count > 0) {
+            *count -= 1;
+            shared += 1;
+        }
+    }
+    2.0 * shared as f64 / (reference.len() + result.len()) as f64
+}
+
+/// Cost an executor printed in its JSON output, like the `total_cost_usd` of Claude's result
+/// line; the last one wins
+pub fn reported_cost_usd(stdout: &str) -> Option<f64> {
+    stdout
+        .lines()
+        .rev()
+        .map(str::trim)
+        .filter(|line| line.starts_with('{'))
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find_map(|value| {
+            value
+                .get("total_cost_usd")
+                .or_else(|| value.get("cost_usd"))
+                .and_then(serde_json::Value::as_f64)
+        })
+}
+
+/// Whether the run's test command exits successfully in the worktree within `timeout`
+async fn run_test_command(command: &str, worktree_path: &str, timeout: Duration) -> bool {
+    let (shell_cmd, shell_arg) = get_shell_command();
+    let status = tokio::process::Command::new(shell_cmd)
+        .arg(shell_arg)
+        .arg(command)
+        .current_dir(worktree_path)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .status();
+    matches!(
+        tokio::time::timeout(timeout, status).await,
+        Ok(Ok(status)) if status.success()
+    )
+}
+
+/// Titles of the tasks in a run, for its report
+pub async fn task_titles(
+    pool: &sqlx::SqlitePool,
+    attempts: &[BenchmarkAttempt],
+) -> Result<HashMap<Uuid, String>, sqlx::Error> {
+    let mut titles = HashMap::new();
+    let task_ids: HashSet<Uuid> = attempts.iter().map(|attempt| attempt.task_id).collect();
+    for task_id in task_ids {
+        if let Some(task) = Task::find_by_id(pool, task_id).await? {
+            titles.insert(task_id, task.title);
+        }
+    }
+    Ok(titles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[(char, &str, &str)]) -> Vec<(char, String, String)> {
+        lines
+            .iter()
+            .map(|(origin, path, content)| (*origin, path.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_similarity_and_reported_cost() {
+        let reference = lines(&[
+            ('-', "src/lib.rs", "    old_call();"),
+            ('+', "src/lib.rs", "    new_call();"),
+            ('+', "src/lib.rs", ""),
+            ('+', "src/lib.rs", "    log();"),
+        ]);
+        assert_eq!(diff_similarity(&reference, &reference), 1.0);
+        // Indentation and blank lines don't count against a result
+        let reindented = lines(&[
+            ('-', "src/lib.rs", "old_call();"),
+            ('+', "src/lib.rs", "\tnew_call();"),
+            ('+', "src/lib.rs", "\tlog();"),
+        ]);
+        assert_eq!(diff_similarity(&reference, &reindented), 1.0);
+        // The same line in another file is a different change
+        let elsewhere = lines(&[('+', "src/main.rs", "new_call();")]);
+        assert_eq!(diff_similarity(&reference, &elsewhere), 0.0);
+        let partial = lines(&[('+', "src/lib.rs", "new_call();")]);
+        assert_eq!(diff_similarity(&reference, &partial), 0.5);
+        assert_eq!(diff_similarity(&[], &[]), 1.0);
+
+        let stdout = r#"{"type":"system","subtype":"init"}
+{"type":"assistant","message":{"content":[]}}
+{"type":"result","subtype":"success","total_cost_usd":0.4213,"duration_ms":81234}"#;
+        assert_eq!(reported_cost_usd(stdout), Some(0.4213));
+        assert_eq!(reported_cost_usd("plain output\nno cost here"), None);
+    }
+}
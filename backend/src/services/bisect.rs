@@ -0,0 +1,405 @@
+//! Bisecting an attempt's commits to find the one that broke a test command
+//!
+//! A run checks out commits one after another, so it gets a temporary worktree of its own,
+//! detached at the attempt's head, and streams `git bisect` as an execution process. The
+//! worktree is removed when the run ends, however it ends; runs left over by a restart are
+//! finished by the reconciliation loop, and worktree maintenance removes what's left.
+
+use std::path::Path;
+
+use git2::{DiffOptions, Oid, Patch, Repository};
+use sqlx::SqlitePool;
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    executors::bisect::{BAD_COMMIT_PASSES_EXIT_CODE, GOOD_COMMIT_FAILS_EXIT_CODE},
+    models::{
+        bisect_run::{BisectCommit, BisectFileChange, BisectRun, BisectRunStatus},
+        execution_process::{ExecutionProcess, ExecutionProcessStatus},
+        project::Project,
+        task::Task,
+        task_attempt::{TaskAttempt, TaskAttemptError},
+    },
+    services::{GitService, ProcessService},
+    utils::worktree_manager::WorktreeManager,
+};
+
+/// Start bisecting an attempt's commits with `command`, or the project's test script when
+/// it's not given
+pub async fn start(
+    app_state: &AppState,
+    attempt_id: Uuid,
+    command: Option<&str>,
+) -> Result<BisectRun, TaskAttemptError> {
+    let pool = &app_state.db_pool;
+    let attempt = TaskAttempt::find_by_id(pool, attempt_id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+    let task = Task::find_by_id(pool, attempt.task_id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+    let project = Project::find_by_id(pool, task.project_id)
+        .await?
+        .ok_or(TaskAttemptError::ProjectNotFound)?
+        .with_repo_config();
+    if project.is_scratch() {
+        return Err(TaskAttemptError::GitUnavailable("Bisecting"));
+    }
+
+    let command = [command, project.test_script.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|command| !command.is_empty())
+        .ok_or_else(|| {
+            TaskAttemptError::ValidationError(
+                "Give a test command, or set a test script for the project".to_string(),
+            )
+        })?
+        .to_string();
+
+    if BisectRun::has_running(pool, attempt.id).await? {
+        return Err(TaskAttemptError::ValidationError(
+            "This attempt is already being bisected".to_string(),
+        ));
+    }
+
+    let git_service = GitService::new(&project.git_repo_path)?;
+    let revision = git_service.branch_revision(&attempt.branch, &attempt.base_branch)?;
+    if git_service.count_commits(revision)? == 0 {
+        return Err(TaskAttemptError::ValidationError(
+            "The attempt has no commits to bisect".to_string(),
+        ));
+    }
+
+    let run_id = Uuid::new_v4();
+    let worktree_path = TaskAttempt::get_worktree_base_dir().join(format!("bisect-{}", run_id));
+    let worktree_path = worktree_path.to_string_lossy().to_string();
+    let head = revision.head.to_string();
+    if let Err(e) = add_detached_worktree(&project.git_repo_path, &worktree_path, &head).await {
+        remove_worktree(&project.git_repo_path, &worktree_path).await;
+        return Err(e);
+    }
+
+    let run = match BisectRun::create(
+        pool,
+        run_id,
+        attempt.id,
+        &revision.base.to_string(),
+        &head,
+        &command,
+        &worktree_path,
+    )
+    .await
+    {
+        Ok(run) => run,
+        Err(e) => {
+            remove_worktree(&project.git_repo_path, &worktree_path).await;
+            return Err(e.into());
+        }
+    };
+
+    if let Err(e) = ProcessService::start_bisect(pool, app_state, &run, task.id).await {
+        let error = format!("The bisect run couldn't start: {}", e);
+        if BisectRun::complete(pool, run.id, BisectRunStatus::Failed, None, Some(&error)).await? {
+            remove_worktree(&project.git_repo_path, &worktree_path).await;
+        }
+        return Err(e);
+    }
+
+    Ok(run)
+}
+
+async fn add_detached_worktree(
+    git_repo_path: &str,
+    worktree_path: &str,
+    commit: &str,
+) -> Result<(), TaskAttemptError> {
+    let output = Command::new("git")
+        .args(["worktree", "add", "--detach", worktree_path, commit])
+        .current_dir(git_repo_path)
+        .output()
+        .await
+        .map_err(|e| {
+            TaskAttemptError::Git(git2::Error::from_str(&format!(
+                "Failed to run git worktree add: {}",
+                e
+            )))
+        })?;
+    if !output.status.success() {
+        return Err(TaskAttemptError::Git(git2::Error::from_str(&format!(
+            "git worktree add failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(())
+}
+
+async fn remove_worktree(git_repo_path: &str, worktree_path: &str) {
+    if let Err(e) =
+        WorktreeManager::cleanup_worktree(Path::new(worktree_path), Some(git_repo_path)).await
+    {
+        tracing::warn!(
+            "Failed to remove bisect worktree {}, worktree maintenance will retry: {}",
+            worktree_path,
+            e
+        );
+    }
+}
+
+/// Record the outcome of a run whose process is no longer running, and remove its worktree
+pub async fn finish_run(pool: &SqlitePool, run_id: Uuid) {
+    if let Err(e) = try_finish_run(pool, run_id).await {
+        tracing::error!("Failed to finish bisect run {}: {}", run_id, e);
+    }
+}
+
+async fn try_finish_run(pool: &SqlitePool, run_id: Uuid) -> Result<(), TaskAttemptError> {
+    let Some(run) = BisectRun::find_by_id(pool, run_id).await? else {
+        return Ok(());
+    };
+    if run.status != BisectRunStatus::Running {
+        return Ok(());
+    }
+
+    let (status, first_bad_commit, error) = match ExecutionProcess::find_by_id(pool, run.id).await?
+    {
+        Some(process) => match process.status {
+            ExecutionProcessStatus::Running => return Ok(()),
+            ExecutionProcessStatus::Killed => (BisectRunStatus::Cancelled, None, None),
+            ExecutionProcessStatus::Completed => {
+                match read_first_bad_commit(&run.worktree_path).await {
+                    Ok(commit) => (BisectRunStatus::Found, Some(commit), None),
+                    Err(e) => (
+                        BisectRunStatus::Failed,
+                        None,
+                        Some(format!(
+                            "Bisecting finished, but its result couldn't be read: {}",
+                            e
+                        )),
+                    ),
+                }
+            }
+            ExecutionProcessStatus::Failed => (
+                BisectRunStatus::Failed,
+                None,
+                Some(failure_message(process.exit_code)),
+            ),
+        },
+        None => (
+            BisectRunStatus::Failed,
+            None,
+            Some("The bisect run didn't start".to_string()),
+        ),
+    };
+
+    if BisectRun::complete(
+        pool,
+        run.id,
+        status,
+        first_bad_commit.as_ref(),
+        error.as_deref(),
+    )
+    .await?
+    {
+        let git_repo_path = match TaskAttempt::find_by_id(pool, run.task_attempt_id).await? {
+            Some(attempt) => Task::find_by_id(pool, attempt.task_id).await?,
+            None => None,
+        };
+        let git_repo_path = match git_repo_path {
+            Some(task) => Project::find_by_id(pool, task.project_id)
+                .await?
+                .map(|project| project.git_repo_path),
+            None => None,
+        };
+        match git_repo_path {
+            Some(git_repo_path) => remove_worktree(&git_repo_path, &run.worktree_path).await,
+            None => {
+                if let Err(e) =
+                    WorktreeManager::cleanup_worktree(Path::new(&run.worktree_path), None).await
+                {
+                    tracing::warn!(
+                        "Failed to remove bisect worktree {}: {}",
+                        run.worktree_path,
+                        e
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finish the runs whose process was stopped or lost without going through `finish_run`
+pub async fn finish_stale_runs(pool: &SqlitePool) {
+    match BisectRun::find_stale(pool).await {
+        Ok(runs) => {
+            for run in runs {
+                finish_run(pool, run.id).await;
+            }
+        }
+        Err(e) => tracing::error!("Failed to look up finished bisect runs: {}", e),
+    }
+}
+
+fn failure_message(exit_code: Option<i64>) -> String {
+    match exit_code {
+        Some(BAD_COMMIT_PASSES_EXIT_CODE) => {
+            "The test command passes at the attempt's head, so there's no failure to bisect"
+                .to_string()
+        }
+        Some(GOOD_COMMIT_FAILS_EXIT_CODE) => {
+            "The test command already fails at the merge base, so the failure predates the attempt"
+                .to_string()
+        }
+        Some(code) => format!(
+            "git bisect stopped with exit code {} before singling out a commit; see the run's output",
+            code
+        ),
+        None => "The bisect run ended unexpectedly".to_string(),
+    }
+}
+
+/// The commit `git bisect` settled on in the run's worktree. Bisect refs belong to the
+/// worktree, so git itself is asked for it.
+async fn read_first_bad_commit(worktree_path: &str) -> Result<BisectCommit, git2::Error> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", "refs/bisect/bad"])
+        .current_dir(worktree_path)
+        .output()
+        .await
+        .map_err(|e| git2::Error::from_str(&format!("Failed to run git rev-parse: {}", e)))?;
+    if !output.status.success() {
+        return Err(git2::Error::from_str(
+            "git bisect didn't record a bad commit",
+        ));
+    }
+    let id = Oid::from_str(String::from_utf8_lossy(&output.stdout).trim())?;
+    describe_commit(&Repository::open(worktree_path)?, id)
+}
+
+/// A commit with the lines it changed per file, compared to its first parent
+pub fn describe_commit(repo: &Repository, id: Oid) -> Result<BisectCommit, git2::Error> {
+    let commit = repo.find_commit(id)?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(
+        parent_tree.as_ref(),
+        Some(&commit.tree()?),
+        Some(&mut DiffOptions::new()),
+    )?;
+
+    let mut files = Vec::new();
+    for index in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(&diff, index)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (_, additions, deletions) = patch.line_stats()?;
+        files.push(BisectFileChange {
+            path,
+            additions,
+            deletions,
+        });
+    }
+
+    Ok(BisectCommit {
+        id: id.to_string(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        author: commit.author().name().unwrap_or_default().to_string(),
+        committed_at: chrono::DateTime::from_timestamp(commit.time().seconds(), 0)
+            .unwrap_or_default(),
+        additions: files.iter().map(|file| file.additions).sum(),
+        deletions: files.iter().map(|file| file.deletions).sum(),
+        files,
+    })
+}
+
+/// Opening of a follow-up prompt about the commit a run found; `None` until it found one
+pub fn followup_context(run: &BisectRun) -> Option<String> {
+    let commit = run.first_bad_commit.as_ref()?;
+    let files = commit
+        .files
+        .iter()
+        .map(|file| format!("- {} (+{} -{})", file.path, file.additions, file.deletions))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(format!(
+        "Bisecting with `{}` found that commit {} (\"{}\") introduced the failure. It changed:\n{}\n\nFix the failure without reverting the rest of that commit's changes.",
+        run.command,
+        &commit.id[..commit.id.len().min(8)],
+        commit.summary,
+        files
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describes_the_lines_a_commit_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit_file = |contents: &str, message: &str| {
+            std::fs::write(dir.path().join("lib.rs"), contents).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("lib.rs")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents = repo
+                .head()
+                .ok()
+                .map(|head| head.peel_to_commit().unwrap())
+                .into_iter()
+                .collect::<Vec<_>>();
+            let parents = parents.iter().collect::<Vec<_>>();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+        };
+
+        commit_file("a\nb\n", "Add lib");
+        let id = commit_file("a\nc\nd\n", "Break lib\n\nDetails");
+
+        let commit = describe_commit(&repo, id).unwrap();
+        assert_eq!(commit.summary, "Break lib");
+        assert_eq!(commit.files.len(), 1);
+        assert_eq!(commit.files[0].path, "lib.rs");
+        assert_eq!((commit.additions, commit.deletions), (2, 1));
+
+        let run = BisectRun {
+            id: Uuid::new_v4(),
+            task_attempt_id: Uuid::new_v4(),
+            good_commit: String::new(),
+            bad_commit: id.to_string(),
+            command: "cargo test".to_string(),
+            worktree_path: String::new(),
+            status: BisectRunStatus::Found,
+            first_bad_commit: Some(commit),
+            error: None,
+            created_at: chrono::Utc::now(),
+            completed_at: None,
+        };
+        let context = followup_context(&run).unwrap();
+        assert!(context.contains(&id.to_string()[..8]));
+        assert!(context.contains("- lib.rs (+2 -1)"));
+    }
+}
@@ -0,0 +1,281 @@
+//! Branch statuses of attempts, computed in the background. Reading a repository's status
+//! can take seconds on slow file systems, so requests get the last computed status right
+//! away, and one that is too old starts a refresh the project's event stream announces once
+//! it's done.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures_util::{
+    future::{BoxFuture, Shared},
+    FutureExt,
+};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    models::task_attempt::{BranchStatus, TaskAttempt},
+    services::project_events::ProjectEventBus,
+};
+
+/// Age after which a branch status is refreshed on its next read
+pub const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// A branch status as last computed
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct CachedBranchStatus {
+    #[serde(flatten)]
+    #[ts(flatten)]
+    pub status: BranchStatus,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub computed_at: DateTime<Utc>,
+    /// Older than the refresh threshold, or the branch was changed since
+    pub stale: bool,
+    /// A refresh is under way; the project's event stream announces when it's done
+    pub refreshing: bool,
+    /// Why the last refresh failed, when it did after this status was computed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    status: Option<(BranchStatus, DateTime<Utc>)>,
+    error: Option<String>,
+    /// When the branch was last changed by a merge or rebase; statuses computed before are
+    /// stale
+    invalidated_at: Option<DateTime<Utc>>,
+}
+
+impl Entry {
+    fn is_stale(&self, computed_at: DateTime<Utc>) -> bool {
+        self.invalidated_at.is_some_and(|at| at >= computed_at)
+            || (Utc::now() - computed_at)
+                .to_std()
+                .is_ok_and(|age| age > STALE_AFTER)
+    }
+}
+
+type Refresh = Shared<BoxFuture<'static, ()>>;
+
+#[derive(Clone, Default)]
+pub struct BranchStatusCache {
+    entries: Arc<Mutex<HashMap<Uuid, Entry>>>,
+    /// Refreshes under way, which later readers join instead of starting their own
+    refreshes: Arc<Mutex<HashMap<Uuid, Refresh>>>,
+}
+
+impl std::fmt::Debug for BranchStatusCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BranchStatusCache")
+            .field("entries", &self.entries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BranchStatusCache {
+    /// The attempt's branch status as last computed, refreshed in the background when it is
+    /// stale. With `wait`, or when it was never computed, the refresh is waited for. Fails
+    /// with the error of the last refresh when there's no status to return.
+    pub async fn get(
+        &self,
+        pool: &SqlitePool,
+        events: &ProjectEventBus,
+        project_id: Uuid,
+        task_id: Uuid,
+        attempt_id: Uuid,
+        wait: bool,
+    ) -> Result<CachedBranchStatus, String> {
+        let (computed, stale) = self.state(attempt_id);
+        if wait || stale {
+            let refresh = self.refresh(pool, events, project_id, task_id, attempt_id);
+            // Otherwise it runs on its own, and the stale status is returned meanwhile
+            if wait || !computed {
+                refresh.await;
+            }
+        }
+        self.cached(attempt_id)
+    }
+
+    /// Have the next read refresh the attempt's branch status
+    pub fn invalidate(&self, attempt_id: Uuid) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(&attempt_id) {
+            entry.invalidated_at = Some(Utc::now());
+        }
+    }
+
+    /// Whether a status was computed for the attempt, and whether it is stale
+    fn state(&self, attempt_id: Uuid) -> (bool, bool) {
+        let entries = self.entries.lock().unwrap();
+        let stale = entries.get(&attempt_id).and_then(|entry| {
+            let (_, computed_at) = entry.status.as_ref()?;
+            Some(entry.is_stale(*computed_at))
+        });
+        (stale.is_some(), stale.unwrap_or(true))
+    }
+
+    fn cached(&self, attempt_id: Uuid) -> Result<CachedBranchStatus, String> {
+        let refreshing = self.refreshes.lock().unwrap().contains_key(&attempt_id);
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(&attempt_id);
+        match entry.and_then(|entry| entry.status.as_ref().map(|status| (entry, status))) {
+            Some((entry, (status, computed_at))) => Ok(CachedBranchStatus {
+                status: status.clone(),
+                computed_at: *computed_at,
+                stale: entry.is_stale(*computed_at),
+                refreshing,
+                error: entry.error.clone(),
+            }),
+            None => Err(entry
+                .and_then(|entry| entry.error.clone())
+                .unwrap_or_else(|| "Branch status hasn't been computed yet".to_string())),
+        }
+    }
+
+    /// Recompute the attempt's branch status, or join the refresh already under way. The
+    /// refresh runs to completion whether or not it's awaited.
+    fn refresh(
+        &self,
+        pool: &SqlitePool,
+        events: &ProjectEventBus,
+        project_id: Uuid,
+        task_id: Uuid,
+        attempt_id: Uuid,
+    ) -> Refresh {
+        let mut refreshes = self.refreshes.lock().unwrap();
+        if let Some(refresh) = refreshes.get(&attempt_id) {
+            return refresh.clone();
+        }
+
+        let cache = self.clone();
+        let pool = pool.clone();
+        let events = events.clone();
+        let refresh = async move {
+            let started_at = Utc::now();
+            let result =
+                TaskAttempt::get_branch_status(&pool, attempt_id, task_id, project_id).await;
+            let success = result.is_ok();
+            {
+                let mut entries = cache.entries.lock().unwrap();
+                let entry = entries.entry(attempt_id).or_default();
+                match result {
+                    Ok(status) => {
+                        entry.status = Some((status, started_at));
+                        entry.error = None;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to refresh branch status of attempt {}: {}",
+                            attempt_id,
+                            e
+                        );
+                        entry.error = Some(e.to_string());
+                    }
+                }
+            }
+            cache.refreshes.lock().unwrap().remove(&attempt_id);
+            events.branch_status_refreshed(project_id, task_id, attempt_id, success);
+        }
+        .boxed()
+        .shared();
+
+        refreshes.insert(attempt_id, refresh.clone());
+        tokio::spawn(refresh.clone());
+        refresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::{BranchType, Repository};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        services::project_events::ProjectChange,
+        test_utils::{insert_attempt_on, insert_project, insert_task, memory_pool},
+    };
+
+    #[tokio::test]
+    async fn test_refreshes_are_shared_and_failures_keep_the_last_status() {
+        let repo_dir = TempDir::new().unwrap();
+        let repo = Repository::init(repo_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let head = repo
+            .commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+        let head = repo.find_commit(head).unwrap();
+        repo.branch("base", &head, false).unwrap();
+        repo.branch("attempt", &head, false).unwrap();
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, &repo_path).await;
+        let task_id = insert_task(&pool, project_id, "t").await;
+        let attempt_id = insert_attempt_on(&pool, task_id, &repo_path, "attempt", "base").await;
+
+        let cache = BranchStatusCache::default();
+        let events = ProjectEventBus::default();
+        let mut subscription = events.subscribe(project_id, None).events;
+        let mut refreshed = || {
+            let mut count = 0;
+            while let Ok(event) = subscription.try_recv() {
+                assert!(matches!(
+                    event.change,
+                    ProjectChange::BranchStatusRefreshed { attempt_id: id, .. } if id == attempt_id
+                ));
+                count += 1;
+            }
+            count
+        };
+        let get = |wait: bool| {
+            let (cache, pool, events) = (cache.clone(), pool.clone(), events.clone());
+            async move {
+                cache
+                    .get(&pool, &events, project_id, task_id, attempt_id, wait)
+                    .await
+            }
+        };
+
+        // Nothing computed yet, so both wait, for the same refresh
+        let (first, second) = tokio::join!(get(false), get(false));
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.computed_at, second.computed_at);
+        assert!(first.status.up_to_date && !first.stale && first.error.is_none());
+        assert_eq!(refreshed(), 1);
+
+        let cached = get(false).await.unwrap();
+        assert_eq!(cached.computed_at, first.computed_at);
+        assert!(!cached.stale && !cached.refreshing);
+        assert_eq!(refreshed(), 0);
+
+        // A merge or rebase makes it stale; it's returned while a refresh starts, which a
+        // caller that waits joins
+        cache.invalidate(attempt_id);
+        let stale = get(false).await.unwrap();
+        assert!(stale.stale && stale.refreshing);
+        assert_eq!(stale.computed_at, first.computed_at);
+        let fresh = get(true).await.unwrap();
+        assert!(fresh.computed_at > first.computed_at && !fresh.stale && !fresh.refreshing);
+        assert_eq!(refreshed(), 1);
+
+        // A failed refresh keeps the last status along with the error
+        repo.find_branch("attempt", BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+        let failed = get(true).await.unwrap();
+        assert_eq!(failed.computed_at, fresh.computed_at);
+        assert!(failed.error.is_some());
+        assert_eq!(refreshed(), 1);
+    }
+}
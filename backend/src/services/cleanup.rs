@@ -0,0 +1,207 @@
+use std::{path::Path, time::Duration};
+
+use chrono::{DateTime, Utc};
+use tokio::process::Command;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    app_state::AppState,
+    models::task_attempt::{IdleWorktree, TaskAttempt},
+    utils::worktree_manager::WorktreeManager,
+};
+
+/// How often the job scheduler runs the cleanup
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+pub const DEFAULT_MAX_DISK_GB: f64 = 10.0;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Removes worktrees of attempts that have been idle for longer than `retention_days`, then
+/// the least recently active ones until idle worktrees fit in `max_disk_gb`
+pub struct TaskAttemptCleanupService {
+    app_state: AppState,
+    pub retention_days: u32,
+    pub max_disk_gb: f64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CleanupReport {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+impl TaskAttemptCleanupService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            app_state,
+            retention_days: DEFAULT_RETENTION_DAYS,
+            max_disk_gb: DEFAULT_MAX_DISK_GB,
+        }
+    }
+
+    /// Remove what is due and report it
+    pub async fn run(&self) -> Result<CleanupReport, sqlx::Error> {
+        let report = self.run_once().await?;
+        if report.removed == 0 {
+            debug!("No worktrees to clean up");
+            return Ok(report);
+        }
+
+        info!(
+            "Cleaned up {} worktrees, freed {} bytes",
+            report.removed, report.freed_bytes
+        );
+        self.app_state
+            .track_analytics_event(
+                "worktrees_cleaned",
+                Some(serde_json::json!({
+                    "count": report.removed,
+                    "freed_bytes": report.freed_bytes,
+                })),
+            )
+            .await;
+        Ok(report)
+    }
+
+    async fn run_once(&self) -> Result<CleanupReport, sqlx::Error> {
+        let pool = &self.app_state.db_pool;
+
+        let mut candidates = Vec::new();
+        for worktree in TaskAttempt::find_idle_worktrees(pool).await? {
+            let path = worktree.worktree_path.clone();
+            let size = tokio::task::spawn_blocking(move || {
+                let path = Path::new(&path);
+                path.exists().then(|| dir_size(path))
+            })
+            .await
+            .unwrap_or(None);
+
+            // Worktrees deleted by hand are picked up by the execution monitor
+            if let Some(size) = size {
+                candidates.push((worktree, size));
+            }
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::days(i64::from(self.retention_days));
+        let max_bytes = (self.max_disk_gb * BYTES_PER_GB) as u64;
+        let activity: Vec<_> = candidates
+            .iter()
+            .map(|(worktree, size)| (worktree.last_activity_at, *size))
+            .collect();
+
+        let mut report = CleanupReport::default();
+        for index in select_for_removal(&activity, cutoff, max_bytes) {
+            let (worktree, size) = &candidates[index];
+            if let Err(e) = remove_worktree(worktree).await {
+                error!(
+                    "Failed to remove worktree for attempt {}: {}",
+                    worktree.attempt_id, e
+                );
+                continue;
+            }
+            TaskAttempt::mark_worktree_deleted(pool, worktree.attempt_id).await?;
+            report.removed += 1;
+            report.freed_bytes += size;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Indices of the worktrees to remove, given `(last_activity_at, size)` ordered from least
+/// recently active: everything older than `cutoff`, then the oldest of the rest until the
+/// total size is within `max_bytes`
+fn select_for_removal(
+    worktrees: &[(DateTime<Utc>, u64)],
+    cutoff: DateTime<Utc>,
+    max_bytes: u64,
+) -> Vec<usize> {
+    let mut remaining: u64 = worktrees.iter().map(|(_, size)| size).sum();
+
+    worktrees
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (last_activity_at, size))| {
+            if *last_activity_at < cutoff || remaining > max_bytes {
+                remaining -= size;
+                Some(index)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// `git worktree remove --force`, falling back to removing the directory and git metadata by
+/// hand when git can't (e.g. the main repository has moved)
+async fn remove_worktree(worktree: &IdleWorktree) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["worktree", "remove", "--force", &worktree.worktree_path])
+        .current_dir(&worktree.git_repo_path)
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        result => {
+            let reason = match result {
+                Ok(output) => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                Err(e) => e.to_string(),
+            };
+            warn!(
+                "git worktree remove failed for {}, cleaning up manually: {}",
+                worktree.worktree_path, reason
+            );
+            WorktreeManager::cleanup_worktree(
+                Path::new(&worktree.worktree_path),
+                Some(&worktree.git_repo_path),
+            )
+            .await
+            .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Total size of the files under `path`, not following symlinks
+fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| dir_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_for_removal_applies_retention_then_disk_budget() {
+        let now = Utc::now();
+        let days_ago = |days| now - chrono::Duration::days(days);
+        let cutoff = days_ago(30);
+        let worktrees = [
+            (days_ago(45), 100),
+            (days_ago(20), 300),
+            (days_ago(10), 300),
+            (days_ago(1), 300),
+        ];
+
+        // Only the expired worktree goes while the rest fit in the budget
+        assert_eq!(select_for_removal(&worktrees, cutoff, 1000), vec![0]);
+        // Over budget, the least recently active ones go next
+        assert_eq!(select_for_removal(&worktrees, cutoff, 400), vec![0, 1, 2]);
+        assert!(select_for_removal(&worktrees[1..], cutoff, 1000).is_empty());
+    }
+}
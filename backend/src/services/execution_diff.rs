@@ -0,0 +1,353 @@
+//! What a single execution of an attempt changed. The worktree is snapshotted when each
+//! execution ends, so two executions can be diffed against each other, or one against the
+//! execution before it. Snapshot commits are kept by refs of their own; when one was pruned
+//! anyway, the commit HEAD was at when it was taken is used instead, which is the same tree
+//! once the execution's changes were committed.
+
+use std::path::Path;
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        execution_process::ExecutionProcess,
+        execution_snapshot::ExecutionSnapshot,
+        project::Project,
+        task::Task,
+        task_attempt::{TaskAttempt, WorktreeDiff},
+    },
+    services::{GitService, GitServiceError},
+    utils::path_ignore::PathIgnore,
+};
+
+#[derive(Debug)]
+pub enum ExecutionDiffError {
+    AttemptNotFound,
+    /// Not an execution of the attempt
+    ExecutionNotFound(Uuid),
+    /// The execution is still running, or ended before snapshots were taken
+    NoSnapshot(Uuid),
+    /// Neither the execution's snapshot nor the commit it was taken on are in the
+    /// repository anymore
+    SnapshotUnavailable(Uuid),
+    /// The attempt belongs to a scratch project, which has no repository
+    GitUnavailable,
+    Git(GitServiceError),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for ExecutionDiffError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionDiffError::AttemptNotFound => write!(f, "Task attempt not found"),
+            ExecutionDiffError::ExecutionNotFound(id) => {
+                write!(f, "Execution {} is not part of this attempt", id)
+            }
+            ExecutionDiffError::NoSnapshot(id) => write!(
+                f,
+                "Execution {} has no snapshot; it is still running or ended before snapshots were recorded",
+                id
+            ),
+            ExecutionDiffError::SnapshotUnavailable(id) => write!(
+                f,
+                "The snapshot of execution {} was pruned and its commit is gone too",
+                id
+            ),
+            ExecutionDiffError::GitUnavailable => {
+                write!(f, "{}", Project::git_unavailable_message("Diffs"))
+            }
+            ExecutionDiffError::Git(e) => write!(f, "Git error: {}", e),
+            ExecutionDiffError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionDiffError {}
+
+impl From<sqlx::Error> for ExecutionDiffError {
+    fn from(e: sqlx::Error) -> Self {
+        ExecutionDiffError::Database(e)
+    }
+}
+
+impl From<GitServiceError> for ExecutionDiffError {
+    fn from(e: GitServiceError) -> Self {
+        ExecutionDiffError::Git(e)
+    }
+}
+
+async fn git_project(
+    pool: &SqlitePool,
+    attempt: &TaskAttempt,
+) -> Result<Project, ExecutionDiffError> {
+    let task = Task::find_by_id(pool, attempt.task_id)
+        .await?
+        .ok_or(ExecutionDiffError::AttemptNotFound)?;
+    let project = Project::find_by_id(pool, task.project_id)
+        .await?
+        .ok_or(ExecutionDiffError::AttemptNotFound)?;
+    if project.is_scratch() {
+        return Err(ExecutionDiffError::GitUnavailable);
+    }
+    Ok(project)
+}
+
+/// Snapshot the attempt's worktree as the execution left it
+pub async fn record_snapshot(
+    pool: &SqlitePool,
+    attempt: &TaskAttempt,
+    execution_process_id: Uuid,
+) -> Result<(), ExecutionDiffError> {
+    let project = git_project(pool, attempt).await?;
+    let worktree_path = attempt.worktree_path.clone();
+    let snapshot = tokio::task::spawn_blocking(move || {
+        GitService::new(&project.git_repo_path)?.snapshot_worktree(
+            Path::new(&worktree_path),
+            &ExecutionSnapshot::ref_name(execution_process_id),
+        )
+    })
+    .await
+    .map_err(|e| GitServiceError::Git(git2::Error::from_str(&e.to_string())))??;
+
+    ExecutionSnapshot::save(
+        pool,
+        execution_process_id,
+        attempt.id,
+        &snapshot.commit.to_string(),
+        &snapshot.head.to_string(),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn find_snapshot(
+    pool: &SqlitePool,
+    attempt_id: Uuid,
+    execution_process_id: Uuid,
+) -> Result<ExecutionSnapshot, ExecutionDiffError> {
+    match ExecutionProcess::find_by_id(pool, execution_process_id).await? {
+        Some(process) if process.task_attempt_id == attempt_id => {}
+        _ => return Err(ExecutionDiffError::ExecutionNotFound(execution_process_id)),
+    }
+    ExecutionSnapshot::find_by_execution_process_id(pool, execution_process_id)
+        .await?
+        .ok_or(ExecutionDiffError::NoSnapshot(execution_process_id))
+}
+
+fn snapshot_tree(
+    git_service: &GitService,
+    snapshot: &ExecutionSnapshot,
+) -> Result<git2::Oid, ExecutionDiffError> {
+    git_service
+        .first_available_tree(&[&snapshot.snapshot_commit, &snapshot.head_commit])?
+        .ok_or(ExecutionDiffError::SnapshotUnavailable(
+            snapshot.execution_process_id,
+        ))
+}
+
+/// Changes from the end of `from_execution` to the end of `to_execution`. Without
+/// `from_execution`, from the end of the execution before `to_execution`, or from where the
+/// attempt branched off when there is none, making it what `to_execution` itself changed.
+pub async fn diff_executions(
+    pool: &SqlitePool,
+    attempt_id: Uuid,
+    from_execution: Option<Uuid>,
+    to_execution: Uuid,
+    rename_threshold: u16,
+    ignored_paths: PathIgnore,
+) -> Result<WorktreeDiff, ExecutionDiffError> {
+    let attempt = TaskAttempt::find_by_id(pool, attempt_id)
+        .await?
+        .ok_or(ExecutionDiffError::AttemptNotFound)?;
+    let project = git_project(pool, &attempt).await?;
+
+    let to = find_snapshot(pool, attempt_id, to_execution).await?;
+    let from = match from_execution {
+        Some(id) => Some(find_snapshot(pool, attempt_id, id).await?),
+        None => ExecutionSnapshot::find_previous(pool, to_execution).await?,
+    };
+
+    let git_service = GitService::new(&project.git_repo_path)?
+        .with_rename_threshold(rename_threshold)
+        .with_ignored_paths(ignored_paths);
+    let new_tree = snapshot_tree(&git_service, &to)?;
+    let old_tree = match &from {
+        Some(from) => snapshot_tree(&git_service, from)?,
+        None => {
+            let revision = match &attempt.merge_commit {
+                Some(merge_commit) => {
+                    git_service.merged_revision(merge_commit, attempt.created_at.timestamp())?
+                }
+                None => git_service.branch_revision(&attempt.branch, &attempt.base_branch)?,
+            };
+            git_service
+                .first_available_tree(&[&revision.base.to_string()])?
+                .ok_or(ExecutionDiffError::SnapshotUnavailable(to_execution))?
+        }
+    };
+
+    Ok(git_service.diff_trees(old_tree, new_tree)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Repository;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        models::execution_process::{CreateExecutionProcess, ExecutionProcessType},
+        test_utils::{insert_attempt_on, insert_project, insert_task, memory_pool},
+    };
+
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"].iter(), None).unwrap();
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    fn paths(diff: &WorktreeDiff) -> Vec<&str> {
+        let mut paths: Vec<_> = diff.files.iter().map(|file| file.path.as_str()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_diffs_between_the_executions_of_an_attempt() {
+        let repo_dir = TempDir::new().unwrap();
+        let repo = Repository::init(repo_dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        std::fs::write(repo_dir.path().join("a.txt"), "one\n").unwrap();
+        commit_all(&repo, "Initial commit");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("base", &head, false).unwrap();
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, &repo_path).await;
+        let task_id = insert_task(&pool, project_id, "t").await;
+        let attempt_id = insert_attempt_on(&pool, task_id, &repo_path, &branch, "base").await;
+        let attempt = TaskAttempt::find_by_id(&pool, attempt_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut executions = Vec::new();
+        for second in 1..=4 {
+            let id = Uuid::new_v4();
+            ExecutionProcess::create(
+                &pool,
+                &CreateExecutionProcess {
+                    task_attempt_id: attempt_id,
+                    process_type: ExecutionProcessType::CodingAgent,
+                    executor_type: Some("echo".to_string()),
+                    command: "echo".to_string(),
+                    args: None,
+                    working_directory: repo_path.clone(),
+                },
+                id,
+            )
+            .await
+            .unwrap();
+            sqlx::query("UPDATE execution_processes SET created_at = $1 WHERE id = $2")
+                .bind(format!("2025-07-01 12:00:0{}.000", second))
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            executions.push(id);
+        }
+        let (first, second, third, running) =
+            (executions[0], executions[1], executions[2], executions[3]);
+
+        // The first run's changes were committed, the others' were left in the worktree
+        std::fs::write(repo_dir.path().join("a.txt"), "two\n").unwrap();
+        commit_all(&repo, "First run");
+        record_snapshot(&pool, &attempt, first).await.unwrap();
+        std::fs::write(repo_dir.path().join("b.txt"), "new\n").unwrap();
+        record_snapshot(&pool, &attempt, second).await.unwrap();
+        std::fs::write(repo_dir.path().join("c.txt"), "newer\n").unwrap();
+        record_snapshot(&pool, &attempt, third).await.unwrap();
+        // Snapshots leave the worktree's index alone
+        assert!(repo
+            .index()
+            .unwrap()
+            .get_path(Path::new("b.txt"), 0)
+            .is_none());
+
+        let diff = |from: Option<Uuid>, to: Uuid| {
+            let pool = pool.clone();
+            async move { diff_executions(&pool, attempt_id, from, to, 50, PathIgnore::default()).await }
+        };
+        assert_eq!(paths(&diff(None, first).await.unwrap()), vec!["a.txt"]);
+        assert_eq!(paths(&diff(None, second).await.unwrap()), vec!["b.txt"]);
+        assert_eq!(paths(&diff(None, third).await.unwrap()), vec!["c.txt"]);
+        assert_eq!(
+            paths(&diff(Some(first), third).await.unwrap()),
+            vec!["b.txt", "c.txt"]
+        );
+        assert!(matches!(
+            diff(None, running).await,
+            Err(ExecutionDiffError::NoSnapshot(id)) if id == running
+        ));
+        let other = Uuid::new_v4();
+        assert!(matches!(
+            diff(Some(other), third).await,
+            Err(ExecutionDiffError::ExecutionNotFound(id)) if id == other
+        ));
+
+        // A pruned snapshot is recomputed from the commit it was taken on, which doesn't have
+        // the second run's uncommitted file
+        let gone = "0".repeat(40);
+        repo.find_reference(&ExecutionSnapshot::ref_name(second))
+            .unwrap()
+            .delete()
+            .unwrap();
+        sqlx::query(
+            "UPDATE execution_snapshots SET snapshot_commit = $1 WHERE execution_process_id = $2",
+        )
+        .bind(&gone)
+        .bind(second)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            paths(&diff(None, third).await.unwrap()),
+            vec!["b.txt", "c.txt"]
+        );
+
+        sqlx::query(
+            "UPDATE execution_snapshots SET head_commit = $1 WHERE execution_process_id = $2",
+        )
+        .bind(&gone)
+        .bind(second)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(matches!(
+            diff(None, third).await,
+            Err(ExecutionDiffError::SnapshotUnavailable(id)) if id == second
+        ));
+    }
+}
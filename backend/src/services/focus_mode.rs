@@ -0,0 +1,49 @@
+//! Focus mode pauses running agents, setup scripts and bisects for a while so the machine
+//! stays usable, e.g. during a meeting. Dev servers keep running. Nothing is killed: paused
+//! processes resume when focus mode is ended or runs out.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct FocusModeStatus {
+    pub active: bool,
+    /// When the paused processes resume on their own
+    #[serde(with = "crate::utils::rfc3339::option")]
+    pub until: Option<DateTime<Utc>>,
+    /// Execution processes focus mode paused
+    pub paused_process_ids: Vec<Uuid>,
+}
+
+/// Pause running agents for `minutes`, or extend focus mode to `minutes` from now when it's
+/// already on. Agents started in the meantime are paused too.
+pub async fn start(app_state: &AppState, minutes: u32) -> FocusModeStatus {
+    let duration = Duration::from_secs(u64::from(minutes) * 60);
+    let until = Utc::now() + chrono::Duration::minutes(i64::from(minutes));
+    app_state.pause_executions_until(until).await;
+
+    let state = app_state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(duration).await;
+        // Unless it was ended or extended in the meantime
+        if state.focus_mode_until() == Some(until) {
+            tracing::info!("Focus mode ran out, resuming paused executions");
+            state.resume_paused_executions().await;
+        }
+    });
+
+    app_state.focus_mode_status().await
+}
+
+/// Resume everything focus mode paused
+pub async fn end(app_state: &AppState) -> FocusModeStatus {
+    app_state.resume_paused_executions().await;
+    app_state.focus_mode_status().await
+}
@@ -0,0 +1,360 @@
+//! Formatting the files an attempt changed once its coding agent is done
+//!
+//! The project's format script runs as an execution process of its own in the attempt's
+//! worktree, after the agent's changes are committed and before the task goes up for review.
+//! What the formatters change is committed as a separate style commit, so diffs can show the
+//! agent's changes without it, or amended into the agent's commit. A failing formatter is
+//! flagged on the run and in the attempt's activity, but doesn't fail the attempt.
+
+use std::path::Path;
+
+use git2::{Oid, Repository};
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    execution_monitor::{finish_coding_agent, record_execution_snapshot},
+    models::{
+        execution_process::{ExecutionProcess, ExecutionProcessStatus},
+        format_run::{CreateFormatRun, FormatRun, FormatRunStatus},
+        project::{FormatCommitMode, Project},
+        task::Task,
+        task_attempt::{TaskAttempt, TaskAttemptError},
+    },
+    services::{GitService, ProcessService},
+};
+
+/// Message of the separate commit of a format run's changes
+const STYLE_COMMIT_MESSAGE: &str = "Style: format changed files";
+
+/// Start formatting the files the attempt changed, after its coding agent run
+/// `coding_agent_process_id`. `None` when the project has no format script, nothing changed or
+/// the attempt was cancelled, in which case the caller finishes the coding agent stage itself.
+pub async fn start(
+    app_state: &AppState,
+    task_attempt: &TaskAttempt,
+    coding_agent_process_id: Uuid,
+    agent_succeeded: bool,
+    agent_exit_code: Option<i64>,
+) -> Result<Option<FormatRun>, TaskAttemptError> {
+    if app_state.is_attempt_cancelled(task_attempt.id) {
+        return Ok(None);
+    }
+    let pool = &app_state.db_pool;
+    let task = Task::find_by_id(pool, task_attempt.task_id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+    let project = Project::find_by_id(pool, task.project_id)
+        .await?
+        .ok_or(TaskAttemptError::ProjectNotFound)?
+        .with_repo_config();
+    let Some(script) = project
+        .format_script
+        .as_deref()
+        .map(str::trim)
+        .filter(|script| !script.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    let ignored = app_state.get_config().read().await.path_ignore();
+    let files = GitService::new(&project.git_repo_path)?
+        .with_ignored_paths(ignored)
+        .changed_files(
+            Path::new(&task_attempt.worktree_path),
+            &task_attempt.base_branch,
+        )?;
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let run = FormatRun::create(
+        pool,
+        Uuid::new_v4(),
+        &CreateFormatRun {
+            task_attempt_id: task_attempt.id,
+            coding_agent_process_id,
+            agent_succeeded,
+            agent_exit_code,
+            command: script,
+            files: &files,
+            commit_mode: project.format_commit_mode,
+        },
+    )
+    .await?;
+
+    if let Err(e) = ProcessService::start_format_script(
+        pool,
+        app_state,
+        &run,
+        task.id,
+        &task_attempt.worktree_path,
+    )
+    .await
+    {
+        let error = format!("The format script couldn't start: {}", e);
+        FormatRun::complete(pool, run.id, FormatRunStatus::Failed, None, Some(&error)).await?;
+        return Err(e);
+    }
+
+    Ok(Some(run))
+}
+
+/// Commit what a run's process changed, record how it ended and finish the coding agent stage
+/// it held back
+pub async fn finish_run(app_state: &AppState, run_id: Uuid) {
+    if let Err(e) = try_finish_run(app_state, run_id).await {
+        tracing::error!("Failed to finish format run {}: {}", run_id, e);
+    }
+}
+
+async fn try_finish_run(app_state: &AppState, run_id: Uuid) -> Result<(), TaskAttemptError> {
+    let pool = &app_state.db_pool;
+    let Some(run) = FormatRun::find_by_id(pool, run_id).await? else {
+        return Ok(());
+    };
+    if run.status != FormatRunStatus::Running {
+        return Ok(());
+    }
+    let attempt = TaskAttempt::find_by_id(pool, run.task_attempt_id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+
+    let failure = match ExecutionProcess::find_by_id(pool, run.id).await? {
+        Some(process) => match process.status {
+            ExecutionProcessStatus::Running => return Ok(()),
+            ExecutionProcessStatus::Completed => None,
+            ExecutionProcessStatus::Killed => Some((
+                FormatRunStatus::Cancelled,
+                "Formatting was stopped".to_string(),
+            )),
+            ExecutionProcessStatus::Failed => Some((
+                FormatRunStatus::Failed,
+                match process.exit_code {
+                    Some(code) => format!(
+                        "A formatter failed with exit code {}; see the run's output",
+                        code
+                    ),
+                    None => "The format run ended unexpectedly".to_string(),
+                },
+            )),
+        },
+        None => Some((
+            FormatRunStatus::Failed,
+            "The format run didn't start".to_string(),
+        )),
+    };
+
+    // Whatever the formatters did change is committed even when one of them failed, so the
+    // worktree is left clean for review
+    let worktree_path = attempt.worktree_path.clone();
+    let commit_mode = run.commit_mode;
+    let committed = tokio::task::spawn_blocking(move || {
+        commit_formatting(Path::new(&worktree_path), commit_mode)
+    })
+    .await
+    .map_err(|e| TaskAttemptError::Git(git2::Error::from_str(&e.to_string())))?;
+
+    let (status, commit_sha, error) = match (committed, failure) {
+        (Err(e), _) => (
+            FormatRunStatus::Failed,
+            None,
+            Some(format!("The formatted files couldn't be committed: {}", e)),
+        ),
+        (Ok(commit), Some((status, error))) => (status, commit, Some(error)),
+        (Ok(None), None) => (FormatRunStatus::Unchanged, None, None),
+        (Ok(commit), None) => (FormatRunStatus::Formatted, commit, None),
+    };
+    let commit_sha = match commit_mode {
+        FormatCommitMode::Separate => commit_sha.map(|id| id.to_string()),
+        // Folded changes are part of the agent's commit, there's nothing to separate
+        FormatCommitMode::Fold => None,
+    };
+
+    if !FormatRun::complete(
+        pool,
+        run.id,
+        status,
+        commit_sha.as_deref(),
+        error.as_deref(),
+    )
+    .await?
+    {
+        return Ok(());
+    }
+    record_execution_snapshot(app_state, &attempt, run.id).await;
+
+    let note = match (status, &commit_sha, &error) {
+        (FormatRunStatus::Cancelled, _, _) => "formatting was stopped".to_string(),
+        (_, _, Some(error)) => format!("formatting failed: {}", error),
+        (FormatRunStatus::Formatted, Some(commit), _) => {
+            format!("formatting committed as {}", &commit[..commit.len().min(8)])
+        }
+        (FormatRunStatus::Formatted, None, _) => {
+            "formatting folded into the agent's commit".to_string()
+        }
+        _ => "files were already formatted".to_string(),
+    };
+    finish_coding_agent(
+        app_state,
+        &attempt,
+        run.coding_agent_process_id,
+        run.agent_succeeded,
+        run.agent_exit_code,
+        Some(&note),
+    )
+    .await;
+    Ok(())
+}
+
+/// Finish the runs whose process was stopped or lost without going through `finish_run`
+pub async fn finish_stale_runs(app_state: &AppState) {
+    match FormatRun::find_stale(&app_state.db_pool).await {
+        Ok(run_ids) => {
+            for run_id in run_ids {
+                finish_run(app_state, run_id).await;
+            }
+        }
+        Err(e) => tracing::error!("Failed to look up finished format runs: {}", e),
+    }
+}
+
+/// Commit the worktree's changes as a style commit, or amend them into HEAD. Returns the new
+/// commit, or `None` when there was nothing to commit.
+pub fn commit_formatting(
+    worktree_path: &Path,
+    mode: FormatCommitMode,
+) -> Result<Option<Oid>, git2::Error> {
+    let repo = Repository::open(worktree_path)?;
+    let head = repo.head()?.peel_to_commit()?;
+
+    let mut index = repo.index()?;
+    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"].iter(), None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    if tree.id() == head.tree_id() {
+        return Ok(None);
+    }
+
+    let id = match mode {
+        FormatCommitMode::Separate => {
+            let signature = repo.signature()?;
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                STYLE_COMMIT_MESSAGE,
+                &tree,
+                &[&head],
+            )?
+        }
+        FormatCommitMode::Fold => head.amend(Some("HEAD"), None, None, None, None, Some(&tree))?,
+    };
+    Ok(Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::models::{
+        format_run::FormattingDiff,
+        task_attempt::{DiffChunkType, WorktreeDiff},
+    };
+
+    fn commit_all(repo: &Repository, message: &str) -> Oid {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_formatting_is_committed_apart_and_can_be_left_out_of_the_diff() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        let write = |path: &str, contents: &str| std::fs::write(dir.path().join(path), contents);
+
+        write("lib.rs", "fn a() {}\n").unwrap();
+        let base = commit_all(&repo, "Initial");
+        repo.branch("main", &repo.find_commit(base).unwrap(), true)
+            .unwrap();
+
+        write("lib.rs", "fn a() {}\nfn b( ) {}\n").unwrap();
+        write("new.rs", "fn c( ) {}\n").unwrap();
+        commit_all(&repo, "Agent changes");
+        assert_eq!(
+            commit_formatting(dir.path(), FormatCommitMode::Separate).unwrap(),
+            None
+        );
+
+        let git_service = GitService::new(dir.path()).unwrap();
+        let mut files = git_service.changed_files(dir.path(), "main").unwrap();
+        files.sort();
+        assert_eq!(files, ["lib.rs", "new.rs"]);
+
+        write("lib.rs", "fn a() {}\nfn b() {}\n").unwrap();
+        write("new.rs", "fn c() {}\n").unwrap();
+        let style = commit_formatting(dir.path(), FormatCommitMode::Separate)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            repo.find_commit(style).unwrap().summary(),
+            Some(STYLE_COMMIT_MESSAGE)
+        );
+
+        let revision = git_service.diff_revision(dir.path(), "main").unwrap();
+        let inserted = |diff: &WorktreeDiff| {
+            diff.files
+                .iter()
+                .flat_map(|file| &file.chunks)
+                .filter(|chunk| matches!(chunk.chunk_type, DiffChunkType::Insert))
+                .map(|chunk| chunk.content.clone())
+                .collect::<String>()
+        };
+        let format_commits = [style.to_string()];
+        let agent_only = git_service
+            .get_formatting_diff(revision, &format_commits, FormattingDiff::Exclude)
+            .unwrap();
+        assert!(inserted(&agent_only).contains("fn b( ) {}"));
+        let formatting_only = git_service
+            .get_formatting_diff(revision, &format_commits, FormattingDiff::Only)
+            .unwrap();
+        assert_eq!(formatting_only.files.len(), 2);
+        assert!(inserted(&formatting_only).contains("fn b() {}"));
+
+        // Folding amends the agent's commit instead
+        write("new.rs", "fn c() {}\nfn d() {}\n").unwrap();
+        commit_all(&repo, "More agent changes");
+        write("new.rs", "fn c() {}\n\nfn d() {}\n").unwrap();
+        let folded = commit_formatting(dir.path(), FormatCommitMode::Fold)
+            .unwrap()
+            .unwrap();
+        let folded = repo.find_commit(folded).unwrap();
+        assert_eq!(folded.summary(), Some("More agent changes"));
+        assert_eq!(folded.parent_id(0).unwrap(), style);
+        assert_eq!(
+            folded.tree().unwrap().id(),
+            repo.head().unwrap().peel_to_tree().unwrap().id()
+        );
+    }
+}
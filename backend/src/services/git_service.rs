@@ -1,4 +1,8 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 use git2::{
     BranchType, DiffOptions, Error as GitError, RebaseOptions, Repository, WorktreeAddOptions,
@@ -7,16 +11,43 @@ use regex;
 use tracing::{debug, info};
 
 use crate::{
-    models::task_attempt::{DiffChunk, DiffChunkType, FileDiff, WorktreeDiff},
-    utils::worktree_manager::WorktreeManager,
+    models::{
+        format_run::FormattingDiff,
+        project::HeadState,
+        task_attempt::{DiffChunk, DiffChunkType, FileDiff, WorktreeDiff},
+    },
+    utils::{path_ignore::PathIgnore, worktree_manager::WorktreeManager},
 };
 
+/// The commits a worktree's committed diff is taken between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRevision {
+    /// Merge base of the worktree's HEAD and the base branch
+    pub base: git2::Oid,
+    pub head: git2::Oid,
+}
+
+/// The state of a worktree recorded at some point, like the end of an execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorktreeSnapshot {
+    /// Commit holding the worktree's files, uncommitted changes included
+    pub commit: git2::Oid,
+    /// The worktree's HEAD at the time
+    pub head: git2::Oid,
+}
+
+/// Per changed file (by its path at the merge base), the half-open line ranges of the merge
+/// base that its hunks replace. Added, deleted and binary files cover every line.
+pub type ChangedLines = BTreeMap<String, Vec<Range<u32>>>;
+
 #[derive(Debug)]
 pub enum GitServiceError {
     Git(GitError),
     IoError(std::io::Error),
     InvalidRepository(String),
     BranchNotFound(String),
+    DetachedHead(String),
+    Worktree(WorktreeFailure),
 
     MergeConflicts(String),
     InvalidPath(String),
@@ -29,6 +60,12 @@ impl std::fmt::Display for GitServiceError {
             GitServiceError::IoError(e) => write!(f, "IO error: {}", e),
             GitServiceError::InvalidRepository(e) => write!(f, "Invalid repository: {}", e),
             GitServiceError::BranchNotFound(e) => write!(f, "Branch not found: {}", e),
+            GitServiceError::DetachedHead(commit) => write!(
+                f,
+                "Repository HEAD is detached at {}; choose a base branch explicitly",
+                commit
+            ),
+            GitServiceError::Worktree(e) => write!(f, "{}", e),
 
             GitServiceError::MergeConflicts(e) => write!(f, "Merge conflicts: {}", e),
             GitServiceError::InvalidPath(e) => write!(f, "Invalid path: {}", e),
@@ -50,9 +87,108 @@ impl From<std::io::Error> for GitServiceError {
     }
 }
 
+/// Common reasons `git worktree add` fails, each with a remediation hint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorktreeFailure {
+    /// The attempt branch name is already taken
+    BranchExists(String),
+    /// The branch is checked out in another worktree (or the main repository)
+    BranchCheckedOut(String),
+    /// A locked worktree is registered under the same name
+    Locked(String),
+    /// The worktree directory or its git metadata already exists
+    PathExists(String),
+    Other(String),
+}
+
+impl std::fmt::Display for WorktreeFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorktreeFailure::BranchExists(branch) => write!(
+                f,
+                "Branch '{}' already exists. Delete or rename it, or start a new attempt to get a fresh branch name",
+                branch
+            ),
+            WorktreeFailure::BranchCheckedOut(branch) => write!(
+                f,
+                "Branch '{}' is already checked out in another worktree. Remove that worktree with `git worktree remove` or switch it to another branch",
+                branch
+            ),
+            WorktreeFailure::Locked(name) => write!(
+                f,
+                "Worktree '{}' is locked. Run `git worktree unlock {}` in the project repository and retry",
+                name, name
+            ),
+            WorktreeFailure::PathExists(path) => write!(
+                f,
+                "Worktree location '{}' is already in use. Remove the directory or run `git worktree prune` and retry",
+                path
+            ),
+            WorktreeFailure::Other(message) => write!(f, "Failed to create worktree: {}", message),
+        }
+    }
+}
+
+impl WorktreeFailure {
+    /// Map a git error from adding the worktree `worktree_name` for `branch_name` to a known failure
+    pub fn classify(
+        repo: &Repository,
+        err: &GitError,
+        branch_name: &str,
+        worktree_name: &str,
+        worktree_path: &Path,
+    ) -> Self {
+        let message = err.message().to_ascii_lowercase();
+        let locked = repo.find_worktree(worktree_name).is_ok_and(|worktree| {
+            !matches!(worktree.is_locked(), Ok(git2::WorktreeLockStatus::Unlocked))
+        });
+
+        if message.contains("already checked out") {
+            WorktreeFailure::BranchCheckedOut(branch_name.to_string())
+        } else if message.contains("locked") || (err.code() == git2::ErrorCode::Exists && locked) {
+            WorktreeFailure::Locked(worktree_name.to_string())
+        } else if err.code() == git2::ErrorCode::Exists
+            && err.class() == git2::ErrorClass::Reference
+        {
+            WorktreeFailure::BranchExists(branch_name.to_string())
+        } else if err.code() == git2::ErrorCode::Exists || message.contains("already exists") {
+            WorktreeFailure::PathExists(worktree_path.display().to_string())
+        } else {
+            WorktreeFailure::Other(err.message().to_string())
+        }
+    }
+}
+
+/// Paths named in the dirty source repository warning before it is summarised
+const DIRTY_REPO_WARNING_PATHS: usize = 3;
+
+/// Warning for starting an attempt while the source repository has uncommitted changes, which
+/// the attempt's worktree won't include since it is checked out from a commit
+pub fn dirty_source_repo_warning(changes: &[String]) -> Option<String> {
+    if changes.is_empty() {
+        return None;
+    }
+    let mut paths = changes[..changes.len().min(DIRTY_REPO_WARNING_PATHS)].join(", ");
+    if changes.len() > DIRTY_REPO_WARNING_PATHS {
+        paths.push_str(&format!(
+            " and {} more",
+            changes.len() - DIRTY_REPO_WARNING_PATHS
+        ));
+    }
+    Some(format!(
+        "The project repository has uncommitted changes ({}). The attempt's worktree starts from the committed base branch and won't include them.",
+        paths
+    ))
+}
+
+/// Default similarity percentage for rename and copy detection in diffs
+pub const DEFAULT_RENAME_THRESHOLD: u16 = 50;
+
 /// Service for managing Git operations in task execution workflows
 pub struct GitService {
     repo_path: PathBuf,
+    rename_threshold: u16,
+    ignored_paths: PathIgnore,
 }
 
 impl GitService {
@@ -77,7 +213,23 @@ impl GitService {
             ))
         })?;
 
-        Ok(Self { repo_path })
+        Ok(Self {
+            repo_path,
+            rename_threshold: DEFAULT_RENAME_THRESHOLD,
+            ignored_paths: PathIgnore::default(),
+        })
+    }
+
+    /// Set the similarity percentage (1-100) used to detect renamed and copied files
+    pub fn with_rename_threshold(mut self, threshold: u16) -> Self {
+        self.rename_threshold = threshold.clamp(1, 100);
+        self
+    }
+
+    /// Leave files matching `ignored_paths` out of diffs
+    pub fn with_ignored_paths(mut self, ignored_paths: PathIgnore) -> Self {
+        self.ignored_paths = ignored_paths;
+        self
     }
 
     /// Open the repository
@@ -106,31 +258,73 @@ impl GitService {
                 .map_err(|_| GitServiceError::BranchNotFound(base_branch.to_string()))?;
             branch.into_reference()
         } else {
-            // Handle new repositories without any commits
-            match repo.head() {
-                Ok(head_ref) => head_ref,
-                Err(e)
-                    if e.class() == git2::ErrorClass::Reference
-                        && e.code() == git2::ErrorCode::UnbornBranch =>
-                {
-                    // Repository has no commits yet, create an initial commit
+            match HeadState::of(&repo)? {
+                HeadState::Branch { .. } => repo.head()?,
+                // A detached HEAD has no branch to base the attempt on
+                HeadState::Detached { commit } => {
+                    return Err(GitServiceError::DetachedHead(commit));
+                }
+                // Handle new repositories without any commits
+                HeadState::Unborn => {
                     self.create_initial_commit(&repo)?;
                     repo.find_reference("refs/heads/main")?
                 }
-                Err(e) => return Err(e.into()),
             }
         };
 
-        // Create branch
-        repo.branch(branch_name, &base_reference.peel_to_commit()?, false)?;
+        Self::create_branch_worktree(
+            &repo,
+            branch_name,
+            worktree_path,
+            &base_reference.peel_to_commit()?,
+        )
+    }
 
-        let branch = repo.find_branch(branch_name, BranchType::Local)?;
-        let branch_ref = branch.into_reference();
-        let mut worktree_opts = WorktreeAddOptions::new();
-        worktree_opts.reference(Some(&branch_ref));
+    /// Create a worktree with a new branch starting at a given commit, e.g. one a merged
+    /// attempt started from
+    pub fn create_worktree_at_commit(
+        &self,
+        branch_name: &str,
+        worktree_path: &Path,
+        commit: &str,
+    ) -> Result<(), GitServiceError> {
+        let repo = self.open_repo()?;
+        if let Some(parent) = worktree_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let commit = repo.find_commit(git2::Oid::from_str(commit)?)?;
+        Self::create_branch_worktree(&repo, branch_name, worktree_path, &commit)
+    }
 
-        // Create the worktree at the specified path
-        repo.worktree(branch_name, worktree_path, Some(&worktree_opts))?;
+    fn create_branch_worktree(
+        repo: &Repository,
+        branch_name: &str,
+        worktree_path: &Path,
+        base_commit: &git2::Commit,
+    ) -> Result<(), GitServiceError> {
+        // Create branch
+        repo.branch(branch_name, base_commit, false).map_err(|e| {
+            GitServiceError::Worktree(WorktreeFailure::classify(
+                repo,
+                &e,
+                branch_name,
+                branch_name,
+                worktree_path,
+            ))
+        })?;
+
+        // Named after its directory, which is how worktree cleanup finds the git metadata
+        let worktree_name = worktree_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(branch_name);
+        if let Err(e) = Self::add_worktree(repo, worktree_name, branch_name, worktree_path) {
+            // Don't leave behind the branch of a worktree that was never created
+            if let Ok(mut branch) = repo.find_branch(branch_name, BranchType::Local) {
+                let _ = branch.delete();
+            }
+            return Err(e);
+        }
 
         info!(
             "Created worktree '{}' at path: {}",
@@ -140,6 +334,42 @@ impl GitService {
         Ok(())
     }
 
+    /// Delete a local branch, e.g. one created for an attempt that failed to start
+    pub fn delete_branch(&self, branch_name: &str) -> Result<(), GitServiceError> {
+        let repo = self.open_repo()?;
+        repo.find_branch(branch_name, BranchType::Local)
+            .map_err(|_| GitServiceError::BranchNotFound(branch_name.to_string()))?
+            .delete()?;
+        Ok(())
+    }
+
+    /// Check out an existing local branch into a new worktree named `worktree_name`
+    fn add_worktree(
+        repo: &Repository,
+        worktree_name: &str,
+        branch_name: &str,
+        worktree_path: &Path,
+    ) -> Result<(), GitServiceError> {
+        let branch = repo
+            .find_branch(branch_name, BranchType::Local)
+            .map_err(|_| GitServiceError::BranchNotFound(branch_name.to_string()))?;
+        let branch_ref = branch.into_reference();
+        let mut worktree_opts = WorktreeAddOptions::new();
+        worktree_opts.reference(Some(&branch_ref));
+
+        repo.worktree(worktree_name, worktree_path, Some(&worktree_opts))
+            .map_err(|e| {
+                GitServiceError::Worktree(WorktreeFailure::classify(
+                    repo,
+                    &e,
+                    branch_name,
+                    worktree_name,
+                    worktree_path,
+                ))
+            })?;
+        Ok(())
+    }
+
     /// Create an initial commit for empty repositories
     fn create_initial_commit(&self, repo: &Repository) -> Result<(), GitServiceError> {
         let signature = repo.signature().unwrap_or_else(|_| {
@@ -171,12 +401,14 @@ impl GitService {
         Ok(())
     }
 
-    /// Merge changes from a worktree branch back to the main repository
+    /// Merge changes from a worktree branch into `base_branch` of the main repository.
+    /// `message` is used when the merge needs a merge commit.
     pub fn merge_changes(
         &self,
         worktree_path: &Path,
         branch_name: &str,
-        task_title: &str,
+        base_branch: &str,
+        message: &str,
     ) -> Result<String, GitServiceError> {
         let main_repo = self.open_repo()?;
 
@@ -188,9 +420,17 @@ impl GitService {
             .find_branch(branch_name, BranchType::Local)
             .map_err(|_| GitServiceError::BranchNotFound(branch_name.to_string()))?;
 
-        // Get the current HEAD of the main repo (usually main/master)
-        let main_head = main_repo.head()?;
-        let main_commit = main_head.peel_to_commit()?;
+        // Merge into the attempt's base branch rather than whatever HEAD points at, which
+        // may be detached or, in a bare repository, not checked out anywhere
+        let base = main_repo
+            .find_branch(base_branch, BranchType::Local)
+            .map_err(|_| GitServiceError::BranchNotFound(base_branch.to_string()))?;
+        let base_refname = base
+            .get()
+            .name()
+            .ok_or_else(|| GitServiceError::BranchNotFound(base_branch.to_string()))?
+            .to_string();
+        let base_commit = base.get().peel_to_commit()?;
 
         // Get the signature for the merge commit
         let signature = main_repo.signature()?;
@@ -198,35 +438,46 @@ impl GitService {
         // Get the branch commit (this should be the same as the worktree commit)
         let branch_commit = branch.get().peel_to_commit()?;
 
-        // Perform a merge operation using git2's merge facilities
-        let annotated_commit = main_repo.find_annotated_commit(branch_commit.id())?;
-        let analysis = main_repo.merge_analysis(&[&annotated_commit])?;
-
-        if analysis.0.is_fast_forward() {
-            // Fast-forward merge - just update HEAD
-            let refname = format!("refs/heads/{}", main_head.shorthand().unwrap_or("main"));
-            main_repo.reference(&refname, branch_commit.id(), true, "Fast-forward merge")?;
-            main_repo.reset(branch_commit.as_object(), git2::ResetType::Hard, None)?;
+        let fast_forward = branch_commit.id() == base_commit.id()
+            || main_repo.graph_descendant_of(branch_commit.id(), base_commit.id())?;
+        let merged_id = if fast_forward {
+            // Fast-forward merge - just move the base branch
+            main_repo.reference(
+                &base_refname,
+                branch_commit.id(),
+                true,
+                "Fast-forward merge",
+            )?;
             info!("Fast-forward merge completed");
-            Ok(branch_commit.id().to_string())
+            branch_commit.id()
         } else {
-            // Create a proper merge commit
+            // Create a proper merge commit on the base branch
             let merge_commit_id = main_repo.commit(
-                Some("HEAD"),                                    // Update HEAD
-                &signature,                                      // Author
-                &signature,                                      // Committer
-                &format!("Merge: {} (codecommand)", task_title), // Message using task title
-                &branch_commit.tree()?,                          // Use the tree from branch
-                &[&main_commit, &branch_commit], // Parents: main HEAD and branch commit
+                Some(&base_refname),             // Update the base branch
+                &signature,                      // Author
+                &signature,                      // Committer
+                message,                         // Message naming the task
+                &branch_commit.tree()?,          // Use the tree from branch
+                &[&base_commit, &branch_commit], // Parents: base branch and branch commit
             )?;
-
-            // Reset the working directory to match the new HEAD
-            let merge_commit = main_repo.find_commit(merge_commit_id)?;
-            main_repo.reset(merge_commit.as_object(), git2::ResetType::Hard, None)?;
-
             info!("Created merge commit: {}", merge_commit_id);
-            Ok(merge_commit_id.to_string())
+            merge_commit_id
+        };
+
+        // Keep the checkout in sync when the base branch is what it has checked out; bare
+        // repositories have no working directory and a detached HEAD is left where it is
+        let base_checked_out = !main_repo.is_bare()
+            && main_repo
+                .head()
+                .ok()
+                .and_then(|head| head.name().map(|name| name == base_refname))
+                .unwrap_or(false);
+        if base_checked_out {
+            let merged = main_repo.find_commit(merged_id)?;
+            main_repo.reset(merged.as_object(), git2::ResetType::Hard, None)?;
         }
+
+        Ok(merged_id.to_string())
     }
 
     /// Rebase a worktree branch onto a new base
@@ -320,6 +571,7 @@ impl GitService {
             // Task attempt not yet merged - get worktree diff
             self.get_worktree_diff(worktree_path, base_branch, &mut files)?;
         }
+        files.retain(|file| !self.ignored_paths.is_ignored(Path::new(&file.path), false));
 
         Ok(WorktreeDiff { files })
     }
@@ -342,7 +594,7 @@ impl GitService {
         diff_opts.context_lines(10);
         diff_opts.interhunk_lines(0);
 
-        let diff = if parents.len() >= 2 {
+        let mut diff = if parents.len() >= 2 {
             let base_tree = parents[0].tree()?;
             let merged_tree = parents[1].tree()?;
             main_repo.diff_tree_to_tree(
@@ -364,45 +616,13 @@ impl GitService {
                 Some(&mut diff_opts),
             )?
         };
+        self.detect_renames(&mut diff, false)?;
 
         // Process each diff delta
         diff.foreach(
             &mut |delta, _progress| {
-                if let Some(path_str) = delta.new_file().path().and_then(|p| p.to_str()) {
-                    let old_file = delta.old_file();
-                    let new_file = delta.new_file();
-
-                    if let Ok(diff_chunks) =
-                        self.generate_git_diff_chunks(&main_repo, &old_file, &new_file, path_str)
-                    {
-                        if !diff_chunks.is_empty() {
-                            files.push(FileDiff {
-                                path: path_str.to_string(),
-                                chunks: diff_chunks,
-                            });
-                        } else if delta.status() == git2::Delta::Added
-                            || delta.status() == git2::Delta::Deleted
-                        {
-                            files.push(FileDiff {
-                                path: path_str.to_string(),
-                                chunks: vec![DiffChunk {
-                                    chunk_type: if delta.status() == git2::Delta::Added {
-                                        DiffChunkType::Insert
-                                    } else {
-                                        DiffChunkType::Delete
-                                    },
-                                    content: format!(
-                                        "{} file",
-                                        if delta.status() == git2::Delta::Added {
-                                            "Added"
-                                        } else {
-                                            "Deleted"
-                                        }
-                                    ),
-                                }],
-                            });
-                        }
-                    }
+                if let Some(file_diff) = self.file_diff_from_delta(&main_repo, &delta) {
+                    files.push(file_diff);
                 }
                 true
             },
@@ -448,53 +668,18 @@ impl GitService {
         diff_opts.context_lines(10);
         diff_opts.interhunk_lines(0);
 
-        let diff = worktree_repo.diff_tree_to_tree(
+        let mut diff = worktree_repo.diff_tree_to_tree(
             Some(&base_tree),
             Some(&current_tree),
             Some(&mut diff_opts),
         )?;
+        self.detect_renames(&mut diff, false)?;
 
         // Process committed changes
         diff.foreach(
             &mut |delta, _progress| {
-                if let Some(path_str) = delta.new_file().path().and_then(|p| p.to_str()) {
-                    let old_file = delta.old_file();
-                    let new_file = delta.new_file();
-
-                    if let Ok(diff_chunks) = self.generate_git_diff_chunks(
-                        &worktree_repo,
-                        &old_file,
-                        &new_file,
-                        path_str,
-                    ) {
-                        if !diff_chunks.is_empty() {
-                            files.push(FileDiff {
-                                path: path_str.to_string(),
-                                chunks: diff_chunks,
-                            });
-                        } else if delta.status() == git2::Delta::Added
-                            || delta.status() == git2::Delta::Deleted
-                        {
-                            files.push(FileDiff {
-                                path: path_str.to_string(),
-                                chunks: vec![DiffChunk {
-                                    chunk_type: if delta.status() == git2::Delta::Added {
-                                        DiffChunkType::Insert
-                                    } else {
-                                        DiffChunkType::Delete
-                                    },
-                                    content: format!(
-                                        "{} file",
-                                        if delta.status() == git2::Delta::Added {
-                                            "Added"
-                                        } else {
-                                            "Deleted"
-                                        }
-                                    ),
-                                }],
-                            });
-                        }
-                    }
+                if let Some(file_diff) = self.file_diff_from_delta(&worktree_repo, &delta) {
+                    files.push(file_diff);
                 }
                 true
             },
@@ -511,8 +696,9 @@ impl GitService {
         unstaged_diff_opts.interhunk_lines(0);
         unstaged_diff_opts.include_untracked(true);
 
-        let unstaged_diff = worktree_repo
+        let mut unstaged_diff = worktree_repo
             .diff_tree_to_workdir_with_index(Some(&current_tree), Some(&mut unstaged_diff_opts))?;
+        self.detect_renames(&mut unstaged_diff, true)?;
 
         // Process unstaged changes
         unstaged_diff.foreach(
@@ -539,6 +725,524 @@ impl GitService {
         Ok(())
     }
 
+    /// Files added or modified by a worktree's commits since its merge base with the base
+    /// branch, leaving out deleted and ignored files
+    pub fn changed_files(
+        &self,
+        worktree_path: &Path,
+        base_branch: &str,
+    ) -> Result<Vec<String>, GitServiceError> {
+        let revision = self.diff_revision(worktree_path, base_branch)?;
+        let repo = Repository::open(worktree_path)?;
+        let base_tree = repo.find_commit(revision.base)?.tree()?;
+        let head_tree = repo.find_commit(revision.head)?.tree()?;
+        let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)?;
+
+        Ok(diff
+            .deltas()
+            .filter(|delta| delta.status() != git2::Delta::Deleted)
+            .filter_map(|delta| delta.new_file().path().map(Path::to_path_buf))
+            .filter(|path| !self.ignored_paths.is_ignored(path, false))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect())
+    }
+
+    /// The committed diff of `revision` split around the commits of format runs: `Exclude`
+    /// reverts them from the head, `Only` is what they changed on top of that. The commits
+    /// are reverted newest first, so they still apply after a rebase or a squash merge.
+    pub fn get_formatting_diff(
+        &self,
+        revision: DiffRevision,
+        format_commits: &[String],
+        part: FormattingDiff,
+    ) -> Result<WorktreeDiff, GitServiceError> {
+        let repo = self.open_repo()?;
+        let base_tree = repo.find_commit(revision.base)?.tree()?;
+        let head_tree = repo.find_commit(revision.head)?.tree()?;
+
+        let mut unformatted_tree = head_tree.clone();
+        for format_commit in format_commits {
+            let commit = repo.find_commit(git2::Oid::from_str(format_commit)?)?;
+            let mut index = repo.merge_trees(
+                &commit.tree()?,
+                &unformatted_tree,
+                &commit.parent(0)?.tree()?,
+                None,
+            )?;
+            if index.has_conflicts() {
+                return Err(GitServiceError::MergeConflicts(format!(
+                    "later changes overlap the formatting commit {}",
+                    format_commit
+                )));
+            }
+            unformatted_tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+        }
+
+        let (old_tree, new_tree) = match part {
+            FormattingDiff::All => (&base_tree, &head_tree),
+            FormattingDiff::Exclude => (&base_tree, &unformatted_tree),
+            FormattingDiff::Only => (&unformatted_tree, &head_tree),
+        };
+        self.tree_diff(&repo, old_tree, new_tree)
+    }
+
+    /// Diff between two trees of the repository, such as the snapshots of two executions
+    pub fn diff_trees(
+        &self,
+        old_tree: git2::Oid,
+        new_tree: git2::Oid,
+    ) -> Result<WorktreeDiff, GitServiceError> {
+        let repo = self.open_repo()?;
+        let old_tree = repo.find_tree(old_tree)?;
+        let new_tree = repo.find_tree(new_tree)?;
+        self.tree_diff(&repo, &old_tree, &new_tree)
+    }
+
+    fn tree_diff(
+        &self,
+        repo: &Repository,
+        old_tree: &git2::Tree,
+        new_tree: &git2::Tree,
+    ) -> Result<WorktreeDiff, GitServiceError> {
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(10);
+        diff_opts.interhunk_lines(0);
+        let mut diff =
+            repo.diff_tree_to_tree(Some(old_tree), Some(new_tree), Some(&mut diff_opts))?;
+        self.detect_renames(&mut diff, false)?;
+
+        let mut files = Vec::new();
+        diff.foreach(
+            &mut |delta, _progress| {
+                if let Some(file_diff) = self.file_diff_from_delta(repo, &delta) {
+                    files.push(file_diff);
+                }
+                true
+            },
+            None,
+            None,
+            None,
+        )?;
+        files.retain(|file| !self.ignored_paths.is_ignored(Path::new(&file.path), false));
+
+        Ok(WorktreeDiff { files })
+    }
+
+    /// Record the worktree as it is now, uncommitted changes included, in a commit on top of
+    /// its HEAD that no branch points to, kept from garbage collection by `ref_name`. HEAD
+    /// itself is reused when there is nothing uncommitted. The worktree's index is left alone.
+    pub fn snapshot_worktree(
+        &self,
+        worktree_path: &Path,
+        ref_name: &str,
+    ) -> Result<WorktreeSnapshot, GitServiceError> {
+        let repo = Repository::open(worktree_path)?;
+        let head = repo.head()?.peel_to_commit()?;
+
+        // Staged in memory only; the index file isn't written
+        let mut index = repo.index()?;
+        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+        index.update_all(["*"].iter(), None)?;
+        let tree_id = index.write_tree()?;
+
+        let commit = if tree_id == head.tree_id() {
+            head.id()
+        } else {
+            let signature = repo
+                .signature()
+                .or_else(|_| git2::Signature::now("Vibe Kanban", "noreply@vibekanban.com"))?;
+            repo.commit(
+                None,
+                &signature,
+                &signature,
+                "Worktree snapshot",
+                &repo.find_tree(tree_id)?,
+                &[&head],
+            )?
+        };
+        repo.reference(ref_name, commit, true, "worktree snapshot")?;
+
+        Ok(WorktreeSnapshot {
+            commit,
+            head: head.id(),
+        })
+    }
+
+    /// Tree of the first of `commits` that is still in the repository, if any
+    pub fn first_available_tree(
+        &self,
+        commits: &[&str],
+    ) -> Result<Option<git2::Oid>, GitServiceError> {
+        let repo = self.open_repo()?;
+        for commit in commits {
+            let Ok(oid) = git2::Oid::from_str(commit) else {
+                continue;
+            };
+            match repo.find_commit(oid) {
+                Ok(commit) => return Ok(Some(commit.tree_id())),
+                Err(e) if e.code() == git2::ErrorCode::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Merge base and HEAD of a worktree; cheap enough to tell whether its diff changed
+    pub fn diff_revision(
+        &self,
+        worktree_path: &Path,
+        base_branch: &str,
+    ) -> Result<DiffRevision, GitServiceError> {
+        let worktree_repo = Repository::open(worktree_path)?;
+        let main_repo = self.open_repo()?;
+
+        let base_branch_oid = main_repo
+            .find_branch(base_branch, BranchType::Local)
+            .map_err(|_| GitServiceError::BranchNotFound(base_branch.to_string()))?
+            .get()
+            .peel_to_commit()?
+            .id();
+        let head = worktree_repo.head()?.peel_to_commit()?.id();
+        let base = worktree_repo.merge_base(base_branch_oid, head)?;
+
+        Ok(DiffRevision { base, head })
+    }
+
+    /// Merge base of a branch with its base branch, and the branch's tip, read from the main
+    /// repository so it works after the attempt's worktree is gone
+    pub fn branch_revision(
+        &self,
+        branch_name: &str,
+        base_branch: &str,
+    ) -> Result<DiffRevision, GitServiceError> {
+        let repo = self.open_repo()?;
+        let tip = |name: &str| -> Result<git2::Oid, GitServiceError> {
+            Ok(repo
+                .find_branch(name, BranchType::Local)
+                .map_err(|_| GitServiceError::BranchNotFound(name.to_string()))?
+                .get()
+                .peel_to_commit()?
+                .id())
+        };
+        let head = tip(branch_name)?;
+        let base = repo.merge_base(tip(base_branch)?, head)?;
+
+        Ok(DiffRevision { base, head })
+    }
+
+    /// Number of commits reachable from the head of `revision` but not from its base
+    pub fn count_commits(&self, revision: DiffRevision) -> Result<usize, GitServiceError> {
+        let repo = self.open_repo()?;
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(revision.head)?;
+        revwalk.hide(revision.base)?;
+        Ok(revwalk.count())
+    }
+
+    /// Whether a worktree has changes that aren't committed, untracked files included
+    pub fn has_uncommitted_changes(&self, worktree_path: &Path) -> Result<bool, GitServiceError> {
+        let repo = Repository::open(worktree_path)?;
+        let mut status_opts = git2::StatusOptions::new();
+        status_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+
+        Ok(!repo.statuses(Some(&mut status_opts))?.is_empty())
+    }
+
+    /// Paths with uncommitted changes in the repository's own working tree, untracked files
+    /// included. Bare repositories have no working tree, so nothing is reported for them.
+    pub fn source_repo_changes(&self) -> Result<Vec<String>, GitServiceError> {
+        let repo = self.open_repo()?;
+        if repo.is_bare() {
+            return Ok(Vec::new());
+        }
+        let mut status_opts = git2::StatusOptions::new();
+        status_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+
+        let statuses = repo.statuses(Some(&mut status_opts))?;
+        Ok(statuses
+            .iter()
+            .filter_map(|entry| entry.path().map(str::to_string))
+            .collect())
+    }
+
+    /// Changed files of a worktree's committed diff and the lines their hunks touch, without
+    /// loading contents for the caller
+    pub fn changed_lines(
+        &self,
+        worktree_path: &Path,
+        revision: DiffRevision,
+    ) -> Result<ChangedLines, GitServiceError> {
+        let worktree_repo = Repository::open(worktree_path)?;
+        let base_tree = worktree_repo.find_commit(revision.base)?.tree()?;
+        let head_tree = worktree_repo.find_commit(revision.head)?.tree()?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(0);
+        diff_opts.interhunk_lines(0);
+        let diff = worktree_repo.diff_tree_to_tree(
+            Some(&base_tree),
+            Some(&head_tree),
+            Some(&mut diff_opts),
+        )?;
+
+        let mut changed = ChangedLines::new();
+        for index in 0..diff.deltas().len() {
+            let Some(delta) = diff.get_delta(index) else {
+                continue;
+            };
+            let file = match delta.status() {
+                git2::Delta::Added => delta.new_file(),
+                _ => delta.old_file(),
+            };
+            let Some(path) = file.path().and_then(|p| p.to_str()) else {
+                continue;
+            };
+
+            let whole_file = vec![0..u32::MAX];
+            let ranges = match delta.status() {
+                git2::Delta::Added | git2::Delta::Deleted => whole_file,
+                _ => match git2::Patch::from_diff(&diff, index)? {
+                    Some(patch) if patch.num_hunks() > 0 => (0..patch.num_hunks())
+                        .map(|hunk| {
+                            let (hunk, _) = patch.hunk(hunk)?;
+                            // A pure insertion still conflicts with changes right next to it
+                            Ok(hunk.old_start()..hunk.old_start() + hunk.old_lines().max(1))
+                        })
+                        .collect::<Result<_, GitError>>()?,
+                    _ => whole_file,
+                },
+            };
+            changed.insert(path.to_string(), ranges);
+        }
+
+        Ok(changed)
+    }
+
+    /// Lines added between the two commits of `revision`, as `(path, line)` pairs, skipping
+    /// binary files
+    pub fn added_lines(
+        &self,
+        revision: DiffRevision,
+    ) -> Result<Vec<(String, String)>, GitServiceError> {
+        let repo = self.open_repo()?;
+        let base_tree = repo.find_commit(revision.base)?.tree()?;
+        let head_tree = repo.find_commit(revision.head)?.tree()?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(0);
+        let diff =
+            repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), Some(&mut diff_opts))?;
+
+        let mut added = Vec::new();
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            None,
+            Some(&mut |delta, _, line| {
+                if line.origin() == '+' {
+                    if let (Some(path), Ok(content)) = (
+                        delta.new_file().path().and_then(|p| p.to_str()),
+                        std::str::from_utf8(line.content()),
+                    ) {
+                        added.push((path.to_string(), content.trim_end().to_string()));
+                    }
+                }
+                true
+            }),
+        )?;
+
+        Ok(added)
+    }
+
+    /// Where a merged attempt branched off and the tip that was merged. A merge commit names
+    /// both; after a fast-forward the base is the last first-parent commit made before the
+    /// attempt was created.
+    pub fn merged_revision(
+        &self,
+        merge_commit: &str,
+        attempt_created_at: i64,
+    ) -> Result<DiffRevision, GitServiceError> {
+        let repo = self.open_repo()?;
+        let merge = repo.find_commit(git2::Oid::from_str(merge_commit)?)?;
+        if merge.parent_count() >= 2 {
+            let head = merge.parent_id(1)?;
+            let base = repo.merge_base(merge.parent_id(0)?, head)?;
+            return Ok(DiffRevision { base, head });
+        }
+
+        let mut commit = merge.clone();
+        loop {
+            commit = commit.parent(0).map_err(|_| {
+                GitServiceError::InvalidRepository(format!(
+                    "no commit before the attempt that was merged as {}",
+                    merge_commit
+                ))
+            })?;
+            if commit.time().seconds() <= attempt_created_at {
+                return Ok(DiffRevision {
+                    base: commit.id(),
+                    head: merge.id(),
+                });
+            }
+        }
+    }
+
+    /// Added and removed lines between the two commits of `revision`, as `(origin, path, line)`
+    /// with origin `+` or `-`, skipping binary files
+    pub fn diff_lines(
+        &self,
+        revision: DiffRevision,
+    ) -> Result<Vec<(char, String, String)>, GitServiceError> {
+        let repo = self.open_repo()?;
+        let base_tree = repo.find_commit(revision.base)?.tree()?;
+        let head_tree = repo.find_commit(revision.head)?.tree()?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(0);
+        let diff =
+            repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), Some(&mut diff_opts))?;
+        Self::collect_diff_lines(&diff)
+    }
+
+    /// Added and removed lines between a commit and a worktree's files, committed or not, in
+    /// the same form as [`GitService::diff_lines`]
+    pub fn worktree_diff_lines(
+        &self,
+        worktree_path: &Path,
+        base: git2::Oid,
+    ) -> Result<Vec<(char, String, String)>, GitServiceError> {
+        let worktree_repo = Repository::open(worktree_path)?;
+        let base_tree = worktree_repo.find_commit(base)?.tree()?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts
+            .context_lines(0)
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        let diff = worktree_repo
+            .diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut diff_opts))?;
+        Self::collect_diff_lines(&diff)
+    }
+
+    fn collect_diff_lines(
+        diff: &git2::Diff,
+    ) -> Result<Vec<(char, String, String)>, GitServiceError> {
+        let mut lines = Vec::new();
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            None,
+            Some(&mut |delta, _, line| {
+                let file = match line.origin() {
+                    '+' => delta.new_file(),
+                    '-' => delta.old_file(),
+                    _ => return true,
+                };
+                if let (Some(path), Ok(content)) = (
+                    file.path().and_then(|p| p.to_str()),
+                    std::str::from_utf8(line.content()),
+                ) {
+                    lines.push((
+                        line.origin(),
+                        path.to_string(),
+                        content.trim_end().to_string(),
+                    ));
+                }
+                true
+            }),
+        )?;
+        Ok(lines)
+    }
+
+    /// Collapse matching delete/add pairs into renames and copies
+    fn detect_renames(
+        &self,
+        diff: &mut git2::Diff,
+        include_untracked: bool,
+    ) -> Result<(), GitServiceError> {
+        let mut find_opts = git2::DiffFindOptions::new();
+        find_opts
+            .renames(true)
+            .copies(true)
+            .rename_threshold(self.rename_threshold)
+            .copy_threshold(self.rename_threshold)
+            .for_untracked(include_untracked);
+        diff.find_similar(Some(&mut find_opts))?;
+        Ok(())
+    }
+
+    /// Old and new paths of a delta that git detected as a rename or copy
+    fn rename_paths(delta: &git2::DiffDelta) -> (Option<String>, Option<String>) {
+        match delta.status() {
+            git2::Delta::Renamed | git2::Delta::Copied => (
+                delta
+                    .old_file()
+                    .path()
+                    .and_then(|p| p.to_str())
+                    .map(str::to_string),
+                delta
+                    .new_file()
+                    .path()
+                    .and_then(|p| p.to_str())
+                    .map(str::to_string),
+            ),
+            _ => (None, None),
+        }
+    }
+
+    /// Placeholder chunk for a file change that has no content hunks
+    fn placeholder_chunk(delta: &git2::DiffDelta, old_path: Option<&str>) -> Option<DiffChunk> {
+        let (chunk_type, content) = match (delta.status(), old_path) {
+            (git2::Delta::Added | git2::Delta::Untracked, _) => {
+                (DiffChunkType::Insert, "Added file".to_string())
+            }
+            (git2::Delta::Deleted, _) => (DiffChunkType::Delete, "Deleted file".to_string()),
+            (git2::Delta::Renamed, Some(old_path)) => {
+                (DiffChunkType::Renamed, format!("Renamed from {}", old_path))
+            }
+            (git2::Delta::Copied, Some(old_path)) => {
+                (DiffChunkType::Copied, format!("Copied from {}", old_path))
+            }
+            _ => return None,
+        };
+
+        Some(DiffChunk {
+            chunk_type,
+            content,
+        })
+    }
+
+    /// Build the file diff for a committed (tree-to-tree) delta
+    fn file_diff_from_delta(&self, repo: &Repository, delta: &git2::DiffDelta) -> Option<FileDiff> {
+        let path_str = delta.new_file().path().and_then(|p| p.to_str())?;
+        let (old_path, new_path) = Self::rename_paths(delta);
+
+        let diff_chunks = self
+            .generate_git_diff_chunks(repo, &delta.old_file(), &delta.new_file(), path_str)
+            .ok()?;
+
+        let chunks = if !diff_chunks.is_empty() {
+            // For renames and copies these are only the hunks that actually changed
+            diff_chunks
+        } else {
+            vec![Self::placeholder_chunk(delta, old_path.as_deref())?]
+        };
+
+        Some(FileDiff {
+            path: path_str.to_string(),
+            old_path,
+            new_path,
+            chunks,
+        })
+    }
+
     /// Generate diff chunks using Git's native diff algorithm
     fn generate_git_diff_chunks(
         &self,
@@ -629,10 +1333,28 @@ impl GitService {
         path_str: &str,
         delta: &git2::DiffDelta,
     ) -> Result<(), GitServiceError> {
-        // Check if we already have a diff for this file from committed changes
-        if let Some(existing_file) = files.iter_mut().find(|f| f.path == path_str) {
+        let (old_path, new_path) = Self::rename_paths(delta);
+
+        // Check if we already have a diff for this file (or its pre-rename path) from committed changes
+        if let Some(existing_file) = files
+            .iter_mut()
+            .find(|f| f.path == path_str || old_path.as_deref() == Some(f.path.as_str()))
+        {
+            // A committed rename keeps its original base path
+            let base_path = existing_file
+                .old_path
+                .clone()
+                .or_else(|| old_path.clone())
+                .unwrap_or_else(|| path_str.to_string());
+
+            if old_path.is_some() {
+                existing_file.path = path_str.to_string();
+                existing_file.old_path = Some(base_path.clone());
+                existing_file.new_path = Some(path_str.to_string());
+            }
+
             // File already has committed changes, create a combined diff
-            let base_content = self.get_base_file_content(worktree_repo, base_oid, path_str)?;
+            let base_content = self.get_base_file_content(worktree_repo, base_oid, &base_path)?;
             let working_content = self.get_working_file_content(worktree_path, path_str, delta)?;
 
             if base_content != working_content {
@@ -641,41 +1363,35 @@ impl GitService {
                 {
                     existing_file.chunks = combined_chunks;
                 }
+            } else if let Some(old_path) = &existing_file.old_path {
+                existing_file.chunks = vec![DiffChunk {
+                    chunk_type: DiffChunkType::Renamed,
+                    content: format!("Renamed from {}", old_path),
+                }];
             }
         } else {
-            // File only has unstaged changes
-            let base_content = self.get_base_file_content(worktree_repo, base_oid, path_str)?;
+            // File only has unstaged changes; renames are compared against the old path
+            let base_path = old_path.as_deref().unwrap_or(path_str);
+            let base_content = self.get_base_file_content(worktree_repo, base_oid, base_path)?;
             let working_content = self.get_working_file_content(worktree_path, path_str, delta)?;
 
             if base_content != working_content || delta.status() != git2::Delta::Modified {
-                if let Ok(chunks) =
-                    self.create_combined_diff_chunks(&base_content, &working_content, path_str)
-                {
-                    if !chunks.is_empty() {
-                        files.push(FileDiff {
-                            path: path_str.to_string(),
-                            chunks,
-                        });
-                    }
-                } else if delta.status() != git2::Delta::Modified {
-                    // Fallback for added/deleted files
+                let chunks = match self.create_combined_diff_chunks(
+                    &base_content,
+                    &working_content,
+                    path_str,
+                ) {
+                    Ok(chunks) if !chunks.is_empty() => Some(chunks),
+                    // Pure moves, or a fallback for added/deleted files
+                    _ => Self::placeholder_chunk(delta, old_path.as_deref()).map(|c| vec![c]),
+                };
+
+                if let Some(chunks) = chunks {
                     files.push(FileDiff {
                         path: path_str.to_string(),
-                        chunks: vec![DiffChunk {
-                            chunk_type: if delta.status() == git2::Delta::Added {
-                                DiffChunkType::Insert
-                            } else {
-                                DiffChunkType::Delete
-                            },
-                            content: format!(
-                                "{} file",
-                                if delta.status() == git2::Delta::Added {
-                                    "Added"
-                                } else {
-                                    "Deleted"
-                                }
-                            ),
-                        }],
+                        old_path,
+                        new_path,
+                        chunks,
                     });
                 }
             }
@@ -817,21 +1533,18 @@ impl GitService {
         Ok(commit_id.to_string())
     }
 
-    /// Get the default branch name for the repository
+    /// Get the default branch name for the repository.
+    /// Fails with `DetachedHead` when HEAD isn't on a branch, as there is no name to default to.
     pub fn get_default_branch_name(&self) -> Result<String, GitServiceError> {
         let repo = self.open_repo()?;
 
-        let result = match repo.head() {
-            Ok(head_ref) => Ok(head_ref.shorthand().unwrap_or("main").to_string()),
-            Err(e)
-                if e.class() == git2::ErrorClass::Reference
-                    && e.code() == git2::ErrorCode::UnbornBranch =>
-            {
-                Ok("main".to_string()) // Repository has no commits yet
-            }
+        match HeadState::of(&repo) {
+            Ok(HeadState::Branch { name }) => Ok(name),
+            Ok(HeadState::Detached { commit }) => Err(GitServiceError::DetachedHead(commit)),
+            // Repository has no commits yet; the initial commit is created on main
+            Ok(HeadState::Unborn) => Ok("main".to_string()),
             Err(_) => Ok("main".to_string()), // Fallback
-        };
-        result
+        }
     }
 
     /// Recreate a worktree from an existing branch (for cold task support)
@@ -879,14 +1592,10 @@ impl GitService {
             })?;
         }
 
-        // Extract repository path for WorktreeManager
+        // Extract repository path for WorktreeManager (bare repositories have no workdir)
         let repo_path = repo
             .workdir()
-            .ok_or_else(|| {
-                GitServiceError::InvalidRepository(
-                    "Repository has no working directory".to_string(),
-                )
-            })?
+            .unwrap_or_else(|| repo.path())
             .to_str()
             .ok_or_else(|| {
                 GitServiceError::InvalidRepository("Repository path is not valid UTF-8".to_string())
@@ -900,10 +1609,14 @@ impl GitService {
         )
         .await
         .map_err(|e| {
-            GitServiceError::IoError(std::io::Error::other(format!(
-                "WorktreeManager error: {}",
-                e
-            )))
+            // WorktreeManager names the worktree after the branch
+            GitServiceError::Worktree(WorktreeFailure::classify(
+                &repo,
+                &e,
+                branch_name,
+                branch_name,
+                stored_worktree_path,
+            ))
         })?;
 
         info!(
@@ -1020,6 +1733,44 @@ mod tests {
         (temp_dir, repo)
     }
 
+    fn commit_all(repo: &Repository, message: &str) -> git2::Oid {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"].iter(), None).unwrap();
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    /// Commit a 20-line file at `src/a.txt` and mark it with a `base` branch
+    fn setup_rename_fixture(temp_dir: &TempDir, repo: &Repository) -> String {
+        let content: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        std::fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        std::fs::write(temp_dir.path().join("src/a.txt"), &content).unwrap();
+
+        let base_oid = commit_all(repo, "Initial commit");
+        repo.branch("base", &repo.find_commit(base_oid).unwrap(), false)
+            .unwrap();
+
+        std::fs::create_dir_all(temp_dir.path().join("lib")).unwrap();
+        content
+    }
+
     #[test]
     fn test_git_service_creation() {
         let (temp_dir, _repo) = create_test_repo();
@@ -1039,4 +1790,369 @@ mod tests {
         let branch_name = git_service.get_default_branch_name().unwrap();
         assert_eq!(branch_name, "main");
     }
+
+    #[test]
+    fn test_diff_pure_move_is_reported_as_rename() {
+        let (temp_dir, repo) = create_test_repo();
+        setup_rename_fixture(&temp_dir, &repo);
+
+        std::fs::rename(
+            temp_dir.path().join("src/a.txt"),
+            temp_dir.path().join("lib/b.txt"),
+        )
+        .unwrap();
+        commit_all(&repo, "Move a.txt");
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let diff = git_service
+            .get_enhanced_diff(temp_dir.path(), None, "base")
+            .unwrap();
+
+        assert_eq!(diff.files.len(), 1);
+        let file = &diff.files[0];
+        assert_eq!(file.path, "lib/b.txt");
+        assert_eq!(file.old_path.as_deref(), Some("src/a.txt"));
+        assert_eq!(file.new_path.as_deref(), Some("lib/b.txt"));
+        assert_eq!(file.chunks.len(), 1);
+        assert!(matches!(file.chunks[0].chunk_type, DiffChunkType::Renamed));
+    }
+
+    #[test]
+    fn test_diff_rename_with_edit_only_contains_changed_hunks() {
+        let (temp_dir, repo) = create_test_repo();
+        let content = setup_rename_fixture(&temp_dir, &repo);
+
+        std::fs::remove_file(temp_dir.path().join("src/a.txt")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("lib/b.txt"),
+            content.replace("line 10\n", "line ten\n"),
+        )
+        .unwrap();
+        commit_all(&repo, "Move and edit a.txt");
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let diff = git_service
+            .get_enhanced_diff(temp_dir.path(), None, "base")
+            .unwrap();
+
+        assert_eq!(diff.files.len(), 1);
+        let file = &diff.files[0];
+        assert_eq!(file.path, "lib/b.txt");
+        assert_eq!(file.old_path.as_deref(), Some("src/a.txt"));
+
+        let count = |kind: fn(&DiffChunkType) -> bool| {
+            file.chunks.iter().filter(|c| kind(&c.chunk_type)).count()
+        };
+        assert_eq!(count(|t| matches!(t, DiffChunkType::Insert)), 1);
+        assert_eq!(count(|t| matches!(t, DiffChunkType::Delete)), 1);
+        assert!(file.chunks.iter().any(|c| c.content == "line ten\n"));
+    }
+
+    #[test]
+    fn test_diff_uncommitted_rename_with_edit_is_reported_as_rename() {
+        let (temp_dir, repo) = create_test_repo();
+        let content = setup_rename_fixture(&temp_dir, &repo);
+
+        std::fs::remove_file(temp_dir.path().join("src/a.txt")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("lib/b.txt"),
+            content.replace("line 10\n", "line ten\n"),
+        )
+        .unwrap();
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let diff = git_service
+            .get_enhanced_diff(temp_dir.path(), None, "base")
+            .unwrap();
+
+        assert_eq!(diff.files.len(), 1);
+        let file = &diff.files[0];
+        assert_eq!(file.path, "lib/b.txt");
+        assert_eq!(file.old_path.as_deref(), Some("src/a.txt"));
+        assert!(file.chunks.iter().any(|c| c.content == "line ten\n"));
+        assert!(diff
+            .summary(10_000)
+            .starts_with("--- src/a.txt -> lib/b.txt\n"));
+    }
+
+    #[test]
+    fn test_bare_repository_supports_worktrees() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init_bare(temp_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        repo.commit(
+            Some("refs/heads/main"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        assert!(repo.is_bare());
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        assert_eq!(git_service.get_default_branch_name().unwrap(), "main");
+
+        let worktrees_dir = TempDir::new().unwrap();
+        let worktree_path = worktrees_dir.path().join("feature");
+        git_service
+            .create_worktree("feature", &worktree_path, None)
+            .unwrap();
+
+        let worktree = Repository::open(&worktree_path).unwrap();
+        assert_eq!(worktree.head().unwrap().shorthand(), Some("feature"));
+    }
+
+    #[test]
+    fn test_detached_head_requires_explicit_base_branch() {
+        let (temp_dir, repo) = create_test_repo();
+        std::fs::write(temp_dir.path().join("README.md"), "hello\n").unwrap();
+        let oid = commit_all(&repo, "Initial commit");
+        let branch_name = repo.head().unwrap().shorthand().unwrap().to_string();
+        repo.set_head_detached(oid).unwrap();
+
+        assert!(matches!(
+            HeadState::of(&repo).unwrap(),
+            HeadState::Detached { .. }
+        ));
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        assert!(matches!(
+            git_service.get_default_branch_name(),
+            Err(GitServiceError::DetachedHead(_))
+        ));
+
+        let worktrees_dir = TempDir::new().unwrap();
+        assert!(matches!(
+            git_service.create_worktree("implicit", &worktrees_dir.path().join("implicit"), None),
+            Err(GitServiceError::DetachedHead(_))
+        ));
+        git_service
+            .create_worktree(
+                "explicit",
+                &worktrees_dir.path().join("explicit"),
+                Some(&branch_name),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_merge_into_bare_repository_moves_base_branch() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init_bare(temp_dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        let signature = repo.signature().unwrap();
+        let empty_tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let initial = repo
+            .commit(
+                Some("refs/heads/main"),
+                &signature,
+                &signature,
+                "Initial commit",
+                &empty_tree,
+                &[],
+            )
+            .unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let worktrees_dir = TempDir::new().unwrap();
+        let worktree_path = worktrees_dir.path().join("feature");
+        git_service
+            .create_worktree("feature", &worktree_path, None)
+            .unwrap();
+        let worktree = Repository::open(&worktree_path).unwrap();
+        std::fs::write(worktree_path.join("feature.txt"), "feature\n").unwrap();
+        let feature = commit_all(&worktree, "Add feature");
+
+        // Move main on so the merge needs a merge commit
+        let main_tip = repo
+            .commit(
+                Some("refs/heads/main"),
+                &signature,
+                &signature,
+                "Meanwhile on main",
+                &empty_tree,
+                &[&repo.find_commit(initial).unwrap()],
+            )
+            .unwrap();
+
+        let merged = git_service
+            .merge_changes(&worktree_path, "feature", "main", "Merge feature")
+            .unwrap();
+
+        let main = repo.find_reference("refs/heads/main").unwrap();
+        assert_eq!(main.target().unwrap().to_string(), merged);
+        let merge_commit = main.peel_to_commit().unwrap();
+        assert_eq!(
+            merge_commit.parent_ids().collect::<Vec<_>>(),
+            vec![main_tip, feature]
+        );
+        assert!(repo.is_bare());
+    }
+
+    #[test]
+    fn test_merge_with_detached_head_fast_forwards_base_branch() {
+        let (temp_dir, repo) = create_test_repo();
+        std::fs::write(temp_dir.path().join("README.md"), "hello\n").unwrap();
+        let oid = commit_all(&repo, "Initial commit");
+        let branch_name = repo.head().unwrap().shorthand().unwrap().to_string();
+        repo.set_head_detached(oid).unwrap();
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let worktrees_dir = TempDir::new().unwrap();
+        let worktree_path = worktrees_dir.path().join("feature");
+        git_service
+            .create_worktree("feature", &worktree_path, Some(&branch_name))
+            .unwrap();
+        let worktree = Repository::open(&worktree_path).unwrap();
+        std::fs::write(worktree_path.join("feature.txt"), "feature\n").unwrap();
+        let feature = commit_all(&worktree, "Add feature");
+
+        let merged = git_service
+            .merge_changes(&worktree_path, "feature", &branch_name, "Merge feature")
+            .unwrap();
+
+        assert_eq!(merged, feature.to_string());
+        let base = repo.find_branch(&branch_name, BranchType::Local).unwrap();
+        assert_eq!(base.get().target(), Some(feature));
+        assert!(repo.find_reference("refs/heads/HEAD").is_err());
+        // HEAD stays detached where it was, with its checkout untouched
+        assert!(repo.head_detached().unwrap());
+        assert_eq!(repo.head().unwrap().target(), Some(oid));
+        assert!(!temp_dir.path().join("feature.txt").exists());
+    }
+
+    #[test]
+    fn test_unborn_head_creates_initial_commit_for_worktree() {
+        let (temp_dir, repo) = create_test_repo();
+        assert_eq!(HeadState::of(&repo).unwrap(), HeadState::Unborn);
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let worktrees_dir = TempDir::new().unwrap();
+        let worktree_path = worktrees_dir.path().join("first");
+        git_service
+            .create_worktree("first", &worktree_path, None)
+            .unwrap();
+
+        assert!(worktree_path.exists());
+        assert_eq!(
+            HeadState::of(&repo).unwrap(),
+            HeadState::Branch {
+                name: "main".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_existing_branch_is_reported_as_branch_exists() {
+        let (temp_dir, repo) = create_test_repo();
+        std::fs::write(temp_dir.path().join("README.md"), "hello\n").unwrap();
+        commit_all(&repo, "Initial commit");
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let worktrees_dir = TempDir::new().unwrap();
+        git_service
+            .create_worktree("feature", &worktrees_dir.path().join("feature"), None)
+            .unwrap();
+
+        let result =
+            git_service.create_worktree("feature", &worktrees_dir.path().join("again"), None);
+        assert!(matches!(
+            result,
+            Err(GitServiceError::Worktree(WorktreeFailure::BranchExists(ref branch))) if branch == "feature"
+        ));
+    }
+
+    #[test]
+    fn test_branch_already_checked_out_is_categorized() {
+        let (temp_dir, repo) = create_test_repo();
+        std::fs::write(temp_dir.path().join("README.md"), "hello\n").unwrap();
+        commit_all(&repo, "Initial commit");
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let worktrees_dir = TempDir::new().unwrap();
+        git_service
+            .create_worktree("feature", &worktrees_dir.path().join("feature"), None)
+            .unwrap();
+
+        let result = GitService::add_worktree(
+            &repo,
+            "second",
+            "feature",
+            &worktrees_dir.path().join("second"),
+        );
+        let err = result.unwrap_err();
+        assert!(matches!(
+            err,
+            GitServiceError::Worktree(WorktreeFailure::BranchCheckedOut(ref branch)) if branch == "feature"
+        ));
+        assert!(err.to_string().contains("git worktree remove"));
+    }
+
+    #[test]
+    fn test_locked_worktree_is_categorized() {
+        let (temp_dir, repo) = create_test_repo();
+        std::fs::write(temp_dir.path().join("README.md"), "hello\n").unwrap();
+        let oid = commit_all(&repo, "Initial commit");
+
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+        let worktrees_dir = TempDir::new().unwrap();
+        git_service
+            .create_worktree("feature", &worktrees_dir.path().join("feature"), None)
+            .unwrap();
+        repo.find_worktree("feature")
+            .unwrap()
+            .lock(Some("in use"))
+            .unwrap();
+
+        // Re-using the locked worktree's name for another branch must not clobber it
+        repo.branch("other", &repo.find_commit(oid).unwrap(), false)
+            .unwrap();
+        let result = GitService::add_worktree(
+            &repo,
+            "feature",
+            "other",
+            &worktrees_dir.path().join("other"),
+        );
+        let err = result.unwrap_err();
+        assert!(matches!(
+            err,
+            GitServiceError::Worktree(WorktreeFailure::Locked(ref name)) if name == "feature"
+        ));
+        assert!(err.to_string().contains("git worktree unlock feature"));
+    }
+
+    #[test]
+    fn test_dirty_source_repo_warning_only_for_uncommitted_changes() {
+        let (temp_dir, repo) = create_test_repo();
+        std::fs::write(temp_dir.path().join("README.md"), "hello\n").unwrap();
+        commit_all(&repo, "Initial commit");
+        let git_service = GitService::new(temp_dir.path()).unwrap();
+
+        let changes = git_service.source_repo_changes().unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(dirty_source_repo_warning(&changes), None);
+
+        std::fs::write(temp_dir.path().join("README.md"), "hello, world\n").unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "todo\n").unwrap();
+        let changes = git_service.source_repo_changes().unwrap();
+        let warning = dirty_source_repo_warning(&changes).unwrap();
+        assert!(warning.contains("README.md"));
+        assert!(warning.contains("notes.txt"));
+
+        let many: Vec<String> = (0..5).map(|i| format!("file{}.txt", i)).collect();
+        assert!(dirty_source_repo_warning(&many)
+            .unwrap()
+            .contains("file2.txt and 2 more"));
+    }
 }
@@ -2,8 +2,9 @@ use std::time::Duration;
 
 use octocrab::{Octocrab, OctocrabBuilder};
 use serde::{Deserialize, Serialize};
-use tokio::time::sleep;
-use tracing::{info, warn};
+use tracing::info;
+
+use crate::utils::retry::{retry_with_config, RetryConfig};
 
 #[derive(Debug)]
 pub enum GitHubServiceError {
@@ -30,6 +31,16 @@ impl std::fmt::Display for GitHubServiceError {
 
 impl std::error::Error for GitHubServiceError {}
 
+impl GitHubServiceError {
+    /// Authentication failures won't be fixed by trying again
+    fn is_retryable(&self) -> bool {
+        !matches!(
+            self,
+            GitHubServiceError::TokenInvalid | GitHubServiceError::Auth(_)
+        )
+    }
+}
+
 impl From<octocrab::Error> for GitHubServiceError {
     fn from(err: octocrab::Error) -> Self {
         match &err {
@@ -71,31 +82,18 @@ pub struct PullRequestInfo {
     pub url: String,
     pub status: String,
     pub merged: bool,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
     pub merged_at: Option<chrono::DateTime<chrono::Utc>>,
     pub merge_commit_sha: Option<String>,
+    /// Description of the pull request
+    #[serde(default)]
+    pub body: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GitHubService {
     client: Octocrab,
-    retry_config: RetryConfig,
-}
-
-#[derive(Debug, Clone)]
-pub struct RetryConfig {
-    pub max_retries: u32,
-    pub base_delay: Duration,
-    pub max_delay: Duration,
-}
-
-impl Default for RetryConfig {
-    fn default() -> Self {
-        Self {
-            max_retries: 3,
-            base_delay: Duration::from_secs(1),
-            max_delay: Duration::from_secs(30),
-        }
-    }
+    retry_config: RetryConfig<GitHubServiceError>,
 }
 
 impl GitHubService {
@@ -110,7 +108,8 @@ impl GitHubService {
 
         Ok(Self {
             client,
-            retry_config: RetryConfig::default(),
+            retry_config: RetryConfig::new(4, Duration::from_secs(1), Duration::from_secs(30))
+                .retry_on(GitHubServiceError::is_retryable),
         })
     }
 
@@ -209,6 +208,7 @@ impl GitHubService {
             merged: false,
             merged_at: None,
             merge_commit_sha: None,
+            body: pr.body.clone(),
         };
 
         info!(
@@ -263,6 +263,7 @@ impl GitHubService {
             merged: pr.merged_at.is_some(),
             merged_at: pr.merged_at.map(|dt| dt.naive_utc().and_utc()),
             merge_commit_sha: pr.merge_commit_sha.clone(),
+            body: pr.body.clone(),
         };
 
         Ok(pr_info)
@@ -274,34 +275,6 @@ impl GitHubService {
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = Result<T, GitHubServiceError>>,
     {
-        let mut last_error = None;
-
-        for attempt in 0..=self.retry_config.max_retries {
-            match operation().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    last_error = Some(e);
-
-                    if attempt < self.retry_config.max_retries {
-                        let delay = std::cmp::min(
-                            self.retry_config.base_delay * 2_u32.pow(attempt),
-                            self.retry_config.max_delay,
-                        );
-
-                        warn!(
-                            "GitHub API call failed (attempt {}/{}), retrying in {:?}: {}",
-                            attempt + 1,
-                            self.retry_config.max_retries + 1,
-                            delay,
-                            last_error.as_ref().unwrap()
-                        );
-
-                        sleep(delay).await;
-                    }
-                }
-            }
-        }
-
-        Err(last_error.unwrap())
+        retry_with_config(operation, &self.retry_config).await
     }
 }
@@ -0,0 +1,318 @@
+use std::collections::{BTreeSet, HashMap};
+
+use regex::Regex;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    models::glossary::{GlossaryEntry, GlossaryEntryStatus},
+    services::git_service::{DiffRevision, GitService},
+};
+
+/// Most characters of glossary put in front of a task prompt
+pub const PROMPT_BUDGET: usize = 2000;
+/// Times an identifier has to appear in one merge's added lines to be suggested
+const MIN_SUGGESTION_OCCURRENCES: u32 = 4;
+const MAX_SUGGESTIONS_PER_MERGE: usize = 5;
+const MAX_FILE_REFS: usize = 3;
+
+/// Compound identifiers that are common to most codebases, so never project jargon
+const COMMON_IDENTIFIERS: &[&str] = &[
+    "as_ref",
+    "as_str",
+    "class_name",
+    "classname",
+    "clone_from",
+    "console_log",
+    "created_at",
+    "deleted_at",
+    "for_each",
+    "foreach",
+    "from_str",
+    "getelementbyid",
+    "into_iter",
+    "is_empty",
+    "is_none",
+    "is_some",
+    "iter_mut",
+    "map_err",
+    "ok_or",
+    "ok_or_else",
+    "onchange",
+    "onclick",
+    "println",
+    "queryselector",
+    "set_state",
+    "setstate",
+    "setstatus",
+    "tostring",
+    "to_owned",
+    "to_string",
+    "unwrap_or",
+    "unwrap_or_default",
+    "unwrap_or_else",
+    "updated_at",
+    "usecallback",
+    "useeffect",
+    "usememo",
+    "useref",
+    "usestate",
+];
+
+/// The approved glossary of a project as a prompt section, or `None` when it is empty
+pub async fn prompt_section(pool: &SqlitePool, project_id: Uuid) -> Option<String> {
+    match GlossaryEntry::find_by_project_id(pool, project_id, Some(GlossaryEntryStatus::Approved))
+        .await
+    {
+        Ok(entries) => format_section(&entries, PROMPT_BUDGET),
+        Err(e) => {
+            tracing::warn!("Failed to load glossary of project {}: {}", project_id, e);
+            None
+        }
+    }
+}
+
+/// One line per entry in the given order, stopping before `budget` characters are exceeded
+fn format_section(entries: &[GlossaryEntry], budget: usize) -> Option<String> {
+    let header = "Project glossary (what these terms mean in this codebase):";
+    let mut section = header.to_string();
+    let mut included = 0;
+    for entry in entries {
+        let definition = entry
+            .definition
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut line = format!("\n- {}: {}", entry.term, definition);
+        if !entry.file_refs.is_empty() {
+            line.push_str(&format!(" (see {})", entry.file_refs.join(", ")));
+        }
+        if section.len() + line.len() > budget {
+            break;
+        }
+        section.push_str(&line);
+        included += 1;
+    }
+
+    if included == 0 {
+        return None;
+    }
+    if included < entries.len() {
+        section.push_str(&format!(
+            "\n({} less used terms left out)",
+            entries.len() - included
+        ));
+    }
+    Some(section)
+}
+
+/// Put the glossary section in front of a prompt
+pub fn with_glossary(prompt: &str, section: Option<&str>) -> String {
+    match section {
+        Some(section) => format!("{}\n\n{}", section, prompt),
+        None => prompt.to_string(),
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct IdentifierUse {
+    occurrences: u32,
+    files: BTreeSet<String>,
+}
+
+/// Identifiers specific enough to be jargon: compound snake_case or camelCase names that
+/// aren't everyday library vocabulary
+fn is_unusual_identifier(identifier: &str) -> bool {
+    let trimmed = identifier.trim_matches('_');
+    if trimmed.len() < 6 || trimmed.chars().all(|c| c.is_ascii_digit() || c == '_') {
+        return false;
+    }
+    let snake_case = trimmed.split('_').filter(|part| part.len() >= 2).count() >= 2;
+    let camel_case = trimmed
+        .as_bytes()
+        .windows(2)
+        .any(|pair| pair[0].is_ascii_lowercase() && pair[1].is_ascii_uppercase());
+
+    (snake_case || camel_case)
+        && !COMMON_IDENTIFIERS.contains(&trimmed.to_ascii_lowercase().as_str())
+}
+
+fn count_identifiers(added_lines: &[(String, String)]) -> HashMap<String, IdentifierUse> {
+    let identifier = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap();
+    let mut uses: HashMap<String, IdentifierUse> = HashMap::new();
+    for (path, line) in added_lines {
+        for found in identifier.find_iter(line) {
+            if is_unusual_identifier(found.as_str()) {
+                let entry = uses.entry(found.as_str().to_string()).or_default();
+                entry.occurrences += 1;
+                entry.files.insert(path.clone());
+            }
+        }
+    }
+    uses
+}
+
+/// Identifiers worth suggesting, most used first
+fn select_suggestions(uses: HashMap<String, IdentifierUse>) -> Vec<(String, IdentifierUse)> {
+    let mut candidates: Vec<_> = uses
+        .into_iter()
+        .filter(|(_, usage)| usage.occurrences >= MIN_SUGGESTION_OCCURRENCES)
+        .collect();
+    candidates.sort_by(|(a, a_use), (b, b_use)| {
+        b_use
+            .occurrences
+            .cmp(&a_use.occurrences)
+            .then_with(|| a.cmp(b))
+    });
+    candidates.truncate(MAX_SUGGESTIONS_PER_MERGE);
+    candidates
+}
+
+/// Case-insensitive occurrences of a term in the added lines
+fn count_term(added_lines: &[(String, String)], term: &str) -> u32 {
+    let term = term.to_lowercase();
+    if term.is_empty() {
+        return 0;
+    }
+    added_lines
+        .iter()
+        .map(|(_, line)| line.to_lowercase().matches(&term).count() as u32)
+        .sum()
+}
+
+/// Update the glossary from an attempt's merged changes: existing entries get their
+/// occurrences counted and frequent unfamiliar identifiers become suggestions. Runs in the
+/// background; failures are only logged.
+pub fn learn_from_merge(
+    pool: SqlitePool,
+    project_id: Uuid,
+    git_repo_path: String,
+    revision: DiffRevision,
+) {
+    tokio::spawn(async move {
+        let added_lines = tokio::task::spawn_blocking(move || {
+            GitService::new(&git_repo_path)
+                .and_then(|git| git.added_lines(revision))
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+        let added_lines = match added_lines {
+            Ok(lines) => lines,
+            Err(e) => {
+                tracing::warn!("Failed to read merged changes for the glossary: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = record_merged_lines(&pool, project_id, &added_lines).await {
+            tracing::warn!(
+                "Failed to update the glossary of project {}: {}",
+                project_id,
+                e
+            );
+        }
+    });
+}
+
+async fn record_merged_lines(
+    pool: &SqlitePool,
+    project_id: Uuid,
+    added_lines: &[(String, String)],
+) -> Result<(), sqlx::Error> {
+    let entries = GlossaryEntry::find_by_project_id(pool, project_id, None).await?;
+    for entry in &entries {
+        let occurrences = count_term(added_lines, &entry.term);
+        if occurrences > 0 {
+            GlossaryEntry::add_occurrences(pool, entry.id, occurrences).await?;
+        }
+    }
+
+    let known: BTreeSet<_> = entries
+        .iter()
+        .map(|entry| entry.term.to_lowercase())
+        .collect();
+    let uses = count_identifiers(added_lines)
+        .into_iter()
+        .filter(|(identifier, _)| !known.contains(&identifier.to_lowercase()))
+        .collect();
+    for (identifier, usage) in select_suggestions(uses) {
+        let file_refs: Vec<_> = usage.files.into_iter().take(MAX_FILE_REFS).collect();
+        GlossaryEntry::suggest(pool, project_id, &identifier, &file_refs, usage.occurrences)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn entry(term: &str, definition: &str, file_refs: &[&str]) -> GlossaryEntry {
+        GlossaryEntry {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            term: term.to_string(),
+            definition: definition.to_string(),
+            file_refs: file_refs.iter().map(|path| path.to_string()).collect(),
+            status: GlossaryEntryStatus::Approved,
+            occurrences: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_format_section_keeps_leading_entries_within_budget() {
+        let entries = vec![
+            entry(
+                "the reconciler",
+                "Loop that marks\nstale executions as failed",
+                &["backend/src/execution_monitor.rs"],
+            ),
+            entry(
+                "shadow writes",
+                "Normalizations stored next to the shown one",
+                &[],
+            ),
+        ];
+
+        let full = format_section(&entries, PROMPT_BUDGET).unwrap();
+        assert!(full.contains(
+            "- the reconciler: Loop that marks stale executions as failed (see backend/src/execution_monitor.rs)"
+        ));
+        assert!(full.contains("- shadow writes: Normalizations stored next to the shown one"));
+
+        let short = format_section(&entries, 160).unwrap();
+        assert!(short.contains("the reconciler"));
+        assert!(!short.contains("shadow writes"));
+        assert!(short.ends_with("(1 less used terms left out)"));
+        assert_eq!(format_section(&entries, 20), None);
+    }
+
+    #[test]
+    fn test_suggestions_pick_frequent_unusual_identifiers() {
+        let lines: Vec<_> = (0..4)
+            .map(|i| {
+                (
+                    format!("src/file{}.rs", i % 2),
+                    format!(
+                        "let shadow_write = reconcileRuns(x).to_string(); // pass {}",
+                        i
+                    ),
+                )
+            })
+            .chain([("src/once.rs".to_string(), "rareHelper()".to_string())])
+            .collect();
+
+        let suggestions = select_suggestions(count_identifiers(&lines));
+
+        let terms: Vec<_> = suggestions.iter().map(|(term, _)| term.as_str()).collect();
+        assert_eq!(terms, vec!["reconcileRuns", "shadow_write"]);
+        assert_eq!(suggestions[0].1.occurrences, 4);
+        assert_eq!(suggestions[0].1.files.len(), 2);
+        assert_eq!(count_term(&lines, "Shadow_Write"), 4);
+    }
+}
@@ -0,0 +1,468 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::sync::{Notify, RwLock};
+use tracing::{debug, error, info, warn};
+use ts_rs::TS;
+
+use crate::models::scheduled_job::ScheduledJob;
+
+/// How long to back off when the job's state can't be read
+const STATE_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+type JobHandler = Arc<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+#[derive(Debug)]
+pub enum SchedulerError {
+    UnknownJob(String),
+    AlreadyRunning(String),
+    AlreadyRegistered(String),
+    InvalidCron(String),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for SchedulerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchedulerError::UnknownJob(name) => write!(f, "Unknown job: {}", name),
+            SchedulerError::AlreadyRunning(name) => write!(f, "Job {} is already running", name),
+            SchedulerError::AlreadyRegistered(name) => {
+                write!(f, "Job {} is already registered", name)
+            }
+            SchedulerError::InvalidCron(e) => write!(f, "Invalid cron expression: {}", e),
+            SchedulerError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SchedulerError {}
+
+impl From<sqlx::Error> for SchedulerError {
+    fn from(err: sqlx::Error) -> Self {
+        SchedulerError::Database(err)
+    }
+}
+
+/// When a job runs
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    /// Every `Duration`, measured from the start of the previous run
+    Interval(Duration),
+    /// A cron expression with seconds (`sec min hour day month weekday`), evaluated in UTC
+    Cron {
+        expression: String,
+        schedule: Box<cron::Schedule>,
+    },
+}
+
+impl Schedule {
+    pub fn cron(expression: &str) -> Result<Self, SchedulerError> {
+        let schedule = cron::Schedule::from_str(expression)
+            .map_err(|e| SchedulerError::InvalidCron(e.to_string()))?;
+        Ok(Schedule::Cron {
+            expression: expression.to_string(),
+            schedule: Box::new(schedule),
+        })
+    }
+
+    /// First run time strictly after `after`; `None` when a cron schedule has no more runs
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Interval(interval) => chrono::Duration::from_std(*interval)
+                .ok()
+                .map(|interval| after + interval),
+            Schedule::Cron { schedule, .. } => schedule.after(&after).next(),
+        }
+    }
+
+    /// Number of runs that were due between `due` and `now`
+    fn missed_runs(&self, due: DateTime<Utc>, now: DateTime<Utc>) -> usize {
+        let mut missed = 0;
+        let mut next = Some(due);
+        while let Some(at) = next.filter(|at| *at <= now) {
+            missed += 1;
+            // Counting is only for the log line; don't walk years of one-second runs
+            if missed >= 1000 {
+                break;
+            }
+            next = self.next_after(at);
+        }
+        missed
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Schedule::Interval(interval) => format!("every {}s", interval.as_secs()),
+            Schedule::Cron { expression, .. } => expression.clone(),
+        }
+    }
+}
+
+/// A job's schedule and persisted state, as reported by `GET /api/jobs`
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct JobStatus {
+    pub name: String,
+    pub schedule: String,
+    pub enabled: bool,
+    pub running: bool,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub last_started_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "crate::utils::rfc3339::option")]
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<i64>,
+    pub last_succeeded: Option<bool>,
+    pub last_error: Option<String>,
+    pub run_count: i64,
+}
+
+struct RegisteredJob {
+    name: String,
+    schedule: Schedule,
+    handler: JobHandler,
+    running: AtomicBool,
+    /// Wakes the job's loop to re-read its state after it is enabled or disabled
+    wake: Notify,
+}
+
+/// Marks a job as running until dropped
+struct RunGuard(Arc<RegisteredJob>);
+
+impl RunGuard {
+    fn acquire(job: &Arc<RegisteredJob>) -> Option<Self> {
+        (!job.running.swap(true, Ordering::SeqCst)).then(|| Self(job.clone()))
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.0.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Runs registered background jobs on their schedules. Next-run times are persisted, so a run
+/// missed while the server was down happens once at startup, and a job never overlaps itself.
+#[derive(Clone)]
+pub struct JobScheduler {
+    pool: SqlitePool,
+    jobs: Arc<RwLock<BTreeMap<String, Arc<RegisteredJob>>>>,
+}
+
+impl std::fmt::Debug for JobScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobScheduler").finish_non_exhaustive()
+    }
+}
+
+impl JobScheduler {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            pool,
+            jobs: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    /// Register a job and start running it. A job seen for the first time runs right away.
+    pub async fn register<F, Fut>(
+        &self,
+        name: &str,
+        schedule: Schedule,
+        handler: F,
+    ) -> Result<(), SchedulerError>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let job = Arc::new(RegisteredJob {
+            name: name.to_string(),
+            schedule,
+            handler: Arc::new(move || -> BoxFuture<'static, Result<(), String>> {
+                Box::pin(handler())
+            }),
+            running: AtomicBool::new(false),
+            wake: Notify::new(),
+        });
+
+        {
+            let mut jobs = self.jobs.write().await;
+            if jobs.contains_key(name) {
+                return Err(SchedulerError::AlreadyRegistered(name.to_string()));
+            }
+            ScheduledJob::ensure(&self.pool, name, Utc::now()).await?;
+            jobs.insert(name.to_string(), job.clone());
+        }
+
+        info!("Registered job {} ({})", job.name, job.schedule.describe());
+        let scheduler = self.clone();
+        tokio::spawn(async move { scheduler.run_loop(job).await });
+        Ok(())
+    }
+
+    pub async fn statuses(&self) -> Result<Vec<JobStatus>, sqlx::Error> {
+        let jobs: Vec<_> = self.jobs.read().await.values().cloned().collect();
+        let mut statuses = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            statuses.push(self.status_of(&job).await?);
+        }
+        Ok(statuses)
+    }
+
+    pub async fn status(&self, name: &str) -> Result<JobStatus, SchedulerError> {
+        let job = self.job(name).await?;
+        Ok(self.status_of(&job).await?)
+    }
+
+    pub async fn set_enabled(&self, name: &str, enabled: bool) -> Result<(), SchedulerError> {
+        let job = self.job(name).await?;
+        ScheduledJob::set_enabled(&self.pool, name, enabled).await?;
+        job.wake.notify_one();
+        Ok(())
+    }
+
+    /// Start a run now, outside the schedule. The next scheduled run is unaffected.
+    pub async fn trigger(&self, name: &str) -> Result<(), SchedulerError> {
+        let job = self.job(name).await?;
+        let guard = RunGuard::acquire(&job)
+            .ok_or_else(|| SchedulerError::AlreadyRunning(name.to_string()))?;
+
+        let scheduler = self.clone();
+        tokio::spawn(async move { scheduler.execute(guard).await });
+        Ok(())
+    }
+
+    async fn job(&self, name: &str) -> Result<Arc<RegisteredJob>, SchedulerError> {
+        self.jobs
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| SchedulerError::UnknownJob(name.to_string()))
+    }
+
+    async fn status_of(&self, job: &RegisteredJob) -> Result<JobStatus, sqlx::Error> {
+        let state = ScheduledJob::find(&self.pool, &job.name).await?;
+        Ok(JobStatus {
+            name: job.name.clone(),
+            schedule: job.schedule.describe(),
+            enabled: state.as_ref().is_none_or(|s| s.enabled),
+            running: job.running.load(Ordering::SeqCst),
+            next_run_at: state.as_ref().and_then(|s| s.next_run_at),
+            last_started_at: state.as_ref().and_then(|s| s.last_started_at),
+            last_finished_at: state.as_ref().and_then(|s| s.last_finished_at),
+            last_duration_ms: state.as_ref().and_then(|s| s.last_duration_ms),
+            last_succeeded: state.as_ref().and_then(|s| s.last_succeeded),
+            last_error: state.as_ref().and_then(|s| s.last_error.clone()),
+            run_count: state.map_or(0, |s| s.run_count),
+        })
+    }
+
+    async fn run_loop(&self, job: Arc<RegisteredJob>) {
+        loop {
+            let state = match ScheduledJob::find(&self.pool, &job.name).await {
+                Ok(Some(state)) => state,
+                Ok(None) => {
+                    error!("State of job {} is missing, stopping it", job.name);
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to load state of job {}: {}", job.name, e);
+                    tokio::time::sleep(STATE_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            let due = match state.next_run_at {
+                Some(due) if state.enabled => due,
+                // Disabled, or a cron schedule with no runs left: wait to be re-enabled
+                _ => {
+                    job.wake.notified().await;
+                    continue;
+                }
+            };
+
+            let wait = (due - Utc::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = job.wake.notified() => continue,
+            }
+
+            let started_at = Utc::now();
+            let missed = job.schedule.missed_runs(due, started_at);
+            if missed > 1 {
+                info!(
+                    "Job {} missed {} runs while the server was down, running it once to catch up",
+                    job.name, missed
+                );
+            }
+
+            match RunGuard::acquire(&job) {
+                Some(guard) => self.execute(guard).await,
+                None => debug!(
+                    "Job {} is still running from a manual trigger, skipping this run",
+                    job.name
+                ),
+            }
+
+            // A next run that fell due while this one was in progress starts right away, so
+            // those missed runs are coalesced into one
+            let next_run_at = job.schedule.next_after(started_at);
+            if let Err(e) = ScheduledJob::set_next_run(&self.pool, &job.name, next_run_at).await {
+                error!("Failed to save next run of job {}: {}", job.name, e);
+                tokio::time::sleep(STATE_RETRY_DELAY).await;
+            }
+        }
+    }
+
+    async fn execute(&self, guard: RunGuard) {
+        let job = guard.0.clone();
+        let started_at = Utc::now();
+
+        // Run on its own task so a panicking handler is reported instead of ending the loop
+        let result = match tokio::spawn((job.handler)()).await {
+            Ok(result) => result,
+            Err(e) => Err(format!("Job panicked: {}", e)),
+        };
+        let finished_at = Utc::now();
+        drop(guard);
+
+        if let Err(e) = &result {
+            warn!("Job {} failed: {}", job.name, e);
+        }
+        if let Err(e) = ScheduledJob::record_run(
+            &self.pool,
+            &job.name,
+            started_at,
+            finished_at,
+            result.err().as_deref(),
+        )
+        .await
+        {
+            error!("Failed to record run of job {}: {}", job.name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::test_utils::memory_pool;
+
+    async fn wait_for_status(
+        scheduler: &JobScheduler,
+        name: &str,
+        reached: impl Fn(&JobStatus) -> bool,
+    ) -> JobStatus {
+        for _ in 0..200 {
+            let status = scheduler.status(name).await.unwrap();
+            if reached(&status) {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} didn't reach the expected state in time", name);
+    }
+
+    #[tokio::test]
+    async fn test_missed_runs_are_caught_up_once() {
+        let pool = memory_pool().await;
+        let hour = Duration::from_secs(3600);
+
+        // Last persisted state says runs were due three hours and one hour from now
+        ScheduledJob::ensure(&pool, "overdue", Utc::now() - chrono::Duration::hours(3))
+            .await
+            .unwrap();
+        ScheduledJob::ensure(&pool, "upcoming", Utc::now() + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+
+        let scheduler = JobScheduler::new(pool);
+        let overdue_runs = Arc::new(AtomicUsize::new(0));
+        let upcoming_runs = Arc::new(AtomicUsize::new(0));
+        for (name, runs) in [("overdue", &overdue_runs), ("upcoming", &upcoming_runs)] {
+            let runs = runs.clone();
+            scheduler
+                .register(name, Schedule::Interval(hour), move || {
+                    let runs = runs.clone();
+                    async move {
+                        runs.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    }
+                })
+                .await
+                .unwrap();
+        }
+
+        let status = wait_for_status(&scheduler, "overdue", |s| {
+            s.run_count == 1 && s.next_run_at.is_some_and(|at| at > Utc::now())
+        })
+        .await;
+
+        assert_eq!(status.last_succeeded, Some(true));
+        // The three missed runs were coalesced into one, and the other job isn't due yet
+        assert_eq!(overdue_runs.load(Ordering::SeqCst), 1);
+        assert_eq!(upcoming_runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_job_never_overlaps_itself() {
+        let pool = memory_pool().await;
+        ScheduledJob::ensure(&pool, "slow", Utc::now() + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+
+        let scheduler = JobScheduler::new(pool);
+        let release = Arc::new(Notify::new());
+        let runs = Arc::new(AtomicUsize::new(0));
+        {
+            let release = release.clone();
+            let runs = runs.clone();
+            scheduler
+                .register(
+                    "slow",
+                    Schedule::Interval(Duration::from_secs(3600)),
+                    move || {
+                        let release = release.clone();
+                        let runs = runs.clone();
+                        async move {
+                            runs.fetch_add(1, Ordering::SeqCst);
+                            release.notified().await;
+                            Err("gave up".to_string())
+                        }
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        scheduler.trigger("slow").await.unwrap();
+        wait_for_status(&scheduler, "slow", |s| s.running).await;
+        assert!(matches!(
+            scheduler.trigger("slow").await,
+            Err(SchedulerError::AlreadyRunning(_))
+        ));
+
+        release.notify_one();
+        let status = wait_for_status(&scheduler, "slow", |s| s.run_count == 1).await;
+        assert!(!status.running);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(status.last_succeeded, Some(false));
+        assert_eq!(status.last_error.as_deref(), Some("gave up"));
+        assert!(scheduler.trigger("slow").await.is_ok());
+    }
+}
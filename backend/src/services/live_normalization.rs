@@ -0,0 +1,220 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    executor::{IncrementalNormalizer, NormalizedEntry},
+    models::{execution_process::ExecutionProcess, executor_session::ExecutorSession},
+    services::log_normalization::{parser_working_dir, stdout_parser},
+};
+
+/// Messages kept for slow subscribers before they start missing some
+const LIVE_LOG_CHANNEL_CAPACITY: usize = 1024;
+/// Recent stderr lines sent to subscribers that join mid-run
+const STDERR_BACKFILL_LINES: usize = 200;
+
+lazy_static::lazy_static! {
+    /// Live logs of the execution processes whose stdout is being read
+    static ref LIVE_LOGS: Arc<Mutex<HashMap<Uuid, LiveLog>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+/// What subscribers to a running process's output are sent
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export)]
+pub enum LiveLogMessage {
+    /// A normalized entry of stdout, as soon as its line is complete
+    Entry { entry: NormalizedEntry },
+    /// A line written to stderr
+    Stderr { line: String },
+    /// The process ended; always the last message
+    Exited {
+        execution_process_id: Uuid,
+        exit_code: Option<i64>,
+        success: bool,
+    },
+}
+
+struct LiveLog {
+    normalizer: IncrementalNormalizer,
+    stderr_tail: VecDeque<String>,
+    messages: broadcast::Sender<LiveLogMessage>,
+}
+
+/// The output of a running process so far, and its output from then on
+pub struct LiveLogSubscription {
+    pub entries: Vec<NormalizedEntry>,
+    /// The last `STDERR_BACKFILL_LINES` lines of stderr
+    pub stderr: Vec<String>,
+    /// Closed once all of stdout has been read
+    pub messages: broadcast::Receiver<LiveLogMessage>,
+}
+
+/// Start normalizing the stdout of a process as it is read, with the parser of its executor.
+/// Dev servers and bisect runs are left out, since their output isn't a conversation.
+pub async fn start(pool: &SqlitePool, execution_process_id: Uuid) {
+    let process = match ExecutionProcess::find_by_id(pool, execution_process_id).await {
+        Ok(Some(process)) if process.process_type.is_attempt_stage() => process,
+        Ok(_) => return,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load execution process {} for live normalization: {}",
+                execution_process_id,
+                e
+            );
+            return;
+        }
+    };
+    let executor_session = ExecutorSession::find_by_execution_process_id(pool, process.id)
+        .await
+        .ok()
+        .flatten();
+    let executor = match stdout_parser(&process, executor_session.as_ref()) {
+        Ok(executor) => executor,
+        Err(e) => {
+            tracing::debug!(
+                "No live normalization for execution process {}: {}",
+                execution_process_id,
+                e
+            );
+            return;
+        }
+    };
+
+    let live_log = LiveLog {
+        normalizer: IncrementalNormalizer::new(executor, parser_working_dir(&process)),
+        stderr_tail: VecDeque::new(),
+        messages: broadcast::channel(LIVE_LOG_CHANNEL_CAPACITY).0,
+    };
+    LIVE_LOGS
+        .lock()
+        .unwrap()
+        .insert(execution_process_id, live_log);
+}
+
+/// Feed output read from the stdout of a process; a no-op unless `start` registered it
+pub fn push(execution_process_id: Uuid, chunk: &str) {
+    if let Some(live_log) = LIVE_LOGS.lock().unwrap().get_mut(&execution_process_id) {
+        let new_entries = live_log.normalizer.push(chunk);
+        if !new_entries.is_empty() {
+            tracing::trace!(
+                "Normalized {} new entries for execution process {}",
+                new_entries.len(),
+                execution_process_id
+            );
+        }
+        for entry in new_entries {
+            // Fails only when nobody is subscribed
+            let _ = live_log.messages.send(LiveLogMessage::Entry { entry });
+        }
+    }
+}
+
+/// Feed complete lines read from the stderr of a process; a no-op unless `start` registered it
+pub fn push_stderr(execution_process_id: Uuid, lines: &str) {
+    if let Some(live_log) = LIVE_LOGS.lock().unwrap().get_mut(&execution_process_id) {
+        for line in lines.lines() {
+            if live_log.stderr_tail.len() == STDERR_BACKFILL_LINES {
+                live_log.stderr_tail.pop_front();
+            }
+            live_log.stderr_tail.push_back(line.to_string());
+            let _ = live_log.messages.send(LiveLogMessage::Stderr {
+                line: line.to_string(),
+            });
+        }
+    }
+}
+
+/// Stop normalizing a process once all of its stdout has been stored
+pub fn finish(execution_process_id: Uuid) {
+    LIVE_LOGS.lock().unwrap().remove(&execution_process_id);
+}
+
+/// Entries normalized so far for a process whose stdout is still being read
+pub fn entries(execution_process_id: Uuid) -> Option<Vec<NormalizedEntry>> {
+    LIVE_LOGS
+        .lock()
+        .unwrap()
+        .get(&execution_process_id)
+        .map(|live_log| live_log.normalizer.entries().to_vec())
+}
+
+/// Follow the output of a process whose stdout is still being read. Taken under the same
+/// lock as `push`, so nothing falls between the backfill and the first message.
+pub fn subscribe(execution_process_id: Uuid) -> Option<LiveLogSubscription> {
+    LIVE_LOGS
+        .lock()
+        .unwrap()
+        .get(&execution_process_id)
+        .map(|live_log| LiveLogSubscription {
+            entries: live_log.normalizer.entries().to_vec(),
+            stderr: live_log.stderr_tail.iter().cloned().collect(),
+            messages: live_log.messages.subscribe(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{executor::OutputFormat, executors::custom::CustomExecutor};
+
+    #[tokio::test]
+    async fn test_subscribers_get_backfill_then_new_output_until_finish() {
+        let process_id = Uuid::new_v4();
+        LIVE_LOGS.lock().unwrap().insert(
+            process_id,
+            LiveLog {
+                normalizer: IncrementalNormalizer::new(
+                    Box::new(CustomExecutor {
+                        prompt: None,
+                        output_format: OutputFormat::Text,
+                    }),
+                    "/tmp",
+                ),
+                stderr_tail: VecDeque::new(),
+                messages: broadcast::channel(LIVE_LOG_CHANNEL_CAPACITY).0,
+            },
+        );
+        push(process_id, "before\n");
+        push_stderr(process_id, "warming up\n");
+
+        let mut subscriptions = [
+            subscribe(process_id).unwrap(),
+            subscribe(process_id).unwrap(),
+        ];
+        push(process_id, "after\n");
+        push_stderr(process_id, "warning\n");
+        finish(process_id);
+        assert!(subscribe(process_id).is_none());
+
+        for subscription in &mut subscriptions {
+            assert_eq!(subscription.entries.len(), 1);
+            assert_eq!(subscription.entries[0].content, "before");
+            assert_eq!(subscription.stderr, vec!["warming up".to_string()]);
+
+            match subscription.messages.recv().await.unwrap() {
+                LiveLogMessage::Entry { entry } => {
+                    assert_eq!(entry.content, "after");
+                    assert!(entry.sequence > subscription.entries[0].sequence);
+                }
+                other => panic!("expected an entry, got {:?}", other),
+            }
+            assert!(matches!(
+                subscription.messages.recv().await.unwrap(),
+                LiveLogMessage::Stderr { line } if line == "warning"
+            ));
+            assert!(matches!(
+                subscription.messages.recv().await,
+                Err(broadcast::error::RecvError::Closed)
+            ));
+        }
+    }
+}
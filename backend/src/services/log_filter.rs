@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::executor::{ActionType, NormalizedConversation, NormalizedEntry, NormalizedEntryType};
+
+const ENTRY_TYPES: [&str; 6] = [
+    "user_message",
+    "assistant_message",
+    "tool_use",
+    "system_message",
+    "error_message",
+    "thinking",
+];
+const ACTION_KINDS: [&str; 7] = [
+    "file_read",
+    "file_write",
+    "command_run",
+    "search",
+    "web_fetch",
+    "task_create",
+    "other",
+];
+
+#[derive(Debug)]
+pub enum LogFilterError {
+    UnknownEntryType(String),
+    UnknownActionKind(String),
+}
+
+impl std::fmt::Display for LogFilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFilterError::UnknownEntryType(value) => write!(
+                f,
+                "Unknown entry type '{}', expected one of: {}",
+                value,
+                ENTRY_TYPES.join(", ")
+            ),
+            LogFilterError::UnknownActionKind(value) => write!(
+                f,
+                "Unknown action kind '{}', expected one of: {}",
+                value,
+                ACTION_KINDS.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LogFilterError {}
+
+/// Query parameters for filtering normalized logs. List parameters are comma-separated.
+/// Action and tool filters only apply to `tool_use` entries.
+#[derive(Debug, Default, Deserialize)]
+pub struct LogFilterQuery {
+    pub include_types: Option<String>,
+    pub exclude_types: Option<String>,
+    pub include_actions: Option<String>,
+    pub exclude_actions: Option<String>,
+    pub include_tools: Option<String>,
+    pub exclude_tools: Option<String>,
+    /// Keep only assistant messages, errors, file writes and command runs
+    #[serde(default)]
+    pub significant_only: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    include_types: Option<Vec<String>>,
+    exclude_types: Vec<String>,
+    include_actions: Option<Vec<String>>,
+    exclude_actions: Vec<String>,
+    include_tools: Option<Vec<String>>,
+    exclude_tools: Vec<String>,
+    significant_only: bool,
+}
+
+/// A normalized conversation with some entries filtered out
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct FilteredConversation {
+    #[serde(flatten)]
+    #[ts(flatten)]
+    pub conversation: NormalizedConversation,
+    /// Hidden entries by kind: the action kind for tool uses (e.g. `file_read`), otherwise the
+    /// entry type
+    pub hidden_counts: HashMap<String, usize>,
+}
+
+fn split_list(value: Option<String>) -> Option<Vec<String>> {
+    value.map(|value| {
+        value
+            .split(',')
+            .map(|item| item.trim().to_lowercase())
+            .filter(|item| !item.is_empty())
+            .collect()
+    })
+}
+
+fn checked_list(
+    value: Option<String>,
+    allowed: &[&str],
+    error: fn(String) -> LogFilterError,
+) -> Result<Option<Vec<String>>, LogFilterError> {
+    let Some(items) = split_list(value) else {
+        return Ok(None);
+    };
+    if let Some(unknown) = items.iter().find(|item| !allowed.contains(&item.as_str())) {
+        return Err(error(unknown.clone()));
+    }
+    Ok(Some(items))
+}
+
+fn entry_type_name(entry_type: &NormalizedEntryType) -> &'static str {
+    match entry_type {
+        NormalizedEntryType::UserMessage => "user_message",
+        NormalizedEntryType::AssistantMessage => "assistant_message",
+        NormalizedEntryType::ToolUse { .. } => "tool_use",
+        NormalizedEntryType::SystemMessage => "system_message",
+        NormalizedEntryType::ErrorMessage => "error_message",
+        NormalizedEntryType::Thinking => "thinking",
+    }
+}
+
+fn action_kind_name(action_type: &ActionType) -> &'static str {
+    match action_type {
+        ActionType::FileRead { .. } => "file_read",
+        ActionType::FileWrite { .. } => "file_write",
+        ActionType::CommandRun { .. } => "command_run",
+        ActionType::Search { .. } => "search",
+        ActionType::WebFetch { .. } => "web_fetch",
+        ActionType::TaskCreate { .. } => "task_create",
+        ActionType::Other { .. } => "other",
+    }
+}
+
+/// Kind used for `hidden_counts`
+fn entry_kind(entry: &NormalizedEntry) -> &'static str {
+    match &entry.entry_type {
+        NormalizedEntryType::ToolUse { action_type, .. } => action_kind_name(action_type),
+        entry_type => entry_type_name(entry_type),
+    }
+}
+
+fn matches_list(list: &Option<Vec<String>>, value: &str) -> bool {
+    list.as_ref()
+        .is_none_or(|items| items.iter().any(|item| item == value))
+}
+
+impl TryFrom<LogFilterQuery> for LogFilter {
+    type Error = LogFilterError;
+
+    fn try_from(query: LogFilterQuery) -> Result<Self, Self::Error> {
+        Ok(Self {
+            include_types: checked_list(
+                query.include_types,
+                &ENTRY_TYPES,
+                LogFilterError::UnknownEntryType,
+            )?,
+            exclude_types: checked_list(
+                query.exclude_types,
+                &ENTRY_TYPES,
+                LogFilterError::UnknownEntryType,
+            )?
+            .unwrap_or_default(),
+            include_actions: checked_list(
+                query.include_actions,
+                &ACTION_KINDS,
+                LogFilterError::UnknownActionKind,
+            )?,
+            exclude_actions: checked_list(
+                query.exclude_actions,
+                &ACTION_KINDS,
+                LogFilterError::UnknownActionKind,
+            )?
+            .unwrap_or_default(),
+            include_tools: split_list(query.include_tools),
+            exclude_tools: split_list(query.exclude_tools).unwrap_or_default(),
+            significant_only: query.significant_only,
+        })
+    }
+}
+
+impl LogFilter {
+    fn is_significant(entry: &NormalizedEntry) -> bool {
+        // Exit codes aren't recorded per command, so every command run counts
+        match &entry.entry_type {
+            NormalizedEntryType::AssistantMessage | NormalizedEntryType::ErrorMessage => true,
+            NormalizedEntryType::ToolUse { action_type, .. } => matches!(
+                action_type,
+                ActionType::FileWrite { .. } | ActionType::CommandRun { .. }
+            ),
+            _ => false,
+        }
+    }
+
+    pub fn keeps(&self, entry: &NormalizedEntry) -> bool {
+        if self.significant_only && !Self::is_significant(entry) {
+            return false;
+        }
+
+        let entry_type = entry_type_name(&entry.entry_type);
+        if !matches_list(&self.include_types, entry_type)
+            || self.exclude_types.iter().any(|t| t == entry_type)
+        {
+            return false;
+        }
+
+        if let NormalizedEntryType::ToolUse {
+            tool_name,
+            action_type,
+        } = &entry.entry_type
+        {
+            let action = action_kind_name(action_type);
+            let tool = tool_name.to_lowercase();
+            if !matches_list(&self.include_actions, action)
+                || self.exclude_actions.iter().any(|a| a == action)
+                || !matches_list(&self.include_tools, &tool)
+                || self.exclude_tools.contains(&tool)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn apply(&self, mut conversation: NormalizedConversation) -> FilteredConversation {
+        let mut hidden_counts = HashMap::new();
+        conversation.entries.retain(|entry| {
+            let keep = self.keeps(entry);
+            if !keep {
+                *hidden_counts
+                    .entry(entry_kind(entry).to_string())
+                    .or_insert(0) += 1;
+            }
+            keep
+        });
+
+        FilteredConversation {
+            conversation,
+            hidden_counts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(entry_type: NormalizedEntryType) -> NormalizedEntry {
+        NormalizedEntry {
+            sequence: 0,
+            timestamp: None,
+            entry_type,
+            content: String::new(),
+            metadata: None,
+        }
+    }
+
+    fn tool(tool_name: &str, action_type: ActionType) -> NormalizedEntry {
+        entry(NormalizedEntryType::ToolUse {
+            tool_name: tool_name.to_string(),
+            action_type,
+        })
+    }
+
+    fn conversation() -> NormalizedConversation {
+        NormalizedConversation {
+            entries: vec![
+                entry(NormalizedEntryType::UserMessage),
+                tool(
+                    "Read",
+                    ActionType::FileRead {
+                        path: "a.rs".to_string(),
+                    },
+                ),
+                tool(
+                    "Read",
+                    ActionType::FileRead {
+                        path: "b.rs".to_string(),
+                    },
+                ),
+                tool(
+                    "Edit",
+                    ActionType::FileWrite {
+                        path: "a.rs".to_string(),
+                    },
+                ),
+                tool(
+                    "Bash",
+                    ActionType::CommandRun {
+                        command: "cargo test".to_string(),
+                    },
+                ),
+                entry(NormalizedEntryType::Thinking),
+                entry(NormalizedEntryType::AssistantMessage),
+            ],
+            session_id: None,
+            executor_type: "claude".to_string(),
+            prompt: None,
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_significant_only_counts_hidden_entries() {
+        let filter = LogFilter::try_from(LogFilterQuery {
+            significant_only: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let filtered = filter.apply(conversation());
+
+        assert_eq!(filtered.conversation.entries.len(), 3);
+        assert_eq!(filtered.hidden_counts.get("file_read"), Some(&2));
+        assert_eq!(filtered.hidden_counts.get("user_message"), Some(&1));
+        assert_eq!(filtered.hidden_counts.get("thinking"), Some(&1));
+    }
+
+    #[test]
+    fn test_action_and_tool_filters_only_affect_tool_uses() {
+        let filter = LogFilter::try_from(LogFilterQuery {
+            exclude_actions: Some("file_read".to_string()),
+            exclude_tools: Some("bash".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let filtered = filter.apply(conversation());
+
+        assert_eq!(filtered.conversation.entries.len(), 4);
+        assert_eq!(filtered.hidden_counts.get("file_read"), Some(&2));
+        assert_eq!(filtered.hidden_counts.get("command_run"), Some(&1));
+
+        let invalid = LogFilter::try_from(LogFilterQuery {
+            include_types: Some("tool_use,reads".to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(invalid, Err(LogFilterError::UnknownEntryType(t)) if t == "reads"));
+    }
+}
@@ -0,0 +1,638 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    executor::{
+        metadata_cap, Executor, ExecutorConfig, NormalizedConversation, NormalizedEntry,
+        NormalizedEntryType, OutputFormat,
+    },
+    executors::DevServerExecutor,
+    models::{
+        execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
+        executor_session::ExecutorSession,
+        normalized_log_snapshot::{NormalizedLogSnapshot, SnapshotVersion},
+    },
+    services::live_normalization,
+};
+
+/// Above this many entry comparisons the diff falls back to comparing entries by position
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug)]
+pub enum NormalizationError {
+    /// The process finished without producing any output
+    NoLogs,
+    UnsupportedExecutor(String),
+    Parse(String),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for NormalizationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NormalizationError::NoLogs => {
+                write!(f, "No logs available for this execution process")
+            }
+            NormalizationError::UnsupportedExecutor(executor_type) => {
+                write!(f, "Unsupported executor type: {}", executor_type)
+            }
+            NormalizationError::Parse(e) => write!(f, "Failed to normalize logs: {}", e),
+            NormalizationError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NormalizationError {}
+
+impl From<sqlx::Error> for NormalizationError {
+    fn from(err: sqlx::Error) -> Self {
+        NormalizationError::Database(err)
+    }
+}
+
+/// The executor whose `normalize_logs` reads the stdout of `process`
+pub(crate) fn stdout_parser(
+    process: &ExecutionProcess,
+    executor_session: Option<&ExecutorSession>,
+) -> Result<Box<dyn Executor>, NormalizationError> {
+    let executor_type = process.executor_type.as_deref().unwrap_or("unknown");
+
+    if process.process_type == ExecutionProcessType::DevServer {
+        return Ok(Box::new(DevServerExecutor {
+            script: executor_session
+                .and_then(|s| s.prompt.clone())
+                .unwrap_or_else(|| "dev server".to_string()),
+        }));
+    }
+
+    let executor_config = if process.process_type == ExecutionProcessType::SetupScript {
+        ExecutorConfig::SetupScript {
+            script: executor_session
+                .and_then(|s| s.prompt.clone())
+                .unwrap_or_else(|| "setup script".to_string()),
+        }
+    } else {
+        match executor_type
+            .parse::<ExecutorConfig>()
+            .ok()
+            .filter(|config| config.factory().is_some())
+        {
+            Some(config) => config,
+            None => {
+                tracing::warn!(
+                    "Unsupported executor type: {}, cannot normalize logs properly",
+                    executor_type
+                );
+                return Err(NormalizationError::UnsupportedExecutor(
+                    executor_type.to_string(),
+                ));
+            }
+        }
+    };
+
+    let output_format = OutputFormat::from_process_args(process.args.as_deref());
+    Ok(executor_config.create_executor_with_output_format(output_format))
+}
+
+/// Worktree path handed to the parser, used to make file paths relative
+pub(crate) fn parser_working_dir(process: &ExecutionProcess) -> String {
+    // Try to canonicalize if the directory exists, otherwise use the stored path as-is
+    match std::fs::canonicalize(&process.working_directory) {
+        Ok(canonical_path) => canonical_path.to_string_lossy().to_string(),
+        Err(_) => {
+            tracing::debug!(
+                "Working directory {} no longer exists, using stored path for normalization",
+                process.working_directory
+            );
+            process.working_directory.clone()
+        }
+    }
+}
+
+/// Parse the stored stdout/stderr of an execution process with the current executor parsers
+pub async fn normalize_execution_process(
+    pool: &SqlitePool,
+    process: &ExecutionProcess,
+) -> Result<NormalizedConversation, NormalizationError> {
+    let executor_session =
+        match ExecutorSession::find_by_execution_process_id(pool, process.id).await {
+            Ok(session) => session,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to fetch executor session for process {}: {}",
+                    process.id,
+                    e
+                );
+                None
+            }
+        };
+
+    let has_stdout = process
+        .stdout
+        .as_deref()
+        .is_some_and(|stdout| !stdout.trim().is_empty());
+    let has_stderr = process
+        .stderr
+        .as_deref()
+        .is_some_and(|stderr| !stderr.trim().is_empty());
+
+    // While the process runs its stdout is normalized as it is read, ahead of what's stored
+    let live_entries = if process.status == ExecutionProcessStatus::Running {
+        live_normalization::entries(process.id).filter(|entries| !entries.is_empty())
+    } else {
+        None
+    };
+
+    if !has_stdout && !has_stderr && live_entries.is_none() {
+        // A running process simply hasn't produced anything yet
+        if process.status == ExecutionProcessStatus::Running {
+            return Ok(NormalizedConversation {
+                entries: vec![],
+                session_id: None,
+                executor_type: process
+                    .executor_type
+                    .clone()
+                    .unwrap_or("unknown".to_string()),
+                prompt: executor_session.as_ref().and_then(|s| s.prompt.clone()),
+                summary: executor_session.as_ref().and_then(|s| s.summary.clone()),
+            });
+        }
+        return Err(NormalizationError::NoLogs);
+    }
+
+    // Parse stdout as JSONL using executor normalization
+    let mut stdout_entries = Vec::new();
+    let mut stdout_prompt = None;
+    if let Some(entries) = live_entries {
+        stdout_prompt = NormalizedConversation::first_user_message(&entries);
+        stdout_entries = entries;
+    } else if let Some(stdout) = process.stdout.as_deref().filter(|_| has_stdout) {
+        let executor = stdout_parser(process, executor_session.as_ref())?;
+        let working_dir_path = parser_working_dir(process);
+
+        let normalized = executor
+            .normalize_logs(stdout, &working_dir_path)
+            .map_err(|e| {
+                tracing::error!(
+                    "Failed to normalize stdout for process {}: {}",
+                    process.id,
+                    e
+                );
+                NormalizationError::Parse(e)
+            })?;
+        stdout_entries = normalized.entries;
+        stdout_prompt = normalized.prompt;
+        tracing::debug!(
+            "Successfully normalized {} stdout entries for process {}",
+            stdout_entries.len(),
+            process.id
+        );
+    }
+
+    // Parse stderr chunks separated by boundary markers
+    let mut stderr_entries = Vec::new();
+    if let Some(stderr) = process.stderr.as_deref().filter(|_| has_stderr) {
+        for chunk in stderr.trim().split("---STDERR_CHUNK_BOUNDARY---") {
+            let content = chunk.trim();
+            if !content.is_empty() {
+                stderr_entries.push(NormalizedEntry {
+                    sequence: 0,
+                    timestamp: Some(chrono::Utc::now().to_rfc3339()),
+                    entry_type: NormalizedEntryType::ErrorMessage,
+                    content: content.to_string(),
+                    metadata: None,
+                });
+            }
+        }
+    }
+
+    // Merge stdout and stderr entries chronologically (entries without timestamps go last)
+    let mut all_entries = stdout_entries;
+    all_entries.extend(stderr_entries);
+    all_entries.sort_by(|a, b| match (&a.timestamp, &b.timestamp) {
+        (Some(a_ts), Some(b_ts)) => a_ts.cmp(b_ts),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    NormalizedConversation::assign_sequence(&mut all_entries);
+    NormalizedConversation::cap_metadata(&mut all_entries, metadata_cap());
+
+    let executor_type = if process.process_type == ExecutionProcessType::SetupScript {
+        "setup_script".to_string()
+    } else {
+        process
+            .executor_type
+            .clone()
+            .unwrap_or("unknown".to_string())
+    };
+
+    Ok(NormalizedConversation {
+        entries: all_entries,
+        session_id: None,
+        executor_type,
+        // Executors without a user message in their output fall back to the stored prompt
+        prompt: stdout_prompt.or_else(|| executor_session.as_ref().and_then(|s| s.prompt.clone())),
+        summary: executor_session.as_ref().and_then(|s| s.summary.clone()),
+    })
+}
+
+/// The normalization shown for a process: the pinned primary snapshot if one was promoted,
+/// otherwise the stored output parsed with the current parsers
+pub async fn current_normalization(
+    pool: &SqlitePool,
+    process: &ExecutionProcess,
+) -> Result<NormalizedConversation, NormalizationError> {
+    match NormalizedLogSnapshot::find(pool, process.id, SnapshotVersion::Primary).await? {
+        Some(mut pinned) => {
+            // Snapshots stored before entries had sequence numbers
+            NormalizedConversation::assign_sequence(&mut pinned.entries);
+            Ok(pinned)
+        }
+        None => normalize_execution_process(pool, process).await,
+    }
+}
+
+/// Parse a process's stored output with the current parsers, store the result as `version`
+/// and report how it differs from the normalization shown until now
+pub async fn renormalize(
+    pool: &SqlitePool,
+    process: &ExecutionProcess,
+    version: SnapshotVersion,
+) -> Result<NormalizedLogDiff, NormalizationError> {
+    let previous = current_normalization(pool, process).await?;
+    let renormalized = normalize_execution_process(pool, process).await?;
+    NormalizedLogSnapshot::save(pool, process.id, version, &renormalized).await?;
+
+    Ok(diff_normalized_entries(
+        &previous.entries,
+        &renormalized.entries,
+    ))
+}
+
+/// Outcome of re-normalizing one execution process
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct RenormalizeResult {
+    pub execution_process_id: Uuid,
+    /// Changes from the previously shown normalization to the new one
+    pub diff: Option<NormalizedLogDiff>,
+    pub error: Option<String>,
+}
+
+impl RenormalizeResult {
+    pub fn new(
+        execution_process_id: Uuid,
+        outcome: Result<NormalizedLogDiff, NormalizationError>,
+    ) -> Self {
+        match outcome {
+            Ok(diff) => Self {
+                execution_process_id,
+                diff: Some(diff),
+                error: None,
+            },
+            Err(e) => Self {
+                execution_process_id,
+                diff: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Re-normalize every finished process of an attempt and pin the results as primary, so
+/// parser improvements apply to old attempts. Running processes are left alone since their
+/// output is still growing.
+pub async fn renormalize_attempt(
+    pool: &SqlitePool,
+    attempt_id: Uuid,
+) -> Result<Vec<RenormalizeResult>, sqlx::Error> {
+    let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt_id).await?;
+
+    let mut results = Vec::new();
+    for process in processes
+        .iter()
+        .filter(|p| p.status != ExecutionProcessStatus::Running)
+    {
+        let outcome = renormalize(pool, process, SnapshotVersion::Primary).await;
+        if let Err(NormalizationError::Database(e)) = outcome {
+            return Err(e);
+        }
+        results.push(RenormalizeResult::new(process.id, outcome));
+    }
+
+    Ok(results)
+}
+
+/// Difference between two normalizations of the same execution
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct NormalizedLogDiff {
+    /// Entries only in the new normalization
+    pub added: usize,
+    /// Entries only in the old normalization
+    pub removed: usize,
+    /// Entries of the same kind whose content changed in place
+    pub changed: usize,
+    pub unchanged: usize,
+    /// For each old entry (by sequence number), its sequence number in the new
+    /// normalization; `None` where the entry was removed
+    pub sequence_map: Vec<Option<usize>>,
+}
+
+/// Identity of an entry for diffing; timestamps and metadata are ignored because stderr
+/// entries are stamped at normalization time
+fn entry_key(entry: &NormalizedEntry) -> (String, &str) {
+    (
+        serde_json::to_string(&entry.entry_type).unwrap_or_default(),
+        entry.content.as_str(),
+    )
+}
+
+/// Longest-common-subsequence alignment of `old` and `new`: the index pairs that match
+fn align(old: &[(String, &str)], new: &[(String, &str)]) -> Vec<(usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if n > 0 && m > 0 && n * m <= MAX_LCS_CELLS {
+        let width = m + 1;
+        let mut lengths = vec![0u32; (n + 1) * width];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old_mid[i] == new_mid[j] {
+                pairs.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    } else {
+        // Too large to align exactly; only identical entries at the same offset match
+        pairs.extend(
+            old_mid
+                .iter()
+                .zip(new_mid)
+                .enumerate()
+                .filter(|(_, (a, b))| a == b)
+                .map(|(k, _)| (prefix + k, prefix + k)),
+        );
+    }
+
+    pairs.extend((0..suffix).map(|k| (old.len() - suffix + k, new.len() - suffix + k)));
+    pairs
+}
+
+/// Compare two normalizations. Unmatched entries between the same pair of matching entries
+/// count as changed in place when they have the same kind (in order), otherwise as removed
+/// or added.
+pub fn diff_normalized_entries(
+    old: &[NormalizedEntry],
+    new: &[NormalizedEntry],
+) -> NormalizedLogDiff {
+    let old_keys: Vec<_> = old.iter().map(entry_key).collect();
+    let new_keys: Vec<_> = new.iter().map(entry_key).collect();
+
+    let mut diff = NormalizedLogDiff {
+        added: 0,
+        removed: 0,
+        changed: 0,
+        unchanged: 0,
+        sequence_map: vec![None; old.len()],
+    };
+
+    let mut old_start = 0;
+    let mut new_start = 0;
+    let anchors = align(&old_keys, &new_keys)
+        .into_iter()
+        .chain(std::iter::once((old.len(), new.len())));
+    for (old_anchor, new_anchor) in anchors {
+        let mut next_new = new_start;
+        let mut changed = 0;
+        for i in old_start..old_anchor {
+            let kind = std::mem::discriminant(&old[i].entry_type);
+            if let Some(j) =
+                (next_new..new_anchor).find(|&j| std::mem::discriminant(&new[j].entry_type) == kind)
+            {
+                diff.sequence_map[i] = Some(j);
+                next_new = j + 1;
+                changed += 1;
+            }
+        }
+        diff.changed += changed;
+        diff.removed += old_anchor - old_start - changed;
+        diff.added += new_anchor - new_start - changed;
+
+        if old_anchor < old.len() {
+            diff.sequence_map[old_anchor] = Some(new_anchor);
+            diff.unchanged += 1;
+        }
+        old_start = old_anchor + 1;
+        new_start = new_anchor + 1;
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::execution_process::CreateExecutionProcess,
+        test_utils::{seeded_pool, Seeded},
+    };
+
+    fn entry(entry_type: NormalizedEntryType, content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            sequence: 0,
+            timestamp: None,
+            entry_type,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_counts_added_removed_and_changed_entries() {
+        let old = vec![
+            entry(NormalizedEntryType::UserMessage, "fix the bug"),
+            entry(NormalizedEntryType::AssistantMessage, "looking"),
+            entry(NormalizedEntryType::SystemMessage, "noise"),
+            entry(NormalizedEntryType::AssistantMessage, "done"),
+        ];
+        let new = vec![
+            entry(NormalizedEntryType::UserMessage, "fix the bug"),
+            entry(NormalizedEntryType::Thinking, "plan first"),
+            entry(NormalizedEntryType::AssistantMessage, "looking"),
+            entry(NormalizedEntryType::AssistantMessage, "done!"),
+        ];
+
+        let diff = diff_normalized_entries(&old, &new);
+
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.added, 1);
+        assert_eq!(diff.changed, 1);
+        assert_eq!(diff.removed, 1);
+        // "done" became "done!"; the system message has no counterpart
+        assert_eq!(diff.sequence_map, vec![Some(0), Some(2), None, Some(3)]);
+    }
+
+    #[test]
+    fn test_identical_normalizations_map_one_to_one() {
+        let entries = vec![
+            entry(NormalizedEntryType::UserMessage, "a"),
+            entry(NormalizedEntryType::AssistantMessage, "b"),
+        ];
+
+        let diff = diff_normalized_entries(&entries, &entries);
+
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.added + diff.removed + diff.changed, 0);
+        assert_eq!(diff.sequence_map, vec![Some(0), Some(1)]);
+    }
+
+    /// A database with one attempt, returning its id
+    async fn attempt_pool() -> (SqlitePool, Uuid) {
+        let (pool, Seeded { attempt_id, .. }) = seeded_pool().await;
+        (pool, attempt_id)
+    }
+
+    async fn setup_script_process(pool: &SqlitePool, attempt_id: Uuid) -> ExecutionProcess {
+        ExecutionProcess::create(
+            pool,
+            &CreateExecutionProcess {
+                task_attempt_id: attempt_id,
+                process_type: ExecutionProcessType::SetupScript,
+                executor_type: Some("setup-script".to_string()),
+                command: "bash".to_string(),
+                args: None,
+                working_directory: "/tmp".to_string(),
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sequence_numbers_increase_across_interleaved_streams() {
+        let (pool, attempt_id) = attempt_pool().await;
+        let process = setup_script_process(&pool, attempt_id).await;
+        ExecutionProcess::append_stdout(&pool, process.id, "npm install\nadded 12 packages\n")
+            .await
+            .unwrap();
+        ExecutionProcess::append_stderr(
+            &pool,
+            process.id,
+            "npm WARN deprecated\n---STDERR_CHUNK_BOUNDARY---\n",
+        )
+        .await
+        .unwrap();
+        ExecutionProcess::append_stdout(&pool, process.id, "npm run build\n")
+            .await
+            .unwrap();
+        ExecutionProcess::append_stderr(
+            &pool,
+            process.id,
+            "warning: unused import\n---STDERR_CHUNK_BOUNDARY---\n",
+        )
+        .await
+        .unwrap();
+        let process = ExecutionProcess::find_by_id(&pool, process.id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let conversation = normalize_execution_process(&pool, &process).await.unwrap();
+        assert!(conversation
+            .entries
+            .iter()
+            .any(|e| matches!(e.entry_type, NormalizedEntryType::ErrorMessage)));
+        assert_eq!(conversation.entries.len(), 4);
+        assert_eq!(conversation.entries[0].sequence, 0);
+        assert!(conversation
+            .entries
+            .windows(2)
+            .all(|pair| pair[0].sequence < pair[1].sequence));
+
+        let serialized = serde_json::to_value(&conversation.entries[1]).unwrap();
+        assert_eq!(serialized["sequence"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_renormalize_attempt_replaces_stale_normalization() {
+        let (pool, attempt_id) = attempt_pool().await;
+        let process = setup_script_process(&pool, attempt_id).await;
+        let process_id = process.id;
+        ExecutionProcess::append_stdout(&pool, process_id, "npm install\n")
+            .await
+            .unwrap();
+        ExecutionProcess::update_completion(
+            &pool,
+            process_id,
+            ExecutionProcessStatus::Completed,
+            Some(0),
+        )
+        .await
+        .unwrap();
+
+        // What an older parser stored for this output
+        let stale = NormalizedConversation {
+            entries: vec![entry(
+                NormalizedEntryType::SystemMessage,
+                "old parser output",
+            )],
+            session_id: None,
+            executor_type: "setup_script".to_string(),
+            prompt: None,
+            summary: None,
+        };
+        NormalizedLogSnapshot::save(&pool, process.id, SnapshotVersion::Primary, &stale)
+            .await
+            .unwrap();
+
+        let results = renormalize_attempt(&pool, attempt_id).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].error.is_none());
+
+        let process = ExecutionProcess::find_by_id(&pool, process_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let current = current_normalization(&pool, &process).await.unwrap();
+        let fresh = normalize_execution_process(&pool, &process).await.unwrap();
+        assert_eq!(current.entries.len(), fresh.entries.len());
+        assert!(current
+            .entries
+            .iter()
+            .zip(&fresh.entries)
+            .all(|(a, b)| a.content == b.content));
+        assert!(current
+            .entries
+            .iter()
+            .all(|e| e.content != "old parser output"));
+    }
+}
@@ -1,13 +1,50 @@
 pub mod analytics;
+pub mod asset_usage;
+pub mod attempt_export;
+pub mod attempt_overlap;
+pub mod attempt_stop;
+pub mod attempt_wait;
+pub mod benchmark;
+pub mod bisect;
+pub mod branch_status;
+pub mod cleanup;
+pub mod execution_diff;
+pub mod focus_mode;
+pub mod formatting;
 pub mod git_service;
 pub mod github_service;
+pub mod glossary;
+pub mod job_scheduler;
+pub mod live_normalization;
+pub mod log_filter;
+pub mod log_normalization;
+pub mod network_observer;
 pub mod notification_service;
+pub mod orphan_branches;
 pub mod pr_monitor;
 pub mod process_service;
+pub mod project_events;
+pub mod prompt_budget;
+pub mod provenance;
+pub mod repo_config;
+pub mod search;
+#[cfg(feature = "self-update")]
+pub mod self_update;
+pub mod server_discovery;
+pub mod start_hook;
+pub mod untracked_changes;
+pub mod webhooks;
 
-pub use analytics::{generate_user_id, AnalyticsConfig, AnalyticsService};
-pub use git_service::{GitService, GitServiceError};
+pub use analytics::{
+    generate_user_id, redact_content_properties, AnalyticsConfig, AnalyticsService,
+};
+pub use attempt_overlap::AttemptChangeCache;
+pub use branch_status::BranchStatusCache;
+pub use cleanup::TaskAttemptCleanupService;
+pub use git_service::{GitService, GitServiceError, DEFAULT_RENAME_THRESHOLD};
 pub use github_service::{CreatePrRequest, GitHubRepoInfo, GitHubService, GitHubServiceError};
-pub use notification_service::{NotificationConfig, NotificationService};
+pub use job_scheduler::{JobScheduler, Schedule};
+pub use notification_service::{NotificationConfig, NotificationMessage, NotificationService};
 pub use pr_monitor::PrMonitorService;
 pub use process_service::ProcessService;
+pub use project_events::ProjectEventBus;
@@ -0,0 +1,399 @@
+//! Observing the hosts coding agents connect to
+//!
+//! When a project opts in, each coding agent run gets a forward proxy of its own on a local
+//! port, handed to the agent through the usual proxy variables. The proxy records the host of
+//! every HTTPS CONNECT tunnel and plain HTTP request and passes the bytes through untouched;
+//! TLS isn't intercepted, so host names and ports are all it sees. Nothing is blocked, since
+//! agents need their model API, and when the proxy can't start the agent connects directly.
+
+use std::{collections::HashMap, io, sync::Arc, time::Duration};
+
+use sqlx::SqlitePool;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use uuid::Uuid;
+
+use crate::models::network_policy::{NetworkContact, NetworkPolicy, NetworkVerdict};
+
+/// Largest request head read before the connection is dropped
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+/// Addresses agents keep reaching directly, like the attempt's dev server
+const NO_PROXY: &str = "localhost,127.0.0.1,::1";
+
+/// The logging proxy of one execution. It stops taking connections when dropped; open tunnels
+/// run until either side closes them.
+#[derive(Debug)]
+pub struct NetworkObserver {
+    port: u16,
+    accept_loop: JoinHandle<()>,
+}
+
+impl Drop for NetworkObserver {
+    fn drop(&mut self) {
+        self.accept_loop.abort();
+    }
+}
+
+impl NetworkObserver {
+    pub async fn start(
+        pool: SqlitePool,
+        execution_process_id: Uuid,
+        policy: NetworkPolicy,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let port = listener.local_addr()?.port();
+        let policy = Arc::new(policy);
+
+        let accept_loop = tokio::spawn(async move {
+            loop {
+                let client = match listener.accept().await {
+                    Ok((client, _)) => client,
+                    Err(e) => {
+                        // Usually out of file descriptors; the agent is waiting on us, so retry
+                        tracing::warn!(
+                            "Network observer of execution {} failed to accept: {}",
+                            execution_process_id,
+                            e
+                        );
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                let pool = pool.clone();
+                let policy = policy.clone();
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        proxy_connection(client, &pool, execution_process_id, &policy).await
+                    {
+                        tracing::debug!(
+                            "Proxied connection of execution {} ended: {}",
+                            execution_process_id,
+                            e
+                        );
+                    }
+                });
+            }
+        });
+
+        Ok(Self { port, accept_loop })
+    }
+
+    /// Variables pointing the agent's HTTP clients at the proxy
+    pub fn proxy_env(&self) -> HashMap<String, String> {
+        let url = format!("http://127.0.0.1:{}", self.port);
+        let mut env = HashMap::new();
+        for name in ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY"] {
+            env.insert(name.to_string(), url.clone());
+            env.insert(name.to_lowercase(), url.clone());
+        }
+        env.insert("NO_PROXY".to_string(), NO_PROXY.to_string());
+        env.insert("no_proxy".to_string(), NO_PROXY.to_string());
+        env
+    }
+}
+
+/// Start the proxy of a coding agent run when its project observes network access. `None`
+/// when it doesn't, or when the proxy couldn't start, in which case the agent connects
+/// directly.
+pub async fn start_for_agent(
+    pool: &SqlitePool,
+    attempt_id: Uuid,
+    execution_process_id: Uuid,
+) -> Option<NetworkObserver> {
+    let policy = match NetworkPolicy::find_for_attempt(pool, attempt_id).await {
+        Ok(Some(policy)) if policy.enabled => policy,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load the network policy of attempt {}: {}",
+                attempt_id,
+                e
+            );
+            return None;
+        }
+    };
+
+    match NetworkObserver::start(pool.clone(), execution_process_id, policy).await {
+        Ok(observer) => Some(observer),
+        Err(e) => {
+            tracing::warn!(
+                "Network observer for execution {} couldn't start, the agent connects directly: {}",
+                execution_process_id,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Where a proxied request goes
+#[derive(Debug, PartialEq)]
+struct ProxyTarget {
+    host: String,
+    port: u16,
+    /// Request head to send upstream; `None` for a CONNECT tunnel
+    forward: Option<String>,
+}
+
+async fn proxy_connection(
+    mut client: TcpStream,
+    pool: &SqlitePool,
+    execution_process_id: Uuid,
+    policy: &NetworkPolicy,
+) -> io::Result<()> {
+    let (head, body_start) = read_request_head(&mut client).await?;
+    let Some(target) = parse_request_head(&head) else {
+        client
+            .write_all(b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n")
+            .await?;
+        return Ok(());
+    };
+
+    let verdict = policy.verdict(&target.host);
+    if verdict == NetworkVerdict::Denied {
+        tracing::warn!(
+            "Execution {} contacted denylisted host {}:{}",
+            execution_process_id,
+            target.host,
+            target.port
+        );
+    }
+    if let Err(e) = NetworkContact::record(
+        pool,
+        execution_process_id,
+        &target.host,
+        target.port,
+        verdict,
+    )
+    .await
+    {
+        tracing::error!(
+            "Failed to record a network contact of execution {}: {}",
+            execution_process_id,
+            e
+        );
+    }
+
+    let mut upstream = match TcpStream::connect((target.host.as_str(), target.port)).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            client
+                .write_all(b"HTTP/1.1 502 Bad Gateway\r\nConnection: close\r\n\r\n")
+                .await?;
+            return Err(e);
+        }
+    };
+    match &target.forward {
+        Some(head) => upstream.write_all(head.as_bytes()).await?,
+        None => {
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await?
+        }
+    }
+    upstream.write_all(&body_start).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+/// Read up to the end of the request head. Returns the head and whatever was read past it.
+async fn read_request_head(client: &mut TcpStream) -> io::Result<(String, Vec<u8>)> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            let body_start = buffer.split_off(end + 4);
+            let head = String::from_utf8(buffer)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            return Ok((head, body_start));
+        }
+        if buffer.len() > MAX_REQUEST_HEAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        let read = client.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+}
+
+/// The target of a CONNECT request, or of a plain HTTP request in absolute form, which is
+/// rewritten to origin form for the upstream server. Plain requests are sent with
+/// `Connection: close`, so a client can't reuse the connection for another host unseen.
+fn parse_request_head(head: &str) -> Option<ProxyTarget> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let (method, target, version) = (
+        request_line.next()?,
+        request_line.next()?,
+        request_line.next()?,
+    );
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_host_port(target)?;
+        return Some(ProxyTarget {
+            host,
+            port: port?,
+            forward: None,
+        });
+    }
+
+    let url = target.strip_prefix("http://")?;
+    let (authority, path) = match url.find(['/', '?']) {
+        Some(i) => (&url[..i], &url[i..]),
+        None => (url, ""),
+    };
+    let (host, port) = split_host_port(authority)?;
+
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    let mut forward = format!("{} {}{} {}\r\n", method, separator, path, version);
+    for header in lines.filter(|line| !line.is_empty()) {
+        let name = header.split(':').next().unwrap_or_default().trim();
+        let hop_by_hop = ["connection", "proxy-connection", "keep-alive"]
+            .iter()
+            .any(|hop| name.eq_ignore_ascii_case(hop));
+        if !hop_by_hop {
+            forward.push_str(header);
+            forward.push_str("\r\n");
+        }
+    }
+    forward.push_str("Connection: close\r\n\r\n");
+
+    Some(ProxyTarget {
+        host,
+        port: port.unwrap_or(80),
+        forward: Some(forward),
+    })
+}
+
+/// Split `host[:port]`, with IPv6 addresses in brackets and credentials dropped
+fn split_host_port(authority: &str) -> Option<(String, Option<u16>)> {
+    let authority = authority.rsplit('@').next()?;
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed.split_once(']')?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let port = match port {
+        Some(port) => Some(port.parse().ok()?),
+        None => None,
+    };
+    Some((host.to_ascii_lowercase(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::network_policy::normalize_domains,
+        test_utils::{seeded_pool, Seeded},
+    };
+
+    #[test]
+    fn test_requests_are_parsed_into_targets() {
+        assert_eq!(
+            parse_request_head("CONNECT API.example.com:443 HTTP/1.1\r\nHost: x\r\n\r\n"),
+            Some(ProxyTarget {
+                host: "api.example.com".to_string(),
+                port: 443,
+                forward: None,
+            })
+        );
+        assert_eq!(
+            parse_request_head("CONNECT [::1]:8443 HTTP/1.1\r\n\r\n").map(|t| (t.host, t.port)),
+            Some(("::1".to_string(), 8443))
+        );
+        assert_eq!(
+            parse_request_head("CONNECT example.com HTTP/1.1\r\n\r\n"),
+            None
+        );
+        assert_eq!(parse_request_head("GET /relative HTTP/1.1\r\n\r\n"), None);
+
+        let target = parse_request_head(
+            "GET http://example.com:8080/a?b=c HTTP/1.1\r\nHost: example.com:8080\r\nProxy-Connection: keep-alive\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!((target.host.as_str(), target.port), ("example.com", 8080));
+        assert_eq!(
+            target.forward.as_deref(),
+            Some("GET /a?b=c HTTP/1.1\r\nHost: example.com:8080\r\nConnection: close\r\n\r\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tunnels_are_passed_through_and_recorded() {
+        let (pool, Seeded { attempt_id, .. }) = seeded_pool().await;
+        let process_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO execution_processes (id, task_attempt_id, process_type, command, working_directory)
+             VALUES ($1, $2, 'codingagent', 'executor', '/tmp/w')",
+        )
+        .bind(process_id)
+        .bind(attempt_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // An upstream that echoes what it's sent
+        let upstream = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let upstream_port = upstream.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = upstream.accept().await.unwrap();
+            let mut buffer = [0u8; 5];
+            stream.read_exact(&mut buffer).await.unwrap();
+            stream.write_all(&buffer).await.unwrap();
+        });
+
+        let policy = NetworkPolicy {
+            enabled: true,
+            denylist: normalize_domains(&["127.0.0.1".to_string()]).unwrap(),
+            ..NetworkPolicy::disabled(project_id)
+        };
+        let observer = NetworkObserver::start(pool.clone(), process_id, policy)
+            .await
+            .unwrap();
+        let proxy_url = observer.proxy_env()["HTTPS_PROXY"].clone();
+        let proxy_address = proxy_url.strip_prefix("http://").unwrap();
+
+        let mut client = TcpStream::connect(proxy_address).await.unwrap();
+        client
+            .write_all(format!("CONNECT 127.0.0.1:{} HTTP/1.1\r\n\r\n", upstream_port).as_bytes())
+            .await
+            .unwrap();
+        let established = b"HTTP/1.1 200 Connection Established\r\n\r\n";
+        let mut response = vec![0u8; established.len()];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(response, established);
+        client.write_all(b"hello").await.unwrap();
+        let mut echoed = [0u8; 5];
+        client.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(&echoed, b"hello");
+
+        let contacts = NetworkContact::find_by_task_attempt_id(&pool, attempt_id)
+            .await
+            .unwrap();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].host, "127.0.0.1");
+        assert_eq!(contacts[0].port, i64::from(upstream_port));
+        assert_eq!(contacts[0].verdict, NetworkVerdict::Denied);
+        assert!(NetworkContact::attempt_has_violation(&pool, attempt_id)
+            .await
+            .unwrap());
+    }
+}
@@ -28,6 +28,68 @@ impl Default for NotificationConfig {
 /// Cache for WSL root path from PowerShell
 static WSL_ROOT_PATH_CACHE: OnceLock<Option<String>> = OnceLock::new();
 
+/// Built-in notification strings, translatable through a small message catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationMessage {
+    TaskCompleteTitle,
+    TaskCompleteTitleGeneric,
+    TaskSucceeded,
+    TaskFailed,
+    TaskSucceededGeneric,
+    TaskFailedGeneric,
+}
+
+impl NotificationMessage {
+    /// Catalog template for this message in the given primary language subtag
+    fn template(&self, language: &str) -> Option<&'static str> {
+        let template = match (language, self) {
+            ("en", NotificationMessage::TaskCompleteTitle) => "Task Complete: {title}",
+            ("en", NotificationMessage::TaskCompleteTitleGeneric) => "Task Complete",
+            ("en", NotificationMessage::TaskSucceeded) => {
+                "✅ '{title}' completed successfully\nBranch: {branch}\nExecutor: {executor}"
+            }
+            ("en", NotificationMessage::TaskFailed) => {
+                "❌ '{title}' execution failed\nBranch: {branch}\nExecutor: {executor}"
+            }
+            ("en", NotificationMessage::TaskSucceededGeneric) => {
+                "Task execution completed successfully"
+            }
+            ("en", NotificationMessage::TaskFailedGeneric) => "Task execution failed",
+            ("ja", NotificationMessage::TaskCompleteTitle) => "タスク完了: {title}",
+            ("ja", NotificationMessage::TaskCompleteTitleGeneric) => "タスク完了",
+            ("ja", NotificationMessage::TaskSucceeded) => {
+                "✅ '{title}' が正常に完了しました\nブランチ: {branch}\n実行エージェント: {executor}"
+            }
+            ("ja", NotificationMessage::TaskFailed) => {
+                "❌ '{title}' の実行に失敗しました\nブランチ: {branch}\n実行エージェント: {executor}"
+            }
+            ("ja", NotificationMessage::TaskSucceededGeneric) => "タスクの実行が正常に完了しました",
+            ("ja", NotificationMessage::TaskFailedGeneric) => "タスクの実行に失敗しました",
+            _ => return None,
+        };
+        Some(template)
+    }
+
+    /// Render the message for a language tag (e.g. `ja-JP`), falling back to English.
+    /// `{name}` placeholders are replaced with the matching value from `args`.
+    pub fn render(&self, language: Option<&str>, args: &[(&str, &str)]) -> String {
+        let primary = language
+            .and_then(|tag| tag.split('-').next())
+            .map(|subtag| subtag.to_ascii_lowercase())
+            .unwrap_or_else(|| "en".to_string());
+
+        let template = self
+            .template(&primary)
+            .or_else(|| self.template("en"))
+            .unwrap_or_default();
+
+        args.iter()
+            .fold(template.to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), value)
+            })
+    }
+}
+
 impl NotificationService {
     /// Create a new NotificationService with the given configuration
     pub fn new(config: NotificationConfig) -> Self {
@@ -261,3 +323,35 @@ impl NotificationService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_messages_render_in_the_primary_language_or_english() {
+        let args = [
+            ("title", "Fix login"),
+            ("branch", "vk-1"),
+            ("executor", "claude"),
+        ];
+
+        assert_eq!(
+            NotificationMessage::TaskCompleteTitle.render(Some("ja-JP"), &args),
+            "タスク完了: Fix login"
+        );
+        assert_eq!(
+            NotificationMessage::TaskSucceeded.render(None, &args),
+            "✅ 'Fix login' completed successfully\nBranch: vk-1\nExecutor: claude"
+        );
+        // Languages without a catalog fall back to English
+        assert_eq!(
+            NotificationMessage::TaskFailedGeneric.render(Some("de"), &[]),
+            "Task execution failed"
+        );
+        assert_eq!(
+            NotificationMessage::TaskCompleteTitleGeneric.render(Some("JA"), &[]),
+            "タスク完了"
+        );
+    }
+}
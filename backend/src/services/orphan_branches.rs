@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{BranchType, Oid, Repository, Sort};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+use crate::{
+    models::{
+        ignored_attempt_branch::IgnoredAttemptBranch,
+        project::Project,
+        task::{CreateTask, Task, TaskStatus},
+        task_attempt::{TaskAttempt, TaskAttemptError},
+    },
+    services::GitService,
+};
+
+/// Commit subjects listed in the description of a task adopted from a branch
+const MAX_ADOPTED_COMMIT_SUBJECTS: usize = 20;
+
+/// An attempt branch in the repository that no attempt in this install knows about, e.g. one
+/// pushed from another machine
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct UnknownAttemptBranch {
+    pub branch: String,
+    pub local: bool,
+    /// Remotes the branch was pushed to, e.g. `origin`
+    pub remotes: Vec<String>,
+    pub last_commit_sha: String,
+    pub last_commit_subject: String,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub last_commit_at: DateTime<Utc>,
+    pub age_days: u32,
+    pub base_branch: String,
+    pub commits_ahead: u32,
+    pub commits_behind: u32,
+    pub ignored: bool,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct AdoptedBranch {
+    pub task: Task,
+    pub attempt: TaskAttempt,
+}
+
+/// Whether a branch name follows the `vk-<short attempt id>-<task title>` attempt naming
+pub fn is_attempt_branch_name(name: &str) -> bool {
+    let mut parts = name.splitn(3, '-');
+    parts.next() == Some("vk")
+        && parts.next().is_some_and(|id| {
+            id.len() == 4
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        })
+        && parts.next().is_some()
+}
+
+/// Where an attempt branch lives: its local tip and the remotes carrying it
+#[derive(Default)]
+struct BranchLocations {
+    local: Option<Oid>,
+    remote: Option<Oid>,
+    remotes: Vec<String>,
+}
+
+fn attempt_branches(repo: &Repository) -> Result<BTreeMap<String, BranchLocations>, git2::Error> {
+    let mut branches: BTreeMap<String, BranchLocations> = BTreeMap::new();
+    for branch in repo.branches(None)? {
+        let (branch, branch_type) = branch?;
+        let (Some(name), Some(oid)) = (branch.name()?, branch.get().target()) else {
+            continue;
+        };
+        match branch_type {
+            BranchType::Local if is_attempt_branch_name(name) => {
+                branches.entry(name.to_string()).or_default().local = Some(oid);
+            }
+            BranchType::Remote => {
+                let Some((remote, name)) = name.split_once('/') else {
+                    continue;
+                };
+                if is_attempt_branch_name(name) {
+                    let locations = branches.entry(name.to_string()).or_default();
+                    locations.remote.get_or_insert(oid);
+                    locations.remotes.push(remote.to_string());
+                }
+            }
+            BranchType::Local => {}
+        }
+    }
+    Ok(branches)
+}
+
+/// Attempt branches, local or on a remote, that no attempt of any project on this repository
+/// uses. Ignored branches are left out unless `include_ignored` is set.
+pub async fn find_unknown_attempt_branches(
+    pool: &SqlitePool,
+    project: &Project,
+    include_ignored: bool,
+) -> Result<Vec<UnknownAttemptBranch>, TaskAttemptError> {
+    let known = TaskAttempt::branches_in_repo(pool, &project.git_repo_path).await?;
+    let ignored = IgnoredAttemptBranch::branches_for_project(pool, project.id).await?;
+
+    let base_branch = GitService::new(&project.git_repo_path)?.get_default_branch_name()?;
+    let repo = Repository::open(&project.git_repo_path)?;
+    let base = repo
+        .revparse_single(&base_branch)
+        .and_then(|object| object.peel_to_commit())
+        .ok();
+    let now = Utc::now();
+
+    let mut unknown = Vec::new();
+    for (branch, locations) in attempt_branches(&repo)? {
+        if known.contains(&branch) || (!include_ignored && ignored.contains(&branch)) {
+            continue;
+        }
+        let Some(oid) = locations.local.or(locations.remote) else {
+            continue;
+        };
+        let commit = repo.find_commit(oid)?;
+        let last_commit_at = Utc
+            .timestamp_opt(commit.time().seconds(), 0)
+            .single()
+            .unwrap_or(now);
+        let (ahead, behind) = match &base {
+            Some(base) => repo.graph_ahead_behind(oid, base.id())?,
+            None => (0, 0),
+        };
+
+        unknown.push(UnknownAttemptBranch {
+            local: locations.local.is_some(),
+            remotes: locations.remotes,
+            last_commit_sha: oid.to_string(),
+            last_commit_subject: commit.summary().unwrap_or_default().to_string(),
+            last_commit_at,
+            age_days: (now - last_commit_at).num_days().max(0) as u32,
+            base_branch: base_branch.clone(),
+            commits_ahead: ahead as u32,
+            commits_behind: behind as u32,
+            ignored: ignored.contains(&branch),
+            branch,
+        });
+    }
+    Ok(unknown)
+}
+
+async fn find_unknown_branch(
+    pool: &SqlitePool,
+    project: &Project,
+    branch: &str,
+) -> Result<UnknownAttemptBranch, TaskAttemptError> {
+    find_unknown_attempt_branches(pool, project, true)
+        .await?
+        .into_iter()
+        .find(|unknown| unknown.branch == branch)
+        .ok_or_else(|| {
+            TaskAttemptError::ValidationError(format!(
+                "{} is not an attempt branch without an attempt",
+                branch
+            ))
+        })
+}
+
+/// Title and description for a task adopted from a branch: the first commit subject on the
+/// branch becomes the title, falling back to the title part of the branch name
+fn adopted_task_details(
+    repo: &Repository,
+    branch: &UnknownAttemptBranch,
+) -> Result<(String, String), git2::Error> {
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    walk.push(Oid::from_str(&branch.last_commit_sha)?)?;
+    if let Ok(base) = repo.revparse_single(&branch.base_branch) {
+        walk.hide(base.peel_to_commit()?.id())?;
+    }
+    let mut subjects = Vec::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        subjects.push(commit.summary().unwrap_or_default().to_string());
+    }
+
+    let title = subjects
+        .first()
+        .filter(|subject| !subject.is_empty())
+        .cloned()
+        .unwrap_or_else(|| {
+            branch
+                .branch
+                .splitn(3, '-')
+                .nth(2)
+                .unwrap_or(&branch.branch)
+                .replace('-', " ")
+        });
+    let mut description = format!(
+        "Adopted from branch `{}`, last committed {}.",
+        branch.branch,
+        branch.last_commit_at.to_rfc3339()
+    );
+    if !subjects.is_empty() {
+        description.push_str("\n\nCommits:");
+        for subject in subjects.iter().take(MAX_ADOPTED_COMMIT_SUBJECTS) {
+            description.push_str("\n- ");
+            description.push_str(subject);
+        }
+        if subjects.len() > MAX_ADOPTED_COMMIT_SUBJECTS {
+            description.push_str(&format!(
+                "\n- ... and {} more",
+                subjects.len() - MAX_ADOPTED_COMMIT_SUBJECTS
+            ));
+        }
+    }
+    Ok((title, description))
+}
+
+/// Create a task in review with an attempt on an unknown branch, reconstructed from the
+/// branch's commits. A branch only found on a remote gets a local tracking branch first.
+pub async fn adopt_branch(
+    pool: &SqlitePool,
+    project: &Project,
+    branch: &str,
+    worktree_name_pattern: &str,
+) -> Result<AdoptedBranch, TaskAttemptError> {
+    let unknown = find_unknown_branch(pool, project, branch).await?;
+    let (title, description) = {
+        let repo = Repository::open(&project.git_repo_path)?;
+        if !unknown.local {
+            let commit = repo.find_commit(Oid::from_str(&unknown.last_commit_sha)?)?;
+            let mut local = repo.branch(branch, &commit, false)?;
+            if let Some(remote) = unknown.remotes.first() {
+                local.set_upstream(Some(&format!("{}/{}", remote, branch)))?;
+            }
+        }
+        adopted_task_details(&repo, &unknown)?
+    };
+
+    let task = Task::create(
+        pool,
+        &CreateTask {
+            project_id: project.id,
+            title,
+            description: Some(description),
+            estimate: None,
+        },
+        uuid::Uuid::new_v4(),
+    )
+    .await?;
+    Task::update_status(pool, task.id, project.id, TaskStatus::InReview).await?;
+    let attempt = TaskAttempt::adopt_branch(
+        pool,
+        &task,
+        branch,
+        &unknown.base_branch,
+        worktree_name_pattern,
+    )
+    .await?;
+    IgnoredAttemptBranch::forget(pool, project.id, branch).await?;
+
+    let task = Task::find_by_id(pool, task.id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+    Ok(AdoptedBranch { task, attempt })
+}
+
+/// Delete an unknown branch locally, and from the remotes carrying it when `remote` is set
+pub async fn delete_branch(
+    pool: &SqlitePool,
+    project: &Project,
+    branch: &str,
+    remote: bool,
+) -> Result<(), TaskAttemptError> {
+    let unknown = find_unknown_branch(pool, project, branch).await?;
+
+    if remote {
+        for remote_name in &unknown.remotes {
+            let output = tokio::process::Command::new("git")
+                .args(["push", remote_name, "--delete", branch])
+                .current_dir(&project.git_repo_path)
+                .output()
+                .await
+                .map_err(|e| {
+                    TaskAttemptError::ValidationError(format!("Failed to run git: {}", e))
+                })?;
+            if !output.status.success() {
+                return Err(TaskAttemptError::ValidationError(format!(
+                    "Failed to delete {} from {}: {}",
+                    branch,
+                    remote_name,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+        }
+    }
+    if unknown.local {
+        GitService::new(&project.git_repo_path)?.delete_branch(branch)?;
+    }
+    if unknown.local || remote {
+        IgnoredAttemptBranch::forget(pool, project.id, branch).await?;
+    }
+    Ok(())
+}
+
+/// Stop reporting an unknown branch for the project
+pub async fn ignore_branch(
+    pool: &SqlitePool,
+    project: &Project,
+    branch: &str,
+) -> Result<(), TaskAttemptError> {
+    find_unknown_branch(pool, project, branch).await?;
+    IgnoredAttemptBranch::ignore(pool, project.id, branch).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_utils::{insert_project, memory_pool};
+
+    #[test]
+    fn test_attempt_branch_names() {
+        assert!(is_attempt_branch_name("vk-1a2b-fix-login"));
+        assert!(!is_attempt_branch_name("vk-1a2g-fix-login"));
+        assert!(!is_attempt_branch_name("vk-1a2b3-fix"));
+        assert!(!is_attempt_branch_name("feature/vk-1a2b-fix"));
+        assert!(!is_attempt_branch_name("main"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_branch_is_listed_ignored_and_adopted() {
+        let pool = memory_pool().await;
+
+        let repo_dir = TempDir::new().unwrap();
+        let repo = Repository::init(repo_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let base = repo
+            .commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+        let base = repo.find_commit(base).unwrap();
+        let branch = "vk-1a2b-fix-login";
+        repo.commit(
+            Some(&format!("refs/heads/{}", branch)),
+            &signature,
+            &signature,
+            "Fix the login redirect",
+            &tree,
+            &[&base],
+        )
+        .unwrap();
+        repo.branch("feature", &base, false).unwrap();
+
+        let project_id = insert_project(&pool, &repo_dir.path().to_string_lossy()).await;
+        let project = Project::find_by_id(&pool, project_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let unknown = find_unknown_attempt_branches(&pool, &project, false)
+            .await
+            .unwrap();
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].branch, branch);
+        assert_eq!(unknown[0].commits_ahead, 1);
+        assert_eq!(unknown[0].commits_behind, 0);
+
+        ignore_branch(&pool, &project, branch).await.unwrap();
+        assert!(find_unknown_attempt_branches(&pool, &project, false)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let adopted = adopt_branch(&pool, &project, branch, "{attempt_id}")
+            .await
+            .unwrap();
+        assert_eq!(adopted.task.title, "Fix the login redirect");
+        assert_eq!(adopted.task.status, TaskStatus::InReview);
+        assert_eq!(adopted.attempt.branch, branch);
+        assert!(adopted.attempt.worktree_deleted);
+        assert!(find_unknown_attempt_branches(&pool, &project, true)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
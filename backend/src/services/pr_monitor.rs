@@ -1,7 +1,7 @@
-use std::{sync::Arc, time::Duration};
+use std::time::Duration;
 
 use sqlx::SqlitePool;
-use tokio::{sync::RwLock, time::interval};
+use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
@@ -11,7 +11,7 @@ use crate::{
         task::{Task, TaskStatus},
         task_attempt::TaskAttempt,
     },
-    services::{GitHubRepoInfo, GitHubService, GitService},
+    services::{provenance, GitHubRepoInfo, GitHubService, GitService},
 };
 
 /// Service to monitor GitHub PRs and update task status when they are merged
@@ -39,37 +39,33 @@ impl PrMonitorService {
         }
     }
 
-    /// Start the PR monitoring service with config
-    pub async fn start_with_config(&self, config: Arc<RwLock<Config>>) {
-        info!(
-            "Starting PR monitoring service with interval {:?}",
-            self.poll_interval
-        );
-
-        let mut interval = interval(self.poll_interval);
-
-        loop {
-            interval.tick().await;
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
 
-            // Get GitHub token from config
-            let github_token = {
-                let config_read = config.read().await;
-                if config_read.github.pat.is_some() {
-                    config_read.github.pat.clone()
-                } else {
-                    config_read.github.token.clone()
-                }
-            };
+    /// Whether a config update gave PR monitoring a new GitHub token, so open PRs that
+    /// couldn't be checked before should be checked right away
+    pub fn needs_check_after_config_change(old: &Config, new: &Config) -> bool {
+        let token = new.github.resolved_token();
+        token.is_some() && token != old.github.resolved_token()
+    }
 
-            match github_token {
-                Some(token) => {
-                    if let Err(e) = self.check_all_open_prs_with_token(&token).await {
-                        error!("Error checking PRs: {}", e);
-                    }
-                }
-                None => {
-                    debug!("No GitHub token configured, skipping PR monitoring");
-                }
+    /// Check open PRs once; run by the job scheduler every `poll_interval`
+    pub async fn check_with_config(&self, config: &RwLock<Config>) -> Result<(), String> {
+        // Get GitHub token from config
+        let github_token = config.read().await.github.resolved_token();
+
+        match github_token {
+            Some(token) => self
+                .check_all_open_prs_with_token(&token)
+                .await
+                .map_err(|e| {
+                    error!("Error checking PRs: {}", e);
+                    e.to_string()
+                }),
+            None => {
+                debug!("No GitHub token configured, skipping PR monitoring");
+                Ok(())
             }
         }
     }
@@ -192,23 +188,63 @@ impl PrMonitorService {
             )
             .await?;
 
+            // The PR's provenance stamp wins over the attempt recorded for it
+            let (attempt_id, task_id) = self
+                .stamped_attempt(pr_info, pr_status.body.as_deref())
+                .await?;
+            if attempt_id != pr_info.attempt_id {
+                TaskAttempt::update_pr_status(
+                    &self.pool,
+                    attempt_id,
+                    &pr_status.status,
+                    pr_status.merged_at,
+                    merge_commit_sha,
+                )
+                .await?;
+            }
+
             // If the PR was merged, update the task status to done
             if pr_status.merged {
                 info!(
                     "PR #{} was merged, updating task {} to done",
-                    pr_info.pr_number, pr_info.task_id
+                    pr_info.pr_number, task_id
                 );
 
-                Task::update_status(
-                    &self.pool,
-                    pr_info.task_id,
-                    pr_info.project_id,
-                    TaskStatus::Done,
-                )
-                .await?;
+                Task::update_status(&self.pool, task_id, pr_info.project_id, TaskStatus::Done)
+                    .await?;
             }
         }
 
         Ok(())
     }
+
+    /// Attempt and task named by the provenance stamp in a PR's description, when it names
+    /// an existing attempt of the project. The recorded attempt otherwise.
+    async fn stamped_attempt(
+        &self,
+        pr_info: &PrInfo,
+        body: Option<&str>,
+    ) -> Result<(Uuid, Uuid), sqlx::Error> {
+        let recorded = (pr_info.attempt_id, pr_info.task_id);
+        let Some(stamp) = body.and_then(provenance::parse) else {
+            return Ok(recorded);
+        };
+        if stamp.attempt_id == pr_info.attempt_id {
+            return Ok(recorded);
+        }
+
+        let Some(attempt) = TaskAttempt::find_by_id(&self.pool, stamp.attempt_id).await? else {
+            return Ok(recorded);
+        };
+        match Task::find_by_id(&self.pool, attempt.task_id).await? {
+            Some(task) if task.project_id == pr_info.project_id => {
+                warn!(
+                    "PR #{} is recorded for attempt {} but stamped with attempt {}, following the stamp",
+                    pr_info.pr_number, pr_info.attempt_id, attempt.id
+                );
+                Ok((attempt.id, task.id))
+            }
+            _ => Ok(recorded),
+        }
+    }
 }
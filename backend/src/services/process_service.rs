@@ -1,17 +1,32 @@
+use std::collections::HashMap;
+
 use sqlx::SqlitePool;
 use tracing::{debug, info};
 use uuid::Uuid;
 
 use crate::{
+    app_state::QueuedAttempt,
     executor::Executor,
     models::{
-        execution_process::{CreateExecutionProcess, ExecutionProcess, ExecutionProcessType},
+        bisect_run::BisectRun,
+        execution_process::{
+            CreateExecutionProcess, ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType,
+        },
         executor_session::{CreateExecutorSession, ExecutorSession},
+        format_run::FormatRun,
         project::Project,
         task::Task,
-        task_attempt::{TaskAttempt, TaskAttemptError, TaskAttemptStatus},
+        task_attempt::{
+            followup_diff_context, TaskAttempt, TaskAttemptError, TaskAttemptStatus,
+            FOLLOWUP_DIFF_SUMMARY_MAX_CHARS,
+        },
         task_attempt_activity::{CreateTaskAttemptActivity, TaskAttemptActivity},
     },
+    services::{
+        network_observer::{self, NetworkObserver},
+        prompt_budget::{compose_prompt, PromptBudgetReport, PromptSection, PromptSectionKind},
+        start_hook,
+    },
     utils::shell::get_shell_command,
 };
 
@@ -35,7 +50,8 @@ impl ProcessService {
         // Get project to check if setup script exists
         let project = Project::find_by_id(pool, project_id)
             .await?
-            .ok_or(TaskAttemptError::ProjectNotFound)?;
+            .ok_or(TaskAttemptError::ProjectNotFound)?
+            .with_repo_config();
 
         let needs_setup = Self::should_run_setup_script(&project) && !setup_completed;
 
@@ -67,8 +83,18 @@ impl ProcessService {
                         .and_then(|p| p.get("prompt"))
                         .and_then(|p| p.as_str())
                         .unwrap_or("");
+                    let prompt_budget = operation_params
+                        .as_ref()
+                        .and_then(|p| p.get("prompt_budget"))
+                        .and_then(|b| serde_json::from_value(b.clone()).ok());
                     Self::start_followup_execution_direct(
-                        pool, app_state, attempt_id, task_id, project_id, prompt,
+                        pool,
+                        app_state,
+                        attempt_id,
+                        task_id,
+                        project_id,
+                        prompt,
+                        prompt_budget,
                     )
                     .await
                     .map(|_| ())
@@ -164,7 +190,9 @@ impl ProcessService {
         Ok(())
     }
 
-    /// Start the execution flow for a task attempt (setup script + executor)
+    /// Start the execution flow for a task attempt (setup script + executor). When its project
+    /// already runs as many attempts as `max_concurrent_per_project` allows, the attempt is
+    /// queued and started by the execution monitor once a slot frees up.
     pub async fn start_execution(
         pool: &SqlitePool,
         app_state: &crate::app_state::AppState,
@@ -172,14 +200,71 @@ impl ProcessService {
         task_id: Uuid,
         project_id: Uuid,
     ) -> Result<(), TaskAttemptError> {
-        use crate::models::task::{Task, TaskStatus};
+        use crate::models::task::TaskStatus;
+
+        app_state.clear_attempt_cancellation(attempt_id);
 
         // Load required entities
+        let (task_attempt, _) = Self::load_execution_context(pool, attempt_id, project_id).await?;
+
+        // Update task status to indicate execution has started. Benchmark attempts replay a
+        // task that is already done and leave it alone.
+        if task_attempt.benchmark_run_id.is_none() {
+            app_state
+                .project_events
+                .update_task_status(pool, task_id, project_id, TaskStatus::InProgress)
+                .await?;
+            app_state
+                .project_events
+                .attempt_started(project_id, task_id, attempt_id);
+        }
+
+        let slot = QueuedAttempt {
+            attempt_id,
+            task_id,
+            project_id,
+        };
+        if !app_state.try_reserve_project_slot(slot).await {
+            info!(
+                "Queued task attempt {}: project {} is running as many attempts as allowed",
+                attempt_id, project_id
+            );
+            return Ok(());
+        }
+
+        let result =
+            Self::start_admitted_execution(pool, app_state, attempt_id, task_id, project_id).await;
+        app_state
+            .project_slot_started(attempt_id, result.is_ok())
+            .await;
+        result
+    }
+
+    /// Start the execution flow of an attempt given a slot in its project
+    pub async fn start_admitted_execution(
+        pool: &SqlitePool,
+        app_state: &crate::app_state::AppState,
+        attempt_id: Uuid,
+        task_id: Uuid,
+        project_id: Uuid,
+    ) -> Result<(), TaskAttemptError> {
+        use crate::models::task::Task;
+
         let (task_attempt, project) =
             Self::load_execution_context(pool, attempt_id, project_id).await?;
 
-        // Update task status to indicate execution has started
-        Task::update_status(pool, task_id, project_id, TaskStatus::InProgress).await?;
+        let hook = app_state
+            .get_config()
+            .read()
+            .await
+            .attempt_start_hook
+            .clone();
+        if hook.is_some() {
+            if let Some(task) = Task::find_by_id(pool, task_id).await? {
+                let context = start_hook::AttemptStartContext::new(&project, &task, &task_attempt);
+                start_hook::invoke(hook.as_ref(), &context).await;
+            }
+        }
 
         // Determine execution sequence based on project configuration
         if Self::should_run_setup_script(&project) {
@@ -233,6 +318,8 @@ impl ProcessService {
         task_id: Uuid,
         project_id: Uuid,
     ) -> Result<(), TaskAttemptError> {
+        app_state.clear_attempt_cancellation(attempt_id);
+
         // Ensure worktree exists (recreate if needed for cold task support)
         let _worktree_path =
             TaskAttempt::ensure_worktree_exists(pool, attempt_id, project_id, "dev server").await?;
@@ -265,7 +352,8 @@ impl ProcessService {
         // Get the project to access the dev_script
         let project = Project::find_by_id(pool, project_id)
             .await?
-            .ok_or(TaskAttemptError::TaskNotFound)?;
+            .ok_or(TaskAttemptError::TaskNotFound)?
+            .with_repo_config();
 
         let dev_script = project.dev_script.ok_or_else(|| {
             TaskAttemptError::ValidationError(
@@ -317,14 +405,24 @@ impl ProcessService {
         task_id: Uuid,
         project_id: Uuid,
         prompt: &str,
+        include_previous_diff: bool,
     ) -> Result<Uuid, TaskAttemptError> {
-        use crate::models::task::{Task, TaskStatus};
+        use crate::models::task::TaskStatus;
 
         // Get the current task attempt to check if worktree is deleted
         let current_attempt = TaskAttempt::find_by_id(pool, attempt_id)
             .await?
             .ok_or(TaskAttemptError::TaskNotFound)?;
 
+        // Fail before any setup script runs for a follow-up that can't start
+        app_state
+            .get_config()
+            .read()
+            .await
+            .check_executor_enabled(&Self::resolve_executor_config(&current_attempt.executor))
+            .map_err(TaskAttemptError::ExecutorUnavailable)?;
+        app_state.clear_attempt_cancellation(attempt_id);
+
         let actual_attempt_id = attempt_id;
 
         if current_attempt.worktree_deleted {
@@ -339,18 +437,54 @@ impl ProcessService {
             );
         }
 
-        // Update task status to indicate follow-up execution has started
-        Task::update_status(pool, task_id, project_id, TaskStatus::InProgress).await?;
+        let auto_recreate = app_state
+            .get_config()
+            .read()
+            .await
+            .auto_recreate_missing_worktrees;
 
         // Ensure worktree exists (recreate if needed for cold task support)
         // This will resurrect the worktree at the exact same path for session continuity
-        let _worktree_path =
-            TaskAttempt::ensure_worktree_exists(pool, actual_attempt_id, project_id, "followup")
-                .await?;
+        TaskAttempt::ensure_worktree_for_followup(
+            pool,
+            actual_attempt_id,
+            project_id,
+            auto_recreate,
+        )
+        .await?;
+
+        let (prompt, prompt_budget) = Self::compose_followup_prompt(
+            pool,
+            app_state,
+            &current_attempt,
+            task_id,
+            project_id,
+            prompt,
+            include_previous_diff,
+        )
+        .await;
+        if !prompt_budget.within_budget() {
+            return Err(TaskAttemptError::ValidationError(format!(
+                "The follow-up is about {} tokens, over the {} token budget of {} even after trimming its context. Shorten the prompt and try again.",
+                prompt_budget.estimated_tokens,
+                prompt_budget.budget,
+                Self::resolve_executor_config(&current_attempt.executor).display_name()
+            )));
+        }
+
+        // Update task status to indicate follow-up execution has started
+        app_state
+            .project_events
+            .update_task_status(pool, task_id, project_id, TaskStatus::InProgress)
+            .await?;
+        app_state
+            .project_events
+            .attempt_started(project_id, task_id, attempt_id);
 
         // Use automatic setup logic with followup parameters
         let operation_params = serde_json::json!({
-            "prompt": prompt
+            "prompt": prompt,
+            "prompt_budget": prompt_budget
         });
 
         Self::auto_setup_and_execute(
@@ -367,6 +501,68 @@ impl ProcessService {
         Ok(actual_attempt_id)
     }
 
+    /// Compose the prompt of a follow-up, trimmed to fit the token budget of the attempt's
+    /// executor. Optionally gives the agent the changes made so far, in case the session has
+    /// lost track of them.
+    pub async fn compose_followup_prompt(
+        pool: &SqlitePool,
+        app_state: &crate::app_state::AppState,
+        attempt: &TaskAttempt,
+        task_id: Uuid,
+        project_id: Uuid,
+        prompt: &str,
+        include_previous_diff: bool,
+    ) -> (String, PromptBudgetReport) {
+        let (rename_threshold, ignored_paths, budget) = {
+            let config = app_state.get_config().read().await;
+            (
+                config.diff_rename_threshold,
+                config.path_ignore(),
+                config.prompt_token_budget(&Self::resolve_executor_config(&attempt.executor)),
+            )
+        };
+
+        let mut sections = Vec::new();
+        if include_previous_diff {
+            match TaskAttempt::get_diff(
+                pool,
+                attempt.id,
+                task_id,
+                project_id,
+                rename_threshold,
+                ignored_paths,
+            )
+            .await
+            {
+                Ok(diff) => {
+                    if let Some(context) =
+                        followup_diff_context(&diff, FOLLOWUP_DIFF_SUMMARY_MAX_CHARS)
+                    {
+                        sections.push(PromptSection::new(
+                            PromptSectionKind::Diff,
+                            "Changes so far",
+                            context,
+                        ));
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to compute diff for follow-up context on attempt {}: {}",
+                        attempt.id,
+                        e
+                    );
+                }
+            }
+        }
+        sections.push(PromptSection::new(
+            PromptSectionKind::Instructions,
+            "Follow-up",
+            prompt,
+        ));
+
+        compose_prompt(&sections, budget)
+    }
+
     /// Start a follow-up execution directly without setup check (internal method)
     pub async fn start_followup_execution_direct(
         pool: &SqlitePool,
@@ -375,6 +571,7 @@ impl ProcessService {
         task_id: Uuid,
         project_id: Uuid,
         prompt: &str,
+        prompt_budget: Option<PromptBudgetReport>,
     ) -> Result<Uuid, TaskAttemptError> {
         // Ensure worktree exists (recreate if needed for cold task support)
         // This will resurrect the worktree at the exact same path for session continuity
@@ -416,13 +613,14 @@ impl ProcessService {
                 })?;
 
         // Determine the executor config from the stored executor_type
-        let executor_config = match most_recent_coding_agent.executor_type.as_deref() {
-            Some("claude") => crate::executor::ExecutorConfig::Claude,
-            Some("amp") => crate::executor::ExecutorConfig::Amp,
-            Some("gemini") => crate::executor::ExecutorConfig::Gemini,
-            Some("echo") => crate::executor::ExecutorConfig::Echo,
-            Some("opencode") => crate::executor::ExecutorConfig::Opencode,
-            _ => {
+        let executor_config = match most_recent_coding_agent
+            .executor_type
+            .as_deref()
+            .and_then(|name| name.parse::<crate::executor::ExecutorConfig>().ok())
+            .filter(|config| config.factory().is_some())
+        {
+            Some(config) => config,
+            None => {
                 tracing::error!(
                     "Invalid or missing executor type '{}' for execution process {} (task attempt {})",
                     most_recent_coding_agent.executor_type.as_deref().unwrap_or("None"),
@@ -450,6 +648,7 @@ impl ProcessService {
                 config: executor_config.clone(),
                 session_id: executor_session.session_id.clone(),
                 prompt: prompt.to_string(),
+                prompt_budget,
             }
         } else {
             // No session ID available, start new session
@@ -520,27 +719,63 @@ impl ProcessService {
         process_type: ExecutionProcessType,
         worktree_path: &str,
     ) -> Result<(), TaskAttemptError> {
+        if app_state.is_attempt_cancelled(attempt_id) {
+            info!(
+                "Not starting {:?} for task attempt {}: it was cancelled",
+                process_type, attempt_id
+            );
+            return Ok(());
+        }
+
+        let mut output_format = crate::executor::OutputFormat::Json;
+        if let crate::executor::ExecutorType::CodingAgent(config)
+        | crate::executor::ExecutorType::FollowUpCodingAgent { config, .. } = &executor_type
+        {
+            let app_config = app_state.get_config().read().await;
+            app_config
+                .check_executor_enabled(config)
+                .map_err(TaskAttemptError::ExecutorUnavailable)?;
+            output_format = app_config.output_format(config);
+        }
+
         let process_id = Uuid::new_v4();
 
+        // Dev servers have their own concurrency cap, separate from other executions
+        if matches!(process_type, ExecutionProcessType::DevServer) {
+            if let Err(limit) = app_state.try_reserve_dev_server(process_id).await {
+                return Err(TaskAttemptError::ValidationError(format!(
+                    "Maximum of {} concurrent dev server(s) reached. Stop a running dev server before starting another.",
+                    limit
+                )));
+            }
+        }
+
         // Create execution process record
-        let _execution_process = Self::create_execution_process_record(
+        if let Err(e) = Self::create_execution_process_record(
             pool,
             attempt_id,
             process_id,
             &executor_type,
             process_type.clone(),
             worktree_path,
+            output_format,
         )
-        .await?;
+        .await
+        {
+            app_state.release_dev_server(process_id).await;
+            return Err(e);
+        }
 
         // Create executor session for coding agents
         if matches!(process_type, ExecutionProcessType::CodingAgent) {
             // Extract follow-up prompt if this is a follow-up execution
-            let followup_prompt = match &executor_type {
-                crate::executor::ExecutorType::FollowUpCodingAgent { prompt, .. } => {
-                    Some(prompt.clone())
-                }
-                _ => None,
+            let (followup_prompt, prompt_budget) = match &executor_type {
+                crate::executor::ExecutorType::FollowUpCodingAgent {
+                    prompt,
+                    prompt_budget,
+                    ..
+                } => (Some(prompt.clone()), prompt_budget.clone()),
+                _ => (None, None),
             };
             Self::create_executor_session_record(
                 pool,
@@ -548,28 +783,77 @@ impl ProcessService {
                 task_id,
                 process_id,
                 followup_prompt,
+                prompt_budget,
             )
             .await?;
+
+            // Snapshot the files git doesn't track before the agent first touches them
+            let journal_ignore = {
+                let config = app_state.get_config().read().await;
+                config
+                    .untracked_change_journal
+                    .then(|| config.path_ignore())
+            };
+            if let Some(ignored) = journal_ignore {
+                if let Err(e) = crate::services::untracked_changes::record_baseline(
+                    pool,
+                    attempt_id,
+                    worktree_path,
+                    ignored,
+                )
+                .await
+                {
+                    tracing::warn!(
+                        "Failed to snapshot untracked files for attempt {}: {}",
+                        attempt_id,
+                        e
+                    );
+                }
+            }
         }
 
-        // Create activity record (skip for dev servers as they run in parallel)
-        if !matches!(process_type, ExecutionProcessType::DevServer) {
+        // Create activity record (skip for dev servers and bisect runs as they run in parallel)
+        if process_type.is_attempt_stage() {
             Self::create_activity_record(pool, process_id, activity_status.clone(), &activity_note)
                 .await?;
         }
 
         tracing::info!("Starting {} for task attempt {}", activity_note, attempt_id);
 
+        // Agents of projects observing network access talk through a logging proxy
+        let network_observer = match process_type {
+            ExecutionProcessType::CodingAgent => {
+                network_observer::start_for_agent(pool, attempt_id, process_id).await
+            }
+            _ => None,
+        };
+        let proxy_env = network_observer
+            .as_ref()
+            .map(NetworkObserver::proxy_env)
+            .unwrap_or_default();
+
         // Execute the process
-        let child = Self::execute_process(
+        let child = match Self::execute_process(
             &executor_type,
             pool,
             task_id,
             attempt_id,
             process_id,
             worktree_path,
+            output_format,
+            proxy_env,
         )
-        .await?;
+        .await
+        {
+            Ok(child) => child,
+            Err(e) => {
+                app_state.release_dev_server(process_id).await;
+                return Err(e);
+            }
+        };
+        if let Some(observer) = network_observer {
+            app_state.add_network_observer(process_id, observer).await;
+        }
 
         // Register for monitoring
         Self::register_for_monitoring(app_state, process_id, attempt_id, &process_type, child)
@@ -583,6 +867,143 @@ impl ProcessService {
         Ok(())
     }
 
+    /// Start a bisect run in its temporary worktree. Its process has the id of the run and,
+    /// unlike the attempt's own stages, no activity.
+    pub async fn start_bisect(
+        pool: &SqlitePool,
+        app_state: &crate::app_state::AppState,
+        run: &BisectRun,
+        task_id: Uuid,
+    ) -> Result<(), TaskAttemptError> {
+        let executor_type = crate::executor::ExecutorType::Bisect {
+            good_commit: run.good_commit.clone(),
+            bad_commit: run.bad_commit.clone(),
+            command: run.command.clone(),
+        };
+        let output_format = crate::executor::OutputFormat::Json;
+
+        Self::create_execution_process_record(
+            pool,
+            run.task_attempt_id,
+            run.id,
+            &executor_type,
+            ExecutionProcessType::Bisect,
+            &run.worktree_path,
+            output_format,
+        )
+        .await?;
+
+        let child = match Self::execute_process(
+            &executor_type,
+            pool,
+            task_id,
+            run.task_attempt_id,
+            run.id,
+            &run.worktree_path,
+            output_format,
+            HashMap::new(),
+        )
+        .await
+        {
+            Ok(child) => child,
+            Err(e) => {
+                ExecutionProcess::update_completion(
+                    pool,
+                    run.id,
+                    ExecutionProcessStatus::Failed,
+                    None,
+                )
+                .await?;
+                return Err(e);
+            }
+        };
+
+        Self::register_for_monitoring(
+            app_state,
+            run.id,
+            run.task_attempt_id,
+            &ExecutionProcessType::Bisect,
+            child,
+        )
+        .await;
+
+        tracing::info!(
+            "Started bisect run {} for task attempt {}",
+            run.id,
+            run.task_attempt_id
+        );
+        Ok(())
+    }
+
+    /// Start a format run in the attempt's worktree. Like a bisect run, its process has the id
+    /// of the run and no activity; the coding agent it follows is recorded as complete once it
+    /// ends.
+    pub async fn start_format_script(
+        pool: &SqlitePool,
+        app_state: &crate::app_state::AppState,
+        run: &FormatRun,
+        task_id: Uuid,
+        worktree_path: &str,
+    ) -> Result<(), TaskAttemptError> {
+        let executor_type = crate::executor::ExecutorType::FormatScript {
+            script: run.command.clone(),
+            files: run.files.clone(),
+        };
+        let output_format = crate::executor::OutputFormat::Json;
+
+        Self::create_execution_process_record(
+            pool,
+            run.task_attempt_id,
+            run.id,
+            &executor_type,
+            ExecutionProcessType::FormatScript,
+            worktree_path,
+            output_format,
+        )
+        .await?;
+
+        let child = match Self::execute_process(
+            &executor_type,
+            pool,
+            task_id,
+            run.task_attempt_id,
+            run.id,
+            worktree_path,
+            output_format,
+            HashMap::new(),
+        )
+        .await
+        {
+            Ok(child) => child,
+            Err(e) => {
+                ExecutionProcess::update_completion(
+                    pool,
+                    run.id,
+                    ExecutionProcessStatus::Failed,
+                    None,
+                )
+                .await?;
+                return Err(e);
+            }
+        };
+
+        Self::register_for_monitoring(
+            app_state,
+            run.id,
+            run.task_attempt_id,
+            &ExecutionProcessType::FormatScript,
+            child,
+        )
+        .await;
+
+        tracing::info!(
+            "Started format run {} for task attempt {}",
+            run.id,
+            run.task_attempt_id
+        );
+        Ok(())
+    }
+
     /// Load the execution context (task attempt and project) with validation
     async fn load_execution_context(
         pool: &SqlitePool,
@@ -595,7 +1016,8 @@ impl ProcessService {
 
         let project = Project::find_by_id(pool, project_id)
             .await?
-            .ok_or(TaskAttemptError::ProjectNotFound)?;
+            .ok_or(TaskAttemptError::ProjectNotFound)?
+            .with_repo_config();
 
         Ok((task_attempt, project))
     }
@@ -635,14 +1057,14 @@ impl ProcessService {
     }
 
     /// Resolve executor configuration from string name
-    fn resolve_executor_config(executor_name: &Option<String>) -> crate::executor::ExecutorConfig {
-        match executor_name.as_ref().map(|s| s.as_str()) {
-            Some("claude") => crate::executor::ExecutorConfig::Claude,
-            Some("amp") => crate::executor::ExecutorConfig::Amp,
-            Some("gemini") => crate::executor::ExecutorConfig::Gemini,
-            Some("opencode") => crate::executor::ExecutorConfig::Opencode,
-            _ => crate::executor::ExecutorConfig::Echo, // Default for "echo" or None
-        }
+    pub fn resolve_executor_config(
+        executor_name: &Option<String>,
+    ) -> crate::executor::ExecutorConfig {
+        executor_name
+            .as_deref()
+            .and_then(|name| name.parse::<crate::executor::ExecutorConfig>().ok())
+            .filter(|config| config.factory().is_some())
+            .unwrap_or(crate::executor::ExecutorConfig::Echo) // Default for "echo" or None
     }
 
     /// Create execution process database record
@@ -653,6 +1075,7 @@ impl ProcessService {
         executor_type: &crate::executor::ExecutorType,
         process_type: ExecutionProcessType,
         worktree_path: &str,
+        output_format: crate::executor::OutputFormat,
     ) -> Result<ExecutionProcess, TaskAttemptError> {
         let (shell_cmd, shell_arg) = get_shell_command();
         let (command, args, executor_type_string) = match executor_type {
@@ -666,36 +1089,26 @@ impl ProcessService {
                 Some(serde_json::to_string(&[shell_arg, "dev_server"]).unwrap()),
                 None, // Dev servers don't have an executor type
             ),
-            crate::executor::ExecutorType::CodingAgent(config) => {
-                let executor_type_str = match config {
-                    crate::executor::ExecutorConfig::Echo => "echo",
-                    crate::executor::ExecutorConfig::Claude => "claude",
-                    crate::executor::ExecutorConfig::Amp => "amp",
-                    crate::executor::ExecutorConfig::Gemini => "gemini",
-                    crate::executor::ExecutorConfig::Opencode => "opencode",
-                    crate::executor::ExecutorConfig::SetupScript { .. } => "setup_script",
-                };
-                (
-                    "executor".to_string(),
-                    None,
-                    Some(executor_type_str.to_string()),
-                )
-            }
-            crate::executor::ExecutorType::FollowUpCodingAgent { config, .. } => {
-                let executor_type_str = match config {
-                    crate::executor::ExecutorConfig::Echo => "echo",
-                    crate::executor::ExecutorConfig::Claude => "claude",
-                    crate::executor::ExecutorConfig::Amp => "amp",
-                    crate::executor::ExecutorConfig::Gemini => "gemini",
-                    crate::executor::ExecutorConfig::Opencode => "opencode",
-                    crate::executor::ExecutorConfig::SetupScript { .. } => "setup_script",
-                };
-                (
-                    "followup_executor".to_string(),
-                    None,
-                    Some(executor_type_str.to_string()),
-                )
-            }
+            crate::executor::ExecutorType::Bisect { .. } => (
+                "sh".to_string(),
+                Some(serde_json::to_string(&["-c", "bisect"]).unwrap()),
+                None,
+            ),
+            crate::executor::ExecutorType::FormatScript { .. } => (
+                "sh".to_string(),
+                Some(serde_json::to_string(&["-c", "format_script"]).unwrap()),
+                None,
+            ),
+            crate::executor::ExecutorType::CodingAgent(config) => (
+                "executor".to_string(),
+                output_format.process_args(),
+                Some(config.to_string()),
+            ),
+            crate::executor::ExecutorType::FollowUpCodingAgent { config, .. } => (
+                "followup_executor".to_string(),
+                output_format.process_args(),
+                Some(config.to_string()),
+            ),
         };
 
         let create_process = CreateExecutionProcess {
@@ -719,14 +1132,18 @@ impl ProcessService {
         task_id: Uuid,
         process_id: Uuid,
         followup_prompt: Option<String>,
+        prompt_budget: Option<PromptBudgetReport>,
     ) -> Result<(), TaskAttemptError> {
-        // Use follow-up prompt if provided, otherwise get the task to create prompt
+        let task = Task::find_by_id(pool, task_id)
+            .await?
+            .ok_or(TaskAttemptError::TaskNotFound)?;
+        let response_language =
+            crate::executor::resolve_response_language(pool, task.project_id).await;
+
+        // Use follow-up prompt if provided, otherwise create prompt from the task
         let prompt = if let Some(followup_prompt) = followup_prompt {
             followup_prompt
         } else {
-            let task = Task::find_by_id(pool, task_id)
-                .await?
-                .ok_or(TaskAttemptError::TaskNotFound)?;
             format!("{}\n\n{}", task.title, task.description.unwrap_or_default())
         };
 
@@ -735,6 +1152,8 @@ impl ProcessService {
             task_attempt_id: attempt_id,
             execution_process_id: process_id,
             prompt: Some(prompt),
+            response_language,
+            prompt_budget,
         };
 
         ExecutorSession::create(pool, &create_session, session_id)
@@ -763,7 +1182,9 @@ impl ProcessService {
             .map_err(TaskAttemptError::from)
     }
 
-    /// Execute the process based on type
+    /// Execute the process based on type, with the attempt's own environment variables. Those
+    /// win over the `defaults`, like the variables of a network observer.
+    #[allow(clippy::too_many_arguments)]
     async fn execute_process(
         executor_type: &crate::executor::ExecutorType,
         pool: &SqlitePool,
@@ -771,8 +1192,39 @@ impl ProcessService {
         attempt_id: Uuid,
         process_id: Uuid,
         worktree_path: &str,
+        output_format: crate::executor::OutputFormat,
+        defaults: HashMap<String, String>,
     ) -> Result<command_group::AsyncGroupChild, TaskAttemptError> {
-        use crate::executors::{DevServerExecutor, SetupScriptExecutor};
+        let mut env = defaults;
+        env.extend(TaskAttempt::find_env(pool, attempt_id).await?);
+        crate::executor::with_attempt_env(
+            env,
+            Self::spawn_process(
+                executor_type,
+                pool,
+                task_id,
+                attempt_id,
+                process_id,
+                worktree_path,
+                output_format,
+            ),
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn spawn_process(
+        executor_type: &crate::executor::ExecutorType,
+        pool: &SqlitePool,
+        task_id: Uuid,
+        attempt_id: Uuid,
+        process_id: Uuid,
+        worktree_path: &str,
+        output_format: crate::executor::OutputFormat,
+    ) -> Result<command_group::AsyncGroupChild, TaskAttemptError> {
+        use crate::executors::{
+            BisectExecutor, DevServerExecutor, FormatScriptExecutor, SetupScriptExecutor,
+        };
 
         let result = match executor_type {
             crate::executor::ExecutorType::SetupScript(script) => {
@@ -791,8 +1243,31 @@ impl ProcessService {
                     .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
                     .await
             }
+            crate::executor::ExecutorType::Bisect {
+                good_commit,
+                bad_commit,
+                command,
+            } => {
+                let executor = BisectExecutor {
+                    good_commit: good_commit.clone(),
+                    bad_commit: bad_commit.clone(),
+                    command: command.clone(),
+                };
+                executor
+                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
+                    .await
+            }
+            crate::executor::ExecutorType::FormatScript { script, files } => {
+                let executor = FormatScriptExecutor {
+                    script: script.clone(),
+                    files: files.clone(),
+                };
+                executor
+                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
+                    .await
+            }
             crate::executor::ExecutorType::CodingAgent(config) => {
-                let executor = config.create_executor();
+                let executor = config.create_executor_with_output_format(output_format);
                 executor
                     .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
                     .await
@@ -801,59 +1276,34 @@ impl ProcessService {
                 config,
                 session_id,
                 prompt,
+                ..
             } => {
-                use crate::executors::{
-                    AmpFollowupExecutor, ClaudeFollowupExecutor, GeminiFollowupExecutor,
-                    OpencodeFollowupExecutor,
-                };
+                let task = Task::find_by_id(pool, task_id)
+                    .await?
+                    .ok_or(TaskAttemptError::TaskNotFound)?;
+                let response_language =
+                    crate::executor::resolve_response_language(pool, task.project_id).await;
+                let prompt = crate::executor::with_prompt_template(
+                    prompt,
+                    crate::executor::PromptKind::FollowUp,
+                );
+                let prompt =
+                    crate::executor::with_response_language(&prompt, response_language.as_deref());
 
-                let executor: Box<dyn crate::executor::Executor> = match config {
-                    crate::executor::ExecutorConfig::Claude => {
-                        if let Some(sid) = session_id {
-                            Box::new(ClaudeFollowupExecutor {
-                                session_id: sid.clone(),
-                                prompt: prompt.clone(),
-                            })
-                        } else {
-                            return Err(TaskAttemptError::TaskNotFound); // No session ID for followup
-                        }
-                    }
-                    crate::executor::ExecutorConfig::Amp => {
-                        if let Some(tid) = session_id {
-                            Box::new(AmpFollowupExecutor {
-                                thread_id: tid.clone(),
-                                prompt: prompt.clone(),
-                            })
-                        } else {
-                            return Err(TaskAttemptError::TaskNotFound); // No thread ID for followup
-                        }
-                    }
-                    crate::executor::ExecutorConfig::Gemini => {
-                        // For Gemini, we don't use real session IDs, we pass the context directly
-                        Box::new(GeminiFollowupExecutor {
+                let executor = match config.factory() {
+                    Some(factory) => {
+                        factory.create_followup(crate::executors::registry::FollowUp {
                             attempt_id,
-                            prompt: prompt.clone(),
+                            session_id: session_id.clone(),
+                            prompt,
+                            output_format,
                         })
                     }
-                    crate::executor::ExecutorConfig::Echo => {
-                        // Echo doesn't support followup, use regular echo
-                        config.create_executor()
-                    }
-                    crate::executor::ExecutorConfig::Opencode => {
-                        if let Some(sid) = session_id {
-                            Box::new(OpencodeFollowupExecutor {
-                                session_id: sid.clone(),
-                                prompt: prompt.clone(),
-                            })
-                        } else {
-                            return Err(TaskAttemptError::TaskNotFound); // No session ID for followup
-                        }
-                    }
-                    crate::executor::ExecutorConfig::SetupScript { .. } => {
-                        // Setup scripts don't support followup, use regular setup script
-                        config.create_executor()
-                    }
+                    // Setup scripts don't support followup, use regular setup script
+                    None => Some(config.create_executor()),
                 };
+                // No session ID for followup
+                let executor = executor.ok_or(TaskAttemptError::TaskNotFound)?;
 
                 executor
                     .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
@@ -876,6 +1326,8 @@ impl ProcessService {
             ExecutionProcessType::SetupScript => crate::app_state::ExecutionType::SetupScript,
             ExecutionProcessType::CodingAgent => crate::app_state::ExecutionType::CodingAgent,
             ExecutionProcessType::DevServer => crate::app_state::ExecutionType::DevServer,
+            ExecutionProcessType::Bisect => crate::app_state::ExecutionType::Bisect,
+            ExecutionProcessType::FormatScript => crate::app_state::ExecutionType::FormatScript,
         };
 
         app_state
@@ -883,7 +1335,8 @@ impl ProcessService {
                 process_id,
                 crate::app_state::RunningExecution {
                     task_attempt_id: attempt_id,
-                    _execution_type: execution_type,
+                    execution_type: execution_type,
+                    paused: false,
                     child,
                 },
             )
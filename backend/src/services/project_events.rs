@@ -0,0 +1,438 @@
+//! Changes to the tasks and attempts of each project, for clients that follow a project
+//! instead of polling its task list.
+//!
+//! Code with an `AppState` publishes its changes as it makes them. Tasks are also changed
+//! where there's none, most notably by the MCP task server, which runs in its own process, so
+//! the `project_event_sync` job compares every task's status and version with what was last
+//! published and publishes the differences. Each task change is published once either way.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::models::task::{Task, TaskStatus, TaskVersion};
+
+/// Name of the background job that publishes task changes made outside this process
+pub const SYNC_JOB: &str = "project_event_sync";
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Events kept for clients that reconnect, across all projects
+const RECENT_EVENTS: usize = 1000;
+/// Events kept for slow subscribers before they start missing some
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// What changed in a project
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export)]
+pub enum ProjectChange {
+    TaskCreated {
+        task_id: Uuid,
+        status: TaskStatus,
+    },
+    /// Anything but the status changed
+    TaskUpdated {
+        task_id: Uuid,
+        status: TaskStatus,
+    },
+    TaskStatusChanged {
+        task_id: Uuid,
+        old_status: TaskStatus,
+        new_status: TaskStatus,
+    },
+    TaskDeleted {
+        task_id: Uuid,
+    },
+    /// The attempt started running, or a follow-up of it did
+    AttemptStarted {
+        task_id: Uuid,
+        attempt_id: Uuid,
+    },
+    /// Setup failed or the coding agent ended
+    AttemptFinished {
+        task_id: Uuid,
+        attempt_id: Uuid,
+        success: bool,
+    },
+    /// The attempt's branch status was recomputed, or failed to be
+    BranchStatusRefreshed {
+        task_id: Uuid,
+        attempt_id: Uuid,
+        success: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectEvent {
+    /// Increases by one with every event, starting over when the server restarts
+    pub id: u64,
+    pub project_id: Uuid,
+    pub change: ProjectChange,
+}
+
+/// The events of a project a client missed, and its events from then on
+pub struct ProjectEventSubscription {
+    pub missed: Vec<ProjectEvent>,
+    /// Some of the missed events are no longer kept, so the client should reload the project
+    pub gap: bool,
+    /// Every project's events; receivers that fall behind get `RecvError::Lagged`
+    pub events: broadcast::Receiver<ProjectEvent>,
+}
+
+#[derive(Debug, Clone)]
+struct KnownTask {
+    project_id: Uuid,
+    status: TaskStatus,
+    version: i64,
+}
+
+#[derive(Debug)]
+struct EventLog {
+    next_id: u64,
+    recent: VecDeque<ProjectEvent>,
+    /// Every task as last published, to tell what kind of change a task went through
+    tasks: HashMap<Uuid, KnownTask>,
+    /// Whether `tasks` was loaded from the database yet; until then there's no telling a
+    /// new task from one that changed
+    synced: bool,
+}
+
+impl EventLog {
+    fn publish(
+        &mut self,
+        sender: &broadcast::Sender<ProjectEvent>,
+        project_id: Uuid,
+        change: ProjectChange,
+    ) {
+        let event = ProjectEvent {
+            id: self.next_id,
+            project_id,
+            change,
+        };
+        self.next_id += 1;
+        if self.recent.len() == RECENT_EVENTS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(event.clone());
+        // Fails only when nobody is subscribed
+        let _ = sender.send(event);
+    }
+
+    /// Publish how `task` changed since it was last published, if it did
+    fn task_changed(&mut self, sender: &broadcast::Sender<ProjectEvent>, task: TaskVersion) {
+        let change = match self.tasks.get(&task.id) {
+            // Already published, or a read from before the change that was
+            Some(known) if known.version >= task.version => return,
+            Some(known) if known.status != task.status => ProjectChange::TaskStatusChanged {
+                task_id: task.id,
+                old_status: known.status.clone(),
+                new_status: task.status.clone(),
+            },
+            Some(_) => ProjectChange::TaskUpdated {
+                task_id: task.id,
+                status: task.status.clone(),
+            },
+            None if self.synced => ProjectChange::TaskCreated {
+                task_id: task.id,
+                status: task.status.clone(),
+            },
+            None => ProjectChange::TaskUpdated {
+                task_id: task.id,
+                status: task.status.clone(),
+            },
+        };
+        self.tasks.insert(
+            task.id,
+            KnownTask {
+                project_id: task.project_id,
+                status: task.status,
+                version: task.version,
+            },
+        );
+        self.publish(sender, task.project_id, change);
+    }
+}
+
+/// Publishes project changes to subscribers and keeps the latest for replay
+#[derive(Debug, Clone)]
+pub struct ProjectEventBus {
+    log: Arc<Mutex<EventLog>>,
+    sender: broadcast::Sender<ProjectEvent>,
+}
+
+impl Default for ProjectEventBus {
+    fn default() -> Self {
+        Self {
+            log: Arc::new(Mutex::new(EventLog {
+                next_id: 1,
+                recent: VecDeque::new(),
+                tasks: HashMap::new(),
+                synced: false,
+            })),
+            sender: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl ProjectEventBus {
+    /// Follow a project's events, starting with those after `last_event_id`. Taken under the
+    /// same lock as publishing, so nothing falls between the replay and the first event.
+    pub fn subscribe(
+        &self,
+        project_id: Uuid,
+        last_event_id: Option<u64>,
+    ) -> ProjectEventSubscription {
+        let log = self.log.lock().unwrap();
+        let (missed, gap) = match last_event_id {
+            Some(last_event_id) => {
+                let oldest_kept = log.recent.front().map_or(log.next_id, |event| event.id);
+                let missed = log
+                    .recent
+                    .iter()
+                    .filter(|event| event.id > last_event_id && event.project_id == project_id)
+                    .cloned()
+                    .collect();
+                // An id this server hasn't given out yet is from before a restart
+                (
+                    missed,
+                    last_event_id.saturating_add(1) < oldest_kept || last_event_id >= log.next_id,
+                )
+            }
+            None => (Vec::new(), false),
+        };
+        ProjectEventSubscription {
+            missed,
+            gap,
+            events: self.sender.subscribe(),
+        }
+    }
+
+    /// Publish a task that was created or updated
+    pub fn task_changed(&self, task: &Task) {
+        let version = TaskVersion {
+            id: task.id,
+            project_id: task.project_id,
+            status: task.status.clone(),
+            version: task.version,
+        };
+        self.log.lock().unwrap().task_changed(&self.sender, version);
+    }
+
+    pub fn task_deleted(&self, project_id: Uuid, task_id: Uuid) {
+        let mut log = self.log.lock().unwrap();
+        // Already published by a sync
+        if log.tasks.remove(&task_id).is_none() && log.synced {
+            return;
+        }
+        log.publish(
+            &self.sender,
+            project_id,
+            ProjectChange::TaskDeleted { task_id },
+        );
+    }
+
+    pub fn attempt_started(&self, project_id: Uuid, task_id: Uuid, attempt_id: Uuid) {
+        self.log.lock().unwrap().publish(
+            &self.sender,
+            project_id,
+            ProjectChange::AttemptStarted {
+                task_id,
+                attempt_id,
+            },
+        );
+    }
+
+    pub fn attempt_finished(
+        &self,
+        project_id: Uuid,
+        task_id: Uuid,
+        attempt_id: Uuid,
+        success: bool,
+    ) {
+        self.log.lock().unwrap().publish(
+            &self.sender,
+            project_id,
+            ProjectChange::AttemptFinished {
+                task_id,
+                attempt_id,
+                success,
+            },
+        );
+    }
+
+    pub fn branch_status_refreshed(
+        &self,
+        project_id: Uuid,
+        task_id: Uuid,
+        attempt_id: Uuid,
+        success: bool,
+    ) {
+        self.log.lock().unwrap().publish(
+            &self.sender,
+            project_id,
+            ProjectChange::BranchStatusRefreshed {
+                task_id,
+                attempt_id,
+                success,
+            },
+        );
+    }
+
+    /// `Task::update_status`, published
+    pub async fn update_task_status(
+        &self,
+        pool: &SqlitePool,
+        task_id: Uuid,
+        project_id: Uuid,
+        status: TaskStatus,
+    ) -> Result<(), sqlx::Error> {
+        Task::update_status(pool, task_id, project_id, status).await?;
+        if let Some(task) = Task::find_by_id(pool, task_id).await? {
+            self.task_changed(&task);
+        }
+        Ok(())
+    }
+
+    /// Publish the task changes made without publishing them, like those of the MCP task
+    /// server. The first sync only loads the tasks as they are.
+    pub async fn sync_tasks(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        // Tasks published before the query can only be reported deleted if it misses them
+        let known_before: HashSet<Uuid> = self.log.lock().unwrap().tasks.keys().copied().collect();
+        let tasks = Task::find_all_versions(pool).await?;
+
+        let mut log = self.log.lock().unwrap();
+        if !log.synced {
+            for task in tasks {
+                log.tasks.entry(task.id).or_insert(KnownTask {
+                    project_id: task.project_id,
+                    status: task.status,
+                    version: task.version,
+                });
+            }
+            log.synced = true;
+            return Ok(());
+        }
+
+        let found: HashSet<Uuid> = tasks.iter().map(|task| task.id).collect();
+        for task in tasks {
+            log.task_changed(&self.sender, task);
+        }
+        for task_id in known_before.difference(&found) {
+            if let Some(known) = log.tasks.remove(task_id) {
+                log.publish(
+                    &self.sender,
+                    known.project_id,
+                    ProjectChange::TaskDeleted { task_id: *task_id },
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::task::CreateTask,
+        test_utils::{insert_project, memory_pool},
+    };
+
+    fn changes(events: &[ProjectEvent]) -> Vec<ProjectChange> {
+        events.iter().map(|event| event.change.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_published_once_whoever_makes_them() {
+        let pool = memory_pool().await;
+        let project_id = insert_project(&pool, "/tmp/p").await;
+        let other_project_id = insert_project(&pool, "/tmp/q").await;
+        let create = |project_id, title: &str| CreateTask {
+            project_id,
+            title: title.to_string(),
+            description: None,
+            estimate: None,
+        };
+        let existing = Task::create(&pool, &create(project_id, "existing"), Uuid::new_v4())
+            .await
+            .unwrap();
+
+        let bus = ProjectEventBus::default();
+        bus.sync_tasks(&pool).await.unwrap();
+        let mut subscription = bus.subscribe(project_id, None);
+        assert!(subscription.missed.is_empty());
+
+        // Published by the code making the change, and not again by the next sync
+        let task = Task::create(&pool, &create(project_id, "new"), Uuid::new_v4())
+            .await
+            .unwrap();
+        bus.task_changed(&task);
+        bus.update_task_status(&pool, task.id, project_id, TaskStatus::InProgress)
+            .await
+            .unwrap();
+        let attempt_id = Uuid::new_v4();
+        bus.attempt_started(project_id, task.id, attempt_id);
+        Task::create(
+            &pool,
+            &create(other_project_id, "elsewhere"),
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        bus.sync_tasks(&pool).await.unwrap();
+
+        // Changed in another process, so only the sync sees them
+        Task::update_status(&pool, existing.id, project_id, TaskStatus::InReview)
+            .await
+            .unwrap();
+        Task::delete(&pool, task.id, project_id).await.unwrap();
+        bus.sync_tasks(&pool).await.unwrap();
+        bus.sync_tasks(&pool).await.unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(event) = subscription.events.try_recv() {
+            if event.project_id == project_id {
+                received.push(event);
+            }
+        }
+        assert_eq!(
+            changes(&received),
+            vec![
+                ProjectChange::TaskCreated {
+                    task_id: task.id,
+                    status: TaskStatus::Todo,
+                },
+                ProjectChange::TaskStatusChanged {
+                    task_id: task.id,
+                    old_status: TaskStatus::Todo,
+                    new_status: TaskStatus::InProgress,
+                },
+                ProjectChange::AttemptStarted {
+                    task_id: task.id,
+                    attempt_id,
+                },
+                ProjectChange::TaskStatusChanged {
+                    task_id: existing.id,
+                    old_status: TaskStatus::Todo,
+                    new_status: TaskStatus::InReview,
+                },
+                ProjectChange::TaskDeleted { task_id: task.id },
+            ]
+        );
+
+        // Reconnecting replays this project's events after the last one seen
+        let replay = bus.subscribe(project_id, Some(received[2].id));
+        assert!(!replay.gap);
+        assert_eq!(changes(&replay.missed), changes(&received[3..]));
+        // Ids from before a restart can't be replayed from
+        assert!(bus.subscribe(project_id, Some(received[4].id + 100)).gap);
+    }
+}
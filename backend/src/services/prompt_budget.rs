@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Sections this small are dropped outright rather than truncated to a stub
+const MIN_TRUNCATED_SECTION_TOKENS: u32 = 64;
+
+/// What a section of an auto-composed prompt holds, which decides how readily it is trimmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum PromptSectionKind {
+    Instructions,
+    AcceptanceCriteria,
+    ToolOutput,
+    Comment,
+    History,
+    Diff,
+}
+
+impl PromptSectionKind {
+    /// Order in which sections are trimmed, lowest first; `None` for sections that are
+    /// always kept whole
+    fn trim_priority(self) -> Option<u8> {
+        match self {
+            PromptSectionKind::Instructions | PromptSectionKind::AcceptanceCriteria => None,
+            PromptSectionKind::ToolOutput => Some(0),
+            PromptSectionKind::Comment => Some(1),
+            PromptSectionKind::History => Some(2),
+            PromptSectionKind::Diff => Some(3),
+        }
+    }
+}
+
+/// A part of a prompt, in the order it is sent. Comments and history go oldest first, as
+/// earlier sections of the same kind are trimmed first.
+#[derive(Debug, Clone)]
+pub struct PromptSection {
+    pub kind: PromptSectionKind,
+    pub label: String,
+    pub text: String,
+}
+
+impl PromptSection {
+    pub fn new(kind: PromptSectionKind, label: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            label: label.into(),
+            text: text.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum TrimAction {
+    /// The middle of the section was cut, keeping its start and end
+    Truncated,
+    /// The section was replaced by a note that it was left out
+    Dropped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TrimDecision {
+    pub label: String,
+    pub kind: PromptSectionKind,
+    pub action: TrimAction,
+    pub original_tokens: u32,
+    pub kept_tokens: u32,
+}
+
+/// How a prompt was fitted into the executor's token budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PromptBudgetReport {
+    pub budget: u32,
+    /// Estimate for the prompt as composed, before any trimming
+    pub original_tokens: u32,
+    /// Estimate for the prompt that is sent
+    pub estimated_tokens: u32,
+    pub trims: Vec<TrimDecision>,
+}
+
+impl PromptBudgetReport {
+    pub fn within_budget(&self) -> bool {
+        self.estimated_tokens <= self.budget
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF // Hangul Jamo
+        | 0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF // Hangul Syllables
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0xFF00..=0xFFEF // Fullwidth forms
+    )
+}
+
+/// Cheap token estimate: about four characters per token for English prose, with
+/// punctuation-heavy text like code and diffs, other scripts, and CJK text costing more.
+/// Deliberately errs on the high side, as undershooting is what makes the CLI fail.
+pub fn estimate_tokens(text: &str) -> u32 {
+    // Counted in quarter tokens
+    let quarters: u64 = text
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c.is_ascii_whitespace() {
+                1
+            } else if c.is_ascii() || !is_cjk(c) {
+                2
+            } else {
+                4
+            }
+        })
+        .sum();
+    quarters.div_ceil(4).min(u32::MAX as u64) as u32
+}
+
+/// Keep the first and last lines of `text` that fit in about `max_tokens`, replacing the
+/// middle with a marker saying how many lines were left out
+fn truncate_middle(text: &str, max_tokens: u32) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let half = max_tokens / 2;
+
+    let mut head = 0;
+    let mut head_tokens = 0;
+    while head < lines.len() {
+        let tokens = estimate_tokens(lines[head]) + 1;
+        if head_tokens + tokens > half {
+            break;
+        }
+        head_tokens += tokens;
+        head += 1;
+    }
+
+    let mut tail = lines.len();
+    let mut tail_tokens = 0;
+    while tail > head {
+        let tokens = estimate_tokens(lines[tail - 1]) + 1;
+        if tail_tokens + tokens > half {
+            break;
+        }
+        tail_tokens += tokens;
+        tail -= 1;
+    }
+
+    if head == 0 && tail == lines.len() {
+        // Not even one line fits, e.g. minified output; cut by characters instead
+        let keep = (max_tokens as usize * 4).min(text.len());
+        let mut cut = keep;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        return format!("{}\n[... truncated ...]", &text[..cut]);
+    }
+
+    let mut truncated = lines[..head].join("\n");
+    truncated.push_str(&format!("\n[... {} lines omitted ...]\n", tail - head));
+    truncated.push_str(&lines[tail..].join("\n"));
+    truncated
+}
+
+fn join_sections(sections: &[PromptSection]) -> String {
+    sections
+        .iter()
+        .map(|section| section.text.as_str())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Join the sections into a prompt, trimming sections when the estimate is over `budget`.
+/// Tool output goes first, then comments, history and finally the diff, oldest first within
+/// each kind. Instructions and acceptance criteria are never trimmed, so the result can still
+/// be over budget when they alone exceed it.
+pub fn compose_prompt(sections: &[PromptSection], budget: u32) -> (String, PromptBudgetReport) {
+    let mut sections = sections.to_vec();
+    let original_tokens = estimate_tokens(&join_sections(&sections));
+    let mut estimated_tokens = original_tokens;
+
+    let mut order: Vec<(u8, usize)> = sections
+        .iter()
+        .enumerate()
+        .filter_map(|(index, section)| Some((section.kind.trim_priority()?, index)))
+        .collect();
+    order.sort();
+
+    let mut trims = Vec::new();
+    for (_, index) in order {
+        if estimated_tokens <= budget {
+            break;
+        }
+        let section = &mut sections[index];
+        let tokens = estimate_tokens(&section.text);
+        let target = tokens.saturating_sub(estimated_tokens - budget);
+
+        let (action, text) = if target < MIN_TRUNCATED_SECTION_TOKENS {
+            (
+                TrimAction::Dropped,
+                format!("[{} omitted to fit the context budget]", section.label),
+            )
+        } else {
+            (
+                TrimAction::Truncated,
+                truncate_middle(&section.text, target),
+            )
+        };
+        section.text = text;
+        trims.push(TrimDecision {
+            label: section.label.clone(),
+            kind: section.kind,
+            action,
+            original_tokens: tokens,
+            kept_tokens: estimate_tokens(&section.text),
+        });
+        estimated_tokens = estimate_tokens(&join_sections(&sections));
+    }
+
+    let prompt = join_sections(&sections);
+    (
+        prompt,
+        PromptBudgetReport {
+            budget,
+            original_tokens,
+            estimated_tokens,
+            trims,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens_weighs_code_and_cjk_higher() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert!(estimate_tokens("{}();[]<>") > estimate_tokens("abcdefghi"));
+        assert_eq!(estimate_tokens("日本語"), 3);
+    }
+
+    #[test]
+    fn test_over_budget_prompt_trims_tool_output_then_old_comments() {
+        let log = (0..400)
+            .map(|i| format!("test case {} failed with an assertion error", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let comment = "Please rename this variable to something clearer. ".repeat(20);
+        let sections = vec![
+            PromptSection::new(PromptSectionKind::Comment, "Comment 1", comment.clone()),
+            PromptSection::new(PromptSectionKind::Comment, "Comment 2", comment.clone()),
+            PromptSection::new(PromptSectionKind::ToolOutput, "Test output", log),
+            PromptSection::new(
+                PromptSectionKind::Instructions,
+                "Instructions",
+                "Fix the tests",
+            ),
+        ];
+
+        let (prompt, report) = compose_prompt(&sections, 100_000);
+        assert!(report.trims.is_empty());
+        assert_eq!(report.estimated_tokens, report.original_tokens);
+        assert!(prompt.ends_with("Fix the tests"));
+
+        let (prompt, report) = compose_prompt(&sections, 300);
+        assert!(report.within_budget());
+        assert_eq!(
+            report
+                .trims
+                .iter()
+                .map(|trim| (trim.label.as_str(), trim.action))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Test output", TrimAction::Dropped),
+                ("Comment 1", TrimAction::Dropped),
+            ]
+        );
+        assert!(prompt.contains("[Test output omitted to fit the context budget]"));
+        assert!(prompt.contains(&comment));
+        assert!(prompt.ends_with("Fix the tests"));
+
+        let (prompt, report) = compose_prompt(&sections, 1_500);
+        assert!(report.within_budget());
+        assert_eq!(report.trims.len(), 1);
+        assert_eq!(report.trims[0].action, TrimAction::Truncated);
+        assert!(prompt.contains("test case 0 failed"));
+        assert!(prompt.contains("test case 399 failed"));
+        assert!(prompt.contains("lines omitted ...]"));
+
+        // Instructions are never cut, even when they alone exceed the budget
+        let (_, report) = compose_prompt(&sections[3..], 1);
+        assert!(!report.within_budget());
+        assert!(report.trims.is_empty());
+    }
+}
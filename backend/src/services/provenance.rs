@@ -0,0 +1,313 @@
+//! Provenance stamps on the commits and pull requests codecommand creates, so they can be
+//! traced back to the task and attempt they came from, e.g. months later on GitHub or after
+//! the local database and GitHub drifted apart.
+
+use git2::Repository;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        config::ProvenanceMode,
+        execution_process::{ExecutionProcess, ExecutionProcessType},
+        task::Task,
+        task_attempt::TaskAttempt,
+    },
+    services::benchmark::reported_cost_usd,
+    utils::read_port_file,
+};
+
+pub const ATTEMPT_TRAILER: &str = "Codecommand-Attempt";
+pub const EXECUTOR_TRAILER: &str = "Codecommand-Executor";
+
+/// What an attempt's commits and pull requests are stamped with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    pub project_id: Uuid,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub attempt_id: Uuid,
+    pub executor: Option<String>,
+    /// Total the coding agent reported spending, when it reports costs
+    pub cost_usd: Option<f64>,
+}
+
+impl Provenance {
+    pub fn new(task: &Task, attempt: &TaskAttempt, processes: &[ExecutionProcess]) -> Self {
+        Self {
+            project_id: task.project_id,
+            task_id: task.id,
+            task_title: task.title.clone(),
+            attempt_id: attempt.id,
+            executor: attempt.executor.clone(),
+            cost_usd: processes
+                .iter()
+                .filter(|p| p.process_type == ExecutionProcessType::CodingAgent)
+                .filter_map(|p| p.stdout.as_deref().and_then(reported_cost_usd))
+                .reduce(|a, b| a + b),
+        }
+    }
+
+    pub async fn load(pool: &SqlitePool, attempt: &TaskAttempt) -> Result<Self, sqlx::Error> {
+        let task = Task::find_by_id(pool, attempt.task_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt.id).await?;
+        Ok(Self::new(&task, attempt, &processes))
+    }
+
+    /// Git trailers naming the attempt and its executor
+    pub fn trailers(&self) -> String {
+        let mut trailers = format!("{}: {}", ATTEMPT_TRAILER, self.attempt_id);
+        if let Some(executor) = &self.executor {
+            trailers.push_str(&format!("\n{}: {}", EXECUTOR_TRAILER, executor));
+        }
+        trailers
+    }
+
+    /// `message` with the trailers appended, unless provenance is off
+    pub fn stamp_commit_message(&self, message: &str, mode: ProvenanceMode) -> String {
+        if mode == ProvenanceMode::Off {
+            return message.to_string();
+        }
+        format!("{}\n\n{}", message.trim_end(), self.trailers())
+    }
+
+    /// `body` with a collapsed provenance section appended, when provenance is `Full`.
+    /// `link` opens the attempt in codecommand.
+    pub fn stamp_pr_body(
+        &self,
+        body: Option<&str>,
+        mode: ProvenanceMode,
+        link: Option<&str>,
+    ) -> String {
+        let body = body.unwrap_or_default().trim_end();
+        if mode != ProvenanceMode::Full {
+            return body.to_string();
+        }
+
+        let mut footer = format!(
+            "<details>\n<summary>Created with codecommand</summary>\n\n- Task: {}\n- Attempt: `{}`\n",
+            self.task_title, self.attempt_id
+        );
+        if let Some(executor) = &self.executor {
+            footer.push_str(&format!("- Executor: {}\n", executor));
+        }
+        if let Some(cost) = self.cost_usd {
+            footer.push_str(&format!("- Cost: ${:.2}\n", cost));
+        }
+        if let Some(link) = link {
+            footer.push_str(&format!("- Open in codecommand: {}\n", link));
+        }
+        footer.push_str(&format!("\n{}\n</details>", self.trailers()));
+
+        if body.is_empty() {
+            footer
+        } else {
+            format!("{}\n\n{}", body, footer)
+        }
+    }
+}
+
+/// Base URL of the web UI: the configured `public_url`, or this server on localhost while
+/// it's running
+pub fn web_base_url(public_url: Option<&str>) -> Option<String> {
+    match public_url {
+        Some(url) => Some(url.to_string()),
+        None => read_port_file().map(|port| format!("http://127.0.0.1:{}", port)),
+    }
+}
+
+/// Where the web UI shows the attempt
+pub fn attempt_link(base_url: &str, provenance: &Provenance) -> String {
+    format!(
+        "{}/projects/{}/tasks/{}?attempt={}",
+        base_url.trim_end_matches('/'),
+        provenance.project_id,
+        provenance.task_id,
+        provenance.attempt_id
+    )
+}
+
+/// Attempt and executor named by provenance trailers in a commit message or PR body. The
+/// last trailer wins, as in git.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedProvenance {
+    pub attempt_id: Uuid,
+    #[allow(dead_code)]
+    pub executor: Option<String>,
+}
+
+pub fn parse(text: &str) -> Option<ParsedProvenance> {
+    let trailer = |name: &str| {
+        text.lines()
+            .rev()
+            .find_map(|line| {
+                let (key, value) = line.trim().split_once(':')?;
+                key.trim()
+                    .eq_ignore_ascii_case(name)
+                    .then(|| value.trim().to_string())
+            })
+            .filter(|value| !value.is_empty())
+    };
+
+    Some(ParsedProvenance {
+        attempt_id: Uuid::parse_str(&trailer(ATTEMPT_TRAILER)?).ok()?,
+        executor: trailer(EXECUTOR_TRAILER),
+    })
+}
+
+/// How a commit was traced back to its attempt
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvenanceSource {
+    /// The commit codecommand recorded when merging the attempt or its pull request
+    MergeCommit,
+    /// A provenance trailer in the commit message
+    Trailer,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ProvenanceResolution {
+    /// Full id of the commit
+    pub commit: String,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub attempt_id: Uuid,
+    pub executor: Option<String>,
+    pub source: ProvenanceSource,
+}
+
+#[derive(Debug)]
+pub enum ProvenanceError {
+    /// The repository doesn't have a commit by that name
+    UnknownCommit(String),
+    Git(git2::Error),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for ProvenanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProvenanceError::UnknownCommit(sha) => {
+                write!(f, "The repository has no commit '{}'", sha)
+            }
+            ProvenanceError::Git(e) => write!(f, "Git error: {}", e),
+            ProvenanceError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProvenanceError {}
+
+impl From<sqlx::Error> for ProvenanceError {
+    fn from(err: sqlx::Error) -> Self {
+        ProvenanceError::Database(err)
+    }
+}
+
+/// Map a commit of the project's repository back to the task and attempt it came from.
+/// `None` when nothing ties the commit to an attempt of the project.
+pub async fn resolve(
+    pool: &SqlitePool,
+    project_id: Uuid,
+    git_repo_path: &str,
+    sha: &str,
+) -> Result<Option<ProvenanceResolution>, ProvenanceError> {
+    let (commit, message) = {
+        let repo = Repository::open(git_repo_path).map_err(ProvenanceError::Git)?;
+        let commit = repo
+            .revparse_single(sha)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| ProvenanceError::UnknownCommit(sha.to_string()))?;
+        (
+            commit.id().to_string(),
+            commit.message().unwrap_or_default().to_string(),
+        )
+    };
+
+    let (attempt_id, source) =
+        match TaskAttempt::find_id_by_merge_commit(pool, project_id, &commit).await? {
+            Some(attempt_id) => (attempt_id, ProvenanceSource::MergeCommit),
+            None => match parse(&message) {
+                Some(parsed) => (parsed.attempt_id, ProvenanceSource::Trailer),
+                None => return Ok(None),
+            },
+        };
+
+    let Some(attempt) = TaskAttempt::find_by_id(pool, attempt_id).await? else {
+        return Ok(None);
+    };
+    let Some(task) = Task::find_by_id(pool, attempt.task_id).await? else {
+        return Ok(None);
+    };
+    // A trailer may name an attempt of another project
+    if task.project_id != project_id {
+        return Ok(None);
+    }
+
+    Ok(Some(ProvenanceResolution {
+        commit,
+        task_id: task.id,
+        task_title: task.title,
+        attempt_id: attempt.id,
+        executor: attempt.executor,
+        source,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provenance() -> Provenance {
+        Provenance {
+            project_id: Uuid::new_v4(),
+            task_id: Uuid::new_v4(),
+            task_title: "Add dark mode".to_string(),
+            attempt_id: Uuid::new_v4(),
+            executor: Some("claude".to_string()),
+            cost_usd: Some(0.4213),
+        }
+    }
+
+    #[test]
+    fn test_stamps_parse_back_to_the_attempt() {
+        let provenance = provenance();
+        let expected = ParsedProvenance {
+            attempt_id: provenance.attempt_id,
+            executor: Some("claude".to_string()),
+        };
+
+        let message =
+            provenance.stamp_commit_message("Merge: Add dark mode\n", ProvenanceMode::Trailers);
+        assert!(message.starts_with("Merge: Add dark mode\n\nCodecommand-Attempt: "));
+        assert_eq!(parse(&message), Some(expected.clone()));
+        assert_eq!(
+            provenance.stamp_commit_message("Merge", ProvenanceMode::Off),
+            "Merge"
+        );
+
+        let link = attempt_link("http://127.0.0.1:3000/", &provenance);
+        let body =
+            provenance.stamp_pr_body(Some("Adds a toggle."), ProvenanceMode::Full, Some(&link));
+        assert!(body.starts_with("Adds a toggle.\n\n<details>"));
+        assert!(body.contains("- Cost: $0.42\n"));
+        assert!(body.contains(&link));
+        assert_eq!(parse(&body), Some(expected));
+        assert_eq!(
+            provenance.stamp_pr_body(
+                Some("Adds a toggle."),
+                ProvenanceMode::Trailers,
+                Some(&link)
+            ),
+            "Adds a toggle."
+        );
+
+        assert_eq!(parse("Fix typo\n\nCodecommand-Attempt: not-a-uuid"), None);
+    }
+}
@@ -0,0 +1,194 @@
+//! Project settings committed to the repository in `.codecommand.toml`. They take precedence
+//! over the settings stored for the project, so they travel with the code.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{executor::ExecutorConfig, models::project::Project};
+
+pub const REPO_CONFIG_FILE: &str = ".codecommand.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoConfig {
+    pub setup_script: Option<String>,
+    pub dev_script: Option<String>,
+    pub test_script: Option<String>,
+    pub format_script: Option<String>,
+    /// Executor for attempts started without one
+    pub executor: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum RepoConfigError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    UnknownExecutor(String),
+}
+
+impl std::fmt::Display for RepoConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepoConfigError::Io(e) => write!(f, "Couldn't read {}: {}", REPO_CONFIG_FILE, e),
+            RepoConfigError::Parse(e) => write!(f, "Invalid {}: {}", REPO_CONFIG_FILE, e),
+            RepoConfigError::UnknownExecutor(name) => {
+                write!(
+                    f,
+                    "Invalid {}: unknown executor '{}'",
+                    REPO_CONFIG_FILE, name
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RepoConfigError {}
+
+/// How the repository's `.codecommand.toml` affected a project
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct RepoConfigStatus {
+    pub present: bool,
+    /// Settings the file overrides
+    pub overrides: Vec<String>,
+    /// Executor for attempts started without one
+    pub default_executor: Option<String>,
+    /// Why the file was ignored
+    pub error: Option<String>,
+}
+
+impl RepoConfig {
+    /// The config committed at the root of `repo_path`, if there is one
+    pub fn load(repo_path: &Path) -> Result<Option<Self>, RepoConfigError> {
+        let contents = match std::fs::read_to_string(repo_path.join(REPO_CONFIG_FILE)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(RepoConfigError::Io(e)),
+        };
+        let config: RepoConfig = toml::from_str(&contents).map_err(RepoConfigError::Parse)?;
+
+        if let Some(executor) = &config.executor {
+            let known = ExecutorConfig::agents()
+                .iter()
+                .any(|agent| agent.to_string() == *executor);
+            if !known {
+                return Err(RepoConfigError::UnknownExecutor(executor.clone()));
+            }
+        }
+        Ok(Some(config))
+    }
+
+    /// Set the project's scripts from the ones given here, returning the names of those set
+    pub fn apply(&self, project: &mut Project) -> Vec<String> {
+        let mut overrides = Vec::new();
+        for (name, value, field) in [
+            (
+                "setup_script",
+                &self.setup_script,
+                &mut project.setup_script,
+            ),
+            ("dev_script", &self.dev_script, &mut project.dev_script),
+            ("test_script", &self.test_script, &mut project.test_script),
+            (
+                "format_script",
+                &self.format_script,
+                &mut project.format_script,
+            ),
+        ] {
+            if let Some(value) = value {
+                *field = Some(value.clone());
+                overrides.push(name.to_string());
+            }
+        }
+        overrides
+    }
+}
+
+/// Merge the repository's `.codecommand.toml` over the project's stored settings. A file that
+/// can't be read or parsed is ignored with a warning, and reported in the returned status.
+pub fn merge(project: &mut Project) -> RepoConfigStatus {
+    match RepoConfig::load(Path::new(&project.git_repo_path)) {
+        Ok(Some(config)) => RepoConfigStatus {
+            present: true,
+            overrides: config.apply(project),
+            default_executor: config.executor,
+            error: None,
+        },
+        Ok(None) => RepoConfigStatus::default(),
+        Err(e) => {
+            tracing::warn!("Ignoring config of project {}: {}", project.id, e);
+            RepoConfigStatus {
+                present: true,
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+impl Project {
+    /// The project with its repository's `.codecommand.toml` merged in, as used to run attempts
+    pub fn with_repo_config(mut self) -> Self {
+        merge(&mut self);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::project::ProjectType;
+
+    fn project(repo: &TempDir) -> Project {
+        Project {
+            id: Uuid::new_v4(),
+            name: "project".to_string(),
+            git_repo_path: repo.path().to_string_lossy().to_string(),
+            setup_script: Some("npm install".to_string()),
+            dev_script: Some("npm run dev".to_string()),
+            test_script: Some("npm test".to_string()),
+            format_script: None,
+            format_commit_mode: Default::default(),
+            response_language: None,
+            project_type: ProjectType::Git,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_repo_config_overrides_stored_settings_and_reports_bad_files() {
+        let repo = TempDir::new().unwrap();
+        std::fs::write(
+            repo.path().join(REPO_CONFIG_FILE),
+            "setup_script = \"pnpm install\"\ndev_script = \"pnpm dev\"\nexecutor = \"claude\"\n",
+        )
+        .unwrap();
+
+        let mut merged = project(&repo);
+        let status = merge(&mut merged);
+        assert_eq!(merged.setup_script.as_deref(), Some("pnpm install"));
+        assert_eq!(merged.dev_script.as_deref(), Some("pnpm dev"));
+        assert_eq!(merged.test_script.as_deref(), Some("npm test"));
+        assert_eq!(status.overrides, ["setup_script", "dev_script"]);
+        assert_eq!(status.default_executor.as_deref(), Some("claude"));
+        assert!(status.error.is_none());
+
+        std::fs::write(repo.path().join(REPO_CONFIG_FILE), "setup_script = [").unwrap();
+        let mut unchanged = project(&repo);
+        let status = merge(&mut unchanged);
+        assert!(status.present);
+        assert!(status
+            .error
+            .unwrap()
+            .starts_with("Invalid .codecommand.toml"));
+        assert_eq!(unchanged.setup_script.as_deref(), Some("npm install"));
+        assert!(status.default_executor.is_none());
+    }
+}
@@ -0,0 +1,454 @@
+//! Quick search over projects, tasks, recent attempts and branches, for the command palette.
+//! Each type is searched concurrently under one latency budget: a type that doesn't answer in
+//! time is left out of the results and reported, rather than holding up the others.
+
+use std::{future::Future, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::models::project::Project;
+
+/// Results of each type returned when the caller doesn't ask for a limit
+pub const DEFAULT_LIMIT: usize = 10;
+pub const MAX_LIMIT: usize = 50;
+/// How long the slowest type may take before it's left out
+pub const LATENCY_BUDGET: Duration = Duration::from_millis(300);
+/// Most recent attempts looked through
+const RECENT_ATTEMPTS: i64 = 200;
+/// Score of a task that matched in its description but not its title
+const DESCRIPTION_MATCH_SCORE: f64 = 0.3;
+/// Attempts rank just below the task they belong to when matched by its title
+const ATTEMPT_TASK_TITLE_FACTOR: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum SearchResultType {
+    Project,
+    Task,
+    Attempt,
+    Branch,
+}
+
+impl SearchResultType {
+    pub const ALL: [SearchResultType; 4] = [
+        SearchResultType::Project,
+        SearchResultType::Task,
+        SearchResultType::Attempt,
+        SearchResultType::Branch,
+    ];
+}
+
+impl FromStr for SearchResultType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "project" => Ok(SearchResultType::Project),
+            "task" => Ok(SearchResultType::Task),
+            "attempt" => Ok(SearchResultType::Attempt),
+            "branch" => Ok(SearchResultType::Branch),
+            other => Err(format!("Unknown search result type: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SearchResult {
+    pub result_type: SearchResultType,
+    /// Id of the project, task or attempt; the name of a branch
+    pub id: String,
+    pub title: String,
+    /// Task an attempt belongs to
+    pub subtitle: Option<String>,
+    pub project_id: Uuid,
+    pub project_name: String,
+    /// From 0 to 1, comparable across types
+    pub score: f64,
+    /// Frontend route that opens the result
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct SearchResponse {
+    /// Best matches first
+    pub results: Vec<SearchResult>,
+    /// Types left out because they didn't answer within the latency budget
+    pub timed_out: Vec<SearchResultType>,
+}
+
+pub struct SearchQuery {
+    pub text: String,
+    pub types: Vec<SearchResultType>,
+    /// Results of each type at most
+    pub limit: usize,
+}
+
+/// How well `text` matches `query`, ignoring case: 1 for the same text, less for text that
+/// starts with it, has a word starting with it, contains it or contains each of its words.
+/// `None` when it doesn't match.
+pub fn match_score(text: &str, query: &str) -> Option<f64> {
+    let text = text.to_lowercase();
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+
+    if text == query {
+        return Some(1.0);
+    }
+    if text.starts_with(&query) {
+        return Some(0.8);
+    }
+    let mut found = text.match_indices(&query).map(|(i, _)| i).peekable();
+    if found.peek().is_some() {
+        let at_word_start = found.any(|i| {
+            !text[..i]
+                .chars()
+                .next_back()
+                .is_some_and(char::is_alphanumeric)
+        });
+        return Some(if at_word_start { 0.65 } else { 0.5 });
+    }
+    query
+        .split_whitespace()
+        .all(|word| text.contains(word))
+        .then_some(0.4)
+}
+
+/// Words of `query` as an FTS5 query matching text with words starting with each of them
+fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+fn ranked(mut results: Vec<SearchResult>, limit: usize) -> Vec<SearchResult> {
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(limit);
+    results
+}
+
+async fn search_projects(
+    pool: &SqlitePool,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    let results = Project::find_all(pool)
+        .await?
+        .into_iter()
+        .filter_map(|project| {
+            let score = match_score(&project.name, query)?;
+            Some(SearchResult {
+                result_type: SearchResultType::Project,
+                id: project.id.to_string(),
+                path: format!("/projects/{}/tasks", project.id),
+                title: project.name.clone(),
+                subtitle: None,
+                project_id: project.id,
+                project_name: project.name,
+                score,
+            })
+        })
+        .collect();
+    Ok(ranked(results, limit))
+}
+
+async fn search_tasks(
+    pool: &SqlitePool,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    let Some(fts_query) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+    // Candidates come from the index in relevance order, titles weighing more
+    let candidates = (limit * 5) as i64;
+    let rows = sqlx::query!(
+        r#"SELECT t.id as "id!: Uuid", t.title, t.project_id as "project_id!: Uuid", p.name as project_name
+           FROM task_search
+           JOIN tasks t ON t.id = task_search.task_id
+           JOIN projects p ON p.id = t.project_id
+           WHERE task_search MATCH $1
+           ORDER BY bm25(task_search, 0.0, 10.0, 1.0)
+           LIMIT $2"#,
+        fts_query,
+        candidates
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let results = rows
+        .into_iter()
+        .map(|row| SearchResult {
+            result_type: SearchResultType::Task,
+            id: row.id.to_string(),
+            score: match_score(&row.title, query).unwrap_or(DESCRIPTION_MATCH_SCORE),
+            path: format!("/projects/{}/tasks/{}", row.project_id, row.id),
+            title: row.title,
+            subtitle: None,
+            project_id: row.project_id,
+            project_name: row.project_name,
+        })
+        .collect();
+    Ok(ranked(results, limit))
+}
+
+async fn search_attempts(
+    pool: &SqlitePool,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT ta.id as "id!: Uuid", ta.branch, t.id as "task_id!: Uuid", t.title as task_title,
+                  p.id as "project_id!: Uuid", p.name as project_name
+           FROM task_attempts ta
+           JOIN tasks t ON t.id = ta.task_id
+           JOIN projects p ON p.id = t.project_id
+           ORDER BY ta.created_at DESC
+           LIMIT $1"#,
+        RECENT_ATTEMPTS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let results = rows
+        .into_iter()
+        .filter_map(|row| {
+            let by_branch = match_score(&row.branch, query);
+            let by_task =
+                match_score(&row.task_title, query).map(|score| score * ATTEMPT_TASK_TITLE_FACTOR);
+            let score = by_branch.into_iter().chain(by_task).reduce(f64::max)?;
+            Some(SearchResult {
+                result_type: SearchResultType::Attempt,
+                id: row.id.to_string(),
+                // The task page lists its attempts
+                path: format!("/projects/{}/tasks/{}", row.project_id, row.task_id),
+                title: row.branch,
+                subtitle: Some(row.task_title),
+                project_id: row.project_id,
+                project_name: row.project_name,
+                score,
+            })
+        })
+        .collect();
+    Ok(ranked(results, limit))
+}
+
+/// Branches of every git project, listed concurrently. Projects whose branches can't be
+/// listed, e.g. because their repository moved, are skipped.
+async fn search_branches(
+    pool: &SqlitePool,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    let projects = Project::find_all(pool)
+        .await?
+        .into_iter()
+        .filter(|project| !project.is_scratch());
+    let listings = projects.map(|project| {
+        tokio::task::spawn_blocking(move || {
+            let branches = project.get_all_branches();
+            (project, branches)
+        })
+    });
+
+    let mut results = Vec::new();
+    for listing in futures_util::future::join_all(listings).await {
+        let (project, branches) = match listing {
+            Ok((project, Ok(branches))) => (project, branches),
+            Ok((project, Err(e))) => {
+                tracing::warn!("Failed to list branches of project {}: {}", project.id, e);
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("Branch listing panicked: {}", e);
+                continue;
+            }
+        };
+        results.extend(branches.into_iter().filter_map(|branch| {
+            let score = match_score(&branch.name, query)?;
+            Some(SearchResult {
+                result_type: SearchResultType::Branch,
+                id: branch.name.clone(),
+                title: branch.name,
+                subtitle: None,
+                project_id: project.id,
+                project_name: project.name.clone(),
+                path: format!("/projects/{}/tasks", project.id),
+                score,
+            })
+        }));
+    }
+    Ok(ranked(results, limit))
+}
+
+/// Run the search of `result_type` if it was asked for. `Ok(None)` when it ran out of time.
+async fn within_budget(
+    query: &SearchQuery,
+    result_type: SearchResultType,
+    search: impl Future<Output = Result<Vec<SearchResult>, sqlx::Error>>,
+) -> Result<Option<Vec<SearchResult>>, sqlx::Error> {
+    if !query.types.contains(&result_type) {
+        return Ok(Some(Vec::new()));
+    }
+    match tokio::time::timeout(LATENCY_BUDGET, search).await {
+        Ok(results) => results.map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+pub async fn search(pool: &SqlitePool, query: &SearchQuery) -> Result<SearchResponse, sqlx::Error> {
+    let text = query.text.trim();
+    if text.is_empty() {
+        return Ok(SearchResponse {
+            results: Vec::new(),
+            timed_out: Vec::new(),
+        });
+    }
+
+    let (projects, tasks, attempts, branches) = tokio::join!(
+        within_budget(
+            query,
+            SearchResultType::Project,
+            search_projects(pool, text, query.limit)
+        ),
+        within_budget(
+            query,
+            SearchResultType::Task,
+            search_tasks(pool, text, query.limit)
+        ),
+        within_budget(
+            query,
+            SearchResultType::Attempt,
+            search_attempts(pool, text, query.limit)
+        ),
+        within_budget(
+            query,
+            SearchResultType::Branch,
+            search_branches(pool, text, query.limit)
+        ),
+    );
+
+    let mut results = Vec::new();
+    let mut timed_out = Vec::new();
+    for (result_type, found) in SearchResultType::ALL
+        .into_iter()
+        .zip([projects?, tasks?, attempts?, branches?])
+    {
+        match found {
+            Some(found) => results.extend(found),
+            None => timed_out.push(result_type),
+        }
+    }
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    Ok(SearchResponse { results, timed_out })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_attempt, memory_pool};
+
+    #[tokio::test]
+    async fn test_search_ranks_matches_across_types() {
+        let pool = memory_pool().await;
+
+        let project_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO projects (id, name, git_repo_path, project_type) VALUES ($1, 'Login service', '/tmp/p', 'scratch')",
+        )
+        .bind(project_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let task = |title: &'static str, description: Option<&'static str>| {
+            let pool = pool.clone();
+            async move {
+                let task_id = Uuid::new_v4();
+                sqlx::query(
+                    "INSERT INTO tasks (id, project_id, title, description) VALUES ($1, $2, $3, $4)",
+                )
+                .bind(task_id)
+                .bind(project_id)
+                .bind(title)
+                .bind(description)
+                .execute(&pool)
+                .await
+                .unwrap();
+                task_id
+            }
+        };
+        let login_task = task("Fix login redirect", None).await;
+        let described = task("Session cleanup", Some("Also log out of the login page")).await;
+        let renamed = task("Unrelated", None).await;
+        task("Dark mode", None).await;
+        insert_attempt(&pool, login_task, "/tmp/w", "cc/fix-login-redirect").await;
+        // The index follows edits and deletes
+        sqlx::query("UPDATE tasks SET title = 'Login rate limits' WHERE id = $1")
+            .bind(renamed)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM tasks WHERE id = $1")
+            .bind(described)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let response = search(
+            &pool,
+            &SearchQuery {
+                text: "login".to_string(),
+                types: SearchResultType::ALL.to_vec(),
+                limit: DEFAULT_LIMIT,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(response.timed_out.is_empty());
+        let found: Vec<_> = response
+            .results
+            .iter()
+            .map(|result| (result.result_type, result.title.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (SearchResultType::Project, "Login service"),
+                (SearchResultType::Task, "Login rate limits"),
+                (SearchResultType::Task, "Fix login redirect"),
+                (SearchResultType::Attempt, "cc/fix-login-redirect"),
+            ]
+        );
+        let attempt = &response.results[3];
+        assert_eq!(attempt.subtitle.as_deref(), Some("Fix login redirect"));
+        assert_eq!(
+            attempt.path,
+            format!("/projects/{}/tasks/{}", project_id, login_task)
+        );
+
+        // Per-type limits and type filters
+        let response = search(
+            &pool,
+            &SearchQuery {
+                text: "login".to_string(),
+                types: vec![SearchResultType::Task],
+                limit: 1,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert_eq!(response.results[0].title, "Login rate limits");
+    }
+}
@@ -0,0 +1,409 @@
+//! Update checks against the project's GitHub releases, and replacing the running binary
+//! with a newer release. Built with the `self-update` feature; package manager builds leave
+//! it out and update codecommand themselves.
+//!
+//! Requests go through the proxy from `HTTPS_PROXY`/`ALL_PROXY`, minus `NO_PROXY`, like
+//! every other reqwest client.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::{Notify, RwLock};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{models::config::Config, utils::secrets};
+
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const UPDATE_CHECK_JOB: &str = "update_check";
+pub const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// `owner/name` of the GitHub repository releases are published to
+const RELEASES_REPO: &str = match option_env!("CODECOMMAND_RELEASES_REPO") {
+    Some(repo) => repo,
+    None => "utkarshx/codecommand",
+};
+/// minisign public key release binaries are signed with. Builds without one only check
+/// downloads against their published SHA-256.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("CODECOMMAND_RELEASE_PUBLIC_KEY");
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+const APPLY_TOKEN_PURPOSE: &str = "self-update";
+
+/// A release newer than the running version
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AvailableUpdate {
+    pub version: String,
+    pub release_url: String,
+    /// Body of the GitHub release, in Markdown
+    pub release_notes: Option<String>,
+    pub published_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct UpdateStatus {
+    pub current_version: String,
+    /// Whether daily update checks are turned on in the config
+    pub checks_enabled: bool,
+    pub update: Option<AvailableUpdate>,
+    #[serde(with = "crate::utils::rfc3339::option")]
+    pub checked_at: Option<DateTime<Utc>>,
+    /// Why the last check failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+    body: Option<String>,
+    published_at: Option<String>,
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(Debug)]
+pub enum UpdateError {
+    Http(reqwest::Error),
+    Io(std::io::Error),
+    /// The latest release isn't newer than the running version
+    UpToDate(String),
+    /// The latest release has no build for this platform
+    NoAsset(String),
+    /// The download doesn't match its published checksum or signature
+    Verification(String),
+    InvalidRelease(String),
+}
+
+impl std::fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateError::Http(e) => write!(f, "Failed to reach GitHub releases: {}", e),
+            UpdateError::Io(e) => write!(f, "Failed to install the update: {}", e),
+            UpdateError::UpToDate(version) => {
+                write!(f, "codecommand {} is the latest release", version)
+            }
+            UpdateError::NoAsset(asset) => {
+                write!(f, "The latest release has no {} build", asset)
+            }
+            UpdateError::Verification(e) => {
+                write!(f, "The download failed verification: {}", e)
+            }
+            UpdateError::InvalidRelease(e) => write!(f, "Invalid release: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {}
+
+impl From<reqwest::Error> for UpdateError {
+    fn from(err: reqwest::Error) -> Self {
+        UpdateError::Http(err)
+    }
+}
+
+impl From<std::io::Error> for UpdateError {
+    fn from(err: std::io::Error) -> Self {
+        UpdateError::Io(err)
+    }
+}
+
+fn client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(format!("codecommand/{}", CURRENT_VERSION))
+        .timeout(timeout)
+        .build()
+        .unwrap_or_default()
+}
+
+async fn latest_release(client: &reqwest::Client) -> Result<Release, UpdateError> {
+    let release = client
+        .get(format!(
+            "https://api.github.com/repos/{}/releases/latest",
+            RELEASES_REPO
+        ))
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(release)
+}
+
+/// Version of a release tagged like `v1.2.3` or `1.2.3`, when it's newer than `current`
+fn newer_version(current: &str, tag: &str) -> Option<Version> {
+    let current = Version::parse(current).ok()?;
+    let version = Version::parse(tag.trim().trim_start_matches('v')).ok()?;
+    (version > current).then_some(version)
+}
+
+/// Name of the release asset holding the binary for this platform, e.g.
+/// `codecommand-linux-x86_64`
+pub fn asset_name() -> String {
+    format!(
+        "codecommand-{}-{}{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Latest update check result, shared by the check job, `/api/health` and `/api/updates`,
+/// and the restart requested after installing an update
+#[derive(Debug, Clone)]
+pub struct UpdateService {
+    status: Arc<RwLock<UpdateStatus>>,
+    restart: Arc<Notify>,
+    restart_pending: Arc<AtomicBool>,
+}
+
+impl Default for UpdateService {
+    fn default() -> Self {
+        Self {
+            status: Arc::new(RwLock::new(UpdateStatus {
+                current_version: CURRENT_VERSION.to_string(),
+                checks_enabled: true,
+                update: None,
+                checked_at: None,
+                error: None,
+            })),
+            restart: Arc::new(Notify::new()),
+            restart_pending: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl UpdateService {
+    pub async fn status(&self) -> UpdateStatus {
+        self.status.read().await.clone()
+    }
+
+    /// Look for a newer release, unless update checks are turned off
+    pub async fn check(&self, config: &RwLock<Config>) -> Result<(), String> {
+        let checks_enabled = config.read().await.check_for_updates;
+        self.status.write().await.checks_enabled = checks_enabled;
+        if !checks_enabled {
+            return Ok(());
+        }
+
+        let result = latest_release(&client(CHECK_TIMEOUT)).await;
+        let mut status = self.status.write().await;
+        status.checked_at = Some(Utc::now());
+        match result {
+            Ok(release) => {
+                status.update = newer_version(CURRENT_VERSION, &release.tag_name).map(|version| {
+                    AvailableUpdate {
+                        version: version.to_string(),
+                        release_url: release.html_url,
+                        release_notes: release.body,
+                        published_at: release.published_at,
+                    }
+                });
+                status.error = None;
+                Ok(())
+            }
+            Err(e) => {
+                status.error = Some(e.to_string());
+                Err(e.to_string())
+            }
+        }
+    }
+
+    /// Stop the server through its graceful shutdown, and start the installed binary after
+    pub fn request_restart(&self) {
+        self.restart_pending.store(true, Ordering::SeqCst);
+        self.restart.notify_one();
+    }
+
+    pub async fn restart_requested(&self) {
+        self.restart.notified().await
+    }
+
+    pub fn restart_pending(&self) -> bool {
+        self.restart_pending.load(Ordering::SeqCst)
+    }
+}
+
+/// Download the latest release for this platform, verify it, and swap it in for the running
+/// binary. Returns the installed version. Nothing is replaced unless every step succeeds.
+pub async fn install_latest() -> Result<String, UpdateError> {
+    let client = client(DOWNLOAD_TIMEOUT);
+    let release = latest_release(&client).await?;
+    let version = newer_version(CURRENT_VERSION, &release.tag_name)
+        .ok_or_else(|| UpdateError::UpToDate(CURRENT_VERSION.to_string()))?;
+
+    let name = asset_name();
+    let download = |asset_name: String| {
+        let client = &client;
+        let url = release
+            .assets
+            .iter()
+            .find(|asset| asset.name == asset_name)
+            .map(|asset| asset.browser_download_url.clone());
+        async move {
+            let url = url.ok_or(UpdateError::NoAsset(asset_name))?;
+            let bytes = client
+                .get(url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            Ok::<_, UpdateError>(bytes.to_vec())
+        }
+    };
+
+    let binary = download(name.clone()).await?;
+    let checksum = download(format!("{}.sha256", name)).await?;
+    verify_checksum(&binary, &String::from_utf8_lossy(&checksum))?;
+    if let Some(public_key) = RELEASE_PUBLIC_KEY {
+        let signature = download(format!("{}.minisig", name)).await?;
+        verify_signature(public_key, &binary, &String::from_utf8_lossy(&signature))?;
+    }
+
+    let exe = std::env::current_exe()?.canonicalize()?;
+    tokio::task::spawn_blocking(move || replace_binary(&exe, &binary))
+        .await
+        .map_err(|e| UpdateError::Io(std::io::Error::other(e)))??;
+    Ok(version.to_string())
+}
+
+/// Check `data` against a `<hex digest>  <file name>` line, as written by `sha256sum`
+fn verify_checksum(data: &[u8], published: &str) -> Result<(), UpdateError> {
+    let expected = published
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| UpdateError::InvalidRelease("the checksum file is empty".to_string()))?;
+    let actual: String = Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(UpdateError::Verification(format!(
+            "its SHA-256 is {}, the release says {}",
+            actual, expected
+        )))
+    }
+}
+
+fn verify_signature(public_key: &str, data: &[u8], signature: &str) -> Result<(), UpdateError> {
+    let public_key = minisign_verify::PublicKey::from_base64(public_key)
+        .map_err(|e| UpdateError::Verification(format!("invalid public key: {}", e)))?;
+    let signature = minisign_verify::Signature::decode(signature)
+        .map_err(|e| UpdateError::Verification(format!("invalid signature: {}", e)))?;
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|e| UpdateError::Verification(format!("bad signature: {}", e)))
+}
+
+/// File removed when dropped, unless it was moved into place
+struct StagedFile(Option<PathBuf>);
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Write `data` next to `exe` and rename it over `exe`, so the binary is either the old one
+/// or the new one in full
+fn replace_binary(exe: &Path, data: &[u8]) -> Result<(), UpdateError> {
+    let dir = exe
+        .parent()
+        .ok_or_else(|| UpdateError::Io(std::io::Error::other("the binary has no directory")))?;
+    let file_name = exe.file_name().unwrap_or_default().to_string_lossy();
+    let staged_path = dir.join(format!(".{}.update-{}", file_name, Uuid::new_v4()));
+    let mut staged = StagedFile(Some(staged_path.clone()));
+
+    std::fs::write(&staged_path, data)?;
+    std::fs::set_permissions(&staged_path, std::fs::metadata(exe)?.permissions())?;
+
+    #[cfg(windows)]
+    {
+        // A running executable can be renamed but not replaced
+        let previous = dir.join(format!("{}.old", file_name));
+        let _ = std::fs::remove_file(&previous);
+        std::fs::rename(exe, &previous)?;
+        if let Err(e) = std::fs::rename(&staged_path, exe) {
+            let _ = std::fs::rename(&previous, exe);
+            return Err(e.into());
+        }
+    }
+    #[cfg(not(windows))]
+    std::fs::rename(&staged_path, exe)?;
+
+    staged.0 = None;
+    Ok(())
+}
+
+/// Token `POST /api/updates/apply` requires, derived from the local secret key so only
+/// someone with access to this machine's codecommand data can produce it
+pub fn apply_token() -> anyhow::Result<String> {
+    Ok(URL_SAFE_NO_PAD.encode(secrets::sign(APPLY_TOKEN_PURPOSE, &[])?))
+}
+
+pub fn verify_apply_token(token: &str) -> bool {
+    let Ok(signature) = URL_SAFE_NO_PAD.decode(token.trim()) else {
+        return false;
+    };
+    secrets::verify(APPLY_TOKEN_PURPOSE, &[], &signature).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_verified_newer_releases_replace_the_binary() {
+        assert_eq!(
+            newer_version("0.0.48", "v0.1.0"),
+            Some(Version::new(0, 1, 0))
+        );
+        assert_eq!(newer_version("0.0.48", "0.0.48"), None);
+        assert_eq!(newer_version("0.0.48", "v0.0.9"), None);
+        assert_eq!(newer_version("0.0.48", "nightly"), None);
+
+        let binary = b"#!/bin/sh\necho new\n";
+        let digest = "f6f0e3a2b0d3ce0bd0c0b1e4c59aa1a4e0e0aaf1d7dd26dcf6fbbbf0c1b0a2f0";
+        assert!(matches!(
+            verify_checksum(binary, digest),
+            Err(UpdateError::Verification(_))
+        ));
+        let actual: String = Sha256::digest(binary)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        verify_checksum(binary, &format!("{}  codecommand-linux-x86_64\n", actual)).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("codecommand");
+        std::fs::write(&exe, b"old").unwrap();
+        replace_binary(&exe, binary).unwrap();
+        assert_eq!(std::fs::read(&exe).unwrap(), binary);
+        // Only the binary is left, without a staged copy
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,203 @@
+//! Files announcing running servers. The server listens on a random port by default, so the
+//! CLI, the MCP server and editor extensions find it through these instead. Every server
+//! writes its own file to the asset dir, named after a hash of the asset dir and its pid, and
+//! removes it when it shuts down gracefully. Files left behind by crashed servers are
+//! recognized by probing the pid and port.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::utils::asset_dir;
+
+/// How long a server gets to answer the health check before it's considered gone
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What a running server tells local clients about itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerAnnouncement {
+    pub pid: u32,
+    pub port: u16,
+    /// How clients authenticate. The API is open to local clients, so this is `none`; only
+    /// applying updates takes a token, the one `codecommand self-update --token` prints.
+    pub auth: String,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub started_at: DateTime<Utc>,
+    pub version: String,
+}
+
+impl ServerAnnouncement {
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+}
+
+/// Short hash of the asset dir, so servers with different asset dirs can be told apart
+fn asset_dir_hash(dir: &Path) -> String {
+    Sha256::digest(dir.to_string_lossy().as_bytes())
+        .iter()
+        .take(6)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn file_prefix(dir: &Path) -> String {
+    format!("server-{}-", asset_dir_hash(dir))
+}
+
+fn announcement_path(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{}{}.json", file_prefix(dir), pid))
+}
+
+/// Announce this process as a server listening on `port`. Returns the file to remove on
+/// shutdown.
+pub fn announce(port: u16) -> std::io::Result<PathBuf> {
+    announce_in(&asset_dir(), std::process::id(), port)
+}
+
+fn announce_in(dir: &Path, pid: u32, port: u16) -> std::io::Result<PathBuf> {
+    let announcement = ServerAnnouncement {
+        pid,
+        port,
+        auth: "none".to_string(),
+        started_at: Utc::now(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let path = announcement_path(dir, pid);
+    // Renamed into place so readers never see a partial file
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(&announcement)?)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(path)
+}
+
+pub fn withdraw(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!("Failed to remove server announcement: {}", e);
+        }
+    }
+}
+
+/// The most recently started server of this asset dir that is still running, if any
+pub async fn discover() -> Option<ServerAnnouncement> {
+    discover_in(&asset_dir()).await
+}
+
+async fn discover_in(dir: &Path) -> Option<ServerAnnouncement> {
+    let prefix = file_prefix(dir);
+    let mut announcements: Vec<(PathBuf, ServerAnnouncement)> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".json"))
+        })
+        .filter_map(|path| {
+            let announcement = serde_json::from_slice(&std::fs::read(&path).ok()?).ok()?;
+            Some((path, announcement))
+        })
+        .collect();
+    announcements.sort_by(|(_, a), (_, b)| b.started_at.cmp(&a.started_at));
+
+    for (path, announcement) in announcements {
+        if process_alive(announcement.pid) == Some(false) {
+            tracing::debug!(
+                "Removing announcement of exited server {}",
+                announcement.pid
+            );
+            withdraw(&path);
+            continue;
+        }
+        // The pid may have been reused by an unrelated process, so the port has to answer too
+        if responds_to_health_check(announcement.port).await {
+            return Some(announcement);
+        }
+    }
+    None
+}
+
+/// Whether a process with this pid exists, `None` where that can't be checked
+fn process_alive(pid: u32) -> Option<bool> {
+    #[cfg(unix)]
+    {
+        use nix::{errno::Errno, sys::signal::kill, unistd::Pid};
+
+        match kill(Pid::from_raw(pid as i32), None) {
+            // It exists but belongs to another user
+            Ok(()) | Err(Errno::EPERM) => Some(true),
+            Err(_) => Some(false),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+async fn responds_to_health_check(port: u16) -> bool {
+    let Ok(client) = reqwest::Client::builder().timeout(HEALTH_TIMEOUT).build() else {
+        return false;
+    };
+    let Ok(response) = client
+        .get(format!("http://127.0.0.1:{}/api/health", port))
+        .send()
+        .await
+    else {
+        return false;
+    };
+    response
+        .json::<serde_json::Value>()
+        .await
+        .is_ok_and(|body| body["success"].as_bool() == Some(true))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use axum::{routing::get, Json, Router};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stale_announcements_are_removed_and_live_servers_found() {
+        let dir = TempDir::new().unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = Router::new().route(
+            "/api/health",
+            get(|| async { Json(serde_json::json!({ "success": true })) }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let live = announce_in(dir.path(), std::process::id(), port).unwrap();
+
+        // A server that crashed: its process is gone
+        let mut exited = std::process::Command::new("true").spawn().unwrap();
+        let exited_pid = exited.id();
+        exited.wait().unwrap();
+        let stale = announce_in(dir.path(), exited_pid, port).unwrap();
+
+        // Another asset dir's server isn't this one's
+        let other_dir = TempDir::new().unwrap();
+        let other = announce_in(other_dir.path(), std::process::id(), port).unwrap();
+        std::fs::rename(&other, dir.path().join(other.file_name().unwrap())).unwrap();
+
+        let found = discover_in(dir.path()).await.unwrap();
+        assert_eq!(found.pid, std::process::id());
+        assert_eq!(found.url(), format!("http://127.0.0.1:{}", port));
+        assert!(!stale.exists());
+        assert!(live.exists());
+
+        withdraw(&live);
+        assert!(discover_in(dir.path()).await.is_none());
+    }
+}
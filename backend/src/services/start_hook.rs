@@ -0,0 +1,235 @@
+//! The attempt start hook lets external automation, like a dashboard, know the moment an
+//! attempt begins. It runs once the attempt's worktree is ready and before its setup script
+//! or agent is spawned. It can't hold an attempt back: failures are only logged.
+
+use std::{process::Stdio, time::Duration};
+
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    models::{config::AttemptStartHook, project::Project, task::Task, task_attempt::TaskAttempt},
+    utils::shell::get_shell_command,
+};
+
+/// How long the attempt waits for the hook before starting without it
+pub const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the hook is given: the JSON body of the webhook request, or the script's stdin. Scripts
+/// also get the ids in `CODECOMMAND_*` environment variables and run in the worktree.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct AttemptStartContext {
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub task_description: Option<String>,
+    pub attempt_id: Uuid,
+    pub executor: Option<String>,
+    pub branch: String,
+    pub base_branch: String,
+    pub worktree_path: String,
+}
+
+impl AttemptStartContext {
+    pub fn new(project: &Project, task: &Task, attempt: &TaskAttempt) -> Self {
+        Self {
+            project_id: project.id,
+            project_name: project.name.clone(),
+            task_id: task.id,
+            task_title: task.title.clone(),
+            task_description: task.description.clone(),
+            attempt_id: attempt.id,
+            executor: attempt.executor.clone(),
+            branch: attempt.branch.clone(),
+            base_branch: attempt.base_branch.clone(),
+            worktree_path: attempt.worktree_path.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StartHookError {
+    Request(reqwest::Error),
+    /// The webhook answered with a status other than 2xx
+    Status(u16),
+    Io(std::io::Error),
+    /// The script exited unsuccessfully, with this code unless a signal ended it
+    Exit(Option<i32>),
+    Timeout,
+}
+
+impl std::fmt::Display for StartHookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StartHookError::Request(e) => write!(f, "Webhook request failed: {}", e),
+            StartHookError::Status(status) => write!(f, "Webhook answered with {}", status),
+            StartHookError::Io(e) => write!(f, "Couldn't run the script: {}", e),
+            StartHookError::Exit(Some(code)) => write!(f, "Script exited with code {}", code),
+            StartHookError::Exit(None) => write!(f, "Script was killed by a signal"),
+            StartHookError::Timeout => {
+                write!(f, "Hook didn't finish within {}s", HOOK_TIMEOUT.as_secs())
+            }
+        }
+    }
+}
+
+impl std::error::Error for StartHookError {}
+
+/// Invoke `hook` with `context`, waiting at most `HOOK_TIMEOUT`
+pub async fn run(
+    hook: &AttemptStartHook,
+    context: &AttemptStartContext,
+) -> Result<(), StartHookError> {
+    let invocation = async {
+        match hook {
+            AttemptStartHook::Webhook { url } => post_webhook(url, context).await,
+            AttemptStartHook::Script { command } => run_script(command, context).await,
+        }
+    };
+    tokio::time::timeout(HOOK_TIMEOUT, invocation)
+        .await
+        .map_err(|_| StartHookError::Timeout)?
+}
+
+/// Run the configured hook, if any, logging rather than returning failures
+pub async fn invoke(hook: Option<&AttemptStartHook>, context: &AttemptStartContext) {
+    let Some(hook) = hook else {
+        return;
+    };
+    match run(hook, context).await {
+        Ok(()) => tracing::debug!("Ran start hook of attempt {}", context.attempt_id),
+        Err(e) => tracing::warn!("Start hook of attempt {} failed: {}", context.attempt_id, e),
+    }
+}
+
+async fn post_webhook(url: &str, context: &AttemptStartContext) -> Result<(), StartHookError> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(context)
+        .send()
+        .await
+        .map_err(StartHookError::Request)?;
+    if !response.status().is_success() {
+        return Err(StartHookError::Status(response.status().as_u16()));
+    }
+    Ok(())
+}
+
+async fn run_script(command: &str, context: &AttemptStartContext) -> Result<(), StartHookError> {
+    let payload = serde_json::to_vec(context).map_err(|e| StartHookError::Io(e.into()))?;
+    let (shell_cmd, shell_arg) = get_shell_command();
+
+    let mut child = tokio::process::Command::new(shell_cmd)
+        .arg(shell_arg)
+        .arg(command)
+        .current_dir(&context.worktree_path)
+        .env("CODECOMMAND_PROJECT_ID", context.project_id.to_string())
+        .env("CODECOMMAND_TASK_ID", context.task_id.to_string())
+        .env("CODECOMMAND_ATTEMPT_ID", context.attempt_id.to_string())
+        .env("CODECOMMAND_BRANCH", &context.branch)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(StartHookError::Io)?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A script that doesn't read its input closes the pipe early, which is fine
+        let _ = stdin.write_all(&payload).await;
+    }
+    let status = child.wait().await.map_err(StartHookError::Io)?;
+    if !status.success() {
+        return Err(StartHookError::Exit(status.code()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Json, Router};
+    use tempfile::TempDir;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn context(worktree: &TempDir) -> AttemptStartContext {
+        AttemptStartContext {
+            project_id: Uuid::new_v4(),
+            project_name: "shop".to_string(),
+            task_id: Uuid::new_v4(),
+            task_title: "Add dark mode".to_string(),
+            task_description: Some("Follow the system theme".to_string()),
+            attempt_id: Uuid::new_v4(),
+            executor: Some("claude".to_string()),
+            branch: "cc-add-dark-mode".to_string(),
+            base_branch: "main".to_string(),
+            worktree_path: worktree.path().to_string_lossy().to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_receive_the_attempt_context() {
+        let worktree = TempDir::new().unwrap();
+        let context = context(&worktree);
+        let expected = serde_json::to_value(&context).unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<serde_json::Value>| {
+                tx.send(body).unwrap();
+                async {}
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let webhook = AttemptStartHook::Webhook {
+            url: format!("http://{}/hook", addr),
+        };
+        run(&webhook, &context).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), expected);
+
+        let missing = AttemptStartHook::Webhook {
+            url: format!("http://{}/missing", addr),
+        };
+        assert!(matches!(
+            run(&missing, &context).await,
+            Err(StartHookError::Status(404))
+        ));
+
+        #[cfg(unix)]
+        {
+            let script = AttemptStartHook::Script {
+                command: "cat > context.json && echo \"$CODECOMMAND_ATTEMPT_ID\" > attempt_id"
+                    .to_string(),
+            };
+            run(&script, &context).await.unwrap();
+            let written: serde_json::Value = serde_json::from_str(
+                &std::fs::read_to_string(worktree.path().join("context.json")).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(written, expected);
+            assert_eq!(
+                std::fs::read_to_string(worktree.path().join("attempt_id"))
+                    .unwrap()
+                    .trim(),
+                context.attempt_id.to_string()
+            );
+
+            let failing = AttemptStartHook::Script {
+                command: "exit 3".to_string(),
+            };
+            assert!(matches!(
+                run(&failing, &context).await,
+                Err(StartHookError::Exit(Some(3)))
+            ));
+        }
+    }
+}
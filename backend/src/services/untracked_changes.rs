@@ -0,0 +1,309 @@
+//! Journal of changes to files git doesn't track in an attempt's worktree
+//!
+//! Agents sometimes write gitignored files, like generated configs or local databases, that
+//! never show up in the diff but change how the code behaves. When the journal is enabled, the
+//! untracked files are snapshotted before the agent first runs and compared after each
+//! execution. Snapshots stay cheap: ignored paths are never walked and only small files are
+//! hashed.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use git2::{ObjectType, Oid, Repository};
+use ignore::WalkBuilder;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    models::untracked_change_journal::{
+        FileFingerprint, FileSnapshot, UntrackedChange, UntrackedChangeJournal, UntrackedChangeKind,
+    },
+    utils::path_ignore::PathIgnore,
+};
+
+/// Files up to this size are hashed; larger ones are compared by size and mtime
+pub const HASHED_FILE_MAX_BYTES: u64 = 64 * 1024;
+
+/// Files recorded in one snapshot before the walk stops
+pub const MAX_SNAPSHOT_FILES: usize = 20_000;
+
+#[derive(Debug)]
+pub enum UntrackedChangesError {
+    Database(sqlx::Error),
+    Git(git2::Error),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for UntrackedChangesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UntrackedChangesError::Database(e) => write!(f, "Database error: {}", e),
+            UntrackedChangesError::Git(e) => write!(f, "Git error: {}", e),
+            UntrackedChangesError::Io(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for UntrackedChangesError {}
+
+impl From<sqlx::Error> for UntrackedChangesError {
+    fn from(err: sqlx::Error) -> Self {
+        UntrackedChangesError::Database(err)
+    }
+}
+
+impl From<git2::Error> for UntrackedChangesError {
+    fn from(err: git2::Error) -> Self {
+        UntrackedChangesError::Git(err)
+    }
+}
+
+impl From<std::io::Error> for UntrackedChangesError {
+    fn from(err: std::io::Error) -> Self {
+        UntrackedChangesError::Io(err)
+    }
+}
+
+impl From<tokio::task::JoinError> for UntrackedChangesError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        UntrackedChangesError::Io(std::io::Error::other(err.to_string()))
+    }
+}
+
+/// Files in the git index, by path relative to the worktree. Empty when the worktree isn't a
+/// repository, as for scratch projects.
+fn tracked_paths(worktree: &Path) -> Result<HashSet<String>, git2::Error> {
+    let repo = match Repository::open(worktree) {
+        Ok(repo) => repo,
+        Err(_) => return Ok(HashSet::new()),
+    };
+    let index = repo.index()?;
+    Ok(index
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .collect())
+}
+
+fn fingerprint(path: &Path, metadata: &std::fs::Metadata) -> FileFingerprint {
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since_epoch| since_epoch.as_millis() as i64);
+    let hash = (metadata.len() <= HASHED_FILE_MAX_BYTES)
+        .then(|| Oid::hash_file(ObjectType::Blob, path).ok())
+        .flatten()
+        .map(|oid| oid.to_string());
+    FileFingerprint {
+        size: metadata.len(),
+        modified_ms,
+        hash,
+    }
+}
+
+/// Snapshot the files in `worktree` that git doesn't track, leaving out `.git` and paths
+/// matching `ignored`
+pub fn take_snapshot(
+    worktree: &Path,
+    ignored: &PathIgnore,
+) -> Result<FileSnapshot, UntrackedChangesError> {
+    let tracked = tracked_paths(worktree)?;
+    let root = worktree.to_path_buf();
+    let ignored = ignored.clone();
+    let walker = WalkBuilder::new(worktree)
+        .standard_filters(false)
+        .follow_links(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            if entry.file_name() == ".git" {
+                return false;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&root) else {
+                return true;
+            };
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            relative.as_os_str().is_empty() || !ignored.is_ignored(relative, is_dir)
+        })
+        .build();
+
+    let mut snapshot = FileSnapshot::default();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::debug!("Skipping unreadable path in {}: {}", worktree.display(), e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(worktree) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if tracked.contains(&relative) {
+            continue;
+        }
+        if snapshot.files.len() >= MAX_SNAPSHOT_FILES {
+            snapshot.truncated = true;
+            break;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        snapshot
+            .files
+            .insert(relative, fingerprint(entry.path(), &metadata));
+    }
+    Ok(snapshot)
+}
+
+/// Changes from `baseline` to `current`, sorted by path. A file missing from `current` that
+/// is still on disk was committed rather than deleted. New files aren't reported when either
+/// snapshot was cut short, as they may only have been left out of the baseline.
+pub fn compare(
+    baseline: &FileSnapshot,
+    current: &FileSnapshot,
+    worktree: &Path,
+) -> Vec<UntrackedChange> {
+    let complete = !baseline.truncated && !current.truncated;
+    let mut changes = Vec::new();
+
+    for (path, after) in &current.files {
+        let kind = match baseline.files.get(path) {
+            None if complete => UntrackedChangeKind::Created,
+            Some(before) if before.differs_from(after) => UntrackedChangeKind::Modified,
+            _ => continue,
+        };
+        changes.push(UntrackedChange {
+            path: path.clone(),
+            kind,
+        });
+    }
+    for path in baseline.files.keys() {
+        if !current.files.contains_key(path) && !worktree.join(path).exists() {
+            changes.push(UntrackedChange {
+                path: path.clone(),
+                kind: UntrackedChangeKind::Deleted,
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Snapshot an attempt's untracked files before its agent first runs. Later runs keep the
+/// first baseline, so follow-ups report everything changed since setup.
+pub async fn record_baseline(
+    pool: &SqlitePool,
+    attempt_id: Uuid,
+    worktree_path: &str,
+    ignored: PathIgnore,
+) -> Result<(), UntrackedChangesError> {
+    if UntrackedChangeJournal::has_baseline(pool, attempt_id).await? {
+        return Ok(());
+    }
+
+    let worktree = PathBuf::from(worktree_path);
+    let snapshot =
+        tokio::task::spawn_blocking(move || take_snapshot(&worktree, &ignored)).await??;
+    UntrackedChangeJournal::save_baseline(pool, attempt_id, &snapshot).await?;
+    Ok(())
+}
+
+/// Compare an attempt's untracked files with its baseline after an execution. Returns the
+/// number of changes, or `None` when the attempt has no baseline.
+pub async fn record_changes(
+    pool: &SqlitePool,
+    attempt_id: Uuid,
+    worktree_path: &str,
+    ignored: PathIgnore,
+) -> Result<Option<usize>, UntrackedChangesError> {
+    let Some(baseline) = UntrackedChangeJournal::find_baseline(pool, attempt_id).await? else {
+        return Ok(None);
+    };
+
+    let worktree = PathBuf::from(worktree_path);
+    let (changes, truncated) = tokio::task::spawn_blocking(move || {
+        let current = take_snapshot(&worktree, &ignored)?;
+        Ok::<_, UntrackedChangesError>((compare(&baseline, &current, &worktree), current.truncated))
+    })
+    .await??;
+    UntrackedChangeJournal::save_changes(pool, attempt_id, &changes, truncated).await?;
+    Ok(Some(changes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_untracked_changes_skip_tracked_ignored_and_committed_files() {
+        let dir = TempDir::new().unwrap();
+        let worktree = dir.path();
+        let repo = Repository::init(worktree).unwrap();
+        fs::write(
+            worktree.join(".gitignore"),
+            ".env.local\nlocal.db\ncache/\n",
+        )
+        .unwrap();
+        fs::write(worktree.join("app.rs"), "fn main() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(".gitignore")).unwrap();
+        index.add_path(Path::new("app.rs")).unwrap();
+        index.write().unwrap();
+
+        fs::write(worktree.join("local.db"), "v1").unwrap();
+        fs::create_dir(worktree.join("cache")).unwrap();
+        fs::write(worktree.join("cache/generated.json"), "{}").unwrap();
+        fs::write(worktree.join("notes.txt"), "draft").unwrap();
+        fs::create_dir_all(worktree.join("web/node_modules/pkg")).unwrap();
+        fs::write(worktree.join("web/node_modules/pkg/index.js"), "a").unwrap();
+
+        let ignored = PathIgnore::new(&["node_modules/".to_string()]);
+        let baseline = take_snapshot(worktree, &ignored).unwrap();
+        assert_eq!(
+            baseline.files.keys().collect::<Vec<_>>(),
+            vec!["cache/generated.json", "local.db", "notes.txt"]
+        );
+
+        // What an execution might do: tracked and ignored paths change too, and the
+        // untracked note gets committed
+        fs::write(worktree.join("app.rs"), "fn main() { run() }\n").unwrap();
+        fs::write(worktree.join("web/node_modules/pkg/index.js"), "b").unwrap();
+        fs::write(worktree.join(".env.local"), "API_URL=http://localhost").unwrap();
+        fs::write(worktree.join("local.db"), "v2").unwrap();
+        fs::remove_file(worktree.join("cache/generated.json")).unwrap();
+        index.add_path(Path::new("notes.txt")).unwrap();
+        index.write().unwrap();
+
+        let current = take_snapshot(worktree, &ignored).unwrap();
+        let changes = compare(&baseline, &current, worktree);
+        assert_eq!(
+            changes,
+            vec![
+                UntrackedChange {
+                    path: ".env.local".to_string(),
+                    kind: UntrackedChangeKind::Created,
+                },
+                UntrackedChange {
+                    path: "cache/generated.json".to_string(),
+                    kind: UntrackedChangeKind::Deleted,
+                },
+                UntrackedChange {
+                    path: "local.db".to_string(),
+                    kind: UntrackedChangeKind::Modified,
+                },
+            ]
+        );
+    }
+}
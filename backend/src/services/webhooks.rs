@@ -0,0 +1,468 @@
+//! Outgoing webhooks for attempt lifecycle events
+//!
+//! Events are written to a delivery queue when they happen, one row per subscribed endpoint,
+//! and sent by the `webhook_delivery` job. Each endpoint's deliveries go out one at a time, and
+//! a delivery waits for earlier ones about the same attempt, so consumers see an attempt's
+//! events in order. Failed deliveries are retried with exponential backoff until they become
+//! dead letters, which can be redelivered by hand.
+//!
+//! Every event is also sent on an in-process channel, whether or not any endpoint subscribes
+//! to it, for code that waits on attempts.
+
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        execution_process::ExecutionProcess,
+        task::Task,
+        task_attempt::{TaskAttempt, TaskAttemptStatus},
+        webhook::{WebhookDelivery, WebhookEndpoint, WebhookEventType, WebhookTarget},
+    },
+    utils::{retry::backoff_delay, secrets},
+};
+
+/// Version of the payload schema, bumped on incompatible changes
+pub const WEBHOOK_SCHEMA_VERSION: u32 = 1;
+/// Tries before a delivery becomes a dead letter
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 8;
+/// Name of the background job that sends queued deliveries
+pub const DELIVERY_JOB: &str = "webhook_delivery";
+pub const DELIVERY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Due deliveries fetched at a time
+const DELIVERY_BATCH_SIZE: i64 = 100;
+/// Batches sent in one run, so a run ends even while events keep coming
+const MAX_BATCHES_PER_RUN: usize = 20;
+
+pub const EVENT_HEADER: &str = "X-Codecommand-Event";
+pub const DELIVERY_HEADER: &str = "X-Codecommand-Delivery";
+pub const TIMESTAMP_HEADER: &str = "X-Codecommand-Timestamp";
+/// `sha256=` and the hex HMAC-SHA256 of `{timestamp}.{body}`, keyed with the endpoint's secret
+pub const SIGNATURE_HEADER: &str = "X-Codecommand-Signature";
+
+/// Events kept for slow in-process subscribers before they start missing some
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+lazy_static::lazy_static! {
+    static ref EVENTS: broadcast::Sender<AttemptEvent> =
+        broadcast::channel(EVENT_CHANNEL_CAPACITY).0;
+}
+
+/// An attempt lifecycle event, as sent to in-process subscribers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttemptEvent {
+    pub attempt_id: Uuid,
+    pub event: WebhookEventType,
+}
+
+/// Receive the attempt events emitted from now on. Receivers that fall more than
+/// `EVENT_CHANNEL_CAPACITY` events behind get `RecvError::Lagged` and should re-check
+/// whatever they're watching.
+pub fn subscribe() -> broadcast::Receiver<AttemptEvent> {
+    EVENTS.subscribe()
+}
+
+/// Body of every webhook request
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct WebhookPayload {
+    pub schema_version: u32,
+    /// Same for every endpoint and retry, so consumers can drop duplicates
+    pub event_id: Uuid,
+    pub event: WebhookEventType,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub occurred_at: DateTime<Utc>,
+    pub project_id: Uuid,
+    pub task_id: Uuid,
+    pub attempt_id: Uuid,
+    /// The activity status behind setup and executor events
+    pub status: Option<TaskAttemptStatus>,
+    pub branch: String,
+    #[serde(with = "crate::utils::rfc3339")]
+    pub attempt_created_at: DateTime<Utc>,
+}
+
+/// `sha256=<hex>` signature of a delivery sent at `timestamp` (Unix seconds)
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body.as_bytes());
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+/// Wait before retrying a delivery that has failed `attempts` times; `None` once it has used
+/// up its attempts
+pub fn retry_delay(attempts: u32) -> Option<Duration> {
+    (attempts < MAX_DELIVERY_ATTEMPTS).then(|| {
+        let retry = u8::try_from(attempts.saturating_sub(1)).unwrap_or(u8::MAX);
+        backoff_delay(INITIAL_RETRY_DELAY, MAX_RETRY_DELAY, retry)
+    })
+}
+
+/// Queue `event` about an attempt for the endpoints subscribed to it, and tell in-process
+/// subscribers. Sending happens in the background; a failure to queue is logged rather than
+/// failing what triggered the event.
+pub async fn emit(
+    pool: &SqlitePool,
+    event: WebhookEventType,
+    attempt_id: Uuid,
+    status: Option<TaskAttemptStatus>,
+) {
+    if let Err(e) = queue_event(pool, event, attempt_id, status).await {
+        tracing::warn!(
+            "Failed to queue {} webhooks for attempt {}: {}",
+            event.as_str(),
+            attempt_id,
+            e
+        );
+    }
+    // Fails only when nobody is subscribed
+    let _ = EVENTS.send(AttemptEvent { attempt_id, event });
+}
+
+/// `emit` for an attempt activity recorded against an execution process
+pub async fn emit_for_process(
+    pool: &SqlitePool,
+    execution_process_id: Uuid,
+    status: &TaskAttemptStatus,
+) {
+    match ExecutionProcess::find_by_id(pool, execution_process_id).await {
+        Ok(Some(process)) => {
+            emit(
+                pool,
+                WebhookEventType::for_status(status),
+                process.task_attempt_id,
+                Some(status.clone()),
+            )
+            .await
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(
+            "Failed to look up execution process {} for webhooks: {}",
+            execution_process_id,
+            e
+        ),
+    }
+}
+
+async fn queue_event(
+    pool: &SqlitePool,
+    event: WebhookEventType,
+    attempt_id: Uuid,
+    status: Option<TaskAttemptStatus>,
+) -> Result<usize, sqlx::Error> {
+    let occurred_at = Utc::now();
+    let endpoints: Vec<_> = WebhookEndpoint::find_all(pool)
+        .await?
+        .into_iter()
+        .filter(|endpoint| endpoint.subscribes_to(event))
+        .collect();
+    if endpoints.is_empty() {
+        return Ok(0);
+    }
+
+    let Some(attempt) = TaskAttempt::find_by_id(pool, attempt_id).await? else {
+        return Ok(0);
+    };
+    let Some(task) = Task::find_by_id(pool, attempt.task_id).await? else {
+        return Ok(0);
+    };
+
+    let payload = WebhookPayload {
+        schema_version: WEBHOOK_SCHEMA_VERSION,
+        event_id: Uuid::new_v4(),
+        event,
+        occurred_at,
+        project_id: task.project_id,
+        task_id: task.id,
+        attempt_id,
+        status,
+        branch: attempt.branch,
+        attempt_created_at: attempt.created_at,
+    };
+    let body = serde_json::to_string(&payload).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+    for endpoint in &endpoints {
+        WebhookDelivery::enqueue(
+            pool,
+            endpoint.id,
+            payload.event_id,
+            event,
+            Some(attempt_id),
+            &body,
+        )
+        .await?;
+    }
+    Ok(endpoints.len())
+}
+
+/// Sends queued deliveries; run by the job scheduler every `DELIVERY_POLL_INTERVAL`
+pub struct WebhookDispatcher {
+    pool: SqlitePool,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: SqlitePool) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { pool, client }
+    }
+
+    /// Send every delivery that is due. Endpoints are served in parallel, each from its own
+    /// queue in the order events happened.
+    pub async fn deliver_due(&self) -> Result<(), String> {
+        for _ in 0..MAX_BATCHES_PER_RUN {
+            let due = WebhookDelivery::find_due(&self.pool, DELIVERY_BATCH_SIZE)
+                .await
+                .map_err(|e| e.to_string())?;
+            if due.is_empty() {
+                break;
+            }
+
+            let mut queues: HashMap<Uuid, Vec<WebhookDelivery>> = HashMap::new();
+            for delivery in due {
+                queues
+                    .entry(delivery.endpoint_id)
+                    .or_default()
+                    .push(delivery);
+            }
+            futures_util::future::join_all(
+                queues
+                    .into_iter()
+                    .map(|(endpoint_id, deliveries)| self.deliver_queue(endpoint_id, deliveries)),
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    async fn deliver_queue(&self, endpoint_id: Uuid, deliveries: Vec<WebhookDelivery>) {
+        let target = match WebhookEndpoint::find_target(&self.pool, endpoint_id).await {
+            Ok(Some(target)) => target,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("Failed to load webhook endpoint {}: {}", endpoint_id, e);
+                return;
+            }
+        };
+        let secret = match secrets::decrypt(&target.encrypted_secret) {
+            Ok(secret) => secret,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to decrypt the secret of webhook endpoint {}: {}",
+                    endpoint_id,
+                    e
+                );
+                for delivery in &deliveries {
+                    self.record_failure(delivery, None, &format!("Couldn't sign: {}", e))
+                        .await;
+                }
+                return;
+            }
+        };
+
+        for delivery in &deliveries {
+            self.deliver(&target, &secret, delivery).await;
+        }
+    }
+
+    async fn deliver(&self, target: &WebhookTarget, secret: &str, delivery: &WebhookDelivery) {
+        let timestamp = Utc::now().timestamp();
+        let response = self
+            .client
+            .post(&target.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &delivery.event_type)
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                sign_payload(secret, timestamp, &delivery.payload),
+            )
+            .body(delivery.payload.clone())
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => {
+                if let Err(e) = WebhookDelivery::mark_delivered(
+                    &self.pool,
+                    delivery.id,
+                    response.status().as_u16(),
+                )
+                .await
+                {
+                    tracing::error!("Failed to record webhook delivery {}: {}", delivery.id, e);
+                }
+            }
+            Ok(response) => {
+                let status = response.status();
+                self.record_failure(
+                    delivery,
+                    Some(status.as_u16()),
+                    &format!("Endpoint responded with {}", status),
+                )
+                .await;
+            }
+            Err(e) => self.record_failure(delivery, None, &e.to_string()).await,
+        }
+    }
+
+    async fn record_failure(
+        &self,
+        delivery: &WebhookDelivery,
+        response_status: Option<u16>,
+        error: &str,
+    ) {
+        let retry_in = retry_delay(delivery.attempts + 1);
+        if retry_in.is_none() {
+            tracing::warn!(
+                "Webhook delivery {} to endpoint {} failed {} times and was dead-lettered: {}",
+                delivery.id,
+                delivery.endpoint_id,
+                MAX_DELIVERY_ATTEMPTS,
+                error
+            );
+        }
+        if let Err(e) =
+            WebhookDelivery::mark_failed(&self.pool, delivery.id, response_status, error, retry_in)
+                .await
+        {
+            tracing::error!("Failed to record webhook delivery {}: {}", delivery.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::webhook::WebhookDeliveryStatus,
+        test_utils::{seeded_pool, Seeded},
+    };
+
+    #[test]
+    fn test_signature_covers_timestamp_and_body() {
+        let signature = sign_payload("secret", 1_700_000_000, r#"{"event":"attempt.created"}"#);
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert_eq!(
+            signature,
+            sign_payload("secret", 1_700_000_000, r#"{"event":"attempt.created"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign_payload("secret", 1_700_000_001, r#"{"event":"attempt.created"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign_payload("other", 1_700_000_000, r#"{"event":"attempt.created"}"#)
+        );
+
+        assert_eq!(retry_delay(1), Some(INITIAL_RETRY_DELAY));
+        assert_eq!(retry_delay(2), Some(INITIAL_RETRY_DELAY * 2));
+        assert_eq!(
+            retry_delay(MAX_DELIVERY_ATTEMPTS - 1),
+            Some(INITIAL_RETRY_DELAY * 64)
+        );
+        assert_eq!(retry_delay(MAX_DELIVERY_ATTEMPTS), None);
+    }
+
+    #[tokio::test]
+    async fn test_deliveries_queue_per_endpoint_in_attempt_order() {
+        let (
+            pool,
+            Seeded {
+                project_id,
+                attempt_id,
+                ..
+            },
+        ) = seeded_pool().await;
+
+        let all = WebhookEndpoint::create(&pool, "https://example.com/all", "enc", &[], true)
+            .await
+            .unwrap();
+        let merges = WebhookEndpoint::create(
+            &pool,
+            "https://example.com/merges",
+            "enc",
+            &[WebhookEventType::AttemptMerged],
+            true,
+        )
+        .await
+        .unwrap();
+
+        emit(&pool, WebhookEventType::AttemptCreated, attempt_id, None).await;
+        emit(&pool, WebhookEventType::AttemptMerged, attempt_id, None).await;
+
+        let all_log = WebhookDelivery::find_by_endpoint_id(&pool, all.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(all_log.len(), 2);
+        let merges_log = WebhookDelivery::find_by_endpoint_id(&pool, merges.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(merges_log.len(), 1);
+        let payload: serde_json::Value = serde_json::from_str(&merges_log[0].payload).unwrap();
+        assert_eq!(payload["event"], "attempt.merged");
+        assert_eq!(payload["schema_version"], WEBHOOK_SCHEMA_VERSION);
+        assert_eq!(payload["project_id"], project_id.to_string());
+        assert_eq!(payload["attempt_id"], attempt_id.to_string());
+
+        // The merge event for the catch-all endpoint waits behind the creation event
+        let due = WebhookDelivery::find_due(&pool, 10).await.unwrap();
+        let due_events: Vec<_> = due
+            .iter()
+            .map(|d| (d.endpoint_id, d.event_type.as_str()))
+            .collect();
+        assert_eq!(
+            due_events,
+            vec![(all.id, "attempt.created"), (merges.id, "attempt.merged")]
+        );
+
+        // Once the first one is a dead letter, the next one goes out
+        let created = &due[0];
+        WebhookDelivery::mark_failed(&pool, created.id, Some(500), "boom", None)
+            .await
+            .unwrap();
+        let due = WebhookDelivery::find_due(&pool, 10).await.unwrap();
+        assert!(due
+            .iter()
+            .any(|d| d.endpoint_id == all.id && d.event_type == "attempt.merged"));
+
+        assert!(WebhookDelivery::redeliver(&pool, all.id, created.id)
+            .await
+            .unwrap());
+        let log = WebhookDelivery::find_by_endpoint_id(&pool, all.id, 10)
+            .await
+            .unwrap();
+        let redelivered = log.iter().find(|d| d.id == created.id).unwrap();
+        assert_eq!(redelivered.status, WebhookDeliveryStatus::Pending);
+        assert_eq!(redelivered.attempts, 0);
+        assert!(!WebhookDelivery::redeliver(&pool, all.id, created.id)
+            .await
+            .unwrap());
+    }
+}
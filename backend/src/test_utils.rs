@@ -0,0 +1,95 @@
+//! Fixtures shared by tests that need a database
+
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use uuid::Uuid;
+
+/// Rows inserted by `seeded_pool`
+#[derive(Debug, Clone, Copy)]
+pub struct Seeded {
+    pub project_id: Uuid,
+    pub task_id: Uuid,
+    pub attempt_id: Uuid,
+}
+
+/// An in-memory database with all migrations applied
+pub async fn memory_pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
+}
+
+/// An in-memory database holding one project, one task in it and one attempt of that task,
+/// none of them backed by anything on disk
+pub async fn seeded_pool() -> (SqlitePool, Seeded) {
+    let pool = memory_pool().await;
+    let project_id = insert_project(&pool, "/tmp/p").await;
+    let task_id = insert_task(&pool, project_id, "t").await;
+    let attempt_id = insert_attempt(&pool, task_id, "/tmp/w", "b").await;
+    (
+        pool,
+        Seeded {
+            project_id,
+            task_id,
+            attempt_id,
+        },
+    )
+}
+
+pub async fn insert_project(pool: &SqlitePool, git_repo_path: &str) -> Uuid {
+    let project_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO projects (id, name, git_repo_path) VALUES ($1, 'p', $2)")
+        .bind(project_id)
+        .bind(git_repo_path)
+        .execute(pool)
+        .await
+        .unwrap();
+    project_id
+}
+
+pub async fn insert_task(pool: &SqlitePool, project_id: Uuid, title: &str) -> Uuid {
+    let task_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO tasks (id, project_id, title) VALUES ($1, $2, $3)")
+        .bind(task_id)
+        .bind(project_id)
+        .bind(title)
+        .execute(pool)
+        .await
+        .unwrap();
+    task_id
+}
+
+/// An attempt of `task_id` based on `main`, the column default
+pub async fn insert_attempt(
+    pool: &SqlitePool,
+    task_id: Uuid,
+    worktree_path: &str,
+    branch: &str,
+) -> Uuid {
+    insert_attempt_on(pool, task_id, worktree_path, branch, "main").await
+}
+
+pub async fn insert_attempt_on(
+    pool: &SqlitePool,
+    task_id: Uuid,
+    worktree_path: &str,
+    branch: &str,
+    base_branch: &str,
+) -> Uuid {
+    let attempt_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO task_attempts (id, task_id, worktree_path, branch, base_branch) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(attempt_id)
+    .bind(task_id)
+    .bind(worktree_path)
+    .bind(branch)
+    .bind(base_branch)
+    .execute(pool)
+    .await
+    .unwrap();
+    attempt_id
+}
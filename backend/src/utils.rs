@@ -2,6 +2,13 @@ use std::{env, sync::OnceLock};
 
 use directories::ProjectDirs;
 
+pub mod executable;
+pub mod output_capture;
+pub mod path_ignore;
+pub mod process_priority;
+pub mod retry;
+pub mod rfc3339;
+pub mod secrets;
 pub mod shell;
 pub mod text;
 pub mod worktree_manager;
@@ -52,6 +59,37 @@ pub fn config_path() -> std::path::PathBuf {
     asset_dir().join("config.json")
 }
 
+/// File holding the port the server is listening on, for process managers and test harnesses
+pub fn port_file_path() -> std::path::PathBuf {
+    asset_dir().join("server.port")
+}
+
+/// Record the bound port as plain text. Written to a temporary file and renamed into place so
+/// readers never see a partial number.
+pub fn write_port_file(port: u16) -> std::io::Result<()> {
+    let path = port_file_path();
+    let tmp_path = path.with_extension("port.tmp");
+    std::fs::write(&tmp_path, port.to_string())?;
+    std::fs::rename(&tmp_path, &path)
+}
+
+/// Port written by a running server, if any
+pub fn read_port_file() -> Option<u16> {
+    std::fs::read_to_string(port_file_path())
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub fn remove_port_file() {
+    if let Err(e) = std::fs::remove_file(port_file_path()) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!("Failed to remove port file: {}", e);
+        }
+    }
+}
+
 pub fn cache_dir() -> std::path::PathBuf {
     let proj = if cfg!(debug_assertions) {
         ProjectDirs::from("ai", "bloop-dev", env!("CARGO_PKG_NAME"))
@@ -105,8 +143,27 @@ pub async fn get_powershell_script(
     Ok(script_path)
 }
 
-/// Open URL in browser with WSL2 support
+/// Whether there's no display to open a browser on, like on a server or over SSH. WSL2
+/// opens the Windows browser, so it never counts as headless.
+pub fn is_headless() -> bool {
+    cfg!(target_os = "linux")
+        && !is_wsl2()
+        && std::env::var_os("DISPLAY").is_none()
+        && std::env::var_os("WAYLAND_DISPLAY").is_none()
+}
+
+/// Open URL in browser with WSL2 support, retrying briefly if the launcher fails
 pub async fn open_browser(url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    retry::retry_with_backoff(
+        || async { open_browser_once(url) },
+        3,
+        std::time::Duration::from_millis(250),
+        std::time::Duration::from_secs(1),
+    )
+    .await
+}
+
+fn open_browser_once(url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     if is_wsl2() {
         // In WSL2, use PowerShell to open the browser
         tokio::process::Command::new("powershell.exe")
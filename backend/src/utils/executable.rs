@@ -0,0 +1,111 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::shell::shell_quote;
+
+/// Finds executables and npx packages the way a spawned shell would, so missing tools can be
+/// reported before anything is started
+#[derive(Debug, Clone)]
+pub struct ExecutableLookup {
+    path: Option<OsString>,
+    /// npm's offline mode: npx can only run packages that are already cached
+    npm_offline: bool,
+    npm_cache: Option<PathBuf>,
+}
+
+fn env_flag(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| matches!(value.trim(), "true" | "1"))
+}
+
+impl ExecutableLookup {
+    pub fn from_env() -> Self {
+        Self {
+            path: env::var_os("PATH"),
+            npm_offline: env_flag("npm_config_offline") || env_flag("NPM_CONFIG_OFFLINE"),
+            npm_cache: env::var_os("npm_config_cache")
+                .map(PathBuf::from)
+                .or_else(default_npm_cache),
+        }
+    }
+
+    pub fn new(path: Option<OsString>, npm_offline: bool, npm_cache: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            npm_offline,
+            npm_cache,
+        }
+    }
+
+    pub fn npm_offline(&self) -> bool {
+        self.npm_offline
+    }
+
+    /// Full path of `program` on PATH
+    pub fn find(&self, program: &str) -> Option<PathBuf> {
+        let path = self.path.as_ref()?;
+        env::split_paths(path).find_map(|dir| {
+            executable_names(program)
+                .into_iter()
+                .map(|name| dir.join(name))
+                .find(|candidate| candidate.is_file())
+        })
+    }
+
+    /// Start of the command line running an agent CLI: the binary configured for it, else
+    /// `program` when it's installed, else `npx_command`, which leaves finding it to npx
+    pub fn cli_command(
+        &self,
+        configured: Option<&str>,
+        program: &str,
+        npx_command: &str,
+    ) -> String {
+        match configured {
+            Some(path) => shell_quote(path),
+            None if self.find(program).is_some() => program.to_string(),
+            None => npx_command.to_string(),
+        }
+    }
+
+    /// Whether npx has `package` in its cache and can run it without the network
+    pub fn npx_cached(&self, package: &str) -> bool {
+        let Some(cache) = &self.npm_cache else {
+            return false;
+        };
+        let Ok(entries) = std::fs::read_dir(cache.join("_npx")) else {
+            return false;
+        };
+
+        entries
+            .filter_map(Result::ok)
+            .any(|entry| package_dir(&entry.path().join("node_modules"), package).is_dir())
+    }
+}
+
+fn default_npm_cache() -> Option<PathBuf> {
+    if cfg!(windows) {
+        dirs::data_local_dir().map(|dir| dir.join("npm-cache"))
+    } else {
+        dirs::home_dir().map(|home| home.join(".npm"))
+    }
+}
+
+fn executable_names(program: &str) -> Vec<String> {
+    if cfg!(windows) {
+        ["exe", "cmd", "bat"]
+            .iter()
+            .map(|extension| format!("{}.{}", program, extension))
+            .collect()
+    } else {
+        vec![program.to_string()]
+    }
+}
+
+/// `node_modules/@scope/name` for a scoped package
+fn package_dir(node_modules: &Path, package: &str) -> PathBuf {
+    package
+        .split('/')
+        .fold(node_modules.to_path_buf(), |dir, part| dir.join(part))
+}
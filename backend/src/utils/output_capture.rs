@@ -0,0 +1,200 @@
+//! Byte-oriented capture of child process output
+//!
+//! Executors may print invalid UTF-8, control characters and megabytes without a newline.
+//! Output is read as bytes and only turned into text once a UTF-8 sequence is complete, so
+//! a character split across two reads isn't garbled.
+
+/// Longest line kept whole; longer runs of output without a newline get a synthetic break
+pub const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Size of each read from a child process's output
+pub const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Control characters kept in captured output: line breaks, tabs and the escape that starts
+/// ANSI sequences. Others, NUL in particular, are dropped: they garble the log view and NUL
+/// cuts stored text short.
+fn is_kept_control(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\t' | '\x1b')
+}
+
+/// Decodes a stream of bytes as UTF-8, holding back a sequence split across reads until the
+/// rest of it arrives. Invalid bytes become U+FFFD and unwanted control characters are
+/// dropped; both are counted.
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+    replaced_bytes: usize,
+}
+
+impl Utf8Decoder {
+    /// Text of the bytes read so far, minus an incomplete sequence at the end
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let pending = std::mem::take(&mut self.pending);
+
+        let mut text = String::with_capacity(pending.len());
+        let mut rest = pending.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    self.push_text(&mut text, valid);
+                    rest = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                    self.push_text(
+                        &mut text,
+                        std::str::from_utf8(valid).expect("checked by from_utf8"),
+                    );
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            self.replaced_bytes += len;
+                            rest = &invalid[len..];
+                        }
+                        // The sequence may be completed by the next read
+                        None => {
+                            rest = invalid;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending = rest.to_vec();
+        text
+    }
+
+    /// Text of what's left once the stream has ended; an incomplete sequence is replaced
+    pub fn finish(&mut self) -> String {
+        if self.pending.is_empty() {
+            return String::new();
+        }
+        self.replaced_bytes += self.pending.len();
+        self.pending.clear();
+        char::REPLACEMENT_CHARACTER.to_string()
+    }
+
+    /// Bytes replaced or dropped since the last call
+    pub fn take_replaced_bytes(&mut self) -> usize {
+        std::mem::take(&mut self.replaced_bytes)
+    }
+
+    fn push_text(&mut self, text: &mut String, valid: &str) {
+        for c in valid.chars() {
+            if c.is_control() && !is_kept_control(c) {
+                self.replaced_bytes += c.len_utf8();
+            } else {
+                text.push(c);
+            }
+        }
+    }
+}
+
+/// Splits decoded output into lines, newline included. A line longer than
+/// [`MAX_LINE_BYTES`] is broken up so a single runaway line can't hold everything back.
+#[derive(Debug, Default)]
+pub struct OutputCapture {
+    decoder: Utf8Decoder,
+    partial: String,
+    /// Length of `partial` already searched for a newline
+    scanned: usize,
+}
+
+impl OutputCapture {
+    /// Lines completed by the next bytes of output
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        let text = self.decoder.decode(bytes);
+        self.partial.push_str(&text);
+        self.take_lines()
+    }
+
+    /// The remaining lines once the stream has ended; the last may have no newline
+    pub fn finish(&mut self) -> Vec<String> {
+        let text = self.decoder.finish();
+        self.partial.push_str(&text);
+        let mut lines = self.take_lines();
+        if !self.partial.is_empty() {
+            lines.push(std::mem::take(&mut self.partial));
+        }
+        self.scanned = 0;
+        lines
+    }
+
+    /// Bytes replaced or dropped since the last call
+    pub fn take_replaced_bytes(&mut self) -> usize {
+        self.decoder.take_replaced_bytes()
+    }
+
+    fn take_lines(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        let mut skip = self.scanned;
+        loop {
+            let rest = &self.partial[start..];
+            match rest[skip..].find('\n').map(|i| skip + i) {
+                Some(i) if i <= MAX_LINE_BYTES => {
+                    lines.push(rest[..=i].to_string());
+                    start += i + 1;
+                }
+                _ if rest.len() > MAX_LINE_BYTES => {
+                    let mut end = MAX_LINE_BYTES;
+                    while !rest.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    lines.push(format!("{}\n", &rest[..end]));
+                    start += end;
+                }
+                _ => break,
+            }
+            skip = 0;
+        }
+        self.partial.drain(..start);
+        self.scanned = self.partial.len();
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_capture_handles_split_invalid_and_runaway_output() {
+        let mut capture = OutputCapture::default();
+        // "é" split across two reads stays one character
+        assert!(capture.push(b"caf\xc3").is_empty());
+        assert_eq!(capture.push(b"\xa9\r\n"), vec!["café\r\n"]);
+        assert_eq!(capture.take_replaced_bytes(), 0);
+
+        // Windows-1252 "é" and a NUL are replaced and counted, ANSI colours are kept
+        assert_eq!(
+            capture.push(b"\x1b[32mr\xe9sum\xe9\x00\x1b[0m\n"),
+            vec!["\x1b[32mr\u{fffd}sum\u{fffd}\x1b[0m\n"]
+        );
+        assert_eq!(capture.take_replaced_bytes(), 3);
+
+        // 10 MB without a newline is broken into lines of at most MAX_LINE_BYTES
+        let chunk = vec![b'x'; READ_CHUNK_SIZE];
+        let mut lines = Vec::new();
+        for _ in 0..(10 * 1024 * 1024 / READ_CHUNK_SIZE) {
+            lines.extend(capture.push(&chunk));
+        }
+        lines.extend(capture.push(b"\xf0\x9f"));
+        lines.extend(capture.finish());
+        assert_eq!(lines.len(), 11);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_BYTES + 1));
+        assert_eq!(
+            lines
+                .iter()
+                .map(|line| line.trim_end_matches('\n').len())
+                .sum::<usize>(),
+            10 * 1024 * 1024 + char::REPLACEMENT_CHARACTER.len_utf8()
+        );
+        // The truncated emoji at the end of the stream is replaced
+        assert!(lines.last().unwrap().ends_with('\u{fffd}'));
+        assert_eq!(capture.take_replaced_bytes(), 2);
+    }
+}
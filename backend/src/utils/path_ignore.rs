@@ -0,0 +1,79 @@
+//! Paths left out of attempt diffs and the untracked change journal
+
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Ignored out of the box: dependency, build and cache directories that are large and rarely
+/// what an attempt is about
+pub const DEFAULT_IGNORED_PATHS: &[&str] = &[
+    "node_modules/",
+    "target/",
+    ".venv/",
+    "venv/",
+    "__pycache__/",
+    ".next/",
+    ".turbo/",
+    ".gradle/",
+];
+
+/// Matcher for the configured ignore patterns, in .gitignore syntax and relative to the
+/// worktree
+#[derive(Debug, Clone)]
+pub struct PathIgnore {
+    matcher: Gitignore,
+}
+
+impl PathIgnore {
+    /// Invalid patterns are skipped with a warning rather than failing the diff
+    pub fn new(patterns: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns {
+            if let Err(e) = builder.add_line(None, pattern) {
+                tracing::warn!("Skipping invalid ignore pattern '{}': {}", pattern, e);
+            }
+        }
+        let matcher = builder.build().unwrap_or_else(|e| {
+            tracing::warn!("Failed to build ignore patterns: {}", e);
+            Gitignore::empty()
+        });
+        Self { matcher }
+    }
+
+    /// Whether `path`, relative to the worktree, or any directory above it is ignored
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matcher
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+    }
+}
+
+impl Default for PathIgnore {
+    /// Ignores nothing
+    fn default() -> Self {
+        Self {
+            matcher: Gitignore::empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_patterns_ignore_dependency_directories_at_any_depth() {
+        let patterns: Vec<String> = DEFAULT_IGNORED_PATHS
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let ignore = PathIgnore::new(&patterns);
+
+        assert!(ignore.is_ignored(Path::new("node_modules"), true));
+        assert!(ignore.is_ignored(Path::new("web/node_modules/react/index.js"), false));
+        assert!(ignore.is_ignored(Path::new("target/debug/app"), false));
+        assert!(!ignore.is_ignored(Path::new("src/target.rs"), false));
+        assert!(!ignore.is_ignored(Path::new(".env.local"), false));
+        assert!(!PathIgnore::default().is_ignored(Path::new("node_modules"), true));
+    }
+}
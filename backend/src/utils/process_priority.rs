@@ -0,0 +1,129 @@
+//! CPU and IO priority of spawned process groups, and pausing them without killing them
+
+use std::io;
+
+use crate::models::config::ProcessPriority;
+
+/// Give a freshly spawned process group `priority`. `pid` is the group leader, whose
+/// children inherit the priority. Priorities can only be lowered without privileges, so
+/// `Normal` leaves the process as it was spawned.
+#[cfg(unix)]
+pub fn set_priority(pid: u32, priority: ProcessPriority) -> io::Result<()> {
+    let nice = match priority {
+        ProcessPriority::Normal => return Ok(()),
+        ProcessPriority::Low => 10,
+        ProcessPriority::Idle => 19,
+    };
+    // The leader's pid is the group id
+    if unsafe { libc::setpriority(libc::PRIO_PGRP as _, pid as libc::id_t, nice) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    #[cfg(target_os = "linux")]
+    set_io_priority(pid, priority)?;
+    Ok(())
+}
+
+/// `ionice` for the process group: the lowest best-effort level for `Low`, the idle class for
+/// `Idle`
+#[cfg(target_os = "linux")]
+fn set_io_priority(pgid: u32, priority: ProcessPriority) -> io::Result<()> {
+    const IOPRIO_WHO_PGRP: libc::c_int = 2;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    const IOPRIO_CLASS_BE: libc::c_int = 2;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+
+    let ioprio = match priority {
+        ProcessPriority::Normal => return Ok(()),
+        ProcessPriority::Low => (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7,
+        ProcessPriority::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+    };
+    if unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PGRP,
+            pgid as libc::c_int,
+            ioprio,
+        )
+    } == -1
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Stop every process in the group until `resume`
+#[cfg(unix)]
+pub fn pause(pid: u32) -> io::Result<()> {
+    use nix::{
+        sys::signal::{killpg, Signal},
+        unistd::Pid,
+    };
+
+    killpg(Pid::from_raw(pid as i32), Signal::SIGSTOP)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn resume(pid: u32, _priority: ProcessPriority) -> io::Result<()> {
+    use nix::{
+        sys::signal::{killpg, Signal},
+        unistd::Pid,
+    };
+
+    killpg(Pid::from_raw(pid as i32), Signal::SIGCONT)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn priority_class(priority: ProcessPriority) -> u32 {
+    use windows_sys::Win32::System::Threading::{
+        BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+    };
+
+    match priority {
+        ProcessPriority::Normal => NORMAL_PRIORITY_CLASS,
+        ProcessPriority::Low => BELOW_NORMAL_PRIORITY_CLASS,
+        ProcessPriority::Idle => IDLE_PRIORITY_CLASS,
+    }
+}
+
+#[cfg(windows)]
+fn set_priority_class(pid: u32, class: u32) -> io::Result<()> {
+    use windows_sys::Win32::{
+        Foundation::CloseHandle,
+        System::Threading::{OpenProcess, SetPriorityClass, PROCESS_SET_INFORMATION},
+    };
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_SET_INFORMATION, 0, pid);
+        if handle == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let set = SetPriorityClass(handle, class);
+        CloseHandle(handle);
+        if set == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Set the priority class of the process. Children started afterwards inherit below normal
+/// and idle classes.
+#[cfg(windows)]
+pub fn set_priority(pid: u32, priority: ProcessPriority) -> io::Result<()> {
+    set_priority_class(pid, priority_class(priority))
+}
+
+/// Windows can't stop a process tree from the outside, so pausing drops the process to the
+/// idle class instead
+#[cfg(windows)]
+pub fn pause(pid: u32) -> io::Result<()> {
+    set_priority(pid, ProcessPriority::Idle)
+}
+
+#[cfg(windows)]
+pub fn resume(pid: u32, priority: ProcessPriority) -> io::Result<()> {
+    set_priority(pid, priority)
+}
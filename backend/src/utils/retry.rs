@@ -0,0 +1,198 @@
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use tokio::time::sleep;
+use tracing::warn;
+
+/// Retry policy for `retry_with_config`
+#[derive(Debug)]
+pub struct RetryConfig<E> {
+    /// Total number of attempts, including the first call (at least one is always made)
+    pub max_attempts: u8,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Whether an error is transient and worth another attempt
+    pub retry_on: fn(&E) -> bool,
+}
+
+impl<E> Clone for RetryConfig<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for RetryConfig<E> {}
+
+impl<E> RetryConfig<E> {
+    pub fn new(max_attempts: u8, initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            initial_delay,
+            max_delay,
+            retry_on: |_| true,
+        }
+    }
+
+    pub fn retry_on(self, retry_on: fn(&E) -> bool) -> Self {
+        Self { retry_on, ..self }
+    }
+}
+
+/// Call `f` until it succeeds, retrying every error with exponential backoff and jitter
+pub async fn retry_with_backoff<F, Fut, T, E>(
+    f: F,
+    max_attempts: u8,
+    initial_delay: Duration,
+    max_delay: Duration,
+) -> Result<T, E>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    retry_with_config(f, &RetryConfig::new(max_attempts, initial_delay, max_delay)).await
+}
+
+/// Call `f` until it succeeds, an error is rejected by `retry_on`, or attempts run out.
+/// The last error is returned when giving up.
+pub async fn retry_with_config<F, Fut, T, E>(f: F, config: &RetryConfig<E>) -> Result<T, E>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let max_attempts = config.max_attempts.max(1);
+    let mut attempt = 1;
+
+    loop {
+        match f().await {
+            Ok(result) => return Ok(result),
+            Err(e) if attempt < max_attempts && (config.retry_on)(&e) => {
+                let delay = with_jitter(backoff_delay(
+                    config.initial_delay,
+                    config.max_delay,
+                    attempt - 1,
+                ));
+                warn!(
+                    "Attempt {}/{} failed, retrying in {:?}: {}",
+                    attempt, max_attempts, delay, e
+                );
+                sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// `initial_delay * 2^retry`, capped at `max_delay`
+pub(crate) fn backoff_delay(initial_delay: Duration, max_delay: Duration, retry: u8) -> Duration {
+    2u32.checked_pow(u32::from(retry))
+        .and_then(|factor| initial_delay.checked_mul(factor))
+        .map_or(max_delay, |delay| delay.min(max_delay))
+}
+
+/// Pick a delay in `[delay / 2, delay]` so concurrent callers don't retry in lockstep
+fn with_jitter(delay: Duration) -> Duration {
+    let half = delay / 2;
+    let spread = u64::try_from(half.as_nanos()).unwrap_or(u64::MAX);
+    if spread == 0 {
+        return delay;
+    }
+
+    let random = RandomState::new().build_hasher().finish();
+    half + Duration::from_nanos(random % spread)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay_doubles_and_caps() {
+        let initial = Duration::from_millis(100);
+        let max = Duration::from_secs(1);
+
+        assert_eq!(backoff_delay(initial, max, 0), Duration::from_millis(100));
+        assert_eq!(backoff_delay(initial, max, 1), Duration::from_millis(200));
+        assert_eq!(backoff_delay(initial, max, 3), Duration::from_millis(800));
+        assert_eq!(backoff_delay(initial, max, 4), max);
+        assert_eq!(backoff_delay(initial, max, 200), max);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let delay = Duration::from_millis(500);
+        for _ in 0..100 {
+            let jittered = with_jitter(delay);
+            assert!(jittered >= delay / 2 && jittered <= delay);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let calls = AtomicU32::new(0);
+
+        let result: Result<u32, String> = retry_with_backoff(
+            || async {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                if call < 3 {
+                    Err(format!("failure {}", call))
+                } else {
+                    Ok(call)
+                }
+            },
+            5,
+            Duration::from_millis(1),
+            Duration::from_millis(2),
+        )
+        .await;
+
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+
+        let result: Result<(), String> = retry_with_backoff(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err("always fails".to_string())
+            },
+            3,
+            Duration::from_millis(1),
+            Duration::from_millis(2),
+        )
+        .await;
+
+        assert_eq!(result, Err("always fails".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_stops_immediately() {
+        let calls = AtomicU32::new(0);
+        let config = RetryConfig::new(5, Duration::from_millis(1), Duration::from_millis(2))
+            .retry_on(|e: &String| e != "fatal");
+
+        let result: Result<(), String> = retry_with_config(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err("fatal".to_string())
+            },
+            &config,
+        )
+        .await;
+
+        assert_eq!(result, Err("fatal".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::Write;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const KEY_FILE_NAME: &str = "secret.key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+/// Shortest signature prefix `verify` accepts
+const MIN_SIGNATURE_LEN: usize = 16;
+
+/// Load the local encryption key, creating it (readable by the current user only) on first use
+fn load_or_create_key() -> anyhow::Result<[u8; KEY_LEN]> {
+    let key_path = super::asset_dir().join(KEY_FILE_NAME);
+
+    if let Ok(existing) = std::fs::read(&key_path) {
+        if let Ok(key) = <[u8; KEY_LEN]>::try_from(existing.as_slice()) {
+            return Ok(key);
+        }
+        tracing::warn!(
+            "Ignoring malformed secret key at {}, generating a new one",
+            key_path.display()
+        );
+    }
+
+    let key: [u8; KEY_LEN] = Aes256Gcm::generate_key(&mut OsRng).into();
+    if let Some(parent) = key_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // Created readable by the current user only, so the key is never exposed in between
+        options.mode(0o600);
+    }
+    let mut file = options.open(&key_path)?;
+    // A malformed key file being replaced keeps whatever mode it had
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(&key)?;
+
+    Ok(key)
+}
+
+fn encrypt_with_key(key: &[u8; KEY_LEN], plaintext: &str) -> anyhow::Result<String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt secret"))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload)))
+}
+
+fn decrypt_with_key(key: &[u8; KEY_LEN], encrypted: &str) -> anyhow::Result<String> {
+    let encoded = encrypted
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .ok_or_else(|| anyhow::anyhow!("Secret is not in the expected encrypted format"))?;
+    let payload = STANDARD.decode(encoded)?;
+    if payload.len() <= NONCE_LEN {
+        anyhow::bail!("Encrypted secret is truncated");
+    }
+
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt secret; the key may have changed"))?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+fn signing_mac(key: &[u8; KEY_LEN], purpose: &str, message: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    // The purpose keeps a signature made for one kind of link from being valid for another
+    mac.update(purpose.as_bytes());
+    mac.update(&[0]);
+    mac.update(message);
+    mac
+}
+
+fn sign_with_key(key: &[u8; KEY_LEN], purpose: &str, message: &[u8]) -> Vec<u8> {
+    signing_mac(key, purpose, message)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+fn verify_with_key(key: &[u8; KEY_LEN], purpose: &str, message: &[u8], signature: &[u8]) -> bool {
+    signature.len() >= MIN_SIGNATURE_LEN
+        && signing_mac(key, purpose, message)
+            .verify_truncated_left(signature)
+            .is_ok()
+}
+
+/// Sign `message` with the local key, e.g. for links that act without a session
+pub fn sign(purpose: &str, message: &[u8]) -> anyhow::Result<Vec<u8>> {
+    Ok(sign_with_key(&load_or_create_key()?, purpose, message))
+}
+
+/// Check a signature produced by `sign` for the same purpose, possibly truncated to no less
+/// than 16 bytes
+pub fn verify(purpose: &str, message: &[u8], signature: &[u8]) -> anyhow::Result<bool> {
+    Ok(verify_with_key(
+        &load_or_create_key()?,
+        purpose,
+        message,
+        signature,
+    ))
+}
+
+/// Whether a variable or field name suggests its value is a secret, e.g. `GITHUB_TOKEN` or
+/// `db_password`
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    [
+        "TOKEN",
+        "SECRET",
+        "PASSWORD",
+        "PASSWD",
+        "API_KEY",
+        "APIKEY",
+        "PRIVATE_KEY",
+        "CREDENTIAL",
+        "AUTH",
+    ]
+    .iter()
+    .any(|marker| name.contains(marker))
+}
+
+/// Encrypt a secret (such as an OAuth token) for storage in the config file
+pub fn encrypt(plaintext: &str) -> anyhow::Result<String> {
+    encrypt_with_key(&load_or_create_key()?, plaintext)
+}
+
+/// Decrypt a secret produced by `encrypt`
+pub fn decrypt(encrypted: &str) -> anyhow::Result<String> {
+    decrypt_with_key(&load_or_create_key()?, encrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_tamper_detection() {
+        let key = [7u8; KEY_LEN];
+        let encrypted = encrypt_with_key(&key, "gho_secret").unwrap();
+
+        assert!(encrypted.starts_with(ENCRYPTED_PREFIX));
+        assert!(!encrypted.contains("gho_secret"));
+        assert_eq!(decrypt_with_key(&key, &encrypted).unwrap(), "gho_secret");
+
+        assert!(decrypt_with_key(&[8u8; KEY_LEN], &encrypted).is_err());
+        assert!(decrypt_with_key(&key, "gho_secret").is_err());
+    }
+
+    #[test]
+    fn test_signatures_are_bound_to_key_and_purpose() {
+        let key = [7u8; KEY_LEN];
+        let signature = sign_with_key(&key, "stop-link", b"attempt");
+
+        assert!(verify_with_key(&key, "stop-link", b"attempt", &signature));
+        assert!(verify_with_key(
+            &key,
+            "stop-link",
+            b"attempt",
+            &signature[..16]
+        ));
+        assert!(!verify_with_key(
+            &key,
+            "stop-link",
+            b"attempt",
+            &signature[..8]
+        ));
+        assert!(!verify_with_key(&key, "stop-link", b"other", &signature));
+        assert!(!verify_with_key(&key, "other-link", b"attempt", &signature));
+        assert!(!verify_with_key(
+            &[8u8; KEY_LEN],
+            "stop-link",
+            b"attempt",
+            &signature
+        ));
+    }
+}
@@ -17,3 +17,12 @@ pub fn get_shell_command() -> (&'static str, &'static str) {
         }
     }
 }
+
+/// Quote `value` as a single argument for the shell returned by `get_shell_command`
+pub fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
@@ -159,8 +159,6 @@ impl WorktreeManager {
     ) -> Result<(), GitError> {
         debug!("Performing cleanup for worktree: {}", worktree_name);
 
-        let git_repo_path = Self::get_git_repo_path(repo)?;
-
         // Step 1: Always try to remove worktree registration first (this may fail if not registered)
         if let Err(e) = Self::try_remove_worktree(repo, worktree_name) {
             debug!(
@@ -170,7 +168,7 @@ impl WorktreeManager {
         }
 
         // Step 2: Always force cleanup metadata directory (proactive cleanup)
-        if let Err(e) = Self::force_cleanup_worktree_metadata(&git_repo_path, worktree_name) {
+        if let Err(e) = Self::force_cleanup_worktree_metadata(repo, worktree_name) {
             debug!("Metadata cleanup failed (non-fatal): {}", e);
         }
 
@@ -294,14 +292,9 @@ impl WorktreeManager {
                     );
 
                     // Force cleanup metadata and try one more time
-                    Self::force_cleanup_worktree_metadata(&git_repo_path, &worktree_name).map_err(
-                        |e| {
-                            GitError::from_str(&format!(
-                                "Failed to cleanup worktree metadata: {}",
-                                e
-                            ))
-                        },
-                    )?;
+                    Self::force_cleanup_worktree_metadata(&repo, &worktree_name).map_err(|e| {
+                        GitError::from_str(&format!("Failed to cleanup worktree metadata: {}", e))
+                    })?;
 
                     // Try again after cleanup
                     match repo.worktree(&branch_name, &worktree_path, Some(&worktree_opts)) {
@@ -335,24 +328,13 @@ impl WorktreeManager {
         .map_err(|e| GitError::from_str(&format!("Task join error: {}", e)))?
     }
 
-    /// Get the git repository path
-    fn get_git_repo_path(repo: &Repository) -> Result<String, GitError> {
-        repo.workdir()
-            .ok_or_else(|| GitError::from_str("Repository has no working directory"))?
-            .to_str()
-            .ok_or_else(|| GitError::from_str("Repository path is not valid UTF-8"))
-            .map(|s| s.to_string())
-    }
-
     /// Force cleanup worktree metadata directory
     fn force_cleanup_worktree_metadata(
-        git_repo_path: &str,
+        repo: &Repository,
         worktree_name: &str,
     ) -> Result<(), std::io::Error> {
-        let git_worktree_metadata_path = Path::new(git_repo_path)
-            .join(".git")
-            .join("worktrees")
-            .join(worktree_name);
+        // `path()` is the `.git` directory, or the repository itself when it is bare
+        let git_worktree_metadata_path = repo.path().join("worktrees").join(worktree_name);
 
         if git_worktree_metadata_path.exists() {
             debug!(
@@ -416,6 +398,101 @@ impl WorktreeManager {
         Ok(())
     }
 
+    /// Copy a directory tree for projects without a git repository, where a worktree can't be
+    /// checked out. Paths in `skip` (such as the destination itself when it lies inside the
+    /// source) are left out; symlinks are recreated rather than followed.
+    pub fn copy_directory(
+        source: &Path,
+        destination: &Path,
+        skip: &[PathBuf],
+    ) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(destination)?;
+
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            let path = entry.path();
+            if skip.iter().any(|skipped| skipped == &path) {
+                continue;
+            }
+
+            let target = destination.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                Self::copy_directory(&path, &target, skip)?;
+            } else if file_type.is_symlink() {
+                let link = std::fs::read_link(&path)?;
+                #[cfg(unix)]
+                std::os::unix::fs::symlink(&link, &target)?;
+                #[cfg(windows)]
+                {
+                    if path.is_dir() {
+                        std::os::windows::fs::symlink_dir(&link, &target)?;
+                    } else {
+                        std::os::windows::fs::symlink_file(&link, &target)?;
+                    }
+                }
+            } else {
+                std::fs::copy(&path, &target)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run `git worktree repair` for a worktree that was moved to `worktree_path`, so that the
+    /// repository and the worktree point at each other again. Returns whether git succeeded.
+    pub async fn repair_worktree(repo_path: &str, worktree_path: &Path) -> bool {
+        let output = tokio::process::Command::new("git")
+            .arg("worktree")
+            .arg("repair")
+            .arg(worktree_path)
+            .current_dir(repo_path)
+            .output()
+            .await;
+
+        match output {
+            Ok(output) if output.status.success() => true,
+            Ok(output) => {
+                debug!(
+                    "git worktree repair failed for {}: {}",
+                    worktree_path.display(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                false
+            }
+            Err(e) => {
+                debug!("Failed to run git worktree repair: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Whether `worktree_path` is one of the worktrees `git worktree list` reports for the
+    /// repository
+    pub async fn is_listed_worktree(
+        repo_path: &str,
+        worktree_path: &Path,
+    ) -> Result<bool, std::io::Error> {
+        let output = tokio::process::Command::new("git")
+            .args(["worktree", "list", "--porcelain"])
+            .current_dir(repo_path)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        let wanted = std::fs::canonicalize(worktree_path)?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("worktree "))
+            .any(|listed| {
+                std::fs::canonicalize(listed).unwrap_or_else(|_| PathBuf::from(listed)) == wanted
+            }))
+    }
+
     /// Try to infer the git repository path from a worktree
     async fn infer_git_repo_path(worktree_path: &Path) -> Option<String> {
         // Try using git rev-parse --git-common-dir from within the worktree
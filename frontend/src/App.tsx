@@ -6,6 +6,7 @@ import { ProjectTasks } from '@/pages/project-tasks';
 
 import { Settings } from '@/pages/Settings';
 import { McpServers } from '@/pages/McpServers';
+import { StopAttempt } from '@/pages/StopAttempt';
 import { DisclaimerDialog } from '@/components/DisclaimerDialog';
 import { OnboardingDialog } from '@/components/OnboardingDialog';
 import { PrivacyOptInDialog } from '@/components/PrivacyOptInDialog';
@@ -16,6 +17,7 @@ import type {
   ApiResponse,
   ExecutorConfig,
   EditorType,
+  UserPreferences,
 } from 'shared/types';
 import * as Sentry from '@sentry/react';
 import { GitHubLoginDialog } from '@/components/GitHubLoginDialog';
@@ -28,8 +30,25 @@ function AppContent() {
   const [showOnboarding, setShowOnboarding] = useState(false);
   const [showPrivacyOptIn, setShowPrivacyOptIn] = useState(false);
   const [showGitHubLogin, setShowGitHubLogin] = useState(false);
+  const [preferences, setPreferences] = useState<UserPreferences | null>(null);
   const showNavbar = true;
 
+  useEffect(() => {
+    if (loading) return;
+    const loadPreferences = async () => {
+      try {
+        const response = await fetch('/api/preferences');
+        const data: ApiResponse<UserPreferences> = await response.json();
+        if (data.success && data.data) {
+          setPreferences(data.data);
+        }
+      } catch (err) {
+        console.error('Error loading preferences:', err);
+      }
+    };
+    loadPreferences();
+  }, [loading, config?.github?.username]);
+
   useEffect(() => {
     if (config) {
       setShowDisclaimer(!config.disclaimer_acknowledged);
@@ -43,7 +62,8 @@ function AppContent() {
       // Only show GitHub login if telemetry dialog is not being shown
       if (config.telemetry_acknowledged) {
         const notAuthenticated =
-          !config.github?.username || !config.github?.token;
+          !config.github?.username ||
+          !(config.github?.token || config.github?.has_access_token);
         setShowGitHubLogin(notAuthenticated || githubTokenInvalid);
       } else {
         setShowGitHubLogin(false);
@@ -139,7 +159,11 @@ function AppContent() {
         setShowPrivacyOptIn(false);
         // Now show GitHub login after privacy choice is made
         const notAuthenticated =
-          !updatedConfig.github?.username || !updatedConfig.github?.token;
+          !updatedConfig.github?.username ||
+          !(
+            updatedConfig.github?.token ||
+            updatedConfig.github?.has_access_token
+          );
         setShowGitHubLogin(notAuthenticated);
       }
     } catch (err) {
@@ -159,7 +183,9 @@ function AppContent() {
   }
 
   return (
-    <ThemeProvider initialTheme={config?.theme || 'system'}>
+    <ThemeProvider
+      initialTheme={preferences?.theme || config?.theme || 'system'}
+    >
       <div className="h-screen flex flex-col bg-background">
         <GitHubLoginDialog
           open={showGitHubLogin}
@@ -194,6 +220,7 @@ function AppContent() {
 
             <Route path="/settings" element={<Settings />} />
             <Route path="/mcp-servers" element={<McpServers />} />
+            <Route path="/stop/:token" element={<StopAttempt />} />
           </SentryRoutes>
         </div>
       </div>
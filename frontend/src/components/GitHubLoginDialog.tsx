@@ -32,7 +32,10 @@ export function GitHubLoginDialog({
   const [copied, setCopied] = useState(false);
 
   const isAuthenticated =
-    !!(config?.github?.username && config?.github?.token) &&
+    !!(
+      config?.github?.username &&
+      (config?.github?.token || config?.github?.has_access_token)
+    ) &&
     !githubTokenInvalid;
 
   const handleLogin = async () => {
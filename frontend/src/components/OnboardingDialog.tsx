@@ -19,6 +19,7 @@ import { Label } from '@/components/ui/label';
 import { Input } from '@/components/ui/input';
 import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card';
 import { Sparkles, Code } from 'lucide-react';
+import { isExecutorEnabled, useConfig } from '@/components/config-provider';
 import type { EditorType, ExecutorConfig } from 'shared/types';
 import {
   EXECUTOR_TYPES,
@@ -36,7 +37,13 @@ interface OnboardingDialogProps {
 }
 
 export function OnboardingDialog({ open, onComplete }: OnboardingDialogProps) {
-  const [executor, setExecutor] = useState<ExecutorConfig>({ type: 'claude' });
+  const { config } = useConfig();
+  // The configured default is always enabled, so fall back to it when Claude isn't
+  const [executor, setExecutor] = useState<ExecutorConfig>(
+    isExecutorEnabled(config, 'claude')
+      ? { type: 'claude' }
+      : config?.executor ?? { type: 'claude' }
+  );
   const [editorType, setEditorType] = useState<EditorType>('vscode');
   const [customCommand, setCustomCommand] = useState<string>('');
 
@@ -89,7 +96,9 @@ export function OnboardingDialog({ open, onComplete }: OnboardingDialogProps) {
                     <SelectValue placeholder="Select your preferred coding agent" />
                   </SelectTrigger>
                   <SelectContent>
-                    {EXECUTOR_TYPES.map((type) => (
+                    {EXECUTOR_TYPES.filter((type) =>
+                      isExecutorEnabled(config, type)
+                    ).map((type) => (
                       <SelectItem key={type} value={type}>
                         {EXECUTOR_LABELS[type]}
                       </SelectItem>
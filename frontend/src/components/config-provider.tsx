@@ -131,6 +131,11 @@ export function ConfigProvider({ children }: ConfigProviderProps) {
   );
 }
 
+// Servers can restrict executors with an allow-list; all of them are enabled without one
+export function isExecutorEnabled(config: Config | null, type: string) {
+  return !config?.enabled_executors || config.enabled_executors.includes(type);
+}
+
 export function useConfig() {
   const context = useContext(ConfigContext);
   if (context === undefined) {
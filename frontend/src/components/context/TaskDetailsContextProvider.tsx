@@ -32,6 +32,7 @@ import {
   TaskDiffContext,
   TaskExecutionStateContext,
   TaskSelectedAttemptContext,
+  WORKTREE_MISSING,
 } from './taskDetailsContext.ts';
 
 const TaskDetailsProvider: FC<{
@@ -110,6 +111,8 @@ const TaskDetailsProvider: FC<{
           const result: ApiResponse<WorktreeDiff> = await response.json();
           if (result.success && result.data) {
             setDiff(result.data);
+          } else if (result.message === WORKTREE_MISSING) {
+            setDiffError(WORKTREE_MISSING);
           } else {
             setDiffError('Failed to load diff');
           }
@@ -71,6 +71,9 @@ export const TaskDeletingFilesContext =
     {} as TaskDeletingFilesContextValue
   );
 
+// Message of attempt endpoints when the attempt's worktree was deleted by hand
+export const WORKTREE_MISSING = 'worktree_missing';
+
 interface TaskDiffContextValue {
   setDiffError: Dispatch<SetStateAction<string | null>>;
   fetchDiff: (isBackgroundRefresh?: boolean) => Promise<void>;
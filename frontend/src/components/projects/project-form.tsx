@@ -33,12 +33,20 @@ export function ProjectForm({
   const [gitRepoPath, setGitRepoPath] = useState(project?.git_repo_path || '');
   const [setupScript, setSetupScript] = useState(project?.setup_script ?? '');
   const [devScript, setDevScript] = useState(project?.dev_script ?? '');
+  const [testScript, setTestScript] = useState(project?.test_script ?? '');
+  const [formatScript, setFormatScript] = useState(
+    project?.format_script ?? ''
+  );
+  const [foldFormatting, setFoldFormatting] = useState(
+    project?.format_commit_mode === 'fold'
+  );
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState('');
   const [showFolderPicker, setShowFolderPicker] = useState(false);
   const [repoMode, setRepoMode] = useState<'existing' | 'new'>('existing');
   const [parentPath, setParentPath] = useState('');
   const [folderName, setFolderName] = useState('');
+  const [isScratch, setIsScratch] = useState(false);
 
   const isEditing = !!project;
 
@@ -49,11 +57,17 @@ export function ProjectForm({
       setGitRepoPath(project.git_repo_path || '');
       setSetupScript(project.setup_script ?? '');
       setDevScript(project.dev_script ?? '');
+      setTestScript(project.test_script ?? '');
+      setFormatScript(project.format_script ?? '');
+      setFoldFormatting(project.format_commit_mode === 'fold');
     } else {
       setName('');
       setGitRepoPath('');
       setSetupScript('');
       setDevScript('');
+      setTestScript('');
+      setFormatScript('');
+      setFoldFormatting(false);
     }
   }, [project]);
 
@@ -94,6 +108,10 @@ export function ProjectForm({
           git_repo_path: finalGitRepoPath,
           setup_script: setupScript.trim() || null,
           dev_script: devScript.trim() || null,
+          test_script: testScript.trim() || null,
+          format_script: formatScript.trim() || null,
+          format_commit_mode: foldFormatting ? 'fold' : 'separate',
+          response_language: project.response_language,
         };
         const response = await makeRequest(`/api/projects/${project.id}`, {
           method: 'PUT',
@@ -115,6 +133,11 @@ export function ProjectForm({
           use_existing_repo: repoMode === 'existing',
           setup_script: setupScript.trim() || null,
           dev_script: devScript.trim() || null,
+          test_script: testScript.trim() || null,
+          format_script: formatScript.trim() || null,
+          format_commit_mode: foldFormatting ? 'fold' : 'separate',
+          response_language: null,
+          project_type: isScratch ? 'scratch' : 'git',
         };
         const response = await makeRequest('/api/projects', {
           method: 'POST',
@@ -137,6 +160,7 @@ export function ProjectForm({
       setSetupScript('');
       setParentPath('');
       setFolderName('');
+      setIsScratch(false);
     } catch (error) {
       setError(error instanceof Error ? error.message : 'An error occurred');
     } finally {
@@ -150,14 +174,21 @@ export function ProjectForm({
       setGitRepoPath(project.git_repo_path || '');
       setSetupScript(project.setup_script ?? '');
       setDevScript(project.dev_script ?? '');
+      setTestScript(project.test_script ?? '');
+      setFormatScript(project.format_script ?? '');
+      setFoldFormatting(project.format_commit_mode === 'fold');
     } else {
       setName('');
       setGitRepoPath('');
       setSetupScript('');
       setDevScript('');
+      setTestScript('');
+      setFormatScript('');
+      setFoldFormatting(false);
     }
     setParentPath('');
     setFolderName('');
+    setIsScratch(false);
     setError('');
     onClose();
   };
@@ -208,6 +239,20 @@ export function ProjectForm({
                   <span className="text-sm">Create new repository</span>
                 </label>
               </div>
+              <label className="flex items-center space-x-2 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={isScratch}
+                  onChange={(e) => setIsScratch(e.target.checked)}
+                />
+                <span className="text-sm">Scratch project without git</span>
+              </label>
+              {isScratch && (
+                <p className="text-sm text-muted-foreground">
+                  Attempts run in a copy of the folder. Diffs, merges, branches
+                  and pull requests aren't available.
+                </p>
+              )}
             </div>
           )}
 
@@ -234,7 +279,9 @@ export function ProjectForm({
               </div>
               {!isEditing && (
                 <p className="text-sm text-muted-foreground">
-                  Select a folder that already contains a git repository
+                  {isScratch
+                    ? 'Select the folder to work in'
+                    : 'Select a folder that already contains a git repository'}
                 </p>
               )}
             </div>
@@ -338,6 +385,50 @@ export function ProjectForm({
             </p>
           </div>
 
+          <div className="space-y-2">
+            <Label htmlFor="test-script">Test Script (Optional)</Label>
+            <textarea
+              id="test-script"
+              value={testScript}
+              onChange={(e) => setTestScript(e.target.value)}
+              placeholder="#!/bin/bash&#10;npm test"
+              rows={3}
+              className="w-full px-3 py-2 border border-input bg-background text-foreground rounded-md resize-vertical focus:outline-none focus:ring-2 focus:ring-ring"
+            />
+            <p className="text-sm text-muted-foreground">
+              Exits non-zero when the project is broken. Bisecting an attempt
+              runs it on the attempt's commits to find the one that broke it.
+            </p>
+          </div>
+
+          <div className="space-y-2">
+            <Label htmlFor="format-script">Format Script (Optional)</Label>
+            <textarea
+              id="format-script"
+              value={formatScript}
+              onChange={(e) => setFormatScript(e.target.value)}
+              placeholder="prettier --write&#10;rustfmt --edition 2021"
+              rows={3}
+              className="w-full px-3 py-2 border border-input bg-background text-foreground rounded-md resize-vertical focus:outline-none focus:ring-2 focus:ring-ring"
+            />
+            <label className="flex items-center space-x-2 cursor-pointer">
+              <input
+                type="checkbox"
+                checked={foldFormatting}
+                onChange={(e) => setFoldFormatting(e.target.checked)}
+              />
+              <span className="text-sm">
+                Fold formatting into the agent's commit
+              </span>
+            </label>
+            <p className="text-sm text-muted-foreground">
+              One command per line, each given the files the attempt changed.
+              It runs after the coding agent finishes; its changes get a style
+              commit of their own unless folded. A failing formatter is
+              flagged on the attempt without failing it.
+            </p>
+          </div>
+
           {error && (
             <Alert variant="destructive">
               <AlertCircle className="h-4 w-4" />
@@ -214,7 +214,7 @@ function DiffFile({
             )}
           </Button>
           <p className="text-xs font-medium text-muted-foreground font-mono">
-            {file.path}
+            {file.old_path ? `${file.old_path} → ${file.path}` : file.path}
           </p>
           {collapsedFiles.has(file.path) && (
             <div className="flex items-center gap-1 text-xs text-muted-foreground ml-2">
@@ -1,9 +1,50 @@
 import { DiffCard } from '@/components/tasks/TaskDetails/DiffCard.tsx';
-import { useContext } from 'react';
-import { TaskDiffContext } from '@/components/context/taskDetailsContext.ts';
+import { useContext, useState } from 'react';
+import { Button } from '@/components/ui/button.tsx';
+import {
+  TaskDiffContext,
+  TaskSelectedAttemptContext,
+  WORKTREE_MISSING,
+} from '@/components/context/taskDetailsContext.ts';
+import { makeRequest } from '@/lib/api.ts';
+import type { ApiResponse } from 'shared/types.ts';
 
 function DiffTab() {
-  const { diff, diffLoading, diffError } = useContext(TaskDiffContext);
+  const { diff, diffLoading, diffError, setDiffError, fetchDiff } =
+    useContext(TaskDiffContext);
+  const { selectedAttempt } = useContext(TaskSelectedAttemptContext);
+  const [isRecreating, setIsRecreating] = useState(false);
+
+  const handleRecreateWorktree = async () => {
+    if (!selectedAttempt) return;
+
+    setIsRecreating(true);
+    try {
+      const response = await makeRequest(
+        `/api/task-attempts/${selectedAttempt.id}/recreate-worktree`,
+        {
+          method: 'POST',
+        }
+      );
+
+      if (!response.ok) {
+        throw new Error('Failed to recreate worktree');
+      }
+
+      const result: ApiResponse<string> = await response.json();
+      if (!result.success) {
+        throw new Error(result.message || 'Failed to recreate worktree');
+      }
+
+      await fetchDiff();
+    } catch (err) {
+      setDiffError(
+        err instanceof Error ? err.message : 'Failed to recreate worktree'
+      );
+    } finally {
+      setIsRecreating(false);
+    }
+  };
 
   if (diffLoading) {
     return (
@@ -14,6 +55,24 @@ function DiffTab() {
     );
   }
 
+  if (diffError === WORKTREE_MISSING) {
+    return (
+      <div className="text-center py-8 space-y-4">
+        <p className="text-muted-foreground">
+          This attempt's worktree was deleted. Recreate it from the attempt's
+          branch to see its changes.
+        </p>
+        <Button
+          size="sm"
+          onClick={handleRecreateWorktree}
+          disabled={isRecreating}
+        >
+          {isRecreating ? 'Recreating...' : 'Recreate worktree'}
+        </Button>
+      </div>
+    );
+  }
+
   if (diffError) {
     return (
       <div className="text-center py-8 text-destructive">
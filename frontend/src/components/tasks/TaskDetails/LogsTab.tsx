@@ -103,6 +103,12 @@ function LogsTab() {
               .join('\n') || 'Waiting for setup script output...'}
           </div>
         )}
+        {setupProcess && Number(setupProcess.replaced_output_bytes) > 0 && (
+          <p className="mt-2 text-xs text-muted-foreground">
+            {Number(setupProcess.replaced_output_bytes)} bytes of output were
+            not valid text and were replaced or left out.
+          </p>
+        )}
       </div>
     );
   }
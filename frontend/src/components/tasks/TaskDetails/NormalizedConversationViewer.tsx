@@ -5,7 +5,7 @@ import { MarkdownRenderer } from '@/components/ui/markdown-renderer.tsx';
 import type {
   ApiResponse,
   ExecutionProcess,
-  NormalizedConversation,
+  FilteredConversation,
   NormalizedEntry,
   WorktreeDiff,
 } from 'shared/types.ts';
@@ -118,12 +118,13 @@ export function NormalizedConversationViewer({
 }: NormalizedConversationViewerProps) {
   const { projectId } = useContext(TaskDetailsContext);
   const [conversation, setConversation] =
-    useState<NormalizedConversation | null>(null);
+    useState<FilteredConversation | null>(null);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [clusteringEnabled, setClusteringEnabled] = useState(
     GEMINI_CLUSTERING_CONFIG.enabled
   );
+  const [significantOnly, setSignificantOnly] = useState(false);
 
   const fetchNormalizedLogs = useCallback(
     async (isPolling = false) => {
@@ -134,11 +135,11 @@ export function NormalizedConversationViewer({
         }
 
         const response = await makeRequest(
-          `/api/projects/${projectId}/execution-processes/${executionProcess.id}/normalized-logs`
+          `/api/projects/${projectId}/execution-processes/${executionProcess.id}/normalized-logs${significantOnly ? '?significant_only=true' : ''}`
         );
 
         if (response.ok) {
-          const result: ApiResponse<NormalizedConversation> =
+          const result: ApiResponse<FilteredConversation> =
             await response.json();
           if (result.success && result.data) {
             setConversation((prev) => {
@@ -175,7 +176,7 @@ export function NormalizedConversationViewer({
         }
       }
     },
-    [executionProcess.id, projectId, onConversationUpdate]
+    [executionProcess.id, projectId, onConversationUpdate, significantOnly]
   );
 
   // Initial fetch
@@ -213,6 +214,10 @@ export function NormalizedConversationViewer({
         : conversation?.entries || [],
     [isGeminiExecutor, conversation?.entries, clusteringEnabled]
   );
+  const hiddenCounts = Object.entries(conversation?.hidden_counts ?? {});
+  const hiddenSummary = hiddenCounts
+    .map(([kind, count]) => `${count} ${kind.replace(/_/g, ' ')}`)
+    .join(', ');
 
   if (loading) {
     return (
@@ -226,7 +231,10 @@ export function NormalizedConversationViewer({
     return <div className="text-xs text-red-600 text-center">{error}</div>;
   }
 
-  if (!conversation || conversation.entries.length === 0) {
+  if (
+    !conversation ||
+    (conversation.entries.length === 0 && hiddenCounts.length === 0)
+  ) {
     // If the execution process is still running, show loading instead of "no data"
     if (executionProcess.status === 'running') {
       return (
@@ -295,6 +303,23 @@ export function NormalizedConversationViewer({
         </div>
       )}
 
+      {/* Noise filtering */}
+      <div className="mb-2 flex items-center justify-between text-xs text-muted-foreground">
+        <span>{hiddenSummary && `${hiddenSummary} hidden`}</span>
+        <button
+          onClick={() => setSignificantOnly(!significantOnly)}
+          className="flex items-center gap-1 hover:text-foreground transition-colors"
+          title="Only show assistant messages, errors, file writes and commands"
+        >
+          {significantOnly ? (
+            <ToggleRight className="h-4 w-4" />
+          ) : (
+            <ToggleLeft className="h-4 w-4" />
+          )}
+          <span>Significant only</span>
+        </button>
+      </div>
+
       {/* Display conversation entries */}
       <div className="space-y-2">
         {displayEntries.map((entry, index) => (
@@ -1,7 +1,7 @@
-import { useCallback, useContext, useEffect, useState } from 'react';
+import { useCallback, useContext, useEffect, useMemo, useState } from 'react';
 import { Play } from 'lucide-react';
 import { Button } from '@/components/ui/button';
-import { useConfig } from '@/components/config-provider';
+import { isExecutorEnabled, useConfig } from '@/components/config-provider';
 import { makeRequest } from '@/lib/api';
 import type { ApiResponse, GitBranch, TaskAttempt } from 'shared/types';
 import {
@@ -22,6 +22,9 @@ const availableExecutors = [
   { id: 'amp', name: 'Amp' },
   { id: 'gemini', name: 'Gemini' },
   { id: 'opencode', name: 'OpenCode' },
+  { id: 'aider', name: 'Aider' },
+  { id: 'codex', name: 'Codex' },
+  { id: 'custom', name: 'Custom Command' },
 ];
 
 function TaskDetailsToolbar() {
@@ -39,6 +42,10 @@ function TaskDetailsToolbar() {
   const [taskAttempts, setTaskAttempts] = useState<TaskAttempt[]>([]);
 
   const { config } = useConfig();
+  const enabledExecutors = useMemo(
+    () => availableExecutors.filter((e) => isExecutorEnabled(config, e.id)),
+    [config]
+  );
 
   const [branches, setBranches] = useState<GitBranch[]>([]);
   const [selectedBranch, setSelectedBranch] = useState<string | null>(null);
@@ -115,12 +122,12 @@ function TaskDetailsToolbar() {
       // Only update executor if it's different from default and exists in available executors
       if (
         latestAttempt.executor &&
-        availableExecutors.some((e) => e.id === latestAttempt.executor)
+        enabledExecutors.some((e) => e.id === latestAttempt.executor)
       ) {
         setCreateAttemptExecutor(latestAttempt.executor);
       }
     }
-  }, [taskAttempts, branches, availableExecutors]);
+  }, [taskAttempts, branches, enabledExecutors]);
 
   const fetchTaskAttempts = useCallback(async () => {
     if (!task) return;
@@ -199,7 +206,7 @@ function TaskDetailsToolbar() {
       // Use latest attempt's executor if it exists, otherwise use current selected executor
       if (
         latestAttempt.executor &&
-        availableExecutors.some((e) => e.id === latestAttempt.executor)
+        enabledExecutors.some((e) => e.id === latestAttempt.executor)
       ) {
         setCreateAttemptExecutor(latestAttempt.executor);
       } else {
@@ -210,7 +217,13 @@ function TaskDetailsToolbar() {
       setCreateAttemptBranch(selectedBranch);
       setCreateAttemptExecutor(selectedExecutor);
     }
-  }, [taskAttempts, branches, selectedBranch, selectedExecutor]);
+  }, [
+    taskAttempts,
+    branches,
+    selectedBranch,
+    selectedExecutor,
+    enabledExecutors,
+  ]);
 
   return (
     <>
@@ -234,7 +247,7 @@ function TaskDetailsToolbar() {
             setCreateAttemptBranch={setCreateAttemptBranch}
             setIsInCreateAttemptMode={setIsInCreateAttemptMode}
             setCreateAttemptExecutor={setCreateAttemptExecutor}
-            availableExecutors={availableExecutors}
+            availableExecutors={enabledExecutors}
           />
         ) : (
           <div className="space-y-3 p-3 bg-muted/20 rounded-lg border">
@@ -1,9 +1,11 @@
 import { AlertCircle, Send } from 'lucide-react';
 import { Button } from '@/components/ui/button';
 import { Alert, AlertDescription } from '@/components/ui/alert';
+import { Checkbox } from '@/components/ui/checkbox';
+import { Label } from '@/components/ui/label';
 import { FileSearchTextarea } from '@/components/ui/file-search-textarea';
 import { useContext, useMemo, useState } from 'react';
-import { makeRequest } from '@/lib/api.ts';
+import { makeIdempotentRequest } from '@/lib/api.ts';
 import {
   TaskAttemptDataContext,
   TaskDetailsContext,
@@ -20,6 +22,7 @@ export function TaskFollowUpSection() {
   const [followUpMessage, setFollowUpMessage] = useState('');
   const [isSendingFollowUp, setIsSendingFollowUp] = useState(false);
   const [followUpError, setFollowUpError] = useState<string | null>(null);
+  const [includePreviousDiff, setIncludePreviousDiff] = useState(false);
 
   const canSendFollowUp = useMemo(() => {
     if (
@@ -49,7 +52,7 @@ export function TaskFollowUpSection() {
     try {
       setIsSendingFollowUp(true);
       setFollowUpError(null);
-      const response = await makeRequest(
+      const response = await makeIdempotentRequest(
         `/api/projects/${projectId}/tasks/${selectedAttempt.task_id}/attempts/${selectedAttempt.id}/follow-up`,
         {
           method: 'POST',
@@ -58,6 +61,7 @@ export function TaskFollowUpSection() {
           },
           body: JSON.stringify({
             prompt: followUpMessage.trim(),
+            include_previous_diff: includePreviousDiff,
           }),
         }
       );
@@ -136,6 +140,22 @@ export function TaskFollowUpSection() {
               )}
             </Button>
           </div>
+          <div className="flex items-center space-x-2">
+            <Checkbox
+              id="include-previous-diff"
+              checked={includePreviousDiff}
+              onCheckedChange={(checked: boolean) =>
+                setIncludePreviousDiff(checked)
+              }
+              disabled={!canSendFollowUp}
+            />
+            <Label
+              htmlFor="include-previous-diff"
+              className="text-sm text-muted-foreground"
+            >
+              Include changes so far as context
+            </Label>
+          </div>
         </div>
       </div>
     )
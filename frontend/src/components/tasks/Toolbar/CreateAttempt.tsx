@@ -17,7 +17,7 @@ import {
 } from '@/components/ui/dropdown-menu.tsx';
 import { Input } from '@/components/ui/input.tsx';
 import type { GitBranch, TaskAttempt } from 'shared/types.ts';
-import { makeRequest } from '@/lib/api.ts';
+import { makeIdempotentRequest } from '@/lib/api.ts';
 import {
   TaskAttemptDataContext,
   TaskDetailsContext,
@@ -72,7 +72,7 @@ function CreateAttempt({
 
   const onCreateNewAttempt = async (executor?: string, baseBranch?: string) => {
     try {
-      const response = await makeRequest(
+      const response = await makeIdempotentRequest(
         `/api/projects/${projectId}/tasks/${task.id}/attempts`,
         {
           method: 'POST',
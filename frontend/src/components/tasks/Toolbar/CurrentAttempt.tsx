@@ -1,4 +1,5 @@
 import {
+  AlertTriangle,
   ExternalLink,
   GitBranch as GitBranchIcon,
   GitPullRequest,
@@ -21,7 +22,7 @@ import {
   DropdownMenuItem,
   DropdownMenuTrigger,
 } from '@/components/ui/dropdown-menu.tsx';
-import { makeRequest } from '@/lib/api.ts';
+import { makeIdempotentRequest, makeRequest } from '@/lib/api.ts';
 import {
   Dispatch,
   SetStateAction,
@@ -33,6 +34,7 @@ import {
 } from 'react';
 import type {
   ApiResponse,
+  AttemptOverlapReport,
   BranchStatus,
   ExecutionProcess,
   TaskAttempt,
@@ -108,6 +110,8 @@ function CurrentAttempt({
   const [isHoveringDevServer, setIsHoveringDevServer] = useState(false);
   const [branchStatus, setBranchStatus] = useState<BranchStatus | null>(null);
   const [branchStatusLoading, setBranchStatusLoading] = useState(false);
+  const [overlapReport, setOverlapReport] =
+    useState<AttemptOverlapReport | null>(null);
 
   const processedDevServerLogs = useMemo(() => {
     if (!devServerDetails) return 'No output yet...';
@@ -291,12 +295,68 @@ function CurrentAttempt({
     }
   }, [selectedAttempt, fetchBranchStatus]);
 
+  const fetchOverlaps = useCallback(async () => {
+    if (!projectId) return;
+
+    try {
+      const response = await makeRequest(
+        `/api/projects/${projectId}/attempt-overlaps`
+      );
+      if (response.ok) {
+        const result: ApiResponse<AttemptOverlapReport> =
+          await response.json();
+        if (result.success && result.data) {
+          setOverlapReport(result.data);
+        }
+      }
+    } catch (err) {
+      // Advisory only, so a failure just hides the warning
+      console.error('Failed to load attempt overlaps:', err);
+    }
+  }, [projectId]);
+
+  useEffect(() => {
+    if (selectedAttempt && !selectedAttempt.merge_commit) {
+      fetchOverlaps();
+    } else {
+      setOverlapReport(null);
+    }
+  }, [selectedAttempt, fetchOverlaps]);
+
+  const attemptOverlaps = useMemo(() => {
+    if (!overlapReport) return [];
+    return overlapReport.overlaps
+      .filter(
+        (overlap) =>
+          overlap.attempt_id === selectedAttempt.id ||
+          overlap.other_attempt_id === selectedAttempt.id
+      )
+      .map((overlap) => {
+        const otherId =
+          overlap.attempt_id === selectedAttempt.id
+            ? overlap.other_attempt_id
+            : overlap.attempt_id;
+        const other = overlapReport.attempts.find(
+          (attempt) => attempt.attempt_id === otherId
+        );
+        return { ...overlap, otherTitle: other?.task_title ?? otherId };
+      });
+  }, [overlapReport, selectedAttempt.id]);
+
+  const mergeOrderPosition = useMemo(() => {
+    if (!overlapReport || attemptOverlaps.length === 0) return null;
+    const index = overlapReport.suggested_merge_order.indexOf(
+      selectedAttempt.id
+    );
+    return index >= 0 ? index + 1 : null;
+  }, [overlapReport, attemptOverlaps, selectedAttempt.id]);
+
   const performMerge = async () => {
     if (!projectId || !selectedAttempt?.id || !selectedAttempt?.task_id) return;
 
     try {
       setMerging(true);
-      const response = await makeRequest(
+      const response = await makeIdempotentRequest(
         `/api/projects/${projectId}/tasks/${selectedAttempt.task_id}/attempts/${selectedAttempt.id}/merge`,
         {
           method: 'POST',
@@ -448,6 +508,46 @@ function CurrentAttempt({
         </div>
       </div>
 
+      {attemptOverlaps.length > 0 && (
+        <TooltipProvider>
+          <Tooltip>
+            <TooltipTrigger asChild>
+              <div
+                className={`col-span-4 flex items-center gap-1.5 text-xs px-2 py-1 rounded ${
+                  attemptOverlaps.some(
+                    (overlap) => overlap.severity === 'overlapping_hunks'
+                  )
+                    ? 'bg-red-50 text-red-700'
+                    : 'bg-yellow-50 text-yellow-700'
+                }`}
+              >
+                <AlertTriangle className="h-3 w-3" />
+                Changes the same files as {attemptOverlaps.length} other open
+                attempt{attemptOverlaps.length === 1 ? '' : 's'}
+                {mergeOrderPosition !== null &&
+                  ` · suggested merge position ${mergeOrderPosition} of ${overlapReport?.suggested_merge_order.length}`}
+              </div>
+            </TooltipTrigger>
+            <TooltipContent className="max-w-sm">
+              <div className="space-y-1 text-xs">
+                {attemptOverlaps.map((overlap) => (
+                  <div key={overlap.other_attempt_id + overlap.attempt_id}>
+                    <div className="font-medium">{overlap.otherTitle}</div>
+                    {overlap.files.map((file) => (
+                      <div key={file.path} className="font-mono">
+                        {file.path}
+                        {file.severity === 'overlapping_hunks' &&
+                          ' (same lines)'}
+                      </div>
+                    ))}
+                  </div>
+                ))}
+              </div>
+            </TooltipContent>
+          </Tooltip>
+        </TooltipProvider>
+      )}
+
       <div className="col-span-4">
         <div className="flex items-center gap-1.5 mb-1">
           <div className="text-xs font-medium text-muted-foreground uppercase tracking-wide">
@@ -605,6 +705,16 @@ function CurrentAttempt({
                         ? 'Creating...'
                         : 'Create PR'}
                   </Button>
+                  {branchStatus.untracked_changes > 0 && (
+                    <span
+                      className="flex items-center gap-1 text-xs text-yellow-700"
+                      title="These files aren't tracked by git and won't be merged"
+                    >
+                      <AlertTriangle className="h-3 w-3" />
+                      {branchStatus.untracked_changes} untracked file
+                      {branchStatus.untracked_changes === 1 ? '' : 's'} changed
+                    </span>
+                  )}
                   <Button
                     onClick={handleMergeClick}
                     disabled={
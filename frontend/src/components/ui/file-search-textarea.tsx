@@ -2,7 +2,7 @@ import { KeyboardEvent, useEffect, useRef, useState } from 'react';
 import { createPortal } from 'react-dom';
 import { Textarea } from '@/components/ui/textarea';
 import { makeRequest } from '@/lib/api';
-import { ApiResponse } from 'shared/types.ts';
+import { ApiResponse, SearchResultPage } from 'shared/types.ts';
 
 interface FileSearchResult {
   path: string;
@@ -53,13 +53,18 @@ export function FileSearchTextarea({
       setIsLoading(true);
       try {
         const response = await makeRequest(
-          `/api/projects/${projectId}/search?q=${encodeURIComponent(searchQuery)}`
+          `/api/projects/${projectId}/search?q=${encodeURIComponent(searchQuery)}&search_type=files_only`
         );
 
         if (response.ok) {
-          const result: ApiResponse<FileSearchResult[]> = await response.json();
+          const result: ApiResponse<SearchResultPage> = await response.json();
           if (result.success && result.data) {
-            setSearchResults(result.data);
+            setSearchResults(
+              result.data.results.map((file) => ({
+                path: file.path,
+                name: file.path.split('/').pop() || file.path,
+              }))
+            );
             setShowDropdown(true);
             setSelectedIndex(-1);
           }
@@ -9,3 +9,31 @@ export const makeRequest = async (url: string, options: RequestInit = {}) => {
     headers,
   });
 };
+
+const IDEMPOTENT_RETRIES = 3;
+
+/**
+ * Sends a mutating request with an Idempotency-Key header and retries it on network
+ * errors with the same key, so a flaky connection can't create duplicates: the server
+ * replays the first response instead of running the request again.
+ */
+export const makeIdempotentRequest = async (
+  url: string,
+  options: RequestInit = {}
+) => {
+  const headers = {
+    ...(options.headers || {}),
+    'Idempotency-Key': crypto.randomUUID(),
+  };
+
+  for (let attempt = 1; ; attempt++) {
+    try {
+      return await makeRequest(url, { ...options, headers });
+    } catch (err) {
+      if (attempt >= IDEMPOTENT_RETRIES) {
+        throw err;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500 * attempt));
+    }
+  }
+};
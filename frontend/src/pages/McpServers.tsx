@@ -19,7 +19,7 @@ import { Alert, AlertDescription } from '@/components/ui/alert';
 import { Textarea } from '@/components/ui/textarea';
 import { Loader2 } from 'lucide-react';
 import { EXECUTOR_TYPES, EXECUTOR_LABELS } from 'shared/types';
-import { useConfig } from '@/components/config-provider';
+import { isExecutorEnabled, useConfig } from '@/components/config-provider';
 
 export function McpServers() {
   const { config } = useConfig();
@@ -309,7 +309,9 @@ export function McpServers() {
                   <SelectValue placeholder="Select executor" />
                 </SelectTrigger>
                 <SelectContent>
-                  {EXECUTOR_TYPES.map((type) => (
+                  {EXECUTOR_TYPES.filter((type) =>
+                    isExecutorEnabled(config, type)
+                  ).map((type) => (
                     <SelectItem key={type} value={type}>
                       {EXECUTOR_LABELS[type]}
                     </SelectItem>
@@ -19,7 +19,13 @@ import { Alert, AlertDescription } from '@/components/ui/alert';
 import { Checkbox } from '@/components/ui/checkbox';
 import { Input } from '@/components/ui/input';
 import { Key, Loader2, Volume2 } from 'lucide-react';
-import type { EditorType, SoundFile, ThemeMode } from 'shared/types';
+import type {
+  ApiResponse,
+  EditorType,
+  SoundFile,
+  ThemeMode,
+  UserPreferences,
+} from 'shared/types';
 import {
   EDITOR_LABELS,
   EDITOR_TYPES,
@@ -29,7 +35,7 @@ import {
   SOUND_LABELS,
 } from 'shared/types';
 import { useTheme } from '@/components/theme-provider';
-import { useConfig } from '@/components/config-provider';
+import { isExecutorEnabled, useConfig } from '@/components/config-provider';
 import { GitHubLoginDialog } from '@/components/GitHubLoginDialog';
 
 export function Settings() {
@@ -38,7 +44,7 @@ export function Settings() {
   const [saving, setSaving] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [success, setSuccess] = useState(false);
-  const { setTheme } = useTheme();
+  const { theme, setTheme } = useTheme();
   const [showGitHubLogin, setShowGitHubLogin] = useState(false);
 
   const playSound = async (soundFile: SoundFile) => {
@@ -50,6 +56,24 @@ export function Settings() {
     }
   };
 
+  // Theme is a personal preference, saved immediately rather than with the config
+  const saveThemePreference = async (value: ThemeMode) => {
+    try {
+      const response = await fetch('/api/preferences', {
+        method: 'PATCH',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ theme: value }),
+      });
+      const data: ApiResponse<UserPreferences> = await response.json();
+      if (!data.success) {
+        setError(data.message || 'Failed to save theme');
+      }
+    } catch (err) {
+      setError('Failed to save theme');
+      console.error('Error saving theme preference:', err);
+    }
+  };
+
   const handleSave = async () => {
     if (!config) return;
 
@@ -63,8 +87,6 @@ export function Settings() {
 
       if (success) {
         setSuccess(true);
-        // Update theme provider to reflect the saved theme
-        setTheme(config.theme);
 
         setTimeout(() => setSuccess(false), 3000);
       } else {
@@ -90,7 +112,10 @@ export function Settings() {
     updateConfig({ onboarding_acknowledged: false });
   };
 
-  const isAuthenticated = !!(config?.github?.username && config?.github?.token);
+  const isAuthenticated = !!(
+    config?.github?.username &&
+    (config?.github?.token || config?.github?.has_access_token)
+  );
 
   const handleLogout = useCallback(async () => {
     if (!config) return;
@@ -98,6 +123,7 @@ export function Settings() {
       github: {
         ...config.github,
         token: null,
+        has_access_token: false,
         username: null,
         primary_email: null,
       },
@@ -161,10 +187,10 @@ export function Settings() {
               <div className="space-y-2">
                 <Label htmlFor="theme">Theme</Label>
                 <Select
-                  value={config.theme}
+                  value={theme}
                   onValueChange={(value: ThemeMode) => {
-                    updateConfig({ theme: value });
                     setTheme(value);
+                    saveThemePreference(value);
                   }}
                 >
                   <SelectTrigger id="theme">
@@ -208,7 +234,9 @@ export function Settings() {
                     <SelectValue placeholder="Select executor" />
                   </SelectTrigger>
                   <SelectContent>
-                    {EXECUTOR_TYPES.map((type) => (
+                    {EXECUTOR_TYPES.filter((type) =>
+                      isExecutorEnabled(config, type)
+                    ).map((type) => (
                       <SelectItem key={type} value={type}>
                         {EXECUTOR_LABELS[type]}
                       </SelectItem>
@@ -219,6 +247,107 @@ export function Settings() {
                   Choose the default executor for running tasks.
                 </p>
               </div>
+              <div className="flex items-center space-x-2">
+                <Checkbox
+                  id="claude-text-output"
+                  checked={config.executor_output_formats.claude === 'text'}
+                  onCheckedChange={(checked: boolean) =>
+                    updateConfig({
+                      executor_output_formats: {
+                        ...config.executor_output_formats,
+                        claude: checked ? 'text' : 'json',
+                      },
+                    })
+                  }
+                />
+                <div className="space-y-0.5">
+                  <Label htmlFor="claude-text-output" className="cursor-pointer">
+                    Plain Text Claude Output
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Run Claude without JSON output to troubleshoot log parsing.
+                    Tool calls aren't shown and follow-ups can't resume the
+                    session.
+                  </p>
+                </div>
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="worktree-name-pattern">
+                  Worktree Directory Name
+                </Label>
+                <Input
+                  id="worktree-name-pattern"
+                  value={config.worktree_name_pattern}
+                  onChange={(e) =>
+                    updateConfig({ worktree_name_pattern: e.target.value })
+                  }
+                />
+                <p className="text-sm text-muted-foreground">
+                  Pattern for new attempt worktree directories. Must include{' '}
+                  {'{attempt_id}'}; {'{short_attempt_id}'}, {'{task_id}'} and{' '}
+                  {'{task_title}'} are also available.
+                </p>
+              </div>
+              <div className="flex items-center space-x-2">
+                <Checkbox
+                  id="block-attempts-on-dirty-repo"
+                  checked={config.block_attempts_on_dirty_repo}
+                  onCheckedChange={(checked: boolean) =>
+                    updateConfig({ block_attempts_on_dirty_repo: checked })
+                  }
+                />
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="block-attempts-on-dirty-repo"
+                    className="cursor-pointer"
+                  >
+                    Block Attempts on Uncommitted Changes
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Refuse to start an attempt while the project repository has
+                    uncommitted changes, instead of starting it with a warning.
+                  </p>
+                </div>
+              </div>
+              <div className="flex items-center space-x-2">
+                <Checkbox
+                  id="untracked-change-journal"
+                  checked={config.untracked_change_journal}
+                  onCheckedChange={(checked: boolean) =>
+                    updateConfig({ untracked_change_journal: checked })
+                  }
+                />
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="untracked-change-journal"
+                    className="cursor-pointer"
+                  >
+                    Journal Untracked File Changes
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Record changes agents make to gitignored and other untracked
+                    files, which never appear in the diff or get merged.
+                  </p>
+                </div>
+              </div>
+              <div className="flex items-center space-x-2">
+                <Checkbox
+                  id="check-for-updates"
+                  checked={config.check_for_updates}
+                  onCheckedChange={(checked: boolean) =>
+                    updateConfig({ check_for_updates: checked })
+                  }
+                />
+                <div className="space-y-0.5">
+                  <Label htmlFor="check-for-updates" className="cursor-pointer">
+                    Check for Updates
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Look for a newer codecommand release once a day. Run{' '}
+                    <code>codecommand self-update</code> to install it.
+                  </p>
+                </div>
+              </div>
             </CardContent>
           </Card>
 
@@ -485,6 +614,28 @@ export function Settings() {
                   </p>
                 </div>
               </div>
+              <div className="flex items-center space-x-2">
+                <Checkbox
+                  id="analytics-content-hashes"
+                  checked={config.analytics_content_hashes}
+                  onCheckedChange={(checked: boolean) =>
+                    updateConfig({ analytics_content_hashes: checked })
+                  }
+                />
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="analytics-content-hashes"
+                    className="cursor-pointer"
+                  >
+                    Include Hashed Task Identifiers
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Adds one-way hashes of task titles to telemetry events so
+                    related events can be grouped. Task text itself is never
+                    sent.
+                  </p>
+                </div>
+              </div>
             </CardContent>
           </Card>
 
@@ -496,6 +647,28 @@ export function Settings() {
               </CardDescription>
             </CardHeader>
             <CardContent className="space-y-6">
+              <div className="flex items-center space-x-2">
+                <Checkbox
+                  id="mcp-require-confirmation"
+                  checked={config.mcp_require_confirmation}
+                  onCheckedChange={(checked: boolean) =>
+                    updateConfig({ mcp_require_confirmation: checked })
+                  }
+                />
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="mcp-require-confirmation"
+                    className="cursor-pointer"
+                  >
+                    Confirm Destructive MCP Tools
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Agents must pass <code>confirm: true</code> to tools like
+                    delete_task, or the call is rejected. Applies to MCP
+                    servers started after the change.
+                  </p>
+                </div>
+              </div>
               <div className="space-y-2">
                 <div className="flex items-center justify-between">
                   <div>
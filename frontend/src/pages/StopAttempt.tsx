@@ -0,0 +1,140 @@
+import { useEffect, useState } from 'react';
+import { useParams } from 'react-router-dom';
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
+import { Label } from '@/components/ui/label';
+import { Textarea } from '@/components/ui/textarea';
+import { Alert, AlertDescription } from '@/components/ui/alert';
+import { Loader2 } from 'lucide-react';
+import { makeRequest } from '@/lib/api';
+import type {
+  ApiResponse,
+  StopAttemptResult,
+  StopLinkDetails,
+} from 'shared/types';
+
+/** Confirmation page opened from a stop link in a notification */
+export function StopAttempt() {
+  const { token } = useParams<{ token: string }>();
+  const [details, setDetails] = useState<StopLinkDetails | null>(null);
+  const [loading, setLoading] = useState(true);
+  const [submitting, setSubmitting] = useState(false);
+  const [note, setNote] = useState('');
+  const [error, setError] = useState<string | null>(null);
+  const [done, setDone] = useState<string | null>(null);
+
+  useEffect(() => {
+    const load = async () => {
+      try {
+        const response = await makeRequest(`/api/stop-links/${token}`);
+        const result: ApiResponse<StopLinkDetails> = await response.json();
+        if (result.success && result.data) {
+          setDetails(result.data);
+        } else {
+          setError(result.message || 'This stop link is not valid');
+        }
+      } catch (err) {
+        setError('Failed to load the attempt');
+      } finally {
+        setLoading(false);
+      }
+    };
+    load();
+  }, [token]);
+
+  const confirm = async (restartWithNote: boolean) => {
+    setSubmitting(true);
+    setError(null);
+    try {
+      const response = await makeRequest(`/api/stop-links/${token}`, {
+        method: 'POST',
+        body: JSON.stringify({
+          note: note.trim() || null,
+          restart_with_note: restartWithNote,
+        }),
+      });
+      const result: ApiResponse<StopAttemptResult> = await response.json();
+      if (result.success) {
+        setDone(result.message || 'Stopped');
+      } else {
+        setError(result.message || 'Failed to stop the attempt');
+      }
+    } catch (err) {
+      setError('Failed to stop the attempt');
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  if (loading) {
+    return (
+      <div className="flex items-center justify-center p-8">
+        <Loader2 className="h-6 w-6 animate-spin" />
+      </div>
+    );
+  }
+
+  return (
+    <div className="container mx-auto max-w-xl p-4">
+      <Card>
+        <CardHeader>
+          <CardTitle>Stop attempt</CardTitle>
+          {details && (
+            <CardDescription>
+              {details.task_title} ({details.branch})
+              {!details.running && ' is not running right now'}
+            </CardDescription>
+          )}
+        </CardHeader>
+        <CardContent className="space-y-4">
+          {error && (
+            <Alert variant="destructive">
+              <AlertDescription>{error}</AlertDescription>
+            </Alert>
+          )}
+          {done ? (
+            <Alert>
+              <AlertDescription>{done}</AlertDescription>
+            </Alert>
+          ) : (
+            details && (
+              <>
+                <div className="space-y-2">
+                  <Label htmlFor="stop-note">Note (optional)</Label>
+                  <Textarea
+                    id="stop-note"
+                    placeholder="Wrong approach, try using the existing helper instead"
+                    value={note}
+                    onChange={(e) => setNote(e.target.value)}
+                    rows={4}
+                  />
+                </div>
+                <div className="flex flex-wrap gap-2">
+                  <Button
+                    variant="destructive"
+                    disabled={submitting}
+                    onClick={() => confirm(false)}
+                  >
+                    Stop
+                  </Button>
+                  <Button
+                    disabled={submitting || !note.trim()}
+                    onClick={() => confirm(true)}
+                  >
+                    Stop and restart with note
+                  </Button>
+                </div>
+              </>
+            )
+          )}
+        </CardContent>
+      </Card>
+    </div>
+  );
+}
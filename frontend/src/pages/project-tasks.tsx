@@ -4,7 +4,7 @@ import { Button } from '@/components/ui/button';
 import { Card, CardContent } from '@/components/ui/card';
 import { Input } from '@/components/ui/input';
 import { FolderOpen, Plus, Settings } from 'lucide-react';
-import { makeRequest } from '@/lib/api';
+import { makeIdempotentRequest, makeRequest } from '@/lib/api';
 import { TaskFormDialog } from '@/components/tasks/TaskFormDialog';
 import { ProjectForm } from '@/components/projects/project-form';
 import { useKeyboardShortcuts } from '@/lib/keyboard-shortcuts';
@@ -186,14 +186,17 @@ export function ProjectTasks() {
   const handleCreateTask = useCallback(
     async (title: string, description: string) => {
       try {
-        const response = await makeRequest(`/api/projects/${projectId}/tasks`, {
-          method: 'POST',
-          body: JSON.stringify({
-            project_id: projectId,
-            title,
-            description: description || null,
-          }),
-        });
+        const response = await makeIdempotentRequest(
+          `/api/projects/${projectId}/tasks`,
+          {
+            method: 'POST',
+            body: JSON.stringify({
+              project_id: projectId,
+              title,
+              description: description || null,
+            }),
+          }
+        );
 
         if (response.ok) {
           await fetchTasks();
@@ -217,7 +220,7 @@ export function ProjectTasks() {
           executor: executor || null,
         };
 
-        const response = await makeRequest(
+        const response = await makeIdempotentRequest(
           `/api/projects/${projectId}/tasks/create-and-start`,
           {
             method: 'POST',
@@ -99,7 +99,9 @@ function extractAndRun(baseName, launch) {
 
 if (isMcpMode) {
   extractAndRun("codecommand-mcp", (bin) => {
-    const proc = spawn(bin, [], { stdio: ["pipe", "pipe", "pipe"] });
+    // Forward the remaining arguments, e.g. --project-id <id>
+    const args = process.argv.slice(2).filter((arg) => arg !== "--mcp");
+    const proc = spawn(bin, args, { stdio: ["pipe", "pipe", "pipe"] });
     process.stdin.pipe(proc.stdin);
     proc.stdout.pipe(process.stdout);
     proc.stderr.pipe(process.stdout);
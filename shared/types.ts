@@ -4,13 +4,25 @@
 
 export type ApiResponse<T> = { success: boolean, data: T | null, message: string | null, };
 
-export type Config = { theme: ThemeMode, executor: ExecutorConfig, disclaimer_acknowledged: boolean, onboarding_acknowledged: boolean, telemetry_acknowledged: boolean, sound_alerts: boolean, sound_file: SoundFile, push_notifications: boolean, editor: EditorConfig, github: GitHubConfig, analytics_enabled: boolean | null, };
+export type Config = { theme: ThemeMode, executor: ExecutorConfig, disclaimer_acknowledged: boolean, onboarding_acknowledged: boolean, telemetry_acknowledged: boolean, sound_alerts: boolean, sound_file: SoundFile, push_notifications: boolean, editor: EditorConfig, github: GitHubConfig, analytics_enabled: boolean | null, response_language: string | null, diff_rename_threshold: number, max_concurrent_dev_servers: number | null, max_concurrent_per_project: number | null, timezone: string | null, auto_recreate_missing_worktrees: boolean, analytics_content_hashes: boolean, worktree_name_pattern: string, enabled_executors: Array<string> | null, prompt_token_budgets: Record<string, number>, mcp_require_confirmation: boolean, block_attempts_on_dirty_repo: boolean, ignored_paths: Array<string>, untracked_change_journal: boolean, executor_output_formats: Record<string, OutputFormat>, check_for_updates: boolean, process_priorities: ProcessPriorities, graceful_shutdown_secs: number, focus_mode_minutes: number, provenance: ProvenanceMode, public_url: string | null, attempt_start_hook: AttemptStartHook | null, custom_executor_command: string | null, custom_executor_jsonl: boolean, open_pr_in_browser: boolean, asset_quotas: AssetQuotas, initial_prompt_template: string | null, followup_prompt_template: string | null, claude: ClaudeSettings, executable_paths: Record<string, string>, max_entry_metadata_bytes: number, };
+
+export type ProcessPriority = "normal" | "low" | "idle";
+
+export type ProcessPriorities = { setup_script: ProcessPriority, coding_agent: ProcessPriority, dev_server: ProcessPriority, bisect: ProcessPriority, };
+
+export type ProvenanceMode = "off" | "trailers" | "full";
+
+export type AttemptStartHook = { "type": "webhook", url: string, } | { "type": "script", command: string, };
+
+export type AssetQuotas = { database_mb: number | null, aider_sessions_mb: number | null, };
+
+export type ClaudeSettings = { package_version: string, model: string | null, skip_permissions: boolean, };
 
 export type ThemeMode = "light" | "dark" | "system" | "purple" | "green" | "blue" | "orange" | "red";
 
 export type EditorConfig = { editor_type: EditorType, custom_command: string | null, };
 
-export type GitHubConfig = { pat: string | null, token: string | null, username: string | null, primary_email: string | null, default_pr_base: string | null, };
+export type GitHubConfig = { pat: string | null, token: string | null, username: string | null, primary_email: string | null, default_pr_base: string | null, has_access_token: boolean, };
 
 export type EditorType = "vscode" | "cursor" | "windsurf" | "intellij" | "zed" | "custom";
 
@@ -20,49 +32,85 @@ export type SoundFile = "abstract-sound1" | "abstract-sound2" | "abstract-sound3
 
 export type SoundConstants = { sound_files: Array<SoundFile>, sound_labels: Array<string>, };
 
-export type ConfigConstants = { editor: EditorConstants, sound: SoundConstants, };
+export type ConfigConstants = { editor: EditorConstants, sound: SoundConstants, executor: ExecutorConstants, };
+
+export type ExecutorConfig = { "type": "echo" } | { "type": "claude" } | { "type": "amp" } | { "type": "gemini" } | { "type": "opencode" } | { "type": "aider" } | { "type": "codex" } | { "type": "custom" } | { "type": "registered", name: string };
 
-export type ExecutorConfig = { "type": "echo" } | { "type": "claude" } | { "type": "amp" } | { "type": "gemini" } | { "type": "opencode" };
+export type OutputFormat = "json" | "text";
 
 export type ExecutorConstants = { executor_types: Array<ExecutorConfig>, executor_labels: Array<string>, };
 
-export type CreateProject = { name: string, git_repo_path: string, use_existing_repo: boolean, setup_script: string | null, dev_script: string | null, };
+export type ProjectType = "git" | "scratch";
+
+export type FormatCommitMode = "separate" | "fold";
+
+export type ProjectCapabilities = { worktrees: boolean, branches: boolean, diff: boolean, merge: boolean, pull_requests: boolean, };
+
+export type CreateProject = { name: string, git_repo_path: string, use_existing_repo: boolean, setup_script: string | null, dev_script: string | null, test_script: string | null, format_script: string | null, format_commit_mode: FormatCommitMode | null, response_language: string | null, project_type: ProjectType | null, };
 
-export type Project = { id: string, name: string, git_repo_path: string, setup_script: string | null, dev_script: string | null, created_at: Date, updated_at: Date, };
+export type Project = { id: string, name: string, git_repo_path: string, setup_script: string | null, dev_script: string | null, test_script: string | null, format_script: string | null, format_commit_mode: FormatCommitMode, response_language: string | null, project_type: ProjectType, created_at: Date, updated_at: Date, };
 
-export type ProjectWithBranch = { id: string, name: string, git_repo_path: string, setup_script: string | null, dev_script: string | null, current_branch: string | null, created_at: Date, updated_at: Date, };
+export type HeadState = { "type": "branch", name: string, } | { "type": "detached", commit: string, } | { "type": "unborn" };
 
-export type UpdateProject = { name: string | null, git_repo_path: string | null, setup_script: string | null, dev_script: string | null, };
+export type RepoConfigStatus = { present: boolean, overrides: Array<string>, default_executor: string | null, error: string | null, };
 
-export type SearchResult = { path: string, is_file: boolean, match_type: SearchMatchType, };
+export type ProjectWithBranch = { id: string, name: string, git_repo_path: string, setup_script: string | null, dev_script: string | null, test_script: string | null, format_script: string | null, format_commit_mode: FormatCommitMode, response_language: string | null, current_branch: string | null, head_state: HeadState | null, is_bare: boolean, project_type: ProjectType, capabilities: ProjectCapabilities, repo_config: RepoConfigStatus, created_at: Date, updated_at: Date, };
 
-export type SearchMatchType = "FileName" | "DirectoryName" | "FullPath";
+export type ProjectStats = { task_count: number, attempt_count: number, avg_session_duration_secs: number | null, update_conflicts: number, merged_updates: number, };
+
+export type UpdateProject = { name: string | null, git_repo_path: string | null, setup_script: string | null, dev_script: string | null, test_script: string | null, format_script: string | null, format_commit_mode: FormatCommitMode | null, response_language: string | null, };
+
+export type SearchResult = { path: string, is_file: boolean, match_type: SearchMatchType, result_type: SearchResultType, id: string | null, score: number, };
+
+export type SearchMatchType = "FileName" | "DirectoryName" | "FullPath" | "Title" | "Description";
+
+export type SearchResultType = "File" | "Task" | "Project";
+
+export type SearchType = "tasks_only" | "projects_only" | "files_only" | "all";
+
+export type SearchResultPage = { results: Array<SearchResult>, total: number, search_type_applied: SearchType, };
+
+export type HeatmapBucketSize = "hour" | "day";
+
+export type ActivityHeatmapBucket = { day_of_week: number, hour: number | null, execution_count: number, total_runtime_secs: number, };
+
+export type ActivityHeatmap = { project_id: string | null, timezone: string, range_days: number, bucket: HeatmapBucketSize, buckets: Array<ActivityHeatmapBucket>, };
 
 export type GitBranch = { name: string, is_current: boolean, is_remote: boolean, last_commit_date: Date, };
 
 export type CreateBranch = { name: string, base_branch: string | null, };
 
-export type CreateTask = { project_id: string, title: string, description: string | null, };
+export type CreateTask = { project_id: string, title: string, description: string | null, estimate: number | null, };
 
-export type CreateTaskAndStart = { project_id: string, title: string, description: string | null, executor: ExecutorConfig | null, };
+export type CreateTaskAndStart = { project_id: string, title: string, description: string | null, estimate: number | null, executor: ExecutorConfig | null, };
 
 export type TaskStatus = "todo" | "inprogress" | "inreview" | "done" | "cancelled";
 
-export type Task = { id: string, project_id: string, title: string, description: string | null, status: TaskStatus, created_at: string, updated_at: string, };
+export type Task = { id: string, project_id: string, title: string, description: string | null, status: TaskStatus, estimate: number | null, created_at: string, updated_at: string, version: number, };
+
+export type TaskWithAttemptStatus = { id: string, project_id: string, title: string, description: string | null, status: TaskStatus, estimate: number | null, created_at: string, updated_at: string, version: number, has_in_progress_attempt: boolean, has_merged_attempt: boolean, has_failed_attempt: boolean, blocked_by: Array<string>, };
 
-export type TaskWithAttemptStatus = { id: string, project_id: string, title: string, description: string | null, status: TaskStatus, created_at: string, updated_at: string, has_in_progress_attempt: boolean, has_merged_attempt: boolean, has_failed_attempt: boolean, };
+export type UpdateTask = { title: string | null, description: string | null, status: TaskStatus | null, estimate: number | null, expected_version: number | null, };
 
-export type UpdateTask = { title: string | null, description: string | null, status: TaskStatus | null, };
+export type TaskField = "title" | "description" | "status" | "estimate";
+
+export type TaskConflict = { current: Task, conflicting_fields: Array<TaskField>, };
+
+export type TaskEstimateReport = { task_id: string, title: string, estimate: number | null, actual_minutes: number, attempt_count: number, };
+
+export type TaskDependency = { task_id: string, depends_on_task_id: string, created_at: string, };
+
+export type CreateTaskDependency = { depends_on_task_id: string, };
 
 export type TaskAttemptStatus = "setuprunning" | "setupcomplete" | "setupfailed" | "executorrunning" | "executorcomplete" | "executorfailed";
 
-export type TaskAttempt = { id: string, task_id: string, worktree_path: string, branch: string, base_branch: string, merge_commit: string | null, executor: string | null, pr_url: string | null, pr_number: bigint | null, pr_status: string | null, pr_merged_at: string | null, worktree_deleted: boolean, setup_completed_at: string | null, created_at: string, updated_at: string, };
+export type TaskAttempt = { id: string, task_id: string, worktree_path: string, branch: string, base_branch: string, merge_commit: string | null, executor: string | null, pr_url: string | null, pr_number: bigint | null, pr_status: string | null, pr_merged_at: string | null, worktree_deleted: boolean, worktree_deleted_at: string | null, setup_completed_at: string | null, stop_note: string | null, env: Record<string, string> | null, benchmark_run_id: string | null, retry_of: string | null, retry_count: bigint, created_at: string, updated_at: string, };
 
-export type CreateTaskAttempt = { executor: string | null, base_branch: string | null, };
+export type CreateTaskAttempt = { executor: string | null, base_branch: string | null, env: Record<string, string>, retry_of: string | null, };
 
 export type UpdateTaskAttempt = Record<string, never>;
 
-export type CreateFollowUpAttempt = { prompt: string, };
+export type CreateFollowUpAttempt = { prompt: string, include_previous_diff: boolean, bisect_run_id: string | null, };
 
 export type TaskAttemptActivity = { id: string, execution_process_id: string, status: TaskAttemptStatus, note: string | null, created_at: string, };
 
@@ -70,6 +118,14 @@ export type TaskAttemptActivityWithPrompt = { id: string, execution_process_id:
 
 export type CreateTaskAttemptActivity = { execution_process_id: string, status: TaskAttemptStatus | null, note: string | null, };
 
+export type TaskSortOrder = "newest" | "oldest" | "title";
+
+export type DiffGranularity = "line" | "word";
+
+export type UserPreferences = { theme: ThemeMode | null, collapsed_columns: Array<TaskStatus>, default_task_sort: TaskSortOrder | null, diff_granularity: DiffGranularity | null, custom: Record<string, unknown>, };
+
+export type UpdateUserPreferences = { theme: ThemeMode | null, collapsed_columns: Array<TaskStatus> | null, default_task_sort: TaskSortOrder | null, diff_granularity: DiffGranularity | null, custom: Record<string, unknown>, };
+
 export type AttemptData = {
     activities: TaskAttemptActivityWithPrompt[];
     processes: ExecutionProcessSummary[];
@@ -93,55 +149,291 @@ export interface ProcessedSection {
 
 export type DirectoryEntry = { name: string, path: string, is_directory: boolean, is_git_repo: boolean, };
 
-export type DiffChunkType = "Equal" | "Insert" | "Delete";
+export type MovePathRequest = { project_id: string, source: string, destination: string, overwrite: boolean | null, };
+
+export type MovePathResponse = { success: boolean, git_staged: boolean, };
+
+export type StopAllExecutionsRequest = { reason: string, };
+
+export type StopAllExecutionsResponse = { stopped_count: number, };
+
+export type StartFocusMode = { minutes: number | null, };
+
+export type FocusModeStatus = { active: boolean, until: string | null, paused_process_ids: Array<string>, };
+
+export type ProvenanceSource = "merge_commit" | "trailer";
+
+export type ProvenanceResolution = { commit: string, task_id: string, task_title: string, attempt_id: string, executor: string | null, source: ProvenanceSource, };
+
+export type AttemptStartContext = { project_id: string, project_name: string, task_id: string, task_title: string, task_description: string | null, attempt_id: string, executor: string | null, branch: string, base_branch: string, worktree_path: string, };
+
+export type RenormalizeRequest = { execution_id: string | null, all: boolean, executor: string | null, };
+
+export type RenormalizeResult = { execution_process_id: string, diff: NormalizedLogDiff | null, error: string | null, };
+
+export type RenormalizeResponse = { results: Array<RenormalizeResult>, };
+
+export type PromoteNormalizationRequest = { confirm: boolean, };
+
+export type PromotionReport = { promoted: boolean, diff: NormalizedLogDiff, unmapped_entries: Array<number>, };
+
+export type SnapshotVersion = "primary" | "shadow";
+
+export type NormalizedLogDiff = { added: number, removed: number, changed: number, unchanged: number, sequence_map: Array<number | null>, };
+
+export type FilteredConversation = { hidden_counts: Record<string, number>, } & NormalizedConversation;
+
+export type JobStatus = { name: string, schedule: string, enabled: boolean, running: boolean, next_run_at: string | null, last_started_at: string | null, last_finished_at: string | null, last_duration_ms: bigint | null, last_succeeded: boolean | null, last_error: string | null, run_count: bigint, };
+
+export type UpdateJob = { enabled: boolean, };
+
+export type AnnotationType = "approved" | "rejected" | "question";
+
+export type Annotation = { id: string, attempt_id: string, entry_index: number, annotation_type: AnnotationType, comment: string | null, created_at: string, };
+
+export type CreateAnnotation = { entry_index: number, annotation_type: AnnotationType, comment: string | null, };
+
+export type GlossaryEntryStatus = "approved" | "suggested" | "dismissed";
+
+export type GlossaryEntry = { id: string, project_id: string, term: string, definition: string, file_refs: Array<string>, status: GlossaryEntryStatus, occurrences: number, created_at: string, updated_at: string, };
+
+export type CreateGlossaryEntry = { term: string, definition: string, file_refs: Array<string>, };
+
+export type UpdateGlossaryEntry = { term: string | null, definition: string | null, file_refs: Array<string> | null, status: GlossaryEntryStatus | null, };
+
+export type BenchmarkRunStatus = "running" | "completed" | "cancelled";
+
+export type BenchmarkAttemptStatus = "pending" | "running" | "completed" | "failed" | "timed_out";
+
+export type BenchmarkRun = { id: string, project_id: string, executors: Array<string>, test_command: string | null, max_duration_secs: number, status: BenchmarkRunStatus, created_at: string, completed_at: string | null, };
+
+export type BenchmarkAttempt = { id: string, run_id: string, task_id: string, executor: string, source_attempt_id: string, base_commit: string, reference_commit: string, task_attempt_id: string | null, status: BenchmarkAttemptStatus, diff_similarity: number | null, tests_passed: boolean | null, cost_usd: number | null, duration_secs: number | null, error: string | null, created_at: string, updated_at: string, };
+
+export type CreateBenchmarkRun = { executors: Array<string>, task_count: number | null, task_ids: Array<string> | null, test_command: string | null, max_duration_secs: number | null, };
+
+export type ExecutorBenchmarkSummary = { executor: string, attempts: number, completed: number, failed: number, timed_out: number, mean_diff_similarity: number | null, tests_run: number, tests_passed: number, total_cost_usd: number | null, mean_duration_secs: number | null, };
+
+export type BenchmarkTaskResult = { task_id: string, task_title: string, source_attempt_id: string, attempts: Array<BenchmarkAttempt>, };
+
+export type BenchmarkReport = { run: BenchmarkRun, executors: Array<ExecutorBenchmarkSummary>, tasks: Array<BenchmarkTaskResult>, };
+
+export type OverlapSeverity = "same_file" | "overlapping_hunks";
+
+export type FileOverlap = { path: string, severity: OverlapSeverity, };
+
+export type AttemptOverlap = { attempt_id: string, other_attempt_id: string, severity: OverlapSeverity, files: Array<FileOverlap>, };
+
+export type AttemptOverlapSummary = { attempt_id: string, task_id: string, task_title: string, overlapping_attempts: number, severity: OverlapSeverity | null, };
+
+export type AttemptOverlapReport = { attempts: Array<AttemptOverlapSummary>, overlaps: Array<AttemptOverlap>, suggested_merge_order: Array<string>, skipped: Array<string>, };
+
+export type DiffChunkType = "Equal" | "Insert" | "Delete" | "Renamed" | "Copied";
 
 export type DiffChunk = { chunk_type: DiffChunkType, content: string, };
 
-export type FileDiff = { path: string, chunks: Array<DiffChunk>, };
+export type FileDiff = { path: string, old_path: string | null, new_path: string | null, chunks: Array<DiffChunk>, };
 
 export type WorktreeDiff = { files: Array<FileDiff>, };
 
-export type BranchStatus = { is_behind: boolean, commits_behind: number, commits_ahead: number, up_to_date: boolean, merged: boolean, has_uncommitted_changes: boolean, base_branch_name: string, };
+export type BranchStatus = { is_behind: boolean, commits_behind: number, commits_ahead: number, up_to_date: boolean, merged: boolean, has_uncommitted_changes: boolean, base_branch_name: string, untracked_changes: number, };
+
+export type CachedBranchStatus = { computed_at: string, stale: boolean, refreshing: boolean, error: string | null, } & BranchStatus;
+
+export type UntrackedChangeKind = "created" | "modified" | "deleted";
+
+export type UntrackedChange = { path: string, kind: UntrackedChangeKind, };
+
+export type UntrackedChangeReport = { recorded: boolean, baseline_taken_at: string | null, compared_at: string | null, changes: Array<UntrackedChange>, truncated: boolean, };
+
+export type WebhookEventType = "attempt.created" | "attempt.setup_started" | "attempt.setup_completed" | "attempt.setup_failed" | "attempt.executor_started" | "attempt.executor_completed" | "attempt.executor_failed" | "attempt.stopped" | "attempt.pr_opened" | "attempt.merged";
+
+export type WebhookEndpoint = { id: string, url: string, events: Array<WebhookEventType>, enabled: boolean, created_at: string, updated_at: string, };
+
+export type CreateWebhookEndpoint = { url: string, secret: string, events: Array<WebhookEventType>, enabled: boolean | null, };
+
+export type UpdateWebhookEndpoint = { url: string | null, secret: string | null, events: Array<WebhookEventType> | null, enabled: boolean | null, };
+
+export type WebhookDeliveryStatus = "pending" | "delivered" | "dead";
+
+export type WebhookDelivery = { id: string, endpoint_id: string, event_id: string, event_type: string, task_attempt_id: string | null, payload: string, status: WebhookDeliveryStatus, attempts: number, next_attempt_at: string, last_response_status: number | null, last_error: string | null, delivered_at: string | null, created_at: string, updated_at: string, };
+
+export type WebhookPayload = { schema_version: number, event_id: string, event: WebhookEventType, occurred_at: string, project_id: string, task_id: string, attempt_id: string, status: TaskAttemptStatus | null, branch: string, attempt_created_at: string, };
+
+export type BisectRunStatus = "running" | "found" | "failed" | "cancelled";
+
+export type BisectFileChange = { path: string, additions: number, deletions: number, };
+
+export type BisectCommit = { id: string, summary: string, author: string, committed_at: string, files: Array<BisectFileChange>, additions: number, deletions: number, };
+
+export type BisectRun = { id: string, task_attempt_id: string, good_commit: string, bad_commit: string, command: string, worktree_path: string, status: BisectRunStatus, first_bad_commit: BisectCommit | null, error: string | null, created_at: string, completed_at: string | null, };
+
+export type StartBisect = { command: string | null, };
+
+export type FormatRunStatus = "running" | "formatted" | "unchanged" | "failed" | "cancelled";
+
+export type FormattingDiff = "all" | "exclude" | "only";
+
+export type FormatRun = { id: string, task_attempt_id: string, coding_agent_process_id: string, agent_succeeded: boolean, agent_exit_code: number | null, command: string, files: Array<string>, commit_mode: FormatCommitMode, status: FormatRunStatus, commit_sha: string | null, error: string | null, created_at: string, completed_at: string | null, };
+
+export type NetworkPolicy = { project_id: string, enabled: boolean, allowlist: Array<string>, denylist: Array<string>, };
+
+export type UpdateNetworkPolicy = { enabled: boolean, allowlist: Array<string>, denylist: Array<string>, };
+
+export type NetworkVerdict = "allowed" | "unlisted" | "denied";
+
+export type NetworkContact = { execution_process_id: string, host: string, port: number, verdict: NetworkVerdict, connections: number, first_seen_at: string, last_seen_at: string, };
+
+export type AttemptNetworkReport = { enabled: boolean, policy_violation: boolean, contacts: Array<NetworkContact>, };
 
 export type ExecutionState = "NotStarted" | "SetupRunning" | "SetupComplete" | "SetupFailed" | "CodingAgentRunning" | "CodingAgentComplete" | "CodingAgentFailed" | "Complete";
 
-export type TaskAttemptState = { execution_state: ExecutionState, has_changes: boolean, has_setup_script: boolean, setup_process_id: string | null, coding_agent_process_id: string | null, };
+export type TaskAttemptState = { execution_state: ExecutionState, worktree_path: string, has_changes: boolean, has_setup_script: boolean, setup_process_id: string | null, coding_agent_process_id: string | null, annotation_count: number, policy_violation: boolean, paused_until: string | null, };
+
+export type WorktreeStatus = { attempt_id: string, task_id: string, project_id: string, branch: string, worktree_path: string, worktree_deleted: boolean, missing: boolean, };
+
+export type WorktreeRelocation = { attempt_id: string, old_path: string, new_path: string, git_repaired: boolean, };
+
+export type WorktreeRepairReport = { repaired: Array<WorktreeRelocation>, not_found: Array<string>, };
+
+export type AttemptRunStatus = "not_started" | "running" | "completed" | "failed" | "killed";
+
+export type TaskAttemptListItem = { id: string, task_id: string, task_title: string, project_id: string, project_name: string, branch: string, executor_type: string | null, status: AttemptRunStatus, duration_secs: number, created_at: string, };
+
+export type TaskAttemptListPage = { items: Array<TaskAttemptListItem>, next_cursor: string | null, };
+
+export type RelocateWorktree = { new_path: string, };
+
+export type StopTaskAttempt = { note: string | null, restart_with_note: boolean, };
+
+export type RestartAfterStop = { "type": "follow_up", attempt_id: string, } | { "type": "retry_attempt", attempt_id: string, };
+
+export type StopAttemptResult = { stopped_processes: number, restart: RestartAfterStop | null, };
+
+export type CancelAttemptResult = { stopped_processes: number, dequeued: boolean, };
+
+export type WaitCondition = "finished" | "review_ready" | "merged";
+
+export type AttemptWaitResult = { until: WaitCondition, timed_out: boolean, attempt: TaskAttempt, task_status: TaskStatus, running: boolean, };
+
+export type StopLinkDetails = { attempt_id: string, task_id: string, project_id: string, task_title: string, branch: string, running: boolean, expires_at: string, };
+
+export type CreatedStopLink = { path: string, expires_at: string, };
+
+export type UnknownAttemptBranch = { branch: string, local: boolean, remotes: Array<string>, last_commit_sha: string, last_commit_subject: string, last_commit_at: string, age_days: number, base_branch: string, commits_ahead: number, commits_behind: number, ignored: boolean, };
+
+export type AdoptedBranch = { task: Task, attempt: TaskAttempt, };
+
+export type UnknownAttemptBranchAction = { branch: string, remote: boolean, };
+
+export type ExportFormat = "patch" | "bundle";
+
+export type AttemptExportManifest = { attempt_id: string, task_id: string, task_title: string, branch: string, base_branch: string, base_sha: string, head_sha: string, format: ExportFormat, commit_count: number, exported_at: string, };
+
+export type PatchApplyResult = { index: number, subject: string | null, applied: boolean, commit: string | null, error: string | null, };
+
+export type ImportPatchSeries = { task_id: string, base_branch: string | null, patches: Array<string>, };
+
+export type PatchImportResult = { attempt: TaskAttempt, patches: Array<PatchApplyResult>, };
+
+export type DiffApplyResult = { applied: boolean, commit: string | null, conflicts: Array<string>, error: string | null, };
+
+export type ApplyAttemptToBranch = { base_branch: string, };
 
-export type ExecutionProcess = { id: string, task_attempt_id: string, process_type: ExecutionProcessType, executor_type: string | null, status: ExecutionProcessStatus, command: string, args: string | null, working_directory: string, stdout: string | null, stderr: string | null, exit_code: bigint | null, started_at: string, completed_at: string | null, created_at: string, updated_at: string, };
+export type BranchApplyResult = { attempt: TaskAttempt, result: DiffApplyResult, };
 
-export type ExecutionProcessSummary = { id: string, task_attempt_id: string, process_type: ExecutionProcessType, executor_type: string | null, status: ExecutionProcessStatus, command: string, args: string | null, working_directory: string, exit_code: bigint | null, started_at: string, completed_at: string | null, created_at: string, updated_at: string, };
+export type ExecutionProcess = { id: string, task_attempt_id: string, process_type: ExecutionProcessType, executor_type: string | null, status: ExecutionProcessStatus, command: string, args: string | null, working_directory: string, stdout: string | null, stderr: string | null, exit_code: bigint | null, stop_reason: string | null, replaced_output_bytes: bigint, started_at: string, completed_at: string | null, created_at: string, updated_at: string, };
+
+export type ExecutionProcessSummary = { id: string, task_attempt_id: string, process_type: ExecutionProcessType, executor_type: string | null, status: ExecutionProcessStatus, command: string, args: string | null, working_directory: string, exit_code: bigint | null, stop_reason: string | null, started_at: string, completed_at: string | null, created_at: string, updated_at: string, has_snapshot: boolean, paused_until: string | null, };
 
 export type ExecutionProcessStatus = "running" | "completed" | "failed" | "killed";
 
-export type ExecutionProcessType = "setupscript" | "codingagent" | "devserver";
+export type ExecutionProcessType = "setupscript" | "codingagent" | "devserver" | "bisect" | "formatscript";
 
 export type CreateExecutionProcess = { task_attempt_id: string, process_type: ExecutionProcessType, executor_type: string | null, command: string, args: string | null, working_directory: string, };
 
 export type UpdateExecutionProcess = { status: ExecutionProcessStatus | null, exit_code: bigint | null, completed_at: string | null, };
 
-export type ExecutorSession = { id: string, task_attempt_id: string, execution_process_id: string, session_id: string | null, prompt: string | null, summary: string | null, created_at: string, updated_at: string, };
+export type PromptSectionKind = "instructions" | "acceptance_criteria" | "tool_output" | "comment" | "history" | "diff";
+
+export type TrimAction = "truncated" | "dropped";
+
+export type TrimDecision = { label: string, kind: PromptSectionKind, action: TrimAction, original_tokens: number, kept_tokens: number, };
+
+export type PromptBudgetReport = { budget: number, original_tokens: number, estimated_tokens: number, trims: Array<TrimDecision>, };
+
+export type FollowUpPreview = { prompt: string, } & PromptBudgetReport;
 
-export type CreateExecutorSession = { task_attempt_id: string, execution_process_id: string, prompt: string | null, };
+export type ExecutorSession = { id: string, task_attempt_id: string, execution_process_id: string, session_id: string | null, prompt: string | null, summary: string | null, response_language: string | null, prompt_budget: PromptBudgetReport | null, terminated_at: string | null, created_at: string, updated_at: string, };
+
+export type ExecutorSessionWithDuration = { id: string, task_attempt_id: string, execution_process_id: string, session_id: string | null, prompt: string | null, summary: string | null, response_language: string | null, terminated_at: string | null, created_at: string, updated_at: string, duration_secs: number | null, };
+
+export type CreateExecutorSession = { task_attempt_id: string, execution_process_id: string, prompt: string | null, response_language: string | null, prompt_budget: PromptBudgetReport | null, };
 
 export type UpdateExecutorSession = { session_id: string | null, prompt: string | null, summary: string | null, };
 
 export type NormalizedConversation = { entries: Array<NormalizedEntry>, session_id: string | null, executor_type: string, prompt: string | null, summary: string | null, };
 
-export type NormalizedEntry = { timestamp: string | null, entry_type: NormalizedEntryType, content: string, };
+export type NormalizedEntry = { sequence: number, timestamp: string | null, entry_type: NormalizedEntryType, content: string, };
 
 export type NormalizedEntryType = { "type": "user_message" } | { "type": "assistant_message" } | { "type": "tool_use", tool_name: string, action_type: ActionType, } | { "type": "system_message" } | { "type": "error_message" } | { "type": "thinking" };
 
 export type ActionType = { "action": "file_read", path: string, } | { "action": "file_write", path: string, } | { "action": "command_run", command: string, } | { "action": "search", query: string, } | { "action": "web_fetch", url: string, } | { "action": "task_create", description: string, } | { "action": "other", description: string, };
 
+export type LiveLogMessage = { "type": "entry", entry: NormalizedEntry, } | { "type": "stderr", line: string, } | { "type": "exited", execution_process_id: string, exit_code: bigint | null, success: boolean, };
+
+export type ProjectChange = { "type": "task_created", task_id: string, status: TaskStatus, } | { "type": "task_updated", task_id: string, status: TaskStatus, } | { "type": "task_status_changed", task_id: string, old_status: TaskStatus, new_status: TaskStatus, } | { "type": "task_deleted", task_id: string, } | { "type": "attempt_started", task_id: string, attempt_id: string, } | { "type": "attempt_finished", task_id: string, attempt_id: string, success: boolean, } | { "type": "branch_status_refreshed", task_id: string, attempt_id: string, success: boolean, };
+
+export type AssetCategory = "database" | "aider_sessions" | "other";
+
+export type CategoryUsage = { category: AssetCategory, bytes: number, files: number, quota_bytes: number | null, over_quota: boolean, };
+
+export type AssetUsage = { categories: Array<CategoryUsage>, evicted: Array<string>, computed_at: string, };
+
+export type OrphanedAsset = { path: string, bytes: number, };
+
+export type MissingAsset = { execution_process_id: string, session_id: string, path: string, };
+
+export type AssetScanReport = { orphaned: Array<OrphanedAsset>, missing: Array<MissingAsset>, scanned_at: string, };
+
+export type AssetCleanupRequest = { paths: Array<string> | null, };
+
+export type AssetCleanupResult = { deleted: Array<string>, freed_bytes: number, };
+
+export type HealthStatus = { status: string, version: string, update_available: string | null, };
+
+export type SearchResultType = "project" | "task" | "attempt" | "branch";
+
+export type SearchResult = { result_type: SearchResultType, id: string, title: string, subtitle: string | null, project_id: string, project_name: string, score: number, path: string, };
+
+export type SearchResponse = { results: Array<SearchResult>, timed_out: Array<SearchResultType>, };
+
+export type AvailableUpdate = { version: string, release_url: string, release_notes: string | null, published_at: string | null, };
+
+export type UpdateStatus = { current_version: string, checks_enabled: boolean, update: AvailableUpdate | null, checked_at: string | null, error: string | null, };
+
 // Generated constants
 export const EXECUTOR_TYPES: string[] = [
     "echo",
     "claude",
     "amp",
     "gemini",
-    "opencode"
+    "opencode",
+    "aider",
+    "codex",
+    "custom"
 ];
 
+export const EXECUTOR_LABELS: Record<string, string> = {
+    "echo": "Echo (Test Mode)",
+    "claude": "Claude",
+    "amp": "Amp",
+    "gemini": "Gemini",
+    "opencode": "OpenCode",
+    "aider": "Aider",
+    "codex": "Codex",
+    "custom": "Custom Command"
+};
+
 export const EDITOR_TYPES: EditorType[] = [
     "vscode",
     "cursor", 
@@ -151,14 +443,6 @@ export const EDITOR_TYPES: EditorType[] = [
     "custom"
 ];
 
-export const EXECUTOR_LABELS: Record<string, string> = {
-    "echo": "Echo (Test Mode)",
-    "claude": "Claude",
-    "amp": "Amp",
-    "gemini": "Gemini",
-    "opencode": "OpenCode"
-};
-
 export const EDITOR_LABELS: Record<string, string> = {
     "vscode": "VS Code",
     "cursor": "Cursor",